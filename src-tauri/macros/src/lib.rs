@@ -0,0 +1,57 @@
+//! Attribute macros for the Tauri commands of zeami4
+
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span};
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, ItemFn, ReturnType, Type};
+
+/// Time a command and report its outcome to `commands::middleware::finished`
+///
+/// Goes next to `#[tauri::command]`. The body runs unchanged inside a closure
+/// (or an async block for async commands) so early returns and `?` still
+/// produce the command's result; Result errors are recorded by their Display.
+#[proc_macro_attribute]
+pub fn instrumented(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut function = parse_macro_input!(item as ItemFn);
+    let name = function.sig.ident.to_string();
+    let block = &function.block;
+
+    let (output, returns_result) = match &function.sig.output {
+        ReturnType::Default => (quote!(()), false),
+        ReturnType::Type(_, ty) => (quote!(#ty), is_result(ty)),
+    };
+    let body = if function.sig.asyncness.is_some() {
+        quote!(async move #block.await)
+    } else {
+        quote!((move || #block)())
+    };
+
+    // Mixed-site names cannot clash with the body's own variables
+    let started = Ident::new("started", Span::mixed_site());
+    let result = Ident::new("result", Span::mixed_site());
+    let error = if returns_result {
+        quote!(#result.as_ref().err().map(ToString::to_string))
+    } else {
+        quote!(None)
+    };
+
+    function.block = parse_quote!({
+        let #started = ::std::time::Instant::now();
+        #[allow(clippy::redundant_closure_call)]
+        let #result: #output = #body;
+        crate::commands::middleware::finished(#name, #started.elapsed(), #error);
+        #result
+    });
+    quote!(#function).into()
+}
+
+fn is_result(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Result"),
+        _ => false,
+    }
+}
@@ -0,0 +1,83 @@
+use crate::Project;
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use std::path::PathBuf;
+use zeami4::git::{commitlint, links};
+use zeami4::github::{issues, GitHubState, Repository};
+use zeami4::workflow::test_runs;
+
+#[derive(Subcommand)]
+pub enum DevAction {
+    /// Create or check out the branch for an issue and link it
+    Start {
+        /// Issue number
+        number: u64,
+    },
+    /// Run the project's test command; fails when the tests fail
+    Test,
+    /// Install a commit-msg hook that lints commits made with git
+    InstallHooks,
+    /// Lint a commit message file per GitSettings::lint_commit_messages;
+    /// run by the commit-msg hook
+    LintCommit {
+        /// File holding the message, as passed to the hook
+        file: PathBuf,
+    },
+}
+
+pub async fn run(project: &Project, action: DevAction) -> Result<()> {
+    let settings = &project.settings;
+    match action {
+        DevAction::Start { number } => {
+            let repo = Repository::from_settings(&settings.github)?;
+            let client = GitHubState::default().client(&settings.github)?;
+            let issue = issues::get_issue_summary(&client, &repo, number).await?;
+            let link = links::start_issue(&project.root, number, &issue.title, &settings.git)?;
+            project.print(&link, |link| {
+                println!(
+                    "Working on #{} {} in {}",
+                    link.issue, link.title, link.branch
+                )
+            })
+        }
+        DevAction::Test => {
+            let run = test_runs::run_tests(&project.root, &settings.workflow)?;
+            project.print(&run, |run| {
+                if !run.passed() {
+                    println!("{}", run.output.trim_end());
+                }
+                println!("{}", run.summary());
+            })?;
+            if !run.passed() {
+                bail!("Tests failed");
+            }
+            Ok(())
+        }
+        DevAction::InstallHooks => {
+            let path = commitlint::install_commit_msg_hook(&project.root)?;
+            project.print(&path, |path| println!("Installed {}", path.display()))
+        }
+        DevAction::LintCommit { file } => {
+            if !settings.git.lint_commit_messages {
+                return Ok(());
+            }
+            let text = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read {:?}", file))?;
+            let issue = if settings.github.auto_link_issues {
+                links::linked_issue(&project.root, &settings.git)?.map(|link| link.issue)
+            } else {
+                None
+            };
+            let result = commitlint::lint(&commitlint::edited_message(&text), issue);
+            project.print(&result, |result| {
+                for violation in &result.violations {
+                    eprintln!("{}: {}", violation.rule, violation.message);
+                }
+            })?;
+            if !result.valid {
+                bail!("Commit message rejected");
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::Project;
+use anyhow::Result;
+use clap::Subcommand;
+use zeami4::github::issues::{self, IssueFilters};
+use zeami4::github::{GitHubState, Repository};
+
+#[derive(Subcommand)]
+pub enum IssueAction {
+    /// List issues
+    List {
+        /// "open", "closed" or "all"
+        #[arg(long, default_value = "open")]
+        state: String,
+        /// Only issues with this label; repeat for several
+        #[arg(long = "label")]
+        labels: Vec<String>,
+        /// Login, "none" or "*"
+        #[arg(long)]
+        assignee: Option<String>,
+        #[arg(long, default_value_t = 30)]
+        limit: u8,
+    },
+    /// Show issue details
+    Show {
+        /// Issue number
+        number: u64,
+    },
+}
+
+pub async fn run(project: &Project, action: IssueAction) -> Result<()> {
+    let github = &project.settings.github;
+    let repo = Repository::from_settings(github)?;
+    let client = GitHubState::default().client(github)?;
+
+    match action {
+        IssueAction::List {
+            state,
+            labels,
+            assignee,
+            limit,
+        } => {
+            let filters = IssueFilters {
+                state: Some(state),
+                labels,
+                assignee,
+                per_page: Some(limit),
+                page: None,
+            };
+            let found = issues::list_issues(&client, &repo, &filters).await?;
+            project.print(&found, |found| {
+                for issue in found {
+                    let labels = if issue.labels.is_empty() {
+                        String::new()
+                    } else {
+                        format!("  [{}]", issue.labels.join(", "))
+                    };
+                    println!(
+                        "#{:<6} {:<7} {}{}",
+                        issue.number, issue.state, issue.title, labels
+                    );
+                }
+            })
+        }
+        IssueAction::Show { number } => {
+            let detail = issues::get_issue(&client, &repo, number).await?;
+            project.print(&detail, |detail| {
+                let issue = &detail.issue;
+                println!("#{} {} ({})", issue.number, issue.title, issue.state);
+                println!("{}", issue.html_url);
+                if !issue.labels.is_empty() {
+                    println!("Labels: {}", issue.labels.join(", "));
+                }
+                if !issue.body.trim().is_empty() {
+                    println!("\n{}", issue.body.trim_end());
+                }
+                for comment in &detail.comments {
+                    println!(
+                        "\n--- {} at {}\n{}",
+                        comment.author,
+                        comment.created_at,
+                        comment.body.trim_end()
+                    );
+                }
+            })
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! zeami CLI: the app's issue and development workflow without a window
+//!
+//! Shares its logic with the desktop app through the zeami4 library, so it
+//! reads the same settings, keychain and .zeami project state.
+
+mod dev;
+mod issue;
+mod status;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::path::PathBuf;
+use tracing_subscriber::EnvFilter;
+use zeami4::config::{overrides, storage, Settings};
+
+#[derive(Parser)]
+#[command(
+    name = "zeami",
+    version,
+    about = "GitHub issue-driven development from the terminal"
+)]
+struct Cli {
+    /// Project directory; defaults to the current directory
+    #[arg(short = 'C', long, global = true)]
+    project: Option<PathBuf>,
+
+    /// Print results as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Override a setting for this run, e.g. `--set git.remote=upstream`;
+    /// applied over ZEAMI_* environment variables
+    #[arg(long = "set", global = true, value_name = "PATH=VALUE")]
+    _set: Vec<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage GitHub issues
+    Issue {
+        #[command(subcommand)]
+        action: issue::IssueAction,
+    },
+
+    /// Development workflow commands
+    Dev {
+        #[command(subcommand)]
+        action: dev::DevAction,
+    },
+
+    /// Show the branch, linked issue and last test run
+    Status,
+}
+
+/// The project a command runs in and its effective settings
+pub struct Project {
+    pub root: PathBuf,
+    pub settings: Settings,
+    pub json: bool,
+}
+
+impl Project {
+    fn open(root: Option<PathBuf>, json: bool) -> Result<Self> {
+        let root = match root {
+            Some(root) => root,
+            None => std::env::current_dir().context("Failed to read the current directory")?,
+        };
+        let global = match storage::load_settings_or_recover() {
+            Ok(loaded) => loaded.settings,
+            Err(e) => {
+                tracing::warn!("Failed to load settings, using defaults: {:#}", e);
+                Settings::default()
+            }
+        };
+        let settings = storage::settings_for_project(&global, &root)?;
+        // ZEAMI_* variables and `--set` (read from the arguments) win over both
+        let settings = overrides::apply(&settings, &overrides::from_process())?;
+        Ok(Self {
+            root,
+            settings,
+            json,
+        })
+    }
+
+    /// Print `value` as JSON with --json, otherwise run `human`
+    pub fn print<T: Serialize>(&self, value: &T, human: impl FnOnce(&T)) -> Result<()> {
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(value)?);
+        } else {
+            human(value);
+        }
+        Ok(())
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
+    let cli = Cli::parse();
+    let project = Project::open(cli.project, cli.json)?;
+    match cli.command {
+        Command::Issue { action } => issue::run(&project, action).await,
+        Command::Dev { action } => dev::run(&project, action).await,
+        Command::Status => status::run(&project),
+    }
+}
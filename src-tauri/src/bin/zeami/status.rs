@@ -0,0 +1,53 @@
+use crate::Project;
+use anyhow::Result;
+use serde::Serialize;
+use zeami4::git::links::{self, IssueLink};
+use zeami4::git::status::{self, GitStatus};
+use zeami4::workflow::test_runs::{self, TestRun};
+
+#[derive(Serialize)]
+struct Status {
+    git: GitStatus,
+    issue: Option<IssueLink>,
+    test_run: Option<TestRun>,
+}
+
+pub fn run(project: &Project) -> Result<()> {
+    let status = Status {
+        git: status::get_status(&project.root)?,
+        issue: links::linked_issue(&project.root, &project.settings.git)?,
+        test_run: test_runs::last_run(&project.root)?,
+    };
+
+    project.print(&status, |status| {
+        let git = &status.git;
+        let branch = git.branch.as_deref().unwrap_or("(detached)");
+        match &git.upstream {
+            Some(upstream) => println!(
+                "Branch: {} (ahead {}, behind {} {})",
+                branch, git.ahead, git.behind, upstream
+            ),
+            None => println!("Branch: {}", branch),
+        }
+        if git.clean {
+            println!("Working tree: clean");
+        } else {
+            println!(
+                "Working tree: {} staged, {} unstaged, {} untracked, {} conflicted",
+                git.staged.len(),
+                git.unstaged.len(),
+                git.untracked.len(),
+                git.conflicted.len()
+            );
+        }
+        match &status.issue {
+            Some(issue) if issue.title.is_empty() => println!("Issue: #{}", issue.issue),
+            Some(issue) => println!("Issue: #{} {}", issue.issue, issue.title),
+            None => println!("Issue: none"),
+        }
+        match &status.test_run {
+            Some(run) => println!("Tests: {}", run.summary()),
+            None => println!("Tests: not run yet"),
+        }
+    })
+}
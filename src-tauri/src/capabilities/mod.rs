@@ -0,0 +1,87 @@
+//! Consent for commands that change a project
+//!
+//! Running tasks, committing and other changes to the repository, pushing and
+//! Claude tool use each need a Capability granted for the project (see store). A command invoked without
+//! it fails with CapabilityRequired and emits `capability-requested` so the
+//! frontend can ask the user, call `grant_capability` and retry. Grants last
+//! until revoked.
+
+pub mod store;
+
+pub use store::{Capability, CapabilityInfo, CapabilityRequired, CapabilityStore};
+
+use crate::config::storage;
+use crate::events::schema::v1;
+use crate::events::CAPABILITY_REQUESTED_EVENT_NAME;
+use anyhow::Result;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+/// Capability grants, managed as Tauri state
+pub struct CapabilityRegistry {
+    store: CapabilityStore,
+}
+
+impl CapabilityRegistry {
+    /// Registry with its grants in ~/.zeami/capabilities.json
+    pub fn open_default() -> Result<Self> {
+        let store = CapabilityStore::open(&storage::config_dir()?.join("capabilities.json"));
+        Ok(Self { store })
+    }
+
+    /// Succeed when `capability` is granted for `project_root`; otherwise ask
+    /// the user for it and fail with CapabilityRequired
+    pub fn require(
+        &self,
+        app: &AppHandle,
+        project_root: &Path,
+        capability: Capability,
+        command: &str,
+    ) -> Result<()> {
+        if self.store.is_granted(project_root, capability) {
+            return Ok(());
+        }
+
+        tracing::info!(%capability, command, path = ?project_root, "Capability not granted");
+        let payload = v1::CapabilityRequested {
+            project_root: project_root.to_path_buf(),
+            capability,
+            description: capability.description().to_string(),
+            command: command.to_string(),
+        };
+        if let Err(e) = app.emit_all(CAPABILITY_REQUESTED_EVENT_NAME, payload) {
+            tracing::error!("Failed to emit capability request: {}", e);
+        }
+        Err(CapabilityRequired {
+            capability,
+            command: command.to_string(),
+            project_root: project_root.to_path_buf(),
+        }
+        .into())
+    }
+
+    /// Check without asking; for background work that must not prompt
+    pub fn is_granted(&self, project_root: &Path, capability: Capability) -> bool {
+        self.store.is_granted(project_root, capability)
+    }
+
+    pub fn list(&self, project_root: &Path) -> Vec<CapabilityInfo> {
+        self.store.list(project_root)
+    }
+
+    pub fn grant(&self, project_root: &Path, capability: Capability) -> Result<()> {
+        self.store
+            .grant(project_root, capability, chrono::Utc::now())?;
+        tracing::info!(%capability, path = ?project_root, "Granted capability");
+        Ok(())
+    }
+
+    /// Returns false when the capability was not granted
+    pub fn revoke(&self, project_root: &Path, capability: Capability) -> Result<bool> {
+        let revoked = self.store.revoke(project_root, capability)?;
+        if revoked {
+            tracing::info!(%capability, path = ?project_root, "Revoked capability");
+        }
+        Ok(revoked)
+    }
+}
@@ -0,0 +1,237 @@
+//! Capabilities granted per project, kept in ~/.zeami/capabilities.json
+//!
+//! Grants live in the user's home rather than in the project's .zeami
+//! directory so a cloned repository cannot arrive with its own commands
+//! pre-approved.
+
+use crate::config::atomic::write_atomic;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Something a backend command can do to a project that needs the user's consent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Run project tasks, tests and builds
+    RunTasks,
+    /// Create commits, check out, delete and clean up branches, resolve
+    /// conflicts and restore WIP commits
+    Commit,
+    /// Push branches, open pull requests and publish releases
+    Push,
+    /// Let Claude call tools on the project
+    ClaudeTools,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 4] = [
+        Capability::RunTasks,
+        Capability::Commit,
+        Capability::Push,
+        Capability::ClaudeTools,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::RunTasks => "run_tasks",
+            Capability::Commit => "commit",
+            Capability::Push => "push",
+            Capability::ClaudeTools => "claude_tools",
+        }
+    }
+
+    /// What granting the capability allows, for the consent prompt
+    pub fn description(self) -> &'static str {
+        match self {
+            Capability::RunTasks => {
+                "Run .zeami/tasks.toml tasks (also on file changes), tests and builds"
+            }
+            Capability::Commit => {
+                "Create commits, including automatic WIP commits and base branch syncs"
+            }
+            Capability::Push => "Push branches, open pull requests and publish releases",
+            Capability::ClaudeTools => "Let Claude read files and run tests in the project",
+        }
+    }
+
+    /// Commands that require the capability
+    pub fn commands(self) -> &'static [&'static str] {
+        match self {
+            Capability::RunTasks => &["run_task", "run_tests", "run_build"],
+            Capability::Commit => &[
+                "create_commit",
+                "watch_auto_commit",
+                "sync_branch_with_base",
+            ],
+            Capability::Push => &["complete_issue", "create_release"],
+            Capability::ClaudeTools => &["run_claude_agent"],
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Returned by a sensitive command while its capability is not granted
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{command} needs the {capability} capability for {}", project_root.display())]
+pub struct CapabilityRequired {
+    pub capability: Capability,
+    pub command: String,
+    pub project_root: PathBuf,
+}
+
+/// A capability and whether it is granted, as reported by `list_capabilities`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapabilityInfo {
+    pub capability: Capability,
+    pub description: String,
+    pub commands: Vec<String>,
+    /// None while not granted
+    pub granted_at: Option<DateTime<Utc>>,
+}
+
+type Grants = BTreeMap<PathBuf, BTreeMap<Capability, DateTime<Utc>>>;
+
+pub struct CapabilityStore {
+    path: PathBuf,
+    grants: Mutex<Grants>,
+}
+
+impl CapabilityStore {
+    /// Load the grants at `path`; a missing or unreadable file grants nothing
+    pub fn open(path: &Path) -> Self {
+        let grants = match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable capability grants {:?}: {}", path, e);
+                Grants::new()
+            }),
+            Err(_) => Grants::new(),
+        };
+
+        Self {
+            path: path.to_path_buf(),
+            grants: Mutex::new(grants),
+        }
+    }
+
+    pub fn is_granted(&self, project_root: &Path, capability: Capability) -> bool {
+        self.grants
+            .lock()
+            .unwrap()
+            .get(&project_key(project_root))
+            .is_some_and(|granted| granted.contains_key(&capability))
+    }
+
+    /// Every capability with its grant for the project
+    pub fn list(&self, project_root: &Path) -> Vec<CapabilityInfo> {
+        let grants = self.grants.lock().unwrap();
+        let granted = grants.get(&project_key(project_root));
+        Capability::ALL
+            .into_iter()
+            .map(|capability| CapabilityInfo {
+                capability,
+                description: capability.description().to_string(),
+                commands: capability
+                    .commands()
+                    .iter()
+                    .map(|command| command.to_string())
+                    .collect(),
+                granted_at: granted.and_then(|granted| granted.get(&capability).copied()),
+            })
+            .collect()
+    }
+
+    pub fn grant(
+        &self,
+        project_root: &Path,
+        capability: Capability,
+        at: DateTime<Utc>,
+    ) -> Result<()> {
+        self.update(|grants| {
+            grants
+                .entry(project_key(project_root))
+                .or_default()
+                .insert(capability, at);
+        })
+    }
+
+    /// Returns false when the capability was not granted
+    pub fn revoke(&self, project_root: &Path, capability: Capability) -> Result<bool> {
+        let mut revoked = false;
+        self.update(|grants| {
+            let key = project_key(project_root);
+            if let Some(granted) = grants.get_mut(&key) {
+                revoked = granted.remove(&capability).is_some();
+                if granted.is_empty() {
+                    grants.remove(&key);
+                }
+            }
+        })?;
+        Ok(revoked)
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Grants)) -> Result<()> {
+        let mut grants = self.grants.lock().unwrap();
+        change(&mut grants);
+        write_atomic(&self.path, &serde_json::to_vec_pretty(&*grants)?)
+    }
+}
+
+/// Grants are keyed by the canonical project path so `./repo` and `/home/me/repo` match
+fn project_key(project_root: &Path) -> PathBuf {
+    std::fs::canonicalize(project_root).unwrap_or_else(|_| project_root.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grants_are_per_project_and_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capabilities.json");
+        let project = dir.path().join("project");
+        let other = dir.path().join("other");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::create_dir_all(&other).unwrap();
+
+        let store = CapabilityStore::open(&path);
+        assert!(!store.is_granted(&project, Capability::RunTasks));
+        store
+            .grant(&project, Capability::RunTasks, Utc::now())
+            .unwrap();
+        assert!(store.is_granted(&project, Capability::RunTasks));
+        assert!(!store.is_granted(&project, Capability::Commit));
+        assert!(!store.is_granted(&other, Capability::RunTasks));
+
+        let reopened = CapabilityStore::open(&path);
+        assert!(reopened.is_granted(&project.join("."), Capability::RunTasks));
+        let listed = reopened.list(&project);
+        assert_eq!(listed.len(), Capability::ALL.len());
+        assert!(listed
+            .iter()
+            .all(|info| info.granted_at.is_some() == (info.capability == Capability::RunTasks)));
+    }
+
+    #[test]
+    fn test_revoke_removes_the_grant() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CapabilityStore::open(&dir.path().join("capabilities.json"));
+        store
+            .grant(dir.path(), Capability::ClaudeTools, Utc::now())
+            .unwrap();
+
+        assert!(store.revoke(dir.path(), Capability::ClaudeTools).unwrap());
+        assert!(!store.revoke(dir.path(), Capability::ClaudeTools).unwrap());
+        assert!(!store.is_granted(dir.path(), Capability::ClaudeTools));
+    }
+}
@@ -0,0 +1,312 @@
+use super::client::{MessagesRequest, Reply, Usage};
+use super::usage::{price_per_token, CACHE_READ_PRICE, CACHE_WRITE_PRICE};
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Response cache and prompt caching statistics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// Requests made, including those answered from the response cache
+    pub requests: u64,
+    pub response_hits: u64,
+    /// Share of requests answered from the response cache, 0 to 1
+    pub hit_rate: f64,
+    /// Input tokens the API read from its prompt cache
+    pub prompt_cache_read_tokens: u64,
+    /// Input tokens the API wrote to its prompt cache
+    pub prompt_cache_write_tokens: u64,
+    /// Cached replies that have not expired yet
+    pub entries: u64,
+    /// Estimated from list prices; prompt cache writes count against it
+    pub estimated_savings_usd: f64,
+}
+
+/// Local cache of Claude replies keyed by model and prompt hash, kept in ~/.zeami/claude.db
+pub struct ResponseCache {
+    conn: Mutex<Connection>,
+}
+
+impl ResponseCache {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open response cache {:?}", path))?;
+        Self::init(conn)
+    }
+
+    /// A cache that lives only as long as the process
+    pub fn in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS responses (
+                key TEXT PRIMARY KEY,
+                model TEXT NOT NULL,
+                content TEXT NOT NULL,
+                stop_reason TEXT,
+                input_tokens INTEGER NOT NULL,
+                output_tokens INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS cache_usage (
+                model TEXT PRIMARY KEY,
+                requests INTEGER NOT NULL DEFAULT 0,
+                hits INTEGER NOT NULL DEFAULT 0,
+                saved_input_tokens INTEGER NOT NULL DEFAULT 0,
+                saved_output_tokens INTEGER NOT NULL DEFAULT 0,
+                cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+                cache_write_tokens INTEGER NOT NULL DEFAULT 0
+            );",
+        )
+        .context("Failed to initialize response cache")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock response cache: {}", e))
+    }
+
+    /// Cached reply for `key` if it is younger than `ttl` seconds
+    ///
+    /// Expired entries are purged on the way.
+    pub fn get(&self, key: &str, ttl: u64) -> Result<Option<Reply>> {
+        let conn = self.conn()?;
+        let cutoff = chrono::Utc::now().timestamp() - ttl as i64;
+        conn.execute(
+            "DELETE FROM responses WHERE created_at <= ?1",
+            params![cutoff],
+        )?;
+        let reply = conn
+            .query_row(
+                "SELECT content, stop_reason, input_tokens, output_tokens
+                 FROM responses WHERE key = ?1",
+                params![key],
+                |row| {
+                    Ok(Reply {
+                        content: row.get(0)?,
+                        stop_reason: row.get(1)?,
+                        usage: Usage {
+                            input_tokens: row.get(2)?,
+                            output_tokens: row.get(3)?,
+                            ..Usage::default()
+                        },
+                        tool_calls: Vec::new(),
+                    })
+                },
+            )
+            .optional()?;
+        Ok(reply)
+    }
+
+    /// Cache a complete text reply; replies cut short by an error or asking for tools are never passed here
+    pub fn put(&self, key: &str, model: &str, reply: &Reply) -> Result<()> {
+        let usage = reply.usage;
+        self.conn()?.execute(
+            "INSERT OR REPLACE INTO responses
+             (key, model, content, stop_reason, input_tokens, output_tokens, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                key,
+                model,
+                reply.content,
+                reply.stop_reason,
+                usage.input_tokens
+                    + usage.cache_creation_input_tokens
+                    + usage.cache_read_input_tokens,
+                usage.output_tokens,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Count a request answered from the cache; `reply.usage` is what the original request used
+    pub fn record_hit(&self, model: &str, reply: &Reply) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT INTO cache_usage (model, requests, hits, saved_input_tokens, saved_output_tokens)
+             VALUES (?1, 1, 1, ?2, ?3)
+             ON CONFLICT (model) DO UPDATE SET
+                requests = requests + 1,
+                hits = hits + 1,
+                saved_input_tokens = saved_input_tokens + excluded.saved_input_tokens,
+                saved_output_tokens = saved_output_tokens + excluded.saved_output_tokens",
+            params![model, reply.usage.input_tokens, reply.usage.output_tokens],
+        )?;
+        Ok(())
+    }
+
+    /// Count a request sent to the API and its prompt cache usage
+    pub fn record_request(&self, model: &str, usage: &Usage) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT INTO cache_usage (model, requests, cache_read_tokens, cache_write_tokens)
+             VALUES (?1, 1, ?2, ?3)
+             ON CONFLICT (model) DO UPDATE SET
+                requests = requests + 1,
+                cache_read_tokens = cache_read_tokens + excluded.cache_read_tokens,
+                cache_write_tokens = cache_write_tokens + excluded.cache_write_tokens",
+            params![
+                model,
+                usage.cache_read_input_tokens,
+                usage.cache_creation_input_tokens
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn stats(&self) -> Result<CacheStats> {
+        let conn = self.conn()?;
+        let mut stats = CacheStats {
+            entries: conn.query_row("SELECT COUNT(*) FROM responses", [], |row| {
+                row.get::<_, i64>(0)
+            })? as u64,
+            ..CacheStats::default()
+        };
+
+        let mut statement = conn.prepare(
+            "SELECT model, requests, hits, saved_input_tokens, saved_output_tokens,
+                    cache_read_tokens, cache_write_tokens
+             FROM cache_usage",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                [
+                    row.get::<_, i64>(1)? as u64,
+                    row.get::<_, i64>(2)? as u64,
+                    row.get::<_, i64>(3)? as u64,
+                    row.get::<_, i64>(4)? as u64,
+                    row.get::<_, i64>(5)? as u64,
+                    row.get::<_, i64>(6)? as u64,
+                ],
+            ))
+        })?;
+        for row in rows {
+            let (model, [requests, hits, saved_input, saved_output, read, write]) = row?;
+            let (input_price, output_price) = price_per_token(&model);
+            stats.requests += requests;
+            stats.response_hits += hits;
+            stats.prompt_cache_read_tokens += read;
+            stats.prompt_cache_write_tokens += write;
+            stats.estimated_savings_usd += saved_input as f64 * input_price
+                + saved_output as f64 * output_price
+                + read as f64 * input_price * (1.0 - CACHE_READ_PRICE)
+                - write as f64 * input_price * (CACHE_WRITE_PRICE - 1.0);
+        }
+        if stats.requests > 0 {
+            stats.hit_rate = stats.response_hits as f64 / stats.requests as f64;
+        }
+        Ok(stats)
+    }
+}
+
+/// Cache key of a request: hash of everything that shapes the reply
+///
+/// Whether the reply is streamed does not change it, so `stream` is left out.
+pub fn cache_key(request: &MessagesRequest) -> Result<String> {
+    let mut body = serde_json::to_value(request)?;
+    if let Some(body) = body.as_object_mut() {
+        body.remove("stream");
+    }
+    let bytes = serde_json::to_vec(&body)?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::client::Message;
+    use crate::config::ClaudeSettings;
+
+    fn reply(content: &str) -> Reply {
+        Reply {
+            content: content.to_string(),
+            stop_reason: Some("end_turn".to_string()),
+            usage: Usage {
+                input_tokens: 1_000,
+                output_tokens: 2_000,
+                ..Usage::default()
+            },
+            tool_calls: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_cache_key_ignores_streaming() {
+        let streaming = ClaudeSettings::default();
+        let buffered = ClaudeSettings {
+            enable_streaming: false,
+            ..ClaudeSettings::default()
+        };
+        let other_model = ClaudeSettings {
+            model: "claude-3-5-haiku-latest".to_string(),
+            ..ClaudeSettings::default()
+        };
+        let messages = vec![Message::user("Hi".to_string())];
+        let key = |settings: &ClaudeSettings| {
+            cache_key(&MessagesRequest::new(settings, messages.clone())).unwrap()
+        };
+
+        assert_eq!(key(&streaming), key(&buffered));
+        assert_ne!(key(&streaming), key(&other_model));
+    }
+
+    #[test]
+    fn test_get_put_and_expiry() {
+        let cache = ResponseCache::in_memory().unwrap();
+        assert!(cache.get("k", 60).unwrap().is_none());
+
+        cache.put("k", "claude-sonnet-4", &reply("Hello")).unwrap();
+        let cached = cache.get("k", 60).unwrap().unwrap();
+        assert_eq!(cached.content, "Hello");
+        assert_eq!(cached.usage.output_tokens, 2_000);
+
+        cache
+            .conn()
+            .unwrap()
+            .execute("UPDATE responses SET created_at = created_at - 120", [])
+            .unwrap();
+        assert!(cache.get("k", 60).unwrap().is_none());
+        assert_eq!(cache.stats().unwrap().entries, 0);
+    }
+
+    #[test]
+    fn test_stats() {
+        let cache = ResponseCache::in_memory().unwrap();
+        assert_eq!(cache.stats().unwrap(), CacheStats::default());
+
+        cache
+            .record_request(
+                "claude-sonnet-4",
+                &Usage {
+                    cache_read_input_tokens: 1_000_000,
+                    cache_creation_input_tokens: 1_000_000,
+                    ..Usage::default()
+                },
+            )
+            .unwrap();
+        cache
+            .record_hit("claude-sonnet-4", &reply("Hello"))
+            .unwrap();
+
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.requests, 2);
+        assert_eq!(stats.response_hits, 1);
+        assert_eq!(stats.hit_rate, 0.5);
+        assert_eq!(stats.prompt_cache_read_tokens, 1_000_000);
+        // 1k input + 2k output saved, 2.70 saved on reads, 0.75 spent on writes
+        let expected = 0.003 + 0.03 + 2.7 - 0.75;
+        assert!((stats.estimated_savings_usd - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,289 @@
+use super::explain;
+use crate::git::commitlint::{COMMIT_TYPES, HEADER_MAX_LENGTH};
+use crate::git::diff::RangeDiff;
+use crate::pty::shell_integration::CommandRecord;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Bytes of diff sent with a prompt; longer diffs are cut at a line boundary
+const PATCH_MAX_BYTES: usize = 60_000;
+
+/// Generated pull request text, for the user to edit before creating the PR
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PullRequestDraft {
+    pub title: String,
+    pub body: String,
+}
+
+/// Generated issue, for the user to review before create_issue
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssueProposal {
+    pub title: String,
+    /// Steps to reproduce, without numbering
+    pub steps: Vec<String>,
+    pub expected: String,
+    pub actual: String,
+    /// The whole description in Markdown, as create_issue takes it
+    pub body: String,
+}
+
+/// Prompt asking for an issue about the failure of `record`
+///
+/// `branch` and `failing_tests` (from the project's last test run) help
+/// Claude name the affected area; either may be empty.
+pub fn issue_draft_prompt(
+    record: &CommandRecord,
+    branch: Option<&str>,
+    failing_tests: &[String],
+) -> String {
+    let exit_code = record
+        .exit_code
+        .map_or("unknown".to_string(), |code| code.to_string());
+    let (output, trimmed) = explain::tail(&record.output);
+    let note = if trimmed || record.output_truncated {
+        "(earlier output omitted)\n"
+    } else {
+        ""
+    };
+    let mut tests = String::new();
+    for name in failing_tests {
+        tests.push_str("- ");
+        tests.push_str(name);
+        tests.push('\n');
+    }
+    if tests.is_empty() {
+        tests.push_str("(none recorded)\n");
+    }
+
+    format!(
+        "Draft a GitHub issue about the failure below, for a developer to review before filing.\n\n\
+         - Put a short, specific title on the first line, within {} characters, then a blank line.\n\
+         - Then write exactly these Markdown sections: `## Steps to reproduce` as a numbered list, \
+         `## Expected behavior` and `## Actual behavior`, quoting the relevant error lines.\n\
+         - Do not invent steps, versions or causes the context does not show; leave a step general instead.\n\
+         - Reply with the title and sections only, without code fences around the whole reply.\n\n\
+         Branch: {}\nWorking directory: {}\nExit code: {}\n\n\
+         <failing_tests>\n{}</failing_tests>\n\n\
+         <command>\n{}\n</command>\n\n<output>\n{}{}\n</output>",
+        HEADER_MAX_LENGTH,
+        branch.filter(|branch| !branch.is_empty()).unwrap_or("unknown"),
+        record.cwd.as_deref().unwrap_or("unknown"),
+        exit_code,
+        tests,
+        record.command,
+        note,
+        output
+    )
+}
+
+/// Prompt asking for a conventional commit message describing `patch`
+pub fn commit_message_prompt(patch: &str) -> Result<String> {
+    if patch.trim().is_empty() {
+        bail!("There are no changes to describe");
+    }
+    Ok(format!(
+        "Write a git commit message for the diff below.\n\n\
+         - Follow Conventional Commits: `type(scope): subject`, where type is one of {}.\n\
+         - Keep the header within {} characters, in the imperative mood, without a trailing period.\n\
+         - Add a body after a blank line only when the change needs explaining; wrap it at 72 characters and say why, not how.\n\
+         - Reply with the commit message only, without code fences or commentary.\n\n\
+         <diff>\n{}</diff>",
+        COMMIT_TYPES.join(", "),
+        HEADER_MAX_LENGTH,
+        truncate_patch(patch, PATCH_MAX_BYTES)
+    ))
+}
+
+/// Prompt asking for a pull request title and description of `range`
+pub fn pr_description_prompt(base: &str, head: &str, range: &RangeDiff) -> Result<String> {
+    if range.patch.trim().is_empty() {
+        bail!("{} has no changes compared to {}", head, base);
+    }
+    let mut commits = String::new();
+    for summary in &range.commits {
+        commits.push_str("- ");
+        commits.push_str(summary);
+        commits.push('\n');
+    }
+    Ok(format!(
+        "Write a pull request title and description for merging `{}` into `{}`.\n\n\
+         - Put the title on the first line, within {} characters, then a blank line, then the description.\n\
+         - Write the description in Markdown: a short summary of what changes and why, then a list of notable changes, then anything reviewers should test or watch out for.\n\
+         - Do not invent issue numbers, links or test results.\n\
+         - Reply with the title and description only, without code fences or commentary.\n\n\
+         <commits>\n{}</commits>\n\n<diff>\n{}</diff>",
+        head,
+        base,
+        HEADER_MAX_LENGTH,
+        commits,
+        truncate_patch(&range.patch, PATCH_MAX_BYTES)
+    ))
+}
+
+/// Generated text without code fences or surrounding whitespace
+pub fn clean_draft(text: &str) -> String {
+    let text = text.trim();
+    let Some(inner) = text.strip_prefix("```") else {
+        return text.to_string();
+    };
+    let Some(inner) = inner.strip_suffix("```") else {
+        return text.to_string();
+    };
+    // Drop the language tag after the opening fence
+    let inner = inner.split_once('\n').map_or("", |(_, rest)| rest);
+    inner.trim().to_string()
+}
+
+/// Split a generated pull request into its first line and the rest
+pub fn parse_pull_request(text: &str) -> PullRequestDraft {
+    let text = clean_draft(text);
+    let (title, body) = text.split_once('\n').unwrap_or((&text, ""));
+    PullRequestDraft {
+        title: title.trim().trim_start_matches('#').trim().to_string(),
+        body: body.trim().to_string(),
+    }
+}
+
+/// Split a generated issue into its title and sections
+///
+/// Sections are recognised by their headings; the body keeps the whole
+/// description, including anything outside the expected sections.
+pub fn parse_issue_proposal(text: &str) -> IssueProposal {
+    let draft = parse_pull_request(text);
+    let mut proposal = IssueProposal {
+        title: draft.title,
+        body: draft.body,
+        ..IssueProposal::default()
+    };
+    let mut section = None;
+    for line in proposal.body.lines() {
+        if let Some(heading) = line.trim_start().strip_prefix("##") {
+            let heading = heading.trim_start_matches('#').trim().to_lowercase();
+            section = if heading.contains("step") {
+                Some(0)
+            } else if heading.contains("expected") {
+                Some(1)
+            } else if heading.contains("actual") {
+                Some(2)
+            } else {
+                None
+            };
+            continue;
+        }
+        match section {
+            Some(0) => {
+                let step = line
+                    .trim()
+                    .trim_start_matches(|c: char| c.is_ascii_digit())
+                    .trim_start_matches(['.', ')', '-', '*'])
+                    .trim();
+                if !step.is_empty() {
+                    proposal.steps.push(step.to_string());
+                }
+            }
+            Some(1) => push_line(&mut proposal.expected, line),
+            Some(2) => push_line(&mut proposal.actual, line),
+            _ => {}
+        }
+    }
+    proposal.expected = proposal.expected.trim().to_string();
+    proposal.actual = proposal.actual.trim().to_string();
+    proposal
+}
+
+fn push_line(text: &mut String, line: &str) {
+    text.push_str(line);
+    text.push('\n');
+}
+
+fn truncate_patch(patch: &str, max_bytes: usize) -> String {
+    if patch.len() <= max_bytes {
+        return patch.to_string();
+    }
+    let mut end = max_bytes;
+    while !patch.is_char_boundary(end) {
+        end -= 1;
+    }
+    let end = patch[..end].rfind('\n').map_or(end, |newline| newline + 1);
+    let omitted = patch[end..].lines().count();
+    format!(
+        "{}[diff truncated: {} more lines]\n",
+        &patch[..end],
+        omitted
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompts() {
+        assert!(commit_message_prompt("  \n").is_err());
+        let prompt = commit_message_prompt("+added\n").unwrap();
+        assert!(prompt.contains("feat, fix"));
+        assert!(prompt.ends_with("<diff>\n+added\n</diff>"));
+
+        let range = RangeDiff {
+            commits: vec!["Add login".to_string()],
+            patch: "+login\n".to_string(),
+        };
+        let prompt = pr_description_prompt("main", "feature", &range).unwrap();
+        assert!(prompt.contains("merging `feature` into `main`"));
+        assert!(prompt.contains("<commits>\n- Add login\n</commits>"));
+    }
+
+    #[test]
+    fn test_truncate_patch() {
+        let patch = "+line\n".repeat(10);
+        assert_eq!(truncate_patch(&patch, 100), patch);
+        assert_eq!(
+            truncate_patch(&patch, 15),
+            "+line\n+line\n[diff truncated: 8 more lines]\n"
+        );
+        assert!(truncate_patch("é\né", 1).starts_with("[diff truncated"));
+    }
+
+    #[test]
+    fn test_clean_and_parse_drafts() {
+        assert_eq!(
+            clean_draft("```text\nfix: handle resize\n```"),
+            "fix: handle resize"
+        );
+        assert_eq!(clean_draft("  feat: add x \n"), "feat: add x");
+
+        let draft = parse_pull_request("# Add login\n\n## Summary\nAdds a page");
+        assert_eq!(draft.title, "Add login");
+        assert_eq!(draft.body, "## Summary\nAdds a page");
+        assert_eq!(parse_pull_request("Only a title").body, "");
+    }
+
+    #[test]
+    fn test_issue_drafts() {
+        let record = CommandRecord {
+            command: "cargo test".to_string(),
+            cwd: Some("/work".to_string()),
+            exit_code: Some(101),
+            output: "test adds ... FAILED\n".to_string(),
+            output_truncated: false,
+            started_at: chrono::Utc::now(),
+            duration_ms: 10,
+        };
+        let prompt = issue_draft_prompt(&record, Some("42-login"), &["adds".to_string()]);
+        assert!(prompt.contains("Branch: 42-login"));
+        assert!(prompt.contains("<failing_tests>\n- adds\n</failing_tests>"));
+        assert!(issue_draft_prompt(&record, None, &[]).contains("(none recorded)"));
+
+        let proposal = parse_issue_proposal(
+            "Login test fails on empty password\n\n\
+             ## Steps to reproduce\n1. Check out `42-login`\n2. Run `cargo test`\n\n\
+             ## Expected behavior\nAll tests pass.\n\n\
+             ## Actual behavior\n`adds` fails:\n```\nassertion failed\n```\n",
+        );
+        assert_eq!(proposal.title, "Login test fails on empty password");
+        assert_eq!(proposal.steps, ["Check out `42-login`", "Run `cargo test`"]);
+        assert_eq!(proposal.expected, "All tests pass.");
+        assert_eq!(proposal.actual, "`adds` fails:\n```\nassertion failed\n```");
+        assert!(proposal.body.starts_with("## Steps to reproduce"));
+    }
+}
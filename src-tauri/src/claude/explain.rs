@@ -0,0 +1,91 @@
+use crate::pty::command;
+use crate::pty::shell_integration::CommandRecord;
+
+/// Lines of command output sent with a prompt, counted from the end
+const OUTPUT_MAX_LINES: usize = 80;
+
+/// Bytes of command output sent with a prompt, counted from the end
+const OUTPUT_MAX_BYTES: usize = 8_000;
+
+/// Prompt asking why `record` failed and how to fix it
+///
+/// A PTY merges stdout and stderr, so the end of the combined output is sent;
+/// that is where compilers, test runners and shells put their errors.
+pub fn error_explanation_prompt(record: &CommandRecord, shell: &str) -> String {
+    let exit_code = record
+        .exit_code
+        .map_or("unknown".to_string(), |code| code.to_string());
+    let cwd = record.cwd.as_deref().unwrap_or("unknown");
+    let (output, trimmed) = tail(&record.output);
+    let note = if trimmed || record.output_truncated {
+        "(earlier output omitted)\n"
+    } else {
+        ""
+    };
+
+    format!(
+        "A command failed in my terminal. Explain the most likely cause in a few sentences, \
+         then suggest a fix. Put any commands to run in fenced code blocks. If the output is \
+         not enough to tell, say what to check next.\n\n\
+         Shell: {}\nOS: {}\nWorking directory: {}\nExit code: {}\n\n\
+         <command>\n{}\n</command>\n\n<output>\n{}{}\n</output>",
+        shell,
+        std::env::consts::OS,
+        cwd,
+        exit_code,
+        record.command,
+        note,
+        output
+    )
+}
+
+/// The end of `output` within OUTPUT_MAX_LINES and OUTPUT_MAX_BYTES, and whether anything was cut
+pub(super) fn tail(output: &str) -> (&str, bool) {
+    let mut tail = command::output_tail(output, OUTPUT_MAX_BYTES);
+    if let Some((index, _)) = tail.rmatch_indices('\n').nth(OUTPUT_MAX_LINES - 1) {
+        tail = &tail[index + 1..];
+    }
+    (tail, tail.len() < output.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(output: String) -> CommandRecord {
+        CommandRecord {
+            command: "cargo tset".to_string(),
+            cwd: Some("/work".to_string()),
+            exit_code: Some(101),
+            output,
+            output_truncated: false,
+            started_at: chrono::Utc::now(),
+            duration_ms: 10,
+        }
+    }
+
+    #[test]
+    fn test_prompt() {
+        let prompt = error_explanation_prompt(&record("error: no such command".to_string()), "zsh");
+        assert!(prompt.contains("Shell: zsh"));
+        assert!(prompt.contains("Exit code: 101"));
+        assert!(prompt.contains("<command>\ncargo tset\n</command>"));
+        assert!(prompt.ends_with("<output>\nerror: no such command\n</output>"));
+    }
+
+    #[test]
+    fn test_tail() {
+        let lines: Vec<String> = (0..100).map(|n| n.to_string()).collect();
+        let joined = lines.join("\n");
+        let (output, trimmed) = tail(&joined);
+        assert!(trimmed);
+        assert_eq!(output.lines().count(), OUTPUT_MAX_LINES);
+        assert!(output.starts_with("20\n"));
+
+        let long = "é".repeat(OUTPUT_MAX_BYTES);
+        let (output, trimmed) = tail(&long);
+        assert!(trimmed);
+        assert!(output.len() <= OUTPUT_MAX_BYTES);
+        assert_eq!(tail("short"), ("short", false));
+    }
+}
@@ -0,0 +1,282 @@
+use super::client::{Message, Role};
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Longest generated conversation title, in characters
+const TITLE_MAX_LENGTH: usize = 60;
+
+/// A conversation as listed in the history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub title: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub message_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredMessage {
+    pub role: Role,
+    pub content: String,
+    pub created_at: String,
+}
+
+/// A conversation with all of its messages, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    #[serde(flatten)]
+    pub summary: ConversationSummary,
+    pub messages: Vec<StoredMessage>,
+}
+
+/// SQLite store of Claude conversations, kept in ~/.zeami/claude.db
+pub struct ConversationStore {
+    conn: Mutex<Connection>,
+}
+
+impl ConversationStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open conversation store {:?}", path))?;
+        Self::init(conn)
+    }
+
+    /// A store that lives only as long as the process
+    pub fn in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "PRAGMA foreign_keys = ON;
+            CREATE TABLE IF NOT EXISTS conversations (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS messages (
+                conversation_id TEXT NOT NULL REFERENCES conversations (id) ON DELETE CASCADE,
+                seq INTEGER NOT NULL,
+                role TEXT NOT NULL,
+                content TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (conversation_id, seq)
+            );",
+        )
+        .context("Failed to initialize conversation store")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock conversation store: {}", e))
+    }
+
+    /// Messages of a conversation in API form; empty for a new conversation
+    pub fn history(&self, id: &str) -> Result<Vec<Message>> {
+        let conn = self.conn()?;
+        Ok(messages(&conn, id)?
+            .into_iter()
+            .map(|message| Message {
+                role: message.role,
+                content: message.content,
+            })
+            .collect())
+    }
+
+    /// Record a completed exchange, creating the conversation on its first message
+    ///
+    /// New conversations are titled after the first line of the user's message.
+    pub fn append(&self, id: &str, user: &Message, assistant: &Message) -> Result<()> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO conversations (id, title, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)
+             ON CONFLICT (id) DO UPDATE SET updated_at = excluded.updated_at",
+            params![id, generate_title(&user.content), now],
+        )?;
+        let next: i64 = tx.query_row(
+            "SELECT COALESCE(MAX(seq), -1) + 1 FROM messages WHERE conversation_id = ?1",
+            params![id],
+            |row| row.get(0),
+        )?;
+        for (offset, message) in [user, assistant].into_iter().enumerate() {
+            tx.execute(
+                "INSERT INTO messages (conversation_id, seq, role, content, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    id,
+                    next + offset as i64,
+                    role_name(message.role),
+                    message.content,
+                    now
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// All conversations, most recently active first
+    pub fn list(&self) -> Result<Vec<ConversationSummary>> {
+        let conn = self.conn()?;
+        let mut statement = conn.prepare(
+            "SELECT c.id, c.title, c.created_at, c.updated_at,
+                    (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id)
+             FROM conversations c
+             ORDER BY c.updated_at DESC",
+        )?;
+        let summaries = statement
+            .query_map([], summary_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(summaries)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<Conversation>> {
+        let conn = self.conn()?;
+        let summary = conn
+            .query_row(
+                "SELECT c.id, c.title, c.created_at, c.updated_at,
+                        (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id)
+                 FROM conversations c WHERE c.id = ?1",
+                params![id],
+                summary_from_row,
+            )
+            .optional()?;
+        let Some(summary) = summary else {
+            return Ok(None);
+        };
+        let messages = messages(&conn, id)?;
+        Ok(Some(Conversation { summary, messages }))
+    }
+
+    /// Remove a conversation and its messages; returns false when it did not exist
+    pub fn delete(&self, id: &str) -> Result<bool> {
+        let deleted = self
+            .conn()?
+            .execute("DELETE FROM conversations WHERE id = ?1", params![id])?;
+        Ok(deleted > 0)
+    }
+}
+
+fn messages(conn: &Connection, id: &str) -> Result<Vec<StoredMessage>> {
+    let mut statement = conn.prepare(
+        "SELECT role, content, created_at FROM messages
+         WHERE conversation_id = ?1 ORDER BY seq",
+    )?;
+    let messages = statement
+        .query_map(params![id], |row| {
+            let role: String = row.get(0)?;
+            Ok(StoredMessage {
+                role: if role == "assistant" {
+                    Role::Assistant
+                } else {
+                    Role::User
+                },
+                content: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(messages)
+}
+
+fn summary_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ConversationSummary> {
+    Ok(ConversationSummary {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        created_at: row.get(2)?,
+        updated_at: row.get(3)?,
+        message_count: row.get::<_, i64>(4)? as usize,
+    })
+}
+
+fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+/// First non-empty line of `content`, cut at a word boundary to TITLE_MAX_LENGTH
+fn generate_title(content: &str) -> String {
+    let line = content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("New conversation");
+    if line.chars().count() <= TITLE_MAX_LENGTH {
+        return line.to_string();
+    }
+
+    let cut: String = line.chars().take(TITLE_MAX_LENGTH).collect();
+    let title = match cut.rfind(char::is_whitespace) {
+        Some(space) if space > TITLE_MAX_LENGTH / 2 => &cut[..space],
+        _ => cut.as_str(),
+    };
+    format!("{}…", title.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_list_get_delete() {
+        let store = ConversationStore::in_memory().unwrap();
+        assert!(store.history("a").unwrap().is_empty());
+
+        store
+            .append(
+                "a",
+                &Message::user("\nExplain the PTY module\nin detail".to_string()),
+                &Message::assistant("It spawns shells".to_string()),
+            )
+            .unwrap();
+        store
+            .append(
+                "a",
+                &Message::user("Thanks".to_string()),
+                &Message::assistant("You're welcome".to_string()),
+            )
+            .unwrap();
+
+        let history = store.history("a").unwrap();
+        assert_eq!(history.len(), 4);
+        assert_eq!(history[3], Message::assistant("You're welcome".to_string()));
+
+        let list = store.list().unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].title, "Explain the PTY module");
+        assert_eq!(list[0].message_count, 4);
+
+        let conversation = store.get("a").unwrap().unwrap();
+        assert_eq!(conversation.messages[1].role, Role::Assistant);
+        assert!(store.get("b").unwrap().is_none());
+
+        assert!(store.delete("a").unwrap());
+        assert!(!store.delete("a").unwrap());
+        assert!(store.history("a").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_generate_title() {
+        assert_eq!(generate_title("  "), "New conversation");
+        let long = "word ".repeat(20);
+        let title = generate_title(&long);
+        assert!(title.ends_with('…'));
+        assert!(title.chars().count() <= TITLE_MAX_LENGTH + 1);
+    }
+}
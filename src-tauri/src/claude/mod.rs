@@ -0,0 +1,175 @@
+pub mod cache;
+pub mod client;
+pub mod drafts;
+pub mod explain;
+pub mod history;
+pub mod openai;
+pub mod prompt;
+pub mod provider;
+pub mod redact;
+pub mod requests;
+pub mod stream;
+pub mod suggest;
+pub mod tools;
+pub mod transcript;
+pub mod usage;
+
+pub use client::{ClaudeClient, Message, MessagesRequest, Reply, Usage};
+
+use crate::config::keychain::{self, SecretKey};
+use crate::config::{storage, ClaudeSettings, LlmProviderKind};
+use anyhow::{Context, Result};
+use cache::ResponseCache;
+use history::ConversationStore;
+use openai::OpenAiClient;
+use provider::LlmProvider;
+use requests::InFlight;
+use tools::ToolApprovals;
+use usage::UsageLedger;
+
+/// No Claude API key is stored for the active profile
+#[derive(Debug, thiserror::Error)]
+#[error("No Claude API key is stored; add one in settings")]
+pub struct MissingApiKey;
+
+/// Shared HTTP client, conversation history, response cache, usage ledger,
+/// pending tool approvals and in-flight requests
+pub struct ClaudeState {
+    http: reqwest::Client,
+    pub conversations: ConversationStore,
+    pub responses: ResponseCache,
+    pub usage: UsageLedger,
+    pub approvals: ToolApprovals,
+    pub requests: InFlight,
+}
+
+impl ClaudeState {
+    /// Open ~/.zeami/claude.db and usage.json, falling back to in-memory stores
+    pub fn open() -> Self {
+        let dir = storage::config_dir();
+        let path = dir.as_ref().map(|dir| dir.join("claude.db"));
+        let conversations = path
+            .as_ref()
+            .map_err(|e| anyhow::anyhow!("{:#}", e))
+            .and_then(|path| ConversationStore::open(path))
+            .or_else(|e| {
+                tracing::warn!("Using in-memory conversation store: {:#}", e);
+                ConversationStore::in_memory()
+            })
+            .expect("failed to create in-memory conversation store");
+        let responses = path
+            .as_ref()
+            .map_err(|e| anyhow::anyhow!("{:#}", e))
+            .and_then(|path| ResponseCache::open(path))
+            .or_else(|e| {
+                tracing::warn!("Using in-memory response cache: {:#}", e);
+                ResponseCache::in_memory()
+            })
+            .expect("failed to create in-memory response cache");
+        let usage = match &dir {
+            Ok(dir) => UsageLedger::open(&dir.join("usage.json")),
+            Err(e) => {
+                tracing::warn!("Using in-memory usage ledger: {:#}", e);
+                UsageLedger::in_memory()
+            }
+        };
+
+        Self {
+            http: reqwest::Client::new(),
+            conversations,
+            responses,
+            usage,
+            approvals: ToolApprovals::default(),
+            requests: InFlight::default(),
+        }
+    }
+
+    /// Client for the Messages API sharing this state's connection pool
+    pub fn client(&self, api_key: String) -> ClaudeClient {
+        ClaudeClient::new(self.http.clone(), api_key)
+    }
+
+    /// Send `request` to the provider the settings select
+    async fn send(
+        &self,
+        settings: &ClaudeSettings,
+        request: &MessagesRequest,
+        on_text: impl FnMut(&str) + Send,
+    ) -> Result<Reply> {
+        match settings.provider {
+            LlmProviderKind::Anthropic => {
+                let api_key = keychain::retrieve_secret(SecretKey::ClaudeApiKey)
+                    .context("Failed to read the Claude API key")?
+                    .ok_or(MissingApiKey)?;
+                let client = self.client(api_key).with_base_url(&settings.base_url);
+                LlmProvider::send(&client, request, on_text).await
+            }
+            LlmProviderKind::OpenAi => {
+                // Local servers usually need no key; OPENAI_API_KEY is read
+                // when none is stored, as other OpenAI clients do
+                let api_key = match keychain::retrieve_secret(SecretKey::LlmApiKey)
+                    .context("Failed to read the provider API key")?
+                {
+                    Some(key) => Some(key),
+                    None => std::env::var(openai::API_KEY_ENV).ok(),
+                };
+                let client = OpenAiClient::new(self.http.clone(), &settings.base_url, api_key);
+                client.send(request, on_text).await
+            }
+        }
+    }
+
+    /// Send `request`, answering from the response cache when an identical
+    /// request was made within ClaudeSettings::response_cache_ttl
+    ///
+    /// A cached reply is passed to `on_text` in one piece and reports no usage.
+    /// Usage is only recorded for Anthropic, as other providers are not billed
+    /// at Claude's prices.
+    pub async fn complete(
+        &self,
+        settings: &ClaudeSettings,
+        request: &MessagesRequest,
+        mut on_text: impl FnMut(&str) + Send,
+    ) -> Result<Reply> {
+        let ttl = if settings.enable_caching {
+            settings.response_cache_ttl
+        } else {
+            0
+        };
+        let key = cache::cache_key(request)?;
+        if ttl > 0 {
+            match self.responses.get(&key, ttl) {
+                Ok(Some(cached)) => {
+                    if let Err(e) = self.responses.record_hit(&settings.model, &cached) {
+                        tracing::warn!("Failed to record response cache hit: {:#}", e);
+                    }
+                    on_text(&cached.content);
+                    return Ok(Reply {
+                        usage: Usage::default(),
+                        ..cached
+                    });
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to read response cache: {:#}", e),
+            }
+        }
+
+        let reply = self.send(settings, request, on_text).await?;
+
+        if settings.provider == LlmProviderKind::Anthropic {
+            if let Err(e) = self.responses.record_request(&settings.model, &reply.usage) {
+                tracing::warn!("Failed to record prompt cache usage: {:#}", e);
+            }
+            let today = chrono::Local::now().date_naive();
+            if let Err(e) = self.usage.record(&settings.model, &reply.usage, today) {
+                tracing::warn!("Failed to record Claude usage: {:#}", e);
+            }
+        }
+        if ttl > 0 && reply.tool_calls.is_empty() {
+            if let Err(e) = self.responses.put(&key, &settings.model, &reply) {
+                tracing::warn!("Failed to cache Claude reply: {:#}", e);
+            }
+        }
+        Ok(reply)
+    }
+}
@@ -0,0 +1,281 @@
+//! Client for OpenAI-compatible chat completions APIs, e.g. Ollama or llama.cpp
+
+use super::client::{self, ApiStatusError, MessagesRequest, Reply, Role, Usage};
+use super::provider::LlmProvider;
+use super::stream::SseParser;
+use anyhow::{bail, Context, Result};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+
+/// API root used when ClaudeSettings::base_url is empty
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Environment variable read for the API key when none is stored in the keychain
+pub const API_KEY_ENV: &str = "OPENAI_API_KEY";
+
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    temperature: f32,
+    stream: bool,
+    messages: Vec<ChatMessage>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct ChatMessage {
+    role: &'static str,
+    content: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ChatUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+}
+
+impl From<ChatUsage> for Usage {
+    fn from(usage: ChatUsage) -> Self {
+        Usage {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+            ..Usage::default()
+        }
+    }
+}
+
+/// Body of a non-streaming response, or one chunk of a streaming one
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    #[serde(default)]
+    choices: Vec<Choice>,
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    /// Whole reply of a non-streaming response
+    message: Option<ChoiceText>,
+    /// Next piece of a streaming response
+    delta: Option<ChoiceText>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChoiceText {
+    content: Option<String>,
+}
+
+/// Chat completions client
+///
+/// Tools are not offered, so requests that need them (the agent) are refused.
+pub struct OpenAiClient {
+    http: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiClient {
+    /// Client for the API at `base_url`, or DEFAULT_BASE_URL when it is empty
+    pub fn new(http: reqwest::Client, base_url: &str, api_key: Option<String>) -> Self {
+        let base_url = match base_url.trim().trim_end_matches('/') {
+            "" => DEFAULT_BASE_URL,
+            base_url => base_url,
+        };
+        Self {
+            http,
+            url: format!("{}/chat/completions", base_url),
+            api_key: api_key.filter(|key| !key.trim().is_empty()),
+        }
+    }
+}
+
+impl LlmProvider for OpenAiClient {
+    async fn send(
+        &self,
+        request: &MessagesRequest,
+        mut on_text: impl FnMut(&str) + Send,
+    ) -> Result<Reply> {
+        if request.offers_tools() {
+            bail!("The agent needs tool use, which only the anthropic provider supports");
+        }
+        let body = chat_request(request);
+        let response = client::send_with_retries("OpenAI-compatible API", || {
+            let builder = self.http.post(&self.url).json(&body);
+            match &self.api_key {
+                Some(key) => builder.bearer_auth(key),
+                None => builder,
+            }
+        })
+        .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApiStatusError {
+                status: status.as_u16(),
+                message: format!("{} returned {}: {}", self.url, status, error_message(&body)),
+            }
+            .into());
+        }
+
+        let mut reply = Reply::default();
+        if !request.streaming() {
+            let response: ChatResponse = response
+                .json()
+                .await
+                .context("Invalid chat completion response")?;
+            apply(&mut reply, response, &mut on_text);
+            return Ok(reply);
+        }
+
+        let mut parser = SseParser::default();
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.context("Chat completion stream was interrupted")?;
+            for data in parser.push(&chunk) {
+                if data.trim() == "[DONE]" {
+                    return Ok(reply);
+                }
+                let chunk: ChatResponse =
+                    serde_json::from_str(&data).context("Invalid chat completion chunk")?;
+                apply(&mut reply, chunk, &mut on_text);
+            }
+        }
+        Ok(reply)
+    }
+}
+
+/// The request as a chat completion, with the system text as the first message
+fn chat_request(request: &MessagesRequest) -> ChatRequest<'_> {
+    let system = request.system_text().map(|text| ChatMessage {
+        role: "system",
+        content: text.to_string(),
+    });
+    let messages = request
+        .text_messages()
+        .into_iter()
+        .map(|message| ChatMessage {
+            role: match message.role {
+                Role::User => "user",
+                Role::Assistant => "assistant",
+            },
+            content: message.content,
+        });
+    ChatRequest {
+        model: request.model(),
+        max_tokens: request.max_tokens(),
+        temperature: request.temperature(),
+        stream: request.streaming(),
+        messages: system.into_iter().chain(messages).collect(),
+    }
+}
+
+/// Add a response or stream chunk to `reply`
+fn apply(reply: &mut Reply, response: ChatResponse, on_text: &mut impl FnMut(&str)) {
+    if let Some(usage) = response.usage {
+        reply.usage = usage.into();
+    }
+    let Some(choice) = response.choices.into_iter().next() else {
+        return;
+    };
+    let text = choice
+        .message
+        .or(choice.delta)
+        .and_then(|text| text.content)
+        .unwrap_or_default();
+    if !text.is_empty() {
+        on_text(&text);
+        reply.content.push_str(&text);
+    }
+    if choice.finish_reason.is_some() {
+        reply.stop_reason = choice.finish_reason;
+    }
+}
+
+/// The message of an error response; OpenAI nests it in an object, Ollama
+/// sends a string
+fn error_message(body: &str) -> String {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+        return body.trim().to_string();
+    };
+    let error = &value["error"];
+    error["message"]
+        .as_str()
+        .or_else(|| error.as_str())
+        .map_or_else(|| body.trim().to_string(), str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::client::Message;
+    use crate::config::ClaudeSettings;
+
+    #[test]
+    fn test_chat_request() {
+        let settings = ClaudeSettings {
+            model: "llama3.1".to_string(),
+            system_prompt: "Be brief".to_string(),
+            ..ClaudeSettings::default()
+        };
+        let request = MessagesRequest::new(
+            &settings,
+            vec![
+                Message::user("Hi".to_string()),
+                Message::assistant("Hello".to_string()),
+            ],
+        );
+        let body = serde_json::to_value(chat_request(&request)).unwrap();
+        assert_eq!(body["model"], "llama3.1");
+        assert_eq!(body["stream"], true);
+        assert_eq!(
+            body["messages"],
+            serde_json::json!([
+                { "role": "system", "content": "Be brief" },
+                { "role": "user", "content": "Hi" },
+                { "role": "assistant", "content": "Hello" }
+            ])
+        );
+
+        let client = OpenAiClient::new(reqwest::Client::new(), "http://localhost:11434/v1/", None);
+        assert_eq!(client.url, "http://localhost:11434/v1/chat/completions");
+        assert!(
+            OpenAiClient::new(reqwest::Client::new(), "", Some(" ".to_string()))
+                .api_key
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_stream_chunks() {
+        let mut reply = Reply::default();
+        let mut streamed = String::new();
+        let mut on_text = |text: &str| streamed.push_str(text);
+        for data in [
+            r#"{"choices":[{"delta":{"role":"assistant","content":"fix: "},"finish_reason":null}]}"#,
+            r#"{"choices":[{"delta":{"content":"handle resize"},"finish_reason":"stop"}]}"#,
+            r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":4}}"#,
+        ] {
+            apply(
+                &mut reply,
+                serde_json::from_str(data).unwrap(),
+                &mut on_text,
+            );
+        }
+        assert_eq!(streamed, "fix: handle resize");
+        assert_eq!(reply.content, "fix: handle resize");
+        assert_eq!(reply.stop_reason.as_deref(), Some("stop"));
+        assert_eq!(reply.usage.output_tokens, 4);
+
+        assert_eq!(
+            error_message(r#"{"error":"model \"x\" not found"}"#),
+            "model \"x\" not found"
+        );
+        assert_eq!(
+            error_message(r#"{"error":{"message":"Invalid key","type":"auth"}}"#),
+            "Invalid key"
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Variables in the system prompt and custom instructions
+//!
+//! `{{name}}` in ClaudeSettings::system_prompt or custom_instructions is
+//! replaced when a request is built. Unknown names are left as written, so
+//! text that happens to contain braces is sent unchanged.
+
+use super::redact::Redactor;
+use crate::config::{ClaudeSettings, Settings};
+use crate::git::{self, links};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Values of the template variables at the time of a request
+///
+/// `repo` is the configured GitHub repository, `project` the name of the
+/// project directory, `branch` the checked-out branch and `current_issue` the
+/// issue linked to it as `#42 Title`; each is empty when unknown.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PromptContext {
+    pub variables: BTreeMap<String, String>,
+}
+
+impl PromptContext {
+    /// Variables for a request about `project_root`, if it has one
+    pub fn gather(settings: &Settings, project_root: Option<&Path>) -> Self {
+        let mut variables = BTreeMap::new();
+        let mut set = |name: &str, value: String| {
+            variables.insert(name.to_string(), value);
+        };
+        set("repo", settings.github.repository.trim().to_string());
+        set("os", std::env::consts::OS.to_string());
+        set(
+            "date",
+            chrono::Local::now()
+                .date_naive()
+                .format("%Y-%m-%d")
+                .to_string(),
+        );
+
+        let repo = project_root.and_then(|root| git::open(root).ok());
+        let project = repo
+            .as_ref()
+            .and_then(|repo| git::workdir(repo).ok())
+            .or_else(|| project_root.map(Path::to_path_buf));
+        set(
+            "project",
+            project
+                .as_deref()
+                .and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        );
+        set(
+            "branch",
+            repo.as_ref()
+                .and_then(|repo| git::current_branch(repo).ok())
+                .unwrap_or_default(),
+        );
+        let issue = project_root
+            .and_then(|root| links::linked_issue(root, &settings.git).ok().flatten())
+            .map(|link| format!("#{} {}", link.issue, link.title).trim().to_string());
+        set("current_issue", issue.unwrap_or_default());
+
+        Self { variables }
+    }
+
+    /// `template` with every known `{{name}}` replaced
+    pub fn render(&self, template: &str) -> String {
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start..].find("}}") else {
+                break;
+            };
+            let name = rest[start + 2..start + end].trim();
+            rendered.push_str(&rest[..start]);
+            match self.variables.get(name) {
+                Some(value) => rendered.push_str(value),
+                None => rendered.push_str(&rest[start..start + end + 2]),
+            }
+            rest = &rest[start + end + 2..];
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+
+    /// Names in `template` that are not variables
+    pub fn unknown(&self, template: &str) -> Vec<String> {
+        let mut unknown = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start..].find("}}") else {
+                break;
+            };
+            let name = rest[start + 2..start + end].trim().to_string();
+            if !self.variables.contains_key(&name) && !unknown.contains(&name) {
+                unknown.push(name);
+            }
+            rest = &rest[start + end + 2..];
+        }
+        unknown
+    }
+
+    /// `settings` with the system prompt and custom instructions rendered
+    pub fn apply(&self, settings: &ClaudeSettings) -> ClaudeSettings {
+        ClaudeSettings {
+            system_prompt: self.render(&settings.system_prompt),
+            custom_instructions: self.render(&settings.custom_instructions),
+            ..settings.clone()
+        }
+    }
+}
+
+/// Returned by preview_system_prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemPromptPreview {
+    /// The system text a request would send: the rendered system prompt and
+    /// custom instructions, secrets redacted
+    pub system: String,
+    pub variables: BTreeMap<String, String>,
+    /// `{{names}}` left as written because no such variable exists
+    pub unknown: Vec<String>,
+}
+
+/// What a request built with `settings` in `context` would send as its system text
+pub fn preview(settings: &ClaudeSettings, context: &PromptContext) -> SystemPromptPreview {
+    let rendered = context.apply(settings);
+    let system = [&rendered.system_prompt, &rendered.custom_instructions]
+        .into_iter()
+        .map(|text| text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let system = Redactor::new(&settings.redaction_allowlist).apply(system);
+    let mut unknown = context.unknown(&settings.system_prompt);
+    for name in context.unknown(&settings.custom_instructions) {
+        if !unknown.contains(&name) {
+            unknown.push(name);
+        }
+    }
+    SystemPromptPreview {
+        system,
+        variables: context.variables.clone(),
+        unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_all, init_repo};
+
+    fn context() -> PromptContext {
+        let variables = [
+            ("repo", "acme/app"),
+            ("branch", "42-login"),
+            ("os", "linux"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        PromptContext { variables }
+    }
+
+    #[test]
+    fn test_renders_known_variables_only() {
+        let context = context();
+        assert_eq!(
+            context.render("Working on {{repo}} ({{ branch }}) on {{os}}."),
+            "Working on acme/app (42-login) on linux."
+        );
+        assert_eq!(
+            context.render("Keep {{unknown}} and {{ unclosed"),
+            "Keep {{unknown}} and {{ unclosed"
+        );
+        assert_eq!(context.unknown("{{repo}} {{x}} {{x}}"), ["x"]);
+    }
+
+    #[test]
+    fn test_preview_joins_the_rendered_system_text() {
+        let settings = ClaudeSettings {
+            system_prompt: "Repository: {{repo}}".to_string(),
+            custom_instructions: "Target {{target}}".to_string(),
+            ..ClaudeSettings::default()
+        };
+        let preview = preview(&settings, &context());
+        assert_eq!(preview.system, "Repository: acme/app\n\nTarget {{target}}");
+        assert_eq!(preview.unknown, ["target"]);
+    }
+
+    #[test]
+    fn test_gathers_the_project_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        commit_all(&repo, "Init");
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("issue-42-login", &head, false).unwrap();
+        repo.set_head("refs/heads/issue-42-login").unwrap();
+
+        let context = PromptContext::gather(&Settings::default(), Some(dir.path()));
+        assert_eq!(context.variables["branch"], "issue-42-login");
+        assert_eq!(context.variables["current_issue"], "#42");
+        assert_eq!(context.variables["os"], std::env::consts::OS);
+        assert!(PromptContext::gather(&Settings::default(), None).variables["branch"].is_empty());
+    }
+}
@@ -0,0 +1,24 @@
+//! Services that answer the requests the Claude commands build
+//!
+//! ClaudeSettings::provider picks Anthropic's Messages API (ClaudeClient) or
+//! an OpenAI-compatible chat completions API (OpenAiClient), such as a local
+//! Ollama server for machines without internet access. Both pass reply text to
+//! `on_text` as it arrives, so commands and `claude-stream` events work the
+//! same whichever answers.
+
+use super::client::{MessagesRequest, Reply};
+use anyhow::Result;
+use std::future::Future;
+
+/// A service that completes MessagesRequest
+pub trait LlmProvider {
+    /// Send a request and return the reply
+    ///
+    /// Streaming requests call `on_text` with every text delta as it arrives;
+    /// otherwise it is called once with the whole reply.
+    fn send(
+        &self,
+        request: &MessagesRequest,
+        on_text: impl FnMut(&str) + Send,
+    ) -> impl Future<Output = Result<Reply>> + Send;
+}
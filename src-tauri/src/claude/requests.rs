@@ -0,0 +1,88 @@
+//! In-flight requests, cancellable by the id their `claude-stream` events carry
+
+use anyhow::Result;
+use futures_util::future::{AbortHandle, Abortable};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Returned by InFlight::run when the request was cancelled
+#[derive(Debug, thiserror::Error)]
+#[error("Claude request was cancelled")]
+pub struct Cancelled;
+
+/// Requests running under an id, usually the conversation or stream id
+#[derive(Debug, Default)]
+pub struct InFlight {
+    handles: Mutex<HashMap<String, (u64, AbortHandle)>>,
+    next: AtomicU64,
+}
+
+impl InFlight {
+    /// Run `request` under `id` until it finishes or `cancel(id)` is called
+    ///
+    /// Cancelling drops the request, which closes its HTTP connection and
+    /// stops any retry backoff. A newer request under the same id replaces
+    /// the older one as the one `cancel` stops.
+    pub async fn run<T>(&self, id: &str, request: impl Future<Output = Result<T>>) -> Result<T> {
+        let (handle, registration) = AbortHandle::new_pair();
+        let generation = self.next.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut handles) = self.handles.lock() {
+            handles.insert(id.to_string(), (generation, handle));
+        }
+
+        let result = Abortable::new(request, registration).await;
+
+        if let Ok(mut handles) = self.handles.lock() {
+            if handles
+                .get(id)
+                .is_some_and(|(current, _)| *current == generation)
+            {
+                handles.remove(id);
+            }
+        }
+        result.unwrap_or_else(|_| Err(Cancelled.into()))
+    }
+
+    /// Cancel the request running under `id`; returns false when there is none
+    pub fn cancel(&self, id: &str) -> bool {
+        let handle = self
+            .handles
+            .lock()
+            .ok()
+            .and_then(|mut handles| handles.remove(id));
+        match handle {
+            Some((_, handle)) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_stops_the_running_request() {
+        let requests = InFlight::default();
+        let slow = requests.run("c1", async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok("finished")
+        });
+        let cancel = async {
+            tokio::task::yield_now().await;
+            assert!(requests.cancel("c1"));
+        };
+        let (result, _) = tokio::join!(slow, cancel);
+        assert!(result.unwrap_err().is::<Cancelled>());
+
+        assert!(!requests.cancel("c1"));
+        assert_eq!(requests.run("c1", async { Ok(1) }).await.unwrap(), 1);
+        assert!(!requests.cancel("c1"));
+    }
+}
@@ -0,0 +1,159 @@
+use super::client::{ApiError, Usage};
+use serde::Deserialize;
+
+/// Splits a server-sent event stream into the `data` payloads of complete events
+///
+/// Bytes are buffered until an event is complete, so multi-byte characters split
+/// across chunks decode correctly.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    /// Feed a chunk of the response body; returns the data of every event it completed
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some((end, separator)) = event_end(&self.buffer) {
+            let block: Vec<u8> = self.buffer.drain(..end + separator).collect();
+            let block = String::from_utf8_lossy(&block);
+            let data: Vec<&str> = block
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect();
+            if !data.is_empty() {
+                events.push(data.join("\n"));
+            }
+        }
+        events
+    }
+}
+
+/// Position and length of the first blank line ending an event
+fn event_end(buffer: &[u8]) -> Option<(usize, usize)> {
+    let find = |separator: &[u8]| {
+        buffer
+            .windows(separator.len())
+            .position(|window| window == separator)
+            .map(|position| (position, separator.len()))
+    };
+    match (find(b"\n\n"), find(b"\r\n\r\n")) {
+        (Some(lf), Some(crlf)) => Some(if lf.0 < crlf.0 { lf } else { crlf }),
+        (lf, crlf) => lf.or(crlf),
+    }
+}
+
+/// Messages API stream events this client acts on
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    MessageStart {
+        message: StartMessage,
+    },
+    ContentBlockStart {
+        content_block: StartBlock,
+    },
+    ContentBlockDelta {
+        delta: Delta,
+    },
+    MessageDelta {
+        delta: MessageDelta,
+        usage: Usage,
+    },
+    Error {
+        error: ApiError,
+    },
+    /// ping, content_block_stop, message_stop and future event types
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartMessage {
+    #[serde(default)]
+    pub usage: Usage,
+}
+
+/// Content block opened by `content_block_start`; its content follows as deltas
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StartBlock {
+    ToolUse {
+        id: String,
+        name: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Delta {
+    #[serde(rename = "text_delta")]
+    Text { text: String },
+    #[serde(rename = "input_json_delta")]
+    InputJson { partial_json: String },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MessageDelta {
+    pub stop_reason: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_parser_handles_split_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b"event: ping\ndata: {\"type\"").is_empty());
+        let events = parser.push(b": \"ping\"}\r\n\r\nevent: message_stop\ndata: {}\n\n");
+        assert_eq!(events, vec!["{\"type\": \"ping\"}", "{}"]);
+
+        let text = "data: \u{3042}\n\n".as_bytes();
+        assert!(parser.push(&text[..7]).is_empty());
+        assert_eq!(parser.push(&text[7..]), vec!["\u{3042}"]);
+    }
+
+    #[test]
+    fn test_stream_events() {
+        let delta: StreamEvent = serde_json::from_str(
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            delta,
+            StreamEvent::ContentBlockDelta {
+                delta: Delta::Text { ref text }
+            } if text == "Hi"
+        ));
+
+        let end: StreamEvent = serde_json::from_str(
+            r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":7}}"#,
+        )
+        .unwrap();
+        assert!(
+            matches!(end, StreamEvent::MessageDelta { ref usage, .. } if usage.output_tokens == 7)
+        );
+
+        let start: StreamEvent = serde_json::from_str(
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"t1","name":"read_file","input":{}}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            start,
+            StreamEvent::ContentBlockStart {
+                content_block: StartBlock::ToolUse { ref name, .. }
+            } if name == "read_file"
+        ));
+
+        let ping: StreamEvent = serde_json::from_str(r#"{"type":"ping"}"#).unwrap();
+        assert!(matches!(ping, StreamEvent::Other));
+    }
+}
@@ -0,0 +1,131 @@
+//! Shell commands suggested for a request in plain language
+//!
+//! Suggestions are only returned; the user picks one and insert_command types
+//! it at the prompt without pressing Enter.
+
+use super::drafts;
+use serde::{Deserialize, Serialize};
+
+/// Most suggestions kept from a reply
+pub const MAX_SUGGESTIONS: usize = 3;
+
+/// A candidate command and what it does
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandSuggestion {
+    pub command: String,
+    pub explanation: String,
+}
+
+/// Where the command would run
+#[derive(Debug, Clone, Default)]
+pub struct ShellContext {
+    pub shell: String,
+    /// Working directory last reported by the shell
+    pub cwd: Option<String>,
+    /// Recently completed commands, oldest first
+    pub recent: Vec<String>,
+}
+
+/// Prompt asking for commands that do what `request` describes
+pub fn suggestion_prompt(request: &str, context: &ShellContext) -> String {
+    let mut recent = String::new();
+    for command in &context.recent {
+        recent.push_str(command);
+        recent.push('\n');
+    }
+    format!(
+        "Suggest shell commands for the request below.\n\n\
+         - Give up to {} alternatives, best first, each a single line the shell can run as typed.\n\
+         - Prefer tools that ship with the OS; say so in the explanation when one needs installing.\n\
+         - Point out in the explanation when a command deletes, overwrites or uploads anything.\n\
+         - Reply with a JSON array only, e.g. [{{\"command\": \"ls -la\", \"explanation\": \"Lists all files\"}}].\n\n\
+         Shell: {}\nOS: {}\nWorking directory: {}\n\n\
+         <recent_commands>\n{}</recent_commands>\n\n<request>\n{}\n</request>",
+        MAX_SUGGESTIONS,
+        context.shell,
+        std::env::consts::OS,
+        context.cwd.as_deref().unwrap_or("unknown"),
+        recent,
+        request.trim()
+    )
+}
+
+/// Suggestions in a reply, skipping any that are not a single command line
+///
+/// A reply that is not the requested JSON falls back to its fenced code
+/// blocks, one suggestion per block.
+pub fn parse_suggestions(text: &str) -> Vec<CommandSuggestion> {
+    let cleaned = drafts::clean_draft(text);
+    let suggestions = serde_json::from_str::<Vec<CommandSuggestion>>(&cleaned)
+        .unwrap_or_else(|_| code_blocks(text));
+    suggestions
+        .into_iter()
+        .map(|suggestion| CommandSuggestion {
+            command: suggestion.command.trim().to_string(),
+            explanation: suggestion.explanation.trim().to_string(),
+        })
+        .filter(|suggestion| insertable(&suggestion.command))
+        .take(MAX_SUGGESTIONS)
+        .collect()
+}
+
+/// Whether `command` can be typed at a prompt without running anything
+///
+/// A newline or other control character would submit or edit the line.
+pub fn insertable(command: &str) -> bool {
+    !command.trim().is_empty() && !command.chars().any(char::is_control)
+}
+
+fn code_blocks(text: &str) -> Vec<CommandSuggestion> {
+    let mut blocks = Vec::new();
+    let mut parts = text.split("```");
+    let mut before = parts.next().unwrap_or_default();
+    while let (Some(block), after) = (parts.next(), parts.next()) {
+        let command = block.split_once('\n').map_or(block, |(_, rest)| rest);
+        blocks.push(CommandSuggestion {
+            command: command.trim().to_string(),
+            explanation: before.trim().lines().last().unwrap_or_default().to_string(),
+        });
+        before = after.unwrap_or_default();
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt() {
+        let context = ShellContext {
+            shell: "zsh".to_string(),
+            cwd: Some("/work".to_string()),
+            recent: vec!["git status".to_string()],
+        };
+        let prompt = suggestion_prompt(" find large files ", &context);
+        assert!(prompt.contains("Shell: zsh\n"));
+        assert!(prompt.contains("<recent_commands>\ngit status\n</recent_commands>"));
+        assert!(prompt.ends_with("<request>\nfind large files\n</request>"));
+    }
+
+    #[test]
+    fn test_parse_suggestions() {
+        let suggestions = parse_suggestions(
+            "```json\n[{\"command\": \"du -sh * \", \"explanation\": \"Sizes\"},\
+             {\"command\": \"rm -rf build\\nls\", \"explanation\": \"Two lines\"}]\n```",
+        );
+        assert_eq!(
+            suggestions,
+            [CommandSuggestion {
+                command: "du -sh *".to_string(),
+                explanation: "Sizes".to_string(),
+            }]
+        );
+
+        let suggestions = parse_suggestions("List files by size:\n```sh\nls -S\n```\nDone.");
+        assert_eq!(suggestions[0].command, "ls -S");
+        assert_eq!(suggestions[0].explanation, "List files by size:");
+        assert!(parse_suggestions("I can't help with that.").is_empty());
+        assert!(!insertable("ls\r"));
+    }
+}
@@ -0,0 +1,311 @@
+//! Tool-use bridge: lets the model call a fixed whitelist of Zeami backend tools
+//!
+//! Only the tools in [`Tool`] are offered, and a call to any other name is
+//! answered with an error instead of being executed. Unless a tool is listed
+//! in ClaudeSettings::auto_approved_tools, every call waits for the user to
+//! approve it; calls that are declined or left unanswered are reported back
+//! to the model as refused.
+
+use super::client::{MessagesRequest, Reply, ToolCall, ToolDefinition, ToolResult, Usage};
+use super::ClaudeState;
+use crate::config::ClaudeSettings;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Model turns in one agent run before it is stopped
+const MAX_TOOL_ROUNDS: usize = 10;
+
+/// Largest file read_file returns
+const READ_FILE_MAX_BYTES: u64 = 100 * 1024;
+
+/// The backend tools the model may call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tool {
+    ReadFile,
+    GetGitStatus,
+    ListIssues,
+    RunTests,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 4] = [
+        Tool::ReadFile,
+        Tool::GetGitStatus,
+        Tool::ListIssues,
+        Tool::RunTests,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::ReadFile => "read_file",
+            Tool::GetGitStatus => "get_git_status",
+            Tool::ListIssues => "list_issues",
+            Tool::RunTests => "run_tests",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Tool> {
+        Tool::ALL.into_iter().find(|tool| tool.name() == name)
+    }
+
+    fn definition(self) -> ToolDefinition {
+        let (description, input_schema) = match self {
+            Tool::ReadFile => (
+                "Read a UTF-8 text file of the current project. Paths are relative to the project root.",
+                serde_json::json!({
+                    "type": "object",
+                    "properties": { "path": { "type": "string" } },
+                    "required": ["path"]
+                }),
+            ),
+            Tool::GetGitStatus => (
+                "Get the current branch, ahead/behind counts, changed files and any merge or rebase in progress.",
+                serde_json::json!({ "type": "object", "properties": {} }),
+            ),
+            Tool::ListIssues => (
+                "List GitHub issues of the project's repository.",
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "state": { "type": "string", "enum": ["open", "closed", "all"] },
+                        "labels": { "type": "array", "items": { "type": "string" } },
+                        "assignee": { "type": "string" },
+                        "per_page": { "type": "integer", "minimum": 1, "maximum": 100 }
+                    }
+                }),
+            ),
+            Tool::RunTests => (
+                "Run the project's configured test command and return its exit code and output.",
+                serde_json::json!({ "type": "object", "properties": {} }),
+            ),
+        };
+        ToolDefinition {
+            name: self.name(),
+            description,
+            input_schema,
+        }
+    }
+}
+
+/// Definitions of every whitelisted tool, for MessagesRequest::with_tools
+pub fn definitions() -> Vec<ToolDefinition> {
+    Tool::ALL.into_iter().map(Tool::definition).collect()
+}
+
+/// Approves and executes tool calls for an agent run
+pub trait ToolHost {
+    /// Ask the user whether `call` may run
+    fn approve(&self, tool: Tool, call: &ToolCall) -> impl Future<Output = bool> + Send;
+
+    /// Run `tool` and return its result as text for the model
+    fn execute(
+        &self,
+        tool: Tool,
+        input: &serde_json::Value,
+    ) -> impl Future<Output = Result<String>> + Send;
+}
+
+/// Tool calls waiting for the user's decision
+#[derive(Default)]
+pub struct ToolApprovals {
+    pending: Mutex<HashMap<String, oneshot::Sender<bool>>>,
+}
+
+impl ToolApprovals {
+    /// Register an approval request, announce it with `ask` and wait for the answer
+    ///
+    /// Unanswered requests are declined after `timeout`.
+    pub async fn request(&self, timeout: Duration, ask: impl FnOnce(&str)) -> bool {
+        let id = uuid::Uuid::new_v4().to_string();
+        let (sender, receiver) = oneshot::channel();
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(id.clone(), sender);
+        } else {
+            return false;
+        }
+
+        ask(&id);
+        let approved = matches!(tokio::time::timeout(timeout, receiver).await, Ok(Ok(true)));
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(&id);
+        }
+        approved
+    }
+
+    /// Answer a request; returns false when it is unknown or already timed out
+    pub fn respond(&self, id: &str, approved: bool) -> bool {
+        let sender = self
+            .pending
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(id));
+        match sender {
+            Some(sender) => sender.send(approved).is_ok(),
+            None => false,
+        }
+    }
+}
+
+/// Run `request` with the whitelisted tools until the model stops calling them
+///
+/// Text from every turn is passed to `on_text` and joined in the returned
+/// reply, whose usage covers all turns.
+pub async fn run_agent(
+    claude: &ClaudeState,
+    settings: &ClaudeSettings,
+    request: MessagesRequest,
+    host: &impl ToolHost,
+    mut on_text: impl FnMut(&str) + Send,
+) -> Result<Reply> {
+    let mut request = request.with_tools(definitions());
+    let mut content = Vec::new();
+    let mut usage = Usage::default();
+
+    for _ in 0..MAX_TOOL_ROUNDS {
+        let reply = claude.complete(settings, &request, &mut on_text).await?;
+        add_usage(&mut usage, &reply.usage);
+        if !reply.content.trim().is_empty() {
+            content.push(reply.content.trim().to_string());
+        }
+        if reply.tool_calls.is_empty() {
+            return Ok(Reply {
+                content: content.join("\n\n"),
+                usage,
+                ..reply
+            });
+        }
+
+        let mut results = Vec::with_capacity(reply.tool_calls.len());
+        for call in &reply.tool_calls {
+            results.push(run_call(settings, host, call).await);
+        }
+        request.push_tool_results(&reply, results);
+    }
+    bail!("Stopped after {} rounds of tool calls", MAX_TOOL_ROUNDS)
+}
+
+async fn run_call(settings: &ClaudeSettings, host: &impl ToolHost, call: &ToolCall) -> ToolResult {
+    let result = |content: String, is_error: bool| ToolResult {
+        tool_use_id: call.id.clone(),
+        content,
+        is_error,
+    };
+    let Some(tool) = Tool::from_name(&call.name) else {
+        let available: Vec<&str> = Tool::ALL.into_iter().map(Tool::name).collect();
+        return result(
+            format!(
+                "Unknown tool {}; only {} are available",
+                call.name,
+                available.join(", ")
+            ),
+            true,
+        );
+    };
+
+    let auto_approved = settings
+        .auto_approved_tools
+        .iter()
+        .any(|name| name == tool.name());
+    if !auto_approved && !host.approve(tool, call).await {
+        return result("The user declined this tool call".to_string(), true);
+    }
+    match host.execute(tool, &call.input).await {
+        Ok(content) => result(content, false),
+        Err(e) => result(format!("{:#}", e), true),
+    }
+}
+
+fn add_usage(total: &mut Usage, usage: &Usage) {
+    total.input_tokens += usage.input_tokens;
+    total.output_tokens += usage.output_tokens;
+    total.cache_creation_input_tokens += usage.cache_creation_input_tokens;
+    total.cache_read_input_tokens += usage.cache_read_input_tokens;
+}
+
+/// Contents of `path`, which must resolve to a file inside `project_root`
+pub fn read_file(project_root: &Path, path: &str) -> Result<String> {
+    let root = project_root
+        .canonicalize()
+        .with_context(|| format!("Project root {:?} does not exist", project_root))?;
+    let full_path = root
+        .join(path)
+        .canonicalize()
+        .with_context(|| format!("{} does not exist", path))?;
+    if !full_path.starts_with(&root) {
+        bail!("{} is outside the project", path);
+    }
+
+    let metadata = std::fs::metadata(&full_path)?;
+    if !metadata.is_file() {
+        bail!("{} is not a file", path);
+    }
+    if metadata.len() > READ_FILE_MAX_BYTES {
+        bail!(
+            "{} is {} bytes; only files up to {} bytes can be read",
+            path,
+            metadata.len(),
+            READ_FILE_MAX_BYTES
+        );
+    }
+    let bytes = std::fs::read(&full_path)?;
+    String::from_utf8(bytes).with_context(|| format!("{} is not a text file", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_whitelist() {
+        assert_eq!(Tool::from_name("run_tests"), Some(Tool::RunTests));
+        assert_eq!(Tool::from_name("delete_branch"), None);
+        let names: Vec<&str> = definitions().iter().map(|tool| tool.name).collect();
+        assert_eq!(
+            names,
+            vec!["read_file", "get_git_status", "list_issues", "run_tests"]
+        );
+    }
+
+    #[test]
+    fn test_read_file_stays_in_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.path().join("secret.txt"), "token").unwrap();
+        fs::write(root.join("blob.bin"), [0xff, 0xfe]).unwrap();
+
+        assert_eq!(read_file(&root, "src/main.rs").unwrap(), "fn main() {}");
+        assert!(read_file(&root, "../secret.txt").is_err());
+        assert!(read_file(&root, "/etc/passwd").is_err());
+        assert!(read_file(&root, "src").is_err());
+        assert!(read_file(&root, "blob.bin").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_approvals() {
+        let approvals = std::sync::Arc::new(ToolApprovals::default());
+        let responder = approvals.clone();
+        let approved = approvals
+            .request(Duration::from_secs(5), move |id| {
+                assert!(responder.respond(id, true));
+            })
+            .await;
+        assert!(approved);
+
+        let mut asked = String::new();
+        let approved = approvals
+            .request(Duration::from_millis(10), |id| asked = id.to_string())
+            .await;
+        assert!(!approved);
+        assert!(!approvals.respond(&asked, true));
+    }
+}
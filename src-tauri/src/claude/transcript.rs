@@ -0,0 +1,240 @@
+//! Conversations exported as documents, e.g. to attach to the issue they helped with
+
+use super::client::Role;
+use super::history::Conversation;
+use serde::{Deserialize, Serialize};
+
+/// Characters of a GitHub comment, below its 65536 limit to leave room for the wrapper
+const COMMENT_MAX_CHARS: usize = 60_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    Markdown,
+    Html,
+}
+
+/// The conversation in `format`
+pub fn render(conversation: &Conversation, format: TranscriptFormat) -> String {
+    match format {
+        TranscriptFormat::Markdown => to_markdown(conversation),
+        TranscriptFormat::Html => to_html(conversation),
+    }
+}
+
+/// The conversation as Markdown, with each message under a heading
+///
+/// Message text is kept as written, so its code blocks render as they did in the app.
+pub fn to_markdown(conversation: &Conversation) -> String {
+    let mut markdown = format!("# {}\n", conversation.summary.title);
+    for message in &conversation.messages {
+        markdown.push_str(&message_markdown(
+            message.role,
+            &message.created_at,
+            &message.content,
+        ));
+    }
+    markdown
+}
+
+/// The conversation as a standalone HTML page
+pub fn to_html(conversation: &Conversation) -> String {
+    let title = escape_html(&conversation.summary.title);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>body{{font-family:sans-serif;max-width:50em;margin:auto}}\
+         pre{{background:#f4f4f4;padding:.5em;overflow-x:auto}}</style>\n\
+         </head>\n<body>\n<h1>{}</h1>\n",
+        title, title
+    );
+    for message in &conversation.messages {
+        html.push_str(&format!(
+            "<section class=\"{}\">\n<h2>{} <small>{}</small></h2>\n{}</section>\n",
+            role_class(message.role),
+            role_label(message.role),
+            escape_html(&message.created_at),
+            markdown_to_html(&message.content)
+        ));
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Issue comment with the conversation collapsed under a summary line
+///
+/// Messages that would take the comment past GitHub's size limit are left
+/// out from the end, with a note saying how many.
+pub fn issue_comment(conversation: &Conversation) -> String {
+    let mut body = String::new();
+    let mut omitted = 0;
+    for message in &conversation.messages {
+        let text = message_markdown(message.role, &message.created_at, &message.content);
+        if omitted > 0 || body.len() + text.len() > COMMENT_MAX_CHARS {
+            omitted += 1;
+        } else {
+            body.push_str(&text);
+        }
+    }
+    if omitted > 0 {
+        body.push_str(&format!(
+            "\n_Omitted {} later message(s) to fit in a comment._\n",
+            omitted
+        ));
+    }
+    format!(
+        "<details>\n<summary>Claude conversation: {} ({} messages)</summary>\n{}\n</details>\n",
+        escape_html(&conversation.summary.title),
+        conversation.messages.len(),
+        body
+    )
+}
+
+fn message_markdown(role: Role, created_at: &str, content: &str) -> String {
+    format!(
+        "\n## {} ({})\n\n{}\n",
+        role_label(role),
+        created_at,
+        content.trim_end()
+    )
+}
+
+fn role_label(role: Role) -> &'static str {
+    match role {
+        Role::User => "You",
+        Role::Assistant => "Claude",
+    }
+}
+
+fn role_class(role: Role) -> &'static str {
+    match role {
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+/// Paragraphs and fenced code blocks of message text as HTML
+///
+/// Only code blocks get markup; other Markdown is shown as written.
+fn markdown_to_html(text: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<(String, Vec<&str>)> = None;
+    let flush = |html: &mut String, paragraph: &mut Vec<&str>| {
+        if !paragraph.is_empty() {
+            let lines: Vec<String> = paragraph.iter().map(|line| escape_html(line)).collect();
+            html.push_str(&format!("<p>{}</p>\n", lines.join("<br>\n")));
+            paragraph.clear();
+        }
+    };
+
+    for line in text.lines() {
+        let fence = line.trim_start().strip_prefix("```");
+        match (&mut code, fence) {
+            (Some((language, lines)), Some(_)) => {
+                let class = if language.is_empty() {
+                    String::new()
+                } else {
+                    format!(" class=\"language-{}\"", escape_html(language))
+                };
+                html.push_str(&format!(
+                    "<pre><code{}>{}</code></pre>\n",
+                    class,
+                    escape_html(&lines.join("\n"))
+                ));
+                code = None;
+            }
+            (Some((_, lines)), None) => lines.push(line),
+            (None, Some(language)) => {
+                flush(&mut html, &mut paragraph);
+                code = Some((language.trim().to_string(), Vec::new()));
+            }
+            (None, None) if line.trim().is_empty() => flush(&mut html, &mut paragraph),
+            (None, None) => paragraph.push(line),
+        }
+    }
+    // An unclosed fence runs to the end of the message
+    if let Some((_, lines)) = code {
+        html.push_str(&format!(
+            "<pre><code>{}</code></pre>\n",
+            escape_html(&lines.join("\n"))
+        ));
+    }
+    flush(&mut html, &mut paragraph);
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::history::{ConversationSummary, StoredMessage};
+
+    fn conversation(answer: &str) -> Conversation {
+        let message = |role, content: &str| StoredMessage {
+            role,
+            content: content.to_string(),
+            created_at: "2024-05-01 10:00:00".to_string(),
+        };
+        Conversation {
+            summary: ConversationSummary {
+                id: "c1".to_string(),
+                title: "Why does <init> fail?".to_string(),
+                created_at: "2024-05-01 10:00:00".to_string(),
+                updated_at: "2024-05-01 10:00:00".to_string(),
+                message_count: 2,
+            },
+            messages: vec![
+                message(Role::User, "Why does init fail?"),
+                message(Role::Assistant, answer),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_markdown_keeps_code_blocks() {
+        let markdown = to_markdown(&conversation("Run:\n```sh\ncargo clean\n```\n"));
+        assert!(markdown.starts_with("# Why does <init> fail?\n"));
+        assert!(markdown.contains("## You (2024-05-01 10:00:00)\n\nWhy does init fail?\n"));
+        assert!(markdown
+            .ends_with("## Claude (2024-05-01 10:00:00)\n\nRun:\n```sh\ncargo clean\n```\n"));
+    }
+
+    #[test]
+    fn test_html() {
+        let html = to_html(&conversation("Run:\n```sh\nx < y && z\n```\n\nThen retry."));
+        assert!(html.contains("<h1>Why does &lt;init&gt; fail?</h1>"));
+        assert!(html.contains(
+            "<p>Run:</p>\n<pre><code class=\"language-sh\">x &lt; y &amp;&amp; z</code></pre>\n<p>Then retry.</p>\n"
+        ));
+        assert_eq!(
+            markdown_to_html("```\nopen"),
+            "<pre><code>open</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_issue_comment_is_collapsed_and_bounded() {
+        let comment = issue_comment(&conversation("Use `--locked`."));
+        assert!(comment.starts_with(
+            "<details>\n<summary>Claude conversation: Why does &lt;init&gt; fail? (2 messages)</summary>\n"
+        ));
+        assert!(comment.ends_with("Use `--locked`.\n\n</details>\n"));
+
+        let long = issue_comment(&conversation(&"x".repeat(COMMENT_MAX_CHARS)));
+        assert!(long.contains("_Omitted 1 later message(s)"));
+        assert!(long.len() < COMMENT_MAX_CHARS);
+    }
+}
@@ -0,0 +1,339 @@
+//! Token usage and cost accounting for Claude requests
+//!
+//! Every request sent to the API is added to a per-day, per-model tally in
+//! ~/.zeami/usage.json. Costs are estimated from list prices when the request
+//! is recorded, so later price changes do not rewrite history.
+
+use super::client::Usage;
+use crate::config::atomic::write_atomic;
+use anyhow::{Context, Result};
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Prompt cache reads are billed at this share of the input price
+pub const CACHE_READ_PRICE: f64 = 0.1;
+
+/// Prompt cache writes are billed at this multiple of the input price
+pub const CACHE_WRITE_PRICE: f64 = 1.25;
+
+/// Days of history kept in usage.json
+const RETENTION_DAYS: i64 = 400;
+
+/// Period covered by a usage report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageRange {
+    Today,
+    /// The last seven days, including today
+    Week,
+    /// The calendar month so far
+    Month,
+}
+
+/// Token counts and cost of a set of requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, other: &UsageTotals) {
+        self.requests += other.requests;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    pub model: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayUsage {
+    pub date: NaiveDate,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+/// Result of get_claude_usage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub range: UsageRange,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub total: UsageTotals,
+    /// Most expensive model first
+    pub models: Vec<ModelUsage>,
+    /// One entry per day of the range, oldest first, including days without requests
+    pub days: Vec<DayUsage>,
+}
+
+/// Payload of the budget warning, sent once per month when spending passes the budget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetAlert {
+    /// `YYYY-MM`
+    pub month: String,
+    pub budget_usd: f64,
+    pub spent_usd: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct UsageLog {
+    days: BTreeMap<NaiveDate, BTreeMap<String, UsageTotals>>,
+    /// Month a budget alert was last sent for
+    budget_alerted: Option<String>,
+}
+
+/// Claude usage kept in ~/.zeami/usage.json
+pub struct UsageLedger {
+    /// None keeps usage in memory only
+    path: Option<PathBuf>,
+    log: Mutex<UsageLog>,
+}
+
+impl UsageLedger {
+    /// Load the ledger at `path`; a missing or unreadable file starts an empty one
+    pub fn open(path: &Path) -> Self {
+        let log = match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable usage ledger {:?}: {}", path, e);
+                UsageLog::default()
+            }),
+            Err(_) => UsageLog::default(),
+        };
+
+        Self {
+            path: Some(path.to_path_buf()),
+            log: Mutex::new(log),
+        }
+    }
+
+    /// A ledger that lives only as long as the process
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            log: Mutex::new(UsageLog::default()),
+        }
+    }
+
+    /// Add a request made on `date` to the tally of `model`
+    pub fn record(&self, model: &str, usage: &Usage, date: NaiveDate) -> Result<()> {
+        let request = UsageTotals {
+            requests: 1,
+            input_tokens: usage.input_tokens.into(),
+            output_tokens: usage.output_tokens.into(),
+            cache_creation_input_tokens: usage.cache_creation_input_tokens.into(),
+            cache_read_input_tokens: usage.cache_read_input_tokens.into(),
+            cost_usd: cost_usd(model, usage),
+        };
+        self.update(|log| {
+            log.days
+                .entry(date)
+                .or_default()
+                .entry(model.to_string())
+                .or_default()
+                .add(&request);
+            let cutoff = date - Duration::days(RETENTION_DAYS);
+            log.days.retain(|day, _| *day > cutoff);
+        })
+    }
+
+    pub fn report(&self, range: UsageRange, today: NaiveDate) -> Result<UsageReport> {
+        let from = match range {
+            UsageRange::Today => today,
+            UsageRange::Week => today - Duration::days(6),
+            UsageRange::Month => today.with_day(1).unwrap_or(today),
+        };
+        let log = self.lock()?;
+
+        let mut total = UsageTotals::default();
+        let mut models: BTreeMap<&str, UsageTotals> = BTreeMap::new();
+        let mut days = Vec::new();
+        for date in from.iter_days().take_while(|date| *date <= today) {
+            let mut day = UsageTotals::default();
+            for (model, totals) in log.days.get(&date).into_iter().flatten() {
+                day.add(totals);
+                models.entry(model).or_default().add(totals);
+            }
+            total.add(&day);
+            days.push(DayUsage { date, totals: day });
+        }
+
+        let mut models: Vec<ModelUsage> = models
+            .into_iter()
+            .map(|(model, totals)| ModelUsage {
+                model: model.to_string(),
+                totals,
+            })
+            .collect();
+        models.sort_by(|a, b| b.totals.cost_usd.total_cmp(&a.totals.cost_usd));
+
+        Ok(UsageReport {
+            range,
+            from,
+            to: today,
+            total,
+            models,
+            days,
+        })
+    }
+
+    /// An alert when this month's spending has passed `budget_usd` and none
+    /// was sent for the month yet; a budget of 0 disables alerts
+    pub fn budget_alert(&self, budget_usd: f64, today: NaiveDate) -> Result<Option<BudgetAlert>> {
+        if budget_usd <= 0.0 {
+            return Ok(None);
+        }
+        let spent_usd = self.report(UsageRange::Month, today)?.total.cost_usd;
+        let month = today.format("%Y-%m").to_string();
+        if spent_usd < budget_usd {
+            return Ok(None);
+        }
+
+        self.update(|log| {
+            if log.budget_alerted.as_deref() == Some(month.as_str()) {
+                return None;
+            }
+            log.budget_alerted = Some(month.clone());
+            Some(BudgetAlert {
+                month: month.clone(),
+                budget_usd,
+                spent_usd,
+            })
+        })
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, UsageLog>> {
+        self.log
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock usage ledger: {}", e))
+    }
+
+    fn update<T>(&self, change: impl FnOnce(&mut UsageLog) -> T) -> Result<T> {
+        let mut log = self.lock()?;
+        let result = change(&mut log);
+
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)
+                    .with_context(|| format!("Failed to create {:?}", dir))?;
+            }
+            write_atomic(path, serde_json::to_string_pretty(&*log)?.as_bytes())
+                .with_context(|| format!("Failed to write {:?}", path))?;
+        }
+        Ok(result)
+    }
+}
+
+/// List price in USD per input and output token
+pub fn price_per_token(model: &str) -> (f64, f64) {
+    let (input, output) = if model.contains("opus") {
+        (15.0, 75.0)
+    } else if model.contains("haiku") {
+        (0.8, 4.0)
+    } else {
+        (3.0, 15.0)
+    };
+    (input / 1_000_000.0, output / 1_000_000.0)
+}
+
+/// Estimated cost of one request
+pub fn cost_usd(model: &str, usage: &Usage) -> f64 {
+    let (input_price, output_price) = price_per_token(model);
+    usage.input_tokens as f64 * input_price
+        + usage.cache_creation_input_tokens as f64 * input_price * CACHE_WRITE_PRICE
+        + usage.cache_read_input_tokens as f64 * input_price * CACHE_READ_PRICE
+        + usage.output_tokens as f64 * output_price
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input_tokens: u32, output_tokens: u32) -> Usage {
+        Usage {
+            input_tokens,
+            output_tokens,
+            ..Usage::default()
+        }
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    #[test]
+    fn test_cost() {
+        assert_eq!(cost_usd("claude-sonnet-4", &usage(1_000_000, 0)), 3.0);
+        assert_eq!(cost_usd("claude-opus-4", &usage(0, 1_000_000)), 75.0);
+        let cached = Usage {
+            cache_read_input_tokens: 1_000_000,
+            cache_creation_input_tokens: 1_000_000,
+            ..Usage::default()
+        };
+        assert!((cost_usd("claude-3-5-haiku", &cached) - 1.08).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_report_and_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usage.json");
+        let ledger = UsageLedger::open(&path);
+        ledger
+            .record("claude-sonnet-4", &usage(1_000, 100), date(1))
+            .unwrap();
+        ledger
+            .record("claude-opus-4", &usage(1_000, 100), date(9))
+            .unwrap();
+        ledger
+            .record("claude-sonnet-4", &usage(2_000, 200), date(10))
+            .unwrap();
+
+        let ledger = UsageLedger::open(&path);
+        let today = ledger.report(UsageRange::Today, date(10)).unwrap();
+        assert_eq!(today.total.requests, 1);
+        assert_eq!(today.days.len(), 1);
+
+        let week = ledger.report(UsageRange::Week, date(10)).unwrap();
+        assert_eq!(week.from, date(4));
+        assert_eq!(week.days.len(), 7);
+        assert_eq!(week.total.requests, 2);
+        assert_eq!(week.models[0].model, "claude-opus-4");
+        assert_eq!(week.models[1].totals.input_tokens, 2_000);
+
+        let month = ledger.report(UsageRange::Month, date(10)).unwrap();
+        assert_eq!(month.total.requests, 3);
+        assert_eq!(month.total.output_tokens, 400);
+    }
+
+    #[test]
+    fn test_budget_alert_once_per_month() {
+        let ledger = UsageLedger::in_memory();
+        ledger
+            .record("claude-sonnet-4", &usage(1_000_000, 0), date(1))
+            .unwrap();
+
+        assert!(ledger.budget_alert(0.0, date(2)).unwrap().is_none());
+        assert!(ledger.budget_alert(5.0, date(2)).unwrap().is_none());
+        let alert = ledger.budget_alert(2.0, date(2)).unwrap().unwrap();
+        assert_eq!(alert.month, "2026-03");
+        assert_eq!(alert.spent_usd, 3.0);
+        assert!(ledger.budget_alert(2.0, date(3)).unwrap().is_none());
+    }
+}
@@ -0,0 +1,43 @@
+use super::middleware::instrumented;
+use crate::capabilities::{Capability, CapabilityInfo, CapabilityRegistry};
+use crate::error::ZeamiError;
+use std::path::PathBuf;
+use tauri::State;
+
+/// Capabilities a project can grant, with the commands each one unlocks and
+/// when it was granted
+#[tauri::command]
+#[instrumented]
+pub fn list_capabilities(
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+) -> Vec<CapabilityInfo> {
+    capabilities.list(&project_root)
+}
+
+/// Allow the commands needing `capability` to run in `project_root`, typically
+/// after a `capability-requested` prompt
+#[tauri::command]
+#[instrumented]
+pub fn grant_capability(
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+    capability: Capability,
+) -> Result<(), ZeamiError> {
+    capabilities
+        .grant(&project_root, capability)
+        .map_err(|e| ZeamiError::config(e).context("Failed to grant capability"))
+}
+
+/// Withdraw a grant; returns false when it was not granted
+#[tauri::command]
+#[instrumented]
+pub fn revoke_capability(
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+    capability: Capability,
+) -> Result<bool, ZeamiError> {
+    capabilities
+        .revoke(&project_root, capability)
+        .map_err(|e| ZeamiError::config(e).context("Failed to revoke capability"))
+}
@@ -0,0 +1,709 @@
+use super::middleware::instrumented;
+use crate::capabilities::{Capability, CapabilityRegistry};
+use crate::claude::cache::CacheStats;
+use crate::claude::client::ToolCall;
+use crate::claude::drafts::{self, IssueProposal, PullRequestDraft};
+use crate::claude::explain;
+use crate::claude::history::{Conversation, ConversationSummary};
+use crate::claude::prompt::{self, PromptContext, SystemPromptPreview};
+use crate::claude::redact::{RedactedText, Redactor};
+use crate::claude::requests::Cancelled;
+use crate::claude::suggest::{self, CommandSuggestion, ShellContext};
+use crate::claude::tools::{self, Tool, ToolHost};
+use crate::claude::transcript::{self, TranscriptFormat};
+use crate::claude::usage::{UsageRange, UsageReport};
+use crate::claude::{ClaudeState, Message, MessagesRequest, Reply};
+use crate::commands::pty_commands::PtyState;
+use crate::config::{storage, ClaudeSettings, Settings, SettingsState};
+use crate::error::{ErrorKind, ZeamiError};
+use crate::events::schema::v1;
+use crate::events::{
+    CLAUDE_BUDGET_EXCEEDED_EVENT_NAME, CLAUDE_STREAM_EVENT_NAME, CLAUDE_TOOL_APPROVAL_EVENT_NAME,
+};
+use crate::git::{self, diff::DiffTarget};
+use crate::github::issues::IssueFilters;
+use crate::github::{self, GitHubState};
+use crate::i18n;
+use crate::journal::{self, JournalEntry, JournalKind};
+use crate::notifications::{self, NotificationCategory};
+use crate::pty::shell_integration::CommandRecord;
+use crate::workflow::test_runs;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+/// How long a tool call waits for the user's approval before it is declined
+const TOOL_APPROVAL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Characters of a reply shown in the `ClaudeDone` notification
+const SUMMARY_MAX_CHARS: usize = 120;
+
+/// Recent commands of the session sent with suggest_command
+const SUGGEST_RECENT_COMMANDS: usize = 5;
+
+/// Send a message in a conversation and return Claude's reply
+/// The reply text also arrives as `claude-stream` events, token by token with
+/// ClaudeSettings::enable_streaming; a failed request leaves the conversation unchanged.
+/// Repeating a request within ClaudeSettings::response_cache_ttl returns the cached reply.
+/// `project_root` fills the branch and issue variables of the system prompt
+#[tauri::command]
+#[instrumented]
+pub async fn send_claude_message(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    claude: State<'_, ClaudeState>,
+    conversation_id: String,
+    content: String,
+    project_root: Option<PathBuf>,
+) -> Result<Reply, String> {
+    let user = Message::user(content);
+    let mut messages = claude
+        .conversations
+        .history(&conversation_id)
+        .map_err(|e| format!("Failed to load conversation: {:#}", e))?;
+    messages.push(user.clone());
+    let current = project_settings(&settings, project_root.as_deref())?;
+    let settings = PromptContext::gather(&current, project_root.as_deref()).apply(&current.claude);
+    let request = MessagesRequest::new(&settings, messages);
+
+    let reply = stream_completion(
+        &app,
+        &claude,
+        &settings,
+        &conversation_id,
+        &request,
+        "send Claude message",
+    )
+    .await?;
+    let assistant = Message::assistant(reply.content.clone());
+    if let Err(e) = claude
+        .conversations
+        .append(&conversation_id, &user, &assistant)
+    {
+        tracing::error!("Failed to save conversation {}: {:#}", conversation_id, e);
+    }
+    Ok(reply)
+}
+
+/// Draft a commit message for the changes in `diff_scope`
+/// The draft streams as `claude-stream` events under `stream_id` while it is written
+#[tauri::command]
+#[instrumented]
+pub async fn generate_commit_message(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    claude: State<'_, ClaudeState>,
+    project_root: PathBuf,
+    diff_scope: DiffTarget,
+    stream_id: String,
+) -> Result<String, String> {
+    let prompt = git::diff::get_patch(&project_root, &diff_scope)
+        .and_then(|patch| drafts::commit_message_prompt(&patch))
+        .map_err(|e| format!("Failed to generate commit message: {:#}", e))?;
+    let current = project_settings(&settings, Some(&project_root))?;
+    let settings = PromptContext::gather(&current, Some(&project_root)).apply(&current.claude);
+    let request = MessagesRequest::new(&settings, vec![Message::user(prompt)]);
+    let reply = stream_completion(
+        &app,
+        &claude,
+        &settings,
+        &stream_id,
+        &request,
+        "generate commit message",
+    )
+    .await?;
+    Ok(drafts::clean_draft(&reply.content))
+}
+
+/// Draft a pull request title and description for merging `head` into `base`
+/// The draft streams as `claude-stream` events under `stream_id` while it is written
+#[tauri::command]
+#[instrumented]
+pub async fn generate_pr_description(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    claude: State<'_, ClaudeState>,
+    project_root: PathBuf,
+    base: String,
+    head: String,
+    stream_id: String,
+) -> Result<PullRequestDraft, String> {
+    let prompt = git::diff::get_range_diff(&project_root, &base, &head)
+        .and_then(|range| drafts::pr_description_prompt(&base, &head, &range))
+        .map_err(|e| format!("Failed to generate PR description: {:#}", e))?;
+    let current = project_settings(&settings, Some(&project_root))?;
+    let settings = PromptContext::gather(&current, Some(&project_root)).apply(&current.claude);
+    let request = MessagesRequest::new(&settings, vec![Message::user(prompt)]);
+    let reply = stream_completion(
+        &app,
+        &claude,
+        &settings,
+        &stream_id,
+        &request,
+        "generate PR description",
+    )
+    .await?;
+    Ok(drafts::parse_pull_request(&reply.content))
+}
+
+/// Explain why the last command in a terminal session failed and suggest a fix
+/// The explanation streams as `claude-stream` events under `session_id`. Commands
+/// are tracked through shell integration marks, so the shell must emit them
+#[tauri::command]
+#[instrumented]
+pub async fn explain_last_error(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    claude: State<'_, ClaudeState>,
+    pty: State<'_, PtyState>,
+    session_id: String,
+) -> Result<String, String> {
+    let record = last_command(&pty, &session_id)?;
+    if record.exit_code == Some(0) {
+        return Err(format!(
+            "`{}` succeeded; there is no error to explain",
+            record.command
+        ));
+    }
+
+    let cwd = record.cwd.as_deref().map(Path::new);
+    let mut settings = project_settings(&settings, cwd)?;
+    settings.claude = PromptContext::gather(&settings, cwd).apply(&settings.claude);
+    let prompt = explain::error_explanation_prompt(&record, &shell_name(&settings));
+    let request = MessagesRequest::new(&settings.claude, vec![Message::user(prompt)]);
+    let reply = stream_completion(
+        &app,
+        &claude,
+        &settings.claude,
+        &session_id,
+        &request,
+        "explain the error",
+    )
+    .await?;
+    Ok(reply.content)
+}
+
+/// Draft an issue from the last command in a terminal session, the current
+/// branch and the failing tests of the project's last test run
+/// The draft streams as `claude-stream` events under `session_id` and is
+/// returned for review; nothing is filed until create_issue is called
+#[tauri::command]
+#[instrumented]
+pub async fn draft_issue_from_context(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    claude: State<'_, ClaudeState>,
+    pty: State<'_, PtyState>,
+    session_id: String,
+) -> Result<IssueProposal, ZeamiError> {
+    let record = last_command(&pty, &session_id)?;
+    let cwd = record.cwd.as_deref().map(Path::new);
+    let mut settings = project_settings(&settings, cwd)?;
+    let context = PromptContext::gather(&settings, cwd);
+    settings.claude = context.apply(&settings.claude);
+
+    let project_root = cwd
+        .and_then(|cwd| git::open(cwd).ok())
+        .and_then(|repo| git::workdir(&repo).ok());
+    let failing_tests: Vec<String> = project_root
+        .and_then(|root| test_runs::last_run(&root).ok().flatten())
+        .and_then(|run| run.report)
+        .map(|report| report.failures().map(|test| test.name.clone()).collect())
+        .unwrap_or_default();
+    let branch = context.variables.get("branch").map(String::as_str);
+
+    let prompt = drafts::issue_draft_prompt(&record, branch, &failing_tests);
+    let request = MessagesRequest::new(&settings.claude, vec![Message::user(prompt)]);
+    let reply = stream_completion(
+        &app,
+        &claude,
+        &settings.claude,
+        &session_id,
+        &request,
+        "draft the issue",
+    )
+    .await?;
+    Ok(drafts::parse_issue_proposal(&reply.content))
+}
+
+/// Suggest shell commands for `prompt`, a request in plain language, in the
+/// context of a terminal session
+/// Nothing is run; the chosen command is typed with insert_command
+#[tauri::command]
+#[instrumented]
+pub async fn suggest_command(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    claude: State<'_, ClaudeState>,
+    pty: State<'_, PtyState>,
+    prompt: String,
+    session_id: String,
+) -> Result<Vec<CommandSuggestion>, ZeamiError> {
+    if prompt.trim().is_empty() {
+        return Err(ZeamiError::new(
+            ErrorKind::InvalidInput,
+            "Describe what the command should do",
+        ));
+    }
+    let (cwd, recent) = {
+        let sessions = pty.sessions.lock().map_err(|e| {
+            ZeamiError::new(
+                ErrorKind::Internal,
+                format!("Failed to lock sessions: {}", e),
+            )
+        })?;
+        let session = sessions.get(&session_id).ok_or_else(|| {
+            ZeamiError::new(
+                ErrorKind::NotFound,
+                format!("Session not found: {}", session_id),
+            )
+        })?;
+        (
+            session.cwd(),
+            session.recent_commands(SUGGEST_RECENT_COMMANDS),
+        )
+    };
+
+    let mut settings = project_settings(&settings, cwd.as_deref().map(Path::new))?;
+    settings.claude =
+        PromptContext::gather(&settings, cwd.as_deref().map(Path::new)).apply(&settings.claude);
+    let context = ShellContext {
+        shell: shell_name(&settings),
+        cwd,
+        recent,
+    };
+    let request = MessagesRequest::new(
+        &settings.claude,
+        vec![Message::user(suggest::suggestion_prompt(&prompt, &context))],
+    );
+    let reply = stream_completion(
+        &app,
+        &claude,
+        &settings.claude,
+        &session_id,
+        &request,
+        "suggest a command",
+    )
+    .await?;
+    let suggestions = suggest::parse_suggestions(&reply.content);
+    if suggestions.is_empty() {
+        return Err(ZeamiError::new(
+            ErrorKind::Internal,
+            format!("Claude did not suggest a command: {}", reply.content),
+        ));
+    }
+    Ok(suggestions)
+}
+
+/// Send a message in a conversation and let Claude call the whitelisted tools
+/// (read_file, get_git_status, list_issues, run_tests) on `project_root` until it answers.
+/// Text streams as `claude-stream` events; each tool call not auto-approved emits
+/// `claude-tool-approval` and waits for respond_tool_approval
+#[tauri::command]
+#[instrumented]
+pub async fn run_claude_agent(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    claude: State<'_, ClaudeState>,
+    capabilities: State<'_, CapabilityRegistry>,
+    conversation_id: String,
+    content: String,
+    project_root: PathBuf,
+) -> Result<Reply, String> {
+    capabilities
+        .require(
+            &app,
+            &project_root,
+            Capability::ClaudeTools,
+            "run_claude_agent",
+        )
+        .map_err(|e| format!("{:#}", e))?;
+    let started = Instant::now();
+    let prompt = journal::one_line(&content);
+    let user = Message::user(content);
+    let mut messages = claude
+        .conversations
+        .history(&conversation_id)
+        .map_err(|e| format!("Failed to load conversation: {:#}", e))?;
+    messages.push(user.clone());
+    let mut settings = project_settings(&settings, Some(&project_root))?;
+    settings.claude = PromptContext::gather(&settings, Some(&project_root)).apply(&settings.claude);
+    let request = MessagesRequest::new(&settings.claude, messages);
+
+    let host = AppToolHost {
+        app: app.clone(),
+        conversation_id: conversation_id.clone(),
+        project_root: project_root.clone(),
+        settings: settings.clone(),
+    };
+    let agent = tools::run_agent(&claude, &settings.claude, request, &host, |text| {
+        emit_stream(&app, v1::ClaudeStream::delta(&conversation_id, text))
+    });
+    let result = claude.requests.run(&conversation_id, agent).await;
+    let reply = finish_stream(
+        &app,
+        &claude,
+        &settings.claude,
+        &conversation_id,
+        "run Claude agent",
+        result,
+    )?;
+
+    let assistant = Message::assistant(reply.content.clone());
+    if let Err(e) = claude
+        .conversations
+        .append(&conversation_id, &user, &assistant)
+    {
+        tracing::error!("Failed to save conversation {}: {:#}", conversation_id, e);
+    }
+    journal::record(
+        &project_root,
+        JournalEntry::new(JournalKind::ClaudeSession, prompt)
+            .reference(&conversation_id)
+            .duration_ms(started.elapsed().as_millis() as u64),
+    );
+    Ok(reply)
+}
+
+/// Approve or decline a tool call announced by `claude-tool-approval`
+/// Returns false when the request is unknown or already timed out
+#[tauri::command]
+#[instrumented]
+pub fn respond_tool_approval(
+    claude: State<'_, ClaudeState>,
+    request_id: String,
+    approved: bool,
+) -> bool {
+    claude.approvals.respond(&request_id, approved)
+}
+
+/// Tool host of run_claude_agent, scoped to one project
+struct AppToolHost {
+    app: AppHandle,
+    conversation_id: String,
+    project_root: PathBuf,
+    settings: Settings,
+}
+
+impl ToolHost for AppToolHost {
+    async fn approve(&self, tool: Tool, call: &ToolCall) -> bool {
+        let claude = self.app.state::<ClaudeState>();
+        claude
+            .approvals
+            .request(TOOL_APPROVAL_TIMEOUT, |request_id| {
+                let payload = v1::ClaudeToolApproval {
+                    request_id: request_id.to_string(),
+                    conversation_id: self.conversation_id.clone(),
+                    tool,
+                    input: call.input.clone(),
+                };
+                if let Err(e) = self.app.emit_all(CLAUDE_TOOL_APPROVAL_EVENT_NAME, payload) {
+                    tracing::error!("Failed to emit tool approval request: {}", e);
+                }
+            })
+            .await
+    }
+
+    async fn execute(&self, tool: Tool, input: &serde_json::Value) -> anyhow::Result<String> {
+        match tool {
+            Tool::ReadFile => {
+                let path = input["path"].as_str().context("read_file needs a `path`")?;
+                tools::read_file(&self.project_root, path)
+            }
+            Tool::GetGitStatus => {
+                let status = git::status::get_status(&self.project_root)?;
+                Ok(serde_json::to_string_pretty(&status)?)
+            }
+            Tool::ListIssues => {
+                let filters: IssueFilters =
+                    serde_json::from_value(input.clone()).context("Invalid list_issues filters")?;
+                let repo = github::Repository::from_settings(&self.settings.github)?;
+                let client = self
+                    .app
+                    .state::<GitHubState>()
+                    .client(&self.settings.github)?;
+                let issues = github::issues::list_issues(&client, &repo, &filters).await?;
+                Ok(serde_json::to_string_pretty(&issues)?)
+            }
+            Tool::RunTests => {
+                let workflow = self.settings.workflow.clone();
+                let root = self.project_root.clone();
+                let run =
+                    tokio::task::spawn_blocking(move || test_runs::run_tests(&root, &workflow))
+                        .await??;
+                Ok(serde_json::to_string_pretty(&run)?)
+            }
+        }
+    }
+}
+
+/// Settings with the overrides of the project containing `path`, if any
+fn project_settings(settings: &SettingsState, path: Option<&Path>) -> Result<Settings, ZeamiError> {
+    let current = settings.current();
+    let Some(path) = path else {
+        return Ok(current);
+    };
+    let root = git::open(path)
+        .ok()
+        .and_then(|repo| git::workdir(&repo).ok())
+        .unwrap_or_else(|| path.to_path_buf());
+    storage::settings_for_project(&current, &root)
+        .map_err(|e| ZeamiError::config(e).context("Failed to load project settings"))
+}
+
+/// The configured shell, or the user's login shell
+fn shell_name(settings: &Settings) -> String {
+    settings
+        .terminal
+        .shell
+        .clone()
+        .or_else(|| std::env::var("SHELL").ok())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// The last command tracked in a terminal session
+fn last_command(pty: &PtyState, session_id: &str) -> Result<CommandRecord, ZeamiError> {
+    let record = {
+        let sessions = pty.sessions.lock().map_err(|e| {
+            ZeamiError::new(
+                ErrorKind::Internal,
+                format!("Failed to lock sessions: {}", e),
+            )
+        })?;
+        sessions
+            .get(session_id)
+            .ok_or_else(|| {
+                ZeamiError::new(
+                    ErrorKind::NotFound,
+                    format!("Session not found: {}", session_id),
+                )
+            })?
+            .last_command()
+    };
+    record.ok_or_else(|| {
+        ZeamiError::new(
+            ErrorKind::NotFound,
+            "No command has been tracked in this session; enable your shell's integration (OSC 133)",
+        )
+    })
+}
+
+/// Complete `request`, emitting its text as `claude-stream` events under `stream_id`
+async fn stream_completion(
+    app: &AppHandle,
+    claude: &ClaudeState,
+    settings: &ClaudeSettings,
+    stream_id: &str,
+    request: &MessagesRequest,
+    action: &str,
+) -> Result<Reply, ZeamiError> {
+    let completion = claude.complete(settings, request, |text| {
+        emit_stream(app, v1::ClaudeStream::delta(stream_id, text))
+    });
+    let result = claude.requests.run(stream_id, completion).await;
+    finish_stream(app, claude, settings, stream_id, action, result)
+}
+
+fn emit_stream(app: &AppHandle, payload: v1::ClaudeStream) {
+    if let Err(e) = app.emit_all(CLAUDE_STREAM_EVENT_NAME, payload) {
+        tracing::error!("Failed to emit Claude stream: {}", e);
+    }
+}
+
+/// First line of a reply, shortened for a notification
+fn summary_line(content: &str) -> String {
+    let line = content.trim().lines().next().unwrap_or_default();
+    match line.char_indices().nth(SUMMARY_MAX_CHARS) {
+        Some((index, _)) => format!("{}…", &line[..index]),
+        None => line.to_string(),
+    }
+}
+
+/// End the `claude-stream` of `stream_id` with the outcome of a request, and emit
+/// `claude-budget-exceeded` when it took this month past the budget
+fn finish_stream(
+    app: &AppHandle,
+    claude: &ClaudeState,
+    settings: &ClaudeSettings,
+    stream_id: &str,
+    action: &str,
+    result: anyhow::Result<Reply>,
+) -> Result<Reply, ZeamiError> {
+    match result {
+        Ok(reply) => {
+            emit_stream(
+                app,
+                v1::ClaudeStream::done(stream_id, reply.stop_reason.clone(), reply.usage),
+            );
+            notifications::notify(
+                app,
+                NotificationCategory::ClaudeDone,
+                i18n::text("notification.claude_done.title"),
+                summary_line(&reply.content),
+            );
+            let today = chrono::Local::now().date_naive();
+            match claude
+                .usage
+                .budget_alert(settings.monthly_budget_usd, today)
+            {
+                Ok(Some(alert)) => {
+                    let payload: v1::ClaudeBudgetExceeded = alert;
+                    if let Err(e) = app.emit_all(CLAUDE_BUDGET_EXCEEDED_EVENT_NAME, payload) {
+                        tracing::error!("Failed to emit Claude budget warning: {}", e);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to check the Claude budget: {:#}", e),
+            }
+            Ok(reply)
+        }
+        Err(e) if e.is::<Cancelled>() => {
+            emit_stream(app, v1::ClaudeStream::cancelled(stream_id));
+            Err(ZeamiError::from_error(ErrorKind::Cancelled, e))
+        }
+        Err(e) => {
+            let error = ZeamiError::from_error(ErrorKind::Internal, e)
+                .context(format!("Failed to {}", action));
+            emit_stream(
+                app,
+                v1::ClaudeStream::failed(stream_id, error.message.clone()),
+            );
+            Err(error)
+        }
+    }
+}
+
+/// Stop the request streaming under `request_id` (the conversation, stream or
+/// session id of its `claude-stream` events); its stream ends with `cancelled`
+/// Fails with `not_found` when no request is running under that id
+#[tauri::command]
+#[instrumented]
+pub fn cancel_claude_request(
+    claude: State<'_, ClaudeState>,
+    request_id: String,
+) -> Result<(), ZeamiError> {
+    if claude.requests.cancel(&request_id) {
+        Ok(())
+    } else {
+        Err(ZeamiError::new(
+            ErrorKind::NotFound,
+            format!("No Claude request is running under {}", request_id),
+        ))
+    }
+}
+
+/// The system text Claude requests would send, with the `{{variables}}` of the
+/// system prompt and custom instructions filled in for `project_root`
+#[tauri::command]
+#[instrumented]
+pub fn preview_system_prompt(
+    settings: State<'_, SettingsState>,
+    project_root: Option<PathBuf>,
+) -> Result<SystemPromptPreview, ZeamiError> {
+    let settings = project_settings(&settings, project_root.as_deref())?;
+    let context = PromptContext::gather(&settings, project_root.as_deref());
+    Ok(prompt::preview(&settings.claude, &context))
+}
+
+/// `text` as Claude requests would send it, with secrets redacted, and where
+/// they were found; `project_root` adds the project's redaction allowlist
+#[tauri::command]
+#[instrumented]
+pub fn preview_redactions(
+    settings: State<'_, SettingsState>,
+    text: String,
+    project_root: Option<PathBuf>,
+) -> Result<RedactedText, ZeamiError> {
+    let settings = project_settings(&settings, project_root.as_deref())?;
+    Ok(Redactor::new(&settings.claude.redaction_allowlist).redact(&text))
+}
+
+/// Saved conversations, most recently active first
+#[tauri::command]
+#[instrumented]
+pub fn list_conversations(
+    claude: State<'_, ClaudeState>,
+) -> Result<Vec<ConversationSummary>, String> {
+    claude
+        .conversations
+        .list()
+        .map_err(|e| format!("Failed to list conversations: {:#}", e))
+}
+
+/// A saved conversation with its messages; None when it does not exist
+#[tauri::command]
+#[instrumented]
+pub fn get_conversation(
+    claude: State<'_, ClaudeState>,
+    conversation_id: String,
+) -> Result<Option<Conversation>, String> {
+    claude
+        .conversations
+        .get(&conversation_id)
+        .map_err(|e| format!("Failed to load conversation: {:#}", e))
+}
+
+/// A saved conversation as a Markdown or HTML document, code blocks included,
+/// with secrets redacted; `project_root` adds the project's redaction allowlist
+#[tauri::command]
+#[instrumented]
+pub fn export_conversation(
+    settings: State<'_, SettingsState>,
+    claude: State<'_, ClaudeState>,
+    conversation_id: String,
+    format: TranscriptFormat,
+    project_root: Option<PathBuf>,
+) -> Result<String, ZeamiError> {
+    let settings = project_settings(&settings, project_root.as_deref())?;
+    let conversation = claude
+        .conversations
+        .get(&conversation_id)
+        .map_err(|e| {
+            ZeamiError::from_error(ErrorKind::Internal, e).context("Failed to load conversation")
+        })?
+        .ok_or_else(|| {
+            ZeamiError::new(
+                ErrorKind::NotFound,
+                format!("Conversation not found: {}", conversation_id),
+            )
+        })?;
+    let document = transcript::render(&conversation, format);
+    Ok(Redactor::new(&settings.claude.redaction_allowlist).apply(document))
+}
+
+/// Delete a conversation; returns false when it did not exist
+#[tauri::command]
+#[instrumented]
+pub fn delete_conversation(
+    claude: State<'_, ClaudeState>,
+    conversation_id: String,
+) -> Result<bool, String> {
+    claude
+        .conversations
+        .delete(&conversation_id)
+        .map_err(|e| format!("Failed to delete conversation: {:#}", e))
+}
+
+/// Response cache hit rate, prompt cache usage and estimated savings
+#[tauri::command]
+#[instrumented]
+pub fn get_claude_cache_stats(claude: State<'_, ClaudeState>) -> Result<CacheStats, String> {
+    claude
+        .responses
+        .stats()
+        .map_err(|e| format!("Failed to read Claude cache stats: {:#}", e))
+}
+
+/// Token usage and estimated cost per model and per day over `range`
+#[tauri::command]
+#[instrumented]
+pub fn get_claude_usage(
+    claude: State<'_, ClaudeState>,
+    range: UsageRange,
+) -> Result<UsageReport, String> {
+    claude
+        .usage
+        .report(range, chrono::Local::now().date_naive())
+        .map_err(|e| format!("Failed to read Claude usage: {:#}", e))
+}
@@ -0,0 +1,416 @@
+use super::middleware::instrumented;
+use crate::config::keychain::{self, SecretKey};
+use crate::config::profiles::{self, ProfileInfo};
+use crate::config::{
+    storage, BackupInfo, BackupSettings, ConfigChange, ConfigFormat, EffectiveSettings,
+    GitHubAccount, Settings, SettingsState,
+};
+use crate::error::{ErrorKind, ZeamiError};
+use crate::github::{self, GitHubState};
+use std::path::PathBuf;
+use tauri::State;
+
+/// Get the current settings
+#[tauri::command]
+#[instrumented]
+pub fn load_settings(state: State<'_, SettingsState>) -> Result<Settings, ZeamiError> {
+    Ok(state.current())
+}
+
+/// Get the JSON Schema of the settings document, generated from the Rust types
+#[tauri::command]
+#[instrumented]
+pub fn get_settings_schema() -> serde_json::Value {
+    Settings::json_schema()
+}
+
+/// Persist settings and make them current
+#[tauri::command]
+#[instrumented]
+pub fn save_settings(
+    state: State<'_, SettingsState>,
+    settings: Settings,
+) -> Result<(), ZeamiError> {
+    storage::save_settings(&settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to save settings"))?;
+
+    state
+        .replace(settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+
+    Ok(())
+}
+
+/// Restore default settings
+#[tauri::command]
+#[instrumented]
+pub fn reset_settings(state: State<'_, SettingsState>) -> Result<Settings, ZeamiError> {
+    let settings = storage::reset_settings()
+        .map_err(|e| ZeamiError::config(e).context("Failed to reset settings"))?;
+
+    state
+        .replace(settings.clone())
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+
+    Ok(settings)
+}
+
+/// Export settings to a file and return the path written
+/// With a passphrase the file is encrypted; secrets can only be included in encrypted exports
+#[tauri::command]
+#[instrumented]
+pub fn export_settings(
+    state: State<'_, SettingsState>,
+    path: PathBuf,
+    include_secrets: bool,
+    passphrase: Option<String>,
+) -> Result<PathBuf, ZeamiError> {
+    storage::export_settings(
+        &path,
+        &state.current(),
+        include_secrets,
+        passphrase.as_deref(),
+    )
+    .map_err(|e| ZeamiError::config(e).context("Failed to export settings"))
+}
+
+/// Check whether an export file needs a passphrase to import
+#[tauri::command]
+#[instrumented]
+pub fn is_encrypted_export(path: PathBuf) -> Result<bool, ZeamiError> {
+    storage::is_encrypted_export(&path)
+        .map_err(|e| ZeamiError::config(e).context("Failed to read export"))
+}
+
+/// Import settings from an export file and make them current
+#[tauri::command]
+#[instrumented]
+pub fn import_settings(
+    state: State<'_, SettingsState>,
+    github: State<'_, GitHubState>,
+    path: PathBuf,
+    passphrase: Option<String>,
+) -> Result<Settings, ZeamiError> {
+    let settings = storage::import_settings(&path, passphrase.as_deref())
+        .map_err(|e| ZeamiError::config(e).context("Failed to import settings"))?;
+    github.invalidate();
+
+    state
+        .replace(settings.clone())
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+
+    Ok(settings)
+}
+
+/// Get the on-disk format of the config file (json or toml)
+#[tauri::command]
+#[instrumented]
+pub fn get_config_format() -> Result<ConfigFormat, ZeamiError> {
+    storage::config_format()
+        .map_err(|e| ZeamiError::config(e).context("Failed to read config format"))
+}
+
+/// Rewrite the config file in another format and return its new path
+#[tauri::command]
+#[instrumented]
+pub fn set_config_format(format: ConfigFormat) -> Result<PathBuf, ZeamiError> {
+    storage::set_config_format(format)
+        .map_err(|e| ZeamiError::config(e).context("Failed to change config format"))
+}
+
+/// Get global settings merged with a project's .zeami/config.json overrides and
+/// ZEAMI_* / `--set` overrides
+/// Each value is tagged with the layer (default, global, project, environment, cli) it came from
+#[tauri::command]
+#[instrumented]
+pub fn get_effective_settings(
+    project_root: Option<PathBuf>,
+) -> Result<EffectiveSettings, ZeamiError> {
+    storage::load_effective_settings(project_root.as_deref())
+        .map_err(|e| ZeamiError::config(e).context("Failed to load effective settings"))
+}
+
+/// Replace a project's settings overrides and return the resulting effective settings
+#[tauri::command]
+#[instrumented]
+pub fn save_project_settings(
+    project_root: PathBuf,
+    overrides: serde_json::Value,
+) -> Result<EffectiveSettings, ZeamiError> {
+    storage::save_project_settings(&project_root, &overrides)
+        .map_err(|e| ZeamiError::config(e).context("Failed to save project settings"))?;
+
+    storage::load_effective_settings(Some(&project_root))
+        .map_err(|e| ZeamiError::config(e).context("Failed to load effective settings"))
+}
+
+/// Get the recorded settings changes (timestamp and changed sections), oldest first
+#[tauri::command]
+#[instrumented]
+pub fn get_settings_history() -> Result<Vec<ConfigChange>, ZeamiError> {
+    storage::load_history()
+        .map_err(|e| ZeamiError::config(e).context("Failed to load settings history"))
+}
+
+/// List config backups, newest first
+#[tauri::command]
+#[instrumented]
+pub fn list_settings_backups() -> Result<Vec<BackupInfo>, ZeamiError> {
+    storage::list_backups().map_err(|e| ZeamiError::config(e).context("Failed to list backups"))
+}
+
+/// Restore settings from a backup file
+#[tauri::command]
+#[instrumented]
+pub fn restore_settings_backup(
+    state: State<'_, SettingsState>,
+    file_name: String,
+) -> Result<Settings, ZeamiError> {
+    let settings = storage::restore_backup(&file_name)
+        .map_err(|e| ZeamiError::config(e).context("Failed to restore backup"))?;
+
+    state
+        .replace(settings.clone())
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+
+    Ok(settings)
+}
+
+/// Get the backup retention and scheduling settings
+#[tauri::command]
+#[instrumented]
+pub fn get_backup_settings(state: State<'_, SettingsState>) -> Result<BackupSettings, ZeamiError> {
+    Ok(state.current().backup)
+}
+
+/// Update the backup settings and prune existing backups to the new retention policy
+#[tauri::command]
+#[instrumented]
+pub fn set_backup_settings(
+    state: State<'_, SettingsState>,
+    backup: BackupSettings,
+) -> Result<(), ZeamiError> {
+    let mut settings = state.current();
+    settings.backup = backup;
+    storage::save_settings(&settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to save settings"))?;
+
+    storage::apply_backup_retention(&settings.backup)
+        .map_err(|e| ZeamiError::config(e).context("Failed to prune backups"))?;
+
+    state
+        .replace(settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+
+    Ok(())
+}
+
+/// List settings profiles; the active one is flagged
+#[tauri::command]
+#[instrumented]
+pub fn list_profiles() -> Result<Vec<ProfileInfo>, ZeamiError> {
+    profiles::list_profiles().map_err(|e| ZeamiError::config(e).context("Failed to list profiles"))
+}
+
+/// Activate a profile and make its settings current
+#[tauri::command]
+#[instrumented]
+pub fn switch_profile(
+    state: State<'_, SettingsState>,
+    github: State<'_, GitHubState>,
+    name: String,
+) -> Result<Settings, ZeamiError> {
+    let settings = profiles::switch_profile(&name)
+        .map_err(|e| ZeamiError::config(e).context("Failed to switch profile"))?;
+    github.invalidate();
+
+    state
+        .replace(settings.clone())
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+
+    Ok(settings)
+}
+
+/// Create a new profile as a copy of an existing one
+#[tauri::command]
+#[instrumented]
+pub fn clone_profile(source: String, target: String) -> Result<(), ZeamiError> {
+    profiles::clone_profile(&source, &target)
+        .map_err(|e| ZeamiError::config(e).context("Failed to clone profile"))
+}
+
+/// Store a secret (GitHub token, Claude API key) in the keychain
+#[tauri::command]
+#[instrumented]
+pub fn store_secret(
+    github: State<'_, GitHubState>,
+    key: SecretKey,
+    value: String,
+) -> Result<(), ZeamiError> {
+    keychain::store_secret(key, &value)
+        .map_err(|e| ZeamiError::config(e).context("Failed to store secret"))?;
+    github.invalidate();
+    Ok(())
+}
+
+/// Remove a secret from the keychain
+#[tauri::command]
+#[instrumented]
+pub fn delete_secret(github: State<'_, GitHubState>, key: SecretKey) -> Result<(), ZeamiError> {
+    keychain::delete_secret(key)
+        .map_err(|e| ZeamiError::config(e).context("Failed to delete secret"))?;
+    github.invalidate();
+    Ok(())
+}
+
+/// Check whether a secret has been stored (the value itself is never returned)
+#[tauri::command]
+#[instrumented]
+pub fn has_secret(key: SecretKey) -> Result<bool, ZeamiError> {
+    keychain::retrieve_secret(key)
+        .map(|secret| secret.is_some())
+        .map_err(|e| ZeamiError::config(e).context("Failed to read secret"))
+}
+
+/// List the secrets stored for the active profile (names only)
+#[tauri::command]
+#[instrumented]
+pub fn list_stored_secrets() -> Result<Vec<SecretKey>, ZeamiError> {
+    keychain::list_stored_secrets()
+        .map_err(|e| ZeamiError::config(e).context("Failed to list secrets"))
+}
+
+/// Verify a GitHub token and return the login it authenticates as
+/// `api_url` defaults to the API URL of the selected account
+#[tauri::command]
+#[instrumented]
+pub async fn test_github_token(
+    settings: State<'_, SettingsState>,
+    token: String,
+    api_url: Option<String>,
+) -> Result<String, ZeamiError> {
+    let api_url = match api_url {
+        Some(api_url) => api_url,
+        None => github::api_url(&settings.current().github).map_err(ZeamiError::config)?,
+    };
+    keychain::validate_github_token(&token, &api_url)
+        .await
+        .map_err(ZeamiError::github)
+}
+
+/// Validate a new GitHub token, then replace the stored one with it
+/// Returns the login the new token authenticates as
+#[tauri::command]
+#[instrumented]
+pub async fn rotate_github_token(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    token: String,
+) -> Result<String, ZeamiError> {
+    let api_url = settings.current().github.api_url;
+    let login = keychain::rotate_github_token(&token, &api_url)
+        .await
+        .map_err(|e| ZeamiError::github(e).context("Failed to rotate GitHub token"))?;
+    github.invalidate();
+    Ok(login)
+}
+
+/// Add or update a named GitHub account (e.g. GitHub Enterprise) after validating its token
+/// Returns the login the token authenticates as
+#[tauri::command]
+#[instrumented]
+pub async fn add_github_account(
+    github: State<'_, GitHubState>,
+    state: State<'_, SettingsState>,
+    name: String,
+    api_url: String,
+    token: String,
+) -> Result<String, ZeamiError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(ZeamiError::new(
+            ErrorKind::InvalidInput,
+            "Account name must not be empty",
+        ));
+    }
+    let login = keychain::validate_github_token(&token, &api_url)
+        .await
+        .map_err(ZeamiError::github)?;
+    keychain::store_github_account_token(&name, &token)
+        .map_err(|e| ZeamiError::config(e).context("Failed to store secret"))?;
+
+    let mut settings = state.current();
+    settings
+        .github
+        .upsert_account(GitHubAccount { name, api_url });
+    storage::save_settings(&settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to save settings"))?;
+    state
+        .replace(settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+    github.invalidate();
+
+    Ok(login)
+}
+
+/// Remove a named GitHub account and its token
+#[tauri::command]
+#[instrumented]
+pub fn remove_github_account(
+    github: State<'_, GitHubState>,
+    state: State<'_, SettingsState>,
+    name: String,
+) -> Result<(), ZeamiError> {
+    let mut settings = state.current();
+    if !settings.github.remove_account(&name) {
+        return Err(unknown_account(&name));
+    }
+    storage::save_settings(&settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to save settings"))?;
+    keychain::delete_github_account_token(&name)
+        .map_err(|e| ZeamiError::config(e).context("Failed to delete secret"))?;
+    state
+        .replace(settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+    github.invalidate();
+
+    Ok(())
+}
+
+/// Select the GitHub account for a project, or globally when `project_root` is None
+/// `account` None selects the default account
+#[tauri::command]
+#[instrumented]
+pub fn select_github_account(
+    state: State<'_, SettingsState>,
+    project_root: Option<PathBuf>,
+    account: Option<String>,
+) -> Result<(), ZeamiError> {
+    let mut settings = state.current();
+    if let Some(name) = &account {
+        if !settings.github.accounts.iter().any(|a| &a.name == name) {
+            return Err(unknown_account(name));
+        }
+    }
+
+    match project_root {
+        Some(root) => storage::set_project_github_account(&root, account.as_deref())
+            .map_err(|e| ZeamiError::config(e).context("Failed to save project settings")),
+        None => {
+            settings.github.account = account;
+            storage::save_settings(&settings)
+                .map_err(|e| ZeamiError::config(e).context("Failed to save settings"))?;
+            state
+                .replace(settings)
+                .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+            Ok(())
+        }
+    }
+}
+
+fn unknown_account(name: &str) -> ZeamiError {
+    ZeamiError::new(
+        ErrorKind::InvalidInput,
+        format!("Unknown GitHub account '{}'", name),
+    )
+}
@@ -0,0 +1,56 @@
+use super::middleware::instrumented;
+use super::pty_commands::{store_session, CreateSessionResponse, PtyState};
+use crate::containers::{self, Container};
+use crate::error::ZeamiError;
+use crate::pty::PtySession;
+use std::path::PathBuf;
+use tauri::{Manager, State, Window};
+use uuid::Uuid;
+
+/// Running Docker Compose containers started from the project
+#[tauri::command]
+#[instrumented]
+pub async fn list_containers(project_root: PathBuf) -> Result<Vec<Container>, ZeamiError> {
+    tauri::async_runtime::spawn_blocking(move || containers::list(&project_root))
+        .await
+        .map_err(ZeamiError::container)?
+        .map_err(|e| ZeamiError::container(e).context("Failed to list containers"))
+}
+
+/// Open a terminal in a project container through `docker exec`
+/// `container` is a name or Compose service, defaulting to the first container;
+/// `shell` defaults to bash, else sh
+#[tauri::command]
+#[instrumented]
+pub async fn create_container_session(
+    state: State<'_, PtyState>,
+    window: Window,
+    project_root: PathBuf,
+    container: Option<String>,
+    shell: Option<String>,
+    rows: u16,
+    cols: u16,
+) -> Result<CreateSessionResponse, ZeamiError> {
+    let session_id = Uuid::new_v4().to_string();
+    let app = window.app_handle();
+    let session = {
+        let session_id = session_id.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let container = containers::resolve(&project_root, container.as_deref())?;
+            PtySession::container(
+                &project_root,
+                &container,
+                shell,
+                rows,
+                cols,
+                window,
+                session_id,
+            )
+        })
+    }
+    .await
+    .map_err(ZeamiError::container)?
+    .map_err(|e| ZeamiError::container(e).context("Failed to open container session"))?;
+
+    store_session(&app, &state, session_id, session)
+}
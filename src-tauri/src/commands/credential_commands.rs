@@ -0,0 +1,98 @@
+use super::middleware::instrumented;
+use crate::config::{keychain, storage, CredentialSource, RemoteCredential, SettingsState};
+use crate::error::{ErrorKind, ZeamiError};
+use crate::git::credentials::Credentials;
+use crate::git::remote::{self, RemoteAccess};
+use std::path::PathBuf;
+use tauri::State;
+
+/// Add a per-remote credential override, or replace the one with the same `url`
+/// `private_key` is stored in the keychain for deploy key auth; it may be
+/// omitted when updating an override whose key is already stored
+#[tauri::command]
+#[instrumented]
+pub fn set_remote_credential(
+    state: State<'_, SettingsState>,
+    credential: RemoteCredential,
+    private_key: Option<String>,
+) -> Result<(), ZeamiError> {
+    if credential.url.trim().is_empty() {
+        return Err(ZeamiError::new(
+            ErrorKind::InvalidInput,
+            "Remote credential URL must not be empty",
+        ));
+    }
+
+    match (credential.source, private_key) {
+        (CredentialSource::DeployKey | CredentialSource::Auto, Some(key)) => {
+            keychain::store_deploy_key(&credential.url, &key)
+                .map_err(|e| ZeamiError::config(e).context("Failed to store secret"))?
+        }
+        (CredentialSource::DeployKey, None) => {
+            let stored = keychain::retrieve_deploy_key(&credential.url)
+                .map_err(|e| ZeamiError::config(e).context("Failed to read secret"))?;
+            if stored.is_none() {
+                return Err(ZeamiError::new(
+                    ErrorKind::InvalidInput,
+                    format!("{} uses a deploy key but no key was given", credential.url),
+                ));
+            }
+        }
+        (CredentialSource::Auto, None) => {}
+        _ => keychain::delete_deploy_key(&credential.url)
+            .map_err(|e| ZeamiError::config(e).context("Failed to delete secret"))?,
+    }
+
+    let mut settings = state.current();
+    settings.git.upsert_remote_credential(credential);
+    storage::save_settings(&settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to save settings"))?;
+    state
+        .replace(settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+    Ok(())
+}
+
+/// Remove a per-remote credential override and its deploy key
+#[tauri::command]
+#[instrumented]
+pub fn remove_remote_credential(
+    state: State<'_, SettingsState>,
+    url: String,
+) -> Result<(), ZeamiError> {
+    let mut settings = state.current();
+    if !settings.git.remove_remote_credential(&url) {
+        return Err(ZeamiError::new(
+            ErrorKind::NotFound,
+            format!("No credential override for {}", url),
+        ));
+    }
+    storage::save_settings(&settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to save settings"))?;
+    keychain::delete_deploy_key(&url)
+        .map_err(|e| ZeamiError::config(e).context("Failed to delete secret"))?;
+    state
+        .replace(settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+    Ok(())
+}
+
+/// Check that the credentials for `remote` (default GitSettings::remote) can
+/// push there, without pushing; refusals are reported in RemoteAccess::error
+#[tauri::command]
+#[instrumented]
+pub async fn test_remote_access(
+    settings: State<'_, SettingsState>,
+    project_root: PathBuf,
+    remote: Option<String>,
+) -> Result<RemoteAccess, ZeamiError> {
+    let settings = storage::settings_for_project(&settings.current(), &project_root)
+        .map_err(ZeamiError::config)?;
+    let remote = remote.unwrap_or_else(|| settings.git.remote.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        remote::test_access(&project_root, &remote, &Credentials::load(&settings))
+    })
+    .await
+    .map_err(|e| ZeamiError::from_error(ErrorKind::Internal, e))?
+    .map_err(|e| ZeamiError::git(e).context("Failed to test remote access"))
+}
@@ -0,0 +1,9 @@
+use super::middleware::instrumented;
+use crate::deeplink::{self, DeepLink};
+
+/// zeami:// links the app was launched with; later links arrive as `deep-link` events
+#[tauri::command]
+#[instrumented]
+pub fn get_launch_deep_links() -> Vec<DeepLink> {
+    deeplink::launch_links()
+}
@@ -0,0 +1,95 @@
+use super::middleware::{self, instrumented};
+use super::pty_commands::PtyState;
+use crate::config::{keychain, storage, SettingsState};
+use crate::diagnostics::{self, health, AppHealth, BundleContents, DiagnosticBundle};
+use crate::error::{ErrorKind, ZeamiError};
+use crate::github::GitHubState;
+use crate::logging::{self, LogState};
+use crate::scheduler::Scheduler;
+use crate::tasks::TaskWatcher;
+use std::time::Duration;
+use tauri::State;
+
+/// In-memory log records included in a bundle
+const BUNDLE_LOG_LIMIT: usize = 1000;
+
+/// How long the health check waits for GitHub
+const GITHUB_HEALTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Zip crash reports, logs, runtime stats and redacted settings under
+/// ~/.zeami/diagnostics for attaching to a bug report
+#[tauri::command]
+#[instrumented]
+pub fn create_diagnostic_bundle(
+    settings: State<'_, SettingsState>,
+    logs: State<'_, LogState>,
+    pty: State<'_, PtyState>,
+    scheduler: State<'_, Scheduler>,
+    tasks: State<'_, TaskWatcher>,
+) -> Result<DiagnosticBundle, ZeamiError> {
+    let pty_sessions: Vec<String> = pty
+        .sessions
+        .lock()
+        .map(|sessions| sessions.keys().cloned().collect())
+        .unwrap_or_default();
+    let stats = serde_json::json!({
+        "pty_sessions": pty_sessions,
+        "scheduled_jobs": scheduler.list(),
+        "task_watcher_project": tasks.project(),
+        "commands": middleware::COMMAND_METRICS.snapshot(),
+    });
+    let contents = BundleContents {
+        settings: serde_json::to_value(settings.current()).unwrap_or_default(),
+        recent_logs: logs
+            .recent()
+            .recent(tracing::Level::TRACE, BUNDLE_LOG_LIMIT),
+        stats,
+    };
+
+    let result = diagnostics::diagnostics_dir().and_then(|dir| {
+        let crash_dir = diagnostics::crash_dir()?;
+        diagnostics::write_bundle(&dir, &crash_dir, &logging::log_dir()?, contents)
+    });
+    result.map_err(|e| {
+        ZeamiError::from_error(ErrorKind::Internal, e).context("Failed to create diagnostic bundle")
+    })
+}
+
+/// State of each subsystem (task watcher, terminals, GitHub, keychain,
+/// scheduled jobs, disk space) for the diagnostics page
+#[tauri::command]
+#[instrumented]
+pub async fn get_app_health(
+    settings: State<'_, SettingsState>,
+    pty: State<'_, PtyState>,
+    scheduler: State<'_, Scheduler>,
+    tasks: State<'_, TaskWatcher>,
+    github: State<'_, GitHubState>,
+) -> Result<AppHealth, ZeamiError> {
+    let project = tasks.project();
+    let sessions = pty
+        .sessions
+        .lock()
+        .map(|sessions| sessions.len())
+        .unwrap_or_default();
+    let github_settings = settings.current().github;
+    let github_status =
+        tokio::time::timeout(GITHUB_HEALTH_TIMEOUT, github.status(&github_settings))
+            .await
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out")));
+    let keychain = tauri::async_runtime::spawn_blocking(|| {
+        keychain::list_stored_secrets().map(|secrets| secrets.len())
+    })
+    .await
+    .map_err(|e| ZeamiError::from_error(ErrorKind::Internal, e))?;
+    let config_dir = storage::config_dir().map_err(ZeamiError::config)?;
+
+    Ok(AppHealth::new(vec![
+        health::watcher(project.as_deref(), tasks.last_error().as_deref()),
+        health::pty(sessions),
+        health::github(github_status),
+        health::keychain(keychain),
+        health::scheduler(&scheduler.list()),
+        health::disk(&config_dir),
+    ]))
+}
@@ -0,0 +1,9 @@
+use super::middleware::instrumented;
+use crate::events::EventSchemaInfo;
+
+/// Get the event payload schema version and the events it covers
+#[tauri::command]
+#[instrumented]
+pub fn get_event_schema_version() -> EventSchemaInfo {
+    EventSchemaInfo::current()
+}
@@ -0,0 +1,52 @@
+use super::middleware::instrumented;
+use crate::error::{ErrorKind, ZeamiError};
+use crate::files::{self, ProjectFile, WriteResult};
+use std::path::PathBuf;
+
+/// Read a text file of the project; `path` is relative to the project root
+#[tauri::command]
+#[instrumented]
+pub async fn read_project_file(
+    project_root: PathBuf,
+    path: String,
+) -> Result<ProjectFile, ZeamiError> {
+    tauri::async_runtime::spawn_blocking(move || files::read(&project_root, &path))
+        .await
+        .map_err(internal)?
+        .map_err(|e| internal(e).context("Failed to read file"))
+}
+
+/// Write a project file, backing up the previous contents
+/// Pass the `version` from read_project_file to fail instead of overwriting
+/// changes made since
+#[tauri::command]
+#[instrumented]
+pub async fn write_project_file(
+    project_root: PathBuf,
+    path: String,
+    content: String,
+    expected_version: Option<String>,
+) -> Result<WriteResult, ZeamiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        files::write(&project_root, &path, &content, expected_version.as_deref())
+    })
+    .await
+    .map_err(internal)?
+    .map_err(|e| internal(e).context("Failed to write file"))
+}
+
+/// Apply a unified diff to the project; nothing is written unless every hunk applies
+#[tauri::command]
+#[instrumented]
+pub async fn apply_patch(project_root: PathBuf, patch: String) -> Result<WriteResult, ZeamiError> {
+    tauri::async_runtime::spawn_blocking(move || files::apply_patch(&project_root, &patch))
+        .await
+        .map_err(internal)?
+        .map_err(|e| {
+            ZeamiError::from_error(ErrorKind::InvalidInput, e).context("Failed to apply patch")
+        })
+}
+
+fn internal(error: impl Into<anyhow::Error>) -> ZeamiError {
+    ZeamiError::from_error(ErrorKind::Internal, error)
+}
@@ -0,0 +1,34 @@
+use super::middleware::instrumented;
+use crate::error::{ErrorKind, ZeamiError};
+use crate::frecency::{Frecency, RankedPath};
+use std::path::PathBuf;
+use tauri::State;
+
+/// Entries returned when the caller sets no limit
+const DEFAULT_LIMIT: usize = 50;
+
+/// Files of the project ranked by how often and how lately they were changed
+#[tauri::command]
+#[instrumented]
+pub async fn get_recent_files(
+    frecency: State<'_, Frecency>,
+    project_root: PathBuf,
+    limit: Option<usize>,
+) -> Result<Vec<RankedPath>, ZeamiError> {
+    frecency
+        .recent_files(&project_root, limit.unwrap_or(DEFAULT_LIMIT))
+        .map_err(|e| ZeamiError::from_error(ErrorKind::Internal, e))
+}
+
+/// Project directories ranked by how often and how lately terminals moved into them
+#[tauri::command]
+#[instrumented]
+pub async fn get_frequent_dirs(
+    frecency: State<'_, Frecency>,
+    project_root: PathBuf,
+    limit: Option<usize>,
+) -> Result<Vec<RankedPath>, ZeamiError> {
+    frecency
+        .frequent_dirs(&project_root, limit.unwrap_or(DEFAULT_LIMIT))
+        .map_err(|e| ZeamiError::from_error(ErrorKind::Internal, e))
+}
@@ -0,0 +1,352 @@
+use super::middleware::instrumented;
+use crate::capabilities::{Capability, CapabilityRegistry};
+use crate::config::{storage, MergeStrategy, SettingsState};
+use crate::error::{ErrorKind, ZeamiError};
+use crate::events::schema::v1;
+use crate::events::{GIT_COMMIT_EVENT_NAME, GIT_LARGE_FILES_EVENT_NAME};
+use crate::git::blame::{self, BlameLine, LineRange};
+use crate::git::branches::{self, BranchInfo, CheckoutResult};
+use crate::git::commit::{self, CommitInfo, NewCommit};
+use crate::git::commitlint::{self, LintResult};
+use crate::git::conflicts::{self, MergeConflicts, Resolution};
+use crate::git::credentials::Credentials;
+use crate::git::diff::{self, DiffTarget, FileDiff};
+use crate::git::fetcher::{self, FetchScheduler};
+use crate::git::insights::{self, InsightsRange, RepoInsights};
+use crate::git::links::linked_issue;
+use crate::git::remote::RemoteUpdate;
+use crate::git::signatures::{self, CommitSignature};
+use crate::git::status::{self, GitStatus};
+use crate::git::submodules::{self, SubmoduleStatus};
+use crate::git::sync::BaseSync;
+use crate::journal::{self, JournalEntry, JournalKind};
+use crate::scheduler::Scheduler;
+use crate::workflow::sync_base;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+/// Branch, ahead/behind counts, changed files and in-progress operation of a repository
+#[tauri::command]
+#[instrumented]
+pub fn get_git_status(project_root: PathBuf) -> Result<GitStatus, ZeamiError> {
+    status::get_status(&project_root)
+        .map_err(|e| ZeamiError::git(e).context("Failed to get git status"))
+}
+
+/// Stage the given files and commit, applying the commit template, sign-off and GPG signing
+/// Emits `git-commit` on success
+#[tauri::command]
+#[instrumented]
+pub fn create_commit(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+    commit: NewCommit,
+) -> Result<CommitInfo, ZeamiError> {
+    capabilities
+        .require(&app, &project_root, Capability::Commit, "create_commit")
+        .map_err(ZeamiError::git)?;
+    let settings = settings.current();
+    let info = commit::create_commit(
+        &project_root,
+        &commit,
+        &settings.git,
+        settings.github.auto_link_issues,
+    )
+    .map_err(|e| ZeamiError::git(e).context("Failed to commit"))?;
+    journal::record(
+        &project_root,
+        JournalEntry::new(JournalKind::Commit, &info.summary).reference(&info.id),
+    );
+
+    if !info.large_files.is_empty() {
+        let payload = v1::GitLargeFiles {
+            project_root: project_root.clone(),
+            commit_id: info.id.clone(),
+            action: settings.git.large_file_action,
+            files: info.large_files.clone(),
+        };
+        if let Err(e) = app.emit_all(GIT_LARGE_FILES_EVENT_NAME, payload) {
+            tracing::error!("Failed to emit large files: {}", e);
+        }
+    }
+
+    let payload = v1::GitCommit {
+        project_root,
+        commit: info.clone(),
+    };
+    if let Err(e) = app.emit_all(GIT_COMMIT_EVENT_NAME, payload) {
+        tracing::error!("Failed to emit git commit: {}", e);
+    }
+    Ok(info)
+}
+
+/// Local branches with tracking, tip commit, linked issue and merge state
+#[tauri::command]
+#[instrumented]
+pub fn list_branches(
+    settings: State<'_, SettingsState>,
+    project_root: PathBuf,
+) -> Result<Vec<BranchInfo>, ZeamiError> {
+    branches::list_branches(&project_root, &settings.current().git)
+        .map_err(|e| ZeamiError::git(e).context("Failed to list branches"))
+}
+
+/// Create a branch from `start_point` (default HEAD), optionally switching to it
+#[tauri::command]
+#[instrumented]
+pub fn create_branch(
+    settings: State<'_, SettingsState>,
+    project_root: PathBuf,
+    name: String,
+    start_point: Option<String>,
+    checkout: Option<bool>,
+) -> Result<BranchInfo, ZeamiError> {
+    let git = settings.current().git;
+    let branch = branches::create_branch(&project_root, &name, start_point.as_deref(), &git)
+        .map_err(|e| ZeamiError::git(e).context("Failed to create branch"))?;
+    if checkout.unwrap_or(false) {
+        branches::checkout_branch(&project_root, &name, false, &git)
+            .map_err(|e| ZeamiError::git(e).context("Failed to check out branch"))?;
+    }
+    Ok(branch)
+}
+
+/// Switch branches; refuses with uncommitted changes unless `force` discards them
+#[tauri::command]
+#[instrumented]
+pub fn checkout_branch(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+    name: String,
+    force: Option<bool>,
+) -> Result<CheckoutResult, ZeamiError> {
+    capabilities
+        .require(&app, &project_root, Capability::Commit, "checkout_branch")
+        .map_err(ZeamiError::git)?;
+    branches::checkout_branch(
+        &project_root,
+        &name,
+        force.unwrap_or(false),
+        &settings.current().git,
+    )
+    .map_err(|e| ZeamiError::git(e).context("Failed to check out branch"))
+}
+
+/// Delete a local branch; `force` is needed for branches not merged into the default branch
+#[tauri::command]
+#[instrumented]
+pub fn delete_branch(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+    name: String,
+    force: Option<bool>,
+) -> Result<(), ZeamiError> {
+    capabilities
+        .require(&app, &project_root, Capability::Commit, "delete_branch")
+        .map_err(ZeamiError::git)?;
+    branches::delete_branch(
+        &project_root,
+        &name,
+        force.unwrap_or(false),
+        &settings.current().git,
+    )
+    .map_err(|e| ZeamiError::git(e).context("Failed to delete branch"))
+}
+
+/// Delete issue branches merged into the default branch; `dry_run` only lists them
+#[tauri::command]
+#[instrumented]
+pub fn clean_merged_branches(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+    dry_run: Option<bool>,
+) -> Result<Vec<String>, ZeamiError> {
+    let dry_run = dry_run.unwrap_or(false);
+    if !dry_run {
+        capabilities
+            .require(
+                &app,
+                &project_root,
+                Capability::Commit,
+                "clean_merged_branches",
+            )
+            .map_err(ZeamiError::git)?;
+    }
+    branches::clean_merged_branches(&project_root, &settings.current().git, dry_run)
+        .map_err(|e| ZeamiError::git(e).context("Failed to clean branches"))
+}
+
+/// Structured hunks of one file against the index, HEAD or a commit's parent
+#[tauri::command]
+#[instrumented]
+pub fn get_file_diff(
+    project_root: PathBuf,
+    path: String,
+    target: DiffTarget,
+) -> Result<FileDiff, ZeamiError> {
+    diff::get_file_diff(&project_root, &path, &target)
+        .map_err(|e| ZeamiError::git(e).context(format!("Failed to diff {}", path)))
+}
+
+/// Per-line blame of the working tree file, optionally limited to `range`
+#[tauri::command]
+#[instrumented]
+pub fn get_blame(
+    project_root: PathBuf,
+    path: String,
+    range: Option<LineRange>,
+) -> Result<Vec<BlameLine>, ZeamiError> {
+    blame::get_blame(&project_root, &path, range)
+        .map_err(|e| ZeamiError::git(e).context(format!("Failed to blame {}", path)))
+}
+
+/// Fetch the configured remote now; emits `git-remote-updated`
+#[tauri::command]
+#[instrumented]
+pub async fn fetch_remote(
+    app: AppHandle,
+    project_root: PathBuf,
+) -> Result<RemoteUpdate, ZeamiError> {
+    fetcher::fetch_and_emit(&app, &project_root)
+        .await
+        .map_err(|e| ZeamiError::git(e).context("Failed to fetch"))
+}
+
+/// Clone missing submodules and check out the commit recorded for each
+#[tauri::command]
+#[instrumented]
+pub async fn update_submodules(
+    settings: State<'_, SettingsState>,
+    project_root: PathBuf,
+) -> Result<Vec<SubmoduleStatus>, ZeamiError> {
+    let settings = storage::settings_for_project(&settings.current(), &project_root)
+        .map_err(ZeamiError::config)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        submodules::update_submodules(&project_root, &Credentials::load(&settings))
+    })
+    .await
+    .map_err(|e| ZeamiError::from_error(ErrorKind::Internal, e))?
+    .map_err(|e| ZeamiError::git(e).context("Failed to update submodules"))
+}
+
+/// Fetch, then merge or rebase the default branch into the checked-out branch
+/// per `strategy` (default GitSettings::merge_strategy); steps arrive as
+/// `workflow-step` events and conflicts leave the branch untouched
+#[tauri::command]
+#[instrumented]
+pub async fn sync_branch_with_base(
+    app: AppHandle,
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+    strategy: Option<MergeStrategy>,
+) -> Result<BaseSync, ZeamiError> {
+    capabilities
+        .require(
+            &app,
+            &project_root,
+            Capability::Commit,
+            "sync_branch_with_base",
+        )
+        .map_err(ZeamiError::git)?;
+    sync_base::sync_branch_with_base(&app, &project_root, strategy)
+        .await
+        .map_err(|e| ZeamiError::git(e).context("Failed to sync with the base branch"))
+}
+
+/// Auto-fetch `project_root` per GitSettings::auto_fetch (None stops)
+/// Updates arrive as `git-remote-updated` events
+#[tauri::command]
+#[instrumented]
+pub fn watch_remote(
+    fetcher: State<'_, FetchScheduler>,
+    scheduler: State<'_, Scheduler>,
+    project_root: Option<PathBuf>,
+) {
+    fetcher.watch(project_root);
+    scheduler.trigger(fetcher::FETCH_JOB);
+}
+
+/// Conflicted files with base, ours, theirs and the marked-up working copy
+#[tauri::command]
+#[instrumented]
+pub fn get_merge_conflicts(project_root: PathBuf) -> Result<MergeConflicts, ZeamiError> {
+    conflicts::get_merge_conflicts(&project_root)
+        .map_err(|e| ZeamiError::git(e).context("Failed to read merge conflicts"))
+}
+
+/// Write the chosen side (or hand-merged content) of a conflicted file and stage it
+#[tauri::command]
+#[instrumented]
+pub fn resolve_conflict(
+    app: AppHandle,
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+    path: String,
+    resolution: Resolution,
+) -> Result<(), ZeamiError> {
+    capabilities
+        .require(&app, &project_root, Capability::Commit, "resolve_conflict")
+        .map_err(ZeamiError::git)?;
+    conflicts::resolve_conflict(&project_root, &path, &resolution)
+        .map_err(|e| ZeamiError::git(e).context(format!("Failed to resolve {}", path)))
+}
+
+/// Check a commit message against the conventional commit rules
+/// With `project_root`, a reference to the branch's linked issue is expected per
+/// GitHubSettings::auto_link_issues
+#[tauri::command]
+#[instrumented]
+pub fn lint_commit_message(
+    settings: State<'_, SettingsState>,
+    project_root: Option<PathBuf>,
+    message: String,
+) -> Result<LintResult, ZeamiError> {
+    let settings = settings.current();
+    let issue = match project_root {
+        Some(root) if settings.github.auto_link_issues => linked_issue(&root, &settings.git)
+            .map_err(|e| ZeamiError::git(e).context("Failed to read the linked issue"))?
+            .map(|link| link.issue),
+        _ => None,
+    };
+    Ok(commitlint::lint(&message, issue))
+}
+
+/// Signature status, signer and key trust of the commits in `range`
+/// (`base..head` or a revision; HEAD when omitted), newest first
+#[tauri::command]
+#[instrumented]
+pub async fn get_commit_signature_status(
+    project_root: PathBuf,
+    range: Option<String>,
+) -> Result<Vec<CommitSignature>, ZeamiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        signatures::signature_status(&project_root, range.as_deref())
+    })
+    .await
+    .map_err(|e| ZeamiError::from_error(ErrorKind::Internal, e))?
+    .map_err(|e| ZeamiError::git(e).context("Failed to verify commit signatures"))
+}
+
+/// Commit counts, authors, file churn and hotspots from local history in
+/// `range`; per-commit results are cached so repeated calls stay cheap
+#[tauri::command]
+#[instrumented]
+pub async fn get_repo_insights(
+    project_root: PathBuf,
+    range: Option<InsightsRange>,
+) -> Result<RepoInsights, ZeamiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        insights::repo_insights(&project_root, &range.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| ZeamiError::from_error(ErrorKind::Internal, e))?
+    .map_err(|e| ZeamiError::git(e).context("Failed to compute repository insights"))
+}
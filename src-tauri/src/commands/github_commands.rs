@@ -0,0 +1,657 @@
+use super::middleware::instrumented;
+use crate::capabilities::{Capability, CapabilityRegistry};
+use crate::claude::redact::{RedactedText, Redactor};
+use crate::claude::transcript;
+use crate::claude::ClaudeState;
+use crate::config::{storage, GitHubSettings, Settings, SettingsState};
+use crate::error::{ErrorKind, ZeamiError};
+use crate::events::schema::v1::{PullRequestOperation, PullRequestProgress};
+use crate::events::PULL_REQUEST_PROGRESS_EVENT_NAME;
+use crate::git::links::{self, IssueLink};
+use crate::github::cache::{CachedIssue, CachedIssueQuery};
+use crate::github::checks::{self, BranchChecks};
+use crate::github::ci::CiWatcher;
+use crate::github::discussions::{self, DiscussionPage};
+use crate::github::graphql::{self, IssueDetailBundle};
+use crate::github::issues::{self, Issue, IssueComment, IssueDetail, IssueFilters};
+use crate::github::projects::{self, BoardItem, ProjectBoard};
+use crate::github::pulls::{self, NewPullRequest, PullRequest};
+use crate::github::queue::{self, Mutation, MutationOutcome, MutationQueue, PendingMutation};
+use crate::github::releases::{self, NewRelease, Release};
+use crate::github::scheduler::RateLimitStatus;
+use crate::github::sync::SyncService;
+use crate::github::templates::{self, IssueDraft, IssueTemplate, Label};
+use crate::github::{GitHubApiStatus, GitHubState, Repository};
+use crate::i18n;
+use crate::state::{self, ProjectSnapshot};
+use octocrab::Octocrab;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+
+/// Report the authenticated GitHub user and remaining API rate limit
+#[tauri::command]
+#[instrumented]
+pub async fn github_api_status(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+) -> Result<GitHubApiStatus, ZeamiError> {
+    github
+        .status(&settings.current().github)
+        .await
+        .map_err(|e| ZeamiError::github(e).context("Failed to get GitHub API status"))
+}
+
+/// Report the request budget: the rate limit counted down since GitHub last
+/// reported it, and whether background requests are being held back
+#[tauri::command]
+#[instrumented]
+pub async fn get_rate_limit_status(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+) -> Result<RateLimitStatus, ZeamiError> {
+    github
+        .rate_limit_status(&settings.current().github)
+        .await
+        .map_err(|e| ZeamiError::github(e).context("Failed to get GitHub rate limit"))
+}
+
+/// List issues in the configured repository
+#[tauri::command]
+#[instrumented]
+pub async fn list_issues(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    filters: Option<IssueFilters>,
+) -> Result<Vec<Issue>, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    issues::list_issues(&client, &repo, &filters.unwrap_or_default())
+        .await
+        .map_err(ZeamiError::github)
+}
+
+/// Get an issue with its comments
+#[tauri::command]
+#[instrumented]
+pub async fn get_issue(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    number: u64,
+) -> Result<IssueDetail, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    issues::get_issue(&client, &repo, number)
+        .await
+        .map_err(ZeamiError::github)
+}
+
+/// Get an issue with comments, linked pull requests, project status and sub-issues
+/// in a single GraphQL request
+#[tauri::command]
+#[instrumented]
+pub async fn get_issue_detail_bundle(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    number: u64,
+) -> Result<IssueDetailBundle, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    let api_url = github
+        .api_url(&settings.current().github)
+        .map_err(ZeamiError::config)?;
+    graphql::get_issue_detail_bundle(&client, &api_url, &repo, number)
+        .await
+        .map_err(ZeamiError::github)
+}
+
+/// Open a new issue
+#[tauri::command]
+#[instrumented]
+pub async fn create_issue(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    title: String,
+    body: Option<String>,
+    labels: Option<Vec<String>>,
+) -> Result<Issue, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    issues::create_issue(
+        &client,
+        &repo,
+        &title,
+        body.as_deref().unwrap_or_default(),
+        labels.unwrap_or_default(),
+    )
+    .await
+    .map_err(ZeamiError::github)
+}
+
+/// Add a comment to an issue
+/// Queued for replay when GitHub cannot be reached
+#[tauri::command]
+#[instrumented]
+pub async fn comment_on_issue(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    queue: State<'_, MutationQueue>,
+    number: u64,
+    body: String,
+) -> Result<MutationOutcome<IssueComment>, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    let result = issues::comment_on_issue(&client, &repo, number, &body).await;
+    queue_if_offline(&queue, &repo, Mutation::Comment { number, body }, result)
+}
+
+/// The comment attach_conversation_to_issue would post, with secrets redacted,
+/// and where they were found; shown for confirmation before attaching
+#[tauri::command]
+#[instrumented]
+pub fn preview_conversation_comment(
+    settings: State<'_, SettingsState>,
+    claude: State<'_, ClaudeState>,
+    conversation_id: String,
+) -> Result<RedactedText, ZeamiError> {
+    conversation_comment(&settings.current(), &claude, &conversation_id)
+}
+
+/// Post a saved Claude conversation on an issue as a collapsed comment, with
+/// secrets redacted as preview_conversation_comment shows
+/// Queued for replay when GitHub cannot be reached
+#[tauri::command]
+#[instrumented]
+pub async fn attach_conversation_to_issue(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    queue: State<'_, MutationQueue>,
+    claude: State<'_, ClaudeState>,
+    conversation_id: String,
+    number: u64,
+) -> Result<MutationOutcome<IssueComment>, ZeamiError> {
+    let body = conversation_comment(&settings.current(), &claude, &conversation_id)?.text;
+    let (client, repo) = connect(&github, &settings)?;
+    let result = issues::comment_on_issue(&client, &repo, number, &body).await;
+    queue_if_offline(&queue, &repo, Mutation::Comment { number, body }, result)
+}
+
+/// Close an issue, optionally with a closing comment
+/// Queued for replay when GitHub cannot be reached
+#[tauri::command]
+#[instrumented]
+pub async fn close_issue(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    queue: State<'_, MutationQueue>,
+    number: u64,
+    comment: Option<String>,
+) -> Result<MutationOutcome<Issue>, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    let result = issues::close_issue(&client, &repo, number, comment.as_deref()).await;
+    queue_if_offline(
+        &queue,
+        &repo,
+        Mutation::CloseIssue { number, comment },
+        result,
+    )
+}
+
+/// Mutations made offline that are waiting to be replayed, including conflicts
+#[tauri::command]
+#[instrumented]
+pub fn list_pending_mutations(queue: State<'_, MutationQueue>) -> Vec<PendingMutation> {
+    queue.list()
+}
+
+/// Drop a queued mutation without applying it
+#[tauri::command]
+#[instrumented]
+pub fn cancel_pending_mutation(
+    queue: State<'_, MutationQueue>,
+    id: String,
+) -> Result<(), ZeamiError> {
+    match queue.cancel(&id) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ZeamiError::new(
+            ErrorKind::NotFound,
+            format!("No pending mutation {}", id),
+        )),
+        Err(e) => Err(ZeamiError::github(e).context("Failed to cancel mutation")),
+    }
+}
+
+/// Markdown issue templates of the configured repository
+#[tauri::command]
+#[instrumented]
+pub async fn list_issue_templates(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+) -> Result<Vec<IssueTemplate>, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    templates::list_issue_templates(&client, &repo)
+        .await
+        .map_err(ZeamiError::github)
+}
+
+/// Fill a template's `{placeholder}`s to prefill the create issue dialog
+#[tauri::command]
+#[instrumented]
+pub fn render_issue_template(
+    settings: State<'_, SettingsState>,
+    template: IssueTemplate,
+    values: Option<HashMap<String, String>>,
+) -> Result<IssueDraft, ZeamiError> {
+    let repo = Repository::from_settings(&settings.current().github).map_err(repository_error)?;
+    Ok(templates::render_template(
+        &template,
+        &repo,
+        &values.unwrap_or_default(),
+    ))
+}
+
+/// Labels defined in the configured repository
+#[tauri::command]
+#[instrumented]
+pub async fn list_labels(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+) -> Result<Vec<Label>, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    templates::list_labels(&client, &repo)
+        .await
+        .map_err(ZeamiError::github)
+}
+
+/// Create a label; `color` is a hex color with or without `#`
+#[tauri::command]
+#[instrumented]
+pub async fn create_label(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    name: String,
+    color: String,
+    description: Option<String>,
+) -> Result<Label, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    templates::create_label(
+        &client,
+        &repo,
+        &name,
+        &color,
+        description.as_deref().unwrap_or_default(),
+    )
+    .await
+    .map_err(ZeamiError::github)
+}
+
+/// Open a pull request from the current branch of `repo_path`
+/// Progress is reported through `pull-request-progress` events
+#[tauri::command]
+#[instrumented]
+pub async fn create_pull_request(
+    app: AppHandle,
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    repo_path: PathBuf,
+    pull_request: NewPullRequest,
+) -> Result<PullRequest, ZeamiError> {
+    let current = project_settings(&settings, &repo_path)?;
+    let (client, repo) = connect_with(&github, &current.github)?;
+    let result = pulls::create_pull_request(
+        &client,
+        &repo,
+        &repo_path,
+        &current.github,
+        &current.git,
+        pull_request,
+        |progress| emit_progress(&app, progress),
+    )
+    .await;
+    finish(&app, PullRequestOperation::Create, None, result)
+}
+
+/// List pull requests in the configured repository
+#[tauri::command]
+#[instrumented]
+pub async fn list_pull_requests(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    state: Option<String>,
+    page: Option<u32>,
+) -> Result<Vec<PullRequest>, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    pulls::list_pull_requests(&client, &repo, state.as_deref(), page)
+        .await
+        .map_err(ZeamiError::github)
+}
+
+/// Merge a pull request using GitSettings::merge_strategy
+#[tauri::command]
+#[instrumented]
+pub async fn merge_pull_request(
+    app: AppHandle,
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    number: u64,
+) -> Result<PullRequest, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    let strategy = settings.current().git.merge_strategy;
+    let result = pulls::merge_pull_request(&client, &repo, number, strategy, |progress| {
+        emit_progress(&app, progress)
+    })
+    .await;
+    finish(&app, PullRequestOperation::Merge, Some(number), result)
+}
+
+/// Request reviews on a pull request; defaults to GitHubSettings::default_reviewers
+#[tauri::command]
+#[instrumented]
+pub async fn request_reviewers(
+    app: AppHandle,
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    number: u64,
+    reviewers: Option<Vec<String>>,
+) -> Result<PullRequest, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    let reviewers = reviewers.unwrap_or_else(|| settings.current().github.default_reviewers);
+    let operation = PullRequestOperation::RequestReview;
+    emit_progress(&app, PullRequestProgress::started(operation, Some(number)));
+    let result = pulls::request_reviewers(&client, &repo, number, reviewers, |progress| {
+        emit_progress(&app, progress)
+    })
+    .await;
+    if result.is_ok() {
+        emit_progress(
+            &app,
+            PullRequestProgress::completed(operation, Some(number)),
+        );
+    }
+    finish(&app, operation, Some(number), result)
+}
+
+/// Releases of the configured repository, newest first
+#[tauri::command]
+#[instrumented]
+pub async fn list_releases(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    page: Option<u32>,
+) -> Result<Vec<Release>, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    releases::list_releases(&client, &repo, page)
+        .await
+        .map_err(ZeamiError::github)
+}
+
+/// Tag and publish a release; without `notes`, they are drafted from
+/// `generate_notes` (commits or journal of `repo_path` since the previous tag,
+/// or GitHub's generated notes)
+#[tauri::command]
+#[instrumented]
+pub async fn create_release(
+    app: AppHandle,
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    capabilities: State<'_, CapabilityRegistry>,
+    repo_path: PathBuf,
+    release: NewRelease,
+) -> Result<Release, ZeamiError> {
+    capabilities
+        .require(&app, &repo_path, Capability::Push, "create_release")
+        .map_err(ZeamiError::github)?;
+    let current = project_settings(&settings, &repo_path)?;
+    let (client, repo) = connect_with(&github, &current.github)?;
+    releases::create_release(&client, &repo, &repo_path, &current.git, release)
+        .await
+        .map_err(ZeamiError::github)
+}
+
+/// Discussions of the configured repository, most recently updated first;
+/// pass DiscussionPage::next_cursor as `after` for the next page
+#[tauri::command]
+#[instrumented]
+pub async fn list_discussions(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    limit: Option<u8>,
+    after: Option<String>,
+) -> Result<DiscussionPage, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    let api_url = github
+        .api_url(&settings.current().github)
+        .map_err(ZeamiError::config)?;
+    discussions::list_discussions(&client, &api_url, &repo, limit, after.as_deref())
+        .await
+        .map_err(ZeamiError::github)
+}
+
+/// The configured Projects v2 board (GitHubSettings::project) with the
+/// repository's issues in its Status columns
+#[tauri::command]
+#[instrumented]
+pub async fn get_project_board(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+) -> Result<ProjectBoard, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    let current = settings.current();
+    let api_url = github
+        .api_url(&current.github)
+        .map_err(ZeamiError::config)?;
+    projects::get_project_board(&client, &api_url, &repo, current.github.project)
+        .await
+        .map_err(ZeamiError::github)
+}
+
+/// Move an issue to a column (Status option id or name) of the project board
+#[tauri::command]
+#[instrumented]
+pub async fn move_issue_to_column(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    issue: u64,
+    column: String,
+) -> Result<BoardItem, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    let current = settings.current();
+    let api_url = github
+        .api_url(&current.github)
+        .map_err(ZeamiError::config)?;
+    projects::move_issue_to_column(
+        &client,
+        &api_url,
+        &repo,
+        current.github.project,
+        issue,
+        &column,
+    )
+    .await
+    .map_err(ZeamiError::github)
+}
+
+/// Search the local issue cache; works offline and costs no API requests
+#[tauri::command]
+#[instrumented]
+pub fn query_cached_issues(
+    sync: State<'_, SyncService>,
+    settings: State<'_, SettingsState>,
+    query: Option<CachedIssueQuery>,
+) -> Result<Vec<CachedIssue>, ZeamiError> {
+    let query = query.unwrap_or_default();
+    let repository = match &query.repository {
+        Some(repository) => Repository::parse(repository),
+        None => Repository::from_settings(&settings.current().github),
+    }
+    .map_err(repository_error)?;
+
+    sync.cache()
+        .query(&repository.to_string(), &query)
+        .map_err(|e| ZeamiError::github(e).context("Failed to query issue cache"))
+}
+
+/// Start a background sync now; the result arrives as a `github-sync` event
+#[tauri::command]
+#[instrumented]
+pub fn sync_github_now(sync: State<'_, SyncService>) {
+    sync.trigger();
+}
+
+/// Create or check out the branch for an issue in `repo_path` and record the link
+/// Branches follow GitSettings::branch_prefix, e.g. `issue-42-fix-login`
+/// Emits `state-changed`
+#[tauri::command]
+#[instrumented]
+pub async fn start_issue(
+    app: AppHandle,
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    repo_path: PathBuf,
+    number: u64,
+) -> Result<IssueLink, ZeamiError> {
+    let current = project_settings(&settings, &repo_path)?;
+    let (client, repo) = connect_with(&github, &current.github)?;
+    let issue = issues::get_issue_summary(&client, &repo, number)
+        .await
+        .map_err(ZeamiError::github)?;
+    let link = links::start_issue(&repo_path, number, &issue.title, &current.git)
+        .map_err(|e| ZeamiError::git(e).context(format!("Failed to start issue #{}", number)))?;
+
+    match state::load(&repo_path) {
+        Ok(updated) => state::emit_changed(
+            &app,
+            ProjectSnapshot::new(&repo_path, updated, &current.git),
+        ),
+        Err(e) => tracing::warn!("Failed to reload project state: {:#}", e),
+    }
+    Ok(link)
+}
+
+/// Issue linked to the checked-out branch of `repo_path`, if any
+#[tauri::command]
+#[instrumented]
+pub fn get_linked_issue(
+    settings: State<'_, SettingsState>,
+    repo_path: PathBuf,
+) -> Result<Option<IssueLink>, ZeamiError> {
+    links::linked_issue(&repo_path, &settings.current().git)
+        .map_err(|e| ZeamiError::git(e).context("Failed to get linked issue"))
+}
+
+/// GitHub Actions runs for the head commit of `branch`
+/// `head_sha` defaults to the commit of the newest run on the branch
+#[tauri::command]
+#[instrumented]
+pub async fn get_checks_for_branch(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    branch: String,
+    head_sha: Option<String>,
+) -> Result<BranchChecks, ZeamiError> {
+    let (client, repo) = connect(&github, &settings)?;
+    checks::get_checks_for_branch(&client, &repo, &branch, head_sha.as_deref())
+        .await
+        .map_err(ZeamiError::github)
+}
+
+/// Watch CI for the checked-out branch of `repo_path` (None stops watching)
+/// Changes arrive as `ci-status-changed` events
+#[tauri::command]
+#[instrumented]
+pub fn watch_ci_status(watcher: State<'_, CiWatcher>, repo_path: Option<PathBuf>) {
+    watcher.watch(repo_path);
+}
+
+fn emit_progress(app: &AppHandle, progress: PullRequestProgress) {
+    if let Err(e) = app.emit_all(PULL_REQUEST_PROGRESS_EVENT_NAME, progress) {
+        tracing::error!("Failed to emit pull request progress: {}", e);
+    }
+}
+
+/// Report a failed operation to the UI and convert the error for the command result
+fn finish<T>(
+    app: &AppHandle,
+    operation: PullRequestOperation,
+    number: Option<u64>,
+    result: anyhow::Result<T>,
+) -> Result<T, ZeamiError> {
+    result.map_err(|e| {
+        let error = ZeamiError::github(e);
+        emit_progress(
+            app,
+            PullRequestProgress::failed(operation, number, error.message.clone()),
+        );
+        error
+    })
+}
+
+/// Queue `mutation` when `result` failed because GitHub was unreachable
+fn queue_if_offline<T>(
+    queue: &MutationQueue,
+    repo: &Repository,
+    mutation: Mutation,
+    result: anyhow::Result<T>,
+) -> Result<MutationOutcome<T>, ZeamiError> {
+    match result {
+        Ok(result) => Ok(MutationOutcome::Applied { result }),
+        Err(e) if queue::is_offline(&e) => {
+            let error = format!("{:#}", e);
+            tracing::info!(repository = %repo, "GitHub unreachable, queueing mutation: {}", error);
+            let pending = queue
+                .push(repo, mutation, &error, chrono::Utc::now())
+                .map_err(|e| ZeamiError::github(e).context("Failed to queue mutation"))?;
+            Ok(MutationOutcome::Queued { pending })
+        }
+        Err(e) => Err(ZeamiError::github(e)),
+    }
+}
+
+/// Shared client and the configured repository
+/// A saved conversation as an issue comment, redacted with the allowlist of `settings`
+fn conversation_comment(
+    settings: &Settings,
+    claude: &ClaudeState,
+    conversation_id: &str,
+) -> Result<RedactedText, ZeamiError> {
+    let conversation = claude
+        .conversations
+        .get(conversation_id)
+        .map_err(|e| ZeamiError::new(ErrorKind::Internal, format!("{:#}", e)))?
+        .ok_or_else(|| {
+            ZeamiError::new(
+                ErrorKind::NotFound,
+                format!("Conversation not found: {}", conversation_id),
+            )
+        })?;
+    let body = transcript::issue_comment(&conversation);
+    Ok(Redactor::new(&settings.claude.redaction_allowlist).redact(&body))
+}
+
+fn connect(
+    github: &GitHubState,
+    settings: &SettingsState,
+) -> Result<(Arc<Octocrab>, Repository), ZeamiError> {
+    connect_with(github, &settings.current().github)
+}
+
+/// Client of the account selected in `settings` and its repository
+pub(crate) fn connect_with(
+    github: &GitHubState,
+    settings: &GitHubSettings,
+) -> Result<(Arc<Octocrab>, Repository), ZeamiError> {
+    let repo = Repository::from_settings(settings).map_err(repository_error)?;
+    let client = github
+        .client(settings)
+        .map_err(|e| ZeamiError::github(e).context("Failed to connect to GitHub"))?;
+    Ok((client, repo))
+}
+
+/// Current settings with the overrides of the project at `repo_path`
+pub(crate) fn project_settings(
+    settings: &SettingsState,
+    repo_path: &Path,
+) -> Result<Settings, ZeamiError> {
+    storage::settings_for_project(&settings.current(), repo_path)
+        .map_err(|e| ZeamiError::config(e).context("Failed to load project settings"))
+}
+
+fn repository_error(error: anyhow::Error) -> ZeamiError {
+    ZeamiError::config(error).with_hint(i18n::text("error.hint.github_repository_setting"))
+}
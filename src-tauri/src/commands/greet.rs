@@ -1,5 +1,8 @@
+use super::middleware::instrumented;
+
 /// A simple greeting command for testing Tauri IPC
 #[tauri::command]
+#[instrumented]
 pub fn greet(name: &str) -> String {
     format!("Hello, {}! Welcome to zeami4.", name)
 }
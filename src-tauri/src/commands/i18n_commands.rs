@@ -0,0 +1,44 @@
+use super::middleware::instrumented;
+use crate::config::{storage, SettingsState};
+use crate::error::{ErrorKind, ZeamiError};
+use crate::i18n::{self, Translations};
+use tauri::State;
+
+/// Messages of `locale`, or of `ui.language` when unset, with English filling gaps
+#[tauri::command]
+#[instrumented]
+pub fn get_translations(
+    settings: State<'_, SettingsState>,
+    locale: Option<String>,
+) -> Result<Translations, ZeamiError> {
+    let locale = locale.unwrap_or_else(|| settings.current().ui.language);
+    Ok(i18n::translations(&locale))
+}
+
+/// Switch the UI language and the language of backend messages
+#[tauri::command]
+#[instrumented]
+pub fn set_locale(
+    settings: State<'_, SettingsState>,
+    locale: String,
+) -> Result<Translations, ZeamiError> {
+    if i18n::resolve(&locale).is_none() {
+        return Err(ZeamiError::new(
+            ErrorKind::InvalidInput,
+            format!("Unsupported locale '{}'", locale),
+        )
+        .with_hint(format!(
+            "Supported locales: {}",
+            i18n::supported_locales().join(", ")
+        )));
+    }
+    let mut current = settings.current();
+    current.ui.language = locale.clone();
+    storage::save_settings(&current)
+        .map_err(|e| ZeamiError::config(e).context("Failed to save settings"))?;
+    settings
+        .replace(current)
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+    i18n::set_current(&locale);
+    Ok(i18n::translations(&locale))
+}
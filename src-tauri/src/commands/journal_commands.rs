@@ -0,0 +1,25 @@
+use super::middleware::instrumented;
+use crate::error::{ErrorKind, ZeamiError};
+use crate::journal::{self, JournalEntry, JournalKind, JournalRange};
+use std::path::PathBuf;
+
+/// Journal entries of the project in `range` (everything when omitted),
+/// limited to `kinds` when given, oldest first
+#[tauri::command]
+#[instrumented]
+pub async fn query_journal(
+    project_root: PathBuf,
+    range: Option<JournalRange>,
+    kinds: Option<Vec<JournalKind>>,
+) -> Result<Vec<JournalEntry>, ZeamiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        journal::query(
+            &project_root,
+            range.unwrap_or_default(),
+            &kinds.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|e| ZeamiError::from_error(ErrorKind::Internal, e))?
+    .map_err(|e| ZeamiError::from_error(ErrorKind::Internal, e).context("Failed to read journal"))
+}
@@ -0,0 +1,70 @@
+use super::middleware::instrumented;
+use crate::config::{storage, SettingsState};
+use crate::error::{ErrorKind, ZeamiError};
+use crate::keymap::{self, Keymap};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::State;
+
+/// Every action with its chord, the reserved chords and any conflicts
+#[tauri::command]
+#[instrumented]
+pub fn get_keymap(settings: State<'_, SettingsState>) -> Keymap {
+    keymap::keymap(&settings.current().ui.keybindings, keymap::is_mac())
+}
+
+/// Bind `action` to `chord`; no chord restores the default and an empty one
+/// unbinds the action. Fails when the terminal or another action uses the chord
+#[tauri::command]
+#[instrumented]
+pub fn set_binding(
+    settings: State<'_, SettingsState>,
+    action: String,
+    chord: Option<String>,
+) -> Result<Keymap, ZeamiError> {
+    let mut keybindings = settings.current().ui.keybindings;
+    keymap::set_binding(
+        &mut keybindings,
+        &action,
+        chord.as_deref(),
+        keymap::is_mac(),
+    )
+    .map_err(|e| ZeamiError::from_error(ErrorKind::Conflict, e))?;
+    save_keybindings(&settings, keybindings)
+}
+
+/// Write the changed shortcuts to `path` as JSON
+#[tauri::command]
+#[instrumented]
+pub fn export_keymap(settings: State<'_, SettingsState>, path: PathBuf) -> Result<(), ZeamiError> {
+    keymap::export(&settings.current().ui.keybindings, &path)
+        .map_err(|e| ZeamiError::config(e).context("Failed to export keymap"))
+}
+
+/// Replace the changed shortcuts with those of the keymap file at `path`
+#[tauri::command]
+#[instrumented]
+pub fn import_keymap(
+    settings: State<'_, SettingsState>,
+    path: PathBuf,
+) -> Result<Keymap, ZeamiError> {
+    let keybindings = keymap::import(&path, keymap::is_mac()).map_err(|e| {
+        ZeamiError::from_error(ErrorKind::InvalidInput, e).context("Failed to import keymap")
+    })?;
+    save_keybindings(&settings, keybindings)
+}
+
+fn save_keybindings(
+    settings: &SettingsState,
+    keybindings: BTreeMap<String, String>,
+) -> Result<Keymap, ZeamiError> {
+    let mut current = settings.current();
+    current.ui.keybindings = keybindings;
+    storage::save_settings(&current)
+        .map_err(|e| ZeamiError::config(e).context("Failed to save settings"))?;
+    let keymap = keymap::keymap(&current.ui.keybindings, keymap::is_mac());
+    settings
+        .replace(current)
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+    Ok(keymap)
+}
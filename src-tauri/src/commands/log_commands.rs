@@ -0,0 +1,44 @@
+use super::middleware::{self, instrumented};
+use crate::logging::{CommandStats, LogEntry, LogState, LoggingSettings};
+use std::str::FromStr;
+use tauri::State;
+use tracing::Level;
+
+/// Default number of records returned by get_recent_logs
+const DEFAULT_LOG_LIMIT: usize = 200;
+
+/// Get the most recent log records at or above the given level
+#[tauri::command]
+#[instrumented]
+pub fn get_recent_logs(
+    state: State<'_, LogState>,
+    level: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<LogEntry>, String> {
+    let level = match level {
+        Some(level) => {
+            Level::from_str(&level).map_err(|_| format!("Invalid log level: {}", level))?
+        }
+        None => Level::TRACE,
+    };
+
+    Ok(state
+        .recent()
+        .recent(level, limit.unwrap_or(DEFAULT_LOG_LIMIT)))
+}
+
+/// Change log levels (global and per module) at runtime
+#[tauri::command]
+#[instrumented]
+pub fn set_log_levels(state: State<'_, LogState>, settings: LoggingSettings) -> Result<(), String> {
+    state
+        .apply(&settings)
+        .map_err(|e| format!("Failed to apply log levels: {}", e))
+}
+
+/// Call counts, durations, argument sizes and errors of every command since startup
+#[tauri::command]
+#[instrumented]
+pub fn get_command_metrics() -> Vec<CommandStats> {
+    middleware::COMMAND_METRICS.snapshot()
+}
@@ -0,0 +1,55 @@
+//! Timing, tracing and metrics of Tauri command invocations
+//!
+//! `instrument` wraps the invoke handler to note each invocation and the size
+//! of its JSON arguments; `#[instrumented]` on a command reports when it
+//! returns, how long it took and whether it failed. Both feed COMMAND_METRICS,
+//! read by `get_command_metrics`, and the `zeami4::commands` log target.
+//! Invocations are also counted for telemetry.
+
+use crate::logging::CommandMetrics;
+use crate::telemetry::Telemetry;
+use std::time::Duration;
+use tauri::{Invoke, Manager, Runtime};
+
+pub use zeami4_macros::instrumented;
+
+/// Commands slower than this are logged at info level
+const SLOW_COMMAND: Duration = Duration::from_secs(1);
+
+/// Metrics of every command since startup
+pub static COMMAND_METRICS: CommandMetrics = CommandMetrics::new();
+
+/// Wrap a `generate_handler!` invoke handler to record each invocation
+pub fn instrument<R: Runtime>(
+    handler: impl Fn(Invoke<R>) + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) + Send + Sync + 'static {
+    move |invoke| {
+        let command = invoke.message.command();
+        let arg_bytes = serde_json::to_vec(invoke.message.payload())
+            .map(|json| json.len())
+            .unwrap_or_default();
+        tracing::trace!(target: "zeami4::commands", command, arg_bytes, "Command invoked");
+        COMMAND_METRICS.invoked(command, arg_bytes, chrono::Utc::now());
+        if let Some(telemetry) = invoke.message.window().try_state::<Telemetry>() {
+            telemetry.command_invoked(command);
+        }
+        handler(invoke)
+    }
+}
+
+/// Called by `#[instrumented]` commands when they return
+pub fn finished(command: &str, duration: Duration, error: Option<String>) {
+    let duration_ms = duration.as_millis() as u64;
+    match &error {
+        Some(error) => {
+            tracing::warn!(target: "zeami4::commands", command, duration_ms, "Command failed: {}", error)
+        }
+        None if duration >= SLOW_COMMAND => {
+            tracing::info!(target: "zeami4::commands", command, duration_ms, "Slow command")
+        }
+        None => {
+            tracing::debug!(target: "zeami4::commands", command, duration_ms, "Command finished")
+        }
+    }
+    COMMAND_METRICS.finished(command, duration, error);
+}
@@ -1,5 +1,68 @@
+pub mod capability_commands;
+pub mod claude_commands;
+pub mod config_commands;
+pub mod container_commands;
+pub mod credential_commands;
+pub mod deeplink_commands;
+pub mod diagnostics_commands;
+pub mod event_commands;
+pub mod file_commands;
+pub mod frecency_commands;
+pub mod git_commands;
+pub mod github_commands;
 mod greet;
+pub mod i18n_commands;
+pub mod journal_commands;
+pub mod keymap_commands;
+pub mod log_commands;
+pub mod middleware;
+pub mod monitoring_commands;
+pub mod netwatch_commands;
+pub mod notification_commands;
+pub mod onboarding_commands;
+pub mod plugin_commands;
 pub mod pty_commands;
+pub mod scheduler_commands;
+pub mod search_commands;
+pub mod snippet_commands;
+pub mod ssh_commands;
+pub mod state_commands;
+pub mod task_commands;
+pub mod telemetry_commands;
+pub mod theme_commands;
+pub mod update_commands;
+pub mod workflow_commands;
 
+pub use capability_commands::*;
+pub use claude_commands::*;
+pub use config_commands::*;
+pub use container_commands::*;
+pub use credential_commands::*;
+pub use deeplink_commands::*;
+pub use diagnostics_commands::*;
+pub use event_commands::*;
+pub use file_commands::*;
+pub use frecency_commands::*;
+pub use git_commands::*;
+pub use github_commands::*;
 pub use greet::*;
+pub use i18n_commands::*;
+pub use journal_commands::*;
+pub use keymap_commands::*;
+pub use log_commands::*;
+pub use monitoring_commands::*;
+pub use netwatch_commands::*;
+pub use notification_commands::*;
+pub use onboarding_commands::*;
+pub use plugin_commands::*;
 pub use pty_commands::*;
+pub use scheduler_commands::*;
+pub use search_commands::*;
+pub use snippet_commands::*;
+pub use ssh_commands::*;
+pub use state_commands::*;
+pub use task_commands::*;
+pub use telemetry_commands::*;
+pub use theme_commands::*;
+pub use update_commands::*;
+pub use workflow_commands::*;
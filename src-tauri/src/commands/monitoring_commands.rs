@@ -0,0 +1,24 @@
+use super::middleware::instrumented;
+use crate::error::{ErrorKind, ZeamiError};
+use crate::monitoring::{ProcessNode, ResourceMonitor, ResourceStats};
+use tauri::State;
+
+/// Latest `resource-stats` sample; None until the first one is taken
+#[tauri::command]
+#[instrumented]
+pub fn get_resource_stats(monitor: State<'_, ResourceMonitor>) -> Option<ResourceStats> {
+    monitor.latest()
+}
+
+/// Processes running in a PTY session with their CPU and memory, as a tree
+/// None once the session's shell has exited
+#[tauri::command]
+#[instrumented]
+pub async fn get_process_tree(
+    monitor: State<'_, ResourceMonitor>,
+    session_id: String,
+) -> Result<Option<ProcessNode>, ZeamiError> {
+    monitor
+        .process_tree(&session_id)
+        .map_err(|e| ZeamiError::from_error(ErrorKind::Internal, e))
+}
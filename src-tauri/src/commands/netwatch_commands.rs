@@ -0,0 +1,64 @@
+use super::middleware::instrumented;
+use crate::config::SettingsState;
+use crate::error::{ErrorKind, ZeamiError};
+use crate::netwatch::{self, DevServer, PortForward, PortForwards, PortWatcher};
+use tauri::{AppHandle, Manager, State};
+
+/// Ports currently listened on by processes started in terminals
+#[tauri::command]
+#[instrumented]
+pub fn list_dev_servers(watcher: State<'_, PortWatcher>) -> Vec<DevServer> {
+    watcher.list()
+}
+
+/// Open http://localhost:`port` in the default browser
+#[tauri::command]
+#[instrumented]
+pub fn open_in_browser(app: AppHandle, port: u16) -> Result<(), ZeamiError> {
+    if port == 0 {
+        return Err(ZeamiError::new(
+            ErrorKind::InvalidInput,
+            "Port must not be 0",
+        ));
+    }
+    tauri::api::shell::open(&app.shell_scope(), netwatch::local_url(port), None).map_err(|e| {
+        ZeamiError::new(
+            ErrorKind::Internal,
+            format!("Failed to open browser: {}", e),
+        )
+    })
+}
+
+/// Forward `local_port` (default: the same port) to `remote_port` on a configured SSH host
+#[tauri::command]
+#[instrumented]
+pub fn start_port_forward(
+    settings: State<'_, SettingsState>,
+    forwards: State<'_, PortForwards>,
+    host: String,
+    remote_port: u16,
+    local_port: Option<u16>,
+) -> Result<PortForward, ZeamiError> {
+    let terminal = settings.current().terminal;
+    let ssh_host = terminal
+        .ssh_host(&host)
+        .ok_or_else(|| super::pty_commands::unknown_ssh_host(&host))?;
+    forwards
+        .start(ssh_host, remote_port, local_port)
+        .map_err(|e| {
+            ZeamiError::from_error(ErrorKind::Network, e).context("Failed to forward port")
+        })
+}
+
+/// Stop forwarding `local_port`; returns false when it was not forwarded
+#[tauri::command]
+#[instrumented]
+pub fn stop_port_forward(forwards: State<'_, PortForwards>, local_port: u16) -> bool {
+    forwards.stop(local_port)
+}
+
+#[tauri::command]
+#[instrumented]
+pub fn list_port_forwards(forwards: State<'_, PortForwards>) -> Vec<PortForward> {
+    forwards.list()
+}
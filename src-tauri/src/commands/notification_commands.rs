@@ -0,0 +1,13 @@
+use super::middleware::instrumented;
+use crate::notifications::{NotificationCenter, NotificationRecord};
+use tauri::State;
+
+/// Recently delivered notifications, newest first
+#[tauri::command]
+#[instrumented]
+pub fn get_notification_history(
+    notifications: State<'_, NotificationCenter>,
+    limit: Option<usize>,
+) -> Vec<NotificationRecord> {
+    notifications.history(limit)
+}
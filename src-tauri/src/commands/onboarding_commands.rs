@@ -0,0 +1,19 @@
+use super::middleware::instrumented;
+use crate::config::SettingsState;
+use crate::error::{ErrorKind, ZeamiError};
+use crate::onboarding::{self, EnvironmentReport};
+use tauri::State;
+
+/// Probe installed shells, git, gh, ssh and toolchains for the setup wizard
+#[tauri::command]
+#[instrumented]
+pub async fn detect_environment(
+    settings: State<'_, SettingsState>,
+) -> Result<EnvironmentReport, ZeamiError> {
+    let settings = settings.current();
+    tauri::async_runtime::spawn_blocking(move || onboarding::detect(&settings))
+        .await
+        .map_err(|e| {
+            ZeamiError::from_error(ErrorKind::Internal, e).context("Failed to detect environment")
+        })
+}
@@ -0,0 +1,47 @@
+use super::middleware::instrumented;
+use crate::error::ZeamiError;
+use crate::plugins::{PluginHost, PluginInfo};
+use serde_json::Value;
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+/// Installed plugins with the commands, events and tasks each one provides
+#[tauri::command]
+#[instrumented]
+pub fn list_plugins(plugins: State<'_, PluginHost>) -> Vec<PluginInfo> {
+    plugins.list()
+}
+
+/// Run a command a plugin registered, passing `args` through as JSON
+#[tauri::command]
+#[instrumented]
+pub async fn invoke_plugin_command(
+    plugins: State<'_, PluginHost>,
+    plugin: String,
+    command: String,
+    args: Option<Value>,
+    project_root: Option<PathBuf>,
+) -> Result<Value, ZeamiError> {
+    plugins
+        .invoke(
+            &plugin,
+            &command,
+            args.unwrap_or(Value::Null),
+            project_root.as_deref(),
+        )
+        .await
+        .map_err(|e| ZeamiError::plugin(e).context(format!("Plugin command {} failed", command)))
+}
+
+/// Restart every plugin, picking up added, removed and changed plugins
+#[tauri::command]
+#[instrumented]
+pub async fn reload_plugins(
+    app: AppHandle,
+    plugins: State<'_, PluginHost>,
+) -> Result<Vec<PluginInfo>, ZeamiError> {
+    plugins
+        .reload(&app)
+        .await
+        .map_err(|e| ZeamiError::plugin(e).context("Failed to load plugins"))
+}
@@ -1,8 +1,16 @@
+use super::middleware::instrumented;
+use crate::claude::suggest;
+use crate::config::SettingsState;
+use crate::error::{ErrorKind, ZeamiError};
+use crate::i18n;
+use crate::monitoring::ResourceMonitor;
+use crate::netwatch::PortWatcher;
+use crate::pty::history::{CommandHistory, HistoryEntry, HistoryFilter};
 use crate::pty::PtySession;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Mutex;
-use tauri::{State, Window};
+use tauri::{AppHandle, Manager, State, Window};
 use uuid::Uuid;
 
 /// PTY session state managed by Tauri
@@ -26,27 +34,62 @@ pub struct CreateSessionResponse {
 }
 
 /// Create a new PTY session
+/// With `host`, the session runs a shell on that configured SSH host instead
 #[tauri::command]
+#[instrumented]
 pub async fn create_pty_session(
     state: State<'_, PtyState>,
+    settings: State<'_, SettingsState>,
     window: Window,
     shell: Option<String>,
+    host: Option<String>,
     rows: u16,
     cols: u16,
-) -> Result<CreateSessionResponse, String> {
+) -> Result<CreateSessionResponse, ZeamiError> {
     // Generate unique session ID
     let session_id = Uuid::new_v4().to_string();
 
-    // Create new PTY session
-    let session = PtySession::new(shell, rows, cols, window, session_id.clone())
-        .map_err(|e| format!("Failed to create PTY session: {}", e))?;
+    let app = window.app_handle();
+    let terminal = settings.current().terminal;
+    let session = match host {
+        Some(name) => {
+            let host = terminal
+                .ssh_host(&name)
+                .ok_or_else(|| unknown_ssh_host(&name))?;
+            PtySession::ssh(host, rows, cols, window, session_id.clone())
+        }
+        // Fall back to the shell configured in settings
+        None => PtySession::new(
+            shell.or(terminal.shell),
+            rows,
+            cols,
+            window,
+            session_id.clone(),
+        ),
+    }
+    .map_err(|e| ZeamiError::pty(e).context("Failed to create PTY session"))?;
 
-    // Store session
-    let mut sessions = state
-        .sessions
-        .lock()
-        .map_err(|e| format!("Failed to lock sessions: {}", e))?;
+    store_session(&app, &state, session_id, session)
+}
 
+/// Keep a new session for the other PTY commands
+pub(crate) fn store_session(
+    app: &AppHandle,
+    state: &PtyState,
+    session_id: String,
+    session: PtySession,
+) -> Result<CreateSessionResponse, ZeamiError> {
+    let mut sessions = state.sessions.lock().map_err(|e| {
+        ZeamiError::new(
+            ErrorKind::Internal,
+            format!("Failed to lock sessions: {}", e),
+        )
+    })?;
+
+    if let Some(pid) = session.process_id() {
+        app.state::<PortWatcher>().track(&session_id, pid);
+        app.state::<ResourceMonitor>().track(&session_id, pid);
+    }
     sessions.insert(session_id.clone(), session);
 
     Ok(CreateSessionResponse { session_id })
@@ -54,63 +97,157 @@ pub async fn create_pty_session(
 
 /// Write data to a PTY session
 #[tauri::command]
+#[instrumented]
 pub async fn write_to_pty(
     state: State<'_, PtyState>,
     session_id: String,
     data: String,
-) -> Result<(), String> {
-    let sessions = state
-        .sessions
-        .lock()
-        .map_err(|e| format!("Failed to lock sessions: {}", e))?;
+) -> Result<(), ZeamiError> {
+    let sessions = state.sessions.lock().map_err(|e| {
+        ZeamiError::new(
+            ErrorKind::Internal,
+            format!("Failed to lock sessions: {}", e),
+        )
+    })?;
 
     if let Some(session) = sessions.get(&session_id) {
         session
             .write(&data)
-            .map_err(|e| format!("Failed to write to PTY: {}", e))?;
+            .map_err(|e| ZeamiError::pty(e).context("Failed to write to PTY"))?;
         Ok(())
     } else {
-        Err(format!("Session not found: {}", session_id))
+        Err(session_not_found(&session_id))
+    }
+}
+
+/// Type `command` at the prompt of a PTY session without running it
+/// Used for suggested commands; the user reviews the line and presses Enter
+#[tauri::command]
+#[instrumented]
+pub async fn insert_command(
+    state: State<'_, PtyState>,
+    session_id: String,
+    command: String,
+) -> Result<(), ZeamiError> {
+    if !suggest::insertable(&command) {
+        return Err(ZeamiError::new(
+            ErrorKind::InvalidInput,
+            "Only a single command line without control characters can be inserted",
+        ));
+    }
+    let sessions = state.sessions.lock().map_err(|e| {
+        ZeamiError::new(
+            ErrorKind::Internal,
+            format!("Failed to lock sessions: {}", e),
+        )
+    })?;
+
+    match sessions.get(&session_id) {
+        Some(session) => session
+            .write(command.trim())
+            .map_err(|e| ZeamiError::pty(e).context("Failed to insert command")),
+        None => Err(session_not_found(&session_id)),
     }
 }
 
 /// Resize a PTY session
 #[tauri::command]
+#[instrumented]
 pub async fn resize_pty(
     state: State<'_, PtyState>,
     session_id: String,
     rows: u16,
     cols: u16,
-) -> Result<(), String> {
-    let sessions = state
-        .sessions
-        .lock()
-        .map_err(|e| format!("Failed to lock sessions: {}", e))?;
+) -> Result<(), ZeamiError> {
+    let sessions = state.sessions.lock().map_err(|e| {
+        ZeamiError::new(
+            ErrorKind::Internal,
+            format!("Failed to lock sessions: {}", e),
+        )
+    })?;
 
     if let Some(session) = sessions.get(&session_id) {
         session
             .resize(rows, cols)
-            .map_err(|e| format!("Failed to resize PTY: {}", e))?;
+            .map_err(|e| ZeamiError::pty(e).context("Failed to resize PTY"))?;
         Ok(())
     } else {
-        Err(format!("Session not found: {}", session_id))
+        Err(session_not_found(&session_id))
     }
 }
 
+/// Tell the backend which session the user is looking at
+/// Long commands finishing in the other sessions, or in any session while the
+/// window is in the background, raise a notification
+#[tauri::command]
+#[instrumented]
+pub async fn focus_pty_session(
+    state: State<'_, PtyState>,
+    session_id: Option<String>,
+) -> Result<(), ZeamiError> {
+    let sessions = state.sessions.lock().map_err(|e| {
+        ZeamiError::new(
+            ErrorKind::Internal,
+            format!("Failed to lock sessions: {}", e),
+        )
+    })?;
+
+    for (id, session) in sessions.iter() {
+        session.set_focused(session_id.as_deref() == Some(id.as_str()));
+    }
+    Ok(())
+}
+
 /// Close a PTY session
 #[tauri::command]
+#[instrumented]
 pub async fn close_pty_session(
     state: State<'_, PtyState>,
+    ports: State<'_, PortWatcher>,
+    monitor: State<'_, ResourceMonitor>,
     session_id: String,
-) -> Result<(), String> {
-    let mut sessions = state
-        .sessions
-        .lock()
-        .map_err(|e| format!("Failed to lock sessions: {}", e))?;
+) -> Result<(), ZeamiError> {
+    ports.untrack(&session_id);
+    monitor.untrack(&session_id);
+    let mut sessions = state.sessions.lock().map_err(|e| {
+        ZeamiError::new(
+            ErrorKind::Internal,
+            format!("Failed to lock sessions: {}", e),
+        )
+    })?;
 
     if sessions.remove(&session_id).is_some() {
         Ok(())
     } else {
-        Err(format!("Session not found: {}", session_id))
+        Err(session_not_found(&session_id))
     }
 }
+
+/// Commands run in any session containing `query`, most recent first
+/// By default each command line appears once, with its latest run
+#[tauri::command]
+#[instrumented]
+pub async fn search_command_history(
+    history: State<'_, CommandHistory>,
+    query: String,
+    filters: Option<HistoryFilter>,
+) -> Result<Vec<HistoryEntry>, ZeamiError> {
+    history
+        .search(&query, &filters.unwrap_or_default())
+        .map_err(|e| ZeamiError::pty(e).context("Failed to search command history"))
+}
+
+fn session_not_found(session_id: &str) -> ZeamiError {
+    ZeamiError::new(
+        ErrorKind::NotFound,
+        format!("Session not found: {}", session_id),
+    )
+    .with_hint(i18n::text("error.hint.terminal_exited"))
+}
+
+pub(crate) fn unknown_ssh_host(name: &str) -> ZeamiError {
+    ZeamiError::new(
+        ErrorKind::InvalidInput,
+        format!("Unknown SSH host '{}'", name),
+    )
+}
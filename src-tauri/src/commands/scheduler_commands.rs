@@ -0,0 +1,33 @@
+use super::middleware::instrumented;
+use crate::scheduler::{ScheduledJob, Scheduler};
+use tauri::State;
+
+/// Background jobs with their intervals, last and next runs
+#[tauri::command]
+#[instrumented]
+pub fn list_scheduled_jobs(scheduler: State<'_, Scheduler>) -> Vec<ScheduledJob> {
+    scheduler.list()
+}
+
+/// Stop running a job until it is resumed, also across restarts
+#[tauri::command]
+#[instrumented]
+pub fn pause_scheduled_job(
+    scheduler: State<'_, Scheduler>,
+    name: String,
+) -> Result<ScheduledJob, String> {
+    scheduler
+        .set_paused(&name, true)
+        .map_err(|e| format!("Failed to pause job: {:#}", e))
+}
+
+#[tauri::command]
+#[instrumented]
+pub fn resume_scheduled_job(
+    scheduler: State<'_, Scheduler>,
+    name: String,
+) -> Result<ScheduledJob, String> {
+    scheduler
+        .set_paused(&name, false)
+        .map_err(|e| format!("Failed to resume job: {:#}", e))
+}
@@ -0,0 +1,48 @@
+use super::middleware::instrumented;
+use crate::error::{ErrorKind, ZeamiError};
+use crate::events::schema::v1;
+use crate::events::SEARCH_RESULT_EVENT_NAME;
+use crate::search::{self, SearchQuery, SearchSummary, Searches};
+use std::path::PathBuf;
+use tauri::{Manager, Window};
+
+/// Search the project's files for `query`, honouring .gitignore
+/// Matches arrive as `search-result` events per file; the summary is returned
+/// when the search ends. Starting another search stops this one.
+#[tauri::command]
+#[instrumented]
+pub async fn search_project(
+    window: Window,
+    project_root: PathBuf,
+    query: String,
+    regex: Option<bool>,
+    globs: Option<Vec<String>>,
+    max_results: Option<usize>,
+) -> Result<SearchSummary, ZeamiError> {
+    let search_id = window.app_handle().state::<Searches>().start();
+    let query = SearchQuery {
+        query,
+        regex: regex.unwrap_or(false),
+        globs: globs.unwrap_or_default(),
+        max_results,
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let app = window.app_handle();
+        let searches = app.state::<Searches>();
+        search::search(
+            &project_root,
+            search_id,
+            &query,
+            &|| !searches.is_current(search_id),
+            &|result| {
+                let payload: v1::SearchResult = result;
+                if let Err(e) = window.emit(SEARCH_RESULT_EVENT_NAME, payload) {
+                    tracing::error!("Failed to emit search result: {}", e);
+                }
+            },
+        )
+    })
+    .await
+    .map_err(|e| ZeamiError::from_error(ErrorKind::Internal, e))?
+    .map_err(|e| ZeamiError::from_error(ErrorKind::InvalidInput, e).context("Search failed"))
+}
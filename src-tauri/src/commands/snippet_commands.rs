@@ -0,0 +1,185 @@
+use super::middleware::instrumented;
+use super::pty_commands::PtyState;
+use crate::config::{storage, SettingsState, Snippet, TerminalSettings};
+use crate::error::{ErrorKind, ZeamiError};
+use crate::snippets::{self, ScopedSnippet, SnippetFile, SnippetScope};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// Snippets of the active profile and, with `project_root`, of the project
+#[tauri::command]
+#[instrumented]
+pub fn list_snippets(
+    settings: State<'_, SettingsState>,
+    project_root: Option<PathBuf>,
+) -> Result<Vec<ScopedSnippet>, ZeamiError> {
+    let project = project_snippets(project_root.as_deref())?;
+    Ok(snippets::merge(
+        &settings.current().terminal.snippets,
+        &project.snippets,
+    ))
+}
+
+/// Add a snippet, or replace the one with the same name in `scope`
+/// Project snippets need `project_root`
+#[tauri::command]
+#[instrumented]
+pub fn save_snippet(
+    settings: State<'_, SettingsState>,
+    snippet: Snippet,
+    scope: SnippetScope,
+    project_root: Option<PathBuf>,
+) -> Result<(), ZeamiError> {
+    snippets::validate(&snippet)
+        .map_err(|e| ZeamiError::new(ErrorKind::InvalidInput, e.to_string()))?;
+    match scope {
+        SnippetScope::Profile => update_profile(&settings, |terminal| {
+            terminal.upsert_snippet(snippet);
+            true
+        }),
+        SnippetScope::Project => {
+            let root = require_project(project_root)?;
+            let mut file = project_snippets(Some(&root))?;
+            file.upsert(snippet);
+            file.save(&root)
+                .map_err(|e| ZeamiError::config(e).context("Failed to save snippets"))
+        }
+    }
+}
+
+/// Remove a snippet from `scope`
+#[tauri::command]
+#[instrumented]
+pub fn delete_snippet(
+    settings: State<'_, SettingsState>,
+    name: String,
+    scope: SnippetScope,
+    project_root: Option<PathBuf>,
+) -> Result<(), ZeamiError> {
+    let removed = match scope {
+        SnippetScope::Profile => {
+            let mut removed = false;
+            update_profile(&settings, |terminal| {
+                removed = terminal.remove_snippet(&name);
+                removed
+            })?;
+            removed
+        }
+        SnippetScope::Project => {
+            let root = require_project(project_root)?;
+            let mut file = project_snippets(Some(&root))?;
+            let removed = file.remove(&name);
+            if removed {
+                file.save(&root)
+                    .map_err(|e| ZeamiError::config(e).context("Failed to save snippets"))?;
+            }
+            removed
+        }
+    };
+    if removed {
+        Ok(())
+    } else {
+        Err(unknown_snippet(&name))
+    }
+}
+
+/// The command of snippet `name` with its placeholders filled from `vars`
+#[tauri::command]
+#[instrumented]
+pub fn expand_snippet(
+    settings: State<'_, SettingsState>,
+    name: String,
+    vars: Option<BTreeMap<String, String>>,
+    project_root: Option<PathBuf>,
+) -> Result<String, ZeamiError> {
+    expand(&settings, &name, &vars.unwrap_or_default(), project_root)
+}
+
+/// Type snippet `name` into a PTY session
+/// It is run unless `run` is false, leaving it at the prompt to edit; a
+/// multi-line snippet runs line by line either way
+#[tauri::command]
+#[instrumented]
+pub fn insert_snippet(
+    state: State<'_, PtyState>,
+    settings: State<'_, SettingsState>,
+    session_id: String,
+    name: String,
+    vars: Option<BTreeMap<String, String>>,
+    run: Option<bool>,
+    project_root: Option<PathBuf>,
+) -> Result<(), ZeamiError> {
+    let mut text = expand(&settings, &name, &vars.unwrap_or_default(), project_root)?;
+    if run.unwrap_or(true) {
+        text.push('\r');
+    }
+    let sessions = state.sessions.lock().map_err(|e| {
+        ZeamiError::new(
+            ErrorKind::Internal,
+            format!("Failed to lock sessions: {}", e),
+        )
+    })?;
+    let session = sessions.get(&session_id).ok_or_else(|| {
+        ZeamiError::new(
+            ErrorKind::NotFound,
+            format!("Session not found: {}", session_id),
+        )
+    })?;
+    session
+        .write(&text)
+        .map_err(|e| ZeamiError::pty(e).context("Failed to write to PTY"))
+}
+
+fn expand(
+    settings: &SettingsState,
+    name: &str,
+    vars: &BTreeMap<String, String>,
+    project_root: Option<PathBuf>,
+) -> Result<String, ZeamiError> {
+    let project = project_snippets(project_root.as_deref())?;
+    let snippet = snippets::merge(&settings.current().terminal.snippets, &project.snippets)
+        .into_iter()
+        .find(|scoped| scoped.snippet.name == name)
+        .ok_or_else(|| unknown_snippet(name))?;
+    snippets::expand(&snippet.snippet.command, vars)
+        .map_err(|e| ZeamiError::new(ErrorKind::InvalidInput, e.to_string()))
+}
+
+fn project_snippets(project_root: Option<&Path>) -> Result<SnippetFile, ZeamiError> {
+    match project_root {
+        Some(root) => SnippetFile::load(root)
+            .map_err(|e| ZeamiError::config(e).context("Failed to load project snippets")),
+        None => Ok(SnippetFile::default()),
+    }
+}
+
+fn require_project(project_root: Option<PathBuf>) -> Result<PathBuf, ZeamiError> {
+    project_root.ok_or_else(|| {
+        ZeamiError::new(
+            ErrorKind::InvalidInput,
+            "Project snippets need a project_root",
+        )
+    })
+}
+
+/// Apply `change` to the active profile's terminal settings and save them if it reports a change
+fn update_profile(
+    state: &SettingsState,
+    change: impl FnOnce(&mut TerminalSettings) -> bool,
+) -> Result<(), ZeamiError> {
+    let mut settings = state.current();
+    if !change(&mut settings.terminal) {
+        return Ok(());
+    }
+    storage::save_settings(&settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to save settings"))?;
+    state
+        .replace(settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+    Ok(())
+}
+
+fn unknown_snippet(name: &str) -> ZeamiError {
+    ZeamiError::new(ErrorKind::NotFound, format!("Unknown snippet '{}'", name))
+}
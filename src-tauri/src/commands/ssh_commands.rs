@@ -0,0 +1,70 @@
+use super::middleware::instrumented;
+use super::pty_commands::unknown_ssh_host;
+use crate::config::{keychain, storage, SettingsState, SshAuth, SshHost};
+use crate::error::{ErrorKind, ZeamiError};
+use crate::pty::ssh;
+use tauri::State;
+
+/// Configured SSH hosts, for `create_pty_session`'s `host`
+#[tauri::command]
+#[instrumented]
+pub fn list_ssh_hosts(settings: State<'_, SettingsState>) -> Vec<SshHost> {
+    settings.current().terminal.ssh_hosts
+}
+
+/// Add an SSH host, or replace the one with the same name
+/// `private_key` is stored in the keychain for key auth; it may be omitted
+/// when updating a host whose key is already stored
+#[tauri::command]
+#[instrumented]
+pub fn add_ssh_host(
+    state: State<'_, SettingsState>,
+    host: SshHost,
+    private_key: Option<String>,
+) -> Result<(), ZeamiError> {
+    ssh::validate(&host).map_err(|e| ZeamiError::new(ErrorKind::InvalidInput, e.to_string()))?;
+
+    match (host.auth, private_key) {
+        (SshAuth::Key, Some(key)) => keychain::store_ssh_key(&host.name, &key)
+            .map_err(|e| ZeamiError::config(e).context("Failed to store secret"))?,
+        (SshAuth::Key, None) => {
+            let stored = keychain::retrieve_ssh_key(&host.name)
+                .map_err(|e| ZeamiError::config(e).context("Failed to read secret"))?;
+            if stored.is_none() {
+                return Err(ZeamiError::new(
+                    ErrorKind::InvalidInput,
+                    format!("SSH host {} uses key auth but no key was given", host.name),
+                ));
+            }
+        }
+        (SshAuth::Agent, _) => keychain::delete_ssh_key(&host.name)
+            .map_err(|e| ZeamiError::config(e).context("Failed to delete secret"))?,
+    }
+
+    let mut settings = state.current();
+    settings.terminal.upsert_ssh_host(host);
+    storage::save_settings(&settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to save settings"))?;
+    state
+        .replace(settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+    Ok(())
+}
+
+/// Remove an SSH host and its stored key; open sessions on it stay open
+#[tauri::command]
+#[instrumented]
+pub fn remove_ssh_host(state: State<'_, SettingsState>, name: String) -> Result<(), ZeamiError> {
+    let mut settings = state.current();
+    if !settings.terminal.remove_ssh_host(&name) {
+        return Err(unknown_ssh_host(&name));
+    }
+    storage::save_settings(&settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to save settings"))?;
+    keychain::delete_ssh_key(&name)
+        .map_err(|e| ZeamiError::config(e).context("Failed to delete secret"))?;
+    state
+        .replace(settings)
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use super::github_commands::project_settings;
+use super::middleware::instrumented;
+use super::pty_commands::PtyState;
+use crate::config::SettingsState;
+use crate::error::{ErrorKind, ZeamiError};
+use crate::state::{self, Layout, ProjectLayout, ProjectSnapshot};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+/// Issue links, profile, session layout and sync times of a project
+#[tauri::command]
+#[instrumented]
+pub fn get_project_state(
+    settings: State<'_, SettingsState>,
+    project_root: PathBuf,
+) -> Result<ProjectSnapshot, String> {
+    let git = project_settings(&settings, &project_root)?.git;
+    let current =
+        state::load(&project_root).map_err(|e| format!("Failed to load project state: {:#}", e))?;
+    Ok(ProjectSnapshot::new(&project_root, current, &git))
+}
+
+/// Merge `patch` (JSON merge patch; null removes a field) into the project state
+/// Emits `state-changed`
+#[tauri::command]
+#[instrumented]
+pub fn update_project_state(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    project_root: PathBuf,
+    patch: serde_json::Value,
+) -> Result<ProjectSnapshot, String> {
+    let git = project_settings(&settings, &project_root)?.git;
+    let updated = state::update(&project_root, |current| state::apply_patch(current, patch))
+        .map_err(|e| format!("Failed to update project state: {:#}", e))?;
+    let snapshot = ProjectSnapshot::new(&project_root, updated, &git);
+    state::emit_changed(&app, snapshot.clone());
+    Ok(snapshot)
+}
+
+/// Tabs and panes of a project, with the panes whose session has ended
+#[tauri::command]
+#[instrumented]
+pub fn get_layout(
+    pty: State<'_, PtyState>,
+    project_root: PathBuf,
+) -> Result<ProjectLayout, ZeamiError> {
+    let current = state::load(&project_root)
+        .map_err(|e| ZeamiError::config(e).context("Failed to load project state"))?;
+    Ok(project_layout(&pty, current.layout.unwrap_or_default()))
+}
+
+/// Replace the tabs and panes of a project
+/// Emits `state-changed`
+#[tauri::command]
+#[instrumented]
+pub fn update_layout(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    pty: State<'_, PtyState>,
+    project_root: PathBuf,
+    layout: Layout,
+) -> Result<ProjectLayout, ZeamiError> {
+    layout.validate().map_err(|e| {
+        ZeamiError::from_error(ErrorKind::InvalidInput, e).context("Invalid layout")
+    })?;
+    let git = project_settings(&settings, &project_root)?.git;
+    let updated = state::update(&project_root, |current| {
+        current.layout = Some(layout.clone());
+        Ok(())
+    })
+    .map_err(|e| ZeamiError::config(e).context("Failed to save layout"))?;
+    state::emit_changed(&app, ProjectSnapshot::new(&project_root, updated, &git));
+    Ok(project_layout(&pty, layout))
+}
+
+fn project_layout(pty: &PtyState, layout: Layout) -> ProjectLayout {
+    let live: HashSet<String> = pty
+        .sessions
+        .lock()
+        .map(|sessions| sessions.keys().cloned().collect())
+        .unwrap_or_default();
+    ProjectLayout {
+        detached_panes: layout.detached_panes(&live),
+        layout,
+    }
+}
@@ -0,0 +1,128 @@
+use super::middleware::instrumented;
+use crate::capabilities::{Capability, CapabilityRegistry};
+use crate::error::{ErrorKind, ZeamiError};
+use crate::tasks::{
+    FilterRule, FilterTest, TaskInfo, TaskRunner, TaskTrigger, TaskWatcher, WatcherConfig,
+};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+/// Tasks of .zeami/tasks.toml with their latest status
+#[tauri::command]
+#[instrumented]
+pub fn list_tasks(
+    runner: State<'_, TaskRunner>,
+    project_root: PathBuf,
+) -> Result<Vec<TaskInfo>, ZeamiError> {
+    runner
+        .list(&project_root)
+        .map_err(|e| ZeamiError::task(e).context("Failed to list tasks"))
+}
+
+/// Run a task after its dependencies; progress arrives as `task-status` events
+#[tauri::command]
+#[instrumented]
+pub fn run_task(
+    app: AppHandle,
+    runner: State<'_, TaskRunner>,
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+    name: String,
+) -> Result<(), ZeamiError> {
+    capabilities
+        .require(&app, &project_root, Capability::RunTasks, "run_task")
+        .map_err(ZeamiError::task)?;
+    runner
+        .start(&app, &project_root, &name, TaskTrigger::Manual)
+        .map_err(|e| ZeamiError::task(e).context(format!("Failed to run task {}", name)))
+}
+
+/// Stop a queued or running task; returns false when it is not active
+#[tauri::command]
+#[instrumented]
+pub fn stop_task(
+    runner: State<'_, TaskRunner>,
+    project_root: PathBuf,
+    name: String,
+) -> Result<bool, ZeamiError> {
+    runner
+        .stop(&project_root, &name)
+        .map_err(|e| ZeamiError::task(e).context(format!("Failed to stop task {}", name)))
+}
+
+/// Rerun tasks with `watch` globs when their files change in `project_root` (None stops)
+#[tauri::command]
+#[instrumented]
+pub fn watch_tasks(watcher: State<'_, TaskWatcher>, project_root: Option<PathBuf>) {
+    watcher.watch(project_root);
+}
+
+/// Watcher ignore rules of .zeami/watcher-config.toml
+#[tauri::command]
+#[instrumented]
+pub fn list_filter_rules(project_root: PathBuf) -> Result<Vec<FilterRule>, ZeamiError> {
+    WatcherConfig::load(&project_root)
+        .map(|config| config.rules)
+        .map_err(|e| ZeamiError::task(e).context("Failed to load watcher rules"))
+}
+
+/// Ignore paths matching `rule` from the next watcher scan on
+#[tauri::command]
+#[instrumented]
+pub fn add_filter_rule(
+    watcher: State<'_, TaskWatcher>,
+    project_root: PathBuf,
+    rule: FilterRule,
+) -> Result<Vec<FilterRule>, ZeamiError> {
+    let mut config = WatcherConfig::load(&project_root)
+        .map_err(|e| ZeamiError::task(e).context("Failed to load watcher rules"))?;
+    config
+        .add(rule)
+        .map_err(|e| ZeamiError::from_error(ErrorKind::InvalidInput, e))?;
+    save_filter_rules(&watcher, &project_root, config)
+}
+
+/// Stop ignoring paths for the rules with `pattern`
+#[tauri::command]
+#[instrumented]
+pub fn remove_filter_rule(
+    watcher: State<'_, TaskWatcher>,
+    project_root: PathBuf,
+    pattern: String,
+) -> Result<Vec<FilterRule>, ZeamiError> {
+    let mut config = WatcherConfig::load(&project_root)
+        .map_err(|e| ZeamiError::task(e).context("Failed to load watcher rules"))?;
+    if !config.remove(&pattern) {
+        return Err(ZeamiError::new(
+            ErrorKind::NotFound,
+            format!("No watcher rule for {}", pattern),
+        ));
+    }
+    save_filter_rules(&watcher, &project_root, config)
+}
+
+/// Whether the watcher ignores `path` (relative to the project root) and why
+#[tauri::command]
+#[instrumented]
+pub fn test_filter_rule(project_root: PathBuf, path: String) -> Result<FilterTest, ZeamiError> {
+    let filter = WatcherConfig::load(&project_root)
+        .and_then(|config| config.compile())
+        .map_err(|e| ZeamiError::task(e).context("Failed to load watcher rules"))?;
+    let relative = PathBuf::from(path.trim_start_matches(['/', '\\']));
+    let is_dir = project_root.join(&relative).is_dir();
+    Ok(filter.test(&relative, is_dir))
+}
+
+fn save_filter_rules(
+    watcher: &TaskWatcher,
+    project_root: &Path,
+    config: WatcherConfig,
+) -> Result<Vec<FilterRule>, ZeamiError> {
+    config
+        .save(project_root)
+        .map_err(|e| ZeamiError::task(e).context("Failed to save watcher rules"))?;
+    if watcher.project().as_deref() == Some(project_root) {
+        watcher.reload();
+    }
+    Ok(config.rules)
+}
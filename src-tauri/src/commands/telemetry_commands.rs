@@ -0,0 +1,33 @@
+use super::middleware::instrumented;
+use crate::config::SettingsState;
+use crate::error::ZeamiError;
+use crate::telemetry::{Telemetry, TelemetryReport};
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+/// Usage counted so far and exactly what the next upload would send
+#[tauri::command]
+#[instrumented]
+pub fn get_telemetry_report(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    telemetry: State<'_, Telemetry>,
+) -> Result<TelemetryReport, ZeamiError> {
+    Ok(telemetry.report(
+        &settings.current().telemetry,
+        &app.package_info().version.to_string(),
+    ))
+}
+
+/// Write the pending telemetry payload to `path` as JSON
+#[tauri::command]
+#[instrumented]
+pub fn export_telemetry(
+    app: AppHandle,
+    telemetry: State<'_, Telemetry>,
+    path: PathBuf,
+) -> Result<(), ZeamiError> {
+    telemetry
+        .export(&path, &app.package_info().version.to_string())
+        .map_err(|e| ZeamiError::config(e).context("Failed to export telemetry"))
+}
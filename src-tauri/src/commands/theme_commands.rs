@@ -0,0 +1,49 @@
+use super::middleware::instrumented;
+use crate::config::SettingsState;
+use crate::error::{ErrorKind, ZeamiError};
+use crate::themes::{self, Palette, ThemeInfo};
+use std::path::PathBuf;
+use tauri::State;
+
+/// Built-in and user terminal themes, with contrast warnings
+#[tauri::command]
+#[instrumented]
+pub fn list_themes() -> Result<Vec<ThemeInfo>, ZeamiError> {
+    let dir = themes::themes_dir().map_err(ZeamiError::config)?;
+    Ok(themes::list(&dir))
+}
+
+/// The full xterm.js palette of theme `name`, or of `terminal.theme` when unset
+#[tauri::command]
+#[instrumented]
+pub fn get_theme_palette(
+    settings: State<'_, SettingsState>,
+    name: Option<String>,
+) -> Result<Palette, ZeamiError> {
+    let name = name.unwrap_or_else(|| settings.current().terminal.theme);
+    let dir = themes::themes_dir().map_err(ZeamiError::config)?;
+    themes::palette(&dir, &name).map_err(|e| ZeamiError::from_error(ErrorKind::NotFound, e))
+}
+
+/// Convert an iTerm2 `.itermcolors` or Windows Terminal JSON file into user
+/// themes; returns the imported themes
+#[tauri::command]
+#[instrumented]
+pub fn import_theme(path: PathBuf) -> Result<Vec<ThemeInfo>, ZeamiError> {
+    let imported = themes::import::from_file(&path)
+        .map_err(|e| ZeamiError::from_error(ErrorKind::InvalidInput, e))?;
+    let dir = themes::themes_dir().map_err(ZeamiError::config)?;
+    imported
+        .into_iter()
+        .map(|theme| {
+            let path = themes::save(&dir, &theme)
+                .map_err(|e| ZeamiError::config(e).context("Failed to save theme"))?;
+            Ok(ThemeInfo {
+                warnings: themes::warnings(&theme.palette),
+                name: theme.name,
+                builtin: false,
+                path: Some(path),
+            })
+        })
+        .collect()
+}
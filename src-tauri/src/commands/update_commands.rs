@@ -0,0 +1,78 @@
+use super::middleware::instrumented;
+use crate::config::{storage, SettingsState, UpdateChannel};
+use crate::error::{ErrorKind, ZeamiError};
+use crate::updater::{self, DownloadedUpdate, UpdateCheck, Updater};
+use chrono::{DateTime, Utc};
+use tauri::{AppHandle, State};
+
+/// Look for a newer release on `channel`, or on the configured channel
+#[tauri::command]
+#[instrumented]
+pub async fn check_for_updates(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    updater: State<'_, Updater>,
+    channel: Option<UpdateChannel>,
+) -> Result<UpdateCheck, ZeamiError> {
+    let settings = settings.current().updates;
+    let channel = channel.unwrap_or(settings.channel);
+    updater
+        .check(&settings, channel, &updater::current_version(&app))
+        .await
+        .map_err(|e| {
+            ZeamiError::from_error(ErrorKind::Network, e).context("Failed to check for updates")
+        })
+}
+
+/// Download the newest release of the configured channel
+/// `update-downloaded` is emitted when it is ready
+#[tauri::command]
+#[instrumented]
+pub async fn download_update(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    updater: State<'_, Updater>,
+) -> Result<DownloadedUpdate, ZeamiError> {
+    let settings = settings.current().updates;
+    let check = updater
+        .check(&settings, settings.channel, &updater::current_version(&app))
+        .await
+        .map_err(|e| {
+            ZeamiError::from_error(ErrorKind::Network, e).context("Failed to check for updates")
+        })?;
+    let update = check
+        .update
+        .ok_or_else(|| ZeamiError::new(ErrorKind::NotFound, "Zeami is up to date"))?;
+    updater.download(&app, &update).await.map_err(|e| {
+        ZeamiError::from_error(ErrorKind::Network, e).context("Failed to download the update")
+    })
+}
+
+/// Pause background update checks for `hours`; 0 resumes them
+#[tauri::command]
+#[instrumented]
+pub fn defer_update(
+    updater: State<'_, Updater>,
+    hours: u64,
+) -> Result<Option<DateTime<Utc>>, ZeamiError> {
+    updater
+        .defer(hours)
+        .map_err(|e| ZeamiError::config(e).context("Failed to defer updates"))
+}
+
+/// Follow `channel` from now on
+#[tauri::command]
+#[instrumented]
+pub fn set_update_channel(
+    settings: State<'_, SettingsState>,
+    channel: UpdateChannel,
+) -> Result<(), ZeamiError> {
+    let mut current = settings.current();
+    current.updates.channel = channel;
+    storage::save_settings(&current)
+        .map_err(|e| ZeamiError::config(e).context("Failed to save settings"))?;
+    settings
+        .replace(current)
+        .map_err(|e| ZeamiError::config(e).context("Failed to apply settings"))?;
+    Ok(())
+}
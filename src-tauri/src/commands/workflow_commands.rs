@@ -0,0 +1,204 @@
+use super::github_commands::{connect_with, project_settings};
+use super::middleware::instrumented;
+use crate::capabilities::{Capability, CapabilityRegistry};
+use crate::config::SettingsState;
+use crate::error::{ErrorKind, ZeamiError};
+use crate::git::snapshot::{self, AutoCommit, RestoreResult};
+use crate::github::GitHubState;
+use crate::i18n;
+use crate::notifications::{self, NotificationCategory};
+use crate::scheduler::Scheduler;
+use crate::workflow::auto_commit;
+use crate::workflow::build_report::BuildStatus;
+use crate::workflow::complete::{self, CompleteOptions, IssueCompletion};
+use crate::workflow::dod::{self, DodStatus};
+use crate::workflow::progress_sync::{self, ProgressSync};
+use crate::workflow::test_runs::{self, TestRun};
+use crate::workflow::{AutoCommitter, Builder, ProgressSyncer};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+/// Take WIP commits of `project_root` per WorkflowSettings::auto_commit (None stops)
+#[tauri::command]
+#[instrumented]
+pub fn watch_auto_commit(
+    app: AppHandle,
+    committer: State<'_, AutoCommitter>,
+    scheduler: State<'_, Scheduler>,
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: Option<PathBuf>,
+) -> Result<(), ZeamiError> {
+    if let Some(root) = &project_root {
+        capabilities
+            .require(&app, root, Capability::Commit, "watch_auto_commit")
+            .map_err(ZeamiError::git)?;
+    }
+    committer.watch(project_root);
+    scheduler.trigger(auto_commit::AUTO_COMMIT_JOB);
+    Ok(())
+}
+
+/// WIP commits of the checked-out branch, newest first
+#[tauri::command]
+#[instrumented]
+pub fn list_auto_commits(project_root: PathBuf) -> Result<Vec<AutoCommit>, String> {
+    snapshot::list_auto_commits(&project_root)
+        .map_err(|e| format!("Failed to list auto-commits: {:#}", e))
+}
+
+/// Restore the working tree from a WIP commit, snapshotting current changes first
+#[tauri::command]
+#[instrumented]
+pub fn restore_auto_commit(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+    id: String,
+) -> Result<RestoreResult, ZeamiError> {
+    capabilities
+        .require(
+            &app,
+            &project_root,
+            Capability::Commit,
+            "restore_auto_commit",
+        )
+        .map_err(ZeamiError::git)?;
+    let message = settings.current().workflow.auto_commit_message;
+    snapshot::restore_auto_commit(&project_root, &id, &message)
+        .map_err(|e| ZeamiError::git(e).context("Failed to restore auto-commit"))
+}
+
+/// Run the project's test command and return the parsed result
+///
+/// A passing run starts a build in the background when auto_build is on.
+#[tauri::command]
+#[instrumented]
+pub async fn run_tests(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+) -> Result<TestRun, ZeamiError> {
+    capabilities
+        .require(&app, &project_root, Capability::RunTasks, "run_tests")
+        .map_err(ZeamiError::task)?;
+    let workflow = project_settings(&settings, &project_root)?.workflow;
+    let run = {
+        let (project_root, workflow) = (project_root.clone(), workflow.clone());
+        tauri::async_runtime::spawn_blocking(move || test_runs::run_tests(&project_root, &workflow))
+    }
+    .await
+    .map_err(|e| ZeamiError::from_error(ErrorKind::Internal, e).context("Failed to run tests"))?
+    .map_err(|e| ZeamiError::task(e).context("Failed to run tests"))?;
+
+    let title = if run.passed() {
+        "notification.tests_passed.title"
+    } else {
+        "notification.tests_failed.title"
+    };
+    notifications::notify(
+        &app,
+        NotificationCategory::TestFinished,
+        i18n::text(title),
+        run.summary(),
+    );
+
+    if run.passed() && workflow.auto_build {
+        tauri::async_runtime::spawn(async move {
+            let builder = app.state::<Builder>();
+            if let Err(e) = builder.run(&app, project_root, workflow).await {
+                tracing::warn!("Auto-build skipped: {:#}", e);
+            }
+        });
+    }
+    Ok(run)
+}
+
+/// Most recent test run of the project, None before the first one
+#[tauri::command]
+#[instrumented]
+pub fn get_last_test_run(project_root: PathBuf) -> Result<Option<TestRun>, String> {
+    test_runs::last_run(&project_root).map_err(|e| format!("Failed to read test runs: {:#}", e))
+}
+
+/// Run the project's build command; progress is also emitted as `build-status`
+#[tauri::command]
+#[instrumented]
+pub async fn run_build(
+    app: AppHandle,
+    builder: State<'_, Builder>,
+    settings: State<'_, SettingsState>,
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+) -> Result<BuildStatus, ZeamiError> {
+    capabilities
+        .require(&app, &project_root, Capability::RunTasks, "run_build")
+        .map_err(ZeamiError::task)?;
+    let workflow = project_settings(&settings, &project_root)?.workflow;
+    builder
+        .run(&app, project_root, workflow)
+        .await
+        .map_err(|e| ZeamiError::task(e).context("Failed to run build"))
+}
+
+/// Refresh the linked issue's progress comment per WorkflowSettings::auto_sync_progress (None stops)
+#[tauri::command]
+#[instrumented]
+pub fn watch_progress_sync(
+    syncer: State<'_, ProgressSyncer>,
+    scheduler: State<'_, Scheduler>,
+    project_root: Option<PathBuf>,
+) {
+    syncer.watch(project_root);
+    scheduler.trigger(progress_sync::PROGRESS_SYNC_JOB);
+}
+
+/// Post or update the progress comment on the issue linked to the checked-out branch
+#[tauri::command]
+#[instrumented]
+pub async fn sync_progress_now(
+    app: AppHandle,
+    project_root: PathBuf,
+) -> Result<ProgressSync, String> {
+    progress_sync::sync_progress(&app, &project_root, true)
+        .await
+        .map_err(|e| format!("Failed to sync progress: {:#}", e))?
+        .ok_or_else(|| "The checked-out branch is not linked to an issue".to_string())
+}
+
+/// Definition of Done of the issue linked to the checked-out branch
+///
+/// Automated items that pass are ticked on the issue as a side effect.
+#[tauri::command]
+#[instrumented]
+pub async fn get_dod_status(
+    github: State<'_, GitHubState>,
+    settings: State<'_, SettingsState>,
+    project_root: PathBuf,
+) -> Result<DodStatus, String> {
+    let settings = project_settings(&settings, &project_root)?;
+    let (client, repo) = connect_with(&github, &settings.github)?;
+    dod::evaluate(&client, &repo, &project_root, &settings)
+        .await
+        .map_err(|e| format!("Failed to check the Definition of Done: {:#}", e))?
+        .ok_or_else(|| "The checked-out branch is not linked to an issue".to_string())
+}
+
+/// Verify the DoD, run the tests, push and open a pull request for the linked issue
+/// Each step is reported through `workflow-step` events
+#[tauri::command]
+#[instrumented]
+pub async fn complete_issue(
+    app: AppHandle,
+    capabilities: State<'_, CapabilityRegistry>,
+    project_root: PathBuf,
+    options: Option<CompleteOptions>,
+) -> Result<IssueCompletion, ZeamiError> {
+    capabilities
+        .require(&app, &project_root, Capability::Push, "complete_issue")
+        .map_err(ZeamiError::github)?;
+    complete::complete_issue(&app, &project_root, options.unwrap_or_default())
+        .await
+        .map_err(|e| ZeamiError::github(e).context("Failed to complete issue"))
+}
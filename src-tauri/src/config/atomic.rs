@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// Replace `path` with `data` so readers see either the old or the new contents
+///
+/// The data is written to a sibling temp file, fsynced, and renamed over the
+/// target, so a crash mid-write never leaves a truncated file behind.
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    write(path, data, false)
+}
+
+/// Like write_atomic, for a file only the current user may read
+///
+/// The temp file is created with mode 0600, so the data is never readable by
+/// others, not even between the write and a chmod.
+pub fn write_atomic_private(path: &Path, data: &[u8]) -> Result<()> {
+    write(path, data, true)
+}
+
+fn write(path: &Path, data: &[u8], private: bool) -> Result<()> {
+    let parent = path
+        .parent()
+        .with_context(|| format!("{:?} has no parent directory", path))?;
+    fs::create_dir_all(parent)?;
+
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("{:?} has no file name", path))?;
+    let tmp_path = parent.join(format!(".{}.tmp", file_name.to_string_lossy()));
+
+    {
+        let mut options = OpenOptions::new();
+        options.write(true);
+        if private {
+            // A temp file left by a crash may have other permissions
+            let _ = fs::remove_file(&tmp_path);
+            options.create_new(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+        } else {
+            options.create(true).truncate(true);
+        }
+        let mut tmp = options
+            .open(&tmp_path)
+            .with_context(|| format!("Failed to create temp file {:?}", tmp_path))?;
+        tmp.write_all(data)?;
+        tmp.sync_all()
+            .with_context(|| format!("Failed to sync {:?}", tmp_path))?;
+    }
+
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to move {:?} into place", tmp_path))?;
+
+    // Persist the rename itself
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(parent) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_replaces_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_private_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.key");
+        fs::write(&path, b"old").unwrap();
+
+        write_atomic_private(&path, b"key").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"key");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,39 @@
+use super::{storage, SettingsState};
+use crate::scheduler::{Job, JobFuture};
+use anyhow::Context;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Scheduler name of the backup job
+pub const BACKUP_JOB: &str = "auto_backup";
+
+/// How often the scheduler checks whether a daily backup is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Automatic daily backups for as long as the app is open
+///
+/// Due-ness is judged from the newest backup on disk, so restarting the app
+/// neither skips nor duplicates the daily backup.
+pub struct AutoBackup;
+
+impl Job for AutoBackup {
+    fn interval(&self, app: &AppHandle) -> Option<Duration> {
+        let policy = app.state::<SettingsState>().current().backup;
+        policy.auto_backup.then_some(CHECK_INTERVAL)
+    }
+
+    fn run(&self, app: &AppHandle) -> JobFuture {
+        let policy = app.state::<SettingsState>().current().backup;
+        Box::pin(async move {
+            let created = tauri::async_runtime::spawn_blocking(move || {
+                storage::run_scheduled_backup(&policy)
+            })
+            .await?
+            .context("Scheduled settings backup failed")?;
+            if let Some(path) = created {
+                tracing::info!(?path, "Created scheduled settings backup");
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// How long after the newest backup a scheduled backup is due
+pub const AUTO_BACKUP_INTERVAL: chrono::Duration = chrono::Duration::hours(24);
+
+const COMPRESSED_EXTENSION: &str = "gz";
+
+/// Backup retention and scheduling
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct BackupSettings {
+    /// Number of backups kept before the oldest are removed
+    pub max_count: usize,
+    /// Backups older than this many days are removed; 0 keeps them regardless of age
+    pub max_age_days: u32,
+    /// Back up config.json once a day in addition to on every save
+    pub auto_backup: bool,
+    /// Write new backups gzip-compressed
+    pub compress: bool,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            max_count: 5,
+            max_age_days: 30,
+            auto_backup: true,
+            compress: false,
+        }
+    }
+}
+
+/// A config backup on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub created_at: String,
+    pub compressed: bool,
+}
+
+/// Copy `config_path` into `backup_dir` as config-<timestamp>.<json|toml>[.gz] and apply retention
+pub fn create_backup(
+    config_path: &Path,
+    backup_dir: &Path,
+    policy: &BackupSettings,
+) -> Result<PathBuf> {
+    fs::create_dir_all(backup_dir)
+        .with_context(|| format!("Failed to create backup directory {:?}", backup_dir))?;
+
+    let timestamp = chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f");
+    let extension = config_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("json");
+    let mut file_name = format!("config-{}.{}", timestamp, extension);
+    if policy.compress {
+        file_name = format!("{}.{}", file_name, COMPRESSED_EXTENSION);
+    }
+    let backup_path = backup_dir.join(file_name);
+
+    let content =
+        fs::read(config_path).with_context(|| format!("Failed to read {:?}", config_path))?;
+    let data = if policy.compress {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&content)?;
+        encoder.finish()?
+    } else {
+        content
+    };
+    fs::write(&backup_path, data)
+        .with_context(|| format!("Failed to back up config to {:?}", backup_path))?;
+
+    apply_retention(backup_dir, policy)?;
+
+    Ok(backup_path)
+}
+
+/// Contents of a backup, decompressed if needed
+pub fn read_backup(path: &Path) -> Result<Vec<u8>> {
+    let data = fs::read(path).with_context(|| format!("Failed to read backup {:?}", path))?;
+    if !is_compressed(path) {
+        return Ok(data);
+    }
+
+    let mut content = Vec::new();
+    GzDecoder::new(data.as_slice())
+        .read_to_end(&mut content)
+        .with_context(|| format!("Failed to decompress backup {:?}", path))?;
+    Ok(content)
+}
+
+/// List backups, newest first
+pub fn list_backups(backup_dir: &Path) -> Result<Vec<BackupInfo>> {
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(backup_dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !file_name.starts_with("config-") {
+            continue;
+        }
+
+        let metadata = entry.metadata()?;
+        let created_at = metadata
+            .modified()
+            .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339())
+            .unwrap_or_default();
+        let path = entry.path();
+
+        backups.push(BackupInfo {
+            file_name,
+            compressed: is_compressed(&path),
+            path,
+            size: metadata.len(),
+            created_at,
+        });
+    }
+
+    // Timestamped names sort chronologically
+    backups.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    Ok(backups)
+}
+
+/// Remove backups beyond `max_count` or older than `max_age_days`
+/// The newest backup is always kept so there is something to recover from
+pub fn apply_retention(backup_dir: &Path, policy: &BackupSettings) -> Result<()> {
+    let max_age = chrono::Duration::days(i64::from(policy.max_age_days));
+    let now = chrono::Utc::now();
+
+    for (index, backup) in list_backups(backup_dir)?.into_iter().enumerate() {
+        let expired = policy.max_age_days > 0
+            && index > 0
+            && backup_time(&backup).is_some_and(|created| now - created > max_age);
+        if index >= policy.max_count.max(1) || expired {
+            fs::remove_file(&backup.path)
+                .with_context(|| format!("Failed to remove old backup {:?}", backup.path))?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether a scheduled backup should run: none exists or the newest is a day old
+pub fn backup_due(backup_dir: &Path) -> Result<bool> {
+    let newest = list_backups(backup_dir)?.into_iter().next();
+    Ok(match newest.as_ref().and_then(backup_time) {
+        Some(created) => chrono::Utc::now() - created >= AUTO_BACKUP_INTERVAL,
+        None => true,
+    })
+}
+
+fn backup_time(backup: &BackupInfo) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(&backup.created_at)
+        .ok()
+        .map(|time| time.with_timezone(&chrono::Utc))
+}
+
+fn is_compressed(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some(COMPRESSED_EXTENSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backups_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.json");
+        let backups = dir.path().join("backups");
+        fs::write(&config, "{}").unwrap();
+        let policy = BackupSettings::default();
+
+        for _ in 0..policy.max_count + 2 {
+            create_backup(&config, &backups, &policy).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        assert_eq!(list_backups(&backups).unwrap().len(), policy.max_count);
+        assert!(!backup_due(&backups).unwrap());
+    }
+
+    #[test]
+    fn test_compressed_backup_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.json");
+        let backups = dir.path().join("backups");
+        fs::write(&config, r#"{"version":1}"#).unwrap();
+        let policy = BackupSettings {
+            compress: true,
+            ..BackupSettings::default()
+        };
+
+        let path = create_backup(&config, &backups, &policy).unwrap();
+
+        assert!(list_backups(&backups).unwrap()[0].compressed);
+        assert_ne!(fs::read(&path).unwrap(), b"{\"version\":1}");
+        assert_eq!(read_backup(&path).unwrap(), b"{\"version\":1}");
+    }
+}
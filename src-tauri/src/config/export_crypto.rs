@@ -0,0 +1,88 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, bail, Result};
+use argon2::Argon2;
+use rand::RngCore;
+
+/// File extension for passphrase-encrypted settings exports
+pub const ENCRYPTED_EXPORT_EXTENSION: &str = "zeami-export";
+
+/// Header identifying an encrypted export (format version 1)
+const MAGIC: &[u8; 8] = b"ZEAMIEX1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Whether `data` is an encrypted export rather than plain JSON
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Encrypt with AES-256-GCM under a key derived from `passphrase` with Argon2id
+/// Layout: MAGIC | salt | nonce | ciphertext
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if passphrase.is_empty() {
+        bail!("Passphrase must not be empty");
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, &salt)?);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| anyhow!("Failed to encrypt export"))?;
+
+    let mut data = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&salt);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+    Ok(data)
+}
+
+/// Decrypt data produced by `encrypt`
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if !is_encrypted(data) || data.len() < MAGIC.len() + SALT_LEN + NONCE_LEN {
+        bail!("Not an encrypted settings export");
+    }
+
+    let rest = &data[MAGIC.len()..];
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, salt)?);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Wrong passphrase or damaged export file"))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key<Aes256Gcm>> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive key: {}", e))?;
+    Ok(*Key::<Aes256Gcm>::from_slice(&key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let data = encrypt(b"{\"settings\":{}}", "correct horse").unwrap();
+
+        assert!(is_encrypted(&data));
+        assert_eq!(
+            decrypt(&data, "correct horse").unwrap(),
+            b"{\"settings\":{}}"
+        );
+        assert!(decrypt(&data, "wrong").is_err());
+    }
+
+    #[test]
+    fn test_plain_json_is_not_encrypted() {
+        assert!(!is_encrypted(b"{}"));
+        assert!(encrypt(b"{}", "").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+/// On-disk format of a config file, chosen by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Format of `path`; compressed backups (`.json.gz`, `.toml.gz`) use the inner extension
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => ConfigFormat::Toml,
+            Some("gz") => path
+                .file_stem()
+                .map(|stem| Self::from_path(Path::new(stem)))
+                .unwrap_or(ConfigFormat::Json),
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Toml => "toml",
+        }
+    }
+
+    pub fn parse(&self, content: &str) -> Result<Value> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(content).context("Invalid JSON"),
+            ConfigFormat::Toml => toml::from_str(content).context("Invalid TOML"),
+        }
+    }
+
+    /// TOML has no null, so None fields are left out and read back as defaults
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<String> {
+        match self {
+            ConfigFormat::Json => Ok(serde_json::to_string_pretty(value)?),
+            ConfigFormat::Toml => Ok(toml::to_string_pretty(value)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::migration::{self, ConfigFile};
+    use crate::config::settings::Settings;
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.toml")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config-1.toml.gz")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config-1.json.gz")),
+            ConfigFormat::Json
+        );
+    }
+
+    #[test]
+    fn test_toml_roundtrip_keeps_checksum() {
+        let mut settings = Settings::default();
+        settings.github.repository = "owner/repo".to_string();
+        let mut file = ConfigFile::new(settings);
+        file.metadata.record_save(&["github"]);
+        file.seal().unwrap();
+
+        let content = ConfigFormat::Toml.serialize(&file).unwrap();
+        let parsed = migration::migrate(ConfigFormat::Toml.parse(&content).unwrap()).unwrap();
+
+        assert_eq!(parsed.settings, file.settings);
+        assert!(parsed.checksum_matches());
+    }
+}
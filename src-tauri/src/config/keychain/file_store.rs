@@ -0,0 +1,127 @@
+use super::SecretStore;
+use crate::config::atomic::write_atomic_private;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, bail, Context, Result};
+use rand::RngCore;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+const SECRETS_FILE: &str = "secrets.enc";
+const KEY_FILE: &str = "secrets.key";
+const NONCE_LEN: usize = 12;
+
+/// Fallback secret store for systems without an OS keystore
+///
+/// Secrets are kept as one AES-256-GCM encrypted JSON map. The key lives in a
+/// separate owner-only file next to it, so the secrets are never plaintext on
+/// disk, but this is weaker than a real keystore.
+pub struct EncryptedFileStore {
+    dir: PathBuf,
+}
+
+impl EncryptedFileStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn secrets_path(&self) -> PathBuf {
+        self.dir.join(SECRETS_FILE)
+    }
+
+    fn key(&self) -> Result<Key<Aes256Gcm>> {
+        let path = self.dir.join(KEY_FILE);
+        if path.exists() {
+            let bytes = fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+            if bytes.len() != 32 {
+                bail!("Secret key file {:?} is corrupt", path);
+            }
+            return Ok(*Key::<Aes256Gcm>::from_slice(&bytes));
+        }
+
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        write_atomic_private(&path, &bytes)?;
+        Ok(*Key::<Aes256Gcm>::from_slice(&bytes))
+    }
+
+    fn read_all(&self) -> Result<BTreeMap<String, String>> {
+        let path = self.secrets_path();
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let data = fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        if data.len() < NONCE_LEN {
+            bail!("Secret file {:?} is corrupt", path);
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let cipher = Aes256Gcm::new(&self.key()?);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("Failed to decrypt {:?}", path))?;
+
+        serde_json::from_slice(&plaintext).context("Secret file contents are invalid")
+    }
+
+    fn write_all(&self, secrets: &BTreeMap<String, String>) -> Result<()> {
+        let cipher = Aes256Gcm::new(&self.key()?);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let plaintext = serde_json::to_vec(secrets)?;
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| anyhow!("Failed to encrypt secrets"))?;
+
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        write_atomic_private(&self.secrets_path(), &data)
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn store(&self, account: &str, value: &str) -> Result<()> {
+        let mut secrets = self.read_all()?;
+        secrets.insert(account.to_string(), value.to_string());
+        self.write_all(&secrets)
+    }
+
+    fn retrieve(&self, account: &str) -> Result<Option<String>> {
+        Ok(self.read_all()?.remove(account))
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        let mut secrets = self.read_all()?;
+        if secrets.remove(account).is_some() {
+            self.write_all(&secrets)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_retrieve_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EncryptedFileStore::new(dir.path());
+
+        assert_eq!(store.retrieve("github_token").unwrap(), None);
+
+        store.store("github_token", "ghp_secret").unwrap();
+        assert_eq!(
+            store.retrieve("github_token").unwrap().as_deref(),
+            Some("ghp_secret")
+        );
+
+        // Stored bytes are not plaintext
+        let raw = fs::read(dir.path().join(SECRETS_FILE)).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("ghp_secret"));
+
+        store.delete("github_token").unwrap();
+        assert_eq!(store.retrieve("github_token").unwrap(), None);
+    }
+}
@@ -0,0 +1,335 @@
+mod file_store;
+
+use super::GitHubSettings;
+use anyhow::{Context, Result};
+use file_store::EncryptedFileStore;
+use serde::{Deserialize, Serialize};
+
+/// Keychain service name under which all Zeami secrets are stored
+pub const SERVICE_NAME: &str = "com.zeami4.app";
+
+/// Secrets kept out of config.json
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretKey {
+    GithubToken,
+    ClaudeApiKey,
+    /// Key for an OpenAI-compatible provider (ClaudeSettings::provider)
+    LlmApiKey,
+}
+
+impl SecretKey {
+    pub const ALL: [SecretKey; 3] = [
+        SecretKey::GithubToken,
+        SecretKey::ClaudeApiKey,
+        SecretKey::LlmApiKey,
+    ];
+
+    /// Account of the active profile's secret
+    pub fn account(&self) -> &'static str {
+        match self {
+            SecretKey::GithubToken => "github_token",
+            SecretKey::ClaudeApiKey => "claude_api_key",
+            SecretKey::LlmApiKey => "llm_api_key",
+        }
+    }
+
+    /// Account where an inactive profile's secret is parked while another profile is active
+    pub fn profile_account(&self, profile: &str) -> String {
+        format!("profile.{}.{}", profile, self.account())
+    }
+}
+
+/// A backend able to hold secrets by account name
+pub trait SecretStore {
+    fn store(&self, account: &str, value: &str) -> Result<()>;
+    /// Ok(None) when nothing is stored for `account`
+    fn retrieve(&self, account: &str) -> Result<Option<String>>;
+    fn delete(&self, account: &str) -> Result<()>;
+}
+
+/// OS keystore via the keyring crate
+/// (macOS Keychain, Windows Credential Manager, Secret Service on Linux)
+pub struct KeyringStore;
+
+impl KeyringStore {
+    fn entry(account: &str) -> keyring::Result<keyring::Entry> {
+        keyring::Entry::new(SERVICE_NAME, account)
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn store(&self, account: &str, value: &str) -> Result<()> {
+        Self::entry(account)
+            .and_then(|entry| entry.set_password(value))
+            .with_context(|| format!("Failed to store {} in keychain", account))
+    }
+
+    fn retrieve(&self, account: &str) -> Result<Option<String>> {
+        match Self::entry(account).and_then(|entry| entry.get_password()) {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {} from keychain", account)),
+        }
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        match Self::entry(account).and_then(|entry| entry.delete_credential()) {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e).with_context(|| format!("Failed to delete {} from keychain", account)),
+        }
+    }
+}
+
+/// Whether a keyring error means no OS keystore is usable (as opposed to a bad value)
+fn keystore_unavailable(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<keyring::Error>(),
+        Some(keyring::Error::NoStorageAccess(_)) | Some(keyring::Error::PlatformFailure(_))
+    )
+}
+
+fn fallback_store() -> Result<EncryptedFileStore> {
+    Ok(EncryptedFileStore::new(super::storage::config_dir()?))
+}
+
+/// The OS keystore, or the encrypted file when none is available
+pub struct SystemStore;
+
+impl SecretStore for SystemStore {
+    fn store(&self, account: &str, value: &str) -> Result<()> {
+        match KeyringStore.store(account, value) {
+            Err(e) if keystore_unavailable(&e) => {
+                tracing::warn!("OS keystore unavailable, using encrypted file: {:#}", e);
+                fallback_store()?.store(account, value)
+            }
+            result => result,
+        }
+    }
+
+    fn retrieve(&self, account: &str) -> Result<Option<String>> {
+        match KeyringStore.retrieve(account) {
+            Ok(Some(value)) => Ok(Some(value)),
+            Ok(None) => fallback_store()?.retrieve(account),
+            Err(e) if keystore_unavailable(&e) => fallback_store()?.retrieve(account),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Removes the secret from the OS keystore and the fallback file
+    fn delete(&self, account: &str) -> Result<()> {
+        match KeyringStore.delete(account) {
+            Err(e) if !keystore_unavailable(&e) => return Err(e),
+            _ => {}
+        }
+        fallback_store()?.delete(account)
+    }
+}
+
+/// Store a secret for the active profile
+pub fn store_secret(key: SecretKey, value: &str) -> Result<()> {
+    SystemStore.store(key.account(), value)
+}
+
+/// Read a secret of the active profile; Ok(None) when it was never stored
+pub fn retrieve_secret(key: SecretKey) -> Result<Option<String>> {
+    SystemStore.retrieve(key.account())
+}
+
+/// Remove a secret of the active profile
+pub fn delete_secret(key: SecretKey) -> Result<()> {
+    SystemStore.delete(key.account())
+}
+
+/// Secrets stored for the active profile (names only)
+pub fn list_stored_secrets() -> Result<Vec<SecretKey>> {
+    let mut stored = Vec::new();
+    for key in SecretKey::ALL {
+        if SystemStore.retrieve(key.account())?.is_some() {
+            stored.push(key);
+        }
+    }
+    Ok(stored)
+}
+
+/// Keychain account holding the token of a named GitHub account
+pub fn github_account_key(name: &str) -> String {
+    format!("github_account.{}", name)
+}
+
+/// Store the token of a named GitHub account (shared by all profiles)
+pub fn store_github_account_token(name: &str, token: &str) -> Result<()> {
+    SystemStore.store(&github_account_key(name), token)
+}
+
+/// Token of a named GitHub account; Ok(None) when none is stored
+pub fn retrieve_github_account_token(name: &str) -> Result<Option<String>> {
+    SystemStore.retrieve(&github_account_key(name))
+}
+
+pub fn delete_github_account_token(name: &str) -> Result<()> {
+    SystemStore.delete(&github_account_key(name))
+}
+
+/// Keychain account holding the private key of a configured SSH host
+pub fn ssh_key_account(host: &str) -> String {
+    format!("ssh_host.{}", host)
+}
+
+/// Store the private key of an SSH host (shared by all profiles)
+pub fn store_ssh_key(host: &str, key: &str) -> Result<()> {
+    SystemStore.store(&ssh_key_account(host), key)
+}
+
+/// Private key of an SSH host; Ok(None) when none is stored
+pub fn retrieve_ssh_key(host: &str) -> Result<Option<String>> {
+    SystemStore.retrieve(&ssh_key_account(host))
+}
+
+pub fn delete_ssh_key(host: &str) -> Result<()> {
+    SystemStore.delete(&ssh_key_account(host))
+}
+
+/// Keychain account holding the deploy key of a GitSettings::remote_credentials entry
+pub fn deploy_key_account(url: &str) -> String {
+    format!("deploy_key.{}", url)
+}
+
+/// Store the private deploy key for remotes matching `url` (shared by all profiles)
+pub fn store_deploy_key(url: &str, key: &str) -> Result<()> {
+    SystemStore.store(&deploy_key_account(url), key)
+}
+
+/// Deploy key for remotes matching `url`; Ok(None) when none is stored
+pub fn retrieve_deploy_key(url: &str) -> Result<Option<String>> {
+    SystemStore.retrieve(&deploy_key_account(url))
+}
+
+pub fn delete_deploy_key(url: &str) -> Result<()> {
+    SystemStore.delete(&deploy_key_account(url))
+}
+
+/// Token of the account selected in `settings`, or the profile's default token
+pub fn github_token(settings: &GitHubSettings) -> Result<Option<String>> {
+    match &settings.account {
+        Some(name) => retrieve_github_account_token(name),
+        None => retrieve_secret(SecretKey::GithubToken),
+    }
+}
+
+/// Check a GitHub token against the API at `api_url` and return the login it authenticates as
+pub async fn validate_github_token(token: &str, api_url: &str) -> Result<String> {
+    let client = octocrab::Octocrab::builder()
+        .base_uri(api_url)
+        .with_context(|| format!("Invalid GitHub API URL: {}", api_url))?
+        .personal_token(token.to_string())
+        .build()
+        .context("Failed to build GitHub client")?;
+
+    let user = client
+        .current()
+        .user()
+        .await
+        .context("GitHub token is invalid")?;
+
+    Ok(user.login)
+}
+
+/// Replace the stored GitHub token, only after the new one has been validated
+/// Returns the login the new token authenticates as
+pub async fn rotate_github_token(new_token: &str, api_url: &str) -> Result<String> {
+    let login = validate_github_token(new_token, api_url).await?;
+    store_secret(SecretKey::GithubToken, new_token)?;
+    Ok(login)
+}
+
+/// Park the outgoing profile's secrets and make the incoming profile's secrets active
+pub fn switch_profile_secrets(from: &str, to: &str) -> Result<()> {
+    switch_profile_secrets_in(&SystemStore, from, to)
+}
+
+/// Give profile `target` copies of profile `source`'s secrets
+/// `source_active` says whether `source` is the active profile
+pub fn copy_profile_secrets(source: &str, source_active: bool, target: &str) -> Result<()> {
+    copy_profile_secrets_in(&SystemStore, source, source_active, target)
+}
+
+fn switch_profile_secrets_in(store: &impl SecretStore, from: &str, to: &str) -> Result<()> {
+    for key in SecretKey::ALL {
+        let outgoing = store.retrieve(key.account())?;
+        let incoming = store.retrieve(&key.profile_account(to))?;
+
+        match outgoing {
+            Some(value) => store.store(&key.profile_account(from), &value)?,
+            None => store.delete(&key.profile_account(from))?,
+        }
+        match incoming {
+            Some(value) => {
+                store.store(key.account(), &value)?;
+                store.delete(&key.profile_account(to))?;
+            }
+            None => store.delete(key.account())?,
+        }
+    }
+    Ok(())
+}
+
+fn copy_profile_secrets_in(
+    store: &impl SecretStore,
+    source: &str,
+    source_active: bool,
+    target: &str,
+) -> Result<()> {
+    for key in SecretKey::ALL {
+        let value = if source_active {
+            store.retrieve(key.account())?
+        } else {
+            store.retrieve(&key.profile_account(source))?
+        };
+        if let Some(value) = value {
+            store.store(&key.profile_account(target), &value)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_secrets_follow_switches() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EncryptedFileStore::new(dir.path());
+        let github = SecretKey::GithubToken;
+        store.store(github.account(), "work_token").unwrap();
+
+        copy_profile_secrets_in(&store, "default", true, "personal").unwrap();
+        store
+            .store(&github.profile_account("personal"), "personal_token")
+            .unwrap();
+
+        switch_profile_secrets_in(&store, "default", "personal").unwrap();
+        assert_eq!(
+            store.retrieve(github.account()).unwrap().as_deref(),
+            Some("personal_token")
+        );
+        assert_eq!(
+            store.retrieve(&github.profile_account("personal")).unwrap(),
+            None
+        );
+
+        switch_profile_secrets_in(&store, "personal", "default").unwrap();
+        assert_eq!(
+            store.retrieve(github.account()).unwrap().as_deref(),
+            Some("work_token")
+        );
+        assert_eq!(
+            store
+                .retrieve(&github.profile_account("personal"))
+                .unwrap()
+                .as_deref(),
+            Some("personal_token")
+        );
+    }
+}
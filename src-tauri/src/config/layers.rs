@@ -0,0 +1,141 @@
+use super::settings::Settings;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Where an effective setting value came from, lowest priority first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingsLayer {
+    Default,
+    Global,
+    Project,
+    /// ZEAMI_* environment variables
+    Environment,
+    /// `--set` command-line arguments
+    Cli,
+}
+
+/// Settings after all layers are merged, with the layer each value came from
+/// `sources` is keyed by dotted path, e.g. `"workflow.test_command"`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveSettings {
+    pub settings: Settings,
+    pub sources: BTreeMap<String, SettingsLayer>,
+}
+
+/// Merge layers over the defaults; later layers win
+/// Each layer is a (possibly partial) settings document
+pub fn resolve(layers: &[(SettingsLayer, &Value)]) -> Result<EffectiveSettings> {
+    let mut merged = serde_json::to_value(Settings::default())?;
+    let mut sources = BTreeMap::new();
+    for path in leaf_paths(&merged) {
+        sources.insert(path, SettingsLayer::Default);
+    }
+
+    for (layer, value) in layers {
+        if value.is_null() {
+            continue;
+        }
+        merge_values(&mut merged, value);
+        for path in leaf_paths(value) {
+            sources.insert(path, *layer);
+        }
+    }
+
+    let settings: Settings =
+        serde_json::from_value(merged).context("Merged settings are invalid")?;
+
+    // Drop paths that do not correspond to a real setting (unknown keys)
+    let resolved = serde_json::to_value(&settings)?;
+    sources.retain(|path, _| resolved.pointer(&to_pointer(path)).is_some());
+
+    Ok(EffectiveSettings { settings, sources })
+}
+
+/// Recursively merge `overlay` into `base`; objects merge, everything else replaces
+pub fn merge_values(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// Dotted paths of every leaf value; arrays and empty objects count as leaves
+pub fn leaf_paths(value: &Value) -> Vec<String> {
+    let mut paths = Vec::new();
+    collect_leaf_paths(value, String::new(), &mut paths);
+    paths
+}
+
+fn collect_leaf_paths(value: &Value, prefix: String, paths: &mut Vec<String>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                collect_leaf_paths(child, path, paths);
+            }
+        }
+        _ if !prefix.is_empty() => paths.push(prefix),
+        _ => {}
+    }
+}
+
+fn to_pointer(path: &str) -> String {
+    path.split('.').fold(String::new(), |mut pointer, segment| {
+        pointer.push('/');
+        pointer.push_str(segment);
+        pointer
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_project_layer_overrides_global() {
+        let global = json!({ "workflow": { "test_command": "npm test", "auto_build": true } });
+        let project = json!({ "workflow": { "test_command": "cargo test" } });
+
+        let effective = resolve(&[
+            (SettingsLayer::Global, &global),
+            (SettingsLayer::Project, &project),
+        ])
+        .unwrap();
+
+        assert_eq!(effective.settings.workflow.test_command, "cargo test");
+        assert!(effective.settings.workflow.auto_build);
+        assert_eq!(
+            effective.sources["workflow.test_command"],
+            SettingsLayer::Project
+        );
+        assert_eq!(
+            effective.sources["workflow.auto_build"],
+            SettingsLayer::Global
+        );
+        assert_eq!(effective.sources["git.remote"], SettingsLayer::Default);
+    }
+
+    #[test]
+    fn test_unknown_keys_are_not_reported() {
+        let project = json!({ "workflow": { "no_such_setting": 1 } });
+        let effective = resolve(&[(SettingsLayer::Project, &project)]).unwrap();
+        assert!(!effective.sources.contains_key("workflow.no_such_setting"));
+    }
+}
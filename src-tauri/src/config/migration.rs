@@ -0,0 +1,274 @@
+use super::settings::Settings;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Version of the on-disk config file layout
+pub const CURRENT_CONFIG_VERSION: u32 = 1;
+
+/// Number of change records kept in ConfigMetadata::history
+pub const MAX_HISTORY_ENTRIES: usize = 50;
+
+/// On-disk representation of config.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigFile {
+    pub version: u32,
+    pub settings: Settings,
+    pub metadata: ConfigMetadata,
+    /// Settings as read from disk, before defaults filled in missing fields
+    #[serde(skip)]
+    stored_settings: Option<Value>,
+}
+
+/// Bookkeeping stored alongside the settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigMetadata {
+    /// Name of the settings profile this file belongs to
+    #[serde(default = "default_profile_name")]
+    pub profile: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub app_version: String,
+    /// Most recent changes, oldest first, capped at MAX_HISTORY_ENTRIES
+    #[serde(default)]
+    pub history: Vec<ConfigChange>,
+    /// SHA-256 of the serialized settings, written on every save
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+}
+
+/// Profile used when none has been created
+pub const DEFAULT_PROFILE: &str = "default";
+
+fn default_profile_name() -> String {
+    DEFAULT_PROFILE.to_string()
+}
+
+/// One save that changed the settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub timestamp: String,
+    pub sections: Vec<String>,
+}
+
+impl ConfigMetadata {
+    pub fn new() -> Self {
+        let now = chrono::Utc::now().to_rfc3339();
+        Self {
+            profile: default_profile_name(),
+            created_at: now.clone(),
+            updated_at: now,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            history: Vec::new(),
+            checksum: None,
+        }
+    }
+
+    /// Mark the file as updated now, recording the changed sections (if any)
+    pub fn record_save(&mut self, sections: &[&str]) {
+        let now = chrono::Utc::now().to_rfc3339();
+        self.updated_at = now.clone();
+        self.app_version = env!("CARGO_PKG_VERSION").to_string();
+
+        if sections.is_empty() {
+            return;
+        }
+
+        self.history.push(ConfigChange {
+            timestamp: now,
+            sections: sections.iter().map(|s| s.to_string()).collect(),
+        });
+        if self.history.len() > MAX_HISTORY_ENTRIES {
+            let excess = self.history.len() - MAX_HISTORY_ENTRIES;
+            self.history.drain(..excess);
+        }
+    }
+}
+
+impl ConfigFile {
+    pub fn new(settings: Settings) -> Self {
+        Self {
+            version: CURRENT_CONFIG_VERSION,
+            settings,
+            metadata: ConfigMetadata::new(),
+            stored_settings: None,
+        }
+    }
+
+    /// Recompute the stored checksum from the current settings
+    pub fn seal(&mut self) -> Result<()> {
+        self.metadata.checksum = Some(settings_checksum(&self.settings)?);
+        self.stored_settings = None;
+        Ok(())
+    }
+
+    /// False when a stored checksum does not match the settings
+    /// Files without a checksum (older or hand-written) are accepted
+    ///
+    /// Only fields the file has are compared, so one sealed by a version
+    /// without a newer field still matches once defaults fill that field in.
+    pub fn checksum_matches(&self) -> bool {
+        let Some(expected) = &self.metadata.checksum else {
+            return true;
+        };
+        let Some(stored) = &self.stored_settings else {
+            return settings_checksum(&self.settings).is_ok_and(|actual| &actual == expected);
+        };
+        let Ok(value) = serde_json::to_string(&self.settings)
+            .and_then(|json| serde_json::from_str::<Value>(&json))
+        else {
+            return false;
+        };
+        // TOML leaves None fields out, so a missing null may have been sealed
+        [false, true].into_iter().any(|keep_nulls| {
+            let mut value = value.clone();
+            retain_stored(&mut value, stored, keep_nulls);
+            serde_json::to_vec(&value).is_ok_and(|bytes| &checksum_hex(&bytes) == expected)
+        })
+    }
+}
+
+/// Drop the object fields of `value` that `stored` does not have
+fn retain_stored(value: &mut Value, stored: &Value, keep_nulls: bool) {
+    if let (Value::Object(fields), Value::Object(stored)) = (value, stored) {
+        fields.retain(|key, value| match stored.get(key) {
+            Some(stored) => {
+                retain_stored(value, stored, keep_nulls);
+                true
+            }
+            None => keep_nulls && value.is_null(),
+        });
+    }
+}
+
+/// Hex SHA-256 of the compact JSON serialization of `settings`
+pub fn settings_checksum(settings: &Settings) -> Result<String> {
+    Ok(checksum_hex(&serde_json::to_vec(settings)?))
+}
+
+fn checksum_hex(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Bring a parsed config document up to CURRENT_CONFIG_VERSION
+///
+/// Version 0 is the unversioned layout where the settings sections sit at the
+/// top level of the document without metadata.
+pub fn migrate(value: Value) -> Result<ConfigFile> {
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+
+    match version {
+        0 => {
+            let settings: Settings =
+                serde_json::from_value(value).context("Failed to parse unversioned config")?;
+            Ok(ConfigFile::new(settings))
+        }
+        CURRENT_CONFIG_VERSION => {
+            let stored_settings = value.get("settings").cloned();
+            let mut file: ConfigFile =
+                serde_json::from_value(value).context("Failed to parse config file")?;
+            file.stored_settings = stored_settings;
+            Ok(file)
+        }
+        newer => bail!(
+            "Config version {} is newer than supported version {}",
+            newer,
+            CURRENT_CONFIG_VERSION
+        ),
+    }
+}
+
+/// The settings part of a config document as written, without defaults filled in
+pub fn settings_document(value: &Value) -> Value {
+    match value.get("version") {
+        Some(_) => value.get("settings").cloned().unwrap_or(Value::Null),
+        None => value.clone(),
+    }
+}
+
+/// Whether `value` is the old CLI's config.toml: `[github]` with a plaintext token and no version
+pub fn is_legacy_cli_config(value: &Value) -> bool {
+    value.get("version").is_none() && value.pointer("/github/token").is_some()
+}
+
+/// Plaintext GitHub token from the old CLI's config, if set
+pub fn legacy_token(value: &Value) -> Option<String> {
+    value
+        .pointer("/github/token")
+        .and_then(Value::as_str)
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_unversioned_config() {
+        let value = serde_json::json!({ "terminal": { "font_size": 18 } });
+        let file = migrate(value).unwrap();
+
+        assert_eq!(file.version, CURRENT_CONFIG_VERSION);
+        assert_eq!(file.settings.terminal.font_size, 18);
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut metadata = ConfigMetadata::new();
+        for _ in 0..MAX_HISTORY_ENTRIES + 5 {
+            metadata.record_save(&["terminal"]);
+        }
+        metadata.record_save(&[]);
+
+        assert_eq!(metadata.history.len(), MAX_HISTORY_ENTRIES);
+    }
+
+    #[test]
+    fn test_checksum_detects_modified_settings() {
+        let mut file = ConfigFile::new(Settings::default());
+        assert!(file.checksum_matches());
+
+        file.seal().unwrap();
+        assert!(file.checksum_matches());
+
+        file.settings.terminal.font_size += 1;
+        assert!(!file.checksum_matches());
+    }
+
+    #[test]
+    fn test_checksum_ignores_fields_added_since_sealing() {
+        // A file sealed by a version before ClaudeSettings::redaction_allowlist
+        let json = serde_json::to_string(&Settings::default()).unwrap();
+        let mut settings: Value = serde_json::from_str(&json).unwrap();
+        settings["claude"]
+            .as_object_mut()
+            .unwrap()
+            .remove("redaction_allowlist");
+        let checksum = checksum_hex(&serde_json::to_vec(&settings).unwrap());
+        let document = serde_json::json!({
+            "version": CURRENT_CONFIG_VERSION,
+            "settings": settings,
+            "metadata": {
+                "created_at": "",
+                "updated_at": "",
+                "app_version": "0.1.0",
+                "checksum": checksum,
+            },
+        });
+        let file = migrate(document.clone()).unwrap();
+        assert!(file.checksum_matches());
+
+        let mut modified = document;
+        modified["settings"]["terminal"]["font_size"] = serde_json::json!(30);
+        assert!(!migrate(modified).unwrap().checksum_matches());
+    }
+
+    #[test]
+    fn test_migrate_rejects_newer_version() {
+        let value = serde_json::json!({ "version": CURRENT_CONFIG_VERSION + 1 });
+        assert!(migrate(value).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+pub mod atomic;
+pub mod auto_backup;
+mod backup;
+pub mod export_crypto;
+mod format;
+pub mod keychain;
+mod layers;
+mod migration;
+pub mod notify;
+pub mod overrides;
+pub mod profiles;
+mod settings;
+pub mod storage;
+
+pub use backup::{BackupInfo, BackupSettings};
+pub use format::ConfigFormat;
+pub use layers::EffectiveSettings;
+pub use migration::ConfigChange;
+pub use settings::*;
+
+use anyhow::Result;
+use serde_json::Value;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+/// Capacity of the settings change channel; slow subscribers skip stale changes
+const CHANGE_CHANNEL_CAPACITY: usize = 16;
+
+/// Broadcast whenever the current settings are replaced
+#[derive(Debug, Clone)]
+pub struct SettingsChange {
+    /// Names of the top-level sections that differ from the previous settings
+    pub sections: Vec<&'static str>,
+    pub settings: Settings,
+}
+
+impl SettingsChange {
+    pub fn touches(&self, section: &str) -> bool {
+        self.sections.contains(&section)
+    }
+}
+
+/// Current settings managed by Tauri
+/// Loaded once at startup and replaced whenever settings are saved
+pub struct SettingsState {
+    pub settings: Mutex<Settings>,
+    /// ZEAMI_* and `--set` overrides, applied to every replacement
+    overrides: Value,
+    changes: broadcast::Sender<SettingsChange>,
+}
+
+impl SettingsState {
+    pub fn new(settings: Settings) -> Self {
+        Self::with_overrides(settings, Value::Null)
+    }
+
+    /// State whose settings always have `overrides` (a partial settings document) applied
+    pub fn with_overrides(settings: Settings, overrides: Value) -> Self {
+        let (changes, _) = broadcast::channel(CHANGE_CHANNEL_CAPACITY);
+        let settings = overridden(settings, &overrides);
+        Self {
+            settings: Mutex::new(settings),
+            overrides,
+            changes,
+        }
+    }
+
+    /// Snapshot of the current settings
+    pub fn current(&self) -> Settings {
+        self.settings
+            .lock()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+
+    /// Make `settings` current and notify subscribers of the changed sections
+    pub fn replace(&self, settings: Settings) -> Result<Vec<&'static str>> {
+        let settings = overridden(settings, &self.overrides);
+        let sections = {
+            let mut current = self
+                .settings
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock settings: {}", e))?;
+            let sections = current.changed_sections(&settings);
+            *current = settings.clone();
+            sections
+        };
+
+        if !sections.is_empty() {
+            // No receivers is fine: nothing is listening yet
+            let _ = self.changes.send(SettingsChange {
+                sections: sections.clone(),
+                settings,
+            });
+        }
+
+        Ok(sections)
+    }
+
+    /// Receive every future settings change
+    pub fn subscribe(&self) -> broadcast::Receiver<SettingsChange> {
+        self.changes.subscribe()
+    }
+}
+
+fn overridden(settings: Settings, overrides: &Value) -> Settings {
+    match overrides::apply(&settings, overrides) {
+        Ok(settings) => settings,
+        Err(e) => {
+            tracing::warn!("Ignoring setting overrides: {:#}", e);
+            settings
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_overrides_reach_current_settings() {
+        let overrides =
+            overrides::from_env([("ZEAMI_GIT_REMOTE".to_string(), "upstream".to_string())]);
+        let state = SettingsState::with_overrides(Settings::default(), overrides);
+        assert_eq!(state.current().git.remote, "upstream");
+
+        // Saved settings are replaced without the override; it still applies
+        let mut saved = Settings::default();
+        saved.ui.language = "ja".to_string();
+        state.replace(saved).unwrap();
+        assert_eq!(state.current().git.remote, "upstream");
+        assert_eq!(state.current().ui.language, "ja");
+    }
+}
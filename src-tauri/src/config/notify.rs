@@ -0,0 +1,50 @@
+use super::SettingsState;
+use crate::events::schema::v1;
+use crate::events::SETTINGS_CHANGED_EVENT_NAME;
+use crate::i18n;
+use crate::logging::LogState;
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::error::RecvError;
+
+/// Forward settings changes to the frontend and apply them to running subsystems
+///
+/// Log levels are reloaded in place and backend messages switch to the new UI
+/// language. Terminal options (font, theme, scrollback) are applied by the
+/// frontend from the event payload, and new PTY sessions read the default shell
+/// from SettingsState when they start.
+pub fn spawn_listener(app: AppHandle) {
+    let mut changes = app.state::<SettingsState>().subscribe();
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let change = match changes.recv().await {
+                Ok(change) => change,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Settings listener skipped {} changes", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+
+            tracing::info!(sections = ?change.sections, "Settings changed");
+
+            if change.touches("logging") {
+                if let Err(e) = app.state::<LogState>().apply(&change.settings.logging) {
+                    tracing::warn!("Failed to apply log levels: {:#}", e);
+                }
+            }
+
+            if change.touches("ui") {
+                i18n::set_current(&change.settings.ui.language);
+            }
+
+            let payload = v1::SettingsChanged {
+                sections: change.sections.iter().map(|s| s.to_string()).collect(),
+                settings: change.settings,
+            };
+            if let Err(e) = app.emit_all(SETTINGS_CHANGED_EVENT_NAME, payload) {
+                tracing::error!("Failed to emit settings change: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,250 @@
+use super::layers;
+use super::settings::Settings;
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+
+/// Prefix of environment variables that override settings,
+/// e.g. ZEAMI_GITHUB_REPOSITORY sets `github.repository`
+pub const ENV_PREFIX: &str = "ZEAMI_";
+
+/// ZEAMI_* variables that switch modes rather than set a setting
+const NOT_SETTINGS: &[&str] = &["ZEAMI_MOCK_GITHUB"];
+
+/// Command-line flag that overrides a setting, e.g. `--set terminal.shell=/bin/zsh`
+pub const CLI_FLAG: &str = "--set";
+
+/// Partial settings document built from ZEAMI_* variables in `vars`
+/// Variables that do not name a known setting are ignored with a warning
+pub fn from_env(vars: impl IntoIterator<Item = (String, String)>) -> Value {
+    let mut overrides = Vec::new();
+    for (name, raw) in vars {
+        let Some(rest) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if NOT_SETTINGS.contains(&name.as_str()) {
+            continue;
+        }
+        // Section names contain no underscores, so the first one ends the section
+        let Some((section, field)) = rest.split_once('_') else {
+            continue;
+        };
+        let path = format!("{}.{}", section.to_lowercase(), field.to_lowercase());
+        overrides.push((path, raw));
+    }
+    build(overrides, "environment variable")
+}
+
+/// Partial settings document built from `--set <path>=<value>` arguments
+pub fn from_args(args: impl IntoIterator<Item = String>) -> Value {
+    let mut overrides = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let assignment = if arg == CLI_FLAG {
+            match args.next() {
+                Some(next) => next,
+                None => break,
+            }
+        } else if let Some(assignment) = arg.strip_prefix("--set=") {
+            assignment.to_string()
+        } else {
+            continue;
+        };
+
+        match assignment.split_once('=') {
+            Some((path, raw)) => overrides.push((path.to_string(), raw.to_string())),
+            None => tracing::warn!("Ignoring malformed {} {:?}", CLI_FLAG, assignment),
+        }
+    }
+    build(overrides, "command-line override")
+}
+
+/// Overrides of this process: ZEAMI_* variables, then `--set` arguments over them
+pub fn from_process() -> Value {
+    let mut overrides = from_env(std::env::vars());
+    let cli = from_args(std::env::args().skip(1));
+    if !cli.is_null() {
+        layers::merge_values(&mut overrides, &cli);
+    }
+    overrides
+}
+
+/// `settings` with `overrides` (a partial settings document) applied
+pub fn apply(settings: &Settings, overrides: &Value) -> Result<Settings> {
+    if overrides.is_null() {
+        return Ok(settings.clone());
+    }
+    let mut merged = serde_json::to_value(settings)?;
+    layers::merge_values(&mut merged, overrides);
+    serde_json::from_value(merged).context("Overridden settings are invalid")
+}
+
+/// `settings` with every overridden value put back to the one in `saved`,
+/// so saving does not write ZEAMI_* or `--set` values to the config file
+pub fn restore(settings: &Settings, saved: &Settings, overrides: &Value) -> Result<Settings> {
+    if overrides.is_null() {
+        return Ok(settings.clone());
+    }
+    let mut restored = serde_json::to_value(settings)?;
+    let saved = serde_json::to_value(saved)?;
+    for path in layers::leaf_paths(overrides) {
+        let pointer = format!("/{}", path.replace('.', "/"));
+        if let (Some(value), Some(original)) =
+            (restored.pointer_mut(&pointer), saved.pointer(&pointer))
+        {
+            *value = original.clone();
+        }
+    }
+    serde_json::from_value(restored).context("Restored settings are invalid")
+}
+
+/// Typed values are parsed according to the default value at the same path
+fn build(overrides: Vec<(String, String)>, origin: &str) -> Value {
+    let defaults = serde_json::to_value(Settings::default()).unwrap_or(Value::Null);
+    let mut document = Map::new();
+
+    for (path, raw) in overrides {
+        let Some((section, field)) = path.split_once('.') else {
+            tracing::warn!("Ignoring {} for unknown setting {}", origin, path);
+            continue;
+        };
+        let Some(default) = defaults.get(section).and_then(|s| s.get(field)) else {
+            tracing::warn!("Ignoring {} for unknown setting {}", origin, path);
+            continue;
+        };
+        let parsed = match default {
+            Value::Null => parse_optional(&defaults, section, field, &raw),
+            _ => parse_value(default, &raw),
+        };
+        let Some(value) = parsed else {
+            tracing::warn!("Ignoring {} {}: invalid value {:?}", origin, path, raw);
+            continue;
+        };
+
+        if let Value::Object(section) = document
+            .entry(section.to_string())
+            .or_insert_with(|| Value::Object(Map::new()))
+        {
+            section.insert(field.to_string(), value);
+        }
+    }
+
+    if document.is_empty() {
+        Value::Null
+    } else {
+        Value::Object(document)
+    }
+}
+
+/// Optional settings default to null, so `raw` is taken as JSON (a number, say)
+/// when the setting accepts that and as a string otherwise
+fn parse_optional(defaults: &Value, section: &str, field: &str, raw: &str) -> Option<Value> {
+    let accepts = |value: &Value| {
+        let mut candidate = defaults.clone();
+        candidate[section][field] = value.clone();
+        serde_json::from_value::<Settings>(candidate).is_ok()
+    };
+    serde_json::from_str::<Value>(raw)
+        .ok()
+        .filter(accepts)
+        .or_else(|| Some(Value::String(raw.to_string())).filter(accepts))
+}
+
+fn parse_value(default: &Value, raw: &str) -> Option<Value> {
+    match default {
+        Value::String(_) | Value::Null => Some(Value::String(raw.to_string())),
+        Value::Array(_) => Some(Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        )),
+        Value::Bool(_) => raw.parse::<bool>().ok().map(Value::Bool),
+        Value::Number(_) => serde_json::from_str::<Value>(raw)
+            .ok()
+            .filter(Value::is_number),
+        Value::Object(_) => serde_json::from_str::<Value>(raw)
+            .ok()
+            .filter(Value::is_object),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_env_overrides_are_typed_by_default() {
+        let overrides = from_env(vars(&[
+            ("ZEAMI_GITHUB_REPOSITORY", "owner/repo"),
+            ("ZEAMI_GIT_AUTO_FETCH", "true"),
+            ("ZEAMI_TERMINAL_SHELL", "/bin/zsh"),
+            ("ZEAMI_TERMINAL_FONT_SIZE", "16"),
+            ("ZEAMI_GITHUB_DEFAULT_REVIEWERS", "alice, bob"),
+            ("ZEAMI_GIT_FETCH_INTERVAL", "soon"),
+            ("ZEAMI_NO_SUCH_SETTING", "1"),
+            ("PATH", "/usr/bin"),
+        ]));
+
+        assert_eq!(
+            overrides,
+            json!({
+                "github": { "repository": "owner/repo", "default_reviewers": ["alice", "bob"] },
+                "git": { "auto_fetch": true },
+                "terminal": { "shell": "/bin/zsh", "font_size": 16 },
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_overrides() {
+        let args = [
+            "zeami4",
+            "--set",
+            "ui.language=ja",
+            "--set=git.remote=upstream",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            from_args(args),
+            json!({ "ui": { "language": "ja" }, "git": { "remote": "upstream" } })
+        );
+        assert_eq!(from_args(["zeami4".to_string()]), Value::Null);
+    }
+
+    #[test]
+    fn test_optional_overrides_keep_their_type() {
+        let overrides = from_env(vars(&[
+            ("ZEAMI_GITHUB_PROJECT", "3"),
+            ("ZEAMI_GITHUB_ACCOUNT", "123"),
+        ]));
+        let settings = apply(&Settings::default(), &overrides).unwrap();
+
+        assert_eq!(settings.github.project, Some(3));
+        assert_eq!(settings.github.account.as_deref(), Some("123"));
+    }
+
+    #[test]
+    fn test_overrides_are_applied_but_not_saved() {
+        let overrides = from_env(vars(&[("ZEAMI_GIT_REMOTE", "upstream")]));
+        let mut saved = Settings::default();
+        saved.git.remote = "origin".to_string();
+
+        let mut current = apply(&saved, &overrides).unwrap();
+        assert_eq!(current.git.remote, "upstream");
+
+        current.ui.language = "ja".to_string();
+        let to_save = restore(&current, &saved, &overrides).unwrap();
+        assert_eq!(to_save.git.remote, "origin");
+        assert_eq!(to_save.ui.language, "ja");
+    }
+}
@@ -0,0 +1,251 @@
+use super::backup;
+use super::keychain;
+use super::migration::{ConfigFile, ConfigMetadata};
+use super::settings::Settings;
+use super::storage::{self, read_config_file, write_config_file};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// A named settings profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub active: bool,
+    pub updated_at: Option<String>,
+}
+
+/// Locations the profile operations work on
+/// config.json always holds the active profile; inactive ones live in profiles/<name>.json
+pub(crate) struct ProfilePaths {
+    pub config: PathBuf,
+    pub profiles: PathBuf,
+    pub backups: PathBuf,
+}
+
+impl ProfilePaths {
+    fn user() -> Result<Self> {
+        Ok(Self {
+            config: storage::config_path()?,
+            profiles: storage::config_dir()?.join("profiles"),
+            backups: storage::backup_dir()?,
+        })
+    }
+
+    fn profile_file(&self, name: &str) -> PathBuf {
+        self.profiles.join(format!("{}.json", name))
+    }
+}
+
+/// All known profiles, sorted by name
+pub fn list_profiles() -> Result<Vec<ProfileInfo>> {
+    list_in(&ProfilePaths::user()?)
+}
+
+/// Make `name` the active profile and return its settings
+/// The outgoing profile is saved to profiles/<name>.json first, and each
+/// profile's keychain secrets are swapped along with its settings
+///
+/// Secrets are swapped before the config files and swapped back if those
+/// cannot be written, so a failure never pairs one profile's settings with
+/// another's secrets.
+pub fn switch_profile(name: &str) -> Result<Settings> {
+    let paths = ProfilePaths::user()?;
+    let previous = active_config(&paths)?.metadata.profile;
+    if previous == name {
+        return switch_in(&paths, name);
+    }
+    validate_profile_name(name)?;
+    if !paths.profile_file(name).exists() {
+        bail!("Profile '{}' does not exist", name);
+    }
+
+    keychain::switch_profile_secrets(&previous, name)?;
+    switch_in(&paths, name).inspect_err(|_| {
+        if let Err(e) = keychain::switch_profile_secrets(name, &previous) {
+            tracing::error!(
+                "Failed to restore the secrets of profile {}: {:#}",
+                previous,
+                e
+            );
+        }
+    })
+}
+
+/// Create profile `target` as a copy of profile `source`, including its secrets
+pub fn clone_profile(source: &str, target: &str) -> Result<()> {
+    let paths = ProfilePaths::user()?;
+    clone_in(&paths, source, target)?;
+    let source_active = active_config(&paths)?.metadata.profile == source;
+    keychain::copy_profile_secrets(source, source_active, target)
+}
+
+/// Profile names become file names, so keep them to a safe character set
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        bail!(
+            "Invalid profile name '{}': use letters, digits, '-' and '_'",
+            name
+        );
+    }
+    Ok(())
+}
+
+fn active_config(paths: &ProfilePaths) -> Result<ConfigFile> {
+    Ok(read_config_file(&paths.config)?.unwrap_or_else(|| ConfigFile::new(Settings::default())))
+}
+
+pub(crate) fn list_in(paths: &ProfilePaths) -> Result<Vec<ProfileInfo>> {
+    let active = active_config(paths)?;
+    let mut profiles = vec![ProfileInfo {
+        name: active.metadata.profile.clone(),
+        active: true,
+        updated_at: Some(active.metadata.updated_at),
+    }];
+
+    if paths.profiles.exists() {
+        for entry in fs::read_dir(&paths.profiles)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if name == active.metadata.profile {
+                continue;
+            }
+
+            let updated_at = read_config_file(&path)
+                .ok()
+                .flatten()
+                .map(|file| file.metadata.updated_at);
+            profiles.push(ProfileInfo {
+                name: name.to_string(),
+                active: false,
+                updated_at,
+            });
+        }
+    }
+
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(profiles)
+}
+
+pub(crate) fn switch_in(paths: &ProfilePaths, name: &str) -> Result<Settings> {
+    validate_profile_name(name)?;
+
+    let mut current = active_config(paths)?;
+    if current.metadata.profile == name {
+        return Ok(current.settings);
+    }
+
+    let target_path = paths.profile_file(name);
+    let Some(mut target) = read_config_file(&target_path)? else {
+        bail!("Profile '{}' does not exist", name);
+    };
+
+    if paths.config.exists() {
+        backup::create_backup(&paths.config, &paths.backups, &current.settings.backup)?;
+    }
+    let outgoing_path = paths.profile_file(&current.metadata.profile);
+    write_config_file(&outgoing_path, &mut current)?;
+
+    target.metadata.profile = name.to_string();
+    write_config_file(&paths.config, &mut target)?;
+    fs::remove_file(&target_path)?;
+
+    Ok(target.settings)
+}
+
+pub(crate) fn clone_in(paths: &ProfilePaths, source: &str, target: &str) -> Result<()> {
+    validate_profile_name(source)?;
+    validate_profile_name(target)?;
+
+    let existing = list_in(paths)?;
+    if existing.iter().any(|profile| profile.name == target) {
+        bail!("Profile '{}' already exists", target);
+    }
+
+    let active = active_config(paths)?;
+    let settings = if active.metadata.profile == source {
+        active.settings
+    } else {
+        match read_config_file(&paths.profile_file(source))? {
+            Some(file) => file.settings,
+            None => bail!("Profile '{}' does not exist", source),
+        }
+    };
+
+    let mut file = ConfigFile::new(settings);
+    file.metadata = ConfigMetadata {
+        profile: target.to_string(),
+        ..ConfigMetadata::new()
+    };
+    write_config_file(&paths.profile_file(target), &mut file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn active_in(paths: &ProfilePaths) -> Result<String> {
+        Ok(active_config(paths)?.metadata.profile)
+    }
+
+    fn paths(dir: &Path) -> ProfilePaths {
+        ProfilePaths {
+            config: dir.join("config.json"),
+            profiles: dir.join("profiles"),
+            backups: dir.join("backups"),
+        }
+    }
+
+    #[test]
+    fn test_clone_and_switch_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = paths(dir.path());
+
+        let mut work = Settings::default();
+        work.github.repository = "company/app".to_string();
+        storage::save_to(&paths.config, &paths.backups, &work).unwrap();
+        assert_eq!(active_in(&paths).unwrap(), "default");
+
+        clone_in(&paths, "default", "personal").unwrap();
+        let names: Vec<_> = list_in(&paths)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, vec!["default", "personal"]);
+
+        let personal = switch_in(&paths, "personal").unwrap();
+        assert_eq!(personal.github.repository, "company/app");
+        assert_eq!(active_in(&paths).unwrap(), "personal");
+
+        // Edits to the active profile persist across a round trip
+        let mut edited = personal.clone();
+        edited.github.repository = "me/dotfiles".to_string();
+        storage::save_to(&paths.config, &paths.backups, &edited).unwrap();
+
+        assert_eq!(switch_in(&paths, "default").unwrap(), work);
+        assert_eq!(switch_in(&paths, "personal").unwrap(), edited);
+    }
+
+    #[test]
+    fn test_rejects_unknown_and_invalid_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = paths(dir.path());
+
+        assert!(switch_in(&paths, "missing").is_err());
+        assert!(clone_in(&paths, "default", "../escape").is_err());
+        assert!(clone_in(&paths, "default", "default").is_err());
+    }
+}
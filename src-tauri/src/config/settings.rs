@@ -0,0 +1,705 @@
+use super::backup::BackupSettings;
+use crate::logging::LoggingSettings;
+use crate::notifications::NotificationSettings;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Complete user settings persisted in ~/.zeami/config.json
+/// Secrets (GitHub token, Claude API key) are never stored here; see config::keychain
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Settings {
+    pub github: GitHubSettings,
+    pub git: GitSettings,
+    pub terminal: TerminalSettings,
+    pub workflow: WorkflowSettings,
+    pub ui: UISettings,
+    pub claude: ClaudeSettings,
+    pub logging: LoggingSettings,
+    pub backup: BackupSettings,
+    pub updates: UpdateSettings,
+    pub telemetry: TelemetrySettings,
+}
+
+impl Settings {
+    /// Top-level sections whose values differ between `self` and `other`
+    pub fn changed_sections(&self, other: &Settings) -> Vec<&'static str> {
+        let mut sections = Vec::new();
+        if self.github != other.github {
+            sections.push("github");
+        }
+        if self.git != other.git {
+            sections.push("git");
+        }
+        if self.terminal != other.terminal {
+            sections.push("terminal");
+        }
+        if self.workflow != other.workflow {
+            sections.push("workflow");
+        }
+        if self.ui != other.ui {
+            sections.push("ui");
+        }
+        if self.claude != other.claude {
+            sections.push("claude");
+        }
+        if self.logging != other.logging {
+            sections.push("logging");
+        }
+        if self.backup != other.backup {
+            sections.push("backup");
+        }
+        if self.updates != other.updates {
+            sections.push("updates");
+        }
+        if self.telemetry != other.telemetry {
+            sections.push("telemetry");
+        }
+        sections
+    }
+
+    /// JSON Schema of the settings document, shared with the frontend for validation
+    pub fn json_schema() -> serde_json::Value {
+        serde_json::to_value(schemars::schema_for!(Settings)).unwrap_or_default()
+    }
+}
+
+/// GitHub repository and pull request settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct GitHubSettings {
+    /// Repository in `owner/name` form
+    pub repository: String,
+    /// Like accounts, ignored in project settings
+    pub api_url: String,
+    pub default_reviewers: Vec<String>,
+    pub pr_template: String,
+    pub auto_link_issues: bool,
+    pub auto_close_issue_on_pr: bool,
+    /// Poll the repository's event stream and emit `github-event` on issue, PR and comment activity
+    pub watch_events: bool,
+    /// Additional named accounts, e.g. a GitHub Enterprise server next to github.com
+    pub accounts: Vec<GitHubAccount>,
+    /// Account used for requests; None uses `api_url` and the profile's GitHub token
+    /// Usually set per project in .zeami/config.json
+    pub account: Option<String>,
+    /// Number of the owner's Projects v2 board the kanban view reads and moves
+    /// issues on; None uses the project most recently linked to the repository
+    pub project: Option<u64>,
+}
+
+/// A named GitHub account; its token is kept in the keychain under the account name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct GitHubAccount {
+    pub name: String,
+    pub api_url: String,
+}
+
+impl GitHubSettings {
+    /// The selected named account; None for the default account
+    pub fn selected_account(&self) -> anyhow::Result<Option<&GitHubAccount>> {
+        match &self.account {
+            Some(name) => self
+                .accounts
+                .iter()
+                .find(|account| &account.name == name)
+                .map(Some)
+                .ok_or_else(|| anyhow::anyhow!("Unknown GitHub account '{}'", name)),
+            None => Ok(None),
+        }
+    }
+
+    /// Add a named account, or update the API URL of an existing one
+    pub fn upsert_account(&mut self, account: GitHubAccount) {
+        match self.accounts.iter_mut().find(|a| a.name == account.name) {
+            Some(existing) => *existing = account,
+            None => self.accounts.push(account),
+        }
+    }
+
+    /// Remove a named account, deselecting it if it was selected
+    /// Returns false when no such account exists
+    pub fn remove_account(&mut self, name: &str) -> bool {
+        let before = self.accounts.len();
+        self.accounts.retain(|account| account.name != name);
+        if self.account.as_deref() == Some(name) {
+            self.account = None;
+        }
+        self.accounts.len() != before
+    }
+}
+
+impl Default for GitHubSettings {
+    fn default() -> Self {
+        Self {
+            repository: String::new(),
+            api_url: "https://api.github.com".to_string(),
+            default_reviewers: Vec::new(),
+            pr_template: String::new(),
+            auto_link_issues: true,
+            auto_close_issue_on_pr: true,
+            watch_events: true,
+            accounts: Vec::new(),
+            account: None,
+            project: None,
+        }
+    }
+}
+
+/// How branches are integrated into the base branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeStrategy {
+    Merge,
+    Squash,
+    Rebase,
+}
+
+/// What create_commit does with files caught by the large-file guard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LargeFileAction {
+    /// Commit anyway and emit `git-large-files`
+    Warn,
+    /// `git lfs track` the files and stage them again through LFS
+    Track,
+    /// Refuse the commit
+    Block,
+}
+
+/// Credentials for remotes whose URL contains `url`, overriding the default
+/// chain of git::credentials
+/// With a deploy key, the private key is kept in the keychain under `url`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RemoteCredential {
+    /// Part of the remote URL, e.g. `github.com/acme/infra` or `git.example.com`
+    pub url: String,
+    #[serde(default)]
+    pub source: CredentialSource,
+    /// User name for SSH and the credential helper; the one in the URL, else `git`
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialSource {
+    /// ssh-agent then the deploy key for SSH; the credential helper then the
+    /// GitHub token for HTTPS
+    #[default]
+    Auto,
+    SshAgent,
+    /// The private key stored in the keychain for this remote
+    DeployKey,
+    /// The git credential helper configured for the repository
+    CredentialHelper,
+    /// The GitHub token of the selected account
+    GithubToken,
+}
+
+/// Where WorkflowSettings::auto_commit puts WIP commits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoCommitTarget {
+    /// A `refs/zeami/wip/<branch>` ref that leaves the branch and index untouched
+    ShadowRef,
+    /// Commit everything onto the checked-out branch
+    Branch,
+}
+
+/// Local git behavior
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct GitSettings {
+    pub remote: String,
+    pub default_branch: String,
+    pub branch_prefix: String,
+    pub auto_create_branch: bool,
+    pub commit_template: String,
+    /// Add a `Signed-off-by` trailer to commits
+    pub sign_off: bool,
+    /// Reject commits whose message breaks the conventional commit rules (see git::commitlint);
+    /// commits made with git are checked once `zeami dev install-hooks` has been run
+    pub lint_commit_messages: bool,
+    pub sign_commits: bool,
+    pub gpg_key_id: Option<String>,
+    pub auto_fetch: bool,
+    /// Seconds between automatic fetches
+    pub fetch_interval: u64,
+    pub merge_strategy: MergeStrategy,
+    pub auto_clean_branches: bool,
+    /// Files above this many megabytes are caught by the large-file guard;
+    /// 0 turns the size check off
+    pub large_file_limit_mb: u64,
+    /// Globs of files that belong in Git LFS, e.g. `*.psd`
+    pub lfs_patterns: Vec<String>,
+    pub large_file_action: LargeFileAction,
+    /// Per-remote credential overrides; the longest matching `url` wins
+    pub remote_credentials: Vec<RemoteCredential>,
+}
+
+impl Default for GitSettings {
+    fn default() -> Self {
+        Self {
+            remote: "origin".to_string(),
+            default_branch: "main".to_string(),
+            branch_prefix: "issue-".to_string(),
+            auto_create_branch: true,
+            commit_template: String::new(),
+            sign_off: false,
+            lint_commit_messages: false,
+            sign_commits: false,
+            gpg_key_id: None,
+            auto_fetch: false,
+            fetch_interval: 300,
+            merge_strategy: MergeStrategy::Squash,
+            auto_clean_branches: false,
+            large_file_limit_mb: 50,
+            lfs_patterns: Vec::new(),
+            large_file_action: LargeFileAction::Warn,
+            remote_credentials: Vec::new(),
+        }
+    }
+}
+
+impl GitSettings {
+    /// Add an override, or replace the one with the same `url`
+    pub fn upsert_remote_credential(&mut self, credential: RemoteCredential) {
+        match self
+            .remote_credentials
+            .iter_mut()
+            .find(|c| c.url == credential.url)
+        {
+            Some(existing) => *existing = credential,
+            None => self.remote_credentials.push(credential),
+        }
+    }
+
+    /// Returns false when no override has this `url`
+    pub fn remove_remote_credential(&mut self, url: &str) -> bool {
+        let before = self.remote_credentials.len();
+        self.remote_credentials
+            .retain(|credential| credential.url != url);
+        self.remote_credentials.len() != before
+    }
+}
+
+/// Terminal appearance and shell
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct TerminalSettings {
+    /// Shell to launch; falls back to $SHELL when unset
+    pub shell: Option<String>,
+    pub font_family: String,
+    pub font_size: u16,
+    pub theme: String,
+    pub scrollback: u32,
+    pub cursor_style: String,
+    pub cursor_blink: bool,
+    /// Commands running at least this many seconds notify when they finish out
+    /// of view; needs shell integration, 0 disables
+    pub long_command_seconds: u64,
+    /// Remote hosts terminals can be opened on; see pty::ssh
+    pub ssh_hosts: Vec<SshHost>,
+    /// Command snippets of this profile; projects add their own, see snippets
+    pub snippets: Vec<Snippet>,
+}
+
+/// A remote host reached through the system's ssh client
+/// With key auth, the private key is kept in the keychain under the host name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SshHost {
+    /// Name passed as `host` to create_pty_session
+    pub name: String,
+    /// Host name, address or a Host alias from ~/.ssh/config
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub auth: SshAuth,
+    /// Directory to start the remote shell in; the login directory when unset
+    #[serde(default)]
+    pub remote_dir: Option<String>,
+}
+
+/// A named command line with `{{name}}` or `{{name:default}}` placeholders
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Snippet {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SshAuth {
+    /// Keys from the running ssh-agent and ~/.ssh, as plain `ssh` would use
+    #[default]
+    Agent,
+    /// The private key stored in the keychain for this host
+    Key,
+}
+
+impl TerminalSettings {
+    pub fn ssh_host(&self, name: &str) -> Option<&SshHost> {
+        self.ssh_hosts.iter().find(|host| host.name == name)
+    }
+
+    /// Add a host, or replace the one with the same name
+    pub fn upsert_ssh_host(&mut self, host: SshHost) {
+        match self.ssh_hosts.iter_mut().find(|h| h.name == host.name) {
+            Some(existing) => *existing = host,
+            None => self.ssh_hosts.push(host),
+        }
+    }
+
+    /// Returns false when no such host exists
+    pub fn remove_ssh_host(&mut self, name: &str) -> bool {
+        let before = self.ssh_hosts.len();
+        self.ssh_hosts.retain(|host| host.name != name);
+        self.ssh_hosts.len() != before
+    }
+
+    /// Add a snippet, or replace the one with the same name
+    pub fn upsert_snippet(&mut self, snippet: Snippet) {
+        match self.snippets.iter_mut().find(|s| s.name == snippet.name) {
+            Some(existing) => *existing = snippet,
+            None => self.snippets.push(snippet),
+        }
+    }
+
+    /// Returns false when no such snippet exists
+    pub fn remove_snippet(&mut self, name: &str) -> bool {
+        let before = self.snippets.len();
+        self.snippets.retain(|snippet| snippet.name != name);
+        self.snippets.len() != before
+    }
+}
+
+impl Default for TerminalSettings {
+    fn default() -> Self {
+        Self {
+            shell: None,
+            font_family: "Menlo, Monaco, 'Courier New', monospace".to_string(),
+            font_size: 14,
+            theme: "default".to_string(),
+            scrollback: 10000,
+            cursor_style: "block".to_string(),
+            cursor_blink: true,
+            long_command_seconds: 30,
+            ssh_hosts: Vec::new(),
+            snippets: Vec::new(),
+        }
+    }
+}
+
+/// Automated development workflow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct WorkflowSettings {
+    pub auto_run_tests: bool,
+    pub auto_run_tests_pattern: String,
+    /// Like lint_command and build_command, ignored in project settings
+    pub test_command: String,
+    /// JUnit XML report written by test_command, relative to the project root;
+    /// preferred over parsing the console output when present
+    pub test_report_path: Option<String>,
+    /// Project container (name or Compose service) to run test_command in
+    /// with docker exec; on this machine when unset
+    pub test_container: Option<String>,
+    /// Run for "lint" items of Definition of Done checklists
+    pub lint_command: String,
+    /// Coverage percentage required by DoD items that do not name one
+    pub coverage_threshold: u8,
+    pub auto_build: bool,
+    pub build_command: String,
+    /// Globs relative to the project root of the files a build produces
+    pub build_artifacts: Vec<String>,
+    pub auto_commit: bool,
+    /// Seconds between WIP commits
+    pub auto_commit_interval: u64,
+    pub auto_commit_message: String,
+    pub auto_commit_target: AutoCommitTarget,
+    pub auto_sync_progress: bool,
+    /// Seconds between progress comments on the linked issue
+    pub sync_progress_interval: u64,
+}
+
+impl Default for WorkflowSettings {
+    fn default() -> Self {
+        Self {
+            auto_run_tests: false,
+            auto_run_tests_pattern: "src/**/*".to_string(),
+            test_command: "npm test".to_string(),
+            test_report_path: None,
+            test_container: None,
+            lint_command: "npm run lint".to_string(),
+            coverage_threshold: 80,
+            auto_build: false,
+            build_command: "npm run build".to_string(),
+            build_artifacts: vec!["dist/**/*".to_string()],
+            auto_commit: false,
+            auto_commit_interval: 600,
+            auto_commit_message: "WIP: auto-commit".to_string(),
+            auto_commit_target: AutoCommitTarget::ShadowRef,
+            auto_sync_progress: false,
+            sync_progress_interval: 3600,
+        }
+    }
+}
+
+/// Application UI preferences
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct UISettings {
+    pub language: String,
+    pub theme: String,
+    /// Allow native OS notifications; when off, notifications only appear in the app
+    pub show_notifications: bool,
+    pub notification_sound: bool,
+    pub notifications: NotificationSettings,
+    pub show_welcome_screen: bool,
+    /// Shortcuts changed from the defaults, action id to chord; an empty
+    /// chord unbinds the action (see keymap)
+    pub keybindings: BTreeMap<String, String>,
+}
+
+impl Default for UISettings {
+    fn default() -> Self {
+        Self {
+            language: "en".to_string(),
+            theme: "dark".to_string(),
+            show_notifications: true,
+            notification_sound: true,
+            notifications: NotificationSettings::default(),
+            show_welcome_screen: true,
+            keybindings: BTreeMap::new(),
+        }
+    }
+}
+
+/// Service answering Claude requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LlmProviderKind {
+    /// Anthropic's Messages API
+    #[default]
+    Anthropic,
+    /// An OpenAI-compatible chat completions API, e.g. a local Ollama or
+    /// llama.cpp server
+    OpenAi,
+}
+
+/// Claude API request options
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ClaudeSettings {
+    /// Like base_url, ignored in project settings
+    pub provider: LlmProviderKind,
+    /// API root of the provider, e.g. `http://localhost:11434/v1` for Ollama;
+    /// empty uses the provider's public API
+    pub base_url: String,
+    /// Model name as the provider knows it, e.g. `llama3.1` with Ollama
+    pub model: String,
+    pub temperature: f32,
+    pub max_tokens: u32,
+    pub enable_streaming: bool,
+    pub enable_caching: bool,
+    /// Seconds a cached reply to an identical request is reused; 0 disables the
+    /// local response cache
+    pub response_cache_ttl: u64,
+    /// Estimated monthly spend in USD that triggers a warning; 0 disables it
+    pub monthly_budget_usd: f64,
+    /// Tools the agent may call without asking, e.g. `read_file`; ignored in
+    /// project settings
+    pub auto_approved_tools: Vec<String>,
+    /// May use {{repo}}, {{project}}, {{branch}}, {{current_issue}}, {{os}} and
+    /// {{date}}, filled in per request
+    pub system_prompt: String,
+    /// Sent after the system prompt; takes the same variables
+    pub custom_instructions: String,
+    /// Values sent as written although they look like secrets, e.g. keys in
+    /// test fixtures; usually set per project in .zeami/config.json
+    pub redaction_allowlist: Vec<String>,
+}
+
+impl Default for ClaudeSettings {
+    fn default() -> Self {
+        Self {
+            provider: LlmProviderKind::Anthropic,
+            base_url: String::new(),
+            model: "claude-sonnet-4-20250514".to_string(),
+            temperature: 0.7,
+            max_tokens: 4096,
+            enable_streaming: true,
+            enable_caching: true,
+            response_cache_ttl: 3600,
+            monthly_budget_usd: 0.0,
+            auto_approved_tools: Vec::new(),
+            system_prompt: String::new(),
+            custom_instructions: String::new(),
+            redaction_allowlist: Vec::new(),
+        }
+    }
+}
+
+/// Release channel the app updates from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    /// Pre-releases as well as stable releases
+    Beta,
+}
+
+impl UpdateChannel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+        }
+    }
+}
+
+/// App update checks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct UpdateSettings {
+    pub channel: UpdateChannel,
+    /// Check for updates in the background
+    pub auto_check: bool,
+    /// Hours between background checks
+    pub check_interval_hours: u64,
+    /// Download a found update right away instead of waiting for download_update
+    pub auto_download: bool,
+    /// Update manifest URL; `{{channel}}`, `{{target}}`, `{{arch}}` and
+    /// `{{current_version}}` are replaced
+    pub endpoint: String,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            channel: UpdateChannel::Stable,
+            auto_check: true,
+            check_interval_hours: 24,
+            auto_download: true,
+            endpoint:
+                "https://github.com/hiranotomo/zeami4/releases/download/updater/{{channel}}.json"
+                    .to_string(),
+        }
+    }
+}
+
+/// Anonymous usage statistics
+///
+/// Usage is always counted locally; nothing leaves the machine unless
+/// `enabled` is on and an endpoint is set.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    /// Upload daily rollups
+    pub enabled: bool,
+    /// URL the rollups are POSTed to
+    pub endpoint: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_github_accounts() {
+        let mut github = GitHubSettings::default();
+        github.upsert_account(GitHubAccount {
+            name: "work".to_string(),
+            api_url: "https://ghe.example.com/api/v3".to_string(),
+        });
+        github.upsert_account(GitHubAccount {
+            name: "work".to_string(),
+            api_url: "https://github.example.com/api/v3".to_string(),
+        });
+        github.account = Some("work".to_string());
+        assert_eq!(
+            github.selected_account().unwrap().unwrap().api_url,
+            "https://github.example.com/api/v3"
+        );
+
+        assert!(github.remove_account("work"));
+        assert!(!github.remove_account("work"));
+        assert_eq!(github.account, None);
+        assert!(github.selected_account().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_ssh_hosts() {
+        let mut terminal = TerminalSettings::default();
+        let host: SshHost =
+            serde_json::from_str(r#"{ "name": "dev", "host": "dev.example.com" }"#).unwrap();
+        assert_eq!(host.auth, SshAuth::Agent);
+        terminal.upsert_ssh_host(host.clone());
+        terminal.upsert_ssh_host(SshHost {
+            port: Some(2222),
+            ..host
+        });
+        assert_eq!(terminal.ssh_hosts.len(), 1);
+        assert_eq!(terminal.ssh_host("dev").unwrap().port, Some(2222));
+
+        assert!(terminal.remove_ssh_host("dev"));
+        assert!(!terminal.remove_ssh_host("dev"));
+        assert!(terminal.ssh_host("dev").is_none());
+    }
+
+    #[test]
+    fn test_partial_settings_fill_defaults() {
+        let settings: Settings =
+            serde_json::from_str(r#"{ "github": { "repository": "owner/repo" } }"#).unwrap();
+
+        assert_eq!(settings.github.repository, "owner/repo");
+        assert_eq!(settings.github.api_url, "https://api.github.com");
+        assert_eq!(settings.git, GitSettings::default());
+    }
+
+    #[test]
+    fn test_json_schema_covers_sections() {
+        let schema = Settings::json_schema();
+        let properties = schema["properties"].as_object().unwrap();
+
+        for section in [
+            "github",
+            "git",
+            "terminal",
+            "workflow",
+            "ui",
+            "claude",
+            "logging",
+            "backup",
+            "updates",
+            "telemetry",
+        ] {
+            assert!(properties.contains_key(section), "missing {}", section);
+        }
+    }
+
+    #[test]
+    fn test_changed_sections() {
+        let old = Settings::default();
+        let mut new = old.clone();
+        new.terminal.font_size = 16;
+        new.git.auto_fetch = true;
+
+        assert_eq!(old.changed_sections(&new), vec!["git", "terminal"]);
+        assert!(old.changed_sections(&old).is_empty());
+    }
+
+    #[test]
+    fn test_merge_strategy_serializes_lowercase() {
+        let value = serde_json::to_value(MergeStrategy::Squash).unwrap();
+        assert_eq!(value, serde_json::json!("squash"));
+    }
+}
@@ -0,0 +1,295 @@
+//! Docker containers of a project, through the docker CLI
+//!
+//! A container belongs to a project when Docker Compose started it from the
+//! project directory (the `com.docker.compose.project.working_dir` label).
+//! Terminals and tasks reach it with `docker exec`, so they run in the same
+//! PTY pipeline as local ones; the project directory is mapped to where it is
+//! mounted inside the container.
+
+use crate::pty;
+use crate::pty::command::{CommandOptions, CommandOutput};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// Compose label holding the directory a container's project was started from
+const WORKING_DIR_LABEL: &str = "com.docker.compose.project.working_dir";
+const SERVICE_LABEL: &str = "com.docker.compose.service";
+const PROJECT_LABEL: &str = "com.docker.compose.project";
+
+/// A running container of the project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Container {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    /// e.g. "Up 3 hours"
+    pub status: String,
+    /// Compose service, e.g. `app`
+    pub service: Option<String>,
+    /// Compose project name
+    pub compose_project: Option<String>,
+}
+
+impl Container {
+    /// Whether `name` refers to this container by name, compose service or id prefix
+    pub fn matches(&self, name: &str) -> bool {
+        self.name == name
+            || self.service.as_deref() == Some(name)
+            || (name.len() >= 4 && self.id.starts_with(name))
+    }
+}
+
+/// A bind mount of a container
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Mount {
+    #[serde(rename = "Source")]
+    pub source: PathBuf,
+    #[serde(rename = "Destination")]
+    pub destination: String,
+}
+
+/// Running containers started by Docker Compose from `project_root`
+pub fn list(project_root: &Path) -> Result<Vec<Container>> {
+    let output = docker(&["ps", "--no-trunc", "--format", "{{json .}}"])?;
+    let root = canonical(project_root);
+    Ok(parse_ps(&output)
+        .into_iter()
+        .filter(|(_, labels)| {
+            labels
+                .get(WORKING_DIR_LABEL)
+                .is_some_and(|dir| canonical(Path::new(dir)) == root)
+        })
+        .map(|(container, _)| container)
+        .collect())
+}
+
+/// The project container called `name`, or the first one when None
+pub fn resolve(project_root: &Path, name: Option<&str>) -> Result<Container> {
+    let containers = list(project_root)?;
+    let found = match name {
+        Some(name) => containers.into_iter().find(|c| c.matches(name)),
+        None => containers.into_iter().next(),
+    };
+    match (found, name) {
+        (Some(container), _) => Ok(container),
+        (None, Some(name)) => bail!("No running container {} for {:?}", name, project_root),
+        (None, None) => bail!(
+            "No running Docker Compose containers for {:?}",
+            project_root
+        ),
+    }
+}
+
+/// Where `host_dir` is inside `container`; None when it is not mounted there
+pub fn container_dir(container: &str, host_dir: &Path) -> Result<Option<String>> {
+    let output = docker(&["inspect", "--format", "{{json .Mounts}}", container])?;
+    let mounts: Vec<Mount> =
+        serde_json::from_str(output.trim()).context("Unexpected docker inspect output")?;
+    Ok(map_dir(&mounts, host_dir))
+}
+
+/// Arguments for `docker` running `command` in `container`
+///
+/// `workdir` None keeps the image's working directory. With `interactive`
+/// stdin stays attached, for terminals; either way a TTY is allocated so
+/// tools behave as they do in the user's own sessions.
+pub fn exec_args(
+    container: &str,
+    workdir: Option<&str>,
+    env: &BTreeMap<String, String>,
+    interactive: bool,
+    command: &[String],
+) -> Vec<String> {
+    let mut args = vec![
+        "exec".to_string(),
+        if interactive { "-it" } else { "-t" }.to_string(),
+    ];
+    if let Some(workdir) = workdir {
+        args.extend(["-w".to_string(), workdir.to_string()]);
+    }
+    for (key, value) in env {
+        args.extend(["-e".to_string(), format!("{}={}", key, value)]);
+    }
+    args.push(container.to_string());
+    args.extend(command.iter().cloned());
+    args
+}
+
+/// Run `command` through `sh` in a project container, like
+/// pty::command::run_command_with runs it on the host
+///
+/// `cwd` is the host directory, mapped to its mount in the container.
+pub fn run_command_with(
+    project_root: &Path,
+    container: Option<&str>,
+    command: &str,
+    cwd: &Path,
+    timeout: Duration,
+    options: &CommandOptions,
+) -> Result<CommandOutput> {
+    let container = resolve(project_root, container)?;
+    let workdir = container_dir(&container.id, cwd)?;
+    let shell = ["sh".to_string(), "-c".to_string(), command.to_string()];
+    let args = exec_args(
+        &container.id,
+        workdir.as_deref(),
+        &options.env,
+        false,
+        &shell,
+    );
+    // The variables were passed to the container; docker itself needs none
+    let options = CommandOptions {
+        env: BTreeMap::new(),
+        cancel: options.cancel.clone(),
+        process_id: options.process_id.clone(),
+    };
+    pty::command::run_program_with("docker", &args, cwd, timeout, &options)
+}
+
+fn docker(args: &[&str]) -> Result<String> {
+    let output = Command::new("docker")
+        .args(args)
+        .output()
+        .context("Failed to run docker; is Docker installed?")?;
+    if !output.status.success() {
+        bail!(
+            "docker {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[derive(Deserialize)]
+struct PsLine {
+    #[serde(rename = "ID")]
+    id: String,
+    #[serde(rename = "Names")]
+    names: String,
+    #[serde(rename = "Image")]
+    image: String,
+    #[serde(rename = "Status")]
+    status: String,
+    #[serde(rename = "Labels", default)]
+    labels: String,
+}
+
+/// Containers from `docker ps --format '{{json .}}'`, with their labels
+fn parse_ps(output: &str) -> Vec<(Container, BTreeMap<String, String>)> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<PsLine>(line).ok())
+        .map(|line| {
+            // Labels come as "key=value,key=value"
+            let labels: BTreeMap<String, String> = line
+                .labels
+                .split(',')
+                .filter_map(|label| label.split_once('='))
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+            let container = Container {
+                id: line.id,
+                name: line.names.split(',').next().unwrap_or_default().to_string(),
+                image: line.image,
+                status: line.status,
+                service: labels.get(SERVICE_LABEL).cloned(),
+                compose_project: labels.get(PROJECT_LABEL).cloned(),
+            };
+            (container, labels)
+        })
+        .collect()
+}
+
+/// Map `host_dir` through the most specific mount containing it
+fn map_dir(mounts: &[Mount], host_dir: &Path) -> Option<String> {
+    let host_dir = canonical(host_dir);
+    mounts
+        .iter()
+        .filter_map(|mount| {
+            let relative = host_dir.strip_prefix(canonical(&mount.source)).ok()?;
+            Some((mount, relative))
+        })
+        .max_by_key(|(mount, _)| mount.source.components().count())
+        .map(|(mount, relative)| {
+            let mut dir = mount.destination.trim_end_matches('/').to_string();
+            for component in relative.components() {
+                dir.push('/');
+                dir.push_str(&component.as_os_str().to_string_lossy());
+            }
+            if dir.is_empty() {
+                dir.push('/');
+            }
+            dir
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_docker_ps() {
+        let output = r#"{"ID":"abc123","Image":"node:20","Names":"web-app-1","Status":"Up 2 hours","Labels":"com.docker.compose.project=web,com.docker.compose.service=app,com.docker.compose.project.working_dir=/src/web"}
+{"ID":"def456","Image":"redis","Names":"cache","Status":"Up 1 minute","Labels":""}
+not json"#;
+        let containers = parse_ps(output);
+        assert_eq!(containers.len(), 2);
+        let (app, labels) = &containers[0];
+        assert_eq!(app.service.as_deref(), Some("app"));
+        assert_eq!(app.compose_project.as_deref(), Some("web"));
+        assert_eq!(labels[WORKING_DIR_LABEL], "/src/web");
+        assert!(app.matches("app") && app.matches("web-app-1") && app.matches("abc1"));
+        assert!(!app.matches("ab"));
+        assert_eq!(containers[1].0.service, None);
+    }
+
+    #[test]
+    fn test_maps_directories_through_mounts() {
+        let mounts = vec![
+            Mount {
+                source: PathBuf::from("/src/web"),
+                destination: "/app".to_string(),
+            },
+            Mount {
+                source: PathBuf::from("/src/web/data"),
+                destination: "/var/data/".to_string(),
+            },
+        ];
+        assert_eq!(
+            map_dir(&mounts, Path::new("/src/web")).as_deref(),
+            Some("/app")
+        );
+        assert_eq!(
+            map_dir(&mounts, Path::new("/src/web/packages/ui")).as_deref(),
+            Some("/app/packages/ui")
+        );
+        assert_eq!(
+            map_dir(&mounts, Path::new("/src/web/data/x")).as_deref(),
+            Some("/var/data/x")
+        );
+        assert_eq!(map_dir(&mounts, Path::new("/elsewhere")), None);
+    }
+
+    #[test]
+    fn test_builds_exec_arguments() {
+        let env = BTreeMap::from([("CI".to_string(), "1".to_string())]);
+        let command = ["sh".to_string(), "-c".to_string(), "npm test".to_string()];
+        assert_eq!(
+            exec_args("abc", Some("/app"), &env, false, &command).join(" "),
+            "exec -t -w /app -e CI=1 abc sh -c npm test"
+        );
+        assert_eq!(
+            exec_args("abc", None, &BTreeMap::new(), true, &["bash".to_string()]).join(" "),
+            "exec -it abc bash"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use crate::github::Repository;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use url::Url;
+
+/// URI scheme registered by the app
+pub const SCHEME: &str = "zeami";
+
+/// What a zeami:// link asks for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DeepLink {
+    /// zeami://issue/123, optionally with ?repo=owner/name
+    Issue {
+        number: u64,
+        repository: Option<String>,
+    },
+    /// zeami://open?path=/path/to/project
+    Open { path: PathBuf },
+    /// zeami://run-task/test, run in the project selected in the app
+    RunTask { name: String },
+}
+
+impl DeepLink {
+    pub fn parse(link: &str) -> Result<Self> {
+        let url = Url::parse(link.trim()).with_context(|| format!("Invalid link {:?}", link))?;
+        if url.scheme() != SCHEME {
+            bail!("Not a {}:// link: {}", SCHEME, link);
+        }
+        let segments: Vec<_> = url
+            .path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        let query = |key: &str| {
+            url.query_pairs()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.into_owned())
+                .filter(|value| !value.is_empty())
+        };
+
+        match (url.host_str().unwrap_or_default(), segments.as_slice()) {
+            ("issue", [number]) => {
+                let number = number
+                    .trim_start_matches('#')
+                    .parse()
+                    .with_context(|| format!("Invalid issue number {:?}", number))?;
+                let repository = match query("repo") {
+                    Some(repo) => Some(Repository::parse(&repo)?.to_string()),
+                    None => None,
+                };
+                Ok(Self::Issue { number, repository })
+            }
+            ("open", []) => {
+                let path = PathBuf::from(query("path").context("Missing ?path=")?);
+                if !path.is_absolute() {
+                    bail!("Project path must be absolute: {:?}", path);
+                }
+                Ok(Self::Open { path })
+            }
+            ("run-task", [name]) => Ok(Self::RunTask {
+                name: name.to_string(),
+            }),
+            _ => bail!("Unsupported link: {}", link),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_links() {
+        assert_eq!(
+            DeepLink::parse("zeami://issue/123").unwrap(),
+            DeepLink::Issue {
+                number: 123,
+                repository: None
+            }
+        );
+        assert_eq!(
+            DeepLink::parse("zeami://issue/42/?repo=hiranotomo/zeami4").unwrap(),
+            DeepLink::Issue {
+                number: 42,
+                repository: Some("hiranotomo/zeami4".into())
+            }
+        );
+        assert_eq!(
+            DeepLink::parse("zeami://open?path=%2Fhome%2Fme%2Fmy%20app").unwrap(),
+            DeepLink::Open {
+                path: PathBuf::from("/home/me/my app")
+            }
+        );
+        assert_eq!(
+            DeepLink::parse("zeami://run-task/test").unwrap(),
+            DeepLink::RunTask {
+                name: "test".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_links() {
+        for link in [
+            "https://issue/1",
+            "zeami://issue/abc",
+            "zeami://issue/1?repo=nope",
+            "zeami://open",
+            "zeami://open?path=relative/dir",
+            "zeami://run-task",
+            "zeami://settings",
+            "not a link",
+        ] {
+            assert!(DeepLink::parse(link).is_err(), "{}", link);
+        }
+    }
+}
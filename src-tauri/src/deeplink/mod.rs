@@ -0,0 +1,84 @@
+//! zeami:// links, e.g. from GitHub comments, that jump into a working session
+//!
+//! The scheme is registered with the OS at startup (through Info.plist on
+//! macOS). A link opened while the app runs is forwarded to the running
+//! instance and focuses the main window; it is announced as `deep-link` for
+//! the frontend to navigate, and run-task links also start the task. A link
+//! that launches the app arrives as a command line argument (see launch_links).
+
+mod link;
+
+pub use link::*;
+
+use crate::capabilities::{Capability, CapabilityRegistry};
+use crate::events::schema::v1;
+use crate::events::DEEP_LINK_EVENT_NAME;
+use crate::tasks::{TaskRunner, TaskTrigger, TaskWatcher};
+use anyhow::{Context, Result};
+use tauri::{AppHandle, Manager};
+
+/// Handle a zeami:// link opened by the user
+pub fn dispatch(app: &AppHandle, link: &str) {
+    let link = match DeepLink::parse(link) {
+        Ok(link) => link,
+        Err(e) => {
+            tracing::warn!("Ignoring deep link: {:#}", e);
+            return;
+        }
+    };
+    tracing::info!(?link, "Opening deep link");
+
+    if let Some(window) = app.get_window("main") {
+        if let Err(e) = window.unminimize().and_then(|_| window.set_focus()) {
+            tracing::warn!("Failed to focus the main window: {}", e);
+        }
+    }
+
+    let result = match &link {
+        DeepLink::RunTask { name } => run_task(app, name),
+        DeepLink::Issue { .. } | DeepLink::Open { .. } => Ok(()),
+    };
+    if let Err(e) = &result {
+        tracing::warn!(?link, "Deep link failed: {:#}", e);
+    }
+
+    let payload = v1::DeepLinkOpened {
+        link,
+        error: result.err().map(|e| format!("{:#}", e)),
+    };
+    if let Err(e) = app.emit_all(DEEP_LINK_EVENT_NAME, payload) {
+        tracing::error!("Failed to emit deep link: {}", e);
+    }
+}
+
+/// zeami:// links the app was launched with
+///
+/// These arrive before the frontend listens for `deep-link`, so it asks for
+/// them once it has loaded instead.
+pub fn launch_links() -> Vec<DeepLink> {
+    let prefix = format!("{}://", SCHEME);
+    std::env::args()
+        .skip(1)
+        .filter(|arg| arg.starts_with(&prefix))
+        .filter_map(|arg| {
+            DeepLink::parse(&arg)
+                .map_err(|e| tracing::warn!("Ignoring launch link: {:#}", e))
+                .ok()
+        })
+        .collect()
+}
+
+/// Run `name` in the project selected with `watch_tasks`
+///
+/// Links come from places like issue comments, so they may only run a task
+/// the open project defines and never pick the project themselves.
+fn run_task(app: &AppHandle, name: &str) -> Result<()> {
+    let root = app
+        .state::<TaskWatcher>()
+        .project()
+        .context("No project is open")?;
+    app.state::<CapabilityRegistry>()
+        .require(app, &root, Capability::RunTasks, "run_task")?;
+    app.state::<TaskRunner>()
+        .start(app, &root, name, TaskTrigger::DeepLink)
+}
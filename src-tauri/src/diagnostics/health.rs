@@ -0,0 +1,284 @@
+use crate::github::GitHubApiStatus;
+use crate::i18n;
+use crate::scheduler::ScheduledJob;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+/// Free space below which ~/.zeami is reported as a warning
+const LOW_DISK_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Free space below which saving settings, logs and caches is likely to fail
+const CRITICAL_DISK_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Remaining share of the GitHub rate limit reported as a warning
+const LOW_RATE_LIMIT_RATIO: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// State of one subsystem on the diagnostics page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubsystemHealth {
+    pub name: String,
+    pub status: HealthStatus,
+    /// Localized one-line summary
+    pub message: String,
+}
+
+/// Returned by get_app_health
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppHealth {
+    /// The worst status of any subsystem
+    pub status: HealthStatus,
+    pub checked_at: DateTime<Utc>,
+    pub subsystems: Vec<SubsystemHealth>,
+}
+
+impl AppHealth {
+    pub fn new(subsystems: Vec<SubsystemHealth>) -> Self {
+        Self {
+            status: subsystems
+                .iter()
+                .map(|subsystem| subsystem.status)
+                .max()
+                .unwrap_or(HealthStatus::Ok),
+            checked_at: Utc::now(),
+            subsystems,
+        }
+    }
+}
+
+fn subsystem(name: &str, status: HealthStatus, message: String) -> SubsystemHealth {
+    SubsystemHealth {
+        name: name.to_string(),
+        status,
+        message,
+    }
+}
+
+/// The task watcher, from its project and last scan error
+pub fn watcher(project: Option<&Path>, last_error: Option<&str>) -> SubsystemHealth {
+    match (project, last_error) {
+        (Some(_), Some(error)) => subsystem(
+            "watcher",
+            HealthStatus::Warning,
+            i18n::format("health.watcher.failing", &[("error", error)]),
+        ),
+        (Some(project), None) => subsystem(
+            "watcher",
+            HealthStatus::Ok,
+            i18n::format(
+                "health.watcher.watching",
+                &[("project", &project.display().to_string())],
+            ),
+        ),
+        (None, _) => subsystem(
+            "watcher",
+            HealthStatus::Ok,
+            i18n::text("health.watcher.idle"),
+        ),
+    }
+}
+
+pub fn pty(sessions: usize) -> SubsystemHealth {
+    subsystem(
+        "pty",
+        HealthStatus::Ok,
+        i18n::format("health.pty.sessions", &[("count", &sessions.to_string())]),
+    )
+}
+
+/// GitHub sign-in and rate limit, from github_api_status
+pub fn github(status: Result<GitHubApiStatus>) -> SubsystemHealth {
+    match status {
+        Err(e) => subsystem(
+            "github",
+            HealthStatus::Error,
+            i18n::format("health.github.failed", &[("error", &format!("{:#}", e))]),
+        ),
+        Ok(status) if !status.authenticated => subsystem(
+            "github",
+            HealthStatus::Warning,
+            i18n::text("health.github.signed_out"),
+        ),
+        Ok(status)
+            if (status.rate_limit.remaining as f64)
+                < status.rate_limit.limit as f64 * LOW_RATE_LIMIT_RATIO =>
+        {
+            subsystem(
+                "github",
+                HealthStatus::Warning,
+                i18n::format(
+                    "health.github.rate_limit_low",
+                    &[
+                        ("remaining", &status.rate_limit.remaining.to_string()),
+                        ("limit", &status.rate_limit.limit.to_string()),
+                    ],
+                ),
+            )
+        }
+        Ok(status) => subsystem(
+            "github",
+            HealthStatus::Ok,
+            i18n::format(
+                "health.github.signed_in",
+                &[("user", status.user.as_deref().unwrap_or_default())],
+            ),
+        ),
+    }
+}
+
+/// Whether secrets can be read, from listing the stored ones
+pub fn keychain(stored: Result<usize>) -> SubsystemHealth {
+    match stored {
+        Ok(count) => subsystem(
+            "keychain",
+            HealthStatus::Ok,
+            i18n::format("health.keychain.ok", &[("count", &count.to_string())]),
+        ),
+        Err(e) => subsystem(
+            "keychain",
+            HealthStatus::Error,
+            i18n::format("health.keychain.failed", &[("error", &format!("{:#}", e))]),
+        ),
+    }
+}
+
+/// Scheduled jobs whose last run failed are warnings
+pub fn scheduler(jobs: &[ScheduledJob]) -> SubsystemHealth {
+    let failing: Vec<&str> = jobs
+        .iter()
+        .filter(|job| job.last_error.is_some())
+        .map(|job| job.name.as_str())
+        .collect();
+    if failing.is_empty() {
+        subsystem(
+            "scheduler",
+            HealthStatus::Ok,
+            i18n::format("health.scheduler.ok", &[("count", &jobs.len().to_string())]),
+        )
+    } else {
+        subsystem(
+            "scheduler",
+            HealthStatus::Warning,
+            i18n::format("health.scheduler.failing", &[("jobs", &failing.join(", "))]),
+        )
+    }
+}
+
+/// Free space on the disk holding `dir`
+pub fn disk(dir: &Path) -> SubsystemHealth {
+    match free_space(dir) {
+        Some(free) => {
+            let status = if free < CRITICAL_DISK_BYTES {
+                HealthStatus::Error
+            } else if free < LOW_DISK_BYTES {
+                HealthStatus::Warning
+            } else {
+                HealthStatus::Ok
+            };
+            let megabytes = (free / (1024 * 1024)).to_string();
+            subsystem(
+                "disk",
+                status,
+                i18n::format("health.disk.free", &[("megabytes", &megabytes)]),
+            )
+        }
+        None => subsystem(
+            "disk",
+            HealthStatus::Warning,
+            i18n::text("health.disk.unknown"),
+        ),
+    }
+}
+
+/// Bytes available to the user on the disk holding `dir`
+#[cfg(not(target_os = "windows"))]
+fn free_space(dir: &Path) -> Option<u64> {
+    // ~/.zeami may not exist yet on first run
+    let existing = dir.ancestors().find(|dir| dir.exists())?;
+    let output = Command::new("df").arg("-Pk").arg(existing).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_df(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(target_os = "windows")]
+fn free_space(dir: &Path) -> Option<u64> {
+    let drive = dir.components().next()?.as_os_str().to_string_lossy();
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command"])
+        .arg(format!(
+            "(Get-PSDrive -Name '{}').Free",
+            drive.trim_end_matches([':', '\\'])
+        ))
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Available bytes from POSIX `df -Pk` output
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+fn parse_df(output: &str) -> Option<u64> {
+    let fields: Vec<&str> = output.lines().nth(1)?.split_whitespace().collect();
+    // Filesystem 1024-blocks Used Available Capacity Mounted-on; the
+    // filesystem name may contain spaces, so count from the end
+    let available = fields.get(fields.len().checked_sub(3)?)?;
+    available
+        .parse::<u64>()
+        .ok()
+        .map(|kilobytes| kilobytes * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::RateLimitInfo;
+
+    #[test]
+    fn test_parses_df_output() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/disk3s1s1   482797652  10265452 250143984       4% /\n";
+        assert_eq!(parse_df(output), Some(250143984 * 1024));
+        let spaced = "Filesystem 1024-blocks Used Available Capacity Mounted on\n\
+                      map auto home 0 0 0 100% /System/Volumes/Data/home\n";
+        assert_eq!(parse_df(spaced), Some(0));
+        assert_eq!(parse_df("df: /nowhere: No such file or directory\n"), None);
+    }
+
+    #[test]
+    fn test_overall_status_is_the_worst() {
+        let health = AppHealth::new(vec![pty(2), scheduler(&[]), keychain(Ok(1))]);
+        assert_eq!(health.status, HealthStatus::Ok);
+
+        let status = GitHubApiStatus {
+            account: None,
+            api_url: "https://api.github.com".into(),
+            authenticated: true,
+            user: Some("octocat".into()),
+            rate_limit: RateLimitInfo {
+                limit: 5000,
+                remaining: 12,
+                used: 4988,
+                reset: 0,
+            },
+        };
+        let health = AppHealth::new(vec![pty(0), github(Ok(status))]);
+        assert_eq!(health.status, HealthStatus::Warning);
+
+        let health = AppHealth::new(vec![
+            github(Err(anyhow::anyhow!("offline"))),
+            watcher(None, None),
+        ]);
+        assert_eq!(health.status, HealthStatus::Error);
+    }
+}
@@ -0,0 +1,113 @@
+//! Crash reports and diagnostic bundles for bug reports
+//!
+//! The panic hook saves every panic with its backtrace under
+//! ~/.zeami/diagnostics/crashes before the default hook runs.
+//! `create_diagnostic_bundle` zips the newest crash reports and log files with
+//! the recent in-memory logs, runtime stats and the settings into
+//! ~/.zeami/diagnostics, with secrets redacted. `get_app_health` reports the
+//! state of each subsystem for the diagnostics page (see health).
+
+mod bundle;
+pub mod health;
+mod redact;
+
+pub use bundle::{write_bundle, BundleContents, DiagnosticBundle};
+pub use health::{AppHealth, HealthStatus, SubsystemHealth};
+
+use crate::config::storage;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Crash reports kept on disk
+const MAX_CRASH_REPORTS: usize = 20;
+
+/// ~/.zeami/diagnostics
+pub fn diagnostics_dir() -> Result<PathBuf> {
+    Ok(storage::config_dir()?.join("diagnostics"))
+}
+
+/// ~/.zeami/diagnostics/crashes
+pub fn crash_dir() -> Result<PathBuf> {
+    Ok(diagnostics_dir()?.join("crashes"))
+}
+
+/// Save a crash report for every panic, then run the previous hook
+///
+/// Installed first thing in main so that panics during startup are kept too.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        let location = info
+            .location()
+            .map(|location| location.to_string())
+            .unwrap_or_default();
+        let report = crash_report(&message, &location, &Backtrace::force_capture());
+
+        match crash_dir().and_then(|dir| save_crash_report(&dir, &report)) {
+            Ok(path) => tracing::error!(?path, "Panicked at {}: {}", location, message),
+            Err(e) => eprintln!("Failed to save crash report: {:#}", e),
+        }
+        previous(info);
+    }));
+}
+
+fn crash_report(message: &str, location: &str, backtrace: &Backtrace) -> String {
+    let thread = std::thread::current();
+    format!(
+        "zeami {} ({} {})\ntime: {}\nthread: {}\npanicked at {}:\n{}\n\nbacktrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        Utc::now().to_rfc3339(),
+        thread.name().unwrap_or("<unnamed>"),
+        location,
+        message,
+        backtrace
+    )
+}
+
+/// Write `report` to `dir` and remove all but the newest reports
+fn save_crash_report(dir: &Path, report: &str) -> Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    let path = dir.join(format!(
+        "crash-{}-{}.txt",
+        Utc::now().format("%Y%m%d-%H%M%S%.3f"),
+        std::process::id()
+    ));
+    fs::write(&path, report).with_context(|| format!("Failed to write {:?}", path))?;
+
+    for old in bundle::newest_files(dir, usize::MAX)?
+        .into_iter()
+        .skip(MAX_CRASH_REPORTS)
+    {
+        let _ = fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_crash_report_keeps_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = crash_report("boom", "src/main.rs:1:1", &Backtrace::disabled());
+        assert!(report.contains("panicked at src/main.rs:1:1:\nboom"));
+
+        for _ in 0..MAX_CRASH_REPORTS + 2 {
+            save_crash_report(dir.path(), &report).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), MAX_CRASH_REPORTS);
+    }
+}
@@ -0,0 +1,371 @@
+//! Errors returned by Tauri commands
+//!
+//! Commands report a ZeamiError rather than a bare string so the frontend can
+//! act on it: `kind` picks the toast, `retryable` offers a retry button and
+//! `hint` tells the user what to fix. The kind is inferred from the cause
+//! chain of the underlying error (GitHub and LLM API status codes, git2 error
+//! codes, I/O error kinds), falling back to the area the command belongs to. Hints are
+//! in the current UI language.
+
+use crate::capabilities::CapabilityRequired;
+use crate::claude::client::ApiStatusError;
+use crate::claude::requests::Cancelled;
+use crate::claude::MissingApiKey;
+use crate::files::FileError;
+use crate::github::RateLimitExceeded;
+use crate::i18n;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// Settings could not be read, written or applied
+    Config,
+    /// Git operation failed
+    Git,
+    /// GitHub API request failed
+    #[serde(rename = "github")]
+    GitHub,
+    /// Terminal session failed
+    Pty,
+    /// Project task or task watcher failed
+    Task,
+    /// Plugin failed to load or its command failed
+    Plugin,
+    /// Docker is missing or a container command failed
+    Container,
+    /// Missing, invalid or insufficient credentials
+    Auth,
+    /// GitHub API rate limit exhausted
+    RateLimited,
+    /// GitHub or a git remote could not be reached
+    Network,
+    /// Session, file, branch, issue or repository does not exist
+    NotFound,
+    /// Uncommitted changes or merge conflicts are in the way
+    Conflict,
+    /// The project has not granted the capability the command needs
+    CapabilityRequired,
+    /// Arguments rejected before or by the operation
+    InvalidInput,
+    /// The user stopped the request before it finished
+    Cancelled,
+    /// Unexpected failure inside the app
+    Internal,
+}
+
+impl ErrorKind {
+    /// Whether the same request can succeed later without the user changing anything
+    fn retryable(self) -> bool {
+        matches!(self, ErrorKind::RateLimited | ErrorKind::Network)
+    }
+}
+
+/// Error of a Tauri command, serialized as `{ kind, message, retryable, hint }`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("{message}")]
+pub struct ZeamiError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub retryable: bool,
+    /// What the user can do about it
+    pub hint: Option<String>,
+}
+
+impl ZeamiError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            retryable: kind.retryable(),
+            hint: None,
+        }
+    }
+
+    /// Classify `error` by its cause chain; `fallback` applies when no cause is recognized
+    pub fn from_error(fallback: ErrorKind, error: impl Into<anyhow::Error>) -> Self {
+        let error = error.into();
+        let message = format!("{:#}", error);
+        match error.chain().find_map(classify) {
+            Some((kind, retryable, hint)) => Self {
+                kind,
+                message,
+                retryable,
+                hint: hint.map(i18n::text),
+            },
+            None => Self::new(fallback, message),
+        }
+    }
+
+    pub fn config(error: impl Into<anyhow::Error>) -> Self {
+        Self::from_error(ErrorKind::Config, error)
+    }
+
+    pub fn git(error: impl Into<anyhow::Error>) -> Self {
+        Self::from_error(ErrorKind::Git, error)
+    }
+
+    pub fn github(error: impl Into<anyhow::Error>) -> Self {
+        Self::from_error(ErrorKind::GitHub, error)
+    }
+
+    pub fn pty(error: impl Into<anyhow::Error>) -> Self {
+        Self::from_error(ErrorKind::Pty, error)
+    }
+
+    pub fn task(error: impl Into<anyhow::Error>) -> Self {
+        Self::from_error(ErrorKind::Task, error)
+    }
+
+    pub fn plugin(error: impl Into<anyhow::Error>) -> Self {
+        Self::from_error(ErrorKind::Plugin, error)
+    }
+
+    pub fn container(error: impl Into<anyhow::Error>) -> Self {
+        Self::from_error(ErrorKind::Container, error)
+    }
+
+    /// Prefix the message, e.g. "Failed to commit: ..."
+    pub fn context(mut self, context: impl Display) -> Self {
+        self.message = format!("{}: {}", context, self.message);
+        self
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// For commands not yet converted that call helpers returning ZeamiError
+impl From<ZeamiError> for String {
+    fn from(error: ZeamiError) -> Self {
+        error.message
+    }
+}
+
+/// Kind, whether retryable, and the i18n key of the hint
+type Classification = (ErrorKind, bool, Option<&'static str>);
+
+fn classify(cause: &(dyn std::error::Error + 'static)) -> Option<Classification> {
+    if cause.downcast_ref::<CapabilityRequired>().is_some() {
+        return Some((
+            ErrorKind::CapabilityRequired,
+            false,
+            Some("error.hint.capability_required"),
+        ));
+    }
+    if cause.downcast_ref::<Cancelled>().is_some() {
+        return Some((ErrorKind::Cancelled, false, None));
+    }
+    if cause.downcast_ref::<RateLimitExceeded>().is_some() {
+        return Some(rate_limited());
+    }
+    if let Some(e) = cause.downcast_ref::<FileError>() {
+        return Some(match e {
+            FileError::Changed(_) => (ErrorKind::Conflict, false, Some("error.hint.file_changed")),
+            _ => (ErrorKind::InvalidInput, false, None),
+        });
+    }
+    if let Some(e) = cause.downcast_ref::<octocrab::Error>() {
+        return classify_github(e);
+    }
+    if let Some(e) = cause.downcast_ref::<ApiStatusError>() {
+        return classify_llm(e.status);
+    }
+    if cause.downcast_ref::<MissingApiKey>().is_some() {
+        return Some((ErrorKind::Auth, false, Some("error.hint.llm_api_key")));
+    }
+    if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+        if e.is_connect() || e.is_timeout() || e.is_request() {
+            return Some((ErrorKind::Network, true, Some("error.hint.network")));
+        }
+    }
+    if let Some(e) = cause.downcast_ref::<git2::Error>() {
+        return classify_git(e);
+    }
+    if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+        return classify_io(e);
+    }
+    None
+}
+
+fn rate_limited() -> Classification {
+    (
+        ErrorKind::RateLimited,
+        true,
+        Some("error.hint.rate_limited"),
+    )
+}
+
+fn classify_github(error: &octocrab::Error) -> Option<Classification> {
+    match error {
+        octocrab::Error::GitHub { source, .. } => Some(match source.status_code.as_u16() {
+            401 => (ErrorKind::Auth, false, Some("error.hint.github_token")),
+            403 | 429 if source.message.to_lowercase().contains("rate limit") => rate_limited(),
+            403 => (ErrorKind::Auth, false, Some("error.hint.github_scopes")),
+            404 => (
+                ErrorKind::NotFound,
+                false,
+                Some("error.hint.github_repository_access"),
+            ),
+            422 => (ErrorKind::InvalidInput, false, None),
+            500..=599 => (ErrorKind::GitHub, true, None),
+            _ => (ErrorKind::GitHub, false, None),
+        }),
+        octocrab::Error::Hyper { .. }
+        | octocrab::Error::Service { .. }
+        | octocrab::Error::Http { .. } => {
+            Some((ErrorKind::Network, true, Some("error.hint.network")))
+        }
+        _ => None,
+    }
+}
+
+/// Status of a Claude or OpenAI-compatible API response
+fn classify_llm(status: u16) -> Option<Classification> {
+    Some(match status {
+        401 | 403 => (ErrorKind::Auth, false, Some("error.hint.llm_api_key")),
+        429 => (
+            ErrorKind::RateLimited,
+            true,
+            Some("error.hint.llm_rate_limited"),
+        ),
+        404 => (ErrorKind::NotFound, false, None),
+        400..=499 => (ErrorKind::InvalidInput, false, None),
+        500..=599 => (ErrorKind::Network, true, None),
+        _ => return None,
+    })
+}
+
+fn classify_git(error: &git2::Error) -> Option<Classification> {
+    use git2::{ErrorClass, ErrorCode};
+
+    match error.code() {
+        ErrorCode::Auth => {
+            return Some((ErrorKind::Auth, false, Some("error.hint.git_credentials")))
+        }
+        ErrorCode::NotFound => return Some((ErrorKind::NotFound, false, None)),
+        ErrorCode::Conflict
+        | ErrorCode::MergeConflict
+        | ErrorCode::Uncommitted
+        | ErrorCode::Unmerged => {
+            return Some((ErrorKind::Conflict, false, Some("error.hint.git_conflict")))
+        }
+        ErrorCode::Locked => return Some((ErrorKind::Git, true, Some("error.hint.git_locked"))),
+        _ => {}
+    }
+    match error.class() {
+        ErrorClass::Net | ErrorClass::Ssh | ErrorClass::Http => {
+            Some((ErrorKind::Network, true, Some("error.hint.git_network")))
+        }
+        _ => None,
+    }
+}
+
+fn classify_io(error: &std::io::Error) -> Option<Classification> {
+    use std::io::ErrorKind as Io;
+
+    match error.kind() {
+        Io::NotFound => Some((ErrorKind::NotFound, false, None)),
+        Io::TimedOut | Io::Interrupted | Io::WouldBlock => Some((ErrorKind::Internal, true, None)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use git2::ErrorCode;
+
+    #[test]
+    fn test_unrecognized_errors_use_the_fallback_kind() {
+        let error = ZeamiError::git(anyhow::anyhow!("detached HEAD"));
+        assert_eq!(error.kind, ErrorKind::Git);
+        assert_eq!(error.message, "detached HEAD");
+        assert!(!error.retryable);
+        assert_eq!(error.hint, None);
+    }
+
+    #[test]
+    fn test_classifies_by_the_cause_chain() {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+        let error = ZeamiError::config(anyhow::Error::new(io).context("Failed to read export"));
+        assert_eq!(error.kind, ErrorKind::NotFound);
+        assert_eq!(error.message, "Failed to read export: no such file");
+
+        let git = git2::Error::new(ErrorCode::Auth, git2::ErrorClass::Http, "auth failed");
+        let error = ZeamiError::git(Err::<(), _>(git).context("Failed to fetch").unwrap_err());
+        assert_eq!(error.kind, ErrorKind::Auth);
+        assert!(error.hint.is_some());
+
+        let git = git2::Error::new(ErrorCode::GenericError, git2::ErrorClass::Net, "timed out");
+        let error = ZeamiError::git(git);
+        assert_eq!(error.kind, ErrorKind::Network);
+        assert!(error.retryable);
+
+        let error = ZeamiError::from_error(ErrorKind::Network, Cancelled);
+        assert_eq!(error.kind, ErrorKind::Cancelled);
+        assert!(!error.retryable);
+    }
+
+    #[test]
+    fn test_classifies_llm_api_statuses() {
+        let refused = |status| {
+            let error = ApiStatusError {
+                status,
+                message: format!("Claude API returned {}", status),
+            };
+            ZeamiError::from_error(ErrorKind::Internal, error)
+        };
+
+        let error = refused(401);
+        assert_eq!(error.kind, ErrorKind::Auth);
+        assert!(!error.retryable);
+        assert!(error.hint.is_some());
+        assert_eq!(refused(429).kind, ErrorKind::RateLimited);
+        assert!(refused(429).retryable);
+        assert_eq!(refused(400).kind, ErrorKind::InvalidInput);
+        assert!(!refused(400).retryable);
+        assert_eq!(refused(529).kind, ErrorKind::Network);
+        assert!(refused(529).retryable);
+
+        let error = ZeamiError::from_error(ErrorKind::Internal, MissingApiKey);
+        assert_eq!(error.kind, ErrorKind::Auth);
+        assert!(!error.retryable);
+    }
+
+    #[test]
+    fn test_rate_limits_are_retryable() {
+        let error = ZeamiError::github(RateLimitExceeded { reset_in: 30 });
+        assert_eq!(error.kind, ErrorKind::RateLimited);
+        assert!(error.retryable);
+        assert!(error.hint.is_some());
+    }
+
+    #[test]
+    fn test_context_prefixes_the_message() {
+        let error = ZeamiError::new(ErrorKind::NotFound, "Session not found: 1")
+            .context("Failed to write to PTY");
+        assert_eq!(
+            error.message,
+            "Failed to write to PTY: Session not found: 1"
+        );
+    }
+
+    #[test]
+    fn test_serializes_for_the_frontend() {
+        let error = ZeamiError::new(ErrorKind::RateLimited, "slow down").with_hint("wait");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "kind": "rate_limited",
+                "message": "slow down",
+                "retryable": true,
+                "hint": "wait",
+            })
+        );
+    }
+}
@@ -0,0 +1,78 @@
+pub mod schema;
+
+pub use schema::EventSchemaInfo;
+
+/// Event carrying PTY output chunks to the frontend
+pub const PTY_OUTPUT_EVENT_NAME: &str = "pty-output";
+
+/// Event sent after the current settings are replaced
+pub const SETTINGS_CHANGED_EVENT_NAME: &str = "settings-changed";
+
+/// Event sent when config.json was unreadable and settings were recovered at startup
+pub const SETTINGS_RECOVERED_EVENT_NAME: &str = "settings-recovered";
+
+/// Event reporting the progress of pull request create, merge and review requests
+pub const PULL_REQUEST_PROGRESS_EVENT_NAME: &str = "pull-request-progress";
+
+/// Event sent after each background GitHub sync pass
+pub const GITHUB_SYNC_EVENT_NAME: &str = "github-sync";
+
+/// Event sent for new issue, pull request and comment activity on the watched repository
+pub const GITHUB_EVENT_NAME: &str = "github-event";
+
+/// Event sent when the CI state of the watched repository's HEAD commit changes
+pub const CI_STATUS_CHANGED_EVENT_NAME: &str = "ci-status-changed";
+
+/// Event sent after a queued offline GitHub mutation was replayed
+pub const GITHUB_MUTATION_REPLAYED_EVENT_NAME: &str = "github-mutation-replayed";
+
+/// Event sent after a commit is created from the app
+pub const GIT_COMMIT_EVENT_NAME: &str = "git-commit";
+
+/// Event sent after the active repository's remote was fetched
+pub const GIT_REMOTE_UPDATED_EVENT_NAME: &str = "git-remote-updated";
+
+/// Event sent when a commit includes files caught by the large-file guard
+pub const GIT_LARGE_FILES_EVENT_NAME: &str = "git-large-files";
+
+/// Event carrying Claude reply text as it streams in
+pub const CLAUDE_STREAM_EVENT_NAME: &str = "claude-stream";
+
+/// Event sent once a month when estimated Claude spending passes ClaudeSettings::monthly_budget_usd
+pub const CLAUDE_BUDGET_EXCEEDED_EVENT_NAME: &str = "claude-budget-exceeded";
+
+/// Event asking the user to approve a tool call requested by Claude
+pub const CLAUDE_TOOL_APPROVAL_EVENT_NAME: &str = "claude-tool-approval";
+
+/// Event sent for every notification delivered natively or in-app
+pub const NOTIFICATION_EVENT_NAME: &str = "notification";
+
+/// Event sent when a task from .zeami/tasks.toml is queued, starts or finishes
+pub const TASK_STATUS_EVENT_NAME: &str = "task-status";
+
+/// Event sent when a build_command run starts and when it finishes
+pub const BUILD_STATUS_EVENT_NAME: &str = "build-status";
+
+/// Event reporting each step of complete_issue
+pub const WORKFLOW_STEP_EVENT_NAME: &str = "workflow-step";
+
+/// Event sent when a project's .zeami/state.json changes
+pub const STATE_CHANGED_EVENT_NAME: &str = "state-changed";
+
+/// Event sent when a zeami:// link is opened
+pub const DEEP_LINK_EVENT_NAME: &str = "deep-link";
+
+/// Event asking the user to grant a capability a command needs
+pub const CAPABILITY_REQUESTED_EVENT_NAME: &str = "capability-requested";
+
+/// Event sent when a process started in a terminal begins listening on a port
+pub const DEV_SERVER_DETECTED_EVENT_NAME: &str = "dev-server-detected";
+
+/// Event sent every few seconds with the CPU and memory of Zeami, its sessions and tasks
+pub const RESOURCE_STATS_EVENT_NAME: &str = "resource-stats";
+
+/// Event carrying the matches of search_project in one file
+pub const SEARCH_RESULT_EVENT_NAME: &str = "search-result";
+
+/// Event sent when an app update has been downloaded and can be installed
+pub const UPDATE_DOWNLOADED_EVENT_NAME: &str = "update-downloaded";
@@ -0,0 +1,461 @@
+//! Versioned payload definitions for every event emitted to the frontend
+//!
+//! Payload structs live in a version module (`v1`, ...). A breaking change to
+//! any payload gets a new module and a bump of EVENT_SCHEMA_VERSION, so the
+//! TypeScript side can detect a mismatch instead of silently misreading events.
+
+use serde::{Deserialize, Serialize};
+
+/// Current version of the event payload schema
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+pub mod v1 {
+    use crate::capabilities::Capability;
+    use crate::claude::tools::Tool;
+    use crate::claude::usage::BudgetAlert;
+    use crate::claude::Usage;
+    use crate::config::storage::SettingsRecovery;
+    use crate::config::{LargeFileAction, Settings};
+    use crate::deeplink::DeepLink;
+    use crate::git::commit::CommitInfo;
+    use crate::git::large_files::LargeFile;
+    use crate::git::remote::RemoteUpdate;
+    use crate::github::checks::{BranchChecks, CiState};
+    use crate::monitoring::ResourceStats as ResourceSample;
+    use crate::netwatch::DevServer;
+    use crate::notifications::NotificationRecord;
+    use crate::search::SearchResult as SearchFileResult;
+    use crate::state::ProjectSnapshot;
+    use crate::tasks::TaskStatus as TaskRunStatus;
+    use crate::updater::DownloadedUpdate;
+    use crate::workflow::build_report::BuildStatus as BuildRunStatus;
+    use serde::{Deserialize, Serialize};
+    use std::path::PathBuf;
+
+    /// Payload of `pty-output`
+    /// `closed` is true on the final event after the PTY reaches EOF
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct PtyOutput {
+        pub session_id: String,
+        pub data: String,
+        pub closed: bool,
+    }
+
+    impl PtyOutput {
+        pub fn data(session_id: &str, data: String) -> Self {
+            Self {
+                session_id: session_id.to_string(),
+                data,
+                closed: false,
+            }
+        }
+
+        pub fn closed(session_id: &str) -> Self {
+            Self {
+                session_id: session_id.to_string(),
+                data: String::new(),
+                closed: true,
+            }
+        }
+    }
+
+    /// Payload of `settings-changed`
+    /// `sections` lists the top-level settings sections that changed
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct SettingsChanged {
+        pub sections: Vec<String>,
+        pub settings: Settings,
+    }
+
+    /// Payload of `settings-recovered`
+    pub type SettingsRecovered = SettingsRecovery;
+
+    /// Payload of `github-sync`
+    /// `updated` counts cached issues and pull requests that were added or changed
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct GitHubSync {
+        pub repository: String,
+        pub updated: usize,
+        pub not_modified: bool,
+        pub error: Option<String>,
+    }
+
+    /// Payload of `github-event`
+    /// `kind` is the GitHub event type (e.g. `IssueCommentEvent`), `action` its payload action
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct GitHubEvent {
+        pub repository: String,
+        pub kind: String,
+        pub action: String,
+        pub number: Option<u64>,
+        pub pull_request: bool,
+        pub actor: String,
+        pub created_at: String,
+    }
+
+    /// Payload of `github-mutation-replayed`
+    /// Neither applied nor in conflict means the attempt failed and will be retried
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct GitHubMutationReplayed {
+        pub id: String,
+        pub repository: String,
+        pub applied: bool,
+        pub conflict: Option<String>,
+        pub error: Option<String>,
+    }
+
+    /// Payload of `git-commit`
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct GitCommit {
+        pub project_root: PathBuf,
+        pub commit: CommitInfo,
+    }
+
+    /// Payload of `git-remote-updated`
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct GitRemoteUpdated {
+        pub project_root: PathBuf,
+        pub update: RemoteUpdate,
+    }
+
+    /// Payload of `git-large-files`
+    /// With the Track action the files were moved to Git LFS before committing
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct GitLargeFiles {
+        pub project_root: PathBuf,
+        pub commit_id: String,
+        pub action: LargeFileAction,
+        pub files: Vec<LargeFile>,
+    }
+
+    /// Payload of `ci-status-changed`
+    /// `previous` is None on the first report after watching starts
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct CiStatusChanged {
+        pub repository: String,
+        pub previous: Option<CiState>,
+        pub checks: BranchChecks,
+    }
+
+    /// Payload of `claude-stream`
+    /// The last event of a reply has `done` set, with `usage` on success, `error`
+    /// on failure, or `cancelled` set when cancel_claude_request stopped it
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ClaudeStream {
+        pub conversation_id: String,
+        pub delta: String,
+        pub done: bool,
+        pub stop_reason: Option<String>,
+        pub usage: Option<Usage>,
+        pub error: Option<String>,
+        #[serde(default)]
+        pub cancelled: bool,
+    }
+
+    impl ClaudeStream {
+        pub fn delta(conversation_id: &str, delta: &str) -> Self {
+            Self {
+                conversation_id: conversation_id.to_string(),
+                delta: delta.to_string(),
+                done: false,
+                stop_reason: None,
+                usage: None,
+                error: None,
+                cancelled: false,
+            }
+        }
+
+        pub fn done(conversation_id: &str, stop_reason: Option<String>, usage: Usage) -> Self {
+            Self {
+                conversation_id: conversation_id.to_string(),
+                delta: String::new(),
+                done: true,
+                stop_reason,
+                usage: Some(usage),
+                error: None,
+                cancelled: false,
+            }
+        }
+
+        pub fn failed(conversation_id: &str, error: String) -> Self {
+            Self {
+                conversation_id: conversation_id.to_string(),
+                delta: String::new(),
+                done: true,
+                stop_reason: None,
+                usage: None,
+                error: Some(error),
+                cancelled: false,
+            }
+        }
+
+        pub fn cancelled(conversation_id: &str) -> Self {
+            Self {
+                conversation_id: conversation_id.to_string(),
+                delta: String::new(),
+                done: true,
+                stop_reason: None,
+                usage: None,
+                error: None,
+                cancelled: true,
+            }
+        }
+    }
+
+    /// Payload of `claude-tool-approval`
+    /// Answer with respond_tool_approval(request_id, approved)
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ClaudeToolApproval {
+        pub request_id: String,
+        pub conversation_id: String,
+        pub tool: Tool,
+        pub input: serde_json::Value,
+    }
+
+    /// Payload of `claude-budget-exceeded`
+    pub type ClaudeBudgetExceeded = BudgetAlert;
+
+    /// Payload of `notification`
+    pub type Notification = NotificationRecord;
+
+    /// Payload of `task-status`
+    pub type TaskStatus = TaskRunStatus;
+
+    /// Payload of `build-status`
+    pub type BuildStatus = BuildRunStatus;
+
+    /// Pull request operation reported by `pull-request-progress`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum PullRequestOperation {
+        Create,
+        Merge,
+        RequestReview,
+    }
+
+    /// Stage of a pull request operation
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum ProgressStage {
+        Started,
+        Step,
+        Completed,
+        Failed,
+    }
+
+    /// Payload of `pull-request-progress`
+    /// `number` is None until a new pull request has been created
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct PullRequestProgress {
+        pub operation: PullRequestOperation,
+        pub stage: ProgressStage,
+        pub number: Option<u64>,
+        pub message: String,
+    }
+
+    impl PullRequestProgress {
+        pub fn started(operation: PullRequestOperation, number: Option<u64>) -> Self {
+            Self {
+                operation,
+                stage: ProgressStage::Started,
+                number,
+                message: String::new(),
+            }
+        }
+
+        pub fn step(operation: PullRequestOperation, number: Option<u64>, message: String) -> Self {
+            Self {
+                operation,
+                stage: ProgressStage::Step,
+                number,
+                message,
+            }
+        }
+
+        pub fn completed(operation: PullRequestOperation, number: Option<u64>) -> Self {
+            Self {
+                operation,
+                stage: ProgressStage::Completed,
+                number,
+                message: String::new(),
+            }
+        }
+
+        pub fn failed(operation: PullRequestOperation, number: Option<u64>, error: String) -> Self {
+            Self {
+                operation,
+                stage: ProgressStage::Failed,
+                number,
+                message: error,
+            }
+        }
+    }
+
+    /// Step of the complete_issue or sync_branch_with_base workflow
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum WorkflowStepKind {
+        VerifyDod,
+        RunTests,
+        Push,
+        OpenPullRequest,
+        Fetch,
+        CheckConflicts,
+        Merge,
+        Rebase,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum StepState {
+        Pending,
+        Running,
+        Done,
+        Failed,
+    }
+
+    /// Payload of `workflow-step`
+    /// Every step is first reported as pending so the UI can lay out the checklist
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct WorkflowStep {
+        /// Same for all steps of one run
+        pub run_id: String,
+        pub step: WorkflowStepKind,
+        /// Name of the step in the UI language
+        pub label: String,
+        pub state: StepState,
+        pub message: String,
+    }
+
+    /// Payload of `state-changed`
+    pub type StateChanged = ProjectSnapshot;
+
+    /// Payload of `deep-link`
+    /// `error` is set when a run-task link could not start its task
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct DeepLinkOpened {
+        pub link: DeepLink,
+        pub error: Option<String>,
+    }
+
+    /// Payload of `capability-requested`
+    /// `command` was refused; grant_capability lets it run when retried
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct CapabilityRequested {
+        pub project_root: PathBuf,
+        pub capability: Capability,
+        pub description: String,
+        pub command: String,
+    }
+
+    /// Payload of `dev-server-detected`
+    pub type DevServerDetected = DevServer;
+
+    /// Payload of `resource-stats`
+    pub type ResourceStats = ResourceSample;
+
+    /// Payload of `search-result`
+    pub type SearchResult = SearchFileResult;
+
+    /// Payload of `update-downloaded`
+    pub type UpdateDownloaded = DownloadedUpdate;
+}
+
+/// Name and payload type of an emitted event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventDescriptor {
+    pub name: String,
+    pub payload: String,
+}
+
+/// Schema description returned to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventSchemaInfo {
+    pub version: u32,
+    pub events: Vec<EventDescriptor>,
+}
+
+impl EventSchemaInfo {
+    pub fn current() -> Self {
+        let events = [
+            (super::PTY_OUTPUT_EVENT_NAME, "v1::PtyOutput"),
+            (super::SETTINGS_CHANGED_EVENT_NAME, "v1::SettingsChanged"),
+            (
+                super::SETTINGS_RECOVERED_EVENT_NAME,
+                "v1::SettingsRecovered",
+            ),
+            (
+                super::PULL_REQUEST_PROGRESS_EVENT_NAME,
+                "v1::PullRequestProgress",
+            ),
+            (super::GITHUB_SYNC_EVENT_NAME, "v1::GitHubSync"),
+            (super::GITHUB_EVENT_NAME, "v1::GitHubEvent"),
+            (super::CI_STATUS_CHANGED_EVENT_NAME, "v1::CiStatusChanged"),
+            (
+                super::GITHUB_MUTATION_REPLAYED_EVENT_NAME,
+                "v1::GitHubMutationReplayed",
+            ),
+            (super::GIT_COMMIT_EVENT_NAME, "v1::GitCommit"),
+            (super::GIT_REMOTE_UPDATED_EVENT_NAME, "v1::GitRemoteUpdated"),
+            (super::GIT_LARGE_FILES_EVENT_NAME, "v1::GitLargeFiles"),
+            (super::CLAUDE_STREAM_EVENT_NAME, "v1::ClaudeStream"),
+            (
+                super::CLAUDE_BUDGET_EXCEEDED_EVENT_NAME,
+                "v1::ClaudeBudgetExceeded",
+            ),
+            (
+                super::CLAUDE_TOOL_APPROVAL_EVENT_NAME,
+                "v1::ClaudeToolApproval",
+            ),
+            (super::NOTIFICATION_EVENT_NAME, "v1::Notification"),
+            (super::TASK_STATUS_EVENT_NAME, "v1::TaskStatus"),
+            (super::BUILD_STATUS_EVENT_NAME, "v1::BuildStatus"),
+            (super::WORKFLOW_STEP_EVENT_NAME, "v1::WorkflowStep"),
+            (super::STATE_CHANGED_EVENT_NAME, "v1::StateChanged"),
+            (super::DEEP_LINK_EVENT_NAME, "v1::DeepLinkOpened"),
+            (
+                super::CAPABILITY_REQUESTED_EVENT_NAME,
+                "v1::CapabilityRequested",
+            ),
+            (
+                super::DEV_SERVER_DETECTED_EVENT_NAME,
+                "v1::DevServerDetected",
+            ),
+            (super::RESOURCE_STATS_EVENT_NAME, "v1::ResourceStats"),
+            (super::SEARCH_RESULT_EVENT_NAME, "v1::SearchResult"),
+            (super::UPDATE_DOWNLOADED_EVENT_NAME, "v1::UpdateDownloaded"),
+        ]
+        .into_iter()
+        .map(|(name, payload)| EventDescriptor {
+            name: name.to_string(),
+            payload: payload.to_string(),
+        })
+        .collect();
+
+        Self {
+            version: EVENT_SCHEMA_VERSION,
+            events,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pty_output_payload_shape() {
+        let payload = serde_json::to_value(v1::PtyOutput::closed("abc")).unwrap();
+        assert_eq!(
+            payload,
+            serde_json::json!({ "session_id": "abc", "data": "", "closed": true })
+        );
+    }
+
+    #[test]
+    fn test_schema_lists_pty_output() {
+        let info = EventSchemaInfo::current();
+        assert_eq!(info.version, EVENT_SCHEMA_VERSION);
+        assert!(info.events.iter().any(|e| e.name == "pty-output"));
+    }
+}
@@ -0,0 +1,422 @@
+//! Project files for the frontend, sandboxed to the project root
+//!
+//! Paths are relative to the project root and may not leave it: `..`,
+//! absolute paths and symlinks pointing outside are rejected, as is anything
+//! under `.git`. Writes and patches first copy the files they replace to
+//! `.zeami/file-backups/<timestamp>/`, keeping the newest MAX_BACKUPS sets.
+
+pub mod patch;
+
+use crate::config::atomic::write_atomic;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Largest file read or written, so a stray build artifact cannot stall the UI
+pub const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+/// Largest patch accepted by apply_patch
+pub const MAX_PATCH_BYTES: usize = 1024 * 1024;
+/// Backup sets kept per project
+pub const MAX_BACKUPS: usize = 20;
+
+const BACKUP_DIR: &str = ".zeami/file-backups";
+
+/// Why a file request was refused
+#[derive(Debug, thiserror::Error)]
+pub enum FileError {
+    #[error("{0} is outside the project")]
+    OutsideProject(String),
+    #[error("{path} is {size} bytes; the limit is {limit}")]
+    TooLarge { path: String, size: u64, limit: u64 },
+    #[error("{0} is not a UTF-8 text file")]
+    NotText(String),
+    #[error("{0} has changed since it was read")]
+    Changed(String),
+}
+
+/// A text file of the project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectFile {
+    /// Relative to the project root
+    pub path: String,
+    pub content: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    /// Hash of the content; pass it back to write to detect concurrent edits
+    pub version: String,
+}
+
+/// Outcome of a write or patch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteResult {
+    pub files: Vec<ChangedFile>,
+    /// Backup set holding the previous contents; None when only new files were written
+    pub backup: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedFile {
+    pub path: String,
+    pub change: FileChange,
+    /// Version after the change; None for deleted files
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChange {
+    Created,
+    Modified,
+    Deleted,
+}
+
+/// Absolute path of `path` inside `project_root`, rejecting anything outside it
+pub fn resolve(project_root: &Path, path: &str) -> Result<PathBuf> {
+    let outside = || FileError::OutsideProject(path.to_string());
+    let relative = Path::new(path);
+    let mut normal = relative.components().filter(|c| *c != Component::CurDir);
+    match normal.next() {
+        Some(Component::Normal(first)) if first != ".git" => {}
+        _ => return Err(outside().into()),
+    }
+    if !normal.all(|c| matches!(c, Component::Normal(_))) {
+        return Err(outside().into());
+    }
+
+    let root = fs::canonicalize(project_root)
+        .with_context(|| format!("Project {:?} does not exist", project_root))?;
+    let full = root.join(relative);
+    // Symlinks may lead out; check where the nearest existing ancestor really is
+    let existing = full
+        .ancestors()
+        .find(|ancestor| ancestor.symlink_metadata().is_ok())
+        .unwrap_or(&root);
+    let real = fs::canonicalize(existing).with_context(|| format!("Failed to resolve {}", path))?;
+    if !real.starts_with(&root) || real.starts_with(root.join(".git")) {
+        return Err(outside().into());
+    }
+    Ok(full)
+}
+
+/// Read a text file of the project
+pub fn read(project_root: &Path, path: &str) -> Result<ProjectFile> {
+    let full = resolve(project_root, path)?;
+    let metadata = fs::metadata(&full).with_context(|| format!("Failed to read {}", path))?;
+    if metadata.len() > MAX_FILE_BYTES {
+        return Err(FileError::TooLarge {
+            path: path.to_string(),
+            size: metadata.len(),
+            limit: MAX_FILE_BYTES,
+        }
+        .into());
+    }
+    let bytes = fs::read(&full).with_context(|| format!("Failed to read {}", path))?;
+    let content = String::from_utf8(bytes).map_err(|_| FileError::NotText(path.to_string()))?;
+    Ok(ProjectFile {
+        path: path.to_string(),
+        version: version(&content),
+        size: metadata.len(),
+        modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+        content,
+    })
+}
+
+/// Replace or create a project file, backing up the old contents
+///
+/// With `expected_version`, the write fails when the file no longer has the
+/// contents it had when read.
+pub fn write(
+    project_root: &Path,
+    path: &str,
+    content: &str,
+    expected_version: Option<&str>,
+) -> Result<WriteResult> {
+    check_size(path, content)?;
+    let full = resolve(project_root, path)?;
+    let current = read_existing(&full, path)?;
+    if let Some(expected) = expected_version {
+        if current.as_deref().map(version).as_deref() != Some(expected) {
+            return Err(FileError::Changed(path.to_string()).into());
+        }
+    }
+
+    let backup = match &current {
+        Some(_) => Some(backup(project_root, &[path])?),
+        None => None,
+    };
+    write_atomic(&full, content.as_bytes()).with_context(|| format!("Failed to write {}", path))?;
+    Ok(WriteResult {
+        files: vec![ChangedFile {
+            path: path.to_string(),
+            change: if current.is_some() {
+                FileChange::Modified
+            } else {
+                FileChange::Created
+            },
+            version: Some(version(content)),
+        }],
+        backup,
+    })
+}
+
+/// Apply a unified diff to the project
+///
+/// Every file is patched in memory before any is written, so a hunk that no
+/// longer matches leaves the project untouched.
+pub fn apply_patch(project_root: &Path, diff: &str) -> Result<WriteResult> {
+    if diff.len() > MAX_PATCH_BYTES {
+        return Err(FileError::TooLarge {
+            path: "patch".to_string(),
+            size: diff.len() as u64,
+            limit: MAX_PATCH_BYTES as u64,
+        }
+        .into());
+    }
+
+    let mut planned = Vec::new();
+    for file in patch::parse(diff)? {
+        let path = file.path().to_string();
+        let full = resolve(project_root, &path)?;
+        if let Some(old) = file.old_path.as_deref().filter(|old| *old != path) {
+            // Renames are not supported; treat a mismatch as a bad patch
+            anyhow::bail!(
+                "Patch renames {} to {}; renames are not supported",
+                old,
+                path
+            );
+        }
+        let current = read_existing(&full, &path)?;
+        if file.old_path.is_some() && current.is_none() {
+            anyhow::bail!("Patch changes {}, which does not exist", path);
+        }
+        if file.old_path.is_none() && current.is_some() {
+            anyhow::bail!("Patch creates {}, which already exists", path);
+        }
+        let patched = patch::apply(current.as_deref(), &file.hunks)
+            .with_context(|| format!("Failed to patch {}", path))?;
+        let change = match (&file.old_path, &file.new_path) {
+            (None, _) => FileChange::Created,
+            (_, None) => FileChange::Deleted,
+            _ => FileChange::Modified,
+        };
+        check_size(&path, &patched)?;
+        planned.push((path, full, change, patched));
+    }
+
+    let replaced: Vec<&str> = planned
+        .iter()
+        .filter(|(_, _, change, _)| *change != FileChange::Created)
+        .map(|(path, ..)| path.as_str())
+        .collect();
+    let backup = match replaced.is_empty() {
+        true => None,
+        false => Some(backup(project_root, &replaced)?),
+    };
+
+    let mut files = Vec::new();
+    for (path, full, change, patched) in planned {
+        let version = if change == FileChange::Deleted {
+            fs::remove_file(&full).with_context(|| format!("Failed to delete {}", path))?;
+            None
+        } else {
+            write_atomic(&full, patched.as_bytes())
+                .with_context(|| format!("Failed to write {}", path))?;
+            Some(version(&patched))
+        };
+        files.push(ChangedFile {
+            path,
+            change,
+            version,
+        });
+    }
+    Ok(WriteResult { files, backup })
+}
+
+/// Hash identifying `content`
+pub fn version(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+fn check_size(path: &str, content: &str) -> Result<()> {
+    if content.len() as u64 > MAX_FILE_BYTES {
+        return Err(FileError::TooLarge {
+            path: path.to_string(),
+            size: content.len() as u64,
+            limit: MAX_FILE_BYTES,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Current text of a file, None when it does not exist
+fn read_existing(full: &Path, path: &str) -> Result<Option<String>> {
+    match fs::read(full) {
+        Ok(bytes) => Ok(Some(
+            String::from_utf8(bytes).map_err(|_| FileError::NotText(path.to_string()))?,
+        )),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path)),
+    }
+}
+
+/// Copy `paths` into a new backup set and prune old sets
+fn backup(project_root: &Path, paths: &[&str]) -> Result<PathBuf> {
+    let backups = project_root.join(BACKUP_DIR);
+    let set = backups.join(Utc::now().format("%Y%m%d-%H%M%S%.3f").to_string());
+    for path in paths {
+        let target = set.join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create backup directory {:?}", parent))?;
+        }
+        fs::copy(project_root.join(path), &target)
+            .with_context(|| format!("Failed to back up {}", path))?;
+    }
+    prune(&backups)?;
+    Ok(set)
+}
+
+/// Remove all but the newest MAX_BACKUPS sets; names sort by time
+fn prune(backups: &Path) -> Result<()> {
+    let mut sets: Vec<PathBuf> = fs::read_dir(backups)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    sets.sort();
+    let excess = sets.len().saturating_sub(MAX_BACKUPS);
+    for set in &sets[..excess] {
+        if let Err(e) = fs::remove_dir_all(set) {
+            tracing::warn!("Failed to remove old file backup {:?}: {}", set, e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "pub fn a() {}\n").unwrap();
+        dir
+    }
+
+    fn refused(error: anyhow::Error) -> bool {
+        matches!(
+            error.downcast_ref::<FileError>(),
+            Some(FileError::OutsideProject(_))
+        )
+    }
+
+    #[test]
+    fn test_rejects_paths_outside_the_project() {
+        let dir = project();
+        for path in ["../x", "/etc/passwd", "src/../../x", ".git/config", ""] {
+            assert!(refused(resolve(dir.path(), path).unwrap_err()), "{}", path);
+        }
+        assert!(resolve(dir.path(), "src/new/file.rs").is_ok());
+        assert!(resolve(dir.path(), "./src/lib.rs").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rejects_symlinks_leading_out() {
+        let dir = project();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+        assert!(refused(resolve(dir.path(), "link/secret").unwrap_err()));
+    }
+
+    #[test]
+    fn test_writes_with_backup_and_version_check() {
+        let dir = project();
+        let file = read(dir.path(), "src/lib.rs").unwrap();
+        assert_eq!(file.content, "pub fn a() {}\n");
+
+        let result = write(
+            dir.path(),
+            "src/lib.rs",
+            "pub fn b() {}\n",
+            Some(&file.version),
+        )
+        .unwrap();
+        assert_eq!(result.files[0].change, FileChange::Modified);
+        let backup = result.backup.unwrap();
+        assert_eq!(
+            fs::read_to_string(backup.join("src/lib.rs")).unwrap(),
+            "pub fn a() {}\n"
+        );
+
+        // The version read earlier is stale now
+        let stale = write(dir.path(), "src/lib.rs", "x", Some(&file.version)).unwrap_err();
+        assert!(matches!(
+            stale.downcast_ref::<FileError>(),
+            Some(FileError::Changed(_))
+        ));
+
+        let created = write(dir.path(), "docs/new.md", "# New\n", None).unwrap();
+        assert_eq!(created.files[0].change, FileChange::Created);
+        assert!(created.backup.is_none());
+    }
+
+    #[test]
+    fn test_refuses_large_and_binary_files() {
+        let dir = project();
+        fs::write(dir.path().join("blob"), [0xff, 0xfe, 0x00]).unwrap();
+        assert!(matches!(
+            read(dir.path(), "blob")
+                .unwrap_err()
+                .downcast_ref::<FileError>(),
+            Some(FileError::NotText(_))
+        ));
+        let large = "x".repeat(MAX_FILE_BYTES as usize + 1);
+        assert!(write(dir.path(), "big.txt", &large, None).is_err());
+    }
+
+    #[test]
+    fn test_applies_patches_all_or_nothing() {
+        let dir = project();
+        let diff = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1,2 @@\n pub fn a() {}\n+pub fn c() {}\n--- /dev/null\n+++ b/src/c.rs\n@@ -0,0 +1 @@\n+// c\n";
+        let result = apply_patch(dir.path(), diff).unwrap();
+        assert_eq!(result.files.len(), 2);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("src/lib.rs")).unwrap(),
+            "pub fn a() {}\npub fn c() {}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("src/c.rs")).unwrap(),
+            "// c\n"
+        );
+
+        // The second file no longer matches, so neither is written
+        let diff = "--- a/src/c.rs\n+++ b/src/c.rs\n@@ -1 +1 @@\n-// c\n+// see\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-gone\n+x\n";
+        assert!(apply_patch(dir.path(), diff).is_err());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("src/c.rs")).unwrap(),
+            "// c\n"
+        );
+
+        let escape = "--- /dev/null\n+++ b/../evil\n@@ -0,0 +1 @@\n+x\n";
+        assert!(refused(apply_patch(dir.path(), escape).unwrap_err()));
+    }
+
+    #[test]
+    fn test_keeps_the_newest_backup_sets() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..MAX_BACKUPS + 3 {
+            fs::create_dir_all(dir.path().join(format!("20260101-0000{:02}.000", i))).unwrap();
+        }
+        prune(dir.path()).unwrap();
+        let left = fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(left, MAX_BACKUPS);
+        assert!(!dir.path().join("20260101-000000.000").exists());
+    }
+}
@@ -0,0 +1,284 @@
+//! Unified diffs applied to file contents in memory
+//!
+//! Only what `git diff` and `diff -u` produce is understood: `---`/`+++`
+//! headers (`/dev/null` for created and deleted files) followed by hunks.
+//! Context must match exactly, but a hunk may have moved up or down the file
+//! since the diff was made.
+
+use anyhow::{bail, Context, Result};
+
+/// Changes to one file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilePatch {
+    /// None when the file is created
+    pub old_path: Option<String>,
+    /// None when the file is deleted
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FilePatch {
+    /// The path the patch applies to
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    /// 1-based line of the first old line
+    pub old_start: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HunkLine {
+    /// ' ', '-' or '+'
+    pub op: char,
+    pub text: String,
+    /// Followed by "\ No newline at end of file"
+    pub no_newline: bool,
+}
+
+/// Parse a unified diff of one or more files
+pub fn parse(diff: &str) -> Result<Vec<FilePatch>> {
+    let mut patches = Vec::new();
+    let mut lines = diff.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some(old) = line.strip_prefix("--- ") else {
+            // `diff --git`, `index` and other extended headers
+            continue;
+        };
+        let new = lines
+            .next()
+            .and_then(|line| line.strip_prefix("+++ "))
+            .with_context(|| format!("Expected +++ after {:?}", line))?;
+        let mut patch = FilePatch {
+            old_path: header_path(old, "a/"),
+            new_path: header_path(new, "b/"),
+            hunks: Vec::new(),
+        };
+        while let Some(header) = lines.peek().and_then(|line| line.strip_prefix("@@ ")) {
+            let (old_start, mut old_count, mut new_count) = parse_range(header)?;
+            lines.next();
+            let mut hunk = Hunk {
+                old_start,
+                lines: Vec::new(),
+            };
+            while old_count > 0 || new_count > 0 {
+                let line = lines
+                    .next()
+                    .with_context(|| format!("Hunk at line {} is truncated", old_start))?;
+                // Some editors strip the trailing space of empty context lines
+                let (op, text) = match line.chars().next() {
+                    Some(op @ (' ' | '-' | '+')) => (op, &line[1..]),
+                    None => (' ', ""),
+                    Some('\\') => continue,
+                    Some(_) => bail!("Unexpected line in hunk: {:?}", line),
+                };
+                if op != '+' {
+                    old_count = old_count
+                        .checked_sub(1)
+                        .context("Hunk has more old lines than its header says")?;
+                }
+                if op != '-' {
+                    new_count = new_count
+                        .checked_sub(1)
+                        .context("Hunk has more new lines than its header says")?;
+                }
+                hunk.lines.push(HunkLine {
+                    op,
+                    text: text.to_string(),
+                    no_newline: false,
+                });
+            }
+            if lines.peek().is_some_and(|line| line.starts_with('\\')) {
+                lines.next();
+                if let Some(last) = hunk.lines.last_mut() {
+                    last.no_newline = true;
+                }
+            }
+            patch.hunks.push(hunk);
+        }
+        if patch.old_path.is_none() && patch.new_path.is_none() {
+            bail!("Patch has /dev/null on both sides");
+        }
+        patches.push(patch);
+    }
+    if patches.is_empty() {
+        bail!("No file changes found in the patch");
+    }
+    Ok(patches)
+}
+
+/// Apply `hunks` to `content`; None content is a file being created
+pub fn apply(content: Option<&str>, hunks: &[Hunk]) -> Result<String> {
+    let content = content.unwrap_or_default();
+    let mut lines: Vec<&str> = content.lines().collect();
+    let mut trailing_newline = content.is_empty() || content.ends_with('\n');
+    // How far hunks have moved lines so far
+    let mut offset: isize = 0;
+
+    for hunk in hunks {
+        let old: Vec<&str> = hunk
+            .lines
+            .iter()
+            .filter(|line| line.op != '+')
+            .map(|line| line.text.as_str())
+            .collect();
+        let new: Vec<&str> = hunk
+            .lines
+            .iter()
+            .filter(|line| line.op != '-')
+            .map(|line| line.text.as_str())
+            .collect();
+
+        // An empty old side of `@@ -0,0` starts before the first line
+        let expected = hunk.old_start.saturating_sub(1) as isize
+            + if old.is_empty() && hunk.old_start > 0 {
+                1
+            } else {
+                0
+            }
+            + offset;
+        let at = find(&lines, &old, expected.max(0) as usize).with_context(|| {
+            format!(
+                "Hunk at line {} does not match the file; it may have changed",
+                hunk.old_start
+            )
+        })?;
+        lines.splice(at..at + old.len(), new.iter().copied());
+        offset += at as isize - expected + new.len() as isize - old.len() as isize;
+
+        if at + new.len() == lines.len() {
+            if let Some(last) = hunk.lines.iter().rev().find(|line| line.op != '-') {
+                trailing_newline = !last.no_newline;
+            }
+        }
+    }
+
+    let mut patched = lines.join("\n");
+    if trailing_newline && !lines.is_empty() {
+        patched.push('\n');
+    }
+    Ok(patched)
+}
+
+/// Where `old` occurs in `lines`, nearest to `expected`
+fn find(lines: &[&str], old: &[&str], expected: usize) -> Option<usize> {
+    let last = lines.len().checked_sub(old.len())?;
+    let matches = |at: usize| at <= last && lines[at..at + old.len()] == *old;
+    let expected = expected.min(last);
+    (0..=last.max(expected)).find_map(|distance| {
+        [
+            expected.checked_sub(distance),
+            expected.checked_add(distance),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|&at| matches(at))
+    })
+}
+
+/// The path of a `---`/`+++` header, without its timestamp and `a/`/`b/` prefix
+fn header_path(header: &str, prefix: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or_default().trim_end();
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(path.strip_prefix(prefix).unwrap_or(path).to_string())
+}
+
+/// `-12,3 +12,4 @@` into the old start and the old and new line counts
+fn parse_range(header: &str) -> Result<(usize, usize, usize)> {
+    let invalid = || format!("Invalid hunk header: @@ {}", header);
+    let mut ranges = header.split_whitespace();
+    let old = ranges
+        .next()
+        .and_then(|range| range.strip_prefix('-'))
+        .with_context(invalid)?;
+    let new = ranges
+        .next()
+        .and_then(|range| range.strip_prefix('+'))
+        .with_context(invalid)?;
+    let range = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_count) = range(old).with_context(invalid)?;
+    let (_, new_count) = range(new).with_context(invalid)?;
+    Ok((old_start, old_count, new_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/src/main.rs b/src/main.rs
+index 83db48f..bf269f4 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,3 +1,4 @@
+ fn main() {
+-    println!(\"hello\");
++    println!(\"hello, world\");
++    run();
+ }
+--- /dev/null
++++ b/NOTES.md
+@@ -0,0 +1,2 @@
++# Notes
++todo
+";
+
+    #[test]
+    fn test_parses_modified_and_created_files() {
+        let patches = parse(DIFF).unwrap();
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].old_path.as_deref(), Some("src/main.rs"));
+        assert_eq!(patches[0].hunks[0].lines.len(), 5);
+        assert_eq!(patches[1].old_path, None);
+        assert_eq!(patches[1].path(), "NOTES.md");
+    }
+
+    #[test]
+    fn test_applies_hunks() {
+        let patches = parse(DIFF).unwrap();
+        let main = "fn main() {\n    println!(\"hello\");\n}\n";
+        assert_eq!(
+            apply(Some(main), &patches[0].hunks).unwrap(),
+            "fn main() {\n    println!(\"hello, world\");\n    run();\n}\n"
+        );
+        assert_eq!(apply(None, &patches[1].hunks).unwrap(), "# Notes\ntodo\n");
+    }
+
+    #[test]
+    fn test_finds_moved_hunks_and_rejects_changed_context() {
+        let patches = parse(DIFF).unwrap();
+        let moved = "// header\n\nfn main() {\n    println!(\"hello\");\n}\n";
+        assert!(apply(Some(moved), &patches[0].hunks)
+            .unwrap()
+            .contains("hello, world"));
+        let changed = "fn main() {\n    println!(\"bye\");\n}\n";
+        assert!(apply(Some(changed), &patches[0].hunks).is_err());
+    }
+
+    #[test]
+    fn test_honours_missing_newline_markers() {
+        let diff = "--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n\\ No newline at end of file\n+b\n";
+        let patches = parse(diff).unwrap();
+        assert_eq!(apply(Some("a"), &patches[0].hunks).unwrap(), "b\n");
+    }
+
+    #[test]
+    fn test_rejects_malformed_patches() {
+        assert!(parse("just text").is_err());
+        assert!(parse("--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n a\n").is_err());
+        assert!(parse("--- a/x\n+++ b/x\n@@ nonsense @@\n").is_err());
+    }
+}
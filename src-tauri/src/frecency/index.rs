@@ -0,0 +1,182 @@
+use crate::config::atomic::write_atomic;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Hours after which a visit counts half as much
+pub const HALF_LIFE_HOURS: f64 = 72.0;
+/// Paths kept per kind; the lowest ranked are dropped beyond this
+const MAX_ENTRIES: usize = 2000;
+
+/// Contents of <project>/.zeami/frecency.json
+///
+/// Paths are relative to the project root, with `/` separators; the root
+/// directory itself is ".".
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrecencyIndex {
+    pub files: BTreeMap<String, Entry>,
+    pub dirs: BTreeMap<String, Entry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Visits, each decayed by its age as of last_used
+    pub score: f64,
+    pub last_used: DateTime<Utc>,
+}
+
+impl Entry {
+    /// Score decayed to `now`
+    pub fn rank(&self, now: DateTime<Utc>) -> f64 {
+        let hours = (now - self.last_used).num_seconds().max(0) as f64 / 3600.0;
+        self.score * 0.5f64.powf(hours / HALF_LIFE_HOURS)
+    }
+}
+
+/// A path with its rank, for the quick-open palette
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RankedPath {
+    pub path: String,
+    pub score: f64,
+    pub last_used: DateTime<Utc>,
+}
+
+/// <project>/.zeami/frecency.json
+pub fn index_path(project_root: &Path) -> PathBuf {
+    project_root.join(".zeami").join("frecency.json")
+}
+
+impl FrecencyIndex {
+    /// Index of the project at `project_root`; a missing file gives an empty one
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let path = index_path(project_root);
+        match std::fs::read_to_string(&path) {
+            Ok(json) => {
+                serde_json::from_str(&json).with_context(|| format!("Failed to parse {:?}", path))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        }
+    }
+
+    pub fn save(&self, project_root: &Path) -> Result<()> {
+        let json = serde_json::to_vec(self)?;
+        write_atomic(&index_path(project_root), &json)
+    }
+
+    pub fn visit_file(&mut self, path: &str, now: DateTime<Utc>) {
+        visit(&mut self.files, path, now);
+    }
+
+    pub fn visit_dir(&mut self, path: &str, now: DateTime<Utc>) {
+        visit(&mut self.dirs, path, now);
+    }
+
+    /// Drop a file that no longer exists
+    pub fn forget_file(&mut self, path: &str) -> bool {
+        self.files.remove(path).is_some()
+    }
+
+    /// Highest ranked files first
+    pub fn files(&self, limit: usize, now: DateTime<Utc>) -> Vec<RankedPath> {
+        ranked(&self.files, limit, now)
+    }
+
+    /// Highest ranked directories first
+    pub fn dirs(&self, limit: usize, now: DateTime<Utc>) -> Vec<RankedPath> {
+        ranked(&self.dirs, limit, now)
+    }
+}
+
+fn visit(entries: &mut BTreeMap<String, Entry>, path: &str, now: DateTime<Utc>) {
+    let score = entries.get(path).map_or(0.0, |entry| entry.rank(now)) + 1.0;
+    entries.insert(
+        path.to_string(),
+        Entry {
+            score,
+            last_used: now,
+        },
+    );
+    if entries.len() > MAX_ENTRIES {
+        let lowest = entries
+            .iter()
+            .min_by(|a, b| a.1.rank(now).total_cmp(&b.1.rank(now)))
+            .map(|(path, _)| path.clone());
+        if let Some(lowest) = lowest {
+            entries.remove(&lowest);
+        }
+    }
+}
+
+fn ranked(entries: &BTreeMap<String, Entry>, limit: usize, now: DateTime<Utc>) -> Vec<RankedPath> {
+    let mut ranked: Vec<RankedPath> = entries
+        .iter()
+        .map(|(path, entry)| RankedPath {
+            path: path.clone(),
+            score: entry.rank(now),
+            last_used: entry.last_used,
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| b.last_used.cmp(&a.last_used))
+    });
+    ranked.truncate(limit);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_ranks_by_frequency_and_recency() {
+        let start = Utc::now();
+        let mut index = FrecencyIndex::default();
+        for _ in 0..3 {
+            index.visit_file("src/old.rs", start);
+        }
+        index.visit_file("src/new.rs", start + Duration::hours(1));
+        let paths = |ranked: Vec<RankedPath>| -> Vec<String> {
+            ranked.into_iter().map(|r| r.path).collect()
+        };
+
+        // Frequent visits win while recent
+        assert_eq!(
+            paths(index.files(10, start + Duration::hours(1))),
+            ["src/old.rs", "src/new.rs"]
+        );
+        // ...and fade: a week later one fresh visit outranks them
+        let later = start + Duration::days(7);
+        index.visit_file("src/new.rs", later);
+        assert_eq!(paths(index.files(1, later)), ["src/new.rs"]);
+        let old = index.files.get("src/old.rs").unwrap().rank(later);
+        assert!(old < 1.0, "{}", old);
+    }
+
+    #[test]
+    fn test_round_trips_and_forgets() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            FrecencyIndex::load(dir.path()).unwrap(),
+            FrecencyIndex::default()
+        );
+
+        let now = Utc::now();
+        let mut index = FrecencyIndex::default();
+        index.visit_file("a.rs", now);
+        index.visit_dir("src", now);
+        index.save(dir.path()).unwrap();
+        let mut loaded = FrecencyIndex::load(dir.path()).unwrap();
+        assert_eq!(loaded, index);
+        assert_eq!(loaded.dirs(5, now)[0].path, "src");
+
+        assert!(loaded.forget_file("a.rs"));
+        assert!(loaded.files(5, now).is_empty());
+    }
+}
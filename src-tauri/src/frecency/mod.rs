@@ -0,0 +1,156 @@
+//! Frecency of project files and directories, for the quick-open palette
+//!
+//! A file gains a visit whenever it is created or modified in the project
+//! selected with `watch_tasks`, found by polling modification times; a
+//! directory gains one whenever a terminal's shell reports it as its new
+//! working directory. Visits decay with a half-life of HALF_LIFE_HOURS, so
+//! the ranking favours what is used both often and lately. Each project's
+//! index is kept in <project>/.zeami/frecency.json.
+
+mod index;
+pub mod scan;
+
+pub use index::*;
+
+use crate::tasks::TaskWatcher;
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+
+/// How often the active project is scanned for changed files
+const SCAN_INTERVAL: Duration = Duration::from_secs(10);
+/// More changes than this in one scan (a checkout, a formatter run) are not
+/// the user's own edits and are ignored
+const MAX_CHANGES_PER_SCAN: usize = 50;
+
+/// Frecency indexes of the projects used so far; managed by Tauri
+#[derive(Default)]
+pub struct Frecency {
+    indexes: Mutex<HashMap<PathBuf, FrecencyIndex>>,
+}
+
+impl Frecency {
+    /// Highest ranked files of the project that still exist
+    pub fn recent_files(&self, project_root: &Path, limit: usize) -> Result<Vec<RankedPath>> {
+        let now = Utc::now();
+        self.update(project_root, |index| {
+            let gone: Vec<String> = index
+                .files
+                .keys()
+                .filter(|path| !project_root.join(path).is_file())
+                .cloned()
+                .collect();
+            for path in &gone {
+                index.forget_file(path);
+            }
+            Ok((index.files(limit, now), !gone.is_empty()))
+        })
+    }
+
+    /// Highest ranked working directories of the project's terminals
+    pub fn frequent_dirs(&self, project_root: &Path, limit: usize) -> Result<Vec<RankedPath>> {
+        let now = Utc::now();
+        self.update(project_root, |index| Ok((index.dirs(limit, now), false)))
+    }
+
+    /// Count a visit to each of `paths`, relative to the project root
+    pub fn record_files(&self, project_root: &Path, paths: &[String]) -> Result<()> {
+        let now = Utc::now();
+        self.update(project_root, |index| {
+            for path in paths {
+                index.visit_file(path, now);
+            }
+            Ok(((), true))
+        })
+    }
+
+    /// Count a visit to `dir` if it lies inside the project
+    pub fn record_dir(&self, project_root: &Path, dir: &Path) -> Result<()> {
+        let Some(relative) = scan::relative(project_root, dir) else {
+            return Ok(());
+        };
+        let now = Utc::now();
+        self.update(project_root, |index| {
+            index.visit_dir(&relative, now);
+            Ok(((), true))
+        })
+    }
+
+    /// Run `f` on the project's index, saving it when `f` reports a change
+    fn update<T>(
+        &self,
+        project_root: &Path,
+        f: impl FnOnce(&mut FrecencyIndex) -> Result<(T, bool)>,
+    ) -> Result<T> {
+        let mut indexes = self.indexes.lock().unwrap();
+        if !indexes.contains_key(project_root) {
+            let index = FrecencyIndex::load(project_root).unwrap_or_else(|e| {
+                tracing::warn!(path = ?project_root, "Starting a new frecency index: {:#}", e);
+                FrecencyIndex::default()
+            });
+            indexes.insert(project_root.to_path_buf(), index);
+        }
+        let index = indexes
+            .get_mut(project_root)
+            .expect("index was just loaded");
+        let (value, changed) = f(index)?;
+        if changed {
+            index.save(project_root)?;
+        }
+        Ok(value)
+    }
+
+    /// Start polling the active project for created and modified files
+    pub fn spawn(&self, app: AppHandle) {
+        tauri::async_runtime::spawn(async move {
+            let mut scanned: Option<PathBuf> = None;
+            let mut times: HashMap<String, SystemTime> = HashMap::new();
+            loop {
+                tokio::time::sleep(SCAN_INTERVAL).await;
+                let Some(root) = app.state::<TaskWatcher>().project() else {
+                    scanned = None;
+                    continue;
+                };
+                let current = {
+                    let root = root.clone();
+                    tauri::async_runtime::spawn_blocking(move || scan::modification_times(&root))
+                        .await
+                };
+                let current = match current {
+                    Ok(current) => current,
+                    Err(e) => {
+                        tracing::error!("Frecency scan failed: {}", e);
+                        continue;
+                    }
+                };
+                // The first scan of a project only sets the baseline
+                if scanned.as_ref() == Some(&root) {
+                    let changed = scan::changed(&times, &current);
+                    if changed.len() > MAX_CHANGES_PER_SCAN {
+                        tracing::debug!(count = changed.len(), "Ignoring bulk file changes");
+                    } else if !changed.is_empty() {
+                        if let Err(e) = app.state::<Frecency>().record_files(&root, &changed) {
+                            tracing::warn!(path = ?root, "Failed to record file visits: {:#}", e);
+                        }
+                    }
+                }
+                scanned = Some(root);
+                times = current;
+            }
+        });
+    }
+}
+
+/// Count a terminal moving to `cwd` towards the active project's directories
+pub fn record_cwd(app: &AppHandle, cwd: &str) {
+    let Some(root) = app.state::<TaskWatcher>().project() else {
+        return;
+    };
+    if let Err(e) = app.state::<Frecency>().record_dir(&root, Path::new(cwd)) {
+        tracing::warn!(path = ?root, "Failed to record directory visit: {:#}", e);
+    }
+}
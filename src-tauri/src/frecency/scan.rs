@@ -0,0 +1,91 @@
+use ignore::WalkBuilder;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Modification time of every file of the project, by relative path
+///
+/// Hidden and git-ignored files are skipped, like the task watcher does, so
+/// build output and dependencies never count as edits.
+pub fn modification_times(project_root: &Path) -> HashMap<String, SystemTime> {
+    WalkBuilder::new(project_root)
+        .require_git(false)
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((relative(project_root, entry.path())?, modified))
+        })
+        .collect()
+}
+
+/// Files created or modified between two scans
+pub fn changed(
+    previous: &HashMap<String, SystemTime>,
+    current: &HashMap<String, SystemTime>,
+) -> Vec<String> {
+    let mut changed: Vec<String> = current
+        .iter()
+        .filter(|(path, modified)| previous.get(*path) != Some(modified))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.sort();
+    changed
+}
+
+/// `path` relative to `project_root` with `/` separators; "." for the root
+pub fn relative(project_root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(project_root).ok()?;
+    let parts: Vec<_> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+    Some(if parts.is_empty() {
+        ".".to_string()
+    } else {
+        parts.join("/")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+
+    #[test]
+    fn test_reports_created_and_modified_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join("dist")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "dist/\n").unwrap();
+        fs::write(dir.path().join("src/a.rs"), "a").unwrap();
+        fs::write(dir.path().join("src/b.rs"), "b").unwrap();
+        let before = modification_times(dir.path());
+        assert!(before.contains_key("src/a.rs"));
+
+        let file = fs::File::options()
+            .write(true)
+            .open(dir.path().join("src/a.rs"))
+            .unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5))
+            .unwrap();
+        fs::write(dir.path().join("src/c.rs"), "c").unwrap();
+        fs::write(dir.path().join("dist/out.js"), "built").unwrap();
+
+        let after = modification_times(dir.path());
+        assert_eq!(changed(&before, &after), ["src/a.rs", "src/c.rs"]);
+    }
+
+    #[test]
+    fn test_relative_paths() {
+        let root = Path::new("/p");
+        assert_eq!(relative(root, Path::new("/p")).as_deref(), Some("."));
+        assert_eq!(
+            relative(root, Path::new("/p/src/ui")).as_deref(),
+            Some("src/ui")
+        );
+        assert_eq!(relative(root, Path::new("/elsewhere")), None);
+    }
+}
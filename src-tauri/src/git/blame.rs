@@ -0,0 +1,143 @@
+use super::{open, workdir};
+use anyhow::{bail, Context, Result};
+use git2::{BlameOptions, Oid};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Inclusive, 1-based line range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Last change to one line of the working tree file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameLine {
+    pub line: usize,
+    pub content: String,
+    /// None for lines changed but not yet committed
+    pub commit: Option<String>,
+    pub author: String,
+    pub email: String,
+    /// RFC 3339 author time
+    pub time: Option<String>,
+    pub summary: String,
+}
+
+/// Blame `file` (relative to the repository root) as it is in the working tree
+///
+/// Uncommitted edits are accounted for, so line numbers match the editor.
+pub fn get_blame(repo_path: &Path, file: &str, range: Option<LineRange>) -> Result<Vec<BlameLine>> {
+    let repo = open(repo_path)?;
+    let contents =
+        fs::read(workdir(&repo)?.join(file)).with_context(|| format!("Failed to read {}", file))?;
+    let text = String::from_utf8_lossy(&contents);
+    let lines: Vec<&str> = text.lines().collect();
+
+    let (start, end) = match range {
+        Some(range) if range.start == 0 || range.start > range.end => {
+            bail!("Invalid line range {}-{}", range.start, range.end)
+        }
+        Some(range) => (range.start, range.end.min(lines.len())),
+        None => (1, lines.len()),
+    };
+    if start > end {
+        return Ok(Vec::new());
+    }
+
+    let mut options = BlameOptions::new();
+    let committed = repo
+        .blame_file(Path::new(file), Some(&mut options))
+        .with_context(|| format!("{} has no committed history", file))?;
+    let blame = committed.blame_buffer(&contents)?;
+
+    let mut summaries: HashMap<Oid, String> = HashMap::new();
+    let mut result = Vec::with_capacity(end - start + 1);
+    for line in start..=end {
+        let content = lines[line - 1].trim_end_matches('\r').to_string();
+        let Some(hunk) = blame.get_line(line) else {
+            result.push(uncommitted(line, content));
+            continue;
+        };
+        let id = hunk.final_commit_id();
+        if id.is_zero() {
+            result.push(uncommitted(line, content));
+            continue;
+        }
+
+        let summary = match summaries.get(&id) {
+            Some(summary) => summary.clone(),
+            None => {
+                let summary = repo
+                    .find_commit(id)
+                    .ok()
+                    .and_then(|commit| commit.summary().map(str::to_string))
+                    .unwrap_or_default();
+                summaries.insert(id, summary.clone());
+                summary
+            }
+        };
+        let signature = hunk.final_signature();
+        result.push(BlameLine {
+            line,
+            content,
+            commit: Some(id.to_string()),
+            author: signature.name().unwrap_or_default().to_string(),
+            email: signature.email().unwrap_or_default().to_string(),
+            time: chrono::DateTime::from_timestamp(signature.when().seconds(), 0)
+                .map(|time| time.to_rfc3339()),
+            summary,
+        });
+    }
+    Ok(result)
+}
+
+fn uncommitted(line: usize, content: String) -> BlameLine {
+    BlameLine {
+        line,
+        content,
+        commit: None,
+        author: String::new(),
+        email: String::new(),
+        time: None,
+        summary: String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Repository;
+
+    #[test]
+    fn test_blame_with_uncommitted_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("a.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Ann", "ann@example.com").unwrap();
+        let id = repo
+            .commit(Some("HEAD"), &signature, &signature, "Add a", &tree, &[])
+            .unwrap();
+
+        fs::write(dir.path().join("a.txt"), "zero\none\ntwo\n").unwrap();
+        let lines = get_blame(dir.path(), "a.txt", None).unwrap();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].commit.is_none());
+        assert_eq!(lines[1].commit.as_deref(), Some(id.to_string().as_str()));
+        assert_eq!(lines[1].author, "Ann");
+        assert_eq!(lines[1].summary, "Add a");
+
+        let range = LineRange { start: 3, end: 10 };
+        let lines = get_blame(dir.path(), "a.txt", Some(range)).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].content, "two");
+        assert!(get_blame(dir.path(), "a.txt", Some(LineRange { start: 0, end: 1 })).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+use super::links::issue_from_branch;
+use super::open;
+use crate::config::GitSettings;
+use anyhow::{bail, Context, Result};
+use git2::{Branch, BranchType, Repository, StatusOptions};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A local branch as shown in the branch list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchInfo {
+    pub name: String,
+    pub is_head: bool,
+    /// Upstream branch, e.g. `origin/issue-42-fix-login`
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    /// Short id of the tip commit
+    pub commit: String,
+    pub summary: String,
+    /// RFC 3339 commit time of the tip
+    pub committed_at: String,
+    /// Issue number when the name follows the issue branch convention
+    pub issue: Option<u64>,
+    /// Fully merged into GitSettings::default_branch
+    pub merged: bool,
+}
+
+/// Result of checkout_branch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckoutResult {
+    pub branch: String,
+    /// Merged issue branches removed afterwards (GitSettings::auto_clean_branches)
+    pub cleaned: Vec<String>,
+}
+
+/// Local branches, HEAD first, then by name
+pub fn list_branches(repo_path: &Path, git: &GitSettings) -> Result<Vec<BranchInfo>> {
+    let repo = open(repo_path)?;
+    let mut branches = Vec::new();
+    for entry in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = entry?;
+        branches.push(branch_info(&repo, &branch, git)?);
+    }
+    branches.sort_by(|a, b| b.is_head.cmp(&a.is_head).then(a.name.cmp(&b.name)));
+    Ok(branches)
+}
+
+/// Create a branch at `start_point` (a branch, tag or commit; default HEAD)
+pub fn create_branch(
+    repo_path: &Path,
+    name: &str,
+    start_point: Option<&str>,
+    git: &GitSettings,
+) -> Result<BranchInfo> {
+    if !Branch::name_is_valid(name)? {
+        bail!("'{}' is not a valid branch name", name);
+    }
+    let repo = open(repo_path)?;
+    if repo.find_branch(name, BranchType::Local).is_ok() {
+        bail!("Branch {} already exists", name);
+    }
+
+    let start = match start_point {
+        Some(spec) => repo
+            .revparse_single(spec)
+            .and_then(|object| object.peel_to_commit())
+            .with_context(|| format!("Unknown start point {}", spec))?,
+        None => repo
+            .head()
+            .and_then(|head| head.peel_to_commit())
+            .context("Repository has no commits to branch from")?,
+    };
+    let branch = repo
+        .branch(name, &start, false)
+        .with_context(|| format!("Failed to create branch {}", name))?;
+    branch_info(&repo, &branch, git)
+}
+
+/// Switch to a local branch
+///
+/// Refuses while tracked files have uncommitted changes unless `force`, which
+/// discards them. Untracked files are left alone. With auto_clean_branches, merged
+/// issue branches are deleted afterwards.
+pub fn checkout_branch(
+    repo_path: &Path,
+    name: &str,
+    force: bool,
+    git: &GitSettings,
+) -> Result<CheckoutResult> {
+    let repo = open(repo_path)?;
+    if !force && has_uncommitted_changes(&repo)? {
+        bail!(
+            "Cannot switch to {}: commit or stash your changes first",
+            name
+        );
+    }
+    if repo.find_branch(name, BranchType::Local).is_err() {
+        bail!("No local branch {}", name);
+    }
+    checkout_reference(&repo, name, force)?;
+
+    let cleaned = if git.auto_clean_branches {
+        clean_merged_branches(repo_path, git, false)?
+    } else {
+        Vec::new()
+    };
+    Ok(CheckoutResult {
+        branch: name.to_string(),
+        cleaned,
+    })
+}
+
+/// Delete a local branch; unmerged branches need `force`
+pub fn delete_branch(repo_path: &Path, name: &str, force: bool, git: &GitSettings) -> Result<()> {
+    let repo = open(repo_path)?;
+    let mut branch = repo
+        .find_branch(name, BranchType::Local)
+        .with_context(|| format!("No local branch {}", name))?;
+    if branch.is_head() {
+        bail!("Cannot delete the checked-out branch {}", name);
+    }
+    if !force && !is_merged(&repo, &branch, git)? {
+        bail!(
+            "Branch {} is not merged into {}; delete it anyway to discard its commits",
+            name,
+            git.default_branch
+        );
+    }
+    branch
+        .delete()
+        .with_context(|| format!("Failed to delete branch {}", name))
+}
+
+/// Delete issue branches fully merged into the default branch
+///
+/// Only branches following GitSettings::branch_prefix are considered, never the
+/// checked-out one. Squash-merged branches are not detected as merged and are kept.
+/// With `dry_run` the branches are only listed.
+pub fn clean_merged_branches(
+    repo_path: &Path,
+    git: &GitSettings,
+    dry_run: bool,
+) -> Result<Vec<String>> {
+    let repo = open(repo_path)?;
+    let mut cleaned = Vec::new();
+    for entry in repo.branches(Some(BranchType::Local))? {
+        let (mut branch, _) = entry?;
+        let Some(name) = branch.name()?.map(str::to_string) else {
+            continue;
+        };
+        if branch.is_head()
+            || name == git.default_branch
+            || issue_from_branch(&name, &git.branch_prefix).is_none()
+            || !is_merged(&repo, &branch, git)?
+        {
+            continue;
+        }
+
+        if !dry_run {
+            branch
+                .delete()
+                .with_context(|| format!("Failed to delete branch {}", name))?;
+            tracing::info!(branch = %name, "Deleted merged issue branch");
+        }
+        cleaned.push(name);
+    }
+    Ok(cleaned)
+}
+
+/// Check out local branch `name` and point HEAD at it
+/// Without `force` the checkout stops instead of overwriting local changes
+pub fn checkout_reference(repo: &Repository, name: &str, force: bool) -> Result<()> {
+    let reference = repo.find_branch(name, BranchType::Local)?.into_reference();
+    let target = reference.peel(git2::ObjectType::Commit)?;
+
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    if force {
+        checkout.force();
+    } else {
+        checkout.safe();
+    }
+    repo.checkout_tree(&target, Some(&mut checkout))
+        .with_context(|| {
+            format!(
+                "Failed to check out {}; commit or stash local changes",
+                name
+            )
+        })?;
+    repo.set_head(reference.name().context("Branch name is not valid UTF-8")?)?;
+    Ok(())
+}
+
+/// Staged or unstaged changes to tracked files
+fn has_uncommitted_changes(repo: &Repository) -> Result<bool> {
+    let mut options = StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    Ok(!repo.statuses(Some(&mut options))?.is_empty())
+}
+
+/// Whether the branch tip is reachable from the default branch (local or on the remote)
+fn is_merged(repo: &Repository, branch: &Branch<'_>, git: &GitSettings) -> Result<bool> {
+    let Some(tip) = branch.get().target() else {
+        return Ok(false);
+    };
+
+    let bases = [
+        repo.find_branch(&git.default_branch, BranchType::Local),
+        repo.find_branch(
+            &format!("{}/{}", git.remote, git.default_branch),
+            BranchType::Remote,
+        ),
+    ];
+    for base in bases.into_iter().flatten() {
+        let Some(base) = base.get().target() else {
+            continue;
+        };
+        if base == tip || repo.graph_descendant_of(base, tip)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn branch_info(repo: &Repository, branch: &Branch<'_>, git: &GitSettings) -> Result<BranchInfo> {
+    let name = branch
+        .name()?
+        .context("Branch name is not valid UTF-8")?
+        .to_string();
+    let commit = branch.get().peel_to_commit()?;
+
+    let upstream = branch.upstream().ok();
+    let (ahead, behind) = match upstream
+        .as_ref()
+        .and_then(|upstream| upstream.get().target())
+    {
+        Some(upstream_tip) => repo.graph_ahead_behind(commit.id(), upstream_tip)?,
+        None => (0, 0),
+    };
+    let committed_at = chrono::DateTime::from_timestamp(commit.time().seconds(), 0)
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_default();
+
+    Ok(BranchInfo {
+        issue: issue_from_branch(&name, &git.branch_prefix),
+        is_head: branch.is_head(),
+        upstream: upstream.and_then(|upstream| upstream.name().ok().flatten().map(str::to_string)),
+        ahead,
+        behind,
+        commit: commit
+            .as_object()
+            .short_id()?
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        summary: commit.summary().unwrap_or_default().to_string(),
+        committed_at,
+        merged: is_merged(repo, branch, git)?,
+        name,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_file, init_repo};
+    use std::fs;
+
+    #[test]
+    fn test_create_checkout_and_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "a.txt", "one", "a.txt");
+        let git = GitSettings::default();
+
+        assert!(create_branch(dir.path(), "bad..name", None, &git).is_err());
+        let created = create_branch(dir.path(), "issue-3-work", None, &git).unwrap();
+        assert_eq!(created.issue, Some(3));
+        assert!(created.merged);
+
+        checkout_branch(dir.path(), "issue-3-work", false, &git).unwrap();
+        commit_file(&repo, "b.txt", "two", "b.txt");
+
+        fs::write(dir.path().join("b.txt"), "dirty").unwrap();
+        assert!(checkout_branch(dir.path(), "main", false, &git).is_err());
+        checkout_branch(dir.path(), "main", true, &git).unwrap();
+        assert!(!dir.path().join("b.txt").exists());
+
+        let branches = list_branches(dir.path(), &git).unwrap();
+        assert_eq!(branches[0].name, "main");
+        assert!(!branches[1].merged);
+        assert!(delete_branch(dir.path(), "issue-3-work", false, &git).is_err());
+        delete_branch(dir.path(), "issue-3-work", true, &git).unwrap();
+        assert!(delete_branch(dir.path(), "main", true, &git).is_err());
+    }
+
+    #[test]
+    fn test_clean_merged_issue_branches() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "a.txt", "one", "a.txt");
+        let git = GitSettings::default();
+        create_branch(dir.path(), "issue-1-done", None, &git).unwrap();
+        create_branch(dir.path(), "feature", None, &git).unwrap();
+
+        assert_eq!(
+            clean_merged_branches(dir.path(), &git, true).unwrap(),
+            vec!["issue-1-done"]
+        );
+        assert_eq!(list_branches(dir.path(), &git).unwrap().len(), 3);
+        clean_merged_branches(dir.path(), &git, false).unwrap();
+        assert_eq!(list_branches(dir.path(), &git).unwrap().len(), 2);
+    }
+}
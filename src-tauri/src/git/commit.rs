@@ -0,0 +1,390 @@
+use super::commitlint::{self, Severity};
+use super::large_files::{self, LargeFile};
+use super::links::{issue_from_branch, linked_issue};
+use super::{current_branch, open, workdir};
+use crate::config::GitSettings;
+use anyhow::{bail, Context, Result};
+use git2::{Oid, Repository, Signature};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// A commit created by create_commit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitInfo {
+    pub id: String,
+    /// None when committing on a detached HEAD
+    pub branch: Option<String>,
+    pub summary: String,
+    pub amend: bool,
+    pub signed: bool,
+    /// Files caught by the large-file guard (see large_files); already in
+    /// Git LFS when GitSettings::large_file_action is Track
+    pub large_files: Vec<LargeFile>,
+}
+
+/// Options for create_commit
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NewCommit {
+    pub message: String,
+    /// Paths (relative to the repository root) to stage first; deleted files are removed
+    /// from the index. Empty commits whatever is already staged.
+    pub files: Vec<String>,
+    pub amend: bool,
+}
+
+/// Stage `new.files` and commit the index in the repository containing `repo_path`
+///
+/// The message goes through GitSettings::commit_template, a `Signed-off-by`
+/// trailer is added with `sign_off`, and the commit is GPG-signed with
+/// `sign_commits` (key from `gpg_key_id`, then `user.signingkey`). With
+/// `lint_commit_messages` the rendered message must pass git::commitlint; a
+/// reference to the linked issue is expected when `auto_link_issues` is set.
+/// The staged files go through the large-file guard (see large_files).
+pub fn create_commit(
+    repo_path: &Path,
+    new: &NewCommit,
+    git: &GitSettings,
+    auto_link_issues: bool,
+) -> Result<CommitInfo> {
+    let repo = open(repo_path)?;
+    let root = workdir(&repo)?;
+    let branch = current_branch(&repo).ok();
+
+    let issue = linked_issue(&root, git)?
+        .map(|link| link.issue)
+        .or_else(|| {
+            branch
+                .as_deref()
+                .and_then(|branch| issue_from_branch(branch, &git.branch_prefix))
+        });
+    let signature = repo
+        .signature()
+        .context("Set user.name and user.email in your git config")?;
+    let message = render_message(
+        &git.commit_template,
+        &new.message,
+        branch.as_deref(),
+        issue,
+        git.sign_off.then_some(&signature),
+    );
+    if message.trim().is_empty() {
+        bail!("Commit message is empty");
+    }
+    if git.lint_commit_messages {
+        let result = commitlint::lint(&message, issue.filter(|_| auto_link_issues));
+        if !result.valid {
+            let errors: Vec<String> = result
+                .violations
+                .into_iter()
+                .filter(|violation| violation.severity == Severity::Error)
+                .map(|violation| format!("{} ({})", violation.message, violation.rule))
+                .collect();
+            bail!("Commit message rejected: {}", errors.join("; "));
+        }
+    }
+
+    let mut index = repo.index()?;
+    for file in &new.files {
+        let relative = Path::new(file);
+        if root.join(relative).exists() {
+            index
+                .add_path(relative)
+                .with_context(|| format!("Failed to stage {}", file))?;
+        } else {
+            index
+                .remove_path(relative)
+                .with_context(|| format!("Failed to stage removal of {}", file))?;
+        }
+    }
+    index.write()?;
+    let head = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let base = match (&head, new.amend) {
+        (Some(head), true) => head
+            .parents()
+            .next()
+            .map(|parent| parent.tree())
+            .transpose()?,
+        (Some(head), false) => Some(head.tree()?),
+        (None, _) => None,
+    };
+    let large_files = large_files::guard(&repo, &root, &mut index, base.as_ref(), git)?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+
+    let parents = match (&head, new.amend) {
+        (Some(head), true) => head.parents().collect(),
+        (None, true) => bail!("There is no commit to amend"),
+        (Some(head), false) => {
+            if head.tree_id() == tree.id() {
+                bail!("Nothing to commit");
+            }
+            vec![head.clone()]
+        }
+        (None, false) => Vec::new(),
+    };
+    let parents: Vec<&git2::Commit> = parents.iter().collect();
+
+    let id = write_commit(
+        &repo,
+        git.sign_commits.then_some(git),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &parents,
+    )?;
+    update_head(&repo, id, &message)?;
+
+    Ok(CommitInfo {
+        id: id.to_string(),
+        branch,
+        summary: message.lines().next().unwrap_or_default().to_string(),
+        amend: new.amend,
+        signed: git.sign_commits,
+        large_files,
+    })
+}
+
+/// Apply the commit template
+///
+/// `{message}`, `{branch}` and `{issue}` (as `#42`) are substituted; a template
+/// without `{message}` is appended below the message. An empty template leaves
+/// the message as is.
+fn render_message(
+    template: &str,
+    message: &str,
+    branch: Option<&str>,
+    issue: Option<u64>,
+    sign_off: Option<&Signature>,
+) -> String {
+    let message = message.trim();
+    let mut rendered = if template.trim().is_empty() {
+        message.to_string()
+    } else {
+        let filled = template
+            .replace("{branch}", branch.unwrap_or_default())
+            .replace(
+                "{issue}",
+                &issue.map(|n| format!("#{}", n)).unwrap_or_default(),
+            );
+        if filled.contains("{message}") {
+            filled.replace("{message}", message)
+        } else {
+            format!("{}\n\n{}", message, filled.trim())
+        }
+    };
+
+    if let Some(signature) = sign_off {
+        let trailer = format!(
+            "Signed-off-by: {} <{}>",
+            signature.name().unwrap_or_default(),
+            signature.email().unwrap_or_default()
+        );
+        if !rendered.contains(&trailer) {
+            rendered = format!("{}\n\n{}", rendered.trim_end(), trailer);
+        }
+    }
+
+    let mut rendered = rendered.trim_end().to_string();
+    rendered.push('\n');
+    rendered
+}
+
+/// Write a commit without moving any ref, GPG-signed when `sign` is given
+pub(super) fn write_commit(
+    repo: &Repository,
+    sign: Option<&GitSettings>,
+    author: &Signature,
+    committer: &Signature,
+    message: &str,
+    tree: &git2::Tree,
+    parents: &[&git2::Commit],
+) -> Result<Oid> {
+    let buffer = repo.commit_create_buffer(author, committer, message, tree, parents)?;
+    let content = buffer
+        .as_str()
+        .context("Commit content is not valid UTF-8")?;
+    Ok(match sign {
+        Some(git) => {
+            let key = signing_key(repo, git, committer)?;
+            let gpg_signature = gpg_sign(repo, &key, content)?;
+            repo.commit_signed(content, &gpg_signature, None)?
+        }
+        None => repo
+            .odb()?
+            .write(git2::ObjectType::Commit, content.as_bytes())?,
+    })
+}
+
+/// GitSettings::gpg_key_id, then `user.signingkey`, then the committer email
+fn signing_key(repo: &Repository, git: &GitSettings, signature: &Signature) -> Result<String> {
+    if let Some(key) = git.gpg_key_id.as_deref().filter(|key| !key.is_empty()) {
+        return Ok(key.to_string());
+    }
+    if let Ok(key) = repo.config()?.get_string("user.signingkey") {
+        return Ok(key);
+    }
+    signature
+        .email()
+        .map(str::to_string)
+        .context("No GPG key configured for signing")
+}
+
+/// Detached armored signature of `content`, made with `gpg.program` (default `gpg`)
+fn gpg_sign(repo: &Repository, key: &str, content: &str) -> Result<String> {
+    let program = repo
+        .config()?
+        .get_string("gpg.program")
+        .unwrap_or_else(|_| "gpg".to_string());
+
+    let mut child = Command::new(&program)
+        .args(["--status-fd=2", "-bsau", key])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+    child
+        .stdin
+        .take()
+        .context("Failed to open gpg stdin")?
+        .write_all(content.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "GPG signing failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    String::from_utf8(output.stdout).context("GPG signature is not valid UTF-8")
+}
+
+/// Point HEAD (or the branch it refers to, even before its first commit) at `id`
+fn update_head(repo: &Repository, id: Oid, message: &str) -> Result<()> {
+    let summary = message.lines().next().unwrap_or_default();
+    let log_message = format!("commit: {}", summary);
+
+    let head = repo.find_reference("HEAD")?;
+    match head.symbolic_target() {
+        Some(target) => {
+            repo.reference(target, id, true, &log_message)?;
+        }
+        None => repo.set_head_detached(id)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{self, init_repo};
+    use std::fs;
+
+    #[test]
+    fn test_render_message() {
+        let signature = test_support::signature();
+
+        assert_eq!(
+            render_message("", " Fix login ", None, None, None),
+            "Fix login\n"
+        );
+        assert_eq!(
+            render_message(
+                "{message}\n\nRefs {issue}",
+                "Fix login",
+                None,
+                Some(4),
+                None
+            ),
+            "Fix login\n\nRefs #4\n"
+        );
+        assert_eq!(
+            render_message(
+                "[{branch}]",
+                "Fix login",
+                Some("issue-4"),
+                None,
+                Some(&signature)
+            ),
+            "Fix login\n\n[issue-4]\n\nSigned-off-by: Test <test@example.com>\n"
+        );
+    }
+
+    #[test]
+    fn test_create_and_amend_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        let git = GitSettings::default();
+        fs::write(dir.path().join("a.txt"), "one").unwrap();
+
+        let first = create_commit(
+            dir.path(),
+            &NewCommit {
+                message: "Add a".to_string(),
+                files: vec!["a.txt".to_string()],
+                amend: false,
+            },
+            &git,
+            false,
+        )
+        .unwrap();
+        assert_eq!(first.summary, "Add a");
+        assert_eq!(
+            repo.head()
+                .unwrap()
+                .peel_to_commit()
+                .unwrap()
+                .id()
+                .to_string(),
+            first.id
+        );
+
+        let nothing = NewCommit {
+            message: "Again".to_string(),
+            ..NewCommit::default()
+        };
+        assert!(create_commit(dir.path(), &nothing, &git, false).is_err());
+
+        fs::remove_file(dir.path().join("a.txt")).unwrap();
+        let amended = create_commit(
+            dir.path(),
+            &NewCommit {
+                message: "Add nothing".to_string(),
+                files: vec!["a.txt".to_string()],
+                amend: true,
+            },
+            &git,
+            false,
+        )
+        .unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.id().to_string(), amended.id);
+        assert_eq!(head.parent_count(), 0);
+        assert!(head.tree().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lint_rejects_message() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+        let git = GitSettings {
+            lint_commit_messages: true,
+            ..GitSettings::default()
+        };
+        fs::write(dir.path().join("a.txt"), "one").unwrap();
+
+        let mut new = NewCommit {
+            message: "Added a".to_string(),
+            files: vec!["a.txt".to_string()],
+            amend: false,
+        };
+        let error = create_commit(dir.path(), &new, &git, true).unwrap_err();
+        assert!(format!("{:#}", error).contains("header-format"));
+
+        new.message = "feat: add a".to_string();
+        create_commit(dir.path(), &new, &git, true).unwrap();
+    }
+}
@@ -0,0 +1,413 @@
+use super::open;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Conventional commit types
+pub const COMMIT_TYPES: [&str; 11] = [
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert",
+];
+
+/// Longest allowed header line
+pub const HEADER_MAX_LENGTH: usize = 72;
+
+/// First line after the shebang of a commit-msg hook written by install_commit_msg_hook
+const HOOK_MARKER: &str = "# Installed by zeami";
+
+/// Line below which git drops the rest of a message (`commit --verbose`)
+const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+/// Common misspellings of a commit type
+const TYPE_ALIASES: [(&str, &str); 8] = [
+    ("feature", "feat"),
+    ("features", "feat"),
+    ("bugfix", "fix"),
+    ("fixed", "fix"),
+    ("hotfix", "fix"),
+    ("doc", "docs"),
+    ("tests", "test"),
+    ("refactoring", "refactor"),
+];
+
+/// Leading verbs used to guess a type for a header without one
+const VERB_TYPES: [(&str, &str); 10] = [
+    ("add", "feat"),
+    ("implement", "feat"),
+    ("introduce", "feat"),
+    ("support", "feat"),
+    ("fix", "fix"),
+    ("correct", "fix"),
+    ("resolve", "fix"),
+    ("refactor", "refactor"),
+    ("simplify", "refactor"),
+    ("document", "docs"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A broken rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Violation {
+    /// Rule id, e.g. `type-enum`
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    /// What would fix it, when an automatic fix exists
+    pub suggestion: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintResult {
+    /// No errors (warnings are allowed)
+    pub valid: bool,
+    pub violations: Vec<Violation>,
+    /// The message with every automatic fix applied; None when there is nothing to fix
+    pub fixed: Option<String>,
+}
+
+/// Check `message` against the conventional commit rules
+///
+/// `issue` is the issue the commit should reference (as `#42`), set when
+/// GitHubSettings::auto_link_issues is on and the branch is linked to one.
+pub fn lint(message: &str, issue: Option<u64>) -> LintResult {
+    let mut violations = Vec::new();
+    let message = message.trim();
+    let mut lines: Vec<String> = message.lines().map(str::to_string).collect();
+    if lines.is_empty() {
+        violations.push(violation(
+            "header-empty",
+            Severity::Error,
+            "Commit message is empty".to_string(),
+            None,
+        ));
+        return finish(violations, message, None);
+    }
+
+    let header = lines[0].clone();
+    let fixed_header = lint_header(&header, &mut violations);
+    lines[0] = fixed_header.clone();
+
+    if lines.len() > 1 && !lines[1].trim().is_empty() {
+        violations.push(violation(
+            "body-leading-blank",
+            Severity::Warning,
+            "Separate the body from the header with a blank line".to_string(),
+            Some("Insert a blank line after the header".to_string()),
+        ));
+        lines.insert(1, String::new());
+    }
+
+    if let Some(issue) = issue {
+        if !references_issue(message, issue) {
+            violations.push(violation(
+                "issue-reference",
+                Severity::Warning,
+                format!("Reference the linked issue #{}", issue),
+                Some(format!("Append `Refs #{}`", issue)),
+            ));
+            lines.push(String::new());
+            lines.push(format!("Refs #{}", issue));
+        }
+    }
+
+    let fixed = lines.join("\n");
+    finish(violations, message, Some(fixed))
+}
+
+/// A message file as git passes it to the commit-msg hook, without the
+/// comment lines and diff that git removes after the hook has run
+pub fn edited_message(text: &str) -> String {
+    text.lines()
+        .take_while(|line| *line != SCISSORS)
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Install a commit-msg hook in the repository at `repo_path` that runs
+/// `zeami dev lint-commit`, so commits made in a terminal are linted like
+/// those made through create_commit. Returns the hook's path.
+///
+/// A commit-msg hook not written by Zeami is left alone. The hook lets
+/// commits through when `zeami` is not on PATH.
+pub fn install_commit_msg_hook(repo_path: &Path) -> Result<PathBuf> {
+    let repo = open(repo_path)?;
+    let hooks = match repo.config()?.get_path("core.hooksPath") {
+        Ok(path) if path.is_relative() => super::workdir(&repo)?.join(path),
+        Ok(path) => path,
+        Err(_) => repo.path().join("hooks"),
+    };
+    let path = hooks.join("commit-msg");
+    if let Ok(existing) = fs::read_to_string(&path) {
+        if !existing.contains(HOOK_MARKER) {
+            bail!(
+                "{:?} already exists; call `zeami dev lint-commit \"$1\"` from it",
+                path
+            );
+        }
+    }
+
+    fs::create_dir_all(&hooks).with_context(|| format!("Failed to create {:?}", hooks))?;
+    let script = format!(
+        "#!/bin/sh\n{}\ncommand -v zeami >/dev/null 2>&1 || exit 0\nexec zeami dev lint-commit \"$1\"\n",
+        HOOK_MARKER
+    );
+    fs::write(&path, script).with_context(|| format!("Failed to write {:?}", path))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(path)
+}
+
+/// Check the `type(scope)!: subject` header and return it with fixes applied
+fn lint_header(header: &str, violations: &mut Vec<Violation>) -> String {
+    let Some((prefix, subject)) = header.split_once(':') else {
+        let guessed = guess_type(header);
+        violations.push(violation(
+            "header-format",
+            Severity::Error,
+            "Header must look like `type(scope): subject`".to_string(),
+            guessed.as_ref().map(|fixed| format!("Use `{}`", fixed)),
+        ));
+        return guessed.unwrap_or_else(|| header.to_string());
+    };
+
+    let breaking = prefix.ends_with('!');
+    let prefix = prefix.trim_end_matches('!');
+    let (kind, scope) = match prefix.split_once('(') {
+        Some((kind, rest)) => (kind, rest.strip_suffix(')')),
+        None => (prefix, None),
+    };
+    if prefix.contains('(') && scope.is_none() {
+        violations.push(violation(
+            "scope-format",
+            Severity::Error,
+            "Scope must be wrapped in parentheses, e.g. `fix(parser): ...`".to_string(),
+            None,
+        ));
+    }
+
+    let mut kind = kind.trim().to_string();
+    if kind.chars().any(|c| c.is_uppercase()) {
+        violations.push(violation(
+            "type-case",
+            Severity::Error,
+            format!("Type `{}` must be lower case", kind),
+            Some(format!("Use `{}`", kind.to_lowercase())),
+        ));
+        kind = kind.to_lowercase();
+    }
+    if !COMMIT_TYPES.contains(&kind.as_str()) {
+        let alias = TYPE_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == kind)
+            .map(|(_, kind)| kind.to_string());
+        violations.push(violation(
+            "type-enum",
+            Severity::Error,
+            format!("Type must be one of {}", COMMIT_TYPES.join(", ")),
+            alias.as_ref().map(|alias| format!("Use `{}`", alias)),
+        ));
+        if let Some(alias) = alias {
+            kind = alias;
+        }
+    }
+
+    let scope = scope.map(str::trim).filter(|scope| !scope.is_empty());
+    let mut scope = scope.map(str::to_string);
+    if let Some(current) = &scope {
+        if current.chars().any(|c| c.is_uppercase()) {
+            violations.push(violation(
+                "scope-case",
+                Severity::Warning,
+                format!("Scope `{}` should be lower case", current),
+                Some(format!("Use `{}`", current.to_lowercase())),
+            ));
+            scope = Some(current.to_lowercase());
+        }
+    }
+
+    let mut subject = subject.trim().to_string();
+    if subject.is_empty() {
+        violations.push(violation(
+            "subject-empty",
+            Severity::Error,
+            "Subject must not be empty".to_string(),
+            None,
+        ));
+    }
+    if subject.ends_with('.') {
+        violations.push(violation(
+            "subject-full-stop",
+            Severity::Warning,
+            "Subject should not end with a period".to_string(),
+            Some("Remove the trailing period".to_string()),
+        ));
+        subject = subject.trim_end_matches('.').to_string();
+    }
+
+    let fixed = format!(
+        "{}{}{}: {}",
+        kind,
+        scope
+            .map(|scope| format!("({})", scope))
+            .unwrap_or_default(),
+        if breaking { "!" } else { "" },
+        subject
+    );
+    let length = fixed.chars().count();
+    if length > HEADER_MAX_LENGTH {
+        violations.push(violation(
+            "header-max-length",
+            Severity::Error,
+            format!(
+                "Header is {} characters; keep it within {}",
+                length, HEADER_MAX_LENGTH
+            ),
+            None,
+        ));
+    }
+    fixed
+}
+
+/// `Add login` becomes `feat: add login`
+fn guess_type(header: &str) -> Option<String> {
+    let header = header.trim();
+    let verb = header.split_whitespace().next()?.to_lowercase();
+    let (_, kind) = VERB_TYPES
+        .iter()
+        .find(|(candidate, _)| *candidate == verb)?;
+    let mut chars = header.chars();
+    let first = chars.next()?.to_lowercase();
+    Some(format!(
+        "{}: {}{}",
+        kind,
+        first,
+        chars.as_str().trim_end_matches('.')
+    ))
+}
+
+/// Whether `#<issue>` appears as a whole number
+fn references_issue(message: &str, issue: u64) -> bool {
+    let needle = format!("#{}", issue);
+    message.match_indices(&needle).any(|(start, _)| {
+        !message[start + needle.len()..].starts_with(|c: char| c.is_ascii_digit())
+    })
+}
+
+fn violation(
+    rule: &str,
+    severity: Severity,
+    message: String,
+    suggestion: Option<String>,
+) -> Violation {
+    Violation {
+        rule: rule.to_string(),
+        severity,
+        message,
+        suggestion,
+    }
+}
+
+fn finish(violations: Vec<Violation>, original: &str, fixed: Option<String>) -> LintResult {
+    LintResult {
+        valid: violations
+            .iter()
+            .all(|violation| violation.severity == Severity::Warning),
+        fixed: fixed.filter(|fixed| fixed != original),
+        violations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(result: &LintResult) -> Vec<&str> {
+        result
+            .violations
+            .iter()
+            .map(|violation| violation.rule.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_message() {
+        let result = lint("feat(pty)!: resize sessions\n\nRefs #12", Some(12));
+        assert!(result.valid);
+        assert!(result.violations.is_empty());
+        assert!(result.fixed.is_none());
+    }
+
+    #[test]
+    fn test_fixable_violations() {
+        let result = lint("Feature(PTY): resize sessions.\nBody", Some(4));
+        assert!(!result.valid);
+        assert_eq!(
+            rules(&result),
+            vec![
+                "type-case",
+                "type-enum",
+                "scope-case",
+                "subject-full-stop",
+                "body-leading-blank",
+                "issue-reference"
+            ]
+        );
+        assert_eq!(
+            result.fixed.as_deref(),
+            Some("feat(pty): resize sessions\n\nBody\n\nRefs #4")
+        );
+        assert!(lint(result.fixed.as_deref().unwrap(), Some(4)).valid);
+    }
+
+    #[test]
+    fn test_header_without_type() {
+        let result = lint("Add login page", None);
+        assert_eq!(rules(&result), vec!["header-format"]);
+        assert_eq!(result.fixed.as_deref(), Some("feat: add login page"));
+
+        let result = lint("wip", None);
+        assert!(!result.valid);
+        assert!(result.fixed.is_none());
+    }
+
+    #[test]
+    fn test_issue_reference_is_whole_number() {
+        assert!(references_issue("Refs #42", 42));
+        assert!(!references_issue("Refs #420", 42));
+        assert!(!lint(&format!("fix: {}", "x".repeat(80)), None).valid);
+    }
+
+    #[test]
+    fn test_commit_msg_hook() {
+        let dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(dir.path()).unwrap();
+
+        let path = install_commit_msg_hook(dir.path()).unwrap();
+        assert_eq!(path, dir.path().join(".git/hooks/commit-msg"));
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("zeami dev lint-commit \"$1\""));
+        // Reinstalling replaces Zeami's own hook, but not someone else's
+        install_commit_msg_hook(dir.path()).unwrap();
+        fs::write(&path, "#!/bin/sh\nexit 0\n").unwrap();
+        assert!(install_commit_msg_hook(dir.path()).is_err());
+
+        assert_eq!(
+            edited_message(&format!(
+                "fix: resize\n# Please enter the commit message\n\nBody\n{}\ndiff --git a b\n",
+                SCISSORS
+            )),
+            "fix: resize\n\nBody"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use super::status::OperationState;
+use super::{open, workdir};
+use anyhow::{Context, Result};
+use git2::{IndexEntry, Repository};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// One side of a conflicted file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictSide {
+    /// Path on this side; differs from the working tree path after a rename
+    pub path: String,
+    pub id: String,
+    /// None for binary blobs
+    pub content: Option<String>,
+}
+
+/// A conflicted file with everything needed for a three-way merge view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictFile {
+    pub path: String,
+    /// Common ancestor; None when both sides added the file
+    pub base: Option<ConflictSide>,
+    /// None when our side deleted the file
+    pub ours: Option<ConflictSide>,
+    /// None when their side deleted the file
+    pub theirs: Option<ConflictSide>,
+    /// Working tree file with conflict markers; None when missing or binary
+    pub merged: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeConflicts {
+    pub operation: OperationState,
+    pub files: Vec<ConflictFile>,
+}
+
+/// How to resolve a conflicted file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    Base,
+    /// Hand-merged content from the editor
+    Content {
+        content: String,
+    },
+}
+
+/// Conflicted files of the merge, rebase or cherry-pick in progress
+pub fn get_merge_conflicts(repo_path: &Path) -> Result<MergeConflicts> {
+    let repo = open(repo_path)?;
+    let root = workdir(&repo)?;
+    let index = repo.index()?;
+
+    let mut files = Vec::new();
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let base = side(&repo, conflict.ancestor.as_ref())?;
+        let ours = side(&repo, conflict.our.as_ref())?;
+        let theirs = side(&repo, conflict.their.as_ref())?;
+
+        let Some(path) = ours
+            .as_ref()
+            .or(theirs.as_ref())
+            .or(base.as_ref())
+            .map(|side| side.path.clone())
+        else {
+            continue;
+        };
+        let merged = fs::read(root.join(&path))
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok());
+        files.push(ConflictFile {
+            path,
+            base,
+            ours,
+            theirs,
+            merged,
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(MergeConflicts {
+        operation: repo.state().into(),
+        files,
+    })
+}
+
+/// Write the chosen side of `file` to the working tree and stage it
+///
+/// Choosing a side that deleted the file removes it.
+pub fn resolve_conflict(repo_path: &Path, file: &str, resolution: &Resolution) -> Result<()> {
+    let repo = open(repo_path)?;
+    let root = workdir(&repo)?;
+    let mut index = repo.index()?;
+
+    let conflict = index
+        .conflicts()?
+        .filter_map(|conflict| conflict.ok())
+        .find(|conflict| {
+            [&conflict.our, &conflict.their, &conflict.ancestor]
+                .into_iter()
+                .flatten()
+                .any(|entry| entry.path == file.as_bytes())
+        })
+        .with_context(|| format!("{} is not conflicted", file))?;
+
+    let content = match resolution {
+        Resolution::Content { content } => Some(content.as_bytes().to_vec()),
+        Resolution::Ours => blob(&repo, conflict.our.as_ref())?,
+        Resolution::Theirs => blob(&repo, conflict.their.as_ref())?,
+        Resolution::Base => blob(&repo, conflict.ancestor.as_ref())?,
+    };
+
+    let relative = Path::new(file);
+    let full_path = root.join(relative);
+    match content {
+        Some(content) => {
+            if let Some(parent) = full_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&full_path, content).with_context(|| format!("Failed to write {}", file))?;
+            index.add_path(relative)?;
+        }
+        None => {
+            if full_path.exists() {
+                fs::remove_file(&full_path)
+                    .with_context(|| format!("Failed to remove {}", file))?;
+            }
+            index.remove_path(relative)?;
+        }
+    }
+    index.write()?;
+    Ok(())
+}
+
+fn side(repo: &Repository, entry: Option<&IndexEntry>) -> Result<Option<ConflictSide>> {
+    let Some(entry) = entry else {
+        return Ok(None);
+    };
+    let blob = repo.find_blob(entry.id)?;
+    Ok(Some(ConflictSide {
+        path: String::from_utf8_lossy(&entry.path).into_owned(),
+        id: entry.id.to_string(),
+        content: if blob.is_binary() {
+            None
+        } else {
+            String::from_utf8(blob.content().to_vec()).ok()
+        },
+    }))
+}
+
+/// Contents of one side; None when that side deleted the file
+fn blob(repo: &Repository, entry: Option<&IndexEntry>) -> Result<Option<Vec<u8>>> {
+    match entry {
+        Some(entry) => Ok(Some(repo.find_blob(entry.id)?.content().to_vec())),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_file, init_repo};
+
+    /// Repository in the middle of a merge with a conflict in a.txt
+    fn conflicted_repo(dir: &Path) {
+        let repo = init_repo(dir);
+        let base = commit_file(&repo, "a.txt", "base\n", "Base");
+        {
+            let base = repo.find_commit(base).unwrap();
+            repo.branch("other", &base, false).unwrap();
+        }
+        commit_file(&repo, "a.txt", "ours\n", "Ours");
+
+        repo.set_head("refs/heads/other").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        let theirs = commit_file(&repo, "a.txt", "theirs\n", "Theirs");
+        repo.set_head("refs/heads/main").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+
+        let annotated = repo.find_annotated_commit(theirs).unwrap();
+        repo.merge(&[&annotated], None, None).unwrap();
+    }
+
+    #[test]
+    fn test_list_and_resolve_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        conflicted_repo(dir.path());
+
+        let conflicts = get_merge_conflicts(dir.path()).unwrap();
+        assert_eq!(conflicts.operation, OperationState::Merge);
+        assert_eq!(conflicts.files.len(), 1);
+        let file = &conflicts.files[0];
+        assert_eq!(file.path, "a.txt");
+        assert_eq!(
+            file.base.as_ref().unwrap().content.as_deref(),
+            Some("base\n")
+        );
+        assert_eq!(
+            file.ours.as_ref().unwrap().content.as_deref(),
+            Some("ours\n")
+        );
+        assert_eq!(
+            file.theirs.as_ref().unwrap().content.as_deref(),
+            Some("theirs\n")
+        );
+        assert!(file.merged.as_deref().unwrap().contains("<<<<<<<"));
+
+        assert!(resolve_conflict(dir.path(), "missing.txt", &Resolution::Ours).is_err());
+        resolve_conflict(dir.path(), "a.txt", &Resolution::Theirs).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "theirs\n"
+        );
+        let repo = Repository::open(dir.path()).unwrap();
+        assert!(!repo.index().unwrap().has_conflicts());
+        assert!(get_merge_conflicts(dir.path()).unwrap().files.is_empty());
+    }
+}
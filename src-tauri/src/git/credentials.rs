@@ -0,0 +1,225 @@
+//! Credentials for fetch, push and submodule updates
+//!
+//! libgit2 asks for credentials again each time the last ones were refused,
+//! so every request is answered by the next source in a chain: ssh-agent,
+//! then the keychain deploy key for SSH remotes; the git credential helper,
+//! then the GitHub token for HTTPS. A GitSettings::remote_credentials entry
+//! matching the remote URL can pin a single source.
+
+use crate::config::{keychain, CredentialSource, GitSettings, RemoteCredential, Settings};
+use git2::{Cred, CredentialType, RemoteCallbacks};
+use std::cell::Cell;
+
+/// Credential requests before giving up, so a misbehaving transport can't loop forever
+const MAX_CREDENTIAL_ATTEMPTS: usize = 8;
+
+/// User name the GitHub token authenticates with over HTTPS
+const TOKEN_USERNAME: &str = "x-access-token";
+
+/// The sources credentials for a repository's remotes come from
+#[derive(Debug, Default)]
+pub struct Credentials {
+    /// The GitHub token of the selected account
+    token: Option<String>,
+    overrides: Vec<RemoteCredential>,
+    /// The source of the last credentials handed out
+    used: Cell<Option<CredentialSource>>,
+}
+
+impl Credentials {
+    pub fn new(git: &GitSettings, token: Option<String>) -> Self {
+        Self {
+            token,
+            overrides: git.remote_credentials.clone(),
+            used: Cell::new(None),
+        }
+    }
+
+    /// Credentials for `settings`, reading the GitHub token from the keychain
+    /// (a failure to read it is logged and leaves HTTPS to the helper)
+    pub fn load(settings: &Settings) -> Self {
+        let token = keychain::github_token(&settings.github).unwrap_or_else(|e| {
+            tracing::warn!("Failed to read the GitHub token for git: {:#}", e);
+            None
+        });
+        Self::new(&settings.git, token)
+    }
+
+    /// The source of the credentials the remote last accepted or refused;
+    /// None when it asked for none
+    pub fn used(&self) -> Option<CredentialSource> {
+        self.used.get()
+    }
+
+    /// Callbacks answering credential requests from this chain
+    pub(super) fn callbacks<'a>(&'a self, config: &'a git2::Config) -> RemoteCallbacks<'a> {
+        let mut attempts = 0;
+        let mut tried: Vec<CredentialSource> = Vec::new();
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(move |url, username, allowed| {
+            attempts += 1;
+            if attempts > MAX_CREDENTIAL_ATTEMPTS {
+                return Err(git2::Error::from_str("Authentication failed"));
+            }
+            let pinned = self.overrides_for(url);
+            let username = username
+                .or(pinned.and_then(|credential| credential.username.as_deref()))
+                .unwrap_or("git");
+            if allowed == CredentialType::USERNAME {
+                return Cred::username(username);
+            }
+            for source in chain(pinned.map(|credential| credential.source), allowed) {
+                if tried.contains(&source) {
+                    continue;
+                }
+                tried.push(source);
+                if let Some(cred) = self.credential(source, config, url, username, pinned) {
+                    self.used.set(Some(source));
+                    return Ok(cred);
+                }
+            }
+            Err(git2::Error::from_str(&format!(
+                "Authentication failed for {}; tried {}",
+                url,
+                describe(&tried)
+            )))
+        });
+        callbacks
+    }
+
+    fn overrides_for(&self, url: &str) -> Option<&RemoteCredential> {
+        self.overrides
+            .iter()
+            .filter(|credential| !credential.url.is_empty() && url.contains(&credential.url))
+            .max_by_key(|credential| credential.url.len())
+    }
+
+    /// Credentials from `source`; None when it has none to offer
+    fn credential(
+        &self,
+        source: CredentialSource,
+        config: &git2::Config,
+        url: &str,
+        username: &str,
+        pinned: Option<&RemoteCredential>,
+    ) -> Option<Cred> {
+        match source {
+            CredentialSource::SshAgent => Cred::ssh_key_from_agent(username).ok(),
+            CredentialSource::DeployKey => {
+                let pinned = pinned?;
+                let key = keychain::retrieve_deploy_key(&pinned.url)
+                    .inspect_err(|e| tracing::warn!("Failed to read deploy key: {:#}", e))
+                    .ok()??;
+                Cred::ssh_key_from_memory(username, None, &key, None).ok()
+            }
+            CredentialSource::CredentialHelper => {
+                Cred::credential_helper(config, url, Some(username)).ok()
+            }
+            CredentialSource::GithubToken => {
+                let token = self.token.as_deref()?;
+                Cred::userpass_plaintext(TOKEN_USERNAME, token).ok()
+            }
+            CredentialSource::Auto => None,
+        }
+    }
+}
+
+/// Sources to try, in order, for a request allowing `allowed`
+fn chain(pinned: Option<CredentialSource>, allowed: CredentialType) -> Vec<CredentialSource> {
+    let sources = match pinned {
+        Some(CredentialSource::Auto) | None => vec![
+            CredentialSource::SshAgent,
+            CredentialSource::DeployKey,
+            CredentialSource::CredentialHelper,
+            CredentialSource::GithubToken,
+        ],
+        Some(source) => vec![source],
+    };
+    sources
+        .into_iter()
+        .filter(|source| match source {
+            CredentialSource::SshAgent | CredentialSource::DeployKey => {
+                allowed.contains(CredentialType::SSH_KEY)
+                    || allowed.contains(CredentialType::SSH_MEMORY)
+            }
+            CredentialSource::CredentialHelper | CredentialSource::GithubToken => {
+                allowed.contains(CredentialType::USER_PASS_PLAINTEXT)
+            }
+            CredentialSource::Auto => false,
+        })
+        .collect()
+}
+
+fn describe(sources: &[CredentialSource]) -> String {
+    if sources.is_empty() {
+        return "no credentials".to_string();
+    }
+    sources
+        .iter()
+        .map(|source| match source {
+            CredentialSource::Auto => "auto",
+            CredentialSource::SshAgent => "ssh-agent",
+            CredentialSource::DeployKey => "the deploy key",
+            CredentialSource::CredentialHelper => "the credential helper",
+            CredentialSource::GithubToken => "the GitHub token",
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_follows_the_transport_and_overrides() {
+        let ssh = CredentialType::SSH_KEY | CredentialType::SSH_MEMORY;
+        assert_eq!(
+            chain(None, ssh),
+            [CredentialSource::SshAgent, CredentialSource::DeployKey]
+        );
+        assert_eq!(
+            chain(
+                Some(CredentialSource::Auto),
+                CredentialType::USER_PASS_PLAINTEXT
+            ),
+            [
+                CredentialSource::CredentialHelper,
+                CredentialSource::GithubToken
+            ]
+        );
+        assert_eq!(
+            chain(Some(CredentialSource::DeployKey), ssh),
+            [CredentialSource::DeployKey]
+        );
+        // A pinned source the transport can't use leaves nothing to try
+        assert!(chain(Some(CredentialSource::GithubToken), ssh).is_empty());
+    }
+
+    #[test]
+    fn test_longest_matching_override_wins() {
+        let override_for = |url: &str, source| RemoteCredential {
+            url: url.to_string(),
+            source,
+            username: None,
+        };
+        let git = GitSettings {
+            remote_credentials: vec![
+                override_for("github.com", CredentialSource::SshAgent),
+                override_for("github.com:acme/infra", CredentialSource::DeployKey),
+            ],
+            ..GitSettings::default()
+        };
+        let credentials = Credentials::new(&git, None);
+        let source = |url| credentials.overrides_for(url).map(|c| c.source);
+        assert_eq!(
+            source("git@github.com:acme/infra.git"),
+            Some(CredentialSource::DeployKey)
+        );
+        assert_eq!(
+            source("git@github.com:acme/app.git"),
+            Some(CredentialSource::SshAgent)
+        );
+        assert_eq!(source("https://gitlab.com/acme/app.git"), None);
+    }
+}
@@ -0,0 +1,306 @@
+use super::open;
+use super::status::{file_change, ChangeKind};
+use anyhow::{Context, Result};
+use git2::{Delta, Diff, DiffFormat, DiffOptions, Patch, Repository, Sort};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// What a file is compared against
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DiffTarget {
+    /// Index against HEAD (`git diff --staged`)
+    Staged,
+    /// Working tree, including staged changes, against HEAD (`git diff HEAD`)
+    Head,
+    /// A commit against its first parent (`git show <id>`)
+    Commit { id: String },
+}
+
+/// Whether a diff line was added, removed or is unchanged context
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// One line of a hunk, without its trailing newline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    pub old_lineno: Option<u32>,
+    pub new_lineno: Option<u32>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    /// `@@ -1,4 +1,5 @@` header including any function context
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Structured diff of a single file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub path: String,
+    /// None when the file is unchanged
+    pub kind: Option<ChangeKind>,
+    pub old_path: Option<String>,
+    /// Binary files have no hunks
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// Commits and combined diff of a branch compared to its base
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RangeDiff {
+    /// Commit summaries in `base..head`, oldest first
+    pub commits: Vec<String>,
+    /// Unified diff from the merge base to `head`
+    pub patch: String,
+}
+
+/// Commits listed in a RangeDiff at most
+const RANGE_COMMIT_LIMIT: usize = 100;
+
+/// Unified diff of the whole repository against `target`, including untracked files
+pub fn get_patch(repo_path: &Path, target: &DiffTarget) -> Result<String> {
+    let repo = open(repo_path)?;
+    let mut options = DiffOptions::new();
+    options
+        .include_untracked(true)
+        .show_untracked_content(true)
+        .recurse_untracked_dirs(true);
+    let diff = diff_for(&repo, target, &mut options)?;
+    patch_text(&diff)
+}
+
+/// What `head` adds on top of `base`, as a pull request would show it
+pub fn get_range_diff(repo_path: &Path, base: &str, head: &str) -> Result<RangeDiff> {
+    let repo = open(repo_path)?;
+    let resolve = |spec: &str| {
+        repo.revparse_single(spec)
+            .and_then(|object| object.peel_to_commit())
+            .with_context(|| format!("Unknown revision {}", spec))
+    };
+    let base = resolve(base)?;
+    let head = resolve(head)?;
+    let merge_base = repo
+        .find_commit(repo.merge_base(base.id(), head.id())?)
+        .context("Branches have no common history")?;
+
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    walk.push(head.id())?;
+    walk.hide(merge_base.id())?;
+    let mut commits = Vec::new();
+    for id in walk.take(RANGE_COMMIT_LIMIT) {
+        let commit = repo.find_commit(id?)?;
+        commits.push(commit.summary().unwrap_or_default().to_string());
+    }
+
+    let diff = repo.diff_tree_to_tree(
+        Some(&merge_base.tree()?),
+        Some(&head.tree()?),
+        Some(&mut DiffOptions::new()),
+    )?;
+    Ok(RangeDiff {
+        commits,
+        patch: patch_text(&diff)?,
+    })
+}
+
+/// Diff of `file` (relative to the repository root) against `target`
+pub fn get_file_diff(repo_path: &Path, file: &str, target: &DiffTarget) -> Result<FileDiff> {
+    let repo = open(repo_path)?;
+    let mut options = DiffOptions::new();
+    options
+        .pathspec(file)
+        .disable_pathspec_match(true)
+        .include_untracked(true)
+        .show_untracked_content(true)
+        .recurse_untracked_dirs(true);
+    let diff = diff_for(&repo, target, &mut options)?;
+
+    let mut result = FileDiff {
+        path: file.to_string(),
+        kind: None,
+        old_path: None,
+        binary: false,
+        hunks: Vec::new(),
+    };
+    if diff.deltas().len() == 0 {
+        return Ok(result);
+    }
+
+    let Some(patch) = Patch::from_diff(&diff, 0)? else {
+        result.binary = true;
+        return Ok(result);
+    };
+    let delta = patch.delta();
+    if let Some(change) = file_change(&delta) {
+        result.kind = Some(change.kind);
+        result.old_path = change.old_path;
+    } else if delta.status() == Delta::Untracked {
+        result.kind = Some(ChangeKind::Added);
+    }
+    result.binary = delta.flags().is_binary();
+
+    for hunk_index in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_index)?;
+        let mut lines = Vec::with_capacity(line_count);
+        for line_index in 0..line_count {
+            let line = patch.line_in_hunk(hunk_index, line_index)?;
+            let kind = match line.origin() {
+                '+' => LineKind::Added,
+                '-' => LineKind::Removed,
+                ' ' => LineKind::Context,
+                // "\ No newline at end of file" markers
+                _ => continue,
+            };
+            lines.push(DiffLine {
+                kind,
+                old_lineno: line.old_lineno(),
+                new_lineno: line.new_lineno(),
+                content: String::from_utf8_lossy(line.content())
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string(),
+            });
+        }
+        result.hunks.push(DiffHunk {
+            header: String::from_utf8_lossy(hunk.header())
+                .trim_end()
+                .to_string(),
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            lines,
+        });
+    }
+    Ok(result)
+}
+
+fn patch_text(diff: &Diff<'_>) -> Result<String> {
+    let mut patch = String::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            patch.push(line.origin());
+        }
+        patch.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })?;
+    Ok(patch)
+}
+
+fn diff_for<'r>(
+    repo: &'r Repository,
+    target: &DiffTarget,
+    options: &mut DiffOptions,
+) -> Result<Diff<'r>> {
+    // Before the first commit everything is compared against an empty tree
+    let head_tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+    let diff = match target {
+        DiffTarget::Staged => repo.diff_tree_to_index(head_tree.as_ref(), None, Some(options))?,
+        DiffTarget::Head => {
+            repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(options))?
+        }
+        DiffTarget::Commit { id } => {
+            let commit = repo
+                .revparse_single(id)
+                .and_then(|object| object.peel_to_commit())
+                .with_context(|| format!("Unknown commit {}", id))?;
+            let parent_tree = match commit.parents().next() {
+                Some(parent) => Some(parent.tree()?),
+                None => None,
+            };
+            repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(options))?
+        }
+    };
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_all, init_repo};
+    use std::fs;
+
+    #[test]
+    fn test_file_diff_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        fs::write(dir.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        let first = commit_all(&repo, "Add a");
+
+        let diff = get_file_diff(dir.path(), "a.txt", &DiffTarget::Head).unwrap();
+        assert!(diff.kind.is_none());
+        assert!(diff.hunks.is_empty());
+
+        fs::write(dir.path().join("a.txt"), "one\n2\nthree\n").unwrap();
+        let diff = get_file_diff(dir.path(), "a.txt", &DiffTarget::Head).unwrap();
+        assert_eq!(diff.kind, Some(ChangeKind::Modified));
+        let lines = &diff.hunks[0].lines;
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1].kind, LineKind::Removed);
+        assert_eq!(lines[1].content, "two");
+        assert_eq!(lines[2].kind, LineKind::Added);
+        assert_eq!(lines[2].new_lineno, Some(2));
+        assert!(get_file_diff(dir.path(), "a.txt", &DiffTarget::Staged)
+            .unwrap()
+            .hunks
+            .is_empty());
+
+        let first = DiffTarget::Commit {
+            id: first.to_string(),
+        };
+        let diff = get_file_diff(dir.path(), "a.txt", &first).unwrap();
+        assert_eq!(diff.kind, Some(ChangeKind::Added));
+        assert_eq!(diff.hunks[0].lines.len(), 3);
+
+        fs::write(dir.path().join("new.txt"), "fresh\n").unwrap();
+        let diff = get_file_diff(dir.path(), "new.txt", &DiffTarget::Head).unwrap();
+        assert_eq!(diff.kind, Some(ChangeKind::Added));
+        assert_eq!(diff.hunks[0].lines[0].content, "fresh");
+    }
+
+    #[test]
+    fn test_patch_and_range_diff() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+        let base = commit_all(&repo, "Add a");
+        assert!(get_patch(dir.path(), &DiffTarget::Head).unwrap().is_empty());
+
+        fs::write(dir.path().join("a.txt"), "two\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "new\n").unwrap();
+        let patch = get_patch(dir.path(), &DiffTarget::Head).unwrap();
+        assert!(patch.contains("diff --git a/a.txt b/a.txt"));
+        assert!(patch.contains("-one\n+two\n"));
+        assert!(patch.contains("+new\n"));
+        assert!(get_patch(dir.path(), &DiffTarget::Staged)
+            .unwrap()
+            .is_empty());
+
+        {
+            let base = repo.find_commit(base).unwrap();
+            repo.branch("feature", &base, false).unwrap();
+        }
+        repo.set_head("refs/heads/feature").unwrap();
+        commit_all(&repo, "Change a");
+        fs::write(dir.path().join("a.txt"), "three\n").unwrap();
+        commit_all(&repo, "Change a again");
+
+        let range = get_range_diff(dir.path(), "main", "feature").unwrap();
+        assert_eq!(range.commits, vec!["Change a", "Change a again"]);
+        assert!(range.patch.contains("-one\n+three\n"));
+        assert!(get_range_diff(dir.path(), "main", "missing").is_err());
+    }
+}
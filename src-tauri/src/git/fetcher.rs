@@ -0,0 +1,85 @@
+use super::credentials::Credentials;
+use super::remote::{self, RemoteUpdate};
+use crate::config::{storage, SettingsState};
+use crate::events::schema::v1;
+use crate::events::GIT_REMOTE_UPDATED_EVENT_NAME;
+use crate::scheduler::{Job, JobFuture};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Scheduler name of the fetch job
+pub const FETCH_JOB: &str = "auto_fetch";
+
+/// Shortest allowed fetch interval, whatever fetch_interval says
+const MIN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Background fetch of the active repository
+///
+/// The frontend selects the repository with `watch_remote`; while
+/// GitSettings::auto_fetch is on, the scheduler fetches the remote every
+/// fetch_interval seconds and a `git-remote-updated` event carries the new
+/// ahead/behind counts.
+#[derive(Clone, Default)]
+pub struct FetchScheduler {
+    repo_path: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl FetchScheduler {
+    /// Fetch the repository at `repo_path`, or stop with None
+    pub fn watch(&self, repo_path: Option<PathBuf>) {
+        *self.repo_path.lock().unwrap() = repo_path;
+    }
+}
+
+impl Job for FetchScheduler {
+    fn interval(&self, app: &AppHandle) -> Option<Duration> {
+        let path = self.repo_path.lock().unwrap().clone()?;
+        match storage::settings_for_project(&app.state::<SettingsState>().current(), &path) {
+            Ok(settings) if settings.git.auto_fetch => {
+                Some(Duration::from_secs(settings.git.fetch_interval).max(MIN_INTERVAL))
+            }
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!(path = ?path, "Failed to load project settings: {:#}", e);
+                None
+            }
+        }
+    }
+
+    fn run(&self, app: &AppHandle) -> JobFuture {
+        let app = app.clone();
+        let path = self.repo_path.lock().unwrap().clone();
+        Box::pin(async move {
+            if let Some(path) = path {
+                fetch_and_emit(&app, &path)
+                    .await
+                    .with_context(|| format!("Automatic fetch of {:?} failed", path))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Fetch the remote of `path` off the async runtime and emit `git-remote-updated`
+pub async fn fetch_and_emit(app: &AppHandle, path: &Path) -> Result<RemoteUpdate> {
+    let settings = storage::settings_for_project(&app.state::<SettingsState>().current(), path)?;
+    let path = path.to_path_buf();
+    let root = path.clone();
+
+    let update = tauri::async_runtime::spawn_blocking(move || {
+        remote::fetch(&root, &settings.git, &Credentials::load(&settings))
+    })
+    .await??;
+
+    let payload = v1::GitRemoteUpdated {
+        project_root: path,
+        update: update.clone(),
+    };
+    if let Err(e) = app.emit_all(GIT_REMOTE_UPDATED_EVENT_NAME, payload) {
+        tracing::error!("Failed to emit remote update: {}", e);
+    }
+    Ok(update)
+}
@@ -0,0 +1,483 @@
+//! Repository statistics for the dashboard, from local history only
+//!
+//! Computing per-file line counts means diffing every commit, so the result
+//! for each commit is cached in `.zeami/insights-cache.json` keyed by its id;
+//! later calls only diff the commits made since.
+
+use super::open;
+use crate::config::atomic::write_atomic;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use git2::{DiffOptions, Oid, Patch, Repository, Sort};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+pub const INSIGHTS_CACHE_FILE: &str = ".zeami/insights-cache.json";
+
+/// Most commits walked per call
+const MAX_COMMITS: usize = 20_000;
+
+/// Commits kept in the cache; the oldest are dropped beyond this
+const MAX_CACHED_COMMITS: usize = 50_000;
+
+/// Entries in RepoInsights::churn and RepoInsights::hotspots
+const TOP_FILES: usize = 25;
+
+/// Days after which a change counts half as much towards a hotspot
+const HOTSPOT_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Commits included in get_repo_insights; open ends are unbounded
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InsightsRange {
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive
+    pub to: Option<DateTime<Utc>>,
+    /// History of this revision; HEAD when None
+    pub revision: Option<String>,
+}
+
+impl InsightsRange {
+    fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.from.is_none_or(|from| at >= from) && self.to.is_none_or(|to| at < to)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RepoInsights {
+    /// Commit the history was read from
+    pub head: String,
+    pub commits: usize,
+    pub merges: usize,
+    pub additions: usize,
+    pub deletions: usize,
+    pub first_commit: Option<DateTime<Utc>>,
+    pub last_commit: Option<DateTime<Utc>>,
+    /// Most commits first
+    pub authors: Vec<AuthorStats>,
+    /// Most changed lines first, at most TOP_FILES
+    pub churn: Vec<FileChurn>,
+    /// Files still present that change most often lately, at most TOP_FILES
+    pub hotspots: Vec<Hotspot>,
+    /// Commits per week, oldest first, weeks without commits included
+    pub weekly: Vec<WeekActivity>,
+    /// History was cut at MAX_COMMITS
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuthorStats {
+    /// As on the author's latest commit
+    pub name: String,
+    pub email: String,
+    pub commits: usize,
+    pub additions: usize,
+    pub deletions: usize,
+    pub first_commit: DateTime<Utc>,
+    pub last_commit: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileChurn {
+    pub path: String,
+    pub commits: usize,
+    pub additions: usize,
+    pub deletions: usize,
+    pub authors: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hotspot {
+    pub path: String,
+    pub commits: usize,
+    pub authors: usize,
+    /// Changes weighted by recency; each halves after HOTSPOT_HALF_LIFE_DAYS
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeekActivity {
+    /// Monday of the week
+    pub week: NaiveDate,
+    pub commits: usize,
+}
+
+/// What a commit changed, as cached
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CommitStats {
+    name: String,
+    email: String,
+    /// Seconds since the epoch
+    time: i64,
+    merge: bool,
+    /// Against the first parent; empty for merges
+    files: Vec<FileStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FileStats {
+    path: String,
+    additions: usize,
+    deletions: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Cache {
+    commits: HashMap<String, CommitStats>,
+}
+
+/// Statistics of the history of `range.revision` within the time range
+pub fn repo_insights(repo_path: &Path, range: &InsightsRange) -> Result<RepoInsights> {
+    let repo = open(repo_path)?;
+    let root = super::workdir(&repo)?;
+    let head = match &range.revision {
+        Some(revision) => repo
+            .revparse_single(revision)
+            .with_context(|| format!("Unknown revision {}", revision))?
+            .peel_to_commit()?,
+        None => repo.head()?.peel_to_commit()?,
+    };
+
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TIME)?;
+    walk.push(head.id())?;
+
+    let cache_path = root.join(INSIGHTS_CACHE_FILE);
+    let mut cache = load_cache(&cache_path);
+    let mut computed = 0;
+    let mut commits = Vec::new();
+    let mut truncated = false;
+    for (index, id) in walk.enumerate() {
+        if index == MAX_COMMITS {
+            truncated = true;
+            break;
+        }
+        let id = id?;
+        let stats = match cache.commits.get(&id.to_string()) {
+            Some(stats) => stats.clone(),
+            None => {
+                let stats = commit_stats(&repo, id)?;
+                cache.commits.insert(id.to_string(), stats.clone());
+                computed += 1;
+                stats
+            }
+        };
+        if let Some(at) = timestamp(stats.time) {
+            // Sorted by time, so nothing older can be in range
+            if range.from.is_some_and(|from| at < from) {
+                break;
+            }
+            if range.contains(at) {
+                commits.push(stats);
+            }
+        }
+    }
+    if computed > 0 {
+        if let Err(e) = save_cache(&cache_path, cache) {
+            tracing::warn!("Failed to save the insights cache: {:#}", e);
+        }
+    }
+
+    let present = tracked_files(&head.tree()?)?;
+    Ok(summarize(
+        head.id().to_string(),
+        &commits,
+        &present,
+        Utc::now(),
+        truncated,
+    ))
+}
+
+fn commit_stats(repo: &Repository, id: Oid) -> Result<CommitStats> {
+    let commit = repo.find_commit(id)?;
+    let author = commit.author();
+    let merge = commit.parent_count() > 1;
+    let mut files = Vec::new();
+    if !merge {
+        let parent_tree = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+        let mut options = DiffOptions::new();
+        options.context_lines(0);
+        let diff = repo.diff_tree_to_tree(
+            parent_tree.as_ref(),
+            Some(&commit.tree()?),
+            Some(&mut options),
+        )?;
+        for index in 0..diff.deltas().len() {
+            let Some(path) = diff
+                .get_delta(index)
+                .and_then(|delta| delta.new_file().path().or(delta.old_file().path()))
+                .map(|path| path.to_string_lossy().replace('\\', "/"))
+            else {
+                continue;
+            };
+            let (additions, deletions) = match Patch::from_diff(&diff, index)? {
+                Some(patch) => {
+                    let (_, additions, deletions) = patch.line_stats()?;
+                    (additions, deletions)
+                }
+                // Binary
+                None => (0, 0),
+            };
+            files.push(FileStats {
+                path,
+                additions,
+                deletions,
+            });
+        }
+    }
+    Ok(CommitStats {
+        name: author.name().unwrap_or_default().to_string(),
+        email: author.email().unwrap_or_default().to_lowercase(),
+        time: commit.time().seconds(),
+        merge,
+        files,
+    })
+}
+
+fn summarize(
+    head: String,
+    commits: &[CommitStats],
+    present: &HashSet<String>,
+    now: DateTime<Utc>,
+    truncated: bool,
+) -> RepoInsights {
+    let mut authors: HashMap<&str, AuthorStats> = HashMap::new();
+    let mut files: HashMap<&str, (FileChurn, HashSet<&str>, f64)> = HashMap::new();
+    let mut weeks: HashMap<NaiveDate, usize> = HashMap::new();
+    let (mut additions, mut deletions, mut merges) = (0, 0, 0);
+    let (mut first_commit, mut last_commit) = (None::<DateTime<Utc>>, None::<DateTime<Utc>>);
+
+    for commit in commits {
+        let Some(at) = timestamp(commit.time) else {
+            continue;
+        };
+        first_commit = Some(first_commit.map_or(at, |first| first.min(at)));
+        last_commit = Some(last_commit.map_or(at, |last| last.max(at)));
+        merges += usize::from(commit.merge);
+        *weeks.entry(week_of(at)).or_default() += 1;
+
+        let author = authors
+            .entry(commit.email.as_str())
+            .or_insert_with(|| AuthorStats {
+                name: commit.name.clone(),
+                email: commit.email.clone(),
+                commits: 0,
+                additions: 0,
+                deletions: 0,
+                first_commit: at,
+                last_commit: at,
+            });
+        author.commits += 1;
+        if at > author.last_commit {
+            author.name = commit.name.clone();
+            author.last_commit = at;
+        }
+        author.first_commit = author.first_commit.min(at);
+
+        let age_days = (now - at).num_seconds().max(0) as f64 / 86_400.0;
+        let weight = 0.5_f64.powf(age_days / HOTSPOT_HALF_LIFE_DAYS);
+        for file in &commit.files {
+            author.additions += file.additions;
+            author.deletions += file.deletions;
+            additions += file.additions;
+            deletions += file.deletions;
+            let (churn, file_authors, score) =
+                files.entry(file.path.as_str()).or_insert_with(|| {
+                    (
+                        FileChurn {
+                            path: file.path.clone(),
+                            commits: 0,
+                            additions: 0,
+                            deletions: 0,
+                            authors: 0,
+                        },
+                        HashSet::new(),
+                        0.0,
+                    )
+                });
+            churn.commits += 1;
+            churn.additions += file.additions;
+            churn.deletions += file.deletions;
+            file_authors.insert(commit.email.as_str());
+            *score += weight;
+        }
+    }
+
+    let mut authors: Vec<AuthorStats> = authors.into_values().collect();
+    authors.sort_by(|a, b| b.commits.cmp(&a.commits).then(a.email.cmp(&b.email)));
+
+    let files: Vec<(FileChurn, f64)> = files
+        .into_values()
+        .map(|(mut churn, file_authors, score)| {
+            churn.authors = file_authors.len();
+            (churn, score)
+        })
+        .collect();
+    let mut hotspots: Vec<Hotspot> = files
+        .iter()
+        .filter(|(churn, _)| present.contains(&churn.path))
+        .map(|(churn, score)| Hotspot {
+            path: churn.path.clone(),
+            commits: churn.commits,
+            authors: churn.authors,
+            score: (score * 100.0).round() / 100.0,
+        })
+        .collect();
+    hotspots.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.path.cmp(&b.path)));
+    hotspots.truncate(TOP_FILES);
+    let mut churn: Vec<FileChurn> = files.into_iter().map(|(churn, _)| churn).collect();
+    churn.sort_by(|a, b| {
+        (b.additions + b.deletions)
+            .cmp(&(a.additions + a.deletions))
+            .then(a.path.cmp(&b.path))
+    });
+    churn.truncate(TOP_FILES);
+
+    RepoInsights {
+        head,
+        commits: commits.len(),
+        merges,
+        additions,
+        deletions,
+        first_commit,
+        last_commit,
+        authors,
+        churn,
+        hotspots,
+        weekly: weekly(&weeks),
+        truncated,
+    }
+}
+
+/// Every week from the first to the last with commits
+fn weekly(weeks: &HashMap<NaiveDate, usize>) -> Vec<WeekActivity> {
+    let (Some(&first), Some(&last)) = (weeks.keys().min(), weeks.keys().max()) else {
+        return Vec::new();
+    };
+    let mut activity = Vec::new();
+    let mut week = first;
+    while week <= last {
+        activity.push(WeekActivity {
+            week,
+            commits: weeks.get(&week).copied().unwrap_or_default(),
+        });
+        week += Duration::weeks(1);
+    }
+    activity
+}
+
+fn week_of(at: DateTime<Utc>) -> NaiveDate {
+    let day = at.date_naive();
+    day - Duration::days(i64::from(day.weekday().num_days_from_monday()))
+}
+
+fn timestamp(seconds: i64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(seconds, 0).single()
+}
+
+fn tracked_files(tree: &git2::Tree) -> Result<HashSet<String>> {
+    let mut files = HashSet::new();
+    tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() == Some(git2::ObjectType::Blob) {
+            files.insert(format!("{}{}", dir, entry.name().unwrap_or_default()));
+        }
+        git2::TreeWalkResult::Ok
+    })?;
+    Ok(files)
+}
+
+/// The cache, or an empty one when it is missing or unreadable
+fn load_cache(path: &Path) -> Cache {
+    match std::fs::read(path) {
+        Ok(data) => serde_json::from_slice(&data).unwrap_or_else(|e| {
+            tracing::warn!("Discarding the insights cache: {}", e);
+            Cache::default()
+        }),
+        Err(_) => Cache::default(),
+    }
+}
+
+fn save_cache(path: &Path, mut cache: Cache) -> Result<()> {
+    if cache.commits.len() > MAX_CACHED_COMMITS {
+        let mut times: Vec<i64> = cache.commits.values().map(|stats| stats.time).collect();
+        times.sort_unstable_by(|a, b| b.cmp(a));
+        let oldest_kept = times[MAX_CACHED_COMMITS - 1];
+        cache.commits.retain(|_, stats| stats.time >= oldest_kept);
+    }
+    write_atomic(path, &serde_json::to_vec(&cache)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+    use std::fs;
+
+    fn commit(repo: &Repository, author: &str, files: &[(&str, &str)], days_ago: i64) -> Oid {
+        let root = repo.workdir().unwrap();
+        let mut index = repo.index().unwrap();
+        for (name, content) in files {
+            fs::write(root.join(name), content).unwrap();
+            index.add_path(Path::new(name)).unwrap();
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let time = git2::Time::new((Utc::now() - Duration::days(days_ago)).timestamp(), 0);
+        let signature = Signature::new(author, &format!("{}@example.com", author), &time).unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<_> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "change",
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_counts_authors_churn_and_hotspots_and_caches_commits() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit(&repo, "ada", &[("a.rs", "1\n2\n3\n"), ("b.rs", "1\n")], 90);
+        commit(&repo, "grace", &[("a.rs", "1\n2\n4\n")], 10);
+        commit(&repo, "ada", &[("a.rs", "1\n5\n")], 1);
+
+        let insights = repo_insights(dir.path(), &InsightsRange::default()).unwrap();
+        assert_eq!((insights.commits, insights.merges), (3, 0));
+        assert_eq!((insights.additions, insights.deletions), (6, 3));
+        let authors: Vec<_> = insights
+            .authors
+            .iter()
+            .map(|a| (a.name.as_str(), a.commits))
+            .collect();
+        assert_eq!(authors, [("ada", 2), ("grace", 1)]);
+        assert_eq!(insights.churn[0].path, "a.rs");
+        assert_eq!(
+            (insights.churn[0].commits, insights.churn[0].authors),
+            (3, 2)
+        );
+        assert_eq!(insights.hotspots[0].path, "a.rs");
+        assert!(insights.hotspots[0].score > insights.hotspots[1].score);
+        assert_eq!(insights.weekly.iter().map(|w| w.commits).sum::<usize>(), 3);
+        assert!(!insights.truncated);
+
+        let cache = load_cache(&dir.path().join(INSIGHTS_CACHE_FILE));
+        assert_eq!(cache.commits.len(), 3);
+
+        let recent = InsightsRange {
+            from: Some(Utc::now() - Duration::days(30)),
+            ..InsightsRange::default()
+        };
+        let insights = repo_insights(dir.path(), &recent).unwrap();
+        assert_eq!(insights.commits, 2);
+        assert_eq!(insights.authors.len(), 2);
+        assert!(insights.churn.iter().all(|file| file.path == "a.rs"));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::config::{GitSettings, LargeFileAction};
+use anyhow::{bail, Context, Result};
+use git2::{Delta, Index, Repository, Tree};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+/// First line of every Git LFS pointer file
+const LFS_POINTER_PREFIX: &[u8] = b"version https://git-lfs.github.com/spec/";
+
+/// Pointer files are well below this size
+const MAX_POINTER_BYTES: usize = 1024;
+
+/// A staged file the large-file guard caught
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LargeFile {
+    /// Relative to the repository root
+    pub path: String,
+    /// Bytes
+    pub size: u64,
+    /// The GitSettings::lfs_patterns entry it matches; None when it was
+    /// caught by size alone
+    pub pattern: Option<String>,
+}
+
+/// Apply GitSettings::large_file_action to the files staged in `index` that
+/// differ from `base`
+///
+/// Returns the files caught: with Warn they are committed as they are, with
+/// Track they have been moved to Git LFS and `index` re-read; Block fails.
+pub fn guard(
+    repo: &Repository,
+    root: &Path,
+    index: &mut Index,
+    base: Option<&Tree>,
+    git: &GitSettings,
+) -> Result<Vec<LargeFile>> {
+    let files = check(repo, index, base, git)?;
+    if files.is_empty() {
+        return Ok(files);
+    }
+    match git.large_file_action {
+        LargeFileAction::Warn => Ok(files),
+        LargeFileAction::Block => bail!(
+            "Commit blocked: {} belong in Git LFS; track them with `git lfs track` or raise the large file limit",
+            describe(&files)
+        ),
+        LargeFileAction::Track => {
+            track(root, &files)?;
+            index.read(true)?;
+            let remaining = check(repo, index, base, git)?;
+            if !remaining.is_empty() {
+                bail!(
+                    "{} are still not stored in Git LFS; is `git lfs install` done?",
+                    describe(&remaining)
+                );
+            }
+            Ok(files)
+        }
+    }
+}
+
+/// Staged files above GitSettings::large_file_limit_mb or matching
+/// GitSettings::lfs_patterns, other than LFS pointers
+pub fn check(
+    repo: &Repository,
+    index: &Index,
+    base: Option<&Tree>,
+    git: &GitSettings,
+) -> Result<Vec<LargeFile>> {
+    let patterns = git
+        .lfs_patterns
+        .iter()
+        .map(|pattern| {
+            GlobBuilder::new(pattern)
+                .build()
+                .map(|glob| (pattern, glob.compile_matcher()))
+                .with_context(|| format!("Invalid LFS pattern {}", pattern))
+        })
+        .collect::<Result<Vec<(&String, GlobMatcher)>>>()?;
+    let limit = git.large_file_limit_mb.saturating_mul(1024 * 1024);
+    let odb = repo.odb()?;
+
+    let diff = repo.diff_tree_to_index(base, Some(index), None)?;
+    let mut files = Vec::new();
+    for delta in diff.deltas() {
+        if !matches!(
+            delta.status(),
+            Delta::Added | Delta::Modified | Delta::Renamed | Delta::Copied | Delta::Typechange
+        ) {
+            continue;
+        }
+        let Some(path) = delta.new_file().path().map(|path| path.to_string_lossy()) else {
+            continue;
+        };
+        let id = delta.new_file().id();
+        let (size, _) = odb.read_header(id)?;
+        let pattern = patterns
+            .iter()
+            .find(|(_, glob)| glob.is_match(path.as_ref()))
+            .map(|(pattern, _)| pattern.to_string());
+        if pattern.is_none() && (limit == 0 || (size as u64) <= limit) {
+            continue;
+        }
+        if size <= MAX_POINTER_BYTES && is_lfs_pointer(odb.read(id)?.data()) {
+            continue;
+        }
+        files.push(LargeFile {
+            path: path.into_owned(),
+            size: size as u64,
+            pattern,
+        });
+    }
+    Ok(files)
+}
+
+fn is_lfs_pointer(content: &[u8]) -> bool {
+    content.starts_with(LFS_POINTER_PREFIX)
+}
+
+/// `git lfs track` the files (by their pattern, else by path) and stage them
+/// again so the LFS filter stores pointers
+fn track(root: &Path, files: &[LargeFile]) -> Result<()> {
+    let mut patterns: Vec<String> = files
+        .iter()
+        .map(|file| {
+            file.pattern
+                .clone()
+                .unwrap_or_else(|| format!("/{}", file.path))
+        })
+        .collect();
+    patterns.sort();
+    patterns.dedup();
+    run_git(root, &["lfs", "track", "--"], &patterns)
+        .context("Failed to run `git lfs track`; is Git LFS installed?")?;
+
+    let paths: Vec<String> = files.iter().map(|file| file.path.clone()).collect();
+    run_git(root, &["add", "--", ".gitattributes"], &paths)
+        .context("Failed to stage the files through Git LFS")
+}
+
+fn run_git(root: &Path, args: &[&str], more: &[String]) -> Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .args(more)
+        .current_dir(root)
+        .output()?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+fn describe(files: &[LargeFile]) -> String {
+    files
+        .iter()
+        .map(|file| format!("{} ({} MB)", file.path, file.size / (1024 * 1024)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn staged(dir: &Path, files: &[(&str, Vec<u8>)]) -> (Repository, Index) {
+        let repo = Repository::init(dir).unwrap();
+        let mut index = repo.index().unwrap();
+        for (name, content) in files {
+            fs::write(dir.join(name), content).unwrap();
+            index.add_path(Path::new(name)).unwrap();
+        }
+        (repo, index)
+    }
+
+    #[test]
+    fn test_catches_large_files_and_lfs_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        let pointer = b"version https://git-lfs.github.com/spec/v1\noid sha256:abc\nsize 9\n";
+        let (repo, index) = staged(
+            dir.path(),
+            &[
+                ("small.txt", b"hello".to_vec()),
+                ("big.bin", vec![0; 2 * 1024 * 1024 + 1]),
+                ("cover.psd", b"layers".to_vec()),
+                ("art.psd", pointer.to_vec()),
+            ],
+        );
+        let mut git = GitSettings {
+            large_file_limit_mb: 2,
+            lfs_patterns: vec!["*.psd".into()],
+            ..GitSettings::default()
+        };
+
+        let files = check(&repo, &index, None, &git).unwrap();
+        let caught: Vec<(&str, Option<&str>)> = files
+            .iter()
+            .map(|file| (file.path.as_str(), file.pattern.as_deref()))
+            .collect();
+        assert_eq!(caught, [("big.bin", None), ("cover.psd", Some("*.psd"))]);
+
+        git.large_file_limit_mb = 0;
+        git.large_file_action = LargeFileAction::Block;
+        let mut index = index;
+        let error = guard(&repo, dir.path(), &mut index, None, &git).unwrap_err();
+        assert!(error.to_string().contains("cover.psd"));
+        assert!(!error.to_string().contains("big.bin"));
+
+        git.lfs_patterns = vec!["[".into()];
+        assert!(check(&repo, &index, None, &git).is_err());
+    }
+}
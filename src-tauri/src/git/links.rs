@@ -0,0 +1,240 @@
+//! Links between GitHub issues and local branches
+//!
+//! Issue branches are named `<branch_prefix><number>-<slug>` (e.g. `issue-42-fix-login`).
+//! Links are recorded in the project state (`<repo>/.zeami/state.json`) so the
+//! issue title is available offline for status displays and commit message templates.
+
+use super::branches::checkout_reference;
+use super::{current_branch, open, workdir};
+use crate::config::GitSettings;
+use crate::state;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Longest slug appended to an issue branch name
+const MAX_SLUG_LEN: usize = 40;
+
+/// An issue worked on in a local branch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssueLink {
+    pub issue: u64,
+    pub title: String,
+    pub branch: String,
+    /// RFC 3339; empty when the link was inferred from the branch name only
+    pub linked_at: String,
+}
+
+/// Branch name for an issue: prefix, number and a slug of the title
+pub fn branch_name(prefix: &str, number: u64, title: &str) -> String {
+    let slug = slugify(title);
+    if slug.is_empty() {
+        format!("{}{}", prefix, number)
+    } else {
+        format!("{}{}-{}", prefix, number, slug)
+    }
+}
+
+/// Issue number encoded in a branch name such as `issue-42` or `issue-42-fix-login`
+pub fn issue_from_branch(branch: &str, prefix: &str) -> Option<u64> {
+    let rest = branch.strip_prefix(prefix)?;
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if rest.len() > digits.len() && !rest[digits.len()..].starts_with('-') {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// Start work on an issue in the repository containing `repo_path`
+///
+/// With `auto_create_branch` the issue branch is created from the default branch
+/// (or HEAD when it does not exist locally) and checked out; an existing issue
+/// branch is just checked out. Otherwise the current branch must already follow
+/// the naming convention for this issue.
+pub fn start_issue(
+    repo_path: &Path,
+    number: u64,
+    title: &str,
+    git: &GitSettings,
+) -> Result<IssueLink> {
+    let repo = open(repo_path)?;
+    let root = workdir(&repo)?;
+
+    let branch = if git.auto_create_branch {
+        let branch = existing_issue_branch(&repo, number, &git.branch_prefix)?
+            .unwrap_or_else(|| branch_name(&git.branch_prefix, number, title));
+        checkout_branch(&repo, &branch, &git.default_branch)?;
+        branch
+    } else {
+        let branch = current_branch(&repo)?;
+        if issue_from_branch(&branch, &git.branch_prefix) != Some(number) {
+            bail!(
+                "Branch {} does not follow the naming convention for #{}; expected {}",
+                branch,
+                number,
+                branch_name(&git.branch_prefix, number, title)
+            );
+        }
+        branch
+    };
+
+    let link = IssueLink {
+        issue: number,
+        title: title.to_string(),
+        branch,
+        linked_at: chrono::Utc::now().to_rfc3339(),
+    };
+    record_link(&root, link.clone())?;
+    tracing::info!(issue = number, branch = %link.branch, "Started work on issue");
+    Ok(link)
+}
+
+/// Issue linked to the checked-out branch
+///
+/// Falls back to the number in the branch name when no link was recorded.
+pub fn linked_issue(repo_path: &Path, git: &GitSettings) -> Result<Option<IssueLink>> {
+    let repo = open(repo_path)?;
+    let branch = match current_branch(&repo) {
+        Ok(branch) => branch,
+        Err(_) => return Ok(None),
+    };
+
+    let state = state::load(&workdir(&repo)?)?;
+    if let Some(link) = state.links.into_iter().find(|link| link.branch == branch) {
+        return Ok(Some(link));
+    }
+
+    Ok(
+        issue_from_branch(&branch, &git.branch_prefix).map(|issue| IssueLink {
+            issue,
+            title: String::new(),
+            branch,
+            linked_at: String::new(),
+        }),
+    )
+}
+
+/// Lowercase ASCII words joined by `-`, cut at a word boundary
+fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for word in title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        let extra = word.len() + usize::from(!slug.is_empty());
+        if slug.len() + extra > MAX_SLUG_LEN {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_ascii_lowercase());
+    }
+    slug
+}
+
+/// A local branch already following the convention for `number`
+fn existing_issue_branch(
+    repo: &git2::Repository,
+    number: u64,
+    prefix: &str,
+) -> Result<Option<String>> {
+    for entry in repo.branches(Some(git2::BranchType::Local))? {
+        let (branch, _) = entry?;
+        if let Some(name) = branch.name()? {
+            if issue_from_branch(name, prefix) == Some(number) {
+                return Ok(Some(name.to_string()));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn checkout_branch(repo: &git2::Repository, name: &str, default_branch: &str) -> Result<()> {
+    let branch = match repo.find_branch(name, git2::BranchType::Local) {
+        Ok(branch) => branch,
+        Err(_) => {
+            let start = match repo.find_branch(default_branch, git2::BranchType::Local) {
+                Ok(base) => base.get().peel_to_commit()?,
+                Err(_) => repo
+                    .head()
+                    .and_then(|head| head.peel_to_commit())
+                    .context("Repository has no commits to branch from")?,
+            };
+            repo.branch(name, &start, false)
+                .with_context(|| format!("Failed to create branch {}", name))?
+        }
+    };
+
+    let name = branch
+        .name()?
+        .context("Branch name is not valid UTF-8")?
+        .to_string();
+    checkout_reference(repo, &name, false)
+}
+
+fn record_link(repo_root: &Path, link: IssueLink) -> Result<()> {
+    state::update(repo_root, |state| {
+        state
+            .links
+            .retain(|existing| existing.branch != link.branch && existing.issue != link.issue);
+        state.links.push(link);
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_all, init_repo};
+
+    #[test]
+    fn test_branch_name() {
+        assert_eq!(
+            branch_name("issue-", 42, "Fix login: crash on Ünicode names!"),
+            "issue-42-fix-login-crash-on-nicode-names"
+        );
+        assert_eq!(branch_name("issue-", 7, "???"), "issue-7");
+        assert!(slugify(&"word ".repeat(20)).len() <= MAX_SLUG_LEN);
+    }
+
+    #[test]
+    fn test_issue_from_branch() {
+        assert_eq!(issue_from_branch("issue-42", "issue-"), Some(42));
+        assert_eq!(issue_from_branch("issue-42-fix-login", "issue-"), Some(42));
+        assert_eq!(issue_from_branch("issue-42fix", "issue-"), None);
+        assert_eq!(issue_from_branch("feature/login", "issue-"), None);
+    }
+
+    #[test]
+    fn test_start_issue_creates_and_links_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        commit_all(&repo, "initial");
+        let git = GitSettings::default();
+
+        let link = start_issue(dir.path(), 12, "Add dark mode", &git).unwrap();
+        assert_eq!(link.branch, "issue-12-add-dark-mode");
+        assert_eq!(current_branch(&repo).unwrap(), link.branch);
+        assert_eq!(linked_issue(dir.path(), &git).unwrap(), Some(link.clone()));
+
+        // Starting again reuses the branch even if the title changed
+        let again = start_issue(dir.path(), 12, "Dark mode", &git).unwrap();
+        assert_eq!(again.branch, link.branch);
+        assert_eq!(state::load(dir.path()).unwrap().links.len(), 1);
+    }
+
+    #[test]
+    fn test_start_issue_enforces_convention_without_auto_create() {
+        let dir = tempfile::tempdir().unwrap();
+        commit_all(&init_repo(dir.path()), "initial");
+        let git = GitSettings {
+            auto_create_branch: false,
+            ..GitSettings::default()
+        };
+
+        let error = start_issue(dir.path(), 3, "Typo", &git).unwrap_err();
+        assert!(error.to_string().contains("issue-3-typo"));
+    }
+}
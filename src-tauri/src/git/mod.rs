@@ -0,0 +1,56 @@
+pub mod blame;
+pub mod branches;
+pub mod commit;
+pub mod commitlint;
+pub mod conflicts;
+pub mod credentials;
+pub mod diff;
+pub mod fetcher;
+pub mod insights;
+pub mod large_files;
+pub mod links;
+pub mod remote;
+pub mod signatures;
+pub mod snapshot;
+pub mod status;
+pub mod submodules;
+pub mod sync;
+#[cfg(test)]
+pub(crate) mod test_support;
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Open the repository containing `path`
+pub fn open(path: &Path) -> Result<git2::Repository> {
+    git2::Repository::discover(path).with_context(|| format!("{:?} is not a git repository", path))
+}
+
+/// Working directory of a non-bare repository
+pub fn workdir(repo: &git2::Repository) -> Result<PathBuf> {
+    repo.workdir()
+        .map(Path::to_path_buf)
+        .context("Bare repositories are not supported")
+}
+
+/// Name of the checked-out branch
+pub fn current_branch(repo: &git2::Repository) -> Result<String> {
+    let head = repo
+        .head()
+        .context("Repository has no checked-out branch")?;
+    if !head.is_branch() {
+        bail!("HEAD is detached; check out a branch first");
+    }
+    head.shorthand()
+        .map(str::to_string)
+        .context("Branch name is not valid UTF-8")
+}
+
+/// Commit id HEAD points at
+pub fn head_sha(repo: &git2::Repository) -> Result<String> {
+    let commit = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .context("Repository has no commits")?;
+    Ok(commit.id().to_string())
+}
@@ -0,0 +1,214 @@
+use super::credentials::Credentials;
+use super::status::tracking;
+use super::{current_branch, open};
+use crate::config::{CredentialSource, GitSettings};
+use anyhow::{Context, Result};
+use git2::{Direction, FetchOptions, PushOptions};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Tracking state of the checked-out branch after a fetch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteUpdate {
+    pub remote: String,
+    /// None on a detached HEAD
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    /// RFC 3339
+    pub fetched_at: String,
+}
+
+/// Fetch GitSettings::remote and report ahead/behind of the checked-out branch
+///
+/// Credentials come from the chain of git::credentials.
+pub fn fetch(
+    repo_path: &Path,
+    git: &GitSettings,
+    credentials: &Credentials,
+) -> Result<RemoteUpdate> {
+    let repo = open(repo_path)?;
+    let mut remote = repo
+        .find_remote(&git.remote)
+        .with_context(|| format!("No remote named {}", git.remote))?;
+    let config = repo.config()?;
+
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(credentials.callbacks(&config));
+    remote
+        .fetch::<&str>(&[], Some(&mut options), None)
+        .with_context(|| format!("Failed to fetch {}", git.remote))?;
+    drop(remote);
+
+    let branch = current_branch(&repo).ok();
+    let (upstream, ahead, behind) = match &branch {
+        Some(branch) => tracking(&repo, branch)?,
+        None => (None, 0, 0),
+    };
+    Ok(RemoteUpdate {
+        remote: git.remote.clone(),
+        branch,
+        upstream,
+        ahead,
+        behind,
+        fetched_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Push the checked-out branch to GitSettings::remote and track it there
+///
+/// Authenticates like fetch. Returns the pushed branch name.
+pub fn push(repo_path: &Path, git: &GitSettings, credentials: &Credentials) -> Result<String> {
+    let repo = open(repo_path)?;
+    let branch = current_branch(&repo)?;
+    let mut remote = repo
+        .find_remote(&git.remote)
+        .with_context(|| format!("No remote named {}", git.remote))?;
+    let config = repo.config()?;
+
+    let mut rejection: Option<String> = None;
+    let mut callbacks = credentials.callbacks(&config);
+    callbacks.push_update_reference(|_, status| {
+        rejection = status.map(str::to_string);
+        Ok(())
+    });
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+    let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch);
+    remote
+        .push(&[refspec.as_str()], Some(&mut options))
+        .with_context(|| format!("Failed to push {} to {}", branch, git.remote))?;
+    drop(options);
+    drop(remote);
+    if let Some(reason) = rejection {
+        anyhow::bail!("{} rejected the push of {}: {}", git.remote, branch, reason);
+    }
+
+    repo.find_branch(&branch, git2::BranchType::Local)?
+        .set_upstream(Some(&format!("{}/{}", git.remote, branch)))
+        .with_context(|| format!("Failed to track {}/{}", git.remote, branch))?;
+    Ok(branch)
+}
+
+/// Whether the credentials get push access to a remote, from test_remote_access
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteAccess {
+    pub remote: String,
+    /// The push URL
+    pub url: String,
+    pub can_push: bool,
+    /// The source of the credentials last offered; None when the remote asked for none
+    pub credential: Option<CredentialSource>,
+    /// Why the remote refused
+    pub error: Option<String>,
+}
+
+/// Connect to `remote_name` for a push, without pushing anything
+///
+/// Hosts such as GitHub refuse the connection when the credentials can only
+/// read, so a refusal here means workflows can't push either.
+pub fn test_access(
+    repo_path: &Path,
+    remote_name: &str,
+    credentials: &Credentials,
+) -> Result<RemoteAccess> {
+    let repo = open(repo_path)?;
+    let mut remote = repo
+        .find_remote(remote_name)
+        .with_context(|| format!("No remote named {}", remote_name))?;
+    let url = remote
+        .pushurl()
+        .or(remote.url())
+        .unwrap_or_default()
+        .to_string();
+    let config = repo.config()?;
+
+    let error =
+        match remote.connect_auth(Direction::Push, Some(credentials.callbacks(&config)), None) {
+            Ok(connection) => {
+                drop(connection);
+                None
+            }
+            Err(e) => Some(e.message().to_string()),
+        };
+    Ok(RemoteAccess {
+        remote: remote_name.to_string(),
+        url,
+        can_push: error.is_none(),
+        credential: credentials.used(),
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_file, init_repo};
+    use git2::Repository;
+
+    #[test]
+    fn test_fetch_reports_behind() {
+        let upstream_dir = tempfile::tempdir().unwrap();
+        let upstream = init_repo(upstream_dir.path());
+        commit_file(&upstream, "a.txt", "a.txt", "a.txt");
+
+        let local_dir = tempfile::tempdir().unwrap();
+        let url = upstream_dir.path().to_str().unwrap();
+        let local = Repository::clone(url, local_dir.path()).unwrap();
+        assert_eq!(local.head().unwrap().shorthand(), Some("main"));
+
+        commit_file(&upstream, "b.txt", "b.txt", "b.txt");
+        let update = fetch(
+            local_dir.path(),
+            &GitSettings::default(),
+            &Credentials::default(),
+        )
+        .unwrap();
+        assert_eq!(update.branch.as_deref(), Some("main"));
+        assert_eq!(update.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((update.ahead, update.behind), (0, 1));
+
+        let missing = GitSettings {
+            remote: "nowhere".to_string(),
+            ..GitSettings::default()
+        };
+        assert!(fetch(local_dir.path(), &missing, &Credentials::default()).is_err());
+    }
+
+    #[test]
+    fn test_push_tracks_branch() {
+        let upstream_dir = tempfile::tempdir().unwrap();
+        let upstream = Repository::init_bare(upstream_dir.path()).unwrap();
+
+        let local_dir = tempfile::tempdir().unwrap();
+        let local = init_repo(local_dir.path());
+        commit_file(&local, "a.txt", "a.txt", "a.txt");
+        local
+            .remote("origin", upstream_dir.path().to_str().unwrap())
+            .unwrap();
+        let branch = local.head().unwrap().shorthand().unwrap().to_string();
+
+        assert_eq!(
+            push(
+                local_dir.path(),
+                &GitSettings::default(),
+                &Credentials::default()
+            )
+            .unwrap(),
+            branch
+        );
+        let pushed = upstream
+            .find_branch(&branch, git2::BranchType::Local)
+            .unwrap();
+        assert_eq!(pushed.get().target(), local.head().unwrap().target());
+        let (upstream_name, ahead, _) = tracking(&local, &branch).unwrap();
+        assert_eq!(upstream_name, Some(format!("origin/{}", branch)));
+        assert_eq!(ahead, 0);
+
+        let access = test_access(local_dir.path(), "origin", &Credentials::default()).unwrap();
+        assert!(access.can_push && access.error.is_none());
+        assert_eq!(access.credential, None);
+        assert!(test_access(local_dir.path(), "nowhere", &Credentials::default()).is_err());
+    }
+}
@@ -0,0 +1,456 @@
+//! Verification of commit signatures
+//!
+//! Signatures are checked the way `git log --show-signature` does: OpenPGP
+//! with `gpg.program`, X.509 with `gpg.x509.program` and SSH with
+//! `gpg.ssh.program` against `gpg.ssh.allowedSignersFile`. The statuses
+//! follow git's `%G?` so the UI can badge commits the way git reports them.
+
+use super::open;
+use super::sync::base_commit;
+use crate::config::GitSettings;
+use anyhow::{Context, Result};
+use git2::{Commit, ErrorCode, Repository};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use tempfile::NamedTempFile;
+
+/// Most commits verified per call; each one runs the verifier
+const MAX_COMMITS: usize = 100;
+
+/// Namespace git signs commits in with SSH keys
+const SSH_NAMESPACE: &str = "git";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureKind {
+    Gpg,
+    X509,
+    Ssh,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    Unsigned,
+    /// Valid signature from a trusted key
+    Good,
+    /// Valid signature from a key of unknown or no trust
+    UnknownValidity,
+    Bad,
+    ExpiredSignature,
+    ExpiredKey,
+    RevokedKey,
+    /// The key is missing or the verifier could not run
+    CannotCheck,
+}
+
+impl SignatureStatus {
+    /// The signature is intact, whatever the trust in its key
+    pub fn is_valid(self) -> bool {
+        matches!(self, Self::Good | Self::UnknownValidity)
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Unsigned => "unsigned",
+            Self::Good => "good signature",
+            Self::UnknownValidity => "signature from an untrusted key",
+            Self::Bad => "bad signature",
+            Self::ExpiredSignature => "expired signature",
+            Self::ExpiredKey => "expired key",
+            Self::RevokedKey => "revoked key",
+            Self::CannotCheck => "signature could not be checked",
+        }
+    }
+}
+
+/// Owner trust in the signing key, as gpg reports it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustLevel {
+    Undefined,
+    Never,
+    Marginal,
+    Fully,
+    Ultimate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitSignature {
+    pub id: String,
+    pub summary: String,
+    /// None for unsigned commits
+    pub kind: Option<SignatureKind>,
+    pub status: SignatureStatus,
+    /// User ID of the GPG key, or the SSH principal from the allowed signers
+    pub signer: Option<String>,
+    /// Key fingerprint, or the key id when the key is missing
+    pub key: Option<String>,
+    /// For SSH, keys listed in the allowed signers count as fully trusted
+    pub trust: Option<TrustLevel>,
+}
+
+/// What a verifier reported about one signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Verification {
+    status: SignatureStatus,
+    signer: Option<String>,
+    key: Option<String>,
+    trust: Option<TrustLevel>,
+}
+
+impl Verification {
+    fn cannot_check() -> Self {
+        Self {
+            status: SignatureStatus::CannotCheck,
+            signer: None,
+            key: None,
+            trust: None,
+        }
+    }
+}
+
+/// Signatures of the commits in `range` (`base..head`, or a single revision
+/// and its history), newest first and at most MAX_COMMITS; HEAD when None
+pub fn signature_status(repo_path: &Path, range: Option<&str>) -> Result<Vec<CommitSignature>> {
+    let repo = open(repo_path)?;
+    let mut walk = repo.revwalk()?;
+    match range.filter(|range| !range.trim().is_empty()) {
+        Some(range) if range.contains("..") => walk
+            .push_range(range)
+            .with_context(|| format!("Invalid revision range {}", range))?,
+        Some(rev) => walk.push(
+            repo.revparse_single(rev)
+                .with_context(|| format!("Unknown revision {}", rev))?
+                .peel_to_commit()?
+                .id(),
+        )?,
+        None => walk.push_head()?,
+    }
+
+    walk.take(MAX_COMMITS)
+        .map(|id| verify_commit(&repo, &repo.find_commit(id?)?))
+        .collect()
+}
+
+/// Signatures of the commits on the checked-out branch that are not on the
+/// base branch (see sync::base_commit)
+pub fn branch_signatures(repo_path: &Path, git: &GitSettings) -> Result<Vec<CommitSignature>> {
+    let repo = open(repo_path)?;
+    let (_, base) = base_commit(&repo, git)?;
+    signature_status(repo_path, Some(&format!("{}..HEAD", base.id())))
+}
+
+fn verify_commit(repo: &Repository, commit: &Commit) -> Result<CommitSignature> {
+    let (kind, verification) = match repo.extract_signature(&commit.id(), None) {
+        Ok((signature, data)) => {
+            let kind = signature_kind(&signature);
+            let verification = verify(repo, kind, &signature, &data).unwrap_or_else(|e| {
+                tracing::warn!(commit = %commit.id(), "Failed to verify signature: {:#}", e);
+                Verification::cannot_check()
+            });
+            (Some(kind), verification)
+        }
+        Err(e) if e.code() == ErrorCode::NotFound => (
+            None,
+            Verification {
+                status: SignatureStatus::Unsigned,
+                ..Verification::cannot_check()
+            },
+        ),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(CommitSignature {
+        id: commit.id().to_string(),
+        summary: commit.summary().unwrap_or_default().to_string(),
+        kind,
+        status: verification.status,
+        signer: verification.signer,
+        key: verification.key,
+        trust: verification.trust,
+    })
+}
+
+fn signature_kind(signature: &[u8]) -> SignatureKind {
+    if signature.starts_with(b"-----BEGIN SSH SIGNATURE-----") {
+        SignatureKind::Ssh
+    } else if signature.starts_with(b"-----BEGIN SIGNED MESSAGE-----") {
+        SignatureKind::X509
+    } else {
+        SignatureKind::Gpg
+    }
+}
+
+fn verify(
+    repo: &Repository,
+    kind: SignatureKind,
+    signature: &[u8],
+    data: &[u8],
+) -> Result<Verification> {
+    let config = repo.config()?;
+    let program = |key: &str, default: &str| {
+        config
+            .get_string(key)
+            .unwrap_or_else(|_| default.to_string())
+    };
+    let mut signature_file = NamedTempFile::new()?;
+    signature_file.write_all(signature)?;
+    let signature_path = signature_file.path();
+
+    match kind {
+        SignatureKind::Gpg | SignatureKind::X509 => {
+            let program = match kind {
+                SignatureKind::X509 => program("gpg.x509.program", "gpgsm"),
+                _ => program("gpg.program", "gpg"),
+            };
+            let mut command = Command::new(&program);
+            command
+                .args(["--status-fd=1", "--keyid-format=long", "--verify"])
+                .arg(signature_path)
+                .arg("-");
+            let output = run(&mut command, data)?;
+            Ok(parse_gpg_status(&String::from_utf8_lossy(&output.stdout)))
+        }
+        SignatureKind::Ssh => {
+            let program = program("gpg.ssh.program", "ssh-keygen");
+            let allowed = config
+                .get_path("gpg.ssh.allowedSignersFile")
+                .ok()
+                .map(expand_home);
+            verify_ssh(&program, allowed.as_deref(), signature_path, data)
+        }
+    }
+}
+
+/// Verify against the allowed signers when the key is listed there, else
+/// only check that the signature matches the key embedded in it
+fn verify_ssh(
+    program: &str,
+    allowed: Option<&Path>,
+    signature: &Path,
+    data: &[u8],
+) -> Result<Verification> {
+    let principal = match allowed {
+        Some(allowed) => {
+            let output = run(
+                Command::new(program)
+                    .args(["-Y", "find-principals", "-f"])
+                    .arg(allowed)
+                    .arg("-s")
+                    .arg(signature),
+                &[],
+            )?;
+            output
+                .status
+                .success()
+                .then(|| {
+                    String::from_utf8_lossy(&output.stdout)
+                        .lines()
+                        .next()
+                        .map(str::to_string)
+                })
+                .flatten()
+        }
+        None => None,
+    };
+
+    let mut command = Command::new(program);
+    match (allowed, &principal) {
+        (Some(allowed), Some(principal)) => command
+            .args(["-Y", "verify", "-f"])
+            .arg(allowed)
+            .args(["-I", principal]),
+        _ => command.args(["-Y", "check-novalidate"]),
+    };
+    command.args(["-n", SSH_NAMESPACE, "-s"]).arg(signature);
+    let output = run(&mut command, data)?;
+
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let status = match (output.status.success(), &principal) {
+        (false, _) => SignatureStatus::Bad,
+        (true, Some(_)) => SignatureStatus::Good,
+        (true, None) => SignatureStatus::UnknownValidity,
+    };
+    Ok(Verification {
+        status,
+        key: ssh_key_fingerprint(&text),
+        trust: Some(if principal.is_some() {
+            TrustLevel::Fully
+        } else {
+            TrustLevel::Undefined
+        }),
+        signer: principal,
+    })
+}
+
+/// Run `command` with `input` on stdin; a non-zero exit is left to the caller
+fn run(command: &mut Command, input: &[u8]) -> Result<Output> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+    child
+        .stdin
+        .take()
+        .context("Failed to open verifier stdin")?
+        .write_all(input)?;
+    Ok(child.wait_with_output()?)
+}
+
+/// Verification from the `--status-fd` lines of gpg or gpgsm
+fn parse_gpg_status(status: &str) -> Verification {
+    let mut verification = Verification::cannot_check();
+    for line in status.lines() {
+        let Some(line) = line.strip_prefix("[GNUPG:] ") else {
+            continue;
+        };
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let (key, user) = match rest.split_once(' ') {
+            Some((key, user)) => (key, Some(user.to_string())),
+            None => (rest, None),
+        };
+        let status = match keyword {
+            "GOODSIG" => SignatureStatus::Good,
+            "BADSIG" => SignatureStatus::Bad,
+            "EXPSIG" => SignatureStatus::ExpiredSignature,
+            "EXPKEYSIG" => SignatureStatus::ExpiredKey,
+            "REVKEYSIG" => SignatureStatus::RevokedKey,
+            "ERRSIG" => {
+                verification.key.get_or_insert_with(|| key.to_string());
+                continue;
+            }
+            "VALIDSIG" => {
+                verification.key = Some(key.to_string());
+                continue;
+            }
+            _ => {
+                if let Some(trust) = keyword.strip_prefix("TRUST_").and_then(trust_level) {
+                    verification.trust = Some(trust);
+                }
+                continue;
+            }
+        };
+        verification.status = status;
+        verification.signer = user;
+        if verification.key.is_none() {
+            verification.key = Some(key.to_string());
+        }
+    }
+    // Like git, a good signature from an untrusted key is of unknown validity
+    if verification.status == SignatureStatus::Good
+        && verification.trust.unwrap_or(TrustLevel::Undefined) < TrustLevel::Marginal
+    {
+        verification.status = SignatureStatus::UnknownValidity;
+    }
+    verification
+}
+
+fn trust_level(keyword: &str) -> Option<TrustLevel> {
+    Some(match keyword {
+        "UNDEFINED" => TrustLevel::Undefined,
+        "NEVER" => TrustLevel::Never,
+        "MARGINAL" => TrustLevel::Marginal,
+        "FULLY" => TrustLevel::Fully,
+        "ULTIMATE" => TrustLevel::Ultimate,
+        _ => return None,
+    })
+}
+
+/// `SHA256:...` from ssh-keygen's `Good "git" signature ... with ED25519 key SHA256:...`
+fn ssh_key_fingerprint(output: &str) -> Option<String> {
+    let (_, rest) = output.split_once(" key ")?;
+    rest.split_whitespace().next().map(str::to_string)
+}
+
+fn expand_home(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_all, init_repo};
+
+    #[test]
+    fn test_reads_verifier_output() {
+        let good = "\
+[GNUPG:] NEWSIG
+[GNUPG:] KEY_CONSIDERED 4F1C9A0E2B7D8C3A1F0E6D5B4A3C2B1A0F9E8D7C 0
+[GNUPG:] GOODSIG 4A3C2B1A0F9E8D7C Ada Lovelace <ada@example.com>
+[GNUPG:] VALIDSIG 4F1C9A0E2B7D8C3A1F0E6D5B4A3C2B1A0F9E8D7C 2024-05-01 1714521600 0 4 0 22 10 00 4F1C9A0E2B7D8C3A1F0E6D5B4A3C2B1A0F9E8D7C
+[GNUPG:] TRUST_ULTIMATE 0 pgp
+";
+        assert_eq!(
+            parse_gpg_status(good),
+            Verification {
+                status: SignatureStatus::Good,
+                signer: Some("Ada Lovelace <ada@example.com>".to_string()),
+                key: Some("4F1C9A0E2B7D8C3A1F0E6D5B4A3C2B1A0F9E8D7C".to_string()),
+                trust: Some(TrustLevel::Ultimate),
+            }
+        );
+
+        let untrusted = good.replace("TRUST_ULTIMATE", "TRUST_UNDEFINED");
+        assert_eq!(
+            parse_gpg_status(&untrusted).status,
+            SignatureStatus::UnknownValidity
+        );
+
+        let missing = "[GNUPG:] ERRSIG 4A3C2B1A0F9E8D7C 1 10 00 1714521600 9 -\n[GNUPG:] NO_PUBKEY 4A3C2B1A0F9E8D7C\n";
+        let verification = parse_gpg_status(missing);
+        assert_eq!(verification.status, SignatureStatus::CannotCheck);
+        assert_eq!(verification.key.as_deref(), Some("4A3C2B1A0F9E8D7C"));
+
+        let bad = "[GNUPG:] BADSIG 4A3C2B1A0F9E8D7C Ada Lovelace <ada@example.com>\n";
+        assert_eq!(parse_gpg_status(bad).status, SignatureStatus::Bad);
+
+        assert_eq!(
+            ssh_key_fingerprint(
+                "Good \"git\" signature for ada@example.com with ED25519 key SHA256:abc123\n"
+            )
+            .as_deref(),
+            Some("SHA256:abc123")
+        );
+        assert_eq!(
+            signature_kind(b"-----BEGIN SSH SIGNATURE-----\n"),
+            SignatureKind::Ssh
+        );
+        assert_eq!(
+            signature_kind(b"-----BEGIN SIGNED MESSAGE-----\n"),
+            SignatureKind::X509
+        );
+    }
+
+    #[test]
+    fn test_reports_unsigned_commits_in_a_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        let first = commit_all(&repo, "First");
+        commit_all(&repo, "Second");
+
+        let all = signature_status(dir.path(), None).unwrap();
+        let summaries: Vec<_> = all.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries, ["Second", "First"]);
+        assert!(all
+            .iter()
+            .all(|c| c.status == SignatureStatus::Unsigned && c.kind.is_none()));
+
+        let range = format!("{}..HEAD", first);
+        let since = signature_status(dir.path(), Some(&range)).unwrap();
+        assert_eq!(since.len(), 1);
+        assert_eq!(since[0].summary, "Second");
+        assert!(signature_status(dir.path(), Some("nowhere")).is_err());
+    }
+}
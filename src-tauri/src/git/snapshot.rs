@@ -0,0 +1,265 @@
+use super::open;
+use crate::config::{AutoCommitTarget, WorkflowSettings};
+use anyhow::{bail, Context, Result};
+use git2::{Commit, IndexAddOption, Oid, Repository, Signature};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Trailer marking WIP commits, followed by the branch they were taken on
+const SNAPSHOT_TRAILER: &str = "Zeami-Snapshot:";
+
+/// Commits walked when looking for snapshots
+const WALK_LIMIT: usize = 500;
+
+/// A WIP commit made by the auto-commit task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoCommit {
+    pub id: String,
+    /// Branch the snapshot was taken on; `HEAD` when detached
+    pub branch: String,
+    pub message: String,
+    /// RFC 3339
+    pub created_at: String,
+    /// Whether it lives on the shadow ref rather than the branch
+    pub shadow: bool,
+}
+
+/// Result of restore_auto_commit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreResult {
+    pub restored: String,
+    /// Snapshot of the working tree taken before restoring, if it had changes
+    pub backup: Option<AutoCommit>,
+}
+
+/// Shadow ref holding the WIP snapshots of `branch`
+pub fn shadow_ref(branch: &str) -> String {
+    format!("refs/zeami/wip/{}", branch)
+}
+
+/// Take a WIP snapshot of the working tree, including untracked files
+///
+/// Returns None when nothing changed since HEAD or the previous snapshot.
+pub fn auto_commit(repo_path: &Path, workflow: &WorkflowSettings) -> Result<Option<AutoCommit>> {
+    let repo = open(repo_path)?;
+    snapshot(
+        &repo,
+        &workflow.auto_commit_message,
+        workflow.auto_commit_target,
+    )
+}
+
+/// Snapshots of the checked-out branch, newest first
+pub fn list_auto_commits(repo_path: &Path) -> Result<Vec<AutoCommit>> {
+    let repo = open(repo_path)?;
+    let branch = branch_name(&repo);
+
+    let mut commits = Vec::new();
+    if let Ok(reference) = repo.find_reference(&shadow_ref(&branch)) {
+        collect(reference.peel_to_commit()?, true, &mut commits);
+    }
+    if let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) {
+        collect(head, false, &mut commits);
+    }
+    commits.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(commits)
+}
+
+/// Write the files of snapshot `id` into the working tree
+///
+/// HEAD and the index are left alone. Current changes are snapshotted on the
+/// shadow ref first so the restore can itself be undone.
+pub fn restore_auto_commit(repo_path: &Path, id: &str, message: &str) -> Result<RestoreResult> {
+    let repo = open(repo_path)?;
+    let oid = Oid::from_str(id).with_context(|| format!("Invalid commit id {}", id))?;
+    let commit = repo
+        .find_commit(oid)
+        .with_context(|| format!("Unknown commit {}", id))?;
+    if snapshot_branch(&commit).is_none() {
+        bail!("{} is not an auto-commit", id);
+    }
+
+    let backup = snapshot(&repo, message, AutoCommitTarget::ShadowRef)?;
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout.force().update_index(false);
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout))
+        .with_context(|| format!("Failed to restore {}", id))?;
+
+    Ok(RestoreResult {
+        restored: id.to_string(),
+        backup,
+    })
+}
+
+fn snapshot(
+    repo: &Repository,
+    message: &str,
+    target: AutoCommitTarget,
+) -> Result<Option<AutoCommit>> {
+    let branch = branch_name(repo);
+    let head = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+
+    // The repository's in-memory index; only written back when committing to the branch
+    let mut index = repo.index()?;
+    index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+    index.update_all(["*"], None)?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    if head.as_ref().map(|head| head.tree_id()) == Some(tree.id()) {
+        return Ok(None);
+    }
+
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("Zeami", "zeami@localhost"))?;
+    let message = format!("{}\n\n{} {}\n", message.trim(), SNAPSHOT_TRAILER, branch);
+
+    let id = match target {
+        AutoCommitTarget::Branch => {
+            index.write()?;
+            let parents: Vec<&Commit> = head.iter().collect();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                &message,
+                &tree,
+                &parents,
+            )?
+        }
+        AutoCommitTarget::ShadowRef => {
+            let name = shadow_ref(&branch);
+            let previous = repo
+                .find_reference(&name)
+                .and_then(|reference| reference.peel_to_commit())
+                .ok();
+            if previous.as_ref().map(|previous| previous.tree_id()) == Some(tree.id()) {
+                return Ok(None);
+            }
+
+            // First parent chains the snapshots, the second records where HEAD was
+            let mut parents: Vec<&Commit> = previous.iter().collect();
+            if let Some(head) = &head {
+                if !parents.iter().any(|parent| parent.id() == head.id()) {
+                    parents.push(head);
+                }
+            }
+            let id = repo.commit(None, &signature, &signature, &message, &tree, &parents)?;
+            repo.reference(&name, id, true, "auto-commit")?;
+            id
+        }
+    };
+
+    let commit = repo.find_commit(id)?;
+    Ok(Some(auto_commit_info(
+        &commit,
+        branch,
+        target == AutoCommitTarget::ShadowRef,
+    )))
+}
+
+/// Snapshots reachable through first parents of `start`
+fn collect(start: Commit<'_>, shadow: bool, commits: &mut Vec<AutoCommit>) {
+    let mut current = Some(start);
+    for _ in 0..WALK_LIMIT {
+        let Some(commit) = current else {
+            break;
+        };
+        if let Some(branch) = snapshot_branch(&commit) {
+            commits.push(auto_commit_info(&commit, branch, shadow));
+        } else if shadow {
+            // Past the oldest snapshot into regular history
+            break;
+        }
+        current = commit.parent(0).ok();
+    }
+}
+
+fn snapshot_branch(commit: &Commit<'_>) -> Option<String> {
+    commit
+        .message()?
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(SNAPSHOT_TRAILER))
+        .map(|branch| branch.trim().to_string())
+}
+
+fn auto_commit_info(commit: &Commit<'_>, branch: String, shadow: bool) -> AutoCommit {
+    AutoCommit {
+        id: commit.id().to_string(),
+        branch,
+        message: commit.summary().unwrap_or_default().to_string(),
+        created_at: chrono::DateTime::from_timestamp(commit.time().seconds(), 0)
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_default(),
+        shadow,
+    }
+}
+
+/// Checked-out branch, or `HEAD` when detached or unborn
+fn branch_name(repo: &Repository) -> String {
+    super::current_branch(repo).unwrap_or_else(|_| "HEAD".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_file, init_repo};
+    use std::fs;
+
+    #[test]
+    fn test_shadow_snapshots_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "a.txt", "one", "Init");
+        let workflow = WorkflowSettings::default();
+        let head = repo.head().unwrap().target().unwrap();
+
+        assert!(auto_commit(dir.path(), &workflow).unwrap().is_none());
+
+        fs::write(dir.path().join("a.txt"), "two").unwrap();
+        fs::write(dir.path().join("new.txt"), "untracked").unwrap();
+        let first = auto_commit(dir.path(), &workflow).unwrap().unwrap();
+        assert!(first.shadow);
+        assert_eq!(first.branch, "main");
+        assert!(auto_commit(dir.path(), &workflow).unwrap().is_none());
+
+        // The branch and index are untouched
+        assert_eq!(repo.head().unwrap().target().unwrap(), head);
+        assert!(repo
+            .index()
+            .unwrap()
+            .get_path(Path::new("new.txt"), 0)
+            .is_none());
+
+        fs::write(dir.path().join("a.txt"), "three").unwrap();
+        auto_commit(dir.path(), &workflow).unwrap().unwrap();
+        assert_eq!(list_auto_commits(dir.path()).unwrap().len(), 2);
+
+        fs::write(dir.path().join("a.txt"), "lost").unwrap();
+        let restored = restore_auto_commit(dir.path(), &first.id, "backup").unwrap();
+        assert!(restored.backup.is_some());
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "two");
+        assert_eq!(list_auto_commits(dir.path()).unwrap().len(), 3);
+        assert!(restore_auto_commit(dir.path(), &head.to_string(), "backup").is_err());
+    }
+
+    #[test]
+    fn test_branch_target_commits_on_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "a.txt", "one", "Init");
+        let workflow = WorkflowSettings {
+            auto_commit_target: AutoCommitTarget::Branch,
+            ..WorkflowSettings::default()
+        };
+
+        fs::write(dir.path().join("a.txt"), "two").unwrap();
+        let commit = auto_commit(dir.path(), &workflow).unwrap().unwrap();
+        assert!(!commit.shadow);
+        assert_eq!(
+            repo.head().unwrap().target().unwrap().to_string(),
+            commit.id
+        );
+        assert_eq!(list_auto_commits(dir.path()).unwrap().len(), 1);
+    }
+}
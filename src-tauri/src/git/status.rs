@@ -0,0 +1,251 @@
+use super::open;
+use super::submodules::{self, SubmoduleStatus};
+use anyhow::Result;
+use git2::{BranchType, Delta, DiffDelta, Repository, RepositoryState, Status, StatusOptions};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Operation in progress in the working tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationState {
+    None,
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+    Bisect,
+    ApplyMailbox,
+}
+
+impl From<RepositoryState> for OperationState {
+    fn from(state: RepositoryState) -> Self {
+        match state {
+            RepositoryState::Clean => OperationState::None,
+            RepositoryState::Merge => OperationState::Merge,
+            RepositoryState::Revert | RepositoryState::RevertSequence => OperationState::Revert,
+            RepositoryState::CherryPick | RepositoryState::CherryPickSequence => {
+                OperationState::CherryPick
+            }
+            RepositoryState::Bisect => OperationState::Bisect,
+            RepositoryState::Rebase
+            | RepositoryState::RebaseInteractive
+            | RepositoryState::RebaseMerge => OperationState::Rebase,
+            RepositoryState::ApplyMailbox | RepositoryState::ApplyMailboxOrRebase => {
+                OperationState::ApplyMailbox
+            }
+        }
+    }
+}
+
+/// How a file differs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    TypeChange,
+}
+
+/// A changed file, relative to the repository root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    /// Path before a rename
+    pub old_path: Option<String>,
+}
+
+/// Typed equivalent of `git status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitStatus {
+    /// None on a detached HEAD
+    pub branch: Option<String>,
+    /// Short id of the HEAD commit; None before the first commit
+    pub head: Option<String>,
+    /// Upstream branch, e.g. `origin/main`
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    /// Changes in the index
+    pub staged: Vec<FileChange>,
+    /// Changes in the working tree not yet staged
+    pub unstaged: Vec<FileChange>,
+    pub untracked: Vec<String>,
+    pub conflicted: Vec<String>,
+    pub operation: OperationState,
+    /// No staged, unstaged, untracked or conflicted files
+    pub clean: bool,
+    /// Sorted by path
+    pub submodules: Vec<SubmoduleStatus>,
+}
+
+/// Status of the repository containing `path`
+pub fn get_status(path: &Path) -> Result<GitStatus> {
+    let repo = open(path)?;
+
+    let head = repo.head().ok();
+    let branch = head
+        .as_ref()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand())
+        .map(str::to_string);
+    let head_commit = head.as_ref().and_then(|head| head.peel_to_commit().ok());
+    let (upstream, ahead, behind) = match &branch {
+        Some(branch) => tracking(&repo, branch)?,
+        None => (None, 0, 0),
+    };
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true)
+        .renames_index_to_workdir(true);
+    let statuses = repo.statuses(Some(&mut options))?;
+
+    let mut status = GitStatus {
+        branch,
+        head: head_commit
+            .as_ref()
+            .and_then(|commit| commit.as_object().short_id().ok())
+            .and_then(|id| id.as_str().map(str::to_string)),
+        upstream,
+        ahead,
+        behind,
+        staged: Vec::new(),
+        unstaged: Vec::new(),
+        untracked: Vec::new(),
+        conflicted: Vec::new(),
+        operation: repo.state().into(),
+        clean: false,
+        submodules: submodules::list(&repo)?,
+    };
+
+    for entry in statuses.iter() {
+        let flags = entry.status();
+        let path = entry.path().unwrap_or_default().to_string();
+
+        if flags.contains(Status::CONFLICTED) {
+            status.conflicted.push(path);
+            continue;
+        }
+        if flags.contains(Status::WT_NEW) {
+            status.untracked.push(path);
+            continue;
+        }
+        if let Some(delta) = entry.head_to_index() {
+            if let Some(change) = file_change(&delta) {
+                status.staged.push(change);
+            }
+        }
+        if let Some(delta) = entry.index_to_workdir() {
+            if let Some(change) = file_change(&delta) {
+                status.unstaged.push(change);
+            }
+        }
+    }
+
+    // A submodule with changes inside it but the recorded commit checked out
+    // has nothing the superproject could stage
+    let content_only: Vec<&str> = status
+        .submodules
+        .iter()
+        .filter(|submodule| submodule.dirty && !submodule.commit_changed)
+        .map(|submodule| submodule.path.as_str())
+        .collect();
+    status
+        .unstaged
+        .retain(|change| !content_only.contains(&change.path.as_str()));
+
+    status.clean = status.staged.is_empty()
+        && status.unstaged.is_empty()
+        && status.untracked.is_empty()
+        && status.conflicted.is_empty();
+    Ok(status)
+}
+
+/// Upstream name and ahead/behind counts of a local branch
+pub fn tracking(repo: &Repository, branch: &str) -> Result<(Option<String>, usize, usize)> {
+    let local = repo.find_branch(branch, BranchType::Local)?;
+    let Ok(upstream) = local.upstream() else {
+        return Ok((None, 0, 0));
+    };
+    let name = upstream.name()?.map(str::to_string);
+
+    let (Some(local_oid), Some(upstream_oid)) = (local.get().target(), upstream.get().target())
+    else {
+        return Ok((name, 0, 0));
+    };
+    let (ahead, behind) = repo.graph_ahead_behind(local_oid, upstream_oid)?;
+    Ok((name, ahead, behind))
+}
+
+/// Kind and paths of a diff delta; None for unchanged and ignored entries
+pub fn file_change(delta: &DiffDelta<'_>) -> Option<FileChange> {
+    let kind = match delta.status() {
+        Delta::Added => ChangeKind::Added,
+        Delta::Modified => ChangeKind::Modified,
+        Delta::Deleted => ChangeKind::Deleted,
+        Delta::Renamed => ChangeKind::Renamed,
+        Delta::Typechange => ChangeKind::TypeChange,
+        _ => return None,
+    };
+    let new_path = delta
+        .new_file()
+        .path()
+        .map(|p| p.to_string_lossy().into_owned());
+    let old_path = delta
+        .old_file()
+        .path()
+        .map(|p| p.to_string_lossy().into_owned());
+
+    Some(FileChange {
+        path: new_path.clone().or_else(|| old_path.clone())?,
+        kind,
+        old_path: old_path.filter(|_| kind == ChangeKind::Renamed),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_all, init_repo};
+    use std::fs;
+
+    #[test]
+    fn test_status_lists_staged_unstaged_and_untracked() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        fs::write(dir.path().join("a.txt"), "one").unwrap();
+        fs::write(dir.path().join("b.txt"), "one").unwrap();
+        commit_all(&repo, "initial");
+        assert!(get_status(dir.path()).unwrap().clean);
+
+        fs::write(dir.path().join("a.txt"), "two").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("a.txt")).unwrap();
+        index.write().unwrap();
+        fs::write(dir.path().join("b.txt"), "two").unwrap();
+        fs::write(dir.path().join("c.txt"), "new").unwrap();
+
+        let status = get_status(dir.path()).unwrap();
+        assert!(status.branch.is_some());
+        assert!(status.head.is_some());
+        assert_eq!(status.upstream, None);
+        assert_eq!(status.operation, OperationState::None);
+        assert!(!status.clean);
+        assert_eq!(
+            status.staged,
+            vec![FileChange {
+                path: "a.txt".to_string(),
+                kind: ChangeKind::Modified,
+                old_path: None,
+            }]
+        );
+        assert_eq!(status.unstaged[0].path, "b.txt");
+        assert_eq!(status.untracked, vec!["c.txt"]);
+    }
+}
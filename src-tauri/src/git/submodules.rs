@@ -0,0 +1,157 @@
+use super::credentials::Credentials;
+use super::{open, workdir};
+use anyhow::{Context, Result};
+use git2::{
+    FetchOptions, Oid, Repository, SubmoduleIgnore, SubmoduleStatus as Flags,
+    SubmoduleUpdateOptions,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A submodule of the repository, for get_git_status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmoduleStatus {
+    pub name: String,
+    /// Relative to the repository root
+    pub path: String,
+    pub url: Option<String>,
+    /// Short id of the commit the superproject records
+    pub recorded: Option<String>,
+    /// Short id of the commit checked out in the submodule
+    pub checked_out: Option<String>,
+    /// Cloned and checked out; update_submodules does that for the others
+    pub initialized: bool,
+    /// The checked-out commit differs from the recorded one; staging the
+    /// submodule records the new commit
+    pub commit_changed: bool,
+    /// Uncommitted or untracked files inside the submodule, which only its own
+    /// repository can commit
+    pub dirty: bool,
+}
+
+/// Submodules of `repo`, sorted by path
+pub fn list(repo: &Repository) -> Result<Vec<SubmoduleStatus>> {
+    let mut submodules = Vec::new();
+    for submodule in repo.submodules()? {
+        let Some(name) = submodule.name() else {
+            continue;
+        };
+        let flags = repo.submodule_status(name, SubmoduleIgnore::None)?;
+        let initialized = flags.contains(Flags::IN_WD) && !flags.contains(Flags::WD_UNINITIALIZED);
+        submodules.push(SubmoduleStatus {
+            name: name.to_string(),
+            path: to_slash(submodule.path()),
+            url: submodule.url().map(str::to_string),
+            recorded: submodule.index_id().or(submodule.head_id()).map(short),
+            checked_out: submodule.workdir_id().map(short),
+            initialized,
+            commit_changed: initialized
+                && flags.intersects(Flags::WD_MODIFIED | Flags::INDEX_MODIFIED),
+            dirty: flags
+                .intersects(Flags::WD_INDEX_MODIFIED | Flags::WD_WD_MODIFIED | Flags::WD_UNTRACKED),
+        });
+    }
+    submodules.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(submodules)
+}
+
+/// Paths of the submodules of the repository containing `path`, relative to
+/// its root; empty outside a repository
+pub fn paths(path: &Path) -> Vec<PathBuf> {
+    let Ok(repo) = open(path) else {
+        return Vec::new();
+    };
+    repo.submodules()
+        .map(|submodules| {
+            submodules
+                .iter()
+                .map(|submodule| submodule.path().to_path_buf())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Clone missing submodules and check out the recorded commit in each, like
+/// `git submodule update --init`
+///
+/// Authenticates like fetch. Submodules of submodules are left alone.
+pub fn update_submodules(
+    repo_path: &Path,
+    credentials: &Credentials,
+) -> Result<Vec<SubmoduleStatus>> {
+    let repo = open(repo_path)?;
+    workdir(&repo)?;
+    let config = repo.config()?;
+    for mut submodule in repo.submodules()? {
+        let name = submodule.name().unwrap_or_default().to_string();
+        let mut fetch = FetchOptions::new();
+        fetch.remote_callbacks(credentials.callbacks(&config));
+        let mut options = SubmoduleUpdateOptions::new();
+        options.fetch(fetch);
+        submodule
+            .update(true, Some(&mut options))
+            .with_context(|| format!("Failed to update submodule {}", name))?;
+    }
+    list(&repo)
+}
+
+fn short(id: Oid) -> String {
+    id.to_string()[..7].to_string()
+}
+
+fn to_slash(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::status::get_status;
+    use crate::git::test_support::{commit_all, init_repo};
+    use std::fs;
+
+    #[test]
+    fn test_reports_commit_changes_and_dirty_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = init_repo(&dir.path().join("library"));
+        fs::write(dir.path().join("library/lib.rs"), "one").unwrap();
+        commit_all(&library, "initial");
+
+        let app = init_repo(&dir.path().join("app"));
+        fs::write(dir.path().join("app/main.rs"), "main").unwrap();
+        let url = dir.path().join("library").to_string_lossy().into_owned();
+        let mut submodule = app
+            .submodule(&url, Path::new("libs/library"), true)
+            .unwrap();
+        submodule.clone(None).unwrap();
+        submodule.add_finalize().unwrap();
+        commit_all(&app, "Add library");
+
+        let [status] = list(&app).unwrap().try_into().unwrap();
+        assert_eq!(status.path, "libs/library");
+        assert!(status.initialized && !status.commit_changed && !status.dirty);
+        assert_eq!(status.recorded, status.checked_out);
+
+        fs::write(dir.path().join("app/libs/library/notes.txt"), "wip").unwrap();
+        let [status] = list(&app).unwrap().try_into().unwrap();
+        assert!(status.dirty && !status.commit_changed);
+        // Only the submodule's own repository can commit its files
+        let git_status = get_status(&dir.path().join("app")).unwrap();
+        assert!(git_status.unstaged.is_empty());
+        assert!(git_status.clean);
+
+        let checkout = Repository::open(dir.path().join("app/libs/library")).unwrap();
+        commit_all(&checkout, "Add notes");
+        let [status] = list(&app).unwrap().try_into().unwrap();
+        assert!(status.commit_changed && !status.dirty);
+        assert_ne!(status.recorded, status.checked_out);
+        let git_status = get_status(&dir.path().join("app")).unwrap();
+        assert_eq!(git_status.unstaged[0].path, "libs/library");
+        assert_eq!(git_status.submodules, [status]);
+
+        assert_eq!(
+            paths(&dir.path().join("app")),
+            [PathBuf::from("libs/library")]
+        );
+    }
+}
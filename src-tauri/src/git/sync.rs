@@ -0,0 +1,406 @@
+use super::commit::write_commit;
+use super::{current_branch, open};
+use crate::config::{GitSettings, MergeStrategy};
+use anyhow::{bail, Context, Result};
+use git2::build::CheckoutBuilder;
+use git2::{BranchType, Commit, Index, Oid, Repository, RepositoryState, Sort, StatusOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// How the base branch is brought into the checked-out branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncMethod {
+    Merge,
+    Rebase,
+}
+
+impl From<MergeStrategy> for SyncMethod {
+    /// Squashing only applies to pull requests; the branch itself is merged
+    fn from(strategy: MergeStrategy) -> Self {
+        match strategy {
+            MergeStrategy::Rebase => SyncMethod::Rebase,
+            MergeStrategy::Merge | MergeStrategy::Squash => SyncMethod::Merge,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SyncOutcome {
+    /// The branch already contains the base
+    UpToDate,
+    /// The branch had no commits of its own and now points at the base
+    FastForward,
+    Merged,
+    Rebased {
+        commits: usize,
+    },
+    /// Nothing was changed
+    Conflicts {
+        /// The branch commit that does not apply, when rebasing
+        commit: Option<String>,
+        files: Vec<String>,
+    },
+}
+
+/// Result of sync_with_base
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaseSync {
+    pub branch: String,
+    /// e.g. `origin/main`, or `main` when the remote has no such branch
+    pub base: String,
+    pub method: SyncMethod,
+    /// Commits on the branch and on the base before the sync
+    pub ahead: usize,
+    pub behind: usize,
+    /// What happened, or for a dry run what would happen
+    pub outcome: SyncOutcome,
+    /// Branch tip after the sync
+    pub head: String,
+    /// The rebase rewrote commits already on the upstream branch, so the next
+    /// push has to be forced
+    pub force_push_needed: bool,
+}
+
+/// Merge or rebase the checked-out branch onto GitSettings::default_branch,
+/// preferring the remote-tracking branch
+///
+/// Refuses on the base branch itself, with a merge or rebase in progress or
+/// with uncommitted changes to tracked files. The result is built in memory
+/// first, so a conflict leaves the branch and working tree untouched; with
+/// `dry_run` nothing is changed either way. Rebasing replays each commit with
+/// its author and message and, like merge commits, signs it per
+/// GitSettings::sign_commits.
+pub fn sync_with_base(
+    repo_path: &Path,
+    git: &GitSettings,
+    method: SyncMethod,
+    dry_run: bool,
+) -> Result<BaseSync> {
+    let repo = open(repo_path)?;
+    let branch = check_safe(&repo, git)?;
+    let (base_name, base) = base_commit(&repo, git)?;
+    let head = repo.head()?.peel_to_commit()?;
+    let (ahead, behind) = repo.graph_ahead_behind(head.id(), base.id())?;
+
+    let mut sync = BaseSync {
+        branch: branch.clone(),
+        base: base_name.clone(),
+        method,
+        ahead,
+        behind,
+        outcome: SyncOutcome::UpToDate,
+        head: head.id().to_string(),
+        force_push_needed: false,
+    };
+    if behind == 0 {
+        return Ok(sync);
+    }
+
+    let sign = (git.sign_commits && !dry_run).then_some(git);
+    let new_head = if ahead == 0 {
+        sync.outcome = SyncOutcome::FastForward;
+        base.id()
+    } else {
+        let integrated = match method {
+            SyncMethod::Merge => merge(&repo, sign, &head, &base, &base_name, &branch)?,
+            SyncMethod::Rebase => rebase(&repo, sign, &head, &base)?,
+        };
+        match integrated {
+            Integrated::Commit(id) => {
+                sync.outcome = match method {
+                    SyncMethod::Merge => SyncOutcome::Merged,
+                    SyncMethod::Rebase => SyncOutcome::Rebased { commits: ahead },
+                };
+                id
+            }
+            Integrated::Conflicts { commit, files } => {
+                sync.outcome = SyncOutcome::Conflicts { commit, files };
+                return Ok(sync);
+            }
+        }
+    };
+
+    sync.force_push_needed = method == SyncMethod::Rebase
+        && ahead > 0
+        && upstream_tip(&repo, &branch)
+            .is_some_and(|upstream| !is_ancestor(&repo, upstream, new_head));
+    if dry_run {
+        return Ok(sync);
+    }
+
+    let target = repo.find_commit(new_head)?;
+    repo.checkout_tree(target.as_object(), Some(CheckoutBuilder::new().safe()))
+        .context("Failed to update the working tree")?;
+    repo.reference(
+        &format!("refs/heads/{}", branch),
+        new_head,
+        true,
+        &format!("sync: {} onto {}", branch, base_name),
+    )?;
+    sync.head = new_head.to_string();
+    Ok(sync)
+}
+
+/// The checked-out branch, when it can safely be rewritten
+fn check_safe(repo: &Repository, git: &GitSettings) -> Result<String> {
+    if repo.state() != RepositoryState::Clean {
+        bail!("Finish or abort the merge, rebase or cherry-pick in progress first");
+    }
+    let branch = current_branch(repo)?;
+    if branch == git.default_branch {
+        bail!("{} is the base branch; check out an issue branch", branch);
+    }
+    let mut options = StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    let statuses = repo.statuses(Some(&mut options))?;
+    let changed: Vec<String> = statuses
+        .iter()
+        .filter_map(|entry| entry.path().map(str::to_string))
+        .collect();
+    if !changed.is_empty() {
+        bail!(
+            "Commit or stash the changes to {} first",
+            changed.join(", ")
+        );
+    }
+    Ok(branch)
+}
+
+/// `<remote>/<default_branch>`, or the local default branch without one
+pub(super) fn base_commit<'r>(
+    repo: &'r Repository,
+    git: &GitSettings,
+) -> Result<(String, Commit<'r>)> {
+    let remote = format!("{}/{}", git.remote, git.default_branch);
+    for (name, kind) in [
+        (&remote, BranchType::Remote),
+        (&git.default_branch, BranchType::Local),
+    ] {
+        if let Ok(branch) = repo.find_branch(name, kind) {
+            return Ok((name.clone(), branch.get().peel_to_commit()?));
+        }
+    }
+    bail!("Base branch {} does not exist", git.default_branch)
+}
+
+enum Integrated {
+    Commit(Oid),
+    Conflicts {
+        commit: Option<String>,
+        files: Vec<String>,
+    },
+}
+
+fn merge(
+    repo: &Repository,
+    sign: Option<&GitSettings>,
+    head: &Commit,
+    base: &Commit,
+    base_name: &str,
+    branch: &str,
+) -> Result<Integrated> {
+    let mut index = repo.merge_commits(head, base, None)?;
+    if index.has_conflicts() {
+        return Ok(Integrated::Conflicts {
+            commit: None,
+            files: conflicted_paths(&index)?,
+        });
+    }
+    let tree = repo.find_tree(index.write_tree_to(repo)?)?;
+    let signature = repo
+        .signature()
+        .context("Set user.name and user.email in your git config")?;
+    let message = format!("Merge {} into {}\n", base_name, branch);
+    let id = write_commit(
+        repo,
+        sign,
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &[head, base],
+    )?;
+    Ok(Integrated::Commit(id))
+}
+
+/// Replay the branch's own commits, oldest first, onto `base`
+fn rebase(
+    repo: &Repository,
+    sign: Option<&GitSettings>,
+    head: &Commit,
+    base: &Commit,
+) -> Result<Integrated> {
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    walk.push(head.id())?;
+    walk.hide(base.id())?;
+    let committer = repo
+        .signature()
+        .context("Set user.name and user.email in your git config")?;
+
+    let mut onto = base.clone();
+    for id in walk {
+        let commit = repo.find_commit(id?)?;
+        if commit.parent_count() > 1 {
+            bail!(
+                "The branch contains merge commit {}; sync it with the merge strategy",
+                short(commit.id())
+            );
+        }
+        let mut index = repo.cherrypick_commit(&commit, &onto, 0, None)?;
+        if index.has_conflicts() {
+            return Ok(Integrated::Conflicts {
+                commit: Some(short(commit.id())),
+                files: conflicted_paths(&index)?,
+            });
+        }
+        let tree = repo.find_tree(index.write_tree_to(repo)?)?;
+        let message = commit
+            .message()
+            .context("Commit message is not valid UTF-8")?;
+        let id = write_commit(
+            repo,
+            sign,
+            &commit.author(),
+            &committer,
+            message,
+            &tree,
+            &[&onto],
+        )?;
+        onto = repo.find_commit(id)?;
+    }
+    Ok(Integrated::Commit(onto.id()))
+}
+
+fn conflicted_paths(index: &Index) -> Result<Vec<String>> {
+    let mut files = BTreeSet::new();
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+            files.insert(String::from_utf8_lossy(&entry.path).into_owned());
+        }
+    }
+    Ok(files.into_iter().collect())
+}
+
+fn upstream_tip(repo: &Repository, branch: &str) -> Option<Oid> {
+    repo.find_branch(branch, BranchType::Local)
+        .ok()?
+        .upstream()
+        .ok()?
+        .get()
+        .target()
+}
+
+fn is_ancestor(repo: &Repository, ancestor: Oid, of: Oid) -> bool {
+    ancestor == of || repo.graph_descendant_of(of, ancestor).unwrap_or(false)
+}
+
+fn short(id: Oid) -> String {
+    id.to_string()[..7].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_file, init_repo};
+    use std::fs;
+
+    /// A repository whose `main` and `issue-1` both changed since they forked
+    fn diverged(issue_file: (&str, &str)) -> (tempfile::TempDir, Repository, GitSettings) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "shared.txt", "one\n", "Add shared.txt");
+        let main = repo.head().unwrap().shorthand().unwrap().to_string();
+        {
+            let fork = repo.head().unwrap().peel_to_commit().unwrap();
+            repo.branch("issue-1", &fork, false).unwrap();
+        }
+        commit_file(&repo, "shared.txt", "one\ntwo\n", "Change shared.txt");
+
+        repo.set_head("refs/heads/issue-1").unwrap();
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+        commit_file(
+            &repo,
+            issue_file.0,
+            issue_file.1,
+            &format!("Change {}", issue_file.0),
+        );
+        let git = GitSettings {
+            default_branch: main,
+            ..GitSettings::default()
+        };
+        (dir, repo, git)
+    }
+
+    #[test]
+    fn test_rebases_onto_the_base_branch() {
+        let (dir, repo, git) = diverged(("feature.txt", "new\n"));
+        let before = repo.head().unwrap().target().unwrap();
+
+        let dry = sync_with_base(dir.path(), &git, SyncMethod::Rebase, true).unwrap();
+        assert_eq!(dry.outcome, SyncOutcome::Rebased { commits: 1 });
+        assert_eq!(repo.head().unwrap().target().unwrap(), before);
+
+        let sync = sync_with_base(dir.path(), &git, SyncMethod::Rebase, false).unwrap();
+        assert_eq!((sync.ahead, sync.behind), (1, 1));
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(sync.head, head.id().to_string());
+        assert_eq!(head.message(), Some("Change feature.txt"));
+        assert_eq!(head.parent_count(), 1);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("shared.txt")).unwrap(),
+            "one\ntwo\n"
+        );
+        assert!(!sync.force_push_needed);
+
+        let again = sync_with_base(dir.path(), &git, SyncMethod::Rebase, false).unwrap();
+        assert_eq!(again.outcome, SyncOutcome::UpToDate);
+    }
+
+    #[test]
+    fn test_merges_the_base_branch() {
+        let (dir, repo, git) = diverged(("feature.txt", "new\n"));
+        let sync = sync_with_base(dir.path(), &git, SyncMethod::Merge, false).unwrap();
+        assert_eq!(sync.outcome, SyncOutcome::Merged);
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_count(), 2);
+        assert!(dir.path().join("feature.txt").exists());
+    }
+
+    #[test]
+    fn test_reports_conflicts_without_changing_anything() {
+        let (dir, repo, git) = diverged(("shared.txt", "one\nthree\n"));
+        let before = repo.head().unwrap().target().unwrap();
+        for method in [SyncMethod::Merge, SyncMethod::Rebase] {
+            let sync = sync_with_base(dir.path(), &git, method, false).unwrap();
+            match sync.outcome {
+                SyncOutcome::Conflicts { files, .. } => assert_eq!(files, ["shared.txt"]),
+                other => panic!("expected conflicts, got {:?}", other),
+            }
+            assert_eq!(repo.head().unwrap().target().unwrap(), before);
+        }
+        assert_eq!(
+            fs::read_to_string(dir.path().join("shared.txt")).unwrap(),
+            "one\nthree\n"
+        );
+    }
+
+    #[test]
+    fn test_refuses_unsafe_states() {
+        let (dir, repo, git) = diverged(("feature.txt", "new\n"));
+        fs::write(dir.path().join("feature.txt"), "edited\n").unwrap();
+        let error = sync_with_base(dir.path(), &git, SyncMethod::Rebase, false).unwrap_err();
+        assert!(error.to_string().contains("feature.txt"));
+
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+        repo.set_head(&format!("refs/heads/{}", git.default_branch))
+            .unwrap();
+        assert!(sync_with_base(dir.path(), &git, SyncMethod::Merge, false).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+//! Repositories for the tests of the git modules
+
+use git2::{Oid, Repository, Signature};
+use std::fs;
+use std::path::Path;
+
+/// Author and committer of test commits
+pub(crate) fn signature() -> Signature<'static> {
+    Signature::now("Test", "test@example.com").unwrap()
+}
+
+/// Empty repository in `dir` on `main`, with the test identity as its user
+pub(crate) fn init_repo(dir: &Path) -> Repository {
+    let repo = Repository::init(dir).unwrap();
+    repo.set_head("refs/heads/main").unwrap();
+    {
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+    }
+    repo
+}
+
+/// Commit every change in the work tree on HEAD
+pub(crate) fn commit_all(repo: &Repository, message: &str) -> Oid {
+    let mut index = repo.index().unwrap();
+    index
+        .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    index.write().unwrap();
+    commit_index(repo, &mut index, message)
+}
+
+/// Write `content` to `name` and commit that file alone on HEAD
+pub(crate) fn commit_file(repo: &Repository, name: &str, content: &str, message: &str) -> Oid {
+    fs::write(repo.workdir().unwrap().join(name), content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(name)).unwrap();
+    index.write().unwrap();
+    commit_index(repo, &mut index, message)
+}
+
+fn commit_index(repo: &Repository, index: &mut git2::Index, message: &str) -> Oid {
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = signature();
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .unwrap()
+}
@@ -0,0 +1,262 @@
+use super::sync::SyncService;
+use super::{GitHubState, Repository};
+use crate::config::SettingsState;
+use crate::events::schema::v1;
+use crate::events::GITHUB_EVENT_NAME;
+use anyhow::{bail, Context, Result};
+use octocrab::etag::EntityTag;
+use octocrab::Octocrab;
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Poll interval when GitHub does not send X-Poll-Interval
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Event types reported to the UI
+const WATCHED_EVENTS: &[&str] = &[
+    "IssuesEvent",
+    "IssueCommentEvent",
+    "PullRequestEvent",
+    "PullRequestReviewEvent",
+    "PullRequestReviewCommentEvent",
+];
+
+/// An entry of the repository events API
+#[derive(Debug, Deserialize)]
+struct RawEvent {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    actor: RawActor,
+    created_at: String,
+    #[serde(default)]
+    payload: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawActor {
+    login: String,
+}
+
+/// Where polling of one repository left off
+#[derive(Debug, Default)]
+struct PollState {
+    repository: String,
+    etag: Option<EntityTag>,
+    /// Newest event id seen; None until the first poll sets the baseline
+    last_id: Option<u64>,
+}
+
+/// Watch the configured repository's event stream while GitHubSettings::watch_events is on
+///
+/// Uses the Events API with ETags (304s are free) at the interval GitHub asks for.
+/// The first poll only records a baseline so history is not replayed. New activity
+/// is emitted as `github-event` and also triggers a cache sync.
+pub fn spawn_event_poller(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut state = PollState::default();
+        loop {
+            let settings = app.state::<SettingsState>().current().github;
+            let mut interval = DEFAULT_POLL_INTERVAL;
+
+            if let (true, Ok(repo)) = (settings.watch_events, Repository::from_settings(&settings))
+            {
+                if state.repository != repo.to_string() {
+                    state = PollState {
+                        repository: repo.to_string(),
+                        ..PollState::default()
+                    };
+                }
+
+                let result = match app
+                    .state::<GitHubState>()
+                    .background_client(&settings)
+                    .await
+                {
+                    Ok(client) => poll(&client, &repo, &mut state).await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok((events, poll_interval)) => {
+                        interval = poll_interval.max(DEFAULT_POLL_INTERVAL);
+                        if !events.is_empty() {
+                            app.state::<SyncService>().trigger();
+                        }
+                        for event in events {
+                            if let Err(e) = app.emit_all(GITHUB_EVENT_NAME, event) {
+                                tracing::error!("Failed to emit GitHub event: {}", e);
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!(repository = %repo, "GitHub event poll failed: {:#}", e)
+                    }
+                }
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+/// Fetch new events; returns the watched ones (oldest first) and the requested poll interval
+async fn poll(
+    client: &Octocrab,
+    repo: &Repository,
+    state: &mut PollState,
+) -> Result<(Vec<v1::GitHubEvent>, Duration)> {
+    let uri = format!("/repos/{}/{}/events?per_page=100", repo.owner, repo.name);
+    let mut headers = Default::default();
+    if let Some(etag) = state.etag.clone() {
+        EntityTag::insert_if_none_match_header(&mut headers, etag)?;
+    }
+
+    let response = client
+        ._get_with_headers(uri.as_str(), Some(headers))
+        .await
+        .context("Failed to fetch repository events")?;
+    let interval = response
+        .headers()
+        .get("x-poll-interval")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_POLL_INTERVAL);
+
+    let status = response.status();
+    if status.as_u16() == 304 {
+        return Ok((Vec::new(), interval));
+    }
+    let etag = EntityTag::extract_from_response(&response);
+    let body = client.body_to_string(response).await?;
+    if !status.is_success() {
+        bail!("GitHub returned {}: {}", status, body);
+    }
+
+    let raw: Vec<RawEvent> = serde_json::from_str(&body).context("Unexpected events response")?;
+    state.etag = etag;
+    Ok((new_events(repo, raw, &mut state.last_id), interval))
+}
+
+/// Watched events newer than `last_id`, oldest first; advances `last_id`
+/// With no `last_id` yet only the baseline is recorded
+fn new_events(
+    repo: &Repository,
+    raw: Vec<RawEvent>,
+    last_id: &mut Option<u64>,
+) -> Vec<v1::GitHubEvent> {
+    let newest = raw
+        .iter()
+        .filter_map(|event| event.id.parse::<u64>().ok())
+        .max();
+    let Some(previous) = *last_id else {
+        *last_id = newest;
+        return Vec::new();
+    };
+    if let Some(newest) = newest {
+        *last_id = Some(newest.max(previous));
+    }
+
+    let mut events: Vec<v1::GitHubEvent> = raw
+        .into_iter()
+        .filter(|event| event.id.parse::<u64>().is_ok_and(|id| id > previous))
+        .filter_map(|event| to_payload(repo, event))
+        .collect();
+    events.reverse();
+    events
+}
+
+fn to_payload(repo: &Repository, event: RawEvent) -> Option<v1::GitHubEvent> {
+    if !WATCHED_EVENTS.contains(&event.kind.as_str()) {
+        return None;
+    }
+
+    let payload = &event.payload;
+    let number = payload
+        .pointer("/issue/number")
+        .or_else(|| payload.pointer("/pull_request/number"))
+        .or_else(|| payload.get("number"))
+        .and_then(Value::as_u64);
+    let pull_request =
+        event.kind.starts_with("PullRequest") || payload.pointer("/issue/pull_request").is_some();
+
+    Some(v1::GitHubEvent {
+        repository: repo.to_string(),
+        kind: event.kind,
+        action: payload
+            .get("action")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        number,
+        pull_request,
+        actor: event.actor.login,
+        created_at: event.created_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn raw(id: &str, kind: &str, payload: Value) -> RawEvent {
+        serde_json::from_value(json!({
+            "id": id,
+            "type": kind,
+            "actor": { "login": "teammate" },
+            "created_at": "2024-01-01T00:00:00Z",
+            "payload": payload,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_first_poll_sets_baseline() {
+        let repo = Repository::parse("o/r").unwrap();
+        let mut last_id = None;
+
+        let events = new_events(
+            &repo,
+            vec![raw("10", "IssuesEvent", json!({ "action": "opened" }))],
+            &mut last_id,
+        );
+        assert!(events.is_empty());
+        assert_eq!(last_id, Some(10));
+    }
+
+    #[test]
+    fn test_new_watched_events_oldest_first() {
+        let repo = Repository::parse("o/r").unwrap();
+        let mut last_id = Some(10);
+
+        let events = new_events(
+            &repo,
+            vec![
+                raw(
+                    "13",
+                    "IssueCommentEvent",
+                    json!({ "action": "created", "issue": { "number": 4, "pull_request": {} } }),
+                ),
+                raw("12", "PushEvent", json!({})),
+                raw(
+                    "11",
+                    "IssuesEvent",
+                    json!({ "action": "closed", "issue": { "number": 3 } }),
+                ),
+                raw("10", "IssuesEvent", json!({ "action": "opened" })),
+            ],
+            &mut last_id,
+        );
+
+        assert_eq!(last_id, Some(13));
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].number, Some(3));
+        assert_eq!(events[0].action, "closed");
+        assert!(!events[0].pull_request);
+        assert_eq!(events[1].kind, "IssueCommentEvent");
+        assert!(events[1].pull_request);
+    }
+}
@@ -0,0 +1,291 @@
+use super::issues::Issue;
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Default number of results from `query`
+const DEFAULT_QUERY_LIMIT: usize = 100;
+
+/// Where the last incremental sync of a repository left off
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncCursor {
+    /// Newest `updated_at` seen, as `YYYY-MM-DDTHH:MM:SSZ` for the `since` parameter
+    pub since: Option<String>,
+    /// ETag of the last first-page response
+    pub etag: Option<String>,
+}
+
+/// Filters for searching the cache; every field is optional
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CachedIssueQuery {
+    /// `owner/name`; defaults to the configured repository
+    pub repository: Option<String>,
+    /// Matched case-insensitively against title and body
+    pub text: Option<String>,
+    /// "open" or "closed"
+    pub state: Option<String>,
+    pub label: Option<String>,
+    /// Some(true) for only pull requests, Some(false) for only issues
+    pub pull_requests: Option<bool>,
+    pub limit: Option<usize>,
+}
+
+/// A cached issue or pull request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedIssue {
+    #[serde(flatten)]
+    pub issue: Issue,
+    pub pull_request: bool,
+}
+
+/// SQLite cache of issues and pull requests, kept in ~/.zeami/cache/github.db
+pub struct IssueCache {
+    conn: Mutex<Connection>,
+}
+
+impl IssueCache {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open issue cache {:?}", path))?;
+        Self::init(conn)
+    }
+
+    /// A cache that lives only as long as the process
+    pub fn in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS issues (
+                repository TEXT NOT NULL,
+                number INTEGER NOT NULL,
+                pull_request INTEGER NOT NULL,
+                state TEXT NOT NULL,
+                title TEXT NOT NULL,
+                body TEXT NOT NULL,
+                labels TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                data TEXT NOT NULL,
+                PRIMARY KEY (repository, number)
+            );
+            CREATE INDEX IF NOT EXISTS issues_updated ON issues (repository, updated_at);
+            CREATE TABLE IF NOT EXISTS sync_state (
+                repository TEXT PRIMARY KEY,
+                since TEXT,
+                etag TEXT
+            );",
+        )
+        .context("Failed to initialize issue cache")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock issue cache: {}", e))
+    }
+
+    /// Insert or update an issue; returns false when the cached copy is already current
+    pub fn upsert(&self, repository: &str, issue: &Issue, pull_request: bool) -> Result<bool> {
+        let changed = self.conn()?.execute(
+            "INSERT INTO issues
+                (repository, number, pull_request, state, title, body, labels, updated_at, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT (repository, number) DO UPDATE SET
+                pull_request = excluded.pull_request,
+                state = excluded.state,
+                title = excluded.title,
+                body = excluded.body,
+                labels = excluded.labels,
+                updated_at = excluded.updated_at,
+                data = excluded.data
+             WHERE issues.updated_at <> excluded.updated_at",
+            params![
+                repository,
+                issue.number as i64,
+                pull_request,
+                issue.state,
+                issue.title,
+                issue.body,
+                serde_json::to_string(&issue.labels)?,
+                issue.updated_at,
+                serde_json::to_string(issue)?,
+            ],
+        )?;
+        Ok(changed > 0)
+    }
+
+    pub fn cursor(&self, repository: &str) -> Result<SyncCursor> {
+        let cursor = self
+            .conn()?
+            .query_row(
+                "SELECT since, etag FROM sync_state WHERE repository = ?1",
+                params![repository],
+                |row| {
+                    Ok(SyncCursor {
+                        since: row.get(0)?,
+                        etag: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(cursor.unwrap_or_default())
+    }
+
+    pub fn set_cursor(&self, repository: &str, cursor: &SyncCursor) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT INTO sync_state (repository, since, etag) VALUES (?1, ?2, ?3)
+             ON CONFLICT (repository) DO UPDATE SET since = excluded.since, etag = excluded.etag",
+            params![repository, cursor.since, cursor.etag],
+        )?;
+        Ok(())
+    }
+
+    /// Cached issues of `repository` matching `query`, most recently updated first
+    pub fn query(&self, repository: &str, query: &CachedIssueQuery) -> Result<Vec<CachedIssue>> {
+        let text = query
+            .text
+            .as_deref()
+            .filter(|text| !text.trim().is_empty())
+            .map(|text| format!("%{}%", text.trim()));
+        let label = query
+            .label
+            .as_deref()
+            .map(|label| format!("%{}%", serde_json::to_string(label).unwrap_or_default()));
+        let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT) as i64;
+
+        let conn = self.conn()?;
+        let mut statement = conn.prepare(
+            "SELECT data, pull_request FROM issues
+             WHERE repository = ?1
+               AND (?2 IS NULL OR title LIKE ?2 OR body LIKE ?2)
+               AND (?3 IS NULL OR state = ?3)
+               AND (?4 IS NULL OR labels LIKE ?4)
+               AND (?5 IS NULL OR pull_request = ?5)
+             ORDER BY updated_at DESC
+             LIMIT ?6",
+        )?;
+        let rows = statement.query_map(
+            params![
+                repository,
+                text,
+                query.state,
+                label,
+                query.pull_requests,
+                limit
+            ],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)),
+        )?;
+
+        let mut issues = Vec::new();
+        for row in rows {
+            let (data, pull_request) = row?;
+            issues.push(CachedIssue {
+                issue: serde_json::from_str(&data).context("Corrupt cached issue")?,
+                pull_request,
+            });
+        }
+        Ok(issues)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(number: u64, title: &str, updated_at: &str) -> Issue {
+        Issue {
+            number,
+            title: title.to_string(),
+            body: String::new(),
+            state: "open".to_string(),
+            labels: vec!["bug".to_string()],
+            assignees: Vec::new(),
+            author: "octocat".to_string(),
+            comments: 0,
+            html_url: String::new(),
+            created_at: updated_at.to_string(),
+            updated_at: updated_at.to_string(),
+            closed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_upsert_only_reports_changes() {
+        let cache = IssueCache::in_memory().unwrap();
+        let first = issue(1, "Crash on start", "2024-01-01T00:00:00+00:00");
+
+        assert!(cache.upsert("o/r", &first, false).unwrap());
+        assert!(!cache.upsert("o/r", &first, false).unwrap());
+
+        let edited = issue(1, "Crash on startup", "2024-01-02T00:00:00+00:00");
+        assert!(cache.upsert("o/r", &edited, false).unwrap());
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let cache = IssueCache::in_memory().unwrap();
+        cache
+            .upsert(
+                "o/r",
+                &issue(1, "Crash on start", "2024-01-01T00:00:00+00:00"),
+                false,
+            )
+            .unwrap();
+        cache
+            .upsert(
+                "o/r",
+                &issue(2, "Fix crash", "2024-01-02T00:00:00+00:00"),
+                true,
+            )
+            .unwrap();
+        cache
+            .upsert(
+                "o/other",
+                &issue(3, "Crash", "2024-01-03T00:00:00+00:00"),
+                false,
+            )
+            .unwrap();
+
+        let query = CachedIssueQuery {
+            text: Some("crash".to_string()),
+            label: Some("bug".to_string()),
+            ..CachedIssueQuery::default()
+        };
+        let numbers: Vec<u64> = cache
+            .query("o/r", &query)
+            .unwrap()
+            .into_iter()
+            .map(|cached| cached.issue.number)
+            .collect();
+        assert_eq!(numbers, vec![2, 1]);
+
+        let issues_only = CachedIssueQuery {
+            pull_requests: Some(false),
+            ..query
+        };
+        assert_eq!(cache.query("o/r", &issues_only).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cache = IssueCache::in_memory().unwrap();
+        assert_eq!(cache.cursor("o/r").unwrap(), SyncCursor::default());
+
+        let cursor = SyncCursor {
+            since: Some("2024-01-01T00:00:00Z".to_string()),
+            etag: Some("\"abc\"".to_string()),
+        };
+        cache.set_cursor("o/r", &cursor).unwrap();
+        assert_eq!(cache.cursor("o/r").unwrap(), cursor);
+    }
+}
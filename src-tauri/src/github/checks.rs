@@ -0,0 +1,185 @@
+use super::Repository;
+use anyhow::{Context, Result};
+use octocrab::models::workflows::Run;
+use octocrab::Octocrab;
+use serde::{Deserialize, Serialize};
+
+/// Workflow runs fetched per request; enough to cover every workflow of the newest commits
+const RUNS_PER_PAGE: u8 = 50;
+
+/// Conclusions that turn the status bar red
+const FAILED_CONCLUSIONS: &[&str] = &[
+    "failure",
+    "cancelled",
+    "timed_out",
+    "action_required",
+    "startup_failure",
+];
+
+/// Overall CI state of a commit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CiState {
+    /// No workflow ran for the commit
+    None,
+    Pending,
+    Success,
+    Failure,
+}
+
+/// A GitHub Actions workflow run as shown in the UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowRun {
+    pub id: u64,
+    pub name: String,
+    /// "queued", "in_progress", "completed", ...
+    pub status: String,
+    pub conclusion: Option<String>,
+    pub head_sha: String,
+    pub html_url: String,
+    pub updated_at: String,
+}
+
+impl From<Run> for WorkflowRun {
+    fn from(run: Run) -> Self {
+        Self {
+            id: run.id.0,
+            name: run.name,
+            status: run.status,
+            conclusion: run.conclusion,
+            head_sha: run.head_sha,
+            html_url: run.html_url.to_string(),
+            updated_at: run.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Latest run of each workflow for the head commit of a branch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BranchChecks {
+    pub branch: String,
+    /// None when no workflow ran on the branch yet
+    pub head_sha: Option<String>,
+    pub state: CiState,
+    pub runs: Vec<WorkflowRun>,
+}
+
+/// Workflow runs for `branch`
+///
+/// `head_sha` selects the commit (normally the local HEAD); without it the
+/// commit of the newest run is used.
+pub async fn get_checks_for_branch(
+    client: &Octocrab,
+    repo: &Repository,
+    branch: &str,
+    head_sha: Option<&str>,
+) -> Result<BranchChecks> {
+    let page = client
+        .workflows(&repo.owner, &repo.name)
+        .list_all_runs()
+        .branch(branch)
+        .per_page(RUNS_PER_PAGE)
+        .send()
+        .await
+        .with_context(|| format!("Failed to list workflow runs for {}", branch))?;
+
+    let runs: Vec<WorkflowRun> = page.items.into_iter().map(WorkflowRun::from).collect();
+    let head_sha = head_sha
+        .map(str::to_string)
+        .or_else(|| runs.first().map(|run| run.head_sha.clone()));
+    let runs = match &head_sha {
+        Some(sha) => latest_per_workflow(runs, sha),
+        None => Vec::new(),
+    };
+
+    Ok(BranchChecks {
+        branch: branch.to_string(),
+        head_sha,
+        state: summarize(&runs),
+        runs,
+    })
+}
+
+/// Combined state: any failure is red, otherwise anything unfinished is pending
+pub fn summarize(runs: &[WorkflowRun]) -> CiState {
+    if runs.is_empty() {
+        return CiState::None;
+    }
+    let failed = runs.iter().any(|run| {
+        run.conclusion
+            .as_deref()
+            .is_some_and(|conclusion| FAILED_CONCLUSIONS.contains(&conclusion))
+    });
+    if failed {
+        CiState::Failure
+    } else if runs.iter().any(|run| run.status != "completed") {
+        CiState::Pending
+    } else {
+        CiState::Success
+    }
+}
+
+/// Runs for `sha`, keeping only the newest attempt of each workflow
+/// The API lists runs newest first
+fn latest_per_workflow(runs: Vec<WorkflowRun>, sha: &str) -> Vec<WorkflowRun> {
+    let mut latest: Vec<WorkflowRun> = Vec::new();
+    for run in runs.into_iter().filter(|run| run.head_sha == sha) {
+        if !latest.iter().any(|seen| seen.name == run.name) {
+            latest.push(run);
+        }
+    }
+    latest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(name: &str, sha: &str, status: &str, conclusion: Option<&str>) -> WorkflowRun {
+        WorkflowRun {
+            id: 1,
+            name: name.to_string(),
+            status: status.to_string(),
+            conclusion: conclusion.map(str::to_string),
+            head_sha: sha.to_string(),
+            html_url: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        assert_eq!(summarize(&[]), CiState::None);
+        assert_eq!(
+            summarize(&[
+                run("ci", "a", "completed", Some("success")),
+                run("lint", "a", "in_progress", None),
+            ]),
+            CiState::Pending
+        );
+        assert_eq!(
+            summarize(&[
+                run("ci", "a", "completed", Some("failure")),
+                run("lint", "a", "in_progress", None),
+            ]),
+            CiState::Failure
+        );
+        assert_eq!(
+            summarize(&[run("ci", "a", "completed", Some("skipped"))]),
+            CiState::Success
+        );
+    }
+
+    #[test]
+    fn test_latest_per_workflow_ignores_old_attempts_and_commits() {
+        let runs = vec![
+            run("ci", "b", "completed", Some("success")),
+            run("ci", "b", "completed", Some("failure")),
+            run("ci", "a", "completed", Some("failure")),
+        ];
+
+        let latest = latest_per_workflow(runs, "b");
+        assert_eq!(latest.len(), 1);
+        assert_eq!(summarize(&latest), CiState::Success);
+    }
+}
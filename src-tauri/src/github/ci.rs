@@ -0,0 +1,152 @@
+use super::checks::{self, BranchChecks, CiState};
+use super::{GitHubState, Repository};
+use crate::config::{storage, SettingsState};
+use crate::events::schema::v1;
+use crate::events::CI_STATUS_CHANGED_EVENT_NAME;
+use crate::i18n;
+use crate::notifications::{self, NotificationCategory};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+/// Poll interval while workflows are running
+const PENDING_INTERVAL: Duration = Duration::from_secs(20);
+
+/// Poll interval once CI has settled
+const IDLE_INTERVAL: Duration = Duration::from_secs(90);
+
+/// Background watcher of CI for the HEAD commit of the active repository
+///
+/// The frontend selects the repository with `watch_ci_status`; a
+/// `ci-status-changed` event is emitted whenever the branch, HEAD commit or
+/// combined workflow state changes.
+#[derive(Default)]
+pub struct CiWatcher {
+    repo_path: Arc<Mutex<Option<PathBuf>>>,
+    wake: Arc<Notify>,
+}
+
+impl CiWatcher {
+    /// Watch the repository at `repo_path`, or stop watching with None
+    pub fn watch(&self, repo_path: Option<PathBuf>) {
+        *self.repo_path.lock().unwrap() = repo_path;
+        self.wake.notify_one();
+    }
+
+    /// Start the polling loop
+    pub fn spawn(&self, app: AppHandle) {
+        let repo_path = self.repo_path.clone();
+        let wake = self.wake.clone();
+
+        tauri::async_runtime::spawn(async move {
+            let mut last: Option<BranchChecks> = None;
+            loop {
+                let path = repo_path.lock().unwrap().clone();
+                let mut interval = IDLE_INTERVAL;
+
+                if let Some(path) = path {
+                    match poll(&app, &path).await {
+                        Ok(Some((repository, current))) => {
+                            if current.state == CiState::Pending {
+                                interval = PENDING_INTERVAL;
+                            }
+                            if changed(last.as_ref(), &current) {
+                                let payload = v1::CiStatusChanged {
+                                    repository,
+                                    previous: last.as_ref().map(|checks| checks.state),
+                                    checks: current.clone(),
+                                };
+                                if let Err(e) = app.emit_all(CI_STATUS_CHANGED_EVENT_NAME, payload)
+                                {
+                                    tracing::error!("Failed to emit CI status: {}", e);
+                                }
+                                if newly_failed(last.as_ref(), &current) {
+                                    notify_failure(&app, &current);
+                                }
+                            }
+                            last = Some(current);
+                        }
+                        Ok(None) => last = None,
+                        Err(e) => tracing::warn!(path = ?path, "CI status poll failed: {:#}", e),
+                    }
+                } else {
+                    last = None;
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = wake.notified() => {}
+                }
+            }
+        });
+    }
+}
+
+/// Checks for the checked-out branch; None without a repository or on a detached HEAD
+async fn poll(app: &AppHandle, path: &Path) -> Result<Option<(String, BranchChecks)>> {
+    let settings =
+        storage::settings_for_project(&app.state::<SettingsState>().current(), path)?.github;
+    let Ok(repo) = Repository::from_settings(&settings) else {
+        return Ok(None);
+    };
+
+    let (branch, head_sha) = {
+        let local = crate::git::open(path)?;
+        match crate::git::current_branch(&local) {
+            Ok(branch) => (branch, crate::git::head_sha(&local)?),
+            Err(_) => return Ok(None),
+        }
+    };
+
+    let client = app
+        .state::<GitHubState>()
+        .background_client(&settings)
+        .await?;
+    let checks = checks::get_checks_for_branch(&client, &repo, &branch, Some(&head_sha)).await?;
+    Ok(Some((repo.to_string(), checks)))
+}
+
+/// A failure after a known non-failing state; CI that was already failing when
+/// watching started is not reported
+fn newly_failed(last: Option<&BranchChecks>, current: &BranchChecks) -> bool {
+    current.state == CiState::Failure && last.is_some_and(|last| last.state != CiState::Failure)
+}
+
+fn notify_failure(app: &AppHandle, checks: &BranchChecks) {
+    let failed: Vec<&str> = checks
+        .runs
+        .iter()
+        .filter(|run| run.conclusion.as_deref() == Some("failure"))
+        .map(|run| run.name.as_str())
+        .collect();
+    notifications::notify(
+        app,
+        NotificationCategory::CiFailed,
+        i18n::format(
+            "notification.ci_failed.title",
+            &[("branch", &checks.branch)],
+        ),
+        if failed.is_empty() {
+            i18n::text("notification.ci_failed.body_unknown")
+        } else {
+            i18n::format(
+                "notification.ci_failed.body",
+                &[("checks", &failed.join(", "))],
+            )
+        },
+    );
+}
+
+fn changed(last: Option<&BranchChecks>, current: &BranchChecks) -> bool {
+    match last {
+        Some(last) => {
+            last.branch != current.branch
+                || last.head_sha != current.head_sha
+                || last.state != current.state
+        }
+        None => true,
+    }
+}
@@ -0,0 +1,213 @@
+//! Read-only Discussions of the configured repository
+//!
+//! Discussions have no REST API, so they are listed over GraphQL.
+
+use super::graphql;
+use super::Repository;
+use anyhow::{Context, Result};
+use octocrab::Octocrab;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Discussions fetched per request, the most GraphQL allows
+const DISCUSSIONS_PER_PAGE: u8 = 100;
+
+const DISCUSSIONS_QUERY: &str = r#"
+query Discussions($owner: String!, $name: String!, $first: Int!, $after: String) {
+  repository(owner: $owner, name: $name) {
+    discussions(first: $first, after: $after, orderBy: { field: UPDATED_AT, direction: DESC }) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        number
+        title
+        url
+        createdAt
+        updatedAt
+        closed
+        isAnswered
+        author { login }
+        category { name emoji }
+        comments { totalCount }
+        upvoteCount
+      }
+    }
+  }
+}
+"#;
+
+/// A discussion as shown in the UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Discussion {
+    pub number: u64,
+    pub title: String,
+    pub category: String,
+    /// `:emoji:` shortcode of the category
+    pub category_emoji: String,
+    pub author: String,
+    pub comments: u32,
+    pub upvotes: u32,
+    /// Answered in a Q&A category
+    pub answered: bool,
+    pub closed: bool,
+    pub html_url: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A page of discussions, most recently updated first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscussionPage {
+    pub discussions: Vec<Discussion>,
+    /// Pass as `after` for the next page; None on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Discussions of the repository, `limit` (at most 100) per page after `after`
+pub async fn list_discussions(
+    client: &Octocrab,
+    api_url: &str,
+    repo: &Repository,
+    limit: Option<u8>,
+    after: Option<&str>,
+) -> Result<DiscussionPage> {
+    let first = limit
+        .unwrap_or(DISCUSSIONS_PER_PAGE)
+        .clamp(1, DISCUSSIONS_PER_PAGE);
+    let variables = json!({
+        "owner": repo.owner,
+        "name": repo.name,
+        "first": first,
+        "after": after,
+    });
+    let data: Option<ResponseData> = graphql::query(client, api_url, DISCUSSIONS_QUERY, variables)
+        .await
+        .context("Failed to list discussions")?;
+    let connection = data
+        .and_then(|data| data.repository)
+        .with_context(|| format!("Repository {} not found", repo))?
+        .discussions;
+    Ok(connection.into())
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseData {
+    repository: Option<RepositoryNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositoryNode {
+    discussions: DiscussionConnection,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiscussionConnection {
+    page_info: PageInfo,
+    nodes: Vec<Option<DiscussionNode>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiscussionNode {
+    number: u64,
+    title: String,
+    url: String,
+    created_at: String,
+    updated_at: String,
+    closed: bool,
+    is_answered: Option<bool>,
+    author: Option<Actor>,
+    category: CategoryNode,
+    comments: TotalCount,
+    upvote_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct Actor {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CategoryNode {
+    name: String,
+    emoji: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TotalCount {
+    total_count: u32,
+}
+
+impl From<DiscussionConnection> for DiscussionPage {
+    fn from(connection: DiscussionConnection) -> Self {
+        Self {
+            discussions: connection
+                .nodes
+                .into_iter()
+                .flatten()
+                .map(|node| Discussion {
+                    number: node.number,
+                    title: node.title,
+                    category: node.category.name,
+                    category_emoji: node.category.emoji,
+                    author: node.author.map(|author| author.login).unwrap_or_default(),
+                    comments: node.comments.total_count,
+                    upvotes: node.upvote_count,
+                    answered: node.is_answered.unwrap_or(false),
+                    closed: node.closed,
+                    html_url: node.url,
+                    created_at: node.created_at,
+                    updated_at: node.updated_at,
+                })
+                .collect(),
+            next_cursor: connection
+                .page_info
+                .end_cursor
+                .filter(|_| connection.page_info.has_next_page),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discussion_page_from_response() {
+        let data: ResponseData = serde_json::from_value(json!({
+            "repository": { "discussions": {
+                "pageInfo": { "hasNextPage": true, "endCursor": "Y3Vyc29y" },
+                "nodes": [{
+                    "number": 12,
+                    "title": "Roadmap for 2.0",
+                    "url": "https://github.com/o/r/discussions/12",
+                    "createdAt": "2024-01-01T00:00:00Z",
+                    "updatedAt": "2024-01-02T00:00:00Z",
+                    "closed": false,
+                    "isAnswered": null,
+                    "author": null,
+                    "category": { "name": "Ideas", "emoji": ":bulb:" },
+                    "comments": { "totalCount": 4 },
+                    "upvoteCount": 7
+                }, null]
+            } }
+        }))
+        .unwrap();
+
+        let page = DiscussionPage::from(data.repository.unwrap().discussions);
+        assert_eq!(page.next_cursor.as_deref(), Some("Y3Vyc29y"));
+        let [discussion] = page.discussions.try_into().unwrap();
+        assert_eq!(discussion.category, "Ideas");
+        assert_eq!((discussion.comments, discussion.upvotes), (4, 7));
+        assert!(!discussion.answered);
+        assert_eq!(discussion.author, "");
+    }
+}
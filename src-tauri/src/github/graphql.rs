@@ -0,0 +1,423 @@
+//! Single-request issue details over the GraphQL API
+//!
+//! The issue sidebar needs the issue, its comments, linked pull requests,
+//! project board status and sub-issues. Over REST that is one request per
+//! resource; here it is one GraphQL query.
+
+use super::issues::{Issue, IssueComment};
+use super::Repository;
+use anyhow::{bail, Context, Result};
+use octocrab::Octocrab;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const ISSUE_BUNDLE_QUERY: &str = r#"
+query IssueBundle($owner: String!, $name: String!, $number: Int!) {
+  repository(owner: $owner, name: $name) {
+    issue(number: $number) {
+      number
+      title
+      body
+      state
+      url
+      createdAt
+      updatedAt
+      closedAt
+      author { login }
+      assignees(first: 20) { nodes { login } }
+      labels(first: 50) { nodes { name } }
+      comments(first: 100) {
+        totalCount
+        nodes { databaseId author { login } body url createdAt }
+      }
+      timelineItems(first: 50, itemTypes: [CONNECTED_EVENT, CROSS_REFERENCED_EVENT]) {
+        nodes {
+          __typename
+          ... on ConnectedEvent { subject { ...LinkedPullRequest } }
+          ... on CrossReferencedEvent { source { ...LinkedPullRequest } }
+        }
+      }
+      projectItems(first: 10) {
+        nodes {
+          project { title url }
+          fieldValueByName(name: "Status") {
+            ... on ProjectV2ItemFieldSingleSelectValue { name }
+          }
+        }
+      }
+      parent { number title state }
+      subIssues(first: 50) { nodes { number title state } }
+    }
+  }
+}
+
+fragment LinkedPullRequest on PullRequest {
+  number
+  title
+  state
+  url
+}
+"#;
+
+/// A pull request connected to or referencing an issue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkedPullRequest {
+    pub number: u64,
+    pub title: String,
+    /// "open", "closed" or "merged"
+    pub state: String,
+    pub html_url: String,
+}
+
+/// The issue's entry on a project board
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectStatus {
+    pub project: String,
+    pub html_url: String,
+    /// Value of the project's Status field, if it has one
+    pub status: Option<String>,
+}
+
+/// A parent or sub-issue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelatedIssue {
+    pub number: u64,
+    pub title: String,
+    pub state: String,
+}
+
+/// Everything the issue sidebar shows, fetched in one request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueDetailBundle {
+    pub issue: Issue,
+    /// The first 100 comments; `issue.comments` has the total
+    pub comments: Vec<IssueComment>,
+    pub linked_pull_requests: Vec<LinkedPullRequest>,
+    pub projects: Vec<ProjectStatus>,
+    pub parent: Option<RelatedIssue>,
+    pub sub_issues: Vec<RelatedIssue>,
+}
+
+/// Fetch an issue with comments, linked pull requests, project status and sub-issues
+pub async fn get_issue_detail_bundle(
+    client: &Octocrab,
+    api_url: &str,
+    repo: &Repository,
+    number: u64,
+) -> Result<IssueDetailBundle> {
+    let variables = json!({ "owner": repo.owner, "name": repo.name, "number": number });
+    let data: Option<ResponseData> = query(client, api_url, ISSUE_BUNDLE_QUERY, variables)
+        .await
+        .with_context(|| format!("Failed to query issue #{}", number))?;
+    let Some(issue) = data
+        .and_then(|data| data.repository)
+        .and_then(|repository| repository.issue)
+    else {
+        bail!("Issue #{} not found in {}", number, repo);
+    };
+    Ok(issue.into())
+}
+
+/// Run a GraphQL query and return its `data`; GraphQL errors fail the call
+pub(super) async fn query<T: DeserializeOwned>(
+    client: &Octocrab,
+    api_url: &str,
+    query: &str,
+    variables: serde_json::Value,
+) -> Result<Option<T>> {
+    let payload = json!({ "query": query, "variables": variables });
+    let response: Response<T> = client.post(graphql_url(api_url), Some(&payload)).await?;
+    if let Some(errors) = response.errors.filter(|errors| !errors.is_empty()) {
+        let messages: Vec<String> = errors.into_iter().map(|error| error.message).collect();
+        bail!("GitHub GraphQL error: {}", messages.join("; "));
+    }
+    Ok(response.data)
+}
+
+/// GraphQL endpoint for a REST API URL
+/// github.com serves it at /graphql, GitHub Enterprise at /api/graphql next to /api/v3
+fn graphql_url(api_url: &str) -> String {
+    let api_url = api_url.trim_end_matches('/');
+    match api_url.strip_suffix("/v3") {
+        Some(base) => format!("{}/graphql", base),
+        None => format!("{}/graphql", api_url),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Response<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQlError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseData {
+    repository: Option<RepositoryNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositoryNode {
+    issue: Option<IssueNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IssueNode {
+    number: u64,
+    title: String,
+    body: String,
+    state: String,
+    url: String,
+    created_at: String,
+    updated_at: String,
+    closed_at: Option<String>,
+    author: Option<Actor>,
+    assignees: Connection<Actor>,
+    labels: Option<Connection<LabelNode>>,
+    comments: CommentConnection,
+    timeline_items: Connection<TimelineNode>,
+    project_items: Connection<ProjectItemNode>,
+    parent: Option<RelatedIssueNode>,
+    sub_issues: Option<Connection<RelatedIssueNode>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Connection<T> {
+    nodes: Vec<Option<T>>,
+}
+
+impl<T> Connection<T> {
+    fn into_nodes(self) -> impl Iterator<Item = T> {
+        self.nodes.into_iter().flatten()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CommentConnection {
+    total_count: u32,
+    nodes: Vec<Option<CommentNode>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Actor {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LabelNode {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CommentNode {
+    database_id: Option<u64>,
+    author: Option<Actor>,
+    body: String,
+    url: String,
+    created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TimelineNode {
+    /// ConnectedEvent
+    subject: Option<PullRequestNode>,
+    /// CrossReferencedEvent
+    source: Option<PullRequestNode>,
+}
+
+/// Empty (all None) when the referenced item is not a pull request
+#[derive(Debug, Deserialize)]
+struct PullRequestNode {
+    number: Option<u64>,
+    title: Option<String>,
+    state: Option<String>,
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectItemNode {
+    project: ProjectNode,
+    field_value_by_name: Option<FieldValueNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectNode {
+    title: String,
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FieldValueNode {
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RelatedIssueNode {
+    number: u64,
+    title: String,
+    state: String,
+}
+
+impl From<RelatedIssueNode> for RelatedIssue {
+    fn from(node: RelatedIssueNode) -> Self {
+        Self {
+            number: node.number,
+            title: node.title,
+            state: node.state.to_lowercase(),
+        }
+    }
+}
+
+impl From<IssueNode> for IssueDetailBundle {
+    fn from(node: IssueNode) -> Self {
+        let mut linked_pull_requests: Vec<LinkedPullRequest> = Vec::new();
+        for event in node.timeline_items.into_nodes() {
+            let Some(pr) = event.subject.or(event.source) else {
+                continue;
+            };
+            let Some(number) = pr.number else {
+                continue;
+            };
+            if linked_pull_requests
+                .iter()
+                .any(|linked| linked.number == number)
+            {
+                continue;
+            }
+            linked_pull_requests.push(LinkedPullRequest {
+                number,
+                title: pr.title.unwrap_or_default(),
+                state: pr.state.unwrap_or_default().to_lowercase(),
+                html_url: pr.url.unwrap_or_default(),
+            });
+        }
+
+        let issue = Issue {
+            number: node.number,
+            title: node.title,
+            body: node.body,
+            state: node.state.to_lowercase(),
+            labels: node
+                .labels
+                .map(|labels| labels.into_nodes().map(|label| label.name).collect())
+                .unwrap_or_default(),
+            assignees: node.assignees.into_nodes().map(|user| user.login).collect(),
+            author: node.author.map(|user| user.login).unwrap_or_default(),
+            comments: node.comments.total_count,
+            html_url: node.url,
+            created_at: node.created_at,
+            updated_at: node.updated_at,
+            closed_at: node.closed_at,
+        };
+
+        Self {
+            issue,
+            comments: node
+                .comments
+                .nodes
+                .into_iter()
+                .flatten()
+                .map(|comment| IssueComment {
+                    id: comment.database_id.unwrap_or_default(),
+                    author: comment.author.map(|user| user.login).unwrap_or_default(),
+                    body: comment.body,
+                    html_url: comment.url,
+                    created_at: comment.created_at,
+                })
+                .collect(),
+            linked_pull_requests,
+            projects: node
+                .project_items
+                .into_nodes()
+                .map(|item| ProjectStatus {
+                    project: item.project.title,
+                    html_url: item.project.url,
+                    status: item.field_value_by_name.and_then(|value| value.name),
+                })
+                .collect(),
+            parent: node.parent.map(RelatedIssue::from),
+            sub_issues: node
+                .sub_issues
+                .map(|issues| issues.into_nodes().map(RelatedIssue::from).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graphql_url() {
+        assert_eq!(
+            graphql_url("https://api.github.com"),
+            "https://api.github.com/graphql"
+        );
+        assert_eq!(
+            graphql_url("https://github.example.com/api/v3/"),
+            "https://github.example.com/api/graphql"
+        );
+    }
+
+    #[test]
+    fn test_bundle_from_response() {
+        let response: Response<ResponseData> = serde_json::from_value(json!({
+            "data": { "repository": { "issue": {
+                "number": 5,
+                "title": "Sidebar is slow",
+                "body": "",
+                "state": "OPEN",
+                "url": "https://github.com/o/r/issues/5",
+                "createdAt": "2024-01-01T00:00:00Z",
+                "updatedAt": "2024-01-02T00:00:00Z",
+                "closedAt": null,
+                "author": { "login": "alice" },
+                "assignees": { "nodes": [{ "login": "bob" }] },
+                "labels": { "nodes": [{ "name": "perf" }] },
+                "comments": {
+                    "totalCount": 1,
+                    "nodes": [{
+                        "databaseId": 9,
+                        "author": null,
+                        "body": "+1",
+                        "url": "https://github.com/o/r/issues/5#issuecomment-9",
+                        "createdAt": "2024-01-01T01:00:00Z"
+                    }]
+                },
+                "timelineItems": { "nodes": [
+                    { "__typename": "CrossReferencedEvent", "source": { "number": 7, "title": "Batch queries", "state": "MERGED", "url": "u" } },
+                    { "__typename": "CrossReferencedEvent", "source": {} },
+                    { "__typename": "ConnectedEvent", "subject": { "number": 7, "title": "Batch queries", "state": "MERGED", "url": "u" } }
+                ] },
+                "projectItems": { "nodes": [{
+                    "project": { "title": "Roadmap", "url": "p" },
+                    "fieldValueByName": { "name": "In progress" }
+                }] },
+                "parent": null,
+                "subIssues": { "nodes": [{ "number": 6, "title": "Cache", "state": "CLOSED" }] }
+            } } }
+        }))
+        .unwrap();
+
+        let bundle =
+            IssueDetailBundle::from(response.data.unwrap().repository.unwrap().issue.unwrap());
+        assert_eq!(bundle.issue.state, "open");
+        assert_eq!(bundle.issue.assignees, vec!["bob"]);
+        assert_eq!(bundle.comments[0].id, 9);
+        assert_eq!(bundle.comments[0].author, "");
+        assert_eq!(bundle.linked_pull_requests.len(), 1);
+        assert_eq!(bundle.linked_pull_requests[0].state, "merged");
+        assert_eq!(bundle.projects[0].status.as_deref(), Some("In progress"));
+        assert_eq!(bundle.sub_issues[0].state, "closed");
+    }
+}
@@ -0,0 +1,268 @@
+use super::Repository;
+use anyhow::{Context, Result};
+use octocrab::models::issues::{Comment, Issue as GitHubIssue};
+use octocrab::models::{CommentId, IssueState};
+use octocrab::{params, Octocrab};
+use serde::{Deserialize, Serialize};
+
+/// Largest page GitHub returns
+const MAX_PER_PAGE: u8 = 100;
+
+/// Which issues list_issues returns; every field is optional
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IssueFilters {
+    /// "open" (default), "closed" or "all"
+    pub state: Option<String>,
+    pub labels: Vec<String>,
+    /// Login, "none" or "*"
+    pub assignee: Option<String>,
+    pub per_page: Option<u8>,
+    pub page: Option<u32>,
+}
+
+/// An issue as shown in the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Issue {
+    pub number: u64,
+    pub title: String,
+    pub body: String,
+    /// "open" or "closed"
+    pub state: String,
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    pub author: String,
+    pub comments: u32,
+    pub html_url: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub closed_at: Option<String>,
+}
+
+impl From<GitHubIssue> for Issue {
+    fn from(issue: GitHubIssue) -> Self {
+        Self {
+            number: issue.number,
+            title: issue.title,
+            body: issue.body.unwrap_or_default(),
+            state: match issue.state {
+                IssueState::Closed => "closed",
+                _ => "open",
+            }
+            .to_string(),
+            labels: issue.labels.into_iter().map(|label| label.name).collect(),
+            assignees: issue.assignees.into_iter().map(|user| user.login).collect(),
+            author: issue.user.login,
+            comments: issue.comments,
+            html_url: issue.html_url.to_string(),
+            created_at: issue.created_at.to_rfc3339(),
+            updated_at: issue.updated_at.to_rfc3339(),
+            closed_at: issue.closed_at.map(|time| time.to_rfc3339()),
+        }
+    }
+}
+
+/// A comment on an issue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueComment {
+    pub id: u64,
+    pub author: String,
+    pub body: String,
+    pub html_url: String,
+    pub created_at: String,
+}
+
+impl From<Comment> for IssueComment {
+    fn from(comment: Comment) -> Self {
+        Self {
+            id: comment.id.into_inner(),
+            author: comment.user.login,
+            body: comment.body.unwrap_or_default(),
+            html_url: comment.html_url.to_string(),
+            created_at: comment.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// An issue together with its comments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueDetail {
+    pub issue: Issue,
+    pub comments: Vec<IssueComment>,
+}
+
+/// Issues matching `filters`; pull requests (which the API also returns) are left out
+pub async fn list_issues(
+    client: &Octocrab,
+    repo: &Repository,
+    filters: &IssueFilters,
+) -> Result<Vec<Issue>> {
+    let issues = client.issues(&repo.owner, &repo.name);
+    let mut request = issues
+        .list()
+        .state(parse_state(filters.state.as_deref())?)
+        .per_page(filters.per_page.unwrap_or(30).min(MAX_PER_PAGE));
+    if !filters.labels.is_empty() {
+        request = request.labels(&filters.labels);
+    }
+    if let Some(assignee) = filters.assignee.as_deref() {
+        request = request.assignee(assignee);
+    }
+    if let Some(page) = filters.page {
+        request = request.page(page);
+    }
+
+    let page = request.send().await.context("Failed to list issues")?;
+    Ok(page
+        .items
+        .into_iter()
+        .filter(|issue| issue.pull_request.is_none())
+        .map(Issue::from)
+        .collect())
+}
+
+/// One issue with all of its comments
+pub async fn get_issue(client: &Octocrab, repo: &Repository, number: u64) -> Result<IssueDetail> {
+    let issue = get_issue_summary(client, repo, number).await?;
+
+    let first = client
+        .issues(&repo.owner, &repo.name)
+        .list_comments(number)
+        .per_page(MAX_PER_PAGE)
+        .send()
+        .await
+        .with_context(|| format!("Failed to get comments on #{}", number))?;
+    let comments = client
+        .all_pages(first)
+        .await
+        .with_context(|| format!("Failed to get comments on #{}", number))?;
+
+    Ok(IssueDetail {
+        issue,
+        comments: comments.into_iter().map(IssueComment::from).collect(),
+    })
+}
+
+/// An issue without its comments
+pub async fn get_issue_summary(client: &Octocrab, repo: &Repository, number: u64) -> Result<Issue> {
+    let issue = client
+        .issues(&repo.owner, &repo.name)
+        .get(number)
+        .await
+        .with_context(|| format!("Failed to get issue #{}", number))?;
+    Ok(issue.into())
+}
+
+pub async fn create_issue(
+    client: &Octocrab,
+    repo: &Repository,
+    title: &str,
+    body: &str,
+    labels: Vec<String>,
+) -> Result<Issue> {
+    let issue = client
+        .issues(&repo.owner, &repo.name)
+        .create(title)
+        .body(body)
+        .labels(labels)
+        .send()
+        .await
+        .context("Failed to create issue")?;
+    Ok(issue.into())
+}
+
+pub async fn comment_on_issue(
+    client: &Octocrab,
+    repo: &Repository,
+    number: u64,
+    body: &str,
+) -> Result<IssueComment> {
+    let comment = client
+        .issues(&repo.owner, &repo.name)
+        .create_comment(number, body)
+        .await
+        .with_context(|| format!("Failed to comment on #{}", number))?;
+    Ok(comment.into())
+}
+
+/// Replace the body of an issue
+pub async fn update_issue_body(
+    client: &Octocrab,
+    repo: &Repository,
+    number: u64,
+    body: &str,
+) -> Result<Issue> {
+    let issue = client
+        .issues(&repo.owner, &repo.name)
+        .update(number)
+        .body(body)
+        .send()
+        .await
+        .with_context(|| format!("Failed to update #{}", number))?;
+    Ok(issue.into())
+}
+
+/// Replace the body of an issue comment; None when the comment no longer exists
+pub async fn update_comment(
+    client: &Octocrab,
+    repo: &Repository,
+    comment_id: u64,
+    body: &str,
+) -> Result<Option<IssueComment>> {
+    let result = client
+        .issues(&repo.owner, &repo.name)
+        .update_comment(CommentId(comment_id), body)
+        .await;
+    match result {
+        Ok(comment) => Ok(Some(comment.into())),
+        Err(octocrab::Error::GitHub { source, .. }) if source.status_code.as_u16() == 404 => {
+            Ok(None)
+        }
+        Err(e) => Err(e).with_context(|| format!("Failed to update comment {}", comment_id)),
+    }
+}
+
+/// Close an issue, optionally leaving a closing comment first
+pub async fn close_issue(
+    client: &Octocrab,
+    repo: &Repository,
+    number: u64,
+    comment: Option<&str>,
+) -> Result<Issue> {
+    if let Some(comment) = comment.filter(|comment| !comment.trim().is_empty()) {
+        comment_on_issue(client, repo, number, comment).await?;
+    }
+
+    let issue = client
+        .issues(&repo.owner, &repo.name)
+        .update(number)
+        .state(IssueState::Closed)
+        .send()
+        .await
+        .with_context(|| format!("Failed to close #{}", number))?;
+    Ok(issue.into())
+}
+
+fn parse_state(state: Option<&str>) -> Result<params::State> {
+    match state.unwrap_or("open") {
+        "open" => Ok(params::State::Open),
+        "closed" => Ok(params::State::Closed),
+        "all" => Ok(params::State::All),
+        other => anyhow::bail!("Invalid issue state '{}': use open, closed or all", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_state() {
+        assert!(matches!(parse_state(None).unwrap(), params::State::Open));
+        assert!(matches!(
+            parse_state(Some("all")).unwrap(),
+            params::State::All
+        ));
+        assert!(parse_state(Some("merged")).is_err());
+    }
+}
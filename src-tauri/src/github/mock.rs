@@ -0,0 +1,308 @@
+//! In-process mock of the GitHub API for offline development and E2E tests
+//!
+//! Started with `--mock-github[=<dir>]` or ZEAMI_MOCK_GITHUB=<dir|1>, it serves
+//! canned responses from a fixtures directory (tests/fixtures/github by
+//! default) on a local port, and GitHubState sends every request there with a
+//! placeholder token. `routes.json` in the directory maps requests to fixture
+//! files; the first matching route wins and unmatched requests get a 404.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Command-line flag enabling the mock, optionally followed by `=<dir>`
+pub const MOCK_FLAG: &str = "--mock-github";
+
+/// Environment variable enabling the mock; a directory, or `1` for the default
+pub const MOCK_ENV: &str = "ZEAMI_MOCK_GITHUB";
+
+/// Token the clients authenticate to the mock with
+pub const MOCK_TOKEN: &str = "mock-token";
+
+/// Largest request body read, far above any request the app sends
+const MAX_BODY: usize = 1 << 20;
+
+/// A request the mock answers
+#[derive(Debug, Clone, Deserialize)]
+struct Route {
+    method: String,
+    /// Path without the query string; `*` matches one segment
+    path: String,
+    /// GraphQL operation name the query must declare
+    #[serde(default)]
+    operation: Option<String>,
+    /// Text the request body must contain
+    #[serde(default)]
+    contains: Option<String>,
+    #[serde(default = "default_status")]
+    status: u16,
+    /// JSON file relative to the fixtures directory; an empty body when None
+    #[serde(default)]
+    fixture: Option<String>,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+impl Route {
+    fn matches(&self, method: &str, path: &str, body: &str) -> bool {
+        if !self.method.eq_ignore_ascii_case(method) || !path_matches(&self.path, path) {
+            return false;
+        }
+        if let Some(text) = &self.contains {
+            if !body.contains(text.as_str()) {
+                return false;
+            }
+        }
+        match &self.operation {
+            Some(operation) => graphql_operation(body).as_deref() == Some(operation.as_str()),
+            None => true,
+        }
+    }
+}
+
+/// The fixtures directory requested on the command line or in the environment
+pub fn requested(
+    args: impl IntoIterator<Item = String>,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Option<PathBuf> {
+    let from_args = args.into_iter().find_map(|arg| {
+        if arg == MOCK_FLAG {
+            Some(None)
+        } else {
+            arg.strip_prefix(MOCK_FLAG)
+                .and_then(|rest| rest.strip_prefix('='))
+                .map(|dir| Some(PathBuf::from(dir)))
+        }
+    });
+    let from_env = || {
+        vars.into_iter()
+            .find(|(name, _)| name == MOCK_ENV)
+            .and_then(|(_, value)| match value.trim() {
+                "" | "0" | "false" => None,
+                "1" | "true" => Some(None),
+                dir => Some(Some(PathBuf::from(dir))),
+            })
+    };
+    from_args
+        .or_else(from_env)
+        .map(|dir| dir.unwrap_or_else(default_fixtures))
+}
+
+/// tests/fixtures/github of the source tree the app was built from
+pub fn default_fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/fixtures/github")
+}
+
+/// Serve the fixtures in `dir` on a local port; returns the API URL
+/// Must be called within a Tokio runtime, which keeps serving until it stops
+pub async fn start(dir: &Path) -> Result<String> {
+    let routes_file = dir.join("routes.json");
+    let routes: Vec<Route> = serde_json::from_str(
+        &std::fs::read_to_string(&routes_file)
+            .with_context(|| format!("Failed to read {}", routes_file.display()))?,
+    )
+    .with_context(|| format!("Invalid {}", routes_file.display()))?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .context("Failed to bind the mock GitHub server")?;
+    let url = format!("http://{}", listener.local_addr()?);
+    let fixtures = Arc::new((dir.to_path_buf(), routes));
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("Mock GitHub server stopped accepting: {}", e);
+                    return;
+                }
+            };
+            let fixtures = fixtures.clone();
+            tokio::spawn(async move {
+                let (dir, routes) = fixtures.as_ref();
+                if let Err(e) = handle(stream, dir, routes).await {
+                    tracing::debug!("Mock GitHub request failed: {:#}", e);
+                }
+            });
+        }
+    });
+    tracing::info!(url, fixtures = %dir.display(), "Mock GitHub server started");
+    Ok(url)
+}
+
+async fn handle(stream: TcpStream, dir: &Path, routes: &[Route]) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(());
+    };
+    let (method, path) = (
+        method.to_string(),
+        target.split('?').next().unwrap_or_default().to_string(),
+    );
+
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0; length.min(MAX_BODY)];
+    reader.read_exact(&mut body).await?;
+    let body = String::from_utf8_lossy(&body);
+
+    let (status, payload) = match routes
+        .iter()
+        .find(|route| route.matches(&method, &path, &body))
+    {
+        Some(route) => {
+            let payload = match &route.fixture {
+                Some(fixture) => std::fs::read_to_string(dir.join(fixture))
+                    .with_context(|| format!("Missing fixture {}", fixture))?,
+                None => String::new(),
+            };
+            (route.status, payload)
+        }
+        None => {
+            tracing::warn!(method, path, "No mock GitHub route; answering 404");
+            (
+                404,
+                r#"{"message":"Not Found","documentation_url":"https://docs.github.com/rest"}"#
+                    .to_string(),
+            )
+        }
+    };
+
+    let response = format!(
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: application/json; charset=utf-8\r\n\
+         Content-Length: {}\r\n\
+         X-RateLimit-Limit: 5000\r\n\
+         X-RateLimit-Remaining: 5000\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        payload.len(),
+        payload
+    );
+    let mut stream = reader.into_inner();
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.trim_matches('/').split('/').collect();
+    let path: Vec<&str> = path.trim_matches('/').split('/').collect();
+    pattern.len() == path.len()
+        && pattern
+            .iter()
+            .zip(&path)
+            .all(|(expected, actual)| *expected == "*" || expected == actual)
+}
+
+/// Name of the operation in a GraphQL request body, e.g. `IssueBundle`
+fn graphql_operation(body: &str) -> Option<String> {
+    let query = serde_json::from_str::<serde_json::Value>(body).ok()?;
+    let query = query.get("query")?.as_str()?;
+    let mut words = query.split(|c: char| !c.is_alphanumeric() && c != '_');
+    words.find(|word| *word == "query" || *word == "mutation")?;
+    words
+        .find(|word| !word.is_empty())
+        .map(|name| name.to_string())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        304 => "Not Modified",
+        404 => "Not Found",
+        422 => "Unprocessable Entity",
+        _ => "Status",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::{graphql, issues, pulls, Repository};
+
+    #[test]
+    fn test_requested_by_flag_or_environment() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let env = |value: &str| vec![(MOCK_ENV.to_string(), value.to_string())];
+
+        assert_eq!(requested(args(&["zeami4"]), Vec::new()), None);
+        assert_eq!(
+            requested(args(&["zeami4", MOCK_FLAG]), Vec::new()),
+            Some(default_fixtures())
+        );
+        assert_eq!(
+            requested(args(&["zeami4", "--mock-github=/tmp/f"]), env("0")),
+            Some(PathBuf::from("/tmp/f"))
+        );
+        assert_eq!(requested(args(&[]), env("1")), Some(default_fixtures()));
+        assert_eq!(requested(args(&[]), env("false")), None);
+    }
+
+    #[test]
+    fn test_route_matching() {
+        assert!(path_matches("/repos/*/*/issues", "/repos/acme/demo/issues"));
+        assert!(!path_matches(
+            "/repos/*/*/issues",
+            "/repos/acme/demo/issues/1"
+        ));
+        assert_eq!(
+            graphql_operation(r#"{"query":"\nquery IssueBundle($owner: String!) {}"}"#).as_deref(),
+            Some("IssueBundle")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_issue_workflow_against_fixtures() {
+        let url = start(&default_fixtures()).await.unwrap();
+        let client = super::super::build_client(&url, Some(MOCK_TOKEN.to_string())).unwrap();
+        let repo = Repository::parse("acme/demo").unwrap();
+
+        let listed = issues::list_issues(&client, &repo, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 2);
+        let detail = issues::get_issue(&client, &repo, 42).await.unwrap();
+        assert_eq!(detail.comments.len(), 1);
+        let bundle = graphql::get_issue_detail_bundle(&client, &url, &repo, 42)
+            .await
+            .unwrap();
+        assert_eq!(bundle.issue.labels, ["bug"]);
+        issues::comment_on_issue(&client, &repo, 42, "Working on this.")
+            .await
+            .unwrap();
+        assert!(pulls::list_pull_requests(&client, &repo, None, None)
+            .await
+            .unwrap()
+            .is_empty());
+        let closed = issues::close_issue(&client, &repo, 42, Some("Fixed in #44"))
+            .await
+            .unwrap();
+        assert_eq!(closed.state, "closed");
+
+        let other = Repository::parse("a/b").unwrap();
+        assert!(issues::get_issue_summary(&client, &other, 1).await.is_ok());
+        assert!(client.repos("acme", "demo").get().await.is_err());
+    }
+}
@@ -0,0 +1,361 @@
+pub mod activity;
+pub mod cache;
+pub mod checks;
+pub mod ci;
+pub mod discussions;
+pub mod graphql;
+pub mod issues;
+pub mod mock;
+pub mod projects;
+pub mod pulls;
+pub mod queue;
+pub mod releases;
+pub mod replay;
+pub mod scheduler;
+pub mod sync;
+pub mod templates;
+
+use crate::config::keychain;
+use crate::config::GitHubSettings;
+use anyhow::{bail, Context, Result};
+use octocrab::Octocrab;
+use scheduler::{Admission, Priority, RateLimitStatus, RequestScheduler};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A repository in `owner/name` form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repository {
+    pub owner: String,
+    pub name: String,
+}
+
+impl Repository {
+    pub fn parse(repository: &str) -> Result<Self> {
+        match repository.trim().split_once('/') {
+            Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+                Ok(Self {
+                    owner: owner.to_string(),
+                    name: name.trim_end_matches(".git").to_string(),
+                })
+            }
+            _ => bail!("Invalid repository '{}': expected owner/name", repository),
+        }
+    }
+
+    /// The repository configured in GitHubSettings
+    pub fn from_settings(settings: &GitHubSettings) -> Result<Self> {
+        if settings.repository.trim().is_empty() {
+            bail!("No GitHub repository configured");
+        }
+        Self::parse(&settings.repository)
+    }
+}
+
+impl std::fmt::Display for Repository {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.owner, self.name)
+    }
+}
+
+/// Core API rate limit as last reported by GitHub
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitInfo {
+    pub limit: usize,
+    pub remaining: usize,
+    pub used: usize,
+    /// Unix timestamp (seconds) when the limit resets
+    pub reset: u64,
+}
+
+impl RateLimitInfo {
+    /// True while the limit is used up and has not reset yet
+    pub fn exhausted(&self, now: u64) -> bool {
+        self.remaining == 0 && now < self.reset
+    }
+}
+
+impl From<octocrab::models::Rate> for RateLimitInfo {
+    fn from(rate: octocrab::models::Rate) -> Self {
+        Self {
+            limit: rate.limit,
+            remaining: rate.remaining,
+            used: rate.used,
+            reset: rate.reset,
+        }
+    }
+}
+
+/// Reported by github_api_status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHubApiStatus {
+    /// Named account in use; None for the default account
+    pub account: Option<String>,
+    pub api_url: String,
+    pub authenticated: bool,
+    /// Login of the token's user; None without a token
+    pub user: Option<String>,
+    pub rate_limit: RateLimitInfo,
+}
+
+struct CachedClient {
+    api_url: String,
+    authenticated: bool,
+    client: Arc<Octocrab>,
+}
+
+/// Shared GitHub clients managed by Tauri, one per account
+///
+/// Built on first use from the account's keychain token and API URL, and
+/// rebuilt when the API URL changes or after `invalidate` (token or profile
+/// changes). Every client is admitted by the account's RequestScheduler:
+/// interactive requests fail fast while the rate limit is exhausted, background
+/// ones wait while it is low.
+#[derive(Default)]
+pub struct GitHubState {
+    clients: Mutex<HashMap<String, CachedClient>>,
+    scheduler: RequestScheduler,
+    /// API URL of the mock server every account is sent to, see mock
+    mock_url: Option<String>,
+}
+
+impl GitHubState {
+    /// State whose clients all talk to the mock server at `url`
+    pub fn mock(url: String) -> Self {
+        Self {
+            mock_url: Some(url),
+            ..Self::default()
+        }
+    }
+
+    /// API URL requests for the account selected in `settings` go to
+    pub fn api_url(&self, settings: &GitHubSettings) -> Result<String> {
+        match &self.mock_url {
+            Some(url) => Ok(url.clone()),
+            None => api_url(settings),
+        }
+    }
+
+    /// Authenticated client for the account selected in `settings`, for a
+    /// request the user is waiting on; unauthenticated when no token is stored
+    pub fn client(&self, settings: &GitHubSettings) -> Result<Arc<Octocrab>> {
+        match self
+            .scheduler
+            .admit(&cache_key(settings), Priority::Interactive, unix_now())
+        {
+            Admission::Refuse(reset_in) => Err(RateLimitExceeded { reset_in }.into()),
+            Admission::Go | Admission::Wait(_) => Ok(self.cached(settings)?.0),
+        }
+    }
+
+    /// Client for background work such as sync and polling; waits while the
+    /// remaining budget is within the reserve kept for interactive requests
+    pub async fn background_client(&self, settings: &GitHubSettings) -> Result<Arc<Octocrab>> {
+        let key = cache_key(settings);
+        let (client, _) = self.cached(settings)?;
+        let _queued = self.scheduler.enqueue();
+        loop {
+            if self.scheduler.stale(&key, unix_now()) {
+                if let Err(e) = self.refresh_rate_limit(&client, &key).await {
+                    tracing::debug!("Failed to refresh GitHub rate limit: {:#}", e);
+                }
+            }
+            match self.scheduler.admit(&key, Priority::Background, unix_now()) {
+                Admission::Go => return Ok(client),
+                Admission::Wait(seconds) | Admission::Refuse(seconds) => {
+                    tracing::debug!(seconds, "GitHub budget low; delaying background request");
+                    tokio::time::sleep(std::time::Duration::from_secs(seconds)).await;
+                }
+            }
+        }
+    }
+
+    /// The account's budget, refreshed first when it is stale
+    pub async fn rate_limit_status(&self, settings: &GitHubSettings) -> Result<RateLimitStatus> {
+        let key = cache_key(settings);
+        if self.scheduler.stale(&key, unix_now()) {
+            let (client, _) = self.cached(settings)?;
+            self.refresh_rate_limit(&client, &key).await?;
+        }
+        Ok(self
+            .scheduler
+            .status(&key, settings.account.clone(), unix_now()))
+    }
+
+    /// Drop the cached clients so the next request picks up new tokens
+    pub fn invalidate(&self) {
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.clear();
+        }
+        self.scheduler.clear();
+    }
+
+    /// Query the authenticated user and rate limit
+    /// Unlike `client`, this works while rate limited (the rate limit endpoint is free)
+    pub async fn status(&self, settings: &GitHubSettings) -> Result<GitHubApiStatus> {
+        let (client, authenticated) = self.cached(settings)?;
+        let rate = client
+            .ratelimit()
+            .get()
+            .await
+            .context("Failed to query GitHub rate limit")?;
+        let rate_limit = RateLimitInfo::from(rate.resources.core);
+        self.scheduler
+            .record(&cache_key(settings), rate_limit, unix_now());
+
+        let user = if authenticated {
+            let user = client
+                .current()
+                .user()
+                .await
+                .context("GitHub token is invalid")?;
+            Some(user.login)
+        } else {
+            None
+        };
+
+        Ok(GitHubApiStatus {
+            account: settings.account.clone(),
+            api_url: self.api_url(settings)?,
+            authenticated,
+            user,
+            rate_limit,
+        })
+    }
+
+    /// Ask GitHub for the account's rate limit; the endpoint is free
+    async fn refresh_rate_limit(&self, client: &Octocrab, key: &str) -> Result<()> {
+        let rate = client
+            .ratelimit()
+            .get()
+            .await
+            .context("Failed to query GitHub rate limit")?;
+        self.scheduler
+            .record(key, RateLimitInfo::from(rate.resources.core), unix_now());
+        Ok(())
+    }
+
+    fn cached(&self, settings: &GitHubSettings) -> Result<(Arc<Octocrab>, bool)> {
+        let key = cache_key(settings);
+        let api_url = self.api_url(settings)?;
+        let mut clients = self
+            .clients
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock GitHub client: {}", e))?;
+
+        if let Some(existing) = clients.get(&key) {
+            if existing.api_url == api_url {
+                return Ok((existing.client.clone(), existing.authenticated));
+            }
+        }
+
+        let token = match self.mock_url {
+            Some(_) => Some(mock::MOCK_TOKEN.to_string()),
+            None => keychain::github_token(settings)?,
+        };
+        let authenticated = token.is_some();
+        let client = Arc::new(build_client(&api_url, token)?);
+        clients.insert(
+            key,
+            CachedClient {
+                api_url,
+                authenticated,
+                client: client.clone(),
+            },
+        );
+        Ok((client, authenticated))
+    }
+}
+
+/// Returned by `GitHubState::client` while the account's rate limit is exhausted
+#[derive(Debug, thiserror::Error)]
+#[error("GitHub API rate limit exceeded; resets in {reset_in} seconds")]
+pub struct RateLimitExceeded {
+    pub reset_in: u64,
+}
+
+/// API URL of the account selected in `settings`
+pub fn api_url(settings: &GitHubSettings) -> Result<String> {
+    Ok(match settings.selected_account()? {
+        Some(account) => account.api_url.clone(),
+        None => settings.api_url.clone(),
+    })
+}
+
+fn unix_now() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+/// Clients and rate limits are tracked per account; the default account has an empty key
+fn cache_key(settings: &GitHubSettings) -> String {
+    settings.account.clone().unwrap_or_default()
+}
+
+fn build_client(api_url: &str, token: Option<String>) -> Result<Octocrab> {
+    let mut builder = Octocrab::builder()
+        .base_uri(api_url)
+        .with_context(|| format!("Invalid GitHub API URL: {}", api_url))?;
+    if let Some(token) = token {
+        builder = builder.personal_token(token);
+    }
+    builder.build().context("Failed to build GitHub client")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repository() {
+        assert_eq!(
+            Repository::parse("owner/repo.git").unwrap(),
+            Repository {
+                owner: "owner".to_string(),
+                name: "repo".to_string(),
+            }
+        );
+        assert!(Repository::parse("repo").is_err());
+        assert!(Repository::parse("a/b/c").is_err());
+        assert!(Repository::from_settings(&GitHubSettings::default()).is_err());
+    }
+
+    #[test]
+    fn test_api_url_follows_selected_account() {
+        let mut settings = GitHubSettings {
+            accounts: vec![crate::config::GitHubAccount {
+                name: "work".to_string(),
+                api_url: "https://github.example.com/api/v3".to_string(),
+            }],
+            ..GitHubSettings::default()
+        };
+        assert_eq!(api_url(&settings).unwrap(), "https://api.github.com");
+
+        settings.account = Some("work".to_string());
+        assert_eq!(
+            api_url(&settings).unwrap(),
+            "https://github.example.com/api/v3"
+        );
+
+        settings.account = Some("missing".to_string());
+        assert!(api_url(&settings).is_err());
+    }
+
+    #[test]
+    fn test_rate_limit_exhausted_until_reset() {
+        let rate = RateLimitInfo {
+            limit: 5000,
+            remaining: 0,
+            used: 5000,
+            reset: 1_000,
+        };
+
+        assert!(rate.exhausted(999));
+        assert!(!rate.exhausted(1_000));
+        assert!(!RateLimitInfo {
+            remaining: 1,
+            ..rate
+        }
+        .exhausted(999));
+    }
+}
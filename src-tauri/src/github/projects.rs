@@ -0,0 +1,599 @@
+//! Issue triage board backed by a GitHub Projects v2 board
+//!
+//! Columns are the options of the board's Status field, so moving a card in
+//! the kanban view sets that field on the issue's project item. Projects v2
+//! has no REST API; everything goes over GraphQL.
+
+use super::graphql;
+use super::Repository;
+use anyhow::{bail, Context, Result};
+use octocrab::Octocrab;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Single-select field whose options are the board's columns
+const STATUS_FIELD: &str = "Status";
+
+/// Items fetched per request, the most GraphQL allows
+const ITEMS_PER_PAGE: u8 = 100;
+
+/// Pages of items read before the board is reported as truncated
+const MAX_ITEM_PAGES: usize = 10;
+
+/// Project items of an issue searched for the board's item
+const ISSUE_PROJECT_ITEMS: u8 = 50;
+
+const BOARD_FRAGMENT: &str = r#"
+fragment Board on ProjectV2 {
+  id
+  number
+  title
+  url
+  field(name: "Status") {
+    ... on ProjectV2SingleSelectField { id options { id name } }
+  }
+  items(first: $first, after: $after) {
+    pageInfo { hasNextPage endCursor }
+    nodes {
+      id
+      fieldValueByName(name: "Status") {
+        ... on ProjectV2ItemFieldSingleSelectValue { optionId }
+      }
+      content {
+        ... on Issue { number title state url repository { nameWithOwner } }
+      }
+    }
+  }
+}
+"#;
+
+const OWNER_BOARD_QUERY: &str = r#"
+query OwnerBoard($owner: String!, $number: Int!, $first: Int!, $after: String) {
+  repositoryOwner(login: $owner) {
+    ... on ProjectV2Owner { projectV2(number: $number) { ...Board } }
+  }
+}
+"#;
+
+const LINKED_BOARD_QUERY: &str = r#"
+query LinkedBoard($owner: String!, $name: String!, $first: Int!, $after: String) {
+  repository(owner: $owner, name: $name) {
+    projectsV2(first: 1, orderBy: { field: UPDATED_AT, direction: DESC }) {
+      nodes { ...Board }
+    }
+  }
+}
+"#;
+
+const ISSUE_QUERY: &str = r#"
+query IssueItems($owner: String!, $name: String!, $number: Int!, $first: Int!) {
+  repository(owner: $owner, name: $name) {
+    issue(number: $number) {
+      id
+      number
+      title
+      state
+      url
+      projectItems(first: $first) { nodes { id project { id } } }
+    }
+  }
+}
+"#;
+
+const ADD_ITEM_MUTATION: &str = r#"
+mutation AddItem($project: ID!, $content: ID!) {
+  addProjectV2ItemById(input: { projectId: $project, contentId: $content }) {
+    item { id }
+  }
+}
+"#;
+
+const SET_STATUS_MUTATION: &str = r#"
+mutation SetStatus($project: ID!, $item: ID!, $field: ID!, $option: String!) {
+  updateProjectV2ItemFieldValue(input: {
+    projectId: $project, itemId: $item, fieldId: $field,
+    value: { singleSelectOptionId: $option }
+  }) {
+    projectV2Item { id }
+  }
+}
+"#;
+
+/// A Projects v2 board with the repository's issues on it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectBoard {
+    /// GraphQL node id of the project
+    pub id: String,
+    pub number: u64,
+    pub title: String,
+    pub html_url: String,
+    /// Node id of the Status field
+    pub status_field: String,
+    /// Options of the Status field, in board order
+    pub columns: Vec<BoardColumn>,
+    pub items: Vec<BoardItem>,
+    /// The board had more items than were read
+    pub truncated: bool,
+}
+
+/// A column of the board: one option of the Status field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardColumn {
+    /// Option id, what move_issue_to_column sets
+    pub id: String,
+    pub name: String,
+}
+
+/// An issue card on the board
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardItem {
+    /// Node id of the project item
+    pub item_id: String,
+    pub issue: u64,
+    pub title: String,
+    /// `OPEN` or `CLOSED`
+    pub state: String,
+    pub html_url: String,
+    /// Option id of the item's column; None when its status is unset
+    pub column: Option<String>,
+}
+
+/// The board `project` of the repository owner, or the project most recently
+/// linked to the repository when None, with the repository's issues on it
+pub async fn get_project_board(
+    client: &Octocrab,
+    api_url: &str,
+    repo: &Repository,
+    project: Option<u64>,
+) -> Result<ProjectBoard> {
+    let mut board: Option<ProjectBoard> = None;
+    let mut after: Option<String> = None;
+    for _ in 0..MAX_ITEM_PAGES {
+        let node = fetch_board(
+            client,
+            api_url,
+            repo,
+            project,
+            ITEMS_PER_PAGE,
+            after.as_deref(),
+        )
+        .await
+        .context("Failed to read the project board")?;
+        let page_info = node.items.page_info.clone();
+        match &mut board {
+            Some(board) => board.items.extend(node.into_board(repo)?.items),
+            None => board = Some(node.into_board(repo)?),
+        }
+        after = page_info.end_cursor.filter(|_| page_info.has_next_page);
+        if after.is_none() {
+            break;
+        }
+    }
+    let mut board = board.context("Project board not found")?;
+    board.truncated = after.is_some();
+    Ok(board)
+}
+
+/// Set the Status of `issue` on the board to `column`, an option id or name,
+/// adding the issue to the board when it isn't on it yet
+pub async fn move_issue_to_column(
+    client: &Octocrab,
+    api_url: &str,
+    repo: &Repository,
+    project: Option<u64>,
+    issue: u64,
+    column: &str,
+) -> Result<BoardItem> {
+    let board = fetch_board(client, api_url, repo, project, 1, None)
+        .await
+        .context("Failed to read the project board")?
+        .into_board(repo)?;
+    let option = board
+        .column(column)
+        .with_context(|| format!("No column '{}' on project '{}'", column, board.title))?
+        .id
+        .clone();
+
+    let variables = json!({
+        "owner": repo.owner,
+        "name": repo.name,
+        "number": issue,
+        "first": ISSUE_PROJECT_ITEMS,
+    });
+    let data: Option<IssueData> = graphql::query(client, api_url, ISSUE_QUERY, variables)
+        .await
+        .with_context(|| format!("Failed to read issue #{}", issue))?;
+    let node = data
+        .and_then(|data| data.repository)
+        .and_then(|repository| repository.issue)
+        .with_context(|| format!("Issue #{} not found in {}", issue, repo))?;
+
+    let existing = node
+        .project_items
+        .nodes
+        .iter()
+        .flatten()
+        .find(|item| item.project.id == board.id)
+        .map(|item| item.id.clone());
+    let item_id = match existing {
+        Some(id) => id,
+        None => {
+            let variables = json!({ "project": board.id, "content": node.id });
+            let data: Option<AddItemData> =
+                graphql::query(client, api_url, ADD_ITEM_MUTATION, variables)
+                    .await
+                    .with_context(|| format!("Failed to add issue #{} to the board", issue))?;
+            data.and_then(|data| data.add_project_v2_item_by_id.item)
+                .context("GitHub returned no project item")?
+                .id
+        }
+    };
+
+    let variables = json!({
+        "project": board.id,
+        "item": item_id,
+        "field": board.status_field,
+        "option": option,
+    });
+    let _: Option<serde_json::Value> =
+        graphql::query(client, api_url, SET_STATUS_MUTATION, variables)
+            .await
+            .with_context(|| format!("Failed to move issue #{}", issue))?;
+    tracing::info!(repository = %repo, issue, column, "Moved issue on the project board");
+
+    Ok(BoardItem {
+        item_id,
+        issue: node.number,
+        title: node.title,
+        state: node.state,
+        html_url: node.url,
+        column: Some(option),
+    })
+}
+
+impl ProjectBoard {
+    /// The column with option id `column`, or else named `column` ignoring case
+    pub fn column(&self, column: &str) -> Option<&BoardColumn> {
+        self.columns
+            .iter()
+            .find(|option| option.id == column)
+            .or_else(|| {
+                self.columns
+                    .iter()
+                    .find(|option| option.name.eq_ignore_ascii_case(column.trim()))
+            })
+    }
+}
+
+async fn fetch_board(
+    client: &Octocrab,
+    api_url: &str,
+    repo: &Repository,
+    project: Option<u64>,
+    first: u8,
+    after: Option<&str>,
+) -> Result<ProjectNode> {
+    let node = match project {
+        Some(number) => {
+            let query = format!("{}{}", OWNER_BOARD_QUERY, BOARD_FRAGMENT);
+            let variables = json!({
+                "owner": repo.owner,
+                "number": number,
+                "first": first,
+                "after": after,
+            });
+            let data: Option<OwnerBoardData> =
+                graphql::query(client, api_url, &query, variables).await?;
+            data.and_then(|data| data.repository_owner)
+                .and_then(|owner| owner.project_v2)
+                .with_context(|| format!("Project {} of {} not found", number, repo.owner))?
+        }
+        None => {
+            let query = format!("{}{}", LINKED_BOARD_QUERY, BOARD_FRAGMENT);
+            let variables = json!({
+                "owner": repo.owner,
+                "name": repo.name,
+                "first": first,
+                "after": after,
+            });
+            let data: Option<LinkedBoardData> =
+                graphql::query(client, api_url, &query, variables).await?;
+            data.and_then(|data| data.repository)
+                .with_context(|| format!("Repository {} not found", repo))?
+                .projects_v2
+                .nodes
+                .into_iter()
+                .flatten()
+                .next()
+                .with_context(|| format!("No project is linked to {}", repo))?
+        }
+    };
+    Ok(node)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OwnerBoardData {
+    repository_owner: Option<OwnerNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OwnerNode {
+    /// Absent for owners that can't have projects
+    #[serde(default)]
+    project_v2: Option<ProjectNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinkedBoardData {
+    repository: Option<LinkedRepositoryNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LinkedRepositoryNode {
+    projects_v2: Connection<ProjectNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Connection<T> {
+    nodes: Vec<Option<T>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectNode {
+    id: String,
+    number: u64,
+    title: String,
+    url: String,
+    /// Null when the project has no Status field; an empty object when the
+    /// field isn't single-select
+    field: Option<FieldNode>,
+    items: ItemConnection,
+}
+
+#[derive(Debug, Deserialize)]
+struct FieldNode {
+    id: Option<String>,
+    #[serde(default)]
+    options: Vec<OptionNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OptionNode {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItemConnection {
+    page_info: PageInfo,
+    nodes: Vec<Option<ItemNode>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItemNode {
+    id: String,
+    field_value_by_name: Option<FieldValueNode>,
+    /// An empty object for draft issues and pull requests
+    content: Option<ContentNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FieldValueNode {
+    option_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContentNode {
+    number: Option<u64>,
+    title: Option<String>,
+    state: Option<String>,
+    url: Option<String>,
+    repository: Option<NameWithOwner>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NameWithOwner {
+    name_with_owner: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueData {
+    repository: Option<IssueRepositoryNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueRepositoryNode {
+    issue: Option<IssueNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IssueNode {
+    id: String,
+    number: u64,
+    title: String,
+    state: String,
+    url: String,
+    project_items: Connection<IssueItemNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueItemNode {
+    id: String,
+    project: NodeId,
+}
+
+#[derive(Debug, Deserialize)]
+struct NodeId {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddItemData {
+    add_project_v2_item_by_id: AddItemPayload,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddItemPayload {
+    item: Option<NodeId>,
+}
+
+impl ProjectNode {
+    /// The board, keeping the issues of `repo` only; fails without a
+    /// single-select Status field
+    fn into_board(self, repo: &Repository) -> Result<ProjectBoard> {
+        let Some(FieldNode {
+            id: Some(status_field),
+            options,
+        }) = self.field
+        else {
+            bail!(
+                "Project '{}' has no single-select {} field",
+                self.title,
+                STATUS_FIELD
+            );
+        };
+        let repository = repo.to_string();
+        let items = self
+            .items
+            .nodes
+            .into_iter()
+            .flatten()
+            .filter_map(|item| {
+                let content = item.content?;
+                if !content
+                    .repository?
+                    .name_with_owner
+                    .eq_ignore_ascii_case(&repository)
+                {
+                    return None;
+                }
+                Some(BoardItem {
+                    item_id: item.id,
+                    issue: content.number?,
+                    title: content.title.unwrap_or_default(),
+                    state: content.state.unwrap_or_default(),
+                    html_url: content.url.unwrap_or_default(),
+                    column: item.field_value_by_name.and_then(|value| value.option_id),
+                })
+            })
+            .collect();
+        Ok(ProjectBoard {
+            id: self.id,
+            number: self.number,
+            title: self.title,
+            html_url: self.url,
+            status_field,
+            columns: options
+                .into_iter()
+                .map(|option| BoardColumn {
+                    id: option.id,
+                    name: option.name,
+                })
+                .collect(),
+            items,
+            truncated: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_board_from_response() {
+        let data: LinkedBoardData = serde_json::from_value(json!({
+            "repository": { "projectsV2": { "nodes": [{
+                "id": "PVT_1",
+                "number": 3,
+                "title": "Triage",
+                "url": "https://github.com/orgs/o/projects/3",
+                "field": {
+                    "id": "PVTSSF_1",
+                    "options": [
+                        { "id": "f75ad846", "name": "Todo" },
+                        { "id": "47fc9ee4", "name": "In Progress" }
+                    ]
+                },
+                "items": {
+                    "pageInfo": { "hasNextPage": false, "endCursor": null },
+                    "nodes": [
+                        {
+                            "id": "PVTI_1",
+                            "fieldValueByName": { "optionId": "47fc9ee4" },
+                            "content": {
+                                "number": 42,
+                                "title": "Fix login",
+                                "state": "OPEN",
+                                "url": "https://github.com/o/r/issues/42",
+                                "repository": { "nameWithOwner": "o/r" }
+                            }
+                        },
+                        { "id": "PVTI_2", "fieldValueByName": null, "content": {} },
+                        {
+                            "id": "PVTI_3",
+                            "fieldValueByName": null,
+                            "content": {
+                                "number": 7,
+                                "title": "Elsewhere",
+                                "state": "OPEN",
+                                "url": "https://github.com/o/other/issues/7",
+                                "repository": { "nameWithOwner": "o/other" }
+                            }
+                        }
+                    ]
+                }
+            }] } }
+        }))
+        .unwrap();
+
+        let repo = Repository::parse("o/r").unwrap();
+        let node = data
+            .repository
+            .unwrap()
+            .projects_v2
+            .nodes
+            .remove(0)
+            .unwrap();
+        let board = node.into_board(&repo).unwrap();
+        assert_eq!(board.status_field, "PVTSSF_1");
+        let [item] = board.items.clone().try_into().unwrap();
+        assert_eq!(item.issue, 42);
+        assert_eq!(item.column.as_deref(), Some("47fc9ee4"));
+        assert_eq!(board.column("in progress").unwrap().id, "47fc9ee4");
+        assert_eq!(board.column("f75ad846").unwrap().name, "Todo");
+        assert!(board.column("Done").is_none());
+    }
+
+    #[test]
+    fn test_board_needs_a_single_select_status_field() {
+        let node: ProjectNode = serde_json::from_value(json!({
+            "id": "PVT_1",
+            "number": 1,
+            "title": "Roadmap",
+            "url": "https://github.com/users/o/projects/1",
+            "field": {},
+            "items": { "pageInfo": { "hasNextPage": false, "endCursor": null }, "nodes": [] }
+        }))
+        .unwrap();
+        let repo = Repository::parse("o/r").unwrap();
+        assert!(node.into_board(&repo).is_err());
+    }
+}
@@ -0,0 +1,326 @@
+use super::Repository;
+use crate::config::{GitHubSettings, GitSettings, MergeStrategy};
+use crate::events::schema::v1::{PullRequestOperation, PullRequestProgress};
+use crate::git::links::issue_from_branch;
+use anyhow::{bail, Context, Result};
+use octocrab::models::pulls::PullRequest as GitHubPullRequest;
+use octocrab::models::IssueState;
+use octocrab::params::pulls::MergeMethod;
+use octocrab::{params, Octocrab};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A pull request as shown in the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    pub body: String,
+    /// "open", "closed" or "merged"
+    pub state: String,
+    pub draft: bool,
+    pub head: String,
+    pub base: String,
+    pub author: String,
+    pub requested_reviewers: Vec<String>,
+    pub html_url: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub merged_at: Option<String>,
+}
+
+impl From<GitHubPullRequest> for PullRequest {
+    fn from(pr: GitHubPullRequest) -> Self {
+        let state = if pr.merged_at.is_some() {
+            "merged"
+        } else if matches!(pr.state, Some(IssueState::Closed)) {
+            "closed"
+        } else {
+            "open"
+        };
+
+        Self {
+            number: pr.number,
+            title: pr.title.unwrap_or_default(),
+            body: pr.body.unwrap_or_default(),
+            state: state.to_string(),
+            draft: pr.draft.unwrap_or(false),
+            head: pr.head.ref_field,
+            base: pr.base.ref_field,
+            author: pr.user.map(|user| user.login).unwrap_or_default(),
+            requested_reviewers: pr
+                .requested_reviewers
+                .unwrap_or_default()
+                .into_iter()
+                .map(|user| user.login)
+                .collect(),
+            html_url: pr.html_url.map(|url| url.to_string()).unwrap_or_default(),
+            created_at: pr.created_at.map(|time| time.to_rfc3339()),
+            updated_at: pr.updated_at.map(|time| time.to_rfc3339()),
+            merged_at: pr.merged_at.map(|time| time.to_rfc3339()),
+        }
+    }
+}
+
+/// Options for create_pull_request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NewPullRequest {
+    pub title: String,
+    /// Defaults to GitHubSettings::pr_template
+    pub body: Option<String>,
+    /// Defaults to GitSettings::default_branch
+    pub base: Option<String>,
+    pub draft: bool,
+}
+
+/// Open a pull request from the checked-out branch of `repo_path`
+///
+/// The body defaults to the PR template, the linked issue is taken from the branch
+/// name (see `issue_from_branch`), and the default reviewers are requested.
+pub async fn create_pull_request(
+    client: &Octocrab,
+    repo: &Repository,
+    repo_path: &Path,
+    github: &GitHubSettings,
+    git: &GitSettings,
+    new: NewPullRequest,
+    progress: impl Fn(PullRequestProgress),
+) -> Result<PullRequest> {
+    let operation = PullRequestOperation::Create;
+    progress(PullRequestProgress::started(operation, None));
+
+    let head = crate::git::current_branch(&crate::git::open(repo_path)?)?;
+    let base = new.base.unwrap_or_else(|| git.default_branch.clone());
+    if head == base {
+        bail!("Cannot open a pull request from {} into itself", base);
+    }
+
+    let issue = issue_from_branch(&head, &git.branch_prefix);
+    let template = new.body.unwrap_or_else(|| github.pr_template.clone());
+    let body = render_body(&template, &head, issue, github);
+
+    progress(PullRequestProgress::step(
+        operation,
+        None,
+        format!("Creating pull request {} -> {}", head, base),
+    ));
+    let pr = client
+        .pulls(&repo.owner, &repo.name)
+        .create(&new.title, &head, &base)
+        .body(body)
+        .draft(new.draft)
+        .send()
+        .await
+        .with_context(|| format!("Failed to create pull request from {}", head))?;
+    let number = pr.number;
+
+    let pr = if github.default_reviewers.is_empty() {
+        PullRequest::from(pr)
+    } else {
+        request_reviewers(
+            client,
+            repo,
+            number,
+            github.default_reviewers.clone(),
+            &progress,
+        )
+        .await?
+    };
+
+    progress(PullRequestProgress::completed(operation, Some(number)));
+    Ok(pr)
+}
+
+/// Pull requests in the repository; `state` is "open" (default), "closed" or "all"
+pub async fn list_pull_requests(
+    client: &Octocrab,
+    repo: &Repository,
+    state: Option<&str>,
+    page: Option<u32>,
+) -> Result<Vec<PullRequest>> {
+    let state = match state.unwrap_or("open") {
+        "open" => params::State::Open,
+        "closed" => params::State::Closed,
+        "all" => params::State::All,
+        other => bail!(
+            "Invalid pull request state '{}': use open, closed or all",
+            other
+        ),
+    };
+
+    let pulls = client.pulls(&repo.owner, &repo.name);
+    let mut request = pulls.list().state(state).per_page(50u8);
+    if let Some(page) = page {
+        request = request.page(page);
+    }
+
+    let page = request
+        .send()
+        .await
+        .context("Failed to list pull requests")?;
+    Ok(page.items.into_iter().map(PullRequest::from).collect())
+}
+
+/// Most recent pull request opened from `branch` in any state
+pub async fn pull_request_for_branch(
+    client: &Octocrab,
+    repo: &Repository,
+    branch: &str,
+) -> Result<Option<PullRequest>> {
+    let page = client
+        .pulls(&repo.owner, &repo.name)
+        .list()
+        .head(format!("{}:{}", repo.owner, branch))
+        .state(params::State::All)
+        .per_page(1u8)
+        .send()
+        .await
+        .with_context(|| format!("Failed to find pull requests from {}", branch))?;
+    Ok(page.items.into_iter().next().map(PullRequest::from))
+}
+
+/// Merge a pull request with the configured merge strategy
+pub async fn merge_pull_request(
+    client: &Octocrab,
+    repo: &Repository,
+    number: u64,
+    strategy: MergeStrategy,
+    progress: impl Fn(PullRequestProgress),
+) -> Result<PullRequest> {
+    let operation = PullRequestOperation::Merge;
+    progress(PullRequestProgress::started(operation, Some(number)));
+
+    let pulls = client.pulls(&repo.owner, &repo.name);
+    progress(PullRequestProgress::step(
+        operation,
+        Some(number),
+        format!("Merging #{} ({})", number, strategy_name(strategy)),
+    ));
+    let result = pulls
+        .merge(number)
+        .method(merge_method(strategy))
+        .send()
+        .await
+        .with_context(|| format!("Failed to merge #{}", number))?;
+    if !result.merged {
+        bail!(
+            "GitHub did not merge #{}: {}",
+            number,
+            result.message.unwrap_or_default()
+        );
+    }
+
+    let pr = pulls
+        .get(number)
+        .await
+        .with_context(|| format!("Failed to get #{}", number))?;
+    progress(PullRequestProgress::completed(operation, Some(number)));
+    Ok(pr.into())
+}
+
+/// Request reviews on a pull request
+pub async fn request_reviewers(
+    client: &Octocrab,
+    repo: &Repository,
+    number: u64,
+    reviewers: Vec<String>,
+    progress: impl Fn(PullRequestProgress),
+) -> Result<PullRequest> {
+    let operation = PullRequestOperation::RequestReview;
+    if reviewers.is_empty() {
+        bail!("No reviewers given and no default reviewers configured");
+    }
+
+    progress(PullRequestProgress::step(
+        operation,
+        Some(number),
+        format!("Requesting review from {}", reviewers.join(", ")),
+    ));
+    let pulls = client.pulls(&repo.owner, &repo.name);
+    pulls
+        .request_reviews(number, reviewers, Vec::<String>::new())
+        .await
+        .with_context(|| format!("Failed to request reviewers on #{}", number))?;
+
+    let pr = pulls
+        .get(number)
+        .await
+        .with_context(|| format!("Failed to get #{}", number))?;
+    Ok(pr.into())
+}
+
+/// Fill `{branch}` and `{issue}` in the template and link the issue when enabled
+fn render_body(
+    template: &str,
+    branch: &str,
+    issue: Option<u64>,
+    github: &GitHubSettings,
+) -> String {
+    let mut body = template.replace("{branch}", branch);
+    body = body.replace(
+        "{issue}",
+        &issue
+            .map(|number| format!("#{}", number))
+            .unwrap_or_default(),
+    );
+
+    if let Some(number) = issue.filter(|_| github.auto_link_issues) {
+        if !body.contains(&format!("#{}", number)) {
+            let keyword = if github.auto_close_issue_on_pr {
+                "Closes"
+            } else {
+                "Related to"
+            };
+            if !body.is_empty() {
+                body.push_str("\n\n");
+            }
+            body.push_str(&format!("{} #{}", keyword, number));
+        }
+    }
+    body
+}
+
+fn merge_method(strategy: MergeStrategy) -> MergeMethod {
+    match strategy {
+        MergeStrategy::Merge => MergeMethod::Merge,
+        MergeStrategy::Squash => MergeMethod::Squash,
+        MergeStrategy::Rebase => MergeMethod::Rebase,
+    }
+}
+
+fn strategy_name(strategy: MergeStrategy) -> &'static str {
+    match strategy {
+        MergeStrategy::Merge => "merge",
+        MergeStrategy::Squash => "squash",
+        MergeStrategy::Rebase => "rebase",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_body_links_issue() {
+        let github = GitHubSettings {
+            pr_template: "Branch: {branch}".to_string(),
+            ..GitHubSettings::default()
+        };
+
+        assert_eq!(
+            render_body(&github.pr_template, "issue-7", Some(7), &github),
+            "Branch: issue-7\n\nCloses #7"
+        );
+        assert_eq!(
+            render_body("Fixes {issue}", "issue-7", Some(7), &github),
+            "Fixes #7"
+        );
+
+        let unlinked = GitHubSettings {
+            auto_link_issues: false,
+            ..github
+        };
+        assert_eq!(render_body("", "issue-7", Some(7), &unlinked), "");
+    }
+}
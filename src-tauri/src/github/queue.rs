@@ -0,0 +1,357 @@
+//! Persistent queue of GitHub mutations made while offline
+//!
+//! Comments and issue closes that fail because GitHub is unreachable are kept
+//! in ~/.zeami/queue.json and replayed with exponential backoff. Before a replay
+//! the issue is fetched again: if it changed on GitHub in a way that makes the
+//! mutation stale (e.g. it was closed meanwhile), the mutation is marked as a
+//! conflict and left for the user to cancel instead of being applied blindly.
+
+use super::issues::{self, Issue};
+use super::Repository;
+use crate::config::atomic::write_atomic;
+use crate::config::storage;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use octocrab::Octocrab;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// First retry delay; doubled after every failed attempt
+const BASE_BACKOFF_SECS: i64 = 30;
+
+/// Longest delay between retries
+const MAX_BACKOFF_SECS: i64 = 60 * 60;
+
+/// A change to make on GitHub
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Mutation {
+    Comment {
+        number: u64,
+        body: String,
+    },
+    CloseIssue {
+        number: u64,
+        comment: Option<String>,
+    },
+}
+
+impl Mutation {
+    pub fn number(&self) -> u64 {
+        match self {
+            Mutation::Comment { number, .. } | Mutation::CloseIssue { number, .. } => *number,
+        }
+    }
+}
+
+/// A queued mutation waiting for connectivity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingMutation {
+    pub id: String,
+    pub repository: String,
+    pub mutation: Mutation,
+    pub queued_at: DateTime<Utc>,
+    pub attempts: u32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    /// Set when replaying would overwrite a newer change; conflicts are not retried
+    pub conflict: Option<String>,
+}
+
+/// Result of a mutation command: applied now, or queued for later
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MutationOutcome<T> {
+    Applied { result: T },
+    Queued { pending: PendingMutation },
+}
+
+/// Result of replaying one mutation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayResult {
+    Applied,
+    Conflict(String),
+    /// Still offline or GitHub failed; retried later
+    Failed(String),
+}
+
+/// Mutations queued in ~/.zeami/queue.json
+pub struct MutationQueue {
+    path: PathBuf,
+    pending: Mutex<Vec<PendingMutation>>,
+}
+
+impl MutationQueue {
+    /// Load ~/.zeami/queue.json
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::open(&storage::config_dir()?.join("queue.json")))
+    }
+
+    /// Load the queue at `path`; a missing or unreadable file gives an empty queue
+    pub fn open(path: &Path) -> Self {
+        let pending = match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable mutation queue {:?}: {}", path, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+
+        Self {
+            path: path.to_path_buf(),
+            pending: Mutex::new(pending),
+        }
+    }
+
+    pub fn list(&self) -> Vec<PendingMutation> {
+        self.pending.lock().map(|p| p.clone()).unwrap_or_default()
+    }
+
+    /// Queue a mutation that failed with `error`
+    pub fn push(
+        &self,
+        repository: &Repository,
+        mutation: Mutation,
+        error: &str,
+        now: DateTime<Utc>,
+    ) -> Result<PendingMutation> {
+        let pending = PendingMutation {
+            id: uuid::Uuid::new_v4().to_string(),
+            repository: repository.to_string(),
+            mutation,
+            queued_at: now,
+            attempts: 1,
+            next_attempt_at: now + backoff(1),
+            last_error: Some(error.to_string()),
+            conflict: None,
+        };
+        self.update(|entries| entries.push(pending.clone()))?;
+        Ok(pending)
+    }
+
+    /// Drop a mutation; returns false when it is not queued
+    pub fn cancel(&self, id: &str) -> Result<bool> {
+        self.update(|entries| {
+            let before = entries.len();
+            entries.retain(|entry| entry.id != id);
+            entries.len() != before
+        })
+    }
+
+    /// Mutations due for a retry at `now`, oldest first
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<PendingMutation> {
+        self.list()
+            .into_iter()
+            .filter(|entry| entry.conflict.is_none() && entry.next_attempt_at <= now)
+            .collect()
+    }
+
+    /// Apply the result of a replay to the queue
+    pub fn record(&self, id: &str, result: &ReplayResult, now: DateTime<Utc>) -> Result<()> {
+        self.update(|entries| match result {
+            ReplayResult::Applied => entries.retain(|entry| entry.id != id),
+            ReplayResult::Conflict(reason) => {
+                if let Some(entry) = entries.iter_mut().find(|entry| entry.id == id) {
+                    entry.conflict = Some(reason.clone());
+                }
+            }
+            ReplayResult::Failed(error) => {
+                if let Some(entry) = entries.iter_mut().find(|entry| entry.id == id) {
+                    entry.attempts += 1;
+                    entry.next_attempt_at = now + backoff(entry.attempts);
+                    entry.last_error = Some(error.clone());
+                }
+            }
+        })
+    }
+
+    fn update<T>(&self, change: impl FnOnce(&mut Vec<PendingMutation>) -> T) -> Result<T> {
+        let mut pending = self
+            .pending
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock mutation queue: {}", e))?;
+        let result = change(&mut pending);
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        }
+        write_atomic(
+            &self.path,
+            serde_json::to_string_pretty(&*pending)?.as_bytes(),
+        )
+        .with_context(|| format!("Failed to write {:?}", self.path))?;
+        Ok(result)
+    }
+}
+
+/// Whether an error means GitHub could not be reached (as opposed to GitHub rejecting the request)
+pub fn is_offline(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<octocrab::Error>(),
+            Some(octocrab::Error::Hyper { .. })
+                | Some(octocrab::Error::Service { .. })
+                | Some(octocrab::Error::Http { .. })
+        )
+    })
+}
+
+/// Replay a queued mutation after checking it against the issue's current state
+pub async fn replay(client: &Octocrab, pending: &PendingMutation) -> ReplayResult {
+    let repo = match Repository::parse(&pending.repository) {
+        Ok(repo) => repo,
+        Err(e) => return ReplayResult::Conflict(e.to_string()),
+    };
+    let number = pending.mutation.number();
+
+    let current = match issues::get_issue_summary(client, &repo, number).await {
+        Ok(issue) => issue,
+        Err(e) => return ReplayResult::Failed(format!("{:#}", e)),
+    };
+    if let Some(reason) = conflict(pending, &current) {
+        return ReplayResult::Conflict(reason);
+    }
+
+    let result = match &pending.mutation {
+        Mutation::Comment { body, .. } => issues::comment_on_issue(client, &repo, number, body)
+            .await
+            .map(|_| ()),
+        Mutation::CloseIssue { comment, .. } => {
+            issues::close_issue(client, &repo, number, comment.as_deref())
+                .await
+                .map(|_| ())
+        }
+    };
+    match result {
+        Ok(()) => ReplayResult::Applied,
+        Err(e) => ReplayResult::Failed(format!("{:#}", e)),
+    }
+}
+
+/// Why replaying `pending` onto the issue as it is now would be wrong, if it would
+fn conflict(pending: &PendingMutation, current: &Issue) -> Option<String> {
+    let closed_since_queued = current.state == "closed"
+        && current
+            .closed_at
+            .as_deref()
+            .and_then(|closed_at| DateTime::parse_from_rfc3339(closed_at).ok())
+            .is_some_and(|closed_at| closed_at > pending.queued_at);
+
+    match &pending.mutation {
+        Mutation::CloseIssue { number, .. } if current.state == "closed" => {
+            Some(format!("#{} was already closed on GitHub", number))
+        }
+        Mutation::Comment { number, .. } if closed_since_queued => Some(format!(
+            "#{} was closed on GitHub after the comment was written",
+            number
+        )),
+        _ => None,
+    }
+}
+
+fn backoff(attempts: u32) -> chrono::Duration {
+    let secs = BASE_BACKOFF_SECS.saturating_mul(1 << attempts.saturating_sub(1).min(16));
+    chrono::Duration::seconds(secs.min(MAX_BACKOFF_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(state: &str, closed_at: Option<&str>) -> Issue {
+        Issue {
+            number: 1,
+            title: String::new(),
+            body: String::new(),
+            state: state.to_string(),
+            labels: Vec::new(),
+            assignees: Vec::new(),
+            author: String::new(),
+            comments: 0,
+            html_url: String::new(),
+            created_at: String::new(),
+            updated_at: String::new(),
+            closed_at: closed_at.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_queue_persists_and_backs_off() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.json");
+        let repo = Repository::parse("o/r").unwrap();
+        let now = Utc::now();
+
+        let queue = MutationQueue::open(&path);
+        let pending = queue
+            .push(
+                &repo,
+                Mutation::Comment {
+                    number: 1,
+                    body: "hi".to_string(),
+                },
+                "offline",
+                now,
+            )
+            .unwrap();
+        assert!(queue.due(now).is_empty());
+        assert_eq!(queue.due(now + backoff(1)).len(), 1);
+
+        queue
+            .record(
+                &pending.id,
+                &ReplayResult::Failed("still offline".into()),
+                now,
+            )
+            .unwrap();
+        let reopened = MutationQueue::open(&path);
+        let entry = &reopened.list()[0];
+        assert_eq!(entry.attempts, 2);
+        assert_eq!(entry.next_attempt_at, now + backoff(2));
+
+        reopened
+            .record(&pending.id, &ReplayResult::Conflict("closed".into()), now)
+            .unwrap();
+        assert!(reopened.due(now + backoff(10)).is_empty());
+        assert!(reopened.cancel(&pending.id).unwrap());
+        assert!(MutationQueue::open(&path).list().is_empty());
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        assert_eq!(backoff(1).num_seconds(), 30);
+        assert_eq!(backoff(2).num_seconds(), 60);
+        assert_eq!(backoff(40).num_seconds(), MAX_BACKOFF_SECS);
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let queued_at = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let pending = |mutation| PendingMutation {
+            id: "1".to_string(),
+            repository: "o/r".to_string(),
+            mutation,
+            queued_at,
+            attempts: 1,
+            next_attempt_at: queued_at,
+            last_error: None,
+            conflict: None,
+        };
+        let close = pending(Mutation::CloseIssue {
+            number: 1,
+            comment: None,
+        });
+        let comment = pending(Mutation::Comment {
+            number: 1,
+            body: "done".to_string(),
+        });
+
+        assert!(conflict(&close, &issue("open", None)).is_none());
+        assert!(conflict(&close, &issue("closed", Some("2024-01-01T11:00:00Z"))).is_some());
+        assert!(conflict(&comment, &issue("closed", Some("2024-01-01T11:00:00Z"))).is_none());
+        assert!(conflict(&comment, &issue("closed", Some("2024-01-01T13:00:00Z"))).is_some());
+    }
+}
@@ -0,0 +1,328 @@
+//! Releases of the configured repository
+//!
+//! Release notes are given by hand, generated by GitHub, or drafted locally
+//! from the commits since the previous tag or from the project journal.
+
+use super::Repository;
+use crate::config::GitSettings;
+use crate::git;
+use crate::journal::{self, JournalKind, JournalRange};
+use anyhow::{bail, Context, Result};
+use chrono::{TimeZone, Utc};
+use git2::{DescribeFormatOptions, DescribeOptions};
+use octocrab::models::repos::Release as GitHubRelease;
+use octocrab::Octocrab;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Releases fetched per page
+const RELEASES_PER_PAGE: u8 = 30;
+
+/// Commits listed in locally drafted notes
+const MAX_NOTE_COMMITS: usize = 200;
+
+/// A release as shown in the UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Release {
+    pub id: u64,
+    pub tag: String,
+    pub name: String,
+    pub body: String,
+    pub draft: bool,
+    pub prerelease: bool,
+    /// Branch or commit the tag was created from
+    pub target: String,
+    pub author: String,
+    pub assets: usize,
+    pub html_url: String,
+    pub created_at: Option<String>,
+    pub published_at: Option<String>,
+}
+
+impl From<GitHubRelease> for Release {
+    fn from(release: GitHubRelease) -> Self {
+        Self {
+            id: release.id.0,
+            name: release.name.unwrap_or_else(|| release.tag_name.clone()),
+            tag: release.tag_name,
+            body: release.body.unwrap_or_default(),
+            draft: release.draft,
+            prerelease: release.prerelease,
+            target: release.target_commitish,
+            author: release
+                .author
+                .map(|author| author.login)
+                .unwrap_or_default(),
+            assets: release.assets.len(),
+            html_url: release.html_url.to_string(),
+            created_at: release.created_at.map(|time| time.to_rfc3339()),
+            published_at: release.published_at.map(|time| time.to_rfc3339()),
+        }
+    }
+}
+
+/// Where create_release takes notes from when none are given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotesSource {
+    /// Commits since the previous tag, grouped by conventional commit type
+    Commits,
+    /// Issues synced and commits recorded in the journal since the previous tag
+    Journal,
+    /// GitHub's generated notes (merged pull requests and contributors)
+    Github,
+}
+
+/// Options for create_release
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NewRelease {
+    pub tag: String,
+    /// Defaults to the tag
+    pub name: Option<String>,
+    pub notes: Option<String>,
+    /// Used when `notes` is None; no notes when both are None
+    pub generate_notes: Option<NotesSource>,
+    /// Branch or commit to tag when the tag does not exist; defaults to
+    /// GitSettings::default_branch
+    pub target: Option<String>,
+    pub draft: bool,
+    pub prerelease: bool,
+}
+
+/// Releases of the repository, newest first
+pub async fn list_releases(
+    client: &Octocrab,
+    repo: &Repository,
+    page: Option<u32>,
+) -> Result<Vec<Release>> {
+    let releases = client.repos(&repo.owner, &repo.name);
+    let releases = releases.releases();
+    let mut request = releases.list().per_page(RELEASES_PER_PAGE);
+    if let Some(page) = page {
+        request = request.page(page);
+    }
+    let page = request.send().await.context("Failed to list releases")?;
+    Ok(page.items.into_iter().map(Release::from).collect())
+}
+
+/// Publish a release, drafting its notes per NewRelease::generate_notes
+pub async fn create_release(
+    client: &Octocrab,
+    repo: &Repository,
+    repo_path: &Path,
+    git: &GitSettings,
+    release: NewRelease,
+) -> Result<Release> {
+    let tag = release.tag.trim();
+    if tag.is_empty() || tag.contains(char::is_whitespace) {
+        bail!("Invalid release tag '{}'", release.tag);
+    }
+    let target = release
+        .target
+        .clone()
+        .unwrap_or_else(|| git.default_branch.clone());
+
+    let repos = client.repos(&repo.owner, &repo.name);
+    let releases = repos.releases();
+    let notes = match (release.notes, release.generate_notes) {
+        (Some(notes), _) => notes,
+        (None, Some(NotesSource::Github)) => {
+            releases
+                .generate_release_notes(tag)
+                .target_commitish(&target)
+                .send()
+                .await
+                .context("Failed to generate release notes")?
+                .body
+        }
+        (None, Some(source)) => {
+            let (repo_path, git, target) = (repo_path.to_path_buf(), git.clone(), target.clone());
+            tokio::task::spawn_blocking(move || local_notes(&repo_path, &git, &target, source))
+                .await??
+        }
+        (None, None) => String::new(),
+    };
+
+    let name = release.name.unwrap_or_else(|| tag.to_string());
+    let created = releases
+        .create(tag)
+        .target_commitish(&target)
+        .name(&name)
+        .body(&notes)
+        .draft(release.draft)
+        .prerelease(release.prerelease)
+        .send()
+        .await
+        .with_context(|| format!("Failed to create release {}", tag))?;
+    tracing::info!(repository = %repo, tag, "Created release");
+    Ok(created.into())
+}
+
+/// Notes for a release of `target` (a local or remote-tracking branch, or any
+/// revision), from local history or the journal
+pub fn local_notes(
+    repo_path: &Path,
+    git: &GitSettings,
+    target: &str,
+    source: NotesSource,
+) -> Result<String> {
+    let repo = git::open(repo_path)?;
+    let target = repo
+        .revparse_single(target)
+        .or_else(|_| repo.revparse_single(&format!("{}/{}", git.remote, target)))
+        .with_context(|| format!("Unknown release target {}", target))?
+        .peel_to_commit()?;
+    let previous = previous_tag(&repo, &target);
+
+    match source {
+        NotesSource::Commits => {
+            let mut walk = repo.revwalk()?;
+            walk.push(target.id())?;
+            if let Some((_, tagged)) = &previous {
+                walk.hide(tagged.id())?;
+            }
+            let mut summaries = Vec::new();
+            for id in walk.take(MAX_NOTE_COMMITS) {
+                let commit = repo.find_commit(id?)?;
+                if commit.parent_count() > 1 {
+                    continue;
+                }
+                let id = commit.id().to_string();
+                summaries.push(format!(
+                    "{} ({})",
+                    commit.summary().unwrap_or_default(),
+                    &id[..7]
+                ));
+            }
+            Ok(commit_notes(&summaries))
+        }
+        NotesSource::Journal => {
+            let root = git::workdir(&repo)?;
+            let since = previous
+                .as_ref()
+                .and_then(|(_, tagged)| Utc.timestamp_opt(tagged.time().seconds(), 0).single());
+            let range = JournalRange {
+                from: since,
+                to: None,
+            };
+            let entries = journal::query(
+                &root,
+                range,
+                &[JournalKind::IssueSynced, JournalKind::Commit],
+            )?;
+            let mut issues: Vec<String> = Vec::new();
+            let mut commits = Vec::new();
+            for entry in entries.iter().rev() {
+                match (entry.kind, &entry.reference) {
+                    (JournalKind::IssueSynced, Some(number)) => {
+                        let line = format!("- #{}", number);
+                        if !issues.contains(&line) {
+                            issues.push(line);
+                        }
+                    }
+                    (JournalKind::Commit, _) => commits.push(format!("- {}", entry.summary)),
+                    _ => {}
+                }
+            }
+            let mut sections = Vec::new();
+            if !issues.is_empty() {
+                sections.push(format!("## Issues\n\n{}", issues.join("\n")));
+            }
+            if !commits.is_empty() {
+                sections.push(format!("## Commits\n\n{}", commits.join("\n")));
+            }
+            Ok(sections.join("\n\n"))
+        }
+        NotesSource::Github => bail!("GitHub notes are generated by GitHub"),
+    }
+}
+
+/// The newest tag reachable from `commit`, with the commit it points at
+fn previous_tag<'r>(
+    repo: &'r git2::Repository,
+    commit: &git2::Commit,
+) -> Option<(String, git2::Commit<'r>)> {
+    let describe = commit
+        .as_object()
+        .describe(DescribeOptions::new().describe_tags())
+        .ok()?;
+    let tag = describe
+        .format(Some(DescribeFormatOptions::new().abbreviated_size(0)))
+        .ok()?;
+    let tagged = repo.revparse_single(&tag).ok()?.peel_to_commit().ok()?;
+    Some((tag, tagged))
+}
+
+/// Markdown list of commit summaries, grouped by conventional commit type
+/// when there are features or fixes
+fn commit_notes(summaries: &[String]) -> String {
+    let mut features = Vec::new();
+    let mut fixes = Vec::new();
+    let mut other = Vec::new();
+    for summary in summaries {
+        let kind = summary
+            .split_once(':')
+            .map(|(kind, _)| kind.split(['(', '!']).next().unwrap_or_default().trim());
+        let line = format!("- {}", summary);
+        match kind {
+            Some("feat") => features.push(line),
+            Some("fix") => fixes.push(line),
+            _ => other.push(line),
+        }
+    }
+    if features.is_empty() && fixes.is_empty() {
+        return other.join("\n");
+    }
+    [
+        ("Features", features),
+        ("Fixes", fixes),
+        ("Other changes", other),
+    ]
+    .into_iter()
+    .filter(|(_, lines)| !lines.is_empty())
+    .map(|(heading, lines)| format!("## {}\n\n{}", heading, lines.join("\n")))
+    .collect::<Vec<_>>()
+    .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_all, init_repo};
+
+    #[test]
+    fn test_drafts_notes_from_commits_since_the_previous_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        for message in [
+            "Initial commit",
+            "feat: release notes",
+            "fix(ui): badge color",
+            "Update docs",
+        ] {
+            let id = commit_all(&repo, message);
+            if message == "Initial commit" {
+                let object = repo.find_object(id, None).unwrap();
+                repo.tag_lightweight("v1.0.0", &object, false).unwrap();
+            }
+        }
+
+        let notes = local_notes(
+            dir.path(),
+            &GitSettings::default(),
+            "HEAD",
+            NotesSource::Commits,
+        )
+        .unwrap();
+        assert!(notes.starts_with("## Features\n\n- feat: release notes ("));
+        assert!(notes.contains("## Fixes\n\n- fix(ui): badge color ("));
+        assert!(notes.contains("## Other changes\n\n- Update docs ("));
+        assert!(!notes.contains("Initial commit"));
+
+        assert_eq!(
+            commit_notes(&["Tidy up".to_string(), "Bump version".to_string()]),
+            "- Tidy up\n- Bump version"
+        );
+    }
+}
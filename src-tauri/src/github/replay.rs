@@ -0,0 +1,68 @@
+use super::queue::{self, MutationQueue, ReplayResult};
+use super::GitHubState;
+use crate::config::SettingsState;
+use crate::events::schema::v1;
+use crate::events::GITHUB_MUTATION_REPLAYED_EVENT_NAME;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often the queue is checked for mutations due for a retry
+const REPLAY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Replay queued offline mutations as they come due
+/// Each attempt is reported as a `github-mutation-replayed` event
+pub fn spawn_replay(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(REPLAY_INTERVAL).await;
+
+            let queue = app.state::<MutationQueue>();
+            let due = queue.due(chrono::Utc::now());
+            if due.is_empty() {
+                continue;
+            }
+
+            let settings = app.state::<SettingsState>().current().github;
+            let client = match app
+                .state::<GitHubState>()
+                .background_client(&settings)
+                .await
+            {
+                Ok(client) => client,
+                Err(e) => {
+                    tracing::debug!("Not replaying queued mutations: {:#}", e);
+                    continue;
+                }
+            };
+
+            for pending in due {
+                let result = queue::replay(&client, &pending).await;
+                if let Err(e) = queue.record(&pending.id, &result, chrono::Utc::now()) {
+                    tracing::error!("Failed to update mutation queue: {:#}", e);
+                }
+
+                let payload = v1::GitHubMutationReplayed {
+                    id: pending.id.clone(),
+                    repository: pending.repository.clone(),
+                    applied: result == ReplayResult::Applied,
+                    conflict: match &result {
+                        ReplayResult::Conflict(reason) => Some(reason.clone()),
+                        _ => None,
+                    },
+                    error: match &result {
+                        ReplayResult::Failed(error) => Some(error.clone()),
+                        _ => None,
+                    },
+                };
+                if let Err(e) = app.emit_all(GITHUB_MUTATION_REPLAYED_EVENT_NAME, payload) {
+                    tracing::error!("Failed to emit mutation replay: {}", e);
+                }
+
+                // Still offline: the remaining mutations would fail the same way
+                if matches!(result, ReplayResult::Failed(_)) {
+                    break;
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,266 @@
+//! Rate limit budget shared by all GitHub requests
+//!
+//! Every client handed out by GitHubState is admitted here first. Interactive
+//! requests (commands the user is waiting on) go ahead until the limit is used
+//! up; background requests (sync, event polling, CI watching, mutation replay)
+//! wait once the remaining budget drops into the reserve kept for interactive
+//! use, and resume after the limit resets.
+//!
+//! octocrab doesn't expose response headers of typed calls, so the budget is
+//! refreshed from the rate limit endpoint (which costs nothing) when it is
+//! stale, and counted down per admitted request in between.
+
+use super::RateLimitInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Share of the limit kept for interactive requests
+const RESERVE_PERCENT: usize = 10;
+
+/// Smallest reserve, so a low limit still leaves room to click around
+const MIN_RESERVE: usize = 5;
+
+/// Seconds after which the budget is refreshed before a background request
+pub const REFRESH_AFTER_SECS: u64 = 60;
+
+/// Longest a background request sleeps before checking the budget again
+pub const MAX_WAIT_SECS: u64 = 60;
+
+/// Who is waiting on a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// A command the user triggered
+    Interactive,
+    /// Sync, polling and other work nobody is waiting on
+    Background,
+}
+
+/// Outcome of asking for a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    Go,
+    /// Background request; ask again in this many seconds
+    Wait(u64),
+    /// Interactive request while the limit is used up; resets in this many seconds
+    Refuse(u64),
+}
+
+/// Budget of an account as reported by get_rate_limit_status
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitStatus {
+    /// Named account; None for the default account
+    pub account: Option<String>,
+    /// Last reported limit, counted down per request since; None before the
+    /// first report
+    pub rate_limit: Option<RateLimitInfo>,
+    /// Requests kept for interactive use
+    pub reserve: usize,
+    /// Background requests are being held back
+    pub throttled: bool,
+    /// Background requests waiting for the budget
+    pub queued: usize,
+    /// Unix timestamp (seconds) of the last report
+    pub refreshed_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Budget {
+    rate: RateLimitInfo,
+    refreshed_at: u64,
+}
+
+/// Per-account rate limit budgets
+#[derive(Debug, Default)]
+pub struct RequestScheduler {
+    budgets: Mutex<HashMap<String, Budget>>,
+    queued: AtomicUsize,
+}
+
+impl RequestScheduler {
+    /// Remember the rate limit GitHub reported for an account
+    pub fn record(&self, key: &str, rate: RateLimitInfo, now: u64) {
+        if let Ok(mut budgets) = self.budgets.lock() {
+            budgets.insert(
+                key.to_string(),
+                Budget {
+                    rate,
+                    refreshed_at: now,
+                },
+            );
+        }
+    }
+
+    /// Forget all budgets, e.g. after a token change
+    pub fn clear(&self) {
+        if let Ok(mut budgets) = self.budgets.lock() {
+            budgets.clear();
+        }
+    }
+
+    /// Admit a request for the account, counting it against the budget
+    pub fn admit(&self, key: &str, priority: Priority, now: u64) -> Admission {
+        let Ok(mut budgets) = self.budgets.lock() else {
+            return Admission::Go;
+        };
+        let Some(budget) = budgets.get_mut(key) else {
+            return Admission::Go;
+        };
+        if budget.rate.reset <= now {
+            // The window has reset since the last report; nothing is known
+            // about the new one until the next refresh
+            return Admission::Go;
+        }
+        let admission = decide(&budget.rate, priority, now);
+        if admission == Admission::Go {
+            budget.rate.remaining = budget.rate.remaining.saturating_sub(1);
+            budget.rate.used += 1;
+        }
+        admission
+    }
+
+    /// True when the account's budget should be refreshed before a background request
+    pub fn stale(&self, key: &str, now: u64) -> bool {
+        self.budgets
+            .lock()
+            .ok()
+            .and_then(|budgets| budgets.get(key).copied())
+            .is_none_or(|budget| {
+                now.saturating_sub(budget.refreshed_at) >= REFRESH_AFTER_SECS
+                    || budget.rate.reset <= now
+            })
+    }
+
+    /// Count a background request as waiting until the guard is dropped
+    pub fn enqueue(&self) -> QueueGuard<'_> {
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        QueueGuard(&self.queued)
+    }
+
+    /// The account's budget as last reported and counted down since
+    pub fn status(&self, key: &str, account: Option<String>, now: u64) -> RateLimitStatus {
+        let budget = self
+            .budgets
+            .lock()
+            .ok()
+            .and_then(|budgets| budgets.get(key).copied());
+        let rate_limit = budget.map(|budget| budget.rate);
+        RateLimitStatus {
+            account,
+            rate_limit,
+            reserve: rate_limit.map_or(MIN_RESERVE, |rate| reserve(rate.limit)),
+            throttled: rate_limit
+                .is_some_and(|rate| decide(&rate, Priority::Background, now) != Admission::Go),
+            queued: self.queued.load(Ordering::Relaxed),
+            refreshed_at: budget.map(|budget| budget.refreshed_at),
+        }
+    }
+}
+
+/// Decrements the queued count when a waiting background request goes ahead or gives up
+pub struct QueueGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueueGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+fn reserve(limit: usize) -> usize {
+    (limit * RESERVE_PERCENT / 100).max(MIN_RESERVE)
+}
+
+fn decide(rate: &RateLimitInfo, priority: Priority, now: u64) -> Admission {
+    let reset_in = rate.reset.saturating_sub(now);
+    match priority {
+        _ if rate.reset <= now => Admission::Go,
+        Priority::Interactive if rate.remaining == 0 => Admission::Refuse(reset_in),
+        Priority::Interactive => Admission::Go,
+        Priority::Background if rate.remaining <= reserve(rate.limit) => {
+            Admission::Wait(reset_in.clamp(1, MAX_WAIT_SECS))
+        }
+        Priority::Background => Admission::Go,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(remaining: usize) -> RateLimitInfo {
+        RateLimitInfo {
+            limit: 5000,
+            remaining,
+            used: 5000 - remaining,
+            reset: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_background_requests_leave_the_reserve_to_interactive_ones() {
+        let scheduler = RequestScheduler::default();
+        assert_eq!(
+            scheduler.admit("", Priority::Background, 900),
+            Admission::Go
+        );
+
+        scheduler.record("", rate(501), 900);
+        assert_eq!(
+            scheduler.admit("", Priority::Background, 900),
+            Admission::Go
+        );
+        // 500 left, the 10% reserve of a 5000 limit
+        assert_eq!(
+            scheduler.admit("", Priority::Background, 900),
+            Admission::Wait(60)
+        );
+        assert_eq!(
+            scheduler.admit("", Priority::Background, 990),
+            Admission::Wait(10)
+        );
+        assert_eq!(
+            scheduler.admit("", Priority::Interactive, 900),
+            Admission::Go
+        );
+        assert_eq!(
+            scheduler
+                .status("", None, 900)
+                .rate_limit
+                .unwrap()
+                .remaining,
+            499
+        );
+        assert!(scheduler.status("", None, 900).throttled);
+
+        scheduler.record("", rate(0), 900);
+        assert_eq!(
+            scheduler.admit("", Priority::Interactive, 900),
+            Admission::Refuse(100)
+        );
+        // Once the window resets, requests go ahead until the next report
+        assert_eq!(
+            scheduler.admit("", Priority::Background, 1_000),
+            Admission::Go
+        );
+        assert!(scheduler.stale("", 1_000));
+        assert!(!scheduler.stale("", 950));
+    }
+
+    #[test]
+    fn test_budgets_are_per_account() {
+        let scheduler = RequestScheduler::default();
+        scheduler.record("work", rate(0), 900);
+        assert_eq!(
+            scheduler.admit("work", Priority::Interactive, 900),
+            Admission::Refuse(100)
+        );
+        assert_eq!(
+            scheduler.admit("", Priority::Interactive, 900),
+            Admission::Go
+        );
+        assert_eq!(reserve(60), 6);
+        assert_eq!(reserve(20), MIN_RESERVE);
+    }
+}
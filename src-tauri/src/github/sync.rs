@@ -0,0 +1,186 @@
+use super::cache::IssueCache;
+use super::issues::Issue;
+use super::{GitHubState, Repository};
+use crate::config::{storage, SettingsState};
+use crate::events::schema::v1;
+use crate::events::GITHUB_SYNC_EVENT_NAME;
+use anyhow::{bail, Context, Result};
+use octocrab::etag::EntityTag;
+use octocrab::models::issues::Issue as GitHubIssue;
+use octocrab::Octocrab;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+/// Time between background syncs
+const SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Page size for incremental fetches (the API maximum)
+const PER_PAGE: usize = 100;
+
+/// Result of one incremental sync
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SyncReport {
+    /// Issues and pull requests added or changed in the cache
+    pub updated: usize,
+    /// GitHub answered 304: nothing changed since the last sync
+    pub not_modified: bool,
+}
+
+/// Background service keeping the local issue cache up to date
+///
+/// Each pass fetches only issues updated since the previous pass (`since`) and
+/// sends the previous ETag, so an unchanged repository costs a 304 that does not
+/// count against the rate limit.
+pub struct SyncService {
+    cache: Arc<IssueCache>,
+    wake: Arc<Notify>,
+}
+
+impl SyncService {
+    /// Open ~/.zeami/cache/github.db, falling back to an in-memory cache
+    pub fn open() -> Self {
+        let cache = storage::config_dir()
+            .map(|dir| dir.join("cache").join("github.db"))
+            .and_then(|path| IssueCache::open(&path))
+            .or_else(|e| {
+                tracing::warn!("Using in-memory issue cache: {:#}", e);
+                IssueCache::in_memory()
+            })
+            .expect("failed to create in-memory issue cache");
+
+        Self {
+            cache: Arc::new(cache),
+            wake: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn cache(&self) -> &IssueCache {
+        &self.cache
+    }
+
+    /// Run a sync now instead of waiting for the next interval
+    pub fn trigger(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Start the sync loop; results are emitted as `github-sync` events
+    pub fn spawn(&self, app: AppHandle) {
+        let cache = self.cache.clone();
+        let wake = self.wake.clone();
+
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let settings = app.state::<SettingsState>().current().github;
+                if let Ok(repo) = Repository::from_settings(&settings) {
+                    let result = match app
+                        .state::<GitHubState>()
+                        .background_client(&settings)
+                        .await
+                    {
+                        Ok(client) => sync_repository(&client, &cache, &repo).await,
+                        Err(e) => Err(e),
+                    };
+
+                    let payload = match result {
+                        Ok(report) => {
+                            tracing::debug!(repository = %repo, updated = report.updated, "GitHub sync finished");
+                            v1::GitHubSync {
+                                repository: repo.to_string(),
+                                updated: report.updated,
+                                not_modified: report.not_modified,
+                                error: None,
+                            }
+                        }
+                        Err(e) => {
+                            tracing::warn!(repository = %repo, "GitHub sync failed: {:#}", e);
+                            v1::GitHubSync {
+                                repository: repo.to_string(),
+                                updated: 0,
+                                not_modified: false,
+                                error: Some(format!("{:#}", e)),
+                            }
+                        }
+                    };
+                    if let Err(e) = app.emit_all(GITHUB_SYNC_EVENT_NAME, payload) {
+                        tracing::error!("Failed to emit GitHub sync: {}", e);
+                    }
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(SYNC_INTERVAL) => {}
+                    _ = wake.notified() => {}
+                }
+            }
+        });
+    }
+}
+
+/// Fetch issues and pull requests updated since the last sync into the cache
+pub async fn sync_repository(
+    client: &Octocrab,
+    cache: &IssueCache,
+    repo: &Repository,
+) -> Result<SyncReport> {
+    let key = repo.to_string();
+    let cursor = cache.cursor(&key)?;
+    let mut next = cursor.clone();
+    let mut report = SyncReport::default();
+
+    for page in 1.. {
+        let mut uri = format!(
+            "/repos/{}/{}/issues?state=all&sort=updated&direction=asc&per_page={}&page={}",
+            repo.owner, repo.name, PER_PAGE, page
+        );
+        if let Some(since) = &cursor.since {
+            uri.push_str(&format!("&since={}", since));
+        }
+
+        let mut headers = Default::default();
+        if page == 1 {
+            if let Some(etag) = cursor.etag.as_deref().and_then(|tag| tag.parse().ok()) {
+                EntityTag::insert_if_none_match_header(&mut headers, etag)?;
+            }
+        }
+
+        let response = client
+            ._get_with_headers(uri.as_str(), Some(headers))
+            .await
+            .context("Failed to fetch issues")?;
+        if page == 1 {
+            next.etag = EntityTag::extract_from_response(&response).map(|tag| tag.to_string());
+        }
+
+        let status = response.status();
+        if status.as_u16() == 304 {
+            report.not_modified = true;
+            return Ok(report);
+        }
+        let body = client.body_to_string(response).await?;
+        if !status.is_success() {
+            bail!("GitHub returned {}: {}", status, body);
+        }
+
+        let issues: Vec<GitHubIssue> =
+            serde_json::from_str(&body).context("Unexpected issue list response")?;
+        let count = issues.len();
+        for issue in issues {
+            let since = issue.updated_at.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+            if next.since.as_deref() < Some(since.as_str()) {
+                next.since = Some(since);
+            }
+            let pull_request = issue.pull_request.is_some();
+            if cache.upsert(&key, &Issue::from(issue), pull_request)? {
+                report.updated += 1;
+            }
+        }
+
+        if count < PER_PAGE {
+            break;
+        }
+    }
+
+    cache.set_cursor(&key, &next)?;
+    Ok(report)
+}
@@ -0,0 +1,240 @@
+//! Issue templates and labels for the "create issue" dialog
+//!
+//! Templates are the Markdown files in `.github/ISSUE_TEMPLATE/` with their
+//! front matter (`name`, `about`, `title`, `labels`, `assignees`). YAML issue
+//! forms are not rendered and are skipped.
+
+use super::Repository;
+use anyhow::{Context, Result};
+use octocrab::models::Label as GitHubLabel;
+use octocrab::Octocrab;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Directory GitHub reads issue templates from
+const TEMPLATE_DIR: &str = ".github/ISSUE_TEMPLATE";
+
+/// An issue template of the repository
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct IssueTemplate {
+    /// File name, e.g. `bug_report.md`
+    pub file: String,
+    pub name: String,
+    pub about: String,
+    pub title: String,
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    pub body: String,
+}
+
+/// Prefilled fields for a new issue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssueDraft {
+    pub title: String,
+    pub body: String,
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+}
+
+/// A repository label
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Label {
+    pub name: String,
+    /// Hex color without `#`
+    pub color: String,
+    pub description: String,
+}
+
+impl From<GitHubLabel> for Label {
+    fn from(label: GitHubLabel) -> Self {
+        Self {
+            name: label.name,
+            color: label.color,
+            description: label.description.unwrap_or_default(),
+        }
+    }
+}
+
+/// Markdown issue templates on the default branch; empty when the repository has none
+pub async fn list_issue_templates(
+    client: &Octocrab,
+    repo: &Repository,
+) -> Result<Vec<IssueTemplate>> {
+    let repos = client.repos(&repo.owner, &repo.name);
+    let mut dir = match repos.get_content().path(TEMPLATE_DIR).send().await {
+        Ok(dir) => dir,
+        Err(octocrab::Error::GitHub { source, .. }) if source.status_code.as_u16() == 404 => {
+            return Ok(Vec::new())
+        }
+        Err(e) => return Err(e).context("Failed to list issue templates"),
+    };
+
+    let mut templates = Vec::new();
+    for entry in dir.take_items() {
+        if entry.r#type != "file" || !entry.name.ends_with(".md") {
+            continue;
+        }
+        let mut file = repos
+            .get_content()
+            .path(&entry.path)
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch {}", entry.path))?;
+        let content = file
+            .take_items()
+            .into_iter()
+            .next()
+            .and_then(|item| item.decoded_content())
+            .unwrap_or_default();
+        templates.push(parse_template(&entry.name, &content));
+    }
+    Ok(templates)
+}
+
+/// Fill `{key}` placeholders in the template's title and body
+/// `{repository}` is always available; unknown placeholders are left as is
+pub fn render_template(
+    template: &IssueTemplate,
+    repo: &Repository,
+    values: &HashMap<String, String>,
+) -> IssueDraft {
+    let substitute = |text: &str| {
+        let mut text = text.replace("{repository}", &repo.to_string());
+        for (key, value) in values {
+            text = text.replace(&format!("{{{}}}", key), value);
+        }
+        text
+    };
+
+    IssueDraft {
+        title: substitute(&template.title),
+        body: substitute(&template.body),
+        labels: template.labels.clone(),
+        assignees: template.assignees.clone(),
+    }
+}
+
+pub async fn list_labels(client: &Octocrab, repo: &Repository) -> Result<Vec<Label>> {
+    let first = client
+        .issues(&repo.owner, &repo.name)
+        .list_labels_for_repo()
+        .per_page(100)
+        .send()
+        .await
+        .context("Failed to list labels")?;
+    let labels = client
+        .all_pages(first)
+        .await
+        .context("Failed to list labels")?;
+    Ok(labels.into_iter().map(Label::from).collect())
+}
+
+pub async fn create_label(
+    client: &Octocrab,
+    repo: &Repository,
+    name: &str,
+    color: &str,
+    description: &str,
+) -> Result<Label> {
+    let label = client
+        .issues(&repo.owner, &repo.name)
+        .create_label(name, color.trim_start_matches('#'), description)
+        .await
+        .with_context(|| format!("Failed to create label {}", name))?;
+    Ok(label.into())
+}
+
+/// Split a template into front matter and body
+fn parse_template(file: &str, content: &str) -> IssueTemplate {
+    let mut template = IssueTemplate {
+        file: file.to_string(),
+        name: file.trim_end_matches(".md").to_string(),
+        ..IssueTemplate::default()
+    };
+
+    let content = content.replace("\r\n", "\n");
+    let Some((front_matter, body)) = content
+        .strip_prefix("---\n")
+        .and_then(|rest| rest.split_once("\n---"))
+    else {
+        template.body = content;
+        return template;
+    };
+    template.body = body.trim_start_matches('-').trim_start().to_string();
+
+    let mut current_list: Option<&str> = None;
+    for line in front_matter.lines() {
+        if let Some(item) = line.trim_start().strip_prefix("- ") {
+            match current_list {
+                Some("labels") => template.labels.push(unquote(item)),
+                Some("assignees") => template.assignees.push(unquote(item)),
+                _ => {}
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim();
+        let value = value.trim();
+        current_list = Some(key).filter(|_| value.is_empty());
+        match key {
+            "name" => template.name = unquote(value),
+            "about" => template.about = unquote(value),
+            "title" => template.title = unquote(value),
+            "labels" => template.labels.extend(parse_list(value)),
+            "assignees" => template.assignees.extend(parse_list(value)),
+            _ => {}
+        }
+    }
+    template
+}
+
+/// `a, b` or `[a, "b"]`
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(unquote)
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn unquote(value: &str) -> String {
+    value
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_template_front_matter() {
+        let content = "---\nname: Bug report\nabout: Something is broken\ntitle: \"[Bug] {summary}\"\nlabels: bug, 'needs triage'\nassignees:\n  - octocat\n---\n\n**Describe the bug** in {repository}\n";
+
+        let template = parse_template("bug_report.md", content);
+        assert_eq!(template.name, "Bug report");
+        assert_eq!(template.about, "Something is broken");
+        assert_eq!(template.labels, vec!["bug", "needs triage"]);
+        assert_eq!(template.assignees, vec!["octocat"]);
+        assert_eq!(template.body, "**Describe the bug** in {repository}\n");
+
+        let repo = Repository::parse("o/r").unwrap();
+        let values = HashMap::from([("summary".to_string(), "Crash".to_string())]);
+        let draft = render_template(&template, &repo, &values);
+        assert_eq!(draft.title, "[Bug] Crash");
+        assert_eq!(draft.body, "**Describe the bug** in o/r\n");
+    }
+
+    #[test]
+    fn test_template_without_front_matter() {
+        let template = parse_template("plain.md", "Just a body");
+        assert_eq!(template.name, "plain");
+        assert_eq!(template.body, "Just a body");
+        assert!(template.labels.is_empty());
+    }
+}
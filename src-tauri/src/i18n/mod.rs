@@ -0,0 +1,176 @@
+//! Translations of backend-generated text
+//!
+//! Locale bundles are flat JSON maps from message key to text, compiled into
+//! the binary from src-tauri/locales. Text may contain `{name}` placeholders
+//! filled by `format`. The current locale follows `ui.language`; keys missing
+//! from a bundle fall back to English, so a partial translation never shows
+//! raw keys. The frontend gets the same bundles through get_translations.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{OnceLock, RwLock};
+
+/// Bundles compiled into the app, English first
+const BUNDLES: [(&str, &str); 2] = [
+    ("en", include_str!("../../locales/en.json")),
+    ("ja", include_str!("../../locales/ja.json")),
+];
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Locale of backend text, set from ui.language
+static CURRENT: RwLock<&'static str> = RwLock::new(DEFAULT_LOCALE);
+
+/// All messages of a locale, for the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Translations {
+    /// The bundled locale used, after fallback
+    pub locale: String,
+    pub messages: BTreeMap<String, String>,
+}
+
+/// Locales with a bundle
+pub fn supported_locales() -> Vec<&'static str> {
+    BUNDLES.iter().map(|(locale, _)| *locale).collect()
+}
+
+/// The bundled locale for a language tag: `ja-JP` and `ja_JP` give `ja`
+pub fn resolve(language: &str) -> Option<&'static str> {
+    let language = language.trim().to_ascii_lowercase();
+    let primary = language.split(['-', '_']).next().unwrap_or_default();
+    BUNDLES
+        .iter()
+        .map(|(locale, _)| *locale)
+        .find(|locale| *locale == language || *locale == primary)
+}
+
+/// Use `language` for backend text from now on; unsupported languages give English
+pub fn set_current(language: &str) {
+    let locale = resolve(language).unwrap_or(DEFAULT_LOCALE);
+    if let Ok(mut current) = CURRENT.write() {
+        *current = locale;
+    }
+}
+
+pub fn current() -> &'static str {
+    CURRENT
+        .read()
+        .map(|current| *current)
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// Every message of `language`, English where it has no translation
+pub fn translations(language: &str) -> Translations {
+    let locale = resolve(language).unwrap_or(DEFAULT_LOCALE);
+    let mut messages: BTreeMap<String, String> =
+        bundle(DEFAULT_LOCALE).clone().into_iter().collect();
+    messages.extend(bundle(locale).clone());
+    Translations {
+        locale: locale.to_string(),
+        messages,
+    }
+}
+
+/// The message `key` in the current locale
+pub fn text(key: &str) -> String {
+    lookup(current(), key)
+}
+
+/// The message `key` in the current locale with its `{name}` placeholders filled
+pub fn format(key: &str, args: &[(&str, &str)]) -> String {
+    fill(&text(key), args)
+}
+
+fn lookup(locale: &str, key: &str) -> String {
+    bundle(locale)
+        .get(key)
+        .or_else(|| bundle(DEFAULT_LOCALE).get(key))
+        .cloned()
+        .unwrap_or_else(|| {
+            tracing::warn!(key, "Missing translation");
+            key.to_string()
+        })
+}
+
+fn fill(template: &str, args: &[(&str, &str)]) -> String {
+    let mut filled = template.to_string();
+    for (name, value) in args {
+        filled = filled.replace(&format!("{{{}}}", name), value);
+    }
+    filled
+}
+
+fn bundle(locale: &str) -> &'static HashMap<String, String> {
+    static PARSED: OnceLock<HashMap<&'static str, HashMap<String, String>>> = OnceLock::new();
+    let parsed = PARSED.get_or_init(|| {
+        BUNDLES
+            .iter()
+            .map(|(locale, json)| {
+                let messages = serde_json::from_str(json)
+                    .unwrap_or_else(|e| panic!("invalid {} locale bundle: {}", locale, e));
+                (*locale, messages)
+            })
+            .collect()
+    });
+    parsed
+        .get(locale)
+        .unwrap_or_else(|| &parsed[DEFAULT_LOCALE])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn placeholders(text: &str) -> BTreeSet<&str> {
+        text.split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect()
+    }
+
+    #[test]
+    fn test_bundles_match_english() {
+        let english = bundle(DEFAULT_LOCALE);
+        for locale in supported_locales() {
+            for (key, text) in bundle(locale) {
+                let source = english
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{}: {} is not an English key", locale, key));
+                assert_eq!(
+                    placeholders(text),
+                    placeholders(source),
+                    "{}: {}",
+                    locale,
+                    key
+                );
+            }
+        }
+        assert_eq!(bundle("ja").len(), english.len());
+    }
+
+    #[test]
+    fn test_resolves_language_tags() {
+        assert_eq!(resolve("ja-JP"), Some("ja"));
+        assert_eq!(resolve("JA_jp"), Some("ja"));
+        assert_eq!(resolve("en"), Some("en"));
+        assert_eq!(resolve("fr"), None);
+        assert_eq!(translations("fr").locale, "en");
+    }
+
+    #[test]
+    fn test_fills_placeholders_and_falls_back() {
+        assert_eq!(
+            fill(
+                &lookup("ja", "workflow.message.pushed"),
+                &[("branch", "fix-1"), ("remote", "origin")]
+            ),
+            "fix-1 を origin にプッシュしました"
+        );
+        assert_eq!(lookup("en", "no.such.key"), "no.such.key");
+        assert_eq!(
+            lookup("xx", "notification.tests_passed.title"),
+            "Tests passed"
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Activity timeline of a project
+//!
+//! Commits, test runs, progress syncs, Claude agent sessions and long terminal
+//! commands are appended as normalized entries to <project>/.zeami/journal.jsonl,
+//! one JSON object per line. query_journal reads them back by time range and
+//! kind for the "what happened today" timeline, and progress comments list the
+//! entries since their previous sync. Recording is best-effort: a journal that
+//! cannot be written never fails what is being recorded.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Journal file, relative to the project root
+pub const JOURNAL_FILE: &str = ".zeami/journal.jsonl";
+
+/// Size at which the oldest half of the journal is dropped
+const MAX_JOURNAL_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Entries returned by one query at most, the newest ones
+const MAX_QUERY_ENTRIES: usize = 2000;
+
+/// Characters kept of a summary made with one_line
+const MAX_SUMMARY_CHARS: usize = 100;
+
+/// Serializes appends and compaction
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalKind {
+    Commit,
+    TestsPassed,
+    TestsFailed,
+    IssueSynced,
+    ClaudeSession,
+    CommandFinished,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub at: DateTime<Utc>,
+    pub kind: JournalKind,
+    /// One line for the timeline, e.g. a commit summary
+    pub summary: String,
+    /// Commit id, issue number, conversation id or test run id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+impl JournalEntry {
+    pub fn new(kind: JournalKind, summary: impl Into<String>) -> Self {
+        Self {
+            at: Utc::now(),
+            kind,
+            summary: summary.into(),
+            reference: None,
+            duration_ms: None,
+        }
+    }
+
+    pub fn reference(mut self, reference: impl Into<String>) -> Self {
+        self.reference = Some(reference.into());
+        self
+    }
+
+    pub fn duration_ms(mut self, duration_ms: u64) -> Self {
+        self.duration_ms = Some(duration_ms);
+        self
+    }
+}
+
+/// Time range of query_journal; open ends are unbounded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalRange {
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl JournalRange {
+    pub fn since(from: DateTime<Utc>) -> Self {
+        Self {
+            from: Some(from),
+            to: None,
+        }
+    }
+
+    fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.from.is_none_or(|from| at >= from) && self.to.is_none_or(|to| at < to)
+    }
+}
+
+pub fn journal_path(project_root: &Path) -> PathBuf {
+    project_root.join(JOURNAL_FILE)
+}
+
+/// Append `entry` to the journal of the project at `project_root`
+pub fn append(project_root: &Path, entry: &JournalEntry) -> Result<()> {
+    let path = journal_path(project_root);
+    let _guard = WRITE_LOCK.lock().unwrap();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {:?}", path))?;
+    file.write_all(line.as_bytes())?;
+
+    if file.metadata()?.len() > MAX_JOURNAL_BYTES {
+        compact(&path)?;
+    }
+    Ok(())
+}
+
+/// Append `entry`, logging rather than returning a failure
+pub fn record(project_root: &Path, entry: JournalEntry) {
+    if let Err(e) = append(project_root, &entry) {
+        tracing::warn!(path = ?project_root, kind = ?entry.kind, "Failed to write journal: {:#}", e);
+    }
+}
+
+/// Entries in `range` of the given kinds (all kinds when empty), oldest first
+///
+/// Lines that cannot be parsed, e.g. from an interrupted write, are skipped.
+pub fn query(
+    project_root: &Path,
+    range: JournalRange,
+    kinds: &[JournalKind],
+) -> Result<Vec<JournalEntry>> {
+    let path = journal_path(project_root);
+    let file = match fs::File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", path)),
+    };
+    let mut entries: Vec<JournalEntry> = BufReader::new(file)
+        .lines()
+        .map_while(|line| line.ok())
+        .filter_map(|line| serde_json::from_str::<JournalEntry>(&line).ok())
+        .filter(|entry| range.contains(entry.at))
+        .filter(|entry| kinds.is_empty() || kinds.contains(&entry.kind))
+        .collect();
+    entries.sort_by_key(|entry| entry.at);
+    if entries.len() > MAX_QUERY_ENTRIES {
+        entries.drain(..entries.len() - MAX_QUERY_ENTRIES);
+    }
+    Ok(entries)
+}
+
+/// First non-blank line of `text`, shortened for the timeline
+pub fn one_line(text: &str) -> String {
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    match line.char_indices().nth(MAX_SUMMARY_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+/// Keep the newer half of the journal
+fn compact(path: &Path) -> Result<()> {
+    let text = fs::read_to_string(path)?;
+    let lines: Vec<&str> = text.lines().collect();
+    let kept = lines[lines.len() / 2..].join("\n") + "\n";
+    crate::config::atomic::write_atomic(path, kept.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_queries_by_range_and_kind() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = Utc::now();
+        let mut old = JournalEntry::new(JournalKind::Commit, "Add parser").reference("abc1234");
+        old.at = now - Duration::days(2);
+        append(dir.path(), &old).unwrap();
+        append(
+            dir.path(),
+            &JournalEntry::new(JournalKind::TestsFailed, "1 failed").duration_ms(900),
+        )
+        .unwrap();
+        append(
+            dir.path(),
+            &JournalEntry::new(JournalKind::Commit, "Fix parser"),
+        )
+        .unwrap();
+        // A torn line from an interrupted write
+        OpenOptions::new()
+            .append(true)
+            .open(journal_path(dir.path()))
+            .unwrap()
+            .write_all(b"{\"at\":")
+            .unwrap();
+
+        let all = query(dir.path(), JournalRange::default(), &[]).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0], old);
+
+        let today = query(
+            dir.path(),
+            JournalRange::since(now - Duration::hours(1)),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(today.len(), 2);
+
+        let commits = query(
+            dir.path(),
+            JournalRange::since(now - Duration::hours(1)),
+            &[JournalKind::Commit],
+        )
+        .unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary, "Fix parser");
+
+        let before = JournalRange {
+            from: None,
+            to: Some(now - Duration::days(1)),
+        };
+        assert_eq!(query(dir.path(), before, &[]).unwrap(), [old]);
+    }
+
+    #[test]
+    fn test_shortens_summaries_to_one_line() {
+        assert_eq!(one_line("\n  Fix the parser  \nand more"), "Fix the parser");
+        let long = one_line(&"é".repeat(150));
+        assert_eq!(long.chars().count(), MAX_SUMMARY_CHARS + 1);
+        assert!(long.ends_with('…'));
+    }
+
+    #[test]
+    fn test_missing_journal_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(query(dir.path(), JournalRange::default(), &[]).unwrap(), []);
+    }
+}
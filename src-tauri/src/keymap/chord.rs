@@ -0,0 +1,179 @@
+use anyhow::{bail, Result};
+
+/// Keys readline and the shell act on when pressed with Ctrl alone
+const CTRL_KEYS: &[&str] = &["[", "\\", "]", "/", "Space", "Left", "Right"];
+
+/// Keys readline acts on when pressed with Alt alone
+const ALT_KEYS: &[&str] = &["B", "F", "D", ".", "Backspace"];
+
+/// Key names other than single characters and F1 to F24
+const NAMED_KEYS: &[(&str, &[&str])] = &[
+    ("Enter", &["enter", "return"]),
+    ("Tab", &["tab"]),
+    ("Escape", &["escape", "esc"]),
+    ("Space", &["space"]),
+    ("Backspace", &["backspace"]),
+    ("Delete", &["delete", "del"]),
+    ("Insert", &["insert", "ins"]),
+    ("Up", &["up", "arrowup"]),
+    ("Down", &["down", "arrowdown"]),
+    ("Left", &["left", "arrowleft"]),
+    ("Right", &["right", "arrowright"]),
+    ("Home", &["home"]),
+    ("End", &["end"]),
+    ("PageUp", &["pageup", "pgup"]),
+    ("PageDown", &["pagedown", "pgdn"]),
+];
+
+/// A key with modifiers, written like `Ctrl+Shift+T`
+///
+/// Parsing is case-insensitive and accepts common aliases (Control, Option,
+/// Command, Meta, Esc, ArrowUp); `Mod` is Cmd on macOS and Ctrl+Shift
+/// elsewhere. Display gives the canonical form with modifiers in the order
+/// Ctrl, Alt, Shift, Cmd.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// Cmd on macOS, the Windows or Super key elsewhere
+    pub cmd: bool,
+    pub key: String,
+}
+
+impl Chord {
+    pub fn parse(text: &str, mac: bool) -> Result<Self> {
+        let text = text.trim();
+        // `Ctrl++` binds the plus key
+        let (modifiers, key) = match text.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+        let mut chord = Chord {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            cmd: false,
+            key: normalize_key(key.trim())?,
+        };
+        for modifier in modifiers.split('+').filter(|part| !part.trim().is_empty()) {
+            match modifier.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => chord.ctrl = true,
+                "alt" | "option" | "opt" => chord.alt = true,
+                "shift" => chord.shift = true,
+                "cmd" | "command" | "meta" | "super" | "win" => chord.cmd = true,
+                "mod" if mac => chord.cmd = true,
+                "mod" => {
+                    chord.ctrl = true;
+                    chord.shift = true;
+                }
+                other => bail!("Unknown modifier {} in {}", other, text),
+            }
+        }
+        Ok(chord)
+    }
+
+    /// Whether the terminal needs the chord: typing, Ctrl or Alt with a key
+    /// readline uses, or Ctrl with a letter
+    pub fn is_reserved(&self) -> bool {
+        let function_key = self.key.len() > 1
+            && self.key.starts_with('F')
+            && self.key[1..].chars().all(|c| c.is_ascii_digit());
+        let key = self.key.as_str();
+        match (self.ctrl, self.alt, self.shift, self.cmd) {
+            (false, false, _, false) => !function_key,
+            (true, false, false, false) => {
+                (key.len() == 1 && key.chars().all(|c| c.is_ascii_alphabetic()))
+                    || CTRL_KEYS.contains(&key)
+            }
+            (false, true, false, false) => ALT_KEYS.contains(&key),
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Chord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (on, name) in [
+            (self.ctrl, "Ctrl+"),
+            (self.alt, "Alt+"),
+            (self.shift, "Shift+"),
+            (self.cmd, "Cmd+"),
+        ] {
+            if on {
+                f.write_str(name)?;
+            }
+        }
+        f.write_str(&self.key)
+    }
+}
+
+/// Chords that cannot be bound, for the Settings page
+pub fn reserved() -> Vec<String> {
+    let ctrl = ('A'..='Z')
+        .map(String::from)
+        .chain(CTRL_KEYS.iter().map(|key| key.to_string()))
+        .map(|key| format!("Ctrl+{}", key));
+    let alt = ALT_KEYS.iter().map(|key| format!("Alt+{}", key));
+    ctrl.chain(alt).collect()
+}
+
+fn normalize_key(key: &str) -> Result<String> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => bail!("The chord has no key"),
+        (Some(c), None) if c.is_ascii_graphic() => return Ok(c.to_ascii_uppercase().to_string()),
+        _ => {}
+    }
+    let lower = key.to_ascii_lowercase();
+    if let Some((name, _)) = NAMED_KEYS
+        .iter()
+        .find(|(_, aliases)| aliases.contains(&lower.as_str()))
+    {
+        return Ok(name.to_string());
+    }
+    if let Some(number) = lower
+        .strip_prefix('f')
+        .and_then(|number| number.parse::<u8>().ok())
+        .filter(|number| (1..=24).contains(number))
+    {
+        return Ok(format!("F{}", number));
+    }
+    bail!("Unknown key {}", key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_and_normalizes_chords() {
+        let chord = |text: &str| Chord::parse(text, false).unwrap().to_string();
+        assert_eq!(chord("shift+control+t"), "Ctrl+Shift+T");
+        assert_eq!(chord("Mod+,"), "Ctrl+Shift+,");
+        assert_eq!(Chord::parse("Mod+,", true).unwrap().to_string(), "Cmd+,");
+        assert_eq!(chord("Option+ArrowUp"), "Alt+Up");
+        assert_eq!(chord("ctrl++"), "Ctrl++");
+        assert_eq!(chord("f12"), "F12");
+        assert_eq!(chord("esc"), "Escape");
+        assert!(Chord::parse("Hyper+T", false).is_err());
+        assert!(Chord::parse("Ctrl+", false).is_err());
+        assert!(Chord::parse("Ctrl+F99", false).is_err());
+    }
+
+    #[test]
+    fn test_reserves_keys_the_terminal_needs() {
+        let is_reserved = |text: &str| Chord::parse(text, false).unwrap().is_reserved();
+        assert!(is_reserved("Ctrl+C"));
+        assert!(is_reserved("Ctrl+["));
+        assert!(is_reserved("Alt+B"));
+        assert!(is_reserved("Shift+A"));
+        assert!(is_reserved("Enter"));
+        assert!(!is_reserved("F5"));
+        assert!(!is_reserved("Ctrl+Shift+C"));
+        assert!(!is_reserved("Ctrl+PageDown"));
+        assert!(!is_reserved("Cmd+K"));
+        assert!(!is_reserved("Alt+T"));
+        assert!(reserved().len() > 26);
+    }
+}
@@ -0,0 +1,384 @@
+//! Keyboard shortcuts
+//!
+//! Every action the frontend binds has a default chord (see DEFAULT_BINDINGS);
+//! UISettings.keybindings holds the user's changes, action id to chord, with
+//! an empty chord for an unbound action. Chords are validated here: a chord
+//! the terminal needs (Ctrl+C, readline keys, plain typing) cannot be bound,
+//! and two actions cannot share one. Keymaps are exported and imported as
+//! JSON files of the changed bindings.
+
+mod chord;
+
+pub use chord::Chord;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Action ids with their default chords and descriptions
+///
+/// `Mod` is Cmd on macOS and Ctrl+Shift elsewhere, where Ctrl alone belongs
+/// to the terminal.
+pub const DEFAULT_BINDINGS: &[(&str, &str, &str)] = &[
+    ("terminal.new_tab", "Mod+T", "Open a terminal tab"),
+    ("terminal.close_tab", "Mod+W", "Close the terminal tab"),
+    ("terminal.next_tab", "Ctrl+PageDown", "Next tab"),
+    ("terminal.previous_tab", "Ctrl+PageUp", "Previous tab"),
+    (
+        "terminal.split_right",
+        "Mod+D",
+        "Split the pane side by side",
+    ),
+    (
+        "terminal.split_down",
+        "Mod+E",
+        "Split the pane top and bottom",
+    ),
+    ("terminal.focus_next_pane", "Mod+]", "Focus the next pane"),
+    (
+        "terminal.focus_previous_pane",
+        "Mod+[",
+        "Focus the previous pane",
+    ),
+    ("terminal.find", "Mod+F", "Find in the terminal"),
+    ("terminal.clear", "Mod+K", "Clear the terminal"),
+    ("terminal.copy", "Mod+C", "Copy the selection"),
+    ("terminal.paste", "Mod+V", "Paste"),
+    ("app.command_palette", "Mod+P", "Open the command palette"),
+    ("app.settings", "Mod+,", "Open Settings"),
+    ("claude.open", "Mod+L", "Ask Claude"),
+    ("git.commit", "Mod+Enter", "Commit staged changes"),
+];
+
+/// Version of exported keymap files
+const KEYMAP_FILE_VERSION: u32 = 1;
+
+/// One action of get_keymap
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Binding {
+    pub action: String,
+    pub description: String,
+    /// None while unbound
+    pub chord: Option<String>,
+    pub default_chord: String,
+    /// Changed from the default
+    pub customized: bool,
+}
+
+/// Why a chord cannot be used for an action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum KeyConflict {
+    /// The terminal needs the chord
+    Reserved { action: String, chord: String },
+    /// Another action has the chord
+    Binding {
+        action: String,
+        other: String,
+        chord: String,
+    },
+}
+
+impl std::fmt::Display for KeyConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyConflict::Reserved { chord, .. } => {
+                write!(f, "{} is used by the terminal", chord)
+            }
+            KeyConflict::Binding { other, chord, .. } => {
+                write!(f, "{} is already bound to {}", chord, other)
+            }
+        }
+    }
+}
+
+/// Returned by get_keymap and the commands changing it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keymap {
+    pub bindings: Vec<Binding>,
+    /// Chords that cannot be bound, e.g. `Ctrl+C`
+    pub reserved: Vec<String>,
+    /// Problems in the saved bindings, e.g. after editing the settings file
+    pub conflicts: Vec<KeyConflict>,
+}
+
+/// Contents of an exported keymap
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeymapFile {
+    pub version: u32,
+    /// Changed bindings; an empty chord unbinds the action
+    pub bindings: BTreeMap<String, String>,
+}
+
+/// Whether `Mod` means Cmd on this platform
+pub fn is_mac() -> bool {
+    cfg!(target_os = "macos")
+}
+
+/// Effective bindings for the user's changes in `overrides`
+pub fn keymap(overrides: &BTreeMap<String, String>, mac: bool) -> Keymap {
+    let bindings: Vec<Binding> = DEFAULT_BINDINGS
+        .iter()
+        .map(|(action, default, description)| {
+            let default_chord = Chord::parse(default, mac)
+                .expect("default bindings are valid")
+                .to_string();
+            let chord = match overrides.get(*action) {
+                Some(chord) if chord.trim().is_empty() => None,
+                Some(chord) => Some(
+                    Chord::parse(chord, mac)
+                        .map(|chord| chord.to_string())
+                        .unwrap_or_else(|_| chord.clone()),
+                ),
+                None => Some(default_chord.clone()),
+            };
+            Binding {
+                action: action.to_string(),
+                description: description.to_string(),
+                customized: chord.as_deref() != Some(default_chord.as_str()),
+                chord,
+                default_chord,
+            }
+        })
+        .collect();
+    Keymap {
+        conflicts: conflicts(&bindings, mac),
+        bindings,
+        reserved: chord::reserved(),
+    }
+}
+
+/// Bind `action` to `chord` in `overrides`; None restores the default and
+/// an empty chord unbinds the action
+pub fn set_binding(
+    overrides: &mut BTreeMap<String, String>,
+    action: &str,
+    chord: Option<&str>,
+    mac: bool,
+) -> Result<()> {
+    let Some((_, default, _)) = DEFAULT_BINDINGS.iter().find(|(id, ..)| *id == action) else {
+        bail!("Unknown action {}", action);
+    };
+    let default = Chord::parse(default, mac)?.to_string();
+    let mut updated = overrides.clone();
+    match chord {
+        None => {
+            updated.remove(action);
+        }
+        Some(chord) if chord.trim().is_empty() => {
+            updated.insert(action.to_string(), String::new());
+        }
+        Some(chord) => {
+            let chord = Chord::parse(chord, mac)?.to_string();
+            if chord == default {
+                updated.remove(action);
+            } else {
+                updated.insert(action.to_string(), chord);
+            }
+        }
+    }
+    let bindings = keymap(&updated, mac).bindings;
+    let bound = bindings
+        .iter()
+        .find(|binding| binding.action == action)
+        .and_then(|binding| binding.chord.clone());
+    if let Some(chord) = bound {
+        if Chord::parse(&chord, mac)?.is_reserved() {
+            bail!(
+                "{}",
+                KeyConflict::Reserved {
+                    action: action.to_string(),
+                    chord,
+                }
+            );
+        }
+        if let Some(other) = bindings
+            .iter()
+            .find(|binding| binding.action != action && binding.chord.as_ref() == Some(&chord))
+        {
+            bail!(
+                "{}",
+                KeyConflict::Binding {
+                    action: action.to_string(),
+                    other: other.action.clone(),
+                    chord,
+                }
+            );
+        }
+    }
+    *overrides = updated;
+    Ok(())
+}
+
+/// Write the changed bindings to `path`
+pub fn export(overrides: &BTreeMap<String, String>, path: &Path) -> Result<()> {
+    let file = KeymapFile {
+        version: KEYMAP_FILE_VERSION,
+        bindings: overrides.clone(),
+    };
+    std::fs::write(path, serde_json::to_string_pretty(&file)?)
+        .with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// Bindings of the keymap file at `path`, replacing all changes
+///
+/// Unknown actions are skipped, e.g. from a newer version; a conflict fails
+/// the whole import.
+pub fn import(path: &Path, mac: bool) -> Result<BTreeMap<String, String>> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let file: KeymapFile = serde_json::from_str(&text).context("Invalid keymap file")?;
+    if file.version > KEYMAP_FILE_VERSION {
+        bail!("The keymap was exported by a newer version of Zeami");
+    }
+
+    let mut overrides = BTreeMap::new();
+    for (action, chord) in file.bindings {
+        if !DEFAULT_BINDINGS.iter().any(|(id, ..)| *id == action) {
+            tracing::warn!(%action, "Skipped unknown action in keymap");
+            continue;
+        }
+        let chord = if chord.trim().is_empty() {
+            String::new()
+        } else {
+            Chord::parse(&chord, mac)
+                .with_context(|| format!("Invalid chord for {}", action))?
+                .to_string()
+        };
+        overrides.insert(action, chord);
+    }
+    if let Some(conflict) = keymap(&overrides, mac).conflicts.into_iter().next() {
+        bail!("{}: {}", conflict.action(), conflict);
+    }
+    Ok(overrides)
+}
+
+impl KeyConflict {
+    /// The action whose binding is in conflict
+    pub fn action(&self) -> &str {
+        match self {
+            KeyConflict::Reserved { action, .. } | KeyConflict::Binding { action, .. } => action,
+        }
+    }
+}
+
+fn conflicts(bindings: &[Binding], mac: bool) -> Vec<KeyConflict> {
+    let mut conflicts = Vec::new();
+    let mut seen: BTreeMap<String, &str> = BTreeMap::new();
+    for binding in bindings {
+        let Some(text) = &binding.chord else {
+            continue;
+        };
+        let Ok(chord) = Chord::parse(text, mac) else {
+            continue;
+        };
+        if chord.is_reserved() {
+            conflicts.push(KeyConflict::Reserved {
+                action: binding.action.clone(),
+                chord: text.clone(),
+            });
+        } else if let Some(other) = seen.get(text) {
+            conflicts.push(KeyConflict::Binding {
+                action: binding.action.clone(),
+                other: other.to_string(),
+                chord: text.clone(),
+            });
+        } else {
+            seen.insert(text.clone(), &binding.action);
+        }
+    }
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_have_no_conflicts_on_any_platform() {
+        for mac in [true, false] {
+            let keymap = keymap(&BTreeMap::new(), mac);
+            assert_eq!(keymap.conflicts, []);
+            assert!(keymap.bindings.iter().all(|binding| !binding.customized));
+        }
+        let bindings = keymap(&BTreeMap::new(), false).bindings;
+        assert_eq!(bindings[0].chord.as_deref(), Some("Ctrl+Shift+T"));
+    }
+
+    #[test]
+    fn test_rejects_reserved_and_taken_chords() {
+        let mut overrides = BTreeMap::new();
+        let error = set_binding(&mut overrides, "terminal.find", Some("ctrl+r"), false)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "Ctrl+R is used by the terminal");
+        let error = set_binding(&mut overrides, "terminal.find", Some("Ctrl+Shift+T"), false)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "Ctrl+Shift+T is already bound to terminal.new_tab");
+        assert!(set_binding(&mut overrides, "nope", Some("F5"), false).is_err());
+        assert!(overrides.is_empty());
+
+        // Freeing a chord lets another action take it
+        set_binding(&mut overrides, "terminal.new_tab", Some(""), false).unwrap();
+        set_binding(&mut overrides, "terminal.find", Some("shift+ctrl+t"), false).unwrap();
+        assert_eq!(overrides["terminal.find"], "Ctrl+Shift+T");
+        let keymap = keymap(&overrides, false);
+        assert_eq!(keymap.bindings[0].chord, None);
+        assert!(keymap.bindings[0].customized);
+
+        // Taking a chord of an action later in the list is caught too
+        set_binding(&mut overrides, "terminal.new_tab", Some("F3"), false).unwrap();
+        let error = set_binding(
+            &mut overrides,
+            "terminal.new_tab",
+            Some("Ctrl+Shift+K"),
+            false,
+        )
+        .unwrap_err()
+        .to_string();
+        assert_eq!(error, "Ctrl+Shift+K is already bound to terminal.clear");
+
+        // Back to the default drops the override
+        set_binding(&mut overrides, "terminal.find", None, false).unwrap();
+        assert!(!overrides.contains_key("terminal.find"));
+    }
+
+    #[test]
+    fn test_reports_conflicts_in_saved_bindings() {
+        let overrides = BTreeMap::from([
+            ("terminal.find".to_string(), "Ctrl+C".to_string()),
+            ("terminal.clear".to_string(), "Cmd+T".to_string()),
+        ]);
+        let conflicts = keymap(&overrides, true).conflicts;
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].action(), "terminal.find");
+        assert_eq!(
+            conflicts[1],
+            KeyConflict::Binding {
+                action: "terminal.clear".into(),
+                other: "terminal.new_tab".into(),
+                chord: "Cmd+T".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_exports_and_imports_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keymap.json");
+        let mut overrides = BTreeMap::new();
+        set_binding(&mut overrides, "terminal.clear", Some("F6"), false).unwrap();
+        set_binding(&mut overrides, "git.commit", Some(""), false).unwrap();
+        export(&overrides, &path).unwrap();
+        assert_eq!(import(&path, false).unwrap(), overrides);
+
+        std::fs::write(
+            &path,
+            r#"{ "version": 1, "bindings": { "terminal.clear": "Ctrl+D", "old.action": "F1" } }"#,
+        )
+        .unwrap();
+        assert!(import(&path, false).is_err());
+    }
+}
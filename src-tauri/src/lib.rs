@@ -0,0 +1,37 @@
+//! Core of zeami shared by the desktop app and the `zeami` CLI
+//!
+//! The Tauri command layer lives in the app binary (src/main.rs); the CLI
+//! (src/bin/zeami) calls these modules directly so it works headless on CI
+//! and over SSH.
+
+pub mod capabilities;
+pub mod claude;
+pub mod config;
+pub mod containers;
+pub mod deeplink;
+pub mod diagnostics;
+pub mod error;
+pub mod events;
+pub mod files;
+pub mod frecency;
+pub mod git;
+pub mod github;
+pub mod i18n;
+pub mod journal;
+pub mod keymap;
+pub mod logging;
+pub mod monitoring;
+pub mod netwatch;
+pub mod notifications;
+pub mod onboarding;
+pub mod plugins;
+pub mod pty;
+pub mod scheduler;
+pub mod search;
+pub mod snippets;
+pub mod state;
+pub mod tasks;
+pub mod telemetry;
+pub mod themes;
+pub mod updater;
+pub mod workflow;
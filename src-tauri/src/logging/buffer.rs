@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+/// Number of log records kept in memory for the in-app log viewer
+pub const DEFAULT_CAPACITY: usize = 2000;
+
+/// A single captured log record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Bounded ring buffer of the most recent log records
+pub struct RecentLogs {
+    entries: Mutex<VecDeque<LogEntry>>,
+    capacity: usize,
+}
+
+impl RecentLogs {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    pub fn push(&self, entry: LogEntry) {
+        if let Ok(mut entries) = self.entries.lock() {
+            if entries.len() >= self.capacity {
+                entries.pop_front();
+            }
+            entries.push_back(entry);
+        }
+    }
+
+    /// Return up to `limit` newest entries at `level` or more severe, oldest first
+    pub fn recent(&self, level: Level, limit: usize) -> Vec<LogEntry> {
+        let entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut matched: Vec<LogEntry> = entries
+            .iter()
+            .rev()
+            .filter(|entry| {
+                Level::from_str(&entry.level)
+                    .map(|entry_level| entry_level <= level)
+                    .unwrap_or(true)
+            })
+            .take(limit)
+            .cloned()
+            .collect();
+
+        matched.reverse();
+        matched
+    }
+}
+
+impl Default for RecentLogs {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+/// Tracing layer that copies every enabled event into a RecentLogs buffer
+pub struct BufferLayer {
+    logs: std::sync::Arc<RecentLogs>,
+}
+
+impl BufferLayer {
+    pub fn new(logs: std::sync::Arc<RecentLogs>) -> Self {
+        Self { logs }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for BufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        self.logs.push(LogEntry {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+        });
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: BTreeMap<String, String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields
+                .insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(level: Level, message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: String::new(),
+            level: level.to_string(),
+            target: "zeami4".to_string(),
+            message: message.to_string(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_capacity_drops_oldest() {
+        let logs = RecentLogs::new(2);
+        logs.push(entry(Level::INFO, "one"));
+        logs.push(entry(Level::INFO, "two"));
+        logs.push(entry(Level::INFO, "three"));
+
+        let recent = logs.recent(Level::TRACE, 10);
+        let messages: Vec<_> = recent.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, vec!["two", "three"]);
+    }
+
+    #[test]
+    fn test_recent_filters_by_level_and_limit() {
+        let logs = RecentLogs::new(10);
+        logs.push(entry(Level::DEBUG, "debug"));
+        logs.push(entry(Level::WARN, "warn"));
+        logs.push(entry(Level::ERROR, "error"));
+        logs.push(entry(Level::INFO, "info"));
+
+        let warnings = logs.recent(Level::WARN, 10);
+        let messages: Vec<_> = warnings.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, vec!["warn", "error"]);
+
+        let limited = logs.recent(Level::TRACE, 1);
+        assert_eq!(limited.len(), 1);
+        assert_eq!(limited[0].message, "info");
+    }
+}
@@ -0,0 +1,171 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Number of recent durations per command used for the percentiles
+const DURATION_SAMPLES: usize = 100;
+
+/// Invocation statistics of one Tauri command since startup
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CommandStats {
+    pub command: String,
+    /// Completed invocations
+    pub calls: u64,
+    /// Completed invocations that returned an error
+    pub errors: u64,
+    pub mean_ms: f64,
+    /// Median and 95th percentile of the last 100 invocations
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    /// Size of the JSON arguments received from the frontend
+    pub max_arg_bytes: usize,
+    pub total_arg_bytes: usize,
+    pub last_called_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+struct Entry {
+    stats: CommandStats,
+    total: Duration,
+    recent: VecDeque<Duration>,
+}
+
+/// Per-command timings and outcomes, filled in by commands::middleware
+pub struct CommandMetrics {
+    entries: Mutex<BTreeMap<String, Entry>>,
+}
+
+impl CommandMetrics {
+    pub const fn new() -> Self {
+        Self {
+            entries: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// A command was invoked with `arg_bytes` of JSON arguments
+    pub fn invoked(&self, command: &str, arg_bytes: usize, at: DateTime<Utc>) {
+        self.with_entry(command, |entry| {
+            entry.stats.max_arg_bytes = entry.stats.max_arg_bytes.max(arg_bytes);
+            entry.stats.total_arg_bytes += arg_bytes;
+            entry.stats.last_called_at = Some(at);
+        });
+    }
+
+    /// A command returned after `duration`, with `error` when it failed
+    pub fn finished(&self, command: &str, duration: Duration, error: Option<String>) {
+        self.with_entry(command, |entry| {
+            entry.stats.calls += 1;
+            entry.total += duration;
+            if entry.recent.len() >= DURATION_SAMPLES {
+                entry.recent.pop_front();
+            }
+            entry.recent.push_back(duration);
+
+            let stats = &mut entry.stats;
+            stats.mean_ms = millis(entry.total) / stats.calls as f64;
+            stats.max_ms = stats.max_ms.max(millis(duration));
+            if error.is_some() {
+                stats.errors += 1;
+                stats.last_error = error;
+            }
+        });
+    }
+
+    /// Statistics of every command seen so far, in name order
+    pub fn snapshot(&self) -> Vec<CommandStats> {
+        let entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+        entries
+            .values()
+            .map(|entry| {
+                let mut sorted: Vec<Duration> = entry.recent.iter().copied().collect();
+                sorted.sort();
+                CommandStats {
+                    p50_ms: percentile(&sorted, 50),
+                    p95_ms: percentile(&sorted, 95),
+                    ..entry.stats.clone()
+                }
+            })
+            .collect()
+    }
+
+    fn with_entry(&self, command: &str, update: impl FnOnce(&mut Entry)) {
+        if let Ok(mut entries) = self.entries.lock() {
+            let entry = entries.entry(command.to_string()).or_insert_with(|| Entry {
+                stats: CommandStats {
+                    command: command.to_string(),
+                    ..CommandStats::default()
+                },
+                ..Entry::default()
+            });
+            update(entry);
+        }
+    }
+}
+
+impl Default for CommandMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Nearest-rank percentile of ascending `sorted`
+fn percentile(sorted: &[Duration], percent: usize) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    millis(sorted[rank - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregates_durations_and_errors() {
+        let metrics = CommandMetrics::new();
+        metrics.invoked("list_issues", 40, Utc::now());
+        metrics.invoked("list_issues", 10, Utc::now());
+        metrics.finished("list_issues", Duration::from_millis(10), None);
+        metrics.finished(
+            "list_issues",
+            Duration::from_millis(30),
+            Some("offline".to_string()),
+        );
+
+        let stats = metrics.snapshot();
+        assert_eq!(stats.len(), 1);
+        let stats = &stats[0];
+        assert_eq!(stats.command, "list_issues");
+        assert_eq!((stats.calls, stats.errors), (2, 1));
+        assert_eq!(stats.mean_ms, 20.0);
+        assert_eq!(stats.max_ms, 30.0);
+        assert_eq!((stats.max_arg_bytes, stats.total_arg_bytes), (40, 50));
+        assert_eq!(stats.last_error.as_deref(), Some("offline"));
+    }
+
+    #[test]
+    fn test_percentiles_cover_recent_calls_only() {
+        let metrics = CommandMetrics::new();
+        metrics.finished("get_git_status", Duration::from_secs(10), None);
+        for ms in 1..=DURATION_SAMPLES as u64 {
+            metrics.finished("get_git_status", Duration::from_millis(ms), None);
+        }
+
+        let stats = &metrics.snapshot()[0];
+        assert_eq!(stats.p50_ms, 50.0);
+        assert_eq!(stats.p95_ms, 95.0);
+        assert_eq!(stats.max_ms, 10_000.0);
+    }
+}
@@ -0,0 +1,135 @@
+mod buffer;
+mod metrics;
+
+pub use buffer::{LogEntry, RecentLogs};
+pub use metrics::{CommandMetrics, CommandStats};
+
+use anyhow::{Context, Result};
+use buffer::BufferLayer;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Log level configuration
+/// `level` is the default for every target, `modules` overrides it per module path
+/// (e.g. `"zeami4::pty" => "debug"`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LoggingSettings {
+    pub level: String,
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            modules: BTreeMap::new(),
+        }
+    }
+}
+
+impl LoggingSettings {
+    /// Build an EnvFilter directive string such as `info,zeami4::pty=debug`
+    pub fn directives(&self) -> String {
+        let mut directives = vec![self.level.clone()];
+        directives.extend(
+            self.modules
+                .iter()
+                .map(|(module, level)| format!("{}={}", module, level)),
+        );
+        directives.join(",")
+    }
+
+    fn filter(&self) -> Result<EnvFilter> {
+        EnvFilter::try_new(self.directives())
+            .with_context(|| format!("Invalid log level configuration: {}", self.directives()))
+    }
+}
+
+/// Handle to the installed logging subsystem, managed as Tauri state
+pub struct LogState {
+    recent: Arc<RecentLogs>,
+    filter: reload::Handle<EnvFilter, Registry>,
+    _guard: WorkerGuard,
+}
+
+impl LogState {
+    /// Recent in-memory log records, oldest first
+    pub fn recent(&self) -> &RecentLogs {
+        &self.recent
+    }
+
+    /// Replace the active level filter without restarting the app
+    pub fn apply(&self, settings: &LoggingSettings) -> Result<()> {
+        let filter = settings.filter()?;
+        self.filter
+            .reload(filter)
+            .context("Failed to reload log filter")
+    }
+}
+
+/// Directory holding rolling log files (~/.zeami/logs)
+pub fn log_dir() -> Result<PathBuf> {
+    let home = dirs::home_dir().context("Could not find home directory")?;
+    Ok(home.join(".zeami").join("logs"))
+}
+
+/// Install the global tracing subscriber
+/// Logs go to a daily rolling file under ~/.zeami/logs, to stderr, and to an
+/// in-memory buffer backing `get_recent_logs`
+pub fn init(settings: &LoggingSettings) -> Result<LogState> {
+    let dir = log_dir()?;
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create log directory {:?}", dir))?;
+
+    let appender = tracing_appender::rolling::daily(&dir, "zeami.log");
+    let (file_writer, guard) = tracing_appender::non_blocking(appender);
+
+    let filter = settings
+        .filter()
+        .unwrap_or_else(|_| EnvFilter::new(LoggingSettings::default().directives()));
+    let (filter_layer, filter_handle) = reload::Layer::new(filter);
+
+    let recent = Arc::new(RecentLogs::default());
+
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(file_writer)
+                .with_ansi(false),
+        )
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(BufferLayer::new(recent.clone()))
+        .try_init()
+        .context("Failed to install tracing subscriber")?;
+
+    Ok(LogState {
+        recent,
+        filter: filter_handle,
+        _guard: guard,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directives_include_module_overrides() {
+        let mut settings = LoggingSettings::default();
+        settings
+            .modules
+            .insert("zeami4::pty".to_string(), "debug".to_string());
+
+        assert_eq!(settings.directives(), "info,zeami4::pty=debug");
+        assert!(settings.filter().is_ok());
+    }
+}
@@ -2,21 +2,338 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod commands;
-mod pty;
 
 use commands::*;
 use commands::pty_commands::PtyState;
+use config::storage::LoadedSettings;
+use config::SettingsState;
+use events::schema::v1;
+use tauri::Manager;
+use zeami4::{
+    capabilities, claude, config, containers, deeplink, diagnostics, error, events, files,
+    frecency, git, github, i18n, journal, keymap, logging, monitoring, netwatch, notifications,
+    onboarding, plugins, pty, scheduler, search, snippets, state, tasks, telemetry, themes,
+    updater, workflow,
+};
 
 fn main() {
+    diagnostics::install_panic_hook();
+
+    // Hands the link to the running instance and exits when one is already open
+    tauri_plugin_deep_link::prepare("com.zeami4.app");
+
+    let legacy_migration = config::storage::migrate_legacy_config();
+    let (loaded, settings_error) = match config::storage::load_settings_or_recover() {
+        Ok(loaded) => (loaded, None),
+        Err(e) => (
+            LoadedSettings {
+                settings: config::Settings::default(),
+                recovery: None,
+            },
+            Some(e),
+        ),
+    };
+    let LoadedSettings { settings, recovery } = loaded;
+    let settings_state =
+        SettingsState::with_overrides(settings, config::overrides::from_process());
+    let settings = settings_state.current();
+
+    let log_state = logging::init(&settings.logging).expect("failed to initialize logging");
+    i18n::set_current(&settings.ui.language);
+    match legacy_migration {
+        Ok(true) => tracing::info!("Imported settings from the old CLI's config.toml"),
+        Ok(false) => {}
+        Err(e) => tracing::warn!("Failed to import the old CLI's config.toml: {:#}", e),
+    }
+    if let Some(e) = settings_error {
+        tracing::warn!("Failed to load settings, using defaults: {:#}", e);
+    }
+    if let Some(recovery) = &recovery {
+        tracing::warn!(
+            backup = ?recovery.backup_file,
+            "Recovered settings after load failure: {}",
+            recovery.error
+        );
+    }
+
+    let github_state = match github::mock::requested(std::env::args(), std::env::vars()) {
+        Some(fixtures) => {
+            let url = tauri::async_runtime::block_on(github::mock::start(&fixtures))
+                .expect("failed to start the mock GitHub server");
+            tracing::warn!(url, "Using the mock GitHub API; no requests reach GitHub");
+            github::GitHubState::mock(url)
+        }
+        None => github::GitHubState::default(),
+    };
+
     tauri::Builder::default()
+        .manage(log_state)
+        .manage(settings_state)
         .manage(PtyState::default())
-        .invoke_handler(tauri::generate_handler![
+        .manage(github_state)
+        .manage(claude::ClaudeState::open())
+        .manage(github::sync::SyncService::open())
+        .manage(github::ci::CiWatcher::default())
+        .manage(git::fetcher::FetchScheduler::default())
+        .manage(workflow::AutoCommitter::default())
+        .manage(workflow::Builder::default())
+        .manage(workflow::ProgressSyncer::default())
+        .manage(notifications::NotificationCenter::default())
+        .manage(tasks::TaskRunner::default())
+        .manage(tasks::TaskWatcher::default())
+        .manage(
+            github::queue::MutationQueue::open_default()
+                .expect("failed to locate the mutation queue"),
+        )
+        .manage(
+            scheduler::Scheduler::open_default().expect("failed to locate the scheduler state"),
+        )
+        .manage(
+            capabilities::CapabilityRegistry::open_default()
+                .expect("failed to locate the capability grants"),
+        )
+        .manage(plugins::PluginHost::default())
+        .manage(netwatch::PortWatcher::default())
+        .manage(netwatch::PortForwards::default())
+        .manage(monitoring::ResourceMonitor::default())
+        .manage(search::Searches::default())
+        .manage(frecency::Frecency::default())
+        .manage(pty::history::CommandHistory::open_default())
+        .manage(updater::Updater::open_default().expect("failed to locate the update directory"))
+        .manage(telemetry::Telemetry::open_default().expect("failed to locate the telemetry file"))
+        .setup(move |app| {
+            config::notify::spawn_listener(app.handle());
+            app.state::<github::sync::SyncService>().spawn(app.handle());
+            github::activity::spawn_event_poller(app.handle());
+            app.state::<github::ci::CiWatcher>().spawn(app.handle());
+            github::replay::spawn_replay(app.handle());
+            app.state::<tasks::TaskWatcher>().spawn(app.handle());
+            plugins::spawn_load(app.handle());
+            app.state::<netwatch::PortWatcher>().spawn(app.handle());
+            app.state::<monitoring::ResourceMonitor>().spawn(app.handle());
+            app.state::<frecency::Frecency>().spawn(app.handle());
+            app.state::<telemetry::Telemetry>().spawn(app.handle());
+            let scheduler = app.state::<scheduler::Scheduler>();
+            scheduler.register(config::auto_backup::BACKUP_JOB, config::auto_backup::AutoBackup);
+            scheduler.register(
+                git::fetcher::FETCH_JOB,
+                app.state::<git::fetcher::FetchScheduler>().inner().clone(),
+            );
+            scheduler.register(
+                workflow::auto_commit::AUTO_COMMIT_JOB,
+                app.state::<workflow::AutoCommitter>().inner().clone(),
+            );
+            scheduler.register(
+                workflow::progress_sync::PROGRESS_SYNC_JOB,
+                app.state::<workflow::ProgressSyncer>().inner().clone(),
+            );
+            scheduler.register(
+                updater::UPDATE_CHECK_JOB,
+                app.state::<updater::Updater>().inner().clone(),
+            );
+            scheduler.register(
+                telemetry::TELEMETRY_UPLOAD_JOB,
+                app.state::<telemetry::Telemetry>().inner().clone(),
+            );
+            scheduler.spawn(app.handle());
+            let handle = app.handle();
+            if let Err(e) = tauri_plugin_deep_link::register(deeplink::SCHEME, move |link| {
+                deeplink::dispatch(&handle, &link)
+            }) {
+                tracing::warn!("Failed to register the zeami:// scheme: {}", e);
+            }
+            if let Some(recovery) = recovery {
+                let payload: v1::SettingsRecovered = recovery;
+                app.emit_all(events::SETTINGS_RECOVERED_EVENT_NAME, payload)?;
+            }
+            Ok(())
+        })
+        .invoke_handler(commands::middleware::instrument(tauri::generate_handler![
             greet,
             create_pty_session,
             write_to_pty,
             resize_pty,
             close_pty_session,
-        ])
+            focus_pty_session,
+            get_recent_logs,
+            set_log_levels,
+            get_command_metrics,
+            get_event_schema_version,
+            load_settings,
+            get_settings_schema,
+            save_settings,
+            reset_settings,
+            export_settings,
+            import_settings,
+            is_encrypted_export,
+            list_settings_backups,
+            restore_settings_backup,
+            get_backup_settings,
+            set_backup_settings,
+            get_settings_history,
+            get_config_format,
+            set_config_format,
+            get_effective_settings,
+            save_project_settings,
+            list_profiles,
+            switch_profile,
+            clone_profile,
+            store_secret,
+            delete_secret,
+            has_secret,
+            list_stored_secrets,
+            test_github_token,
+            rotate_github_token,
+            add_github_account,
+            remove_github_account,
+            select_github_account,
+            github_api_status,
+            list_issues,
+            get_issue,
+            get_issue_detail_bundle,
+            create_issue,
+            comment_on_issue,
+            close_issue,
+            list_pending_mutations,
+            cancel_pending_mutation,
+            list_issue_templates,
+            render_issue_template,
+            list_labels,
+            create_label,
+            create_pull_request,
+            list_pull_requests,
+            merge_pull_request,
+            request_reviewers,
+            query_cached_issues,
+            sync_github_now,
+            start_issue,
+            get_linked_issue,
+            get_checks_for_branch,
+            watch_ci_status,
+            get_git_status,
+            create_commit,
+            lint_commit_message,
+            list_branches,
+            create_branch,
+            checkout_branch,
+            delete_branch,
+            clean_merged_branches,
+            get_file_diff,
+            get_blame,
+            fetch_remote,
+            watch_remote,
+            get_merge_conflicts,
+            resolve_conflict,
+            watch_auto_commit,
+            list_auto_commits,
+            restore_auto_commit,
+            run_tests,
+            get_last_test_run,
+            run_build,
+            watch_progress_sync,
+            sync_progress_now,
+            get_dod_status,
+            complete_issue,
+            send_claude_message,
+            list_conversations,
+            get_conversation,
+            delete_conversation,
+            get_claude_cache_stats,
+            generate_commit_message,
+            generate_pr_description,
+            get_claude_usage,
+            explain_last_error,
+            run_claude_agent,
+            respond_tool_approval,
+            get_notification_history,
+            list_tasks,
+            run_task,
+            stop_task,
+            watch_tasks,
+            list_scheduled_jobs,
+            pause_scheduled_job,
+            resume_scheduled_job,
+            get_project_state,
+            update_project_state,
+            get_launch_deep_links,
+            create_diagnostic_bundle,
+            list_capabilities,
+            grant_capability,
+            revoke_capability,
+            list_plugins,
+            invoke_plugin_command,
+            reload_plugins,
+            list_ssh_hosts,
+            add_ssh_host,
+            remove_ssh_host,
+            list_dev_servers,
+            open_in_browser,
+            start_port_forward,
+            stop_port_forward,
+            list_port_forwards,
+            list_containers,
+            read_project_file,
+            write_project_file,
+            apply_patch,
+            create_container_session,
+            get_resource_stats,
+            get_process_tree,
+            search_project,
+            get_recent_files,
+            get_frequent_dirs,
+            list_snippets,
+            save_snippet,
+            delete_snippet,
+            expand_snippet,
+            insert_snippet,
+            search_command_history,
+            list_themes,
+            get_theme_palette,
+            import_theme,
+            get_translations,
+            set_locale,
+            check_for_updates,
+            download_update,
+            defer_update,
+            set_update_channel,
+            get_telemetry_report,
+            export_telemetry,
+            detect_environment,
+            get_app_health,
+            list_filter_rules,
+            add_filter_rule,
+            remove_filter_rule,
+            test_filter_rule,
+            get_layout,
+            update_layout,
+            get_keymap,
+            set_binding,
+            export_keymap,
+            import_keymap,
+            query_journal,
+            sync_branch_with_base,
+            update_submodules,
+            set_remote_credential,
+            remove_remote_credential,
+            test_remote_access,
+            get_commit_signature_status,
+            get_repo_insights,
+            list_releases,
+            create_release,
+            list_discussions,
+            get_project_board,
+            move_issue_to_column,
+            get_rate_limit_status,
+            cancel_claude_request,
+            preview_system_prompt,
+            draft_issue_from_context,
+            suggest_command,
+            insert_command,
+            export_conversation,
+            attach_conversation_to_issue,
+            preview_conversation_comment,
+            preview_redactions,
+        ]))
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
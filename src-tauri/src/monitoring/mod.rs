@@ -0,0 +1,164 @@
+//! CPU and memory of Zeami and what it runs, for the status bar
+//!
+//! Every few seconds the process table is sampled (see process) and
+//! `resource-stats` reports the usage of the Zeami process, of each PTY
+//! session's shell with everything started from it, and of each running
+//! task. get_process_tree breaks a session down so a runaway test process
+//! can be found and stopped.
+
+pub mod process;
+
+pub use process::{ProcessInfo, ProcessNode, ProcessTable, Usage};
+
+use crate::events::schema::v1;
+use crate::events::RESOURCE_STATS_EVENT_NAME;
+use crate::tasks::TaskRunner;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often resource usage is sampled
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Usage of one PTY session's processes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionUsage {
+    pub session_id: String,
+    #[serde(flatten)]
+    pub usage: Usage,
+}
+
+/// Usage of one running task's processes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskUsage {
+    pub project_root: PathBuf,
+    pub task: String,
+    #[serde(flatten)]
+    pub usage: Usage,
+}
+
+/// One sample, as emitted in `resource-stats`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceStats {
+    pub sampled_at: DateTime<Utc>,
+    /// The Zeami process itself
+    pub app: Usage,
+    pub sessions: Vec<SessionUsage>,
+    pub tasks: Vec<TaskUsage>,
+}
+
+/// Samples resource usage, managed as Tauri state
+#[derive(Default)]
+pub struct ResourceMonitor {
+    /// Shell process of each PTY session
+    sessions: Arc<Mutex<HashMap<String, u32>>>,
+    latest: Arc<Mutex<Option<ResourceStats>>>,
+}
+
+impl ResourceMonitor {
+    /// Include the processes under `pid` as session `session_id`
+    pub fn track(&self, session_id: &str, pid: u32) {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session_id.to_string(), pid);
+    }
+
+    pub fn untrack(&self, session_id: &str) {
+        self.sessions.lock().unwrap().remove(session_id);
+    }
+
+    /// The last sample; None until the first one is taken
+    pub fn latest(&self) -> Option<ResourceStats> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    /// Processes of a session as a tree, sampled now
+    pub fn process_tree(&self, session_id: &str) -> Result<Option<ProcessNode>> {
+        let pid = self
+            .sessions
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .copied()
+            .with_context(|| format!("Session not found: {}", session_id))?;
+        Ok(ProcessTable::snapshot()?.tree(pid))
+    }
+
+    /// Start the sampling loop
+    pub fn spawn(&self, app: AppHandle) {
+        let sessions = self.sessions.clone();
+        let latest = self.latest.clone();
+
+        tauri::async_runtime::spawn(async move {
+            let mut warned = false;
+            loop {
+                tokio::time::sleep(SAMPLE_INTERVAL).await;
+                let tracked = sessions.lock().unwrap().clone();
+                let tasks = app.state::<TaskRunner>().running_processes();
+
+                let sample = tauri::async_runtime::spawn_blocking(move || {
+                    ProcessTable::snapshot().map(|table| sample(&table, &tracked, &tasks))
+                })
+                .await;
+                let stats = match sample {
+                    Ok(Ok(stats)) => stats,
+                    Ok(Err(e)) => {
+                        if !warned {
+                            tracing::warn!("Resource monitoring unavailable: {:#}", e);
+                            warned = true;
+                        }
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::error!("Resource sampling failed: {}", e);
+                        continue;
+                    }
+                };
+
+                *latest.lock().unwrap() = Some(stats.clone());
+                let payload: v1::ResourceStats = stats;
+                if let Err(e) = app.emit_all(RESOURCE_STATS_EVENT_NAME, payload) {
+                    tracing::error!("Failed to emit resource stats: {}", e);
+                }
+            }
+        });
+    }
+}
+
+fn sample(
+    table: &ProcessTable,
+    sessions: &HashMap<String, u32>,
+    tasks: &[(PathBuf, String, u32)],
+) -> ResourceStats {
+    let mut sessions: Vec<SessionUsage> = sessions
+        .iter()
+        .map(|(session_id, &pid)| SessionUsage {
+            session_id: session_id.clone(),
+            usage: table.usage(&table.descendants(pid)),
+        })
+        .collect();
+    sessions.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+
+    let mut tasks: Vec<TaskUsage> = tasks
+        .iter()
+        .map(|(project_root, task, pid)| TaskUsage {
+            project_root: project_root.clone(),
+            task: task.clone(),
+            usage: table.usage(&table.descendants(*pid)),
+        })
+        .collect();
+    tasks.sort_by(|a, b| (&a.project_root, &a.task).cmp(&(&b.project_root, &b.task)));
+
+    ResourceStats {
+        sampled_at: Utc::now(),
+        app: table.usage(&[std::process::id()]),
+        sessions,
+        tasks,
+    }
+}
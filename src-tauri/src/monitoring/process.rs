@@ -0,0 +1,172 @@
+//! Process table from `ps`, which reports the same columns on macOS and Linux
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::process::Command;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub parent: u32,
+    /// As reported by ps: a recent average on macOS, the lifetime average on Linux
+    pub cpu_percent: f32,
+    /// Resident memory
+    pub memory_bytes: u64,
+    pub command: String,
+}
+
+/// CPU and memory summed over a group of processes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    pub processes: usize,
+}
+
+/// A process with the processes it started, for get_process_tree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessNode {
+    #[serde(flatten)]
+    pub process: ProcessInfo,
+    pub children: Vec<ProcessNode>,
+}
+
+/// Every running process at one moment
+#[derive(Debug, Clone, Default)]
+pub struct ProcessTable {
+    processes: HashMap<u32, ProcessInfo>,
+    children: HashMap<u32, Vec<u32>>,
+}
+
+impl ProcessTable {
+    pub fn snapshot() -> Result<Self> {
+        let output = Command::new("ps")
+            .args([
+                "-A", "-o", "pid=", "-o", "ppid=", "-o", "pcpu=", "-o", "rss=", "-o", "comm=",
+            ])
+            .output()
+            .context("Failed to run ps")?;
+        if !output.status.success() {
+            bail!(
+                "ps failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(Self::parse(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// Parse `ps -o pid=,ppid=,pcpu=,rss=,comm=` output
+    pub fn parse(output: &str) -> Self {
+        let mut table = Self::default();
+        for line in output.lines() {
+            let mut fields = line.split_whitespace();
+            let parsed = (|| {
+                let pid = fields.next()?.parse().ok()?;
+                let parent = fields.next()?.parse().ok()?;
+                let cpu_percent = fields.next()?.parse().ok()?;
+                let rss_kib: u64 = fields.next()?.parse().ok()?;
+                // The command may contain spaces
+                let command = fields.collect::<Vec<_>>().join(" ");
+                Some(ProcessInfo {
+                    pid,
+                    parent,
+                    cpu_percent,
+                    memory_bytes: rss_kib * 1024,
+                    command,
+                })
+            })();
+            if let Some(process) = parsed {
+                table
+                    .children
+                    .entry(process.parent)
+                    .or_default()
+                    .push(process.pid);
+                table.processes.insert(process.pid, process);
+            }
+        }
+        for children in table.children.values_mut() {
+            children.sort_unstable();
+        }
+        table
+    }
+
+    pub fn get(&self, pid: u32) -> Option<&ProcessInfo> {
+        self.processes.get(&pid)
+    }
+
+    /// `root` and every process below it
+    pub fn descendants(&self, root: u32) -> HashSet<u32> {
+        let mut found = HashSet::from([root]);
+        let mut queue = vec![root];
+        while let Some(pid) = queue.pop() {
+            for &child in self.children.get(&pid).into_iter().flatten() {
+                if found.insert(child) {
+                    queue.push(child);
+                }
+            }
+        }
+        found
+    }
+
+    /// Usage of the listed processes that are still running
+    pub fn usage<'a>(&self, pids: impl IntoIterator<Item = &'a u32>) -> Usage {
+        pids.into_iter()
+            .filter_map(|pid| self.processes.get(pid))
+            .fold(Usage::default(), |usage, process| Usage {
+                cpu_percent: usage.cpu_percent + process.cpu_percent,
+                memory_bytes: usage.memory_bytes + process.memory_bytes,
+                processes: usage.processes + 1,
+            })
+    }
+
+    /// `root` and its descendants as a tree; None when `root` is not running
+    pub fn tree(&self, root: u32) -> Option<ProcessNode> {
+        self.subtree(root, &mut HashSet::new())
+    }
+
+    fn subtree(&self, pid: u32, seen: &mut HashSet<u32>) -> Option<ProcessNode> {
+        if !seen.insert(pid) {
+            return None;
+        }
+        let process = self.processes.get(&pid)?.clone();
+        let children = self
+            .children
+            .get(&pid)
+            .into_iter()
+            .flatten()
+            .filter_map(|&child| self.subtree(child, seen))
+            .collect();
+        Some(ProcessNode { process, children })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PS: &str = "    1     0   0.0  1000 /sbin/init\n   10     1   1.5  2048 zsh\n   11    10  98.0 40960 node vite\n   12    11   0.5  1024 esbuild\n   20     1   0.0   512 sshd\n";
+
+    #[test]
+    fn test_finds_descendants() {
+        let table = ProcessTable::parse(PS);
+        assert_eq!(table.descendants(10), HashSet::from([10, 11, 12]));
+        assert_eq!(table.descendants(99), HashSet::from([99]));
+        assert_eq!(table.get(11).unwrap().command, "node vite");
+    }
+
+    #[test]
+    fn test_sums_usage_and_builds_trees() {
+        let table = ProcessTable::parse(PS);
+        let usage = table.usage(&table.descendants(10));
+        assert_eq!(usage.processes, 3);
+        assert_eq!(usage.memory_bytes, (2048 + 40960 + 1024) * 1024);
+        assert!((usage.cpu_percent - 100.0).abs() < 0.01);
+
+        let tree = table.tree(10).unwrap();
+        assert_eq!(tree.children.len(), 1);
+        assert_eq!(tree.children[0].process.pid, 11);
+        assert_eq!(tree.children[0].children[0].process.command, "esbuild");
+        assert!(table.tree(99).is_none());
+    }
+}
@@ -0,0 +1,139 @@
+use crate::config::SshHost;
+use crate::pty::ssh;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+
+/// How long ssh gets to fail (bad host, port in use) before a forward counts as started
+const STARTUP_GRACE: Duration = Duration::from_millis(500);
+
+/// A local port forwarded to a port on an SSH host
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortForward {
+    pub host: String,
+    pub remote_port: u16,
+    pub local_port: u16,
+    pub url: String,
+}
+
+struct Forward {
+    info: PortForward,
+    child: Child,
+    _key_file: Option<NamedTempFile>,
+}
+
+/// Running `ssh -L` processes by local port, managed as Tauri state
+#[derive(Default)]
+pub struct PortForwards {
+    forwards: Mutex<BTreeMap<u16, Forward>>,
+}
+
+impl PortForwards {
+    /// Forward `local_port` (default: `remote_port`) to `remote_port` on `host`
+    pub fn start(
+        &self,
+        host: &SshHost,
+        remote_port: u16,
+        local_port: Option<u16>,
+    ) -> Result<PortForward> {
+        ssh::validate(host)?;
+        let local_port = local_port.unwrap_or(remote_port);
+        let mut forwards = self.forwards.lock().unwrap();
+        self.prune(&mut forwards);
+        if let Some(existing) = forwards.get(&local_port) {
+            bail!(
+                "Port {} is already forwarded to {}:{}",
+                local_port,
+                existing.info.host,
+                existing.info.remote_port
+            );
+        }
+
+        let key_file = ssh::key_file(host)?;
+        let mut child = Command::new("ssh")
+            .args(ssh::forward_args(
+                host,
+                key_file.as_ref().map(|file| file.path()),
+                local_port,
+                remote_port,
+            ))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Failed to start ssh")?;
+        std::thread::sleep(STARTUP_GRACE);
+        if let Some(status) = child.try_wait()? {
+            bail!("ssh exited with {}", status);
+        }
+
+        let info = PortForward {
+            host: host.name.clone(),
+            remote_port,
+            local_port,
+            url: super::local_url(local_port),
+        };
+        tracing::info!(host = %host.name, remote_port, local_port, "Started port forward");
+        forwards.insert(
+            local_port,
+            Forward {
+                info: info.clone(),
+                child,
+                _key_file: key_file,
+            },
+        );
+        Ok(info)
+    }
+
+    /// Returns false when `local_port` is not forwarded
+    pub fn stop(&self, local_port: u16) -> bool {
+        let forward = self.forwards.lock().unwrap().remove(&local_port);
+        match forward {
+            Some(mut forward) => {
+                kill(&mut forward.child);
+                tracing::info!(local_port, "Stopped port forward");
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forwards whose ssh is still running
+    pub fn list(&self) -> Vec<PortForward> {
+        let mut forwards = self.forwards.lock().unwrap();
+        self.prune(&mut forwards);
+        forwards
+            .values()
+            .map(|forward| forward.info.clone())
+            .collect()
+    }
+
+    fn prune(&self, forwards: &mut BTreeMap<u16, Forward>) {
+        forwards.retain(|port, forward| match forward.child.try_wait() {
+            Ok(None) => true,
+            _ => {
+                tracing::info!(local_port = port, "Port forward ended");
+                false
+            }
+        });
+    }
+}
+
+impl Drop for PortForwards {
+    fn drop(&mut self) {
+        for forward in self.forwards.get_mut().unwrap().values_mut() {
+            kill(&mut forward.child);
+        }
+    }
+}
+
+fn kill(child: &mut Child) {
+    if let Err(e) = child.kill() {
+        tracing::debug!("Failed to stop ssh: {}", e);
+    }
+    let _ = child.wait();
+}
@@ -0,0 +1,151 @@
+//! Dev servers started from terminals
+//!
+//! Every couple of seconds the TCP ports listened on by PTY session processes
+//! and their descendants are looked up (see scan). A port showing up emits
+//! `dev-server-detected` with its localhost URL so the UI can offer to open
+//! it. Ports on SSH hosts can be forwarded to this machine (see forward).
+
+pub mod forward;
+mod scan;
+
+pub use forward::{PortForward, PortForwards};
+
+use crate::events::schema::v1;
+use crate::events::DEV_SERVER_DETECTED_EVENT_NAME;
+use crate::monitoring::ProcessTable;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often session processes are checked for listening ports
+const SCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A port listened on by a process started in a terminal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DevServer {
+    pub session_id: String,
+    pub pid: u32,
+    /// Command name, e.g. `node`
+    pub process: String,
+    pub port: u16,
+    pub url: String,
+}
+
+/// URL of a port on this machine
+pub fn local_url(port: u16) -> String {
+    format!("http://localhost:{}", port)
+}
+
+/// Tracks PTY session processes and the ports they listen on, managed as Tauri state
+#[derive(Default)]
+pub struct PortWatcher {
+    /// Shell process of each session
+    sessions: Arc<Mutex<HashMap<String, u32>>>,
+    servers: Arc<Mutex<Vec<DevServer>>>,
+}
+
+impl PortWatcher {
+    /// Watch what runs under `pid` in session `session_id`
+    pub fn track(&self, session_id: &str, pid: u32) {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session_id.to_string(), pid);
+    }
+
+    pub fn untrack(&self, session_id: &str) {
+        self.sessions.lock().unwrap().remove(session_id);
+        self.servers
+            .lock()
+            .unwrap()
+            .retain(|server| server.session_id != session_id);
+    }
+
+    /// Dev servers listening right now, as of the last scan
+    pub fn list(&self) -> Vec<DevServer> {
+        self.servers.lock().unwrap().clone()
+    }
+
+    /// Start the polling loop
+    pub fn spawn(&self, app: AppHandle) {
+        let sessions = self.sessions.clone();
+        let servers = self.servers.clone();
+
+        tauri::async_runtime::spawn(async move {
+            let mut warned = false;
+            loop {
+                tokio::time::sleep(SCAN_INTERVAL).await;
+                let tracked = sessions.lock().unwrap().clone();
+                if tracked.is_empty() {
+                    servers.lock().unwrap().clear();
+                    continue;
+                }
+
+                let found =
+                    tauri::async_runtime::spawn_blocking(move || find_servers(&tracked)).await;
+                let found = match found {
+                    Ok(Ok(found)) => found,
+                    Ok(Err(e)) => {
+                        // Typically lsof missing; there is nothing to retry until it is installed
+                        if !warned {
+                            tracing::warn!("Dev server detection unavailable: {:#}", e);
+                            warned = true;
+                        }
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::error!("Dev server scan failed: {}", e);
+                        continue;
+                    }
+                };
+
+                let mut servers = servers.lock().unwrap();
+                for server in &found {
+                    let known = servers
+                        .iter()
+                        .any(|s| s.session_id == server.session_id && s.port == server.port);
+                    if !known {
+                        tracing::info!(session_id = %server.session_id, port = server.port, process = %server.process, "Dev server detected");
+                        let payload: v1::DevServerDetected = server.clone();
+                        if let Err(e) = app.emit_all(DEV_SERVER_DETECTED_EVENT_NAME, payload) {
+                            tracing::error!("Failed to emit dev server: {}", e);
+                        }
+                    }
+                }
+                *servers = found;
+            }
+        });
+    }
+}
+
+/// Ports listened on below each session's shell
+fn find_servers(sessions: &HashMap<String, u32>) -> anyhow::Result<Vec<DevServer>> {
+    let listeners = scan::listeners()?;
+    if listeners.is_empty() {
+        return Ok(Vec::new());
+    }
+    let table = ProcessTable::snapshot()?;
+
+    let mut servers = Vec::new();
+    for (session_id, &root) in sessions {
+        let processes = table.descendants(root);
+        for listener in listeners.iter().filter(|l| processes.contains(&l.pid)) {
+            let duplicate = servers
+                .iter()
+                .any(|s: &DevServer| s.session_id == *session_id && s.port == listener.port);
+            if !duplicate {
+                servers.push(DevServer {
+                    session_id: session_id.clone(),
+                    pid: listener.pid,
+                    process: listener.process.clone(),
+                    port: listener.port,
+                    url: local_url(listener.port),
+                });
+            }
+        }
+    }
+    servers.sort_by(|a, b| (&a.session_id, a.port).cmp(&(&b.session_id, b.port)));
+    Ok(servers)
+}
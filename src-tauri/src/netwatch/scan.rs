@@ -0,0 +1,92 @@
+//! Listening ports from `lsof`
+//!
+//! lsof ships with macOS and nearly every Linux distribution, and its
+//! machine-readable output is the same on both, which a /proc or libproc
+//! reader would not be.
+
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeSet;
+use std::process::Command;
+
+/// A process listening on a TCP port
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Listener {
+    pub pid: u32,
+    pub port: u16,
+    /// Command name as reported by lsof, e.g. `node`
+    pub process: String,
+}
+
+/// Every process listening on a TCP port
+pub fn listeners() -> Result<Vec<Listener>> {
+    let output = Command::new("lsof")
+        .args(["-nP", "-iTCP", "-sTCP:LISTEN", "-Fpcn"])
+        .output()
+        .context("Failed to run lsof")?;
+    // lsof exits with 1 when nothing is listening
+    if !output.status.success() && !output.stdout.is_empty() {
+        bail!(
+            "lsof failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(parse_lsof(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse `lsof -F pcn` output: a `p` line per process, then its `c` and `n` lines
+fn parse_lsof(output: &str) -> Vec<Listener> {
+    let mut listeners = BTreeSet::new();
+    let mut pid = None;
+    let mut process = String::new();
+    for line in output.lines() {
+        let (field, value) = line.split_at(line.len().min(1));
+        match field {
+            "p" => {
+                pid = value.parse().ok();
+                process.clear();
+            }
+            "c" => process = value.to_string(),
+            "n" => {
+                let port = value
+                    .rsplit_once(':')
+                    .and_then(|(_, port)| port.parse().ok());
+                if let (Some(pid), Some(port)) = (pid, port) {
+                    listeners.insert(Listener {
+                        pid,
+                        port,
+                        process: process.clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    listeners.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_lsof_listeners() {
+        let output =
+            "p100\ncnode\nf21\nn*:5173\nf22\nn[::1]:5173\np200\ncpython3\nf3\nn127.0.0.1:8000\n";
+        assert_eq!(
+            parse_lsof(output),
+            vec![
+                Listener {
+                    pid: 100,
+                    port: 5173,
+                    process: "node".to_string()
+                },
+                Listener {
+                    pid: 200,
+                    port: 8000,
+                    process: "python3".to_string()
+                },
+            ]
+        );
+        assert!(parse_lsof("").is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+//! Notifications for events that happen while the user looks elsewhere
+//!
+//! Finished tests, failed CI, long terminal commands and finished Claude
+//! replies are routed by category (see NotificationSettings): delivered as a
+//! native OS notification, listed in the app only, or dropped. Everything
+//! delivered is kept in a short in-memory history and sent to the frontend as
+//! a `notification` event.
+
+mod rules;
+
+pub use rules::{
+    NotificationCategory, NotificationRecord, NotificationRoute, NotificationSettings,
+};
+
+use crate::config::SettingsState;
+use crate::events::schema::v1;
+use crate::events::NOTIFICATION_EVENT_NAME;
+use chrono::Utc;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::api::notification::{Notification, Sound};
+use tauri::{AppHandle, Manager};
+
+/// Notifications kept for get_notification_history
+const HISTORY_LIMIT: usize = 200;
+
+/// Recently delivered notifications, managed as Tauri state
+#[derive(Default)]
+pub struct NotificationCenter {
+    history: Mutex<VecDeque<NotificationRecord>>,
+}
+
+impl NotificationCenter {
+    /// Delivered notifications, newest first
+    pub fn history(&self, limit: Option<usize>) -> Vec<NotificationRecord> {
+        let Ok(history) = self.history.lock() else {
+            return Vec::new();
+        };
+        history
+            .iter()
+            .rev()
+            .take(limit.unwrap_or(HISTORY_LIMIT))
+            .cloned()
+            .collect()
+    }
+
+    fn push(&self, record: NotificationRecord) {
+        if let Ok(mut history) = self.history.lock() {
+            history.push_back(record);
+            if history.len() > HISTORY_LIMIT {
+                history.pop_front();
+            }
+        }
+    }
+}
+
+/// Route and deliver a notification according to the current UI settings
+pub fn notify(
+    app: &AppHandle,
+    category: NotificationCategory,
+    title: impl Into<String>,
+    body: impl Into<String>,
+) {
+    let ui = app.state::<SettingsState>().current().ui;
+    let focused = app_focused(app);
+    let route = ui.notifications.route(
+        category,
+        ui.show_notifications,
+        focused,
+        chrono::Local::now().time(),
+    );
+    if route == NotificationRoute::Off {
+        return;
+    }
+
+    let record = NotificationRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        category,
+        title: title.into(),
+        body: body.into(),
+        route,
+        created_at: Utc::now(),
+    };
+    if route == NotificationRoute::Native {
+        let mut notification = Notification::new(&app.config().tauri.bundle.identifier)
+            .title(&record.title)
+            .body(&record.body);
+        if ui.notification_sound {
+            notification = notification.sound(Sound::Default);
+        }
+        if let Err(e) = notification.show() {
+            tracing::warn!("Failed to show notification: {}", e);
+        }
+    }
+
+    app.state::<NotificationCenter>().push(record.clone());
+    let payload: v1::Notification = record;
+    if let Err(e) = app.emit_all(NOTIFICATION_EVENT_NAME, payload) {
+        tracing::error!("Failed to emit notification: {}", e);
+    }
+}
+
+/// Whether any Zeami window has focus
+pub fn app_focused(app: &AppHandle) -> bool {
+    app.windows()
+        .values()
+        .any(|window| window.is_focused().unwrap_or(false))
+}
+
+/// Compact duration for notification text, e.g. `4m12s`
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, _) => format!("{}h{:02}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(252)), "4m12s");
+        assert_eq!(format_duration(Duration::from_secs(3 * 3600 + 65)), "3h01m");
+    }
+}
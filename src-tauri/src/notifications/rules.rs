@@ -0,0 +1,219 @@
+use chrono::{DateTime, NaiveTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What a notification is about; each category has its own routing rule
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    TestFinished,
+    CiFailed,
+    /// A long-running terminal command finished or its output went quiet
+    CommandFinished,
+    ClaudeDone,
+}
+
+/// Where notifications of a category are delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationRoute {
+    /// Native OS notification, also listed in the app
+    Native,
+    /// Only listed in the app
+    InApp,
+    Off,
+}
+
+/// A delivered notification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationRecord {
+    pub id: String,
+    pub category: NotificationCategory,
+    pub title: String,
+    pub body: String,
+    /// Native or InApp
+    pub route: NotificationRoute,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct NotificationRule {
+    pub category: NotificationCategory,
+    pub route: NotificationRoute,
+    /// Deliver in-app instead of natively while a Zeami window has focus
+    #[serde(default)]
+    pub only_when_unfocused: bool,
+}
+
+/// Daily quiet hours in local time, during which native notifications are
+/// delivered in-app only; `start` after `end` spans midnight
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct DoNotDisturb {
+    pub enabled: bool,
+    /// `HH:MM`
+    pub start: String,
+    /// `HH:MM`
+    pub end: String,
+}
+
+impl Default for DoNotDisturb {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start: "22:00".to_string(),
+            end: "08:00".to_string(),
+        }
+    }
+}
+
+impl DoNotDisturb {
+    /// Whether `now` falls in the quiet hours; an unparsable schedule never does
+    pub fn covers(&self, now: NaiveTime) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let parse = |time: &str| NaiveTime::parse_from_str(time.trim(), "%H:%M").ok();
+        let (Some(start), Some(end)) = (parse(&self.start), parse(&self.end)) else {
+            return false;
+        };
+        if start <= end {
+            start <= now && now < end
+        } else {
+            now >= start || now < end
+        }
+    }
+}
+
+/// Per-category routing and quiet hours
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Categories without a rule are delivered natively while unfocused
+    pub rules: Vec<NotificationRule>,
+    pub do_not_disturb: DoNotDisturb,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        let rule = |category, only_when_unfocused| NotificationRule {
+            category,
+            route: NotificationRoute::Native,
+            only_when_unfocused,
+        };
+        Self {
+            rules: vec![
+                rule(NotificationCategory::TestFinished, true),
+                rule(NotificationCategory::CiFailed, false),
+                rule(NotificationCategory::CommandFinished, true),
+                rule(NotificationCategory::ClaudeDone, true),
+            ],
+            do_not_disturb: DoNotDisturb::default(),
+        }
+    }
+}
+
+impl NotificationSettings {
+    /// Route for a notification of `category`
+    ///
+    /// `native_enabled` is UISettings::show_notifications; when it is off, or
+    /// the rule defers to a focused window, or quiet hours are on, a native
+    /// route falls back to in-app.
+    pub fn route(
+        &self,
+        category: NotificationCategory,
+        native_enabled: bool,
+        focused: bool,
+        now: NaiveTime,
+    ) -> NotificationRoute {
+        let (route, only_when_unfocused) = self
+            .rules
+            .iter()
+            .find(|rule| rule.category == category)
+            .map_or((NotificationRoute::Native, true), |rule| {
+                (rule.route, rule.only_when_unfocused)
+            });
+
+        if route == NotificationRoute::Native
+            && (!native_enabled
+                || (only_when_unfocused && focused)
+                || self.do_not_disturb.covers(now))
+        {
+            return NotificationRoute::InApp;
+        }
+        route
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_do_not_disturb() {
+        let mut dnd = DoNotDisturb {
+            enabled: true,
+            ..DoNotDisturb::default()
+        };
+        assert!(dnd.covers(time(23, 0)));
+        assert!(dnd.covers(time(7, 59)));
+        assert!(!dnd.covers(time(8, 0)));
+        assert!(!dnd.covers(time(12, 0)));
+
+        dnd.start = "12:00".to_string();
+        dnd.end = "13:30".to_string();
+        assert!(dnd.covers(time(13, 0)));
+        assert!(!dnd.covers(time(14, 0)));
+
+        dnd.end = "noon".to_string();
+        assert!(!dnd.covers(time(13, 0)));
+    }
+
+    #[test]
+    fn test_route() {
+        let mut settings = NotificationSettings::default();
+        let noon = time(12, 0);
+        let claude = NotificationCategory::ClaudeDone;
+        let ci = NotificationCategory::CiFailed;
+
+        assert_eq!(
+            settings.route(claude, true, false, noon),
+            NotificationRoute::Native
+        );
+        assert_eq!(
+            settings.route(claude, true, true, noon),
+            NotificationRoute::InApp
+        );
+        assert_eq!(
+            settings.route(ci, true, true, noon),
+            NotificationRoute::Native
+        );
+        assert_eq!(
+            settings.route(ci, false, false, noon),
+            NotificationRoute::InApp
+        );
+
+        settings.do_not_disturb.enabled = true;
+        assert_eq!(
+            settings.route(ci, true, false, time(23, 0)),
+            NotificationRoute::InApp
+        );
+
+        settings.rules[1].route = NotificationRoute::Off;
+        assert_eq!(
+            settings.route(ci, true, false, noon),
+            NotificationRoute::Off
+        );
+        settings.rules.clear();
+        assert_eq!(
+            settings.route(ci, true, false, noon),
+            NotificationRoute::Native
+        );
+    }
+}
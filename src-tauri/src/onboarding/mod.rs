@@ -0,0 +1,293 @@
+//! Environment detection for the first-run setup wizard
+//!
+//! Probes what is installed and configured on the machine so the wizard can
+//! prefill Settings instead of asking: login shells, git and its user config,
+//! the GitHub CLI, ssh and its keys, node and rust toolchains, and what is
+//! already in ~/.zeami. Every probe is best-effort; a missing tool is reported
+//! as not found rather than failing the report.
+
+use crate::config::{keychain, profiles, storage, Settings};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Tools reported besides git and the shells
+const TOOLS: [(&str, &[&str]); 7] = [
+    ("gh", &["--version"]),
+    ("ssh", &["-V"]),
+    ("node", &["--version"]),
+    ("npm", &["--version"]),
+    ("rustc", &["--version"]),
+    ("cargo", &["--version"]),
+    ("docker", &["--version"]),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentReport {
+    pub os: String,
+    pub arch: String,
+    pub shells: Vec<ShellInfo>,
+    pub git: GitInfo,
+    pub tools: Vec<ToolInfo>,
+    /// Logged in with `gh auth login`, judged from its hosts file
+    pub gh_logged_in: bool,
+    /// Public key files in ~/.ssh
+    pub ssh_keys: Vec<String>,
+    pub existing_config: ExistingConfig,
+    /// Values the wizard can prefill
+    pub suggestions: SetupSuggestions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShellInfo {
+    pub name: String,
+    pub path: PathBuf,
+    /// The user's login shell ($SHELL)
+    pub default: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GitInfo {
+    /// None when git is not installed
+    pub version: Option<String>,
+    pub user_name: Option<String>,
+    pub user_email: Option<String>,
+    /// init.defaultBranch
+    pub default_branch: Option<String>,
+    pub signing_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolInfo {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExistingConfig {
+    pub config_dir: PathBuf,
+    /// config.json or config.toml, when settings have been saved before
+    pub config_file: Option<PathBuf>,
+    pub profiles: usize,
+    pub github_token_stored: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SetupSuggestions {
+    pub shell: Option<String>,
+    pub default_branch: Option<String>,
+    pub sign_commits: bool,
+    pub gpg_key_id: Option<String>,
+}
+
+/// Probe the machine; blocks while the tools report their versions
+pub fn detect(settings: &Settings) -> EnvironmentReport {
+    let home = dirs::home_dir().unwrap_or_default();
+    let login_shell = std::env::var("SHELL").ok();
+    let shells = shells(login_shell.as_deref());
+    let git = git_info();
+    let tools: Vec<ToolInfo> = TOOLS.iter().map(|(name, args)| tool(name, args)).collect();
+
+    let suggestions = SetupSuggestions {
+        shell: login_shell.or_else(|| {
+            shells
+                .first()
+                .map(|shell| shell.path.to_string_lossy().into_owned())
+        }),
+        default_branch: git.default_branch.clone(),
+        sign_commits: git.signing_key.is_some(),
+        gpg_key_id: git.signing_key.clone(),
+    };
+    EnvironmentReport {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        shells,
+        git,
+        tools,
+        gh_logged_in: gh_hosts_file(&home).is_some_and(|path| path.is_file()),
+        ssh_keys: ssh_keys(&home.join(".ssh")),
+        existing_config: existing_config(settings),
+        suggestions,
+    }
+}
+
+/// Shells listed in /etc/shells that exist, the login shell first
+#[cfg(not(target_os = "windows"))]
+fn shells(login_shell: Option<&str>) -> Vec<ShellInfo> {
+    let listed = std::fs::read_to_string("/etc/shells").unwrap_or_default();
+    let mut shells = parse_shells(&listed, login_shell);
+    shells.retain(|shell| shell.path.is_file());
+    shells
+}
+
+#[cfg(target_os = "windows")]
+fn shells(_login_shell: Option<&str>) -> Vec<ShellInfo> {
+    ["pwsh", "powershell", "cmd"]
+        .into_iter()
+        .filter_map(|name| {
+            find_in_path(name).map(|path| ShellInfo {
+                name: name.to_string(),
+                path,
+                default: false,
+            })
+        })
+        .collect()
+}
+
+fn parse_shells(listed: &str, login_shell: Option<&str>) -> Vec<ShellInfo> {
+    let mut shells: Vec<ShellInfo> = Vec::new();
+    for line in listed.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let path = PathBuf::from(line);
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        // /bin/zsh and /usr/bin/zsh are usually the same shell
+        if shells.iter().any(|shell| shell.name == name) {
+            if login_shell == Some(line) {
+                shells.retain(|shell| shell.name != name);
+            } else {
+                continue;
+            }
+        }
+        shells.push(ShellInfo {
+            name: name.to_string(),
+            default: login_shell == Some(line),
+            path,
+        });
+    }
+    shells.sort_by_key(|shell| !shell.default);
+    shells
+}
+
+fn git_info() -> GitInfo {
+    let version = tool("git", &["--version"]).version;
+    let config = git2::Config::open_default().ok();
+    let get = |key: &str| {
+        config
+            .as_ref()
+            .and_then(|config| config.get_string(key).ok())
+            .filter(|value| !value.trim().is_empty())
+    };
+    GitInfo {
+        version,
+        user_name: get("user.name"),
+        user_email: get("user.email"),
+        default_branch: get("init.defaultBranch"),
+        signing_key: get("user.signingkey"),
+    }
+}
+
+fn tool(name: &str, version_args: &[&str]) -> ToolInfo {
+    let path = find_in_path(name);
+    let version = path.as_ref().and_then(|path| {
+        let output = Command::new(path).args(version_args).output().ok()?;
+        // ssh -V reports on stderr
+        let text = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        parse_version(&text)
+    });
+    ToolInfo {
+        name: name.to_string(),
+        path,
+        version,
+    }
+}
+
+/// The first dotted version number in `text`, e.g. `2.43.0` of `git version 2.43.0`
+fn parse_version(text: &str) -> Option<String> {
+    text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '.'))
+        .map(|word| word.trim_start_matches('v'))
+        .find(|word| word.contains('.') && word.starts_with(|c: char| c.is_ascii_digit()))
+        .map(|word| word.trim_end_matches('.').to_string())
+}
+
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    let names: Vec<String> = if cfg!(target_os = "windows") {
+        vec![format!("{}.exe", name), format!("{}.cmd", name)]
+    } else {
+        vec![name.to_string()]
+    };
+    std::env::split_paths(&paths)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+fn gh_hosts_file(home: &Path) -> Option<PathBuf> {
+    let config = match std::env::var_os("GH_CONFIG_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None if cfg!(target_os = "windows") => dirs::config_dir()?.join("GitHub CLI"),
+        None => home.join(".config").join("gh"),
+    };
+    Some(config.join("hosts.yml"))
+}
+
+fn ssh_keys(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut keys: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.ends_with(".pub"))
+        .collect();
+    keys.sort();
+    keys
+}
+
+fn existing_config(settings: &Settings) -> ExistingConfig {
+    let config_dir = storage::config_dir().unwrap_or_default();
+    ExistingConfig {
+        config_file: storage::config_path().ok().filter(|path| path.is_file()),
+        profiles: profiles::list_profiles()
+            .map(|profiles| profiles.len())
+            .unwrap_or_default(),
+        github_token_stored: keychain::github_token(&settings.github)
+            .ok()
+            .flatten()
+            .is_some(),
+        config_dir,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_tool_versions() {
+        assert_eq!(
+            parse_version("git version 2.43.0\n").as_deref(),
+            Some("2.43.0")
+        );
+        assert_eq!(parse_version("v20.11.1").as_deref(), Some("20.11.1"));
+        assert_eq!(
+            parse_version("OpenSSH_9.6p1, LibreSSL 3.3.6").as_deref(),
+            Some("9.6p1")
+        );
+        assert_eq!(
+            parse_version("gh version 2.40.1 (2023-12-13)").as_deref(),
+            Some("2.40.1")
+        );
+        assert_eq!(parse_version("command not found"), None);
+    }
+
+    #[test]
+    fn test_lists_each_shell_once_with_the_login_shell_first() {
+        let listed = "# List of acceptable shells\n/bin/sh\n/bin/bash\n/usr/bin/bash\n/bin/zsh\n/usr/bin/zsh\n";
+        let shells = parse_shells(listed, Some("/usr/bin/zsh"));
+        let names: Vec<(&str, bool)> = shells
+            .iter()
+            .map(|shell| (shell.name.as_str(), shell.default))
+            .collect();
+        assert_eq!(names, [("zsh", true), ("sh", false), ("bash", false)]);
+        assert_eq!(shells[0].path, PathBuf::from("/usr/bin/zsh"));
+    }
+}
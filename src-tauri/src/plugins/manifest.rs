@@ -0,0 +1,162 @@
+use crate::tasks::{TaskDefinition, TaskFile};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Manifest file of a plugin, inside its directory under ~/.zeami/plugins
+pub const MANIFEST_FILE: &str = "plugin.toml";
+
+/// Contents of plugin.toml
+///
+/// ```toml
+/// name = "jira"
+/// description = "Link issues to Jira tickets"
+/// command = "node"
+/// args = ["index.js"]
+/// events = ["git-commit", "task-status"]
+///
+/// [tasks.sync]
+/// command = "node sync.js"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginManifest {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Program speaking JSON-RPC on stdin/stdout; relative paths are resolved
+    /// against the plugin directory, bare names through PATH
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Events forwarded to the plugin as `event` notifications
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Tasks added to every project as `<plugin>:<task>`
+    #[serde(default)]
+    pub tasks: BTreeMap<String, TaskDefinition>,
+}
+
+impl PluginManifest {
+    /// Manifest of the plugin in `dir`
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(MANIFEST_FILE);
+        let text =
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        Self::parse(&text).with_context(|| format!("Invalid {:?}", path))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let manifest: PluginManifest = toml::from_str(text)?;
+        let valid_name = !manifest.name.is_empty()
+            && manifest
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            bail!(
+                "Plugin name {:?} may only use letters, digits, - and _",
+                manifest.name
+            );
+        }
+        if manifest.command.trim().is_empty() {
+            bail!("Plugin {} has no command", manifest.name);
+        }
+        let tasks = TaskFile {
+            tasks: manifest.qualified_tasks(),
+            ..TaskFile::default()
+        };
+        for name in tasks.tasks.keys() {
+            tasks.plan(name)?;
+        }
+        Ok(manifest)
+    }
+
+    /// The program to start, with paths like `./bin/plugin` taken from `dir`
+    pub fn program(&self, dir: &Path) -> PathBuf {
+        let command = Path::new(&self.command);
+        if command.components().count() > 1 {
+            dir.join(command)
+        } else {
+            command.to_path_buf()
+        }
+    }
+
+    /// Contributed tasks under their qualified names
+    ///
+    /// Dependencies on the plugin's own tasks are qualified the same way, so a
+    /// plugin cannot depend on (or be hijacked through) project task names.
+    pub fn qualified_tasks(&self) -> BTreeMap<String, TaskDefinition> {
+        self.tasks
+            .iter()
+            .map(|(name, task)| {
+                let mut task = task.clone();
+                task.depends_on = task
+                    .depends_on
+                    .iter()
+                    .map(|dependency| self.task_name(dependency))
+                    .collect();
+                (self.task_name(name), task)
+            })
+            .collect()
+    }
+
+    fn task_name(&self, task: &str) -> String {
+        format!("{}:{}", self.name, task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+name = "jira"
+command = "./bin/jira"
+events = ["git-commit"]
+
+[tasks.sync]
+command = "jira sync"
+depends_on = ["login"]
+
+[tasks.login]
+command = "jira login"
+"#;
+
+    #[test]
+    fn test_parses_a_manifest() {
+        let manifest = PluginManifest::parse(MANIFEST).unwrap();
+        assert_eq!(manifest.name, "jira");
+        assert_eq!(manifest.events, vec!["git-commit"]);
+        assert_eq!(
+            manifest.program(Path::new("/plugins/jira")),
+            PathBuf::from("/plugins/jira/bin/jira")
+        );
+
+        let bare = PluginManifest::parse("name = \"x\"\ncommand = \"node\"").unwrap();
+        assert_eq!(bare.program(Path::new("/plugins/x")), PathBuf::from("node"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_manifests() {
+        assert!(PluginManifest::parse("name = \"../evil\"\ncommand = \"x\"").is_err());
+        assert!(PluginManifest::parse("name = \"ok\"\ncommand = \" \"").is_err());
+        assert!(PluginManifest::parse("name = \"ok\"\ncommand = \"x\"\nunknown = 1").is_err());
+        let missing_dependency =
+            "name = \"ok\"\ncommand = \"x\"\n[tasks.a]\ncommand = \"a\"\ndepends_on = [\"b\"]";
+        assert!(PluginManifest::parse(missing_dependency).is_err());
+    }
+
+    #[test]
+    fn test_qualifies_task_names_and_dependencies() {
+        let tasks = PluginManifest::parse(MANIFEST).unwrap().qualified_tasks();
+        assert_eq!(
+            tasks.keys().collect::<Vec<_>>(),
+            vec!["jira:login", "jira:sync"]
+        );
+        assert_eq!(tasks["jira:sync"].depends_on, vec!["jira:login"]);
+    }
+}
@@ -0,0 +1,461 @@
+//! Backend extensions running as sidecar processes
+//!
+//! Every directory under ~/.zeami/plugins with a plugin.toml (see manifest)
+//! is started at launch and spoken to over JSON-RPC on stdin/stdout (see
+//! rpc). A plugin offers commands the frontend calls through
+//! `invoke_plugin_command`, receives the events its manifest lists, and
+//! contributes tasks that show up in every project as `<plugin>:<task>`.
+//! Plugins are installed by the user, not by projects, so they run with the
+//! user's trust; a plugin that fails to start or exits only affects itself.
+
+pub mod manifest;
+pub mod rpc;
+
+pub use manifest::PluginManifest;
+pub use rpc::PluginCommand;
+
+use crate::config::storage;
+use crate::tasks::TaskRunner;
+use anyhow::{anyhow, bail, Context, Result};
+use rpc::{Incoming, InitializeResult, Outgoing, RpcError};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, EventHandler, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot, Notify};
+
+/// How long a plugin may take to answer `initialize`
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a plugin command may run
+const INVOKE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A plugin as reported by `list_plugins`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub description: Option<String>,
+    pub dir: PathBuf,
+    pub running: bool,
+    pub commands: Vec<PluginCommand>,
+    pub events: Vec<String>,
+    /// Contributed tasks under their qualified names
+    pub tasks: Vec<String>,
+    /// Why the plugin is not running
+    pub error: Option<String>,
+}
+
+/// Plugin directory ~/.zeami/plugins
+pub fn plugins_dir() -> Result<PathBuf> {
+    Ok(storage::config_dir()?.join("plugins"))
+}
+
+/// Start the installed plugins in the background at launch
+pub fn spawn_load(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = app.state::<PluginHost>().reload(&app).await {
+            tracing::warn!("Failed to load plugins: {:#}", e);
+        }
+    });
+}
+
+/// The loaded plugins, managed as Tauri state
+#[derive(Default)]
+pub struct PluginHost {
+    loaded: Mutex<Loaded>,
+    /// Serializes reloads
+    reloading: tokio::sync::Mutex<()>,
+}
+
+#[derive(Default)]
+struct Loaded {
+    plugins: BTreeMap<String, Plugin>,
+    listeners: Vec<EventHandler>,
+}
+
+struct Plugin {
+    info: PluginInfo,
+    connection: Option<Arc<Connection>>,
+}
+
+impl PluginHost {
+    /// Stop the running plugins, then start every plugin found in plugins_dir
+    pub async fn reload(&self, app: &AppHandle) -> Result<Vec<PluginInfo>> {
+        let _reloading = self.reloading.lock().await;
+        self.stop_all(app);
+
+        let dir = plugins_dir()?;
+        let mut plugins = BTreeMap::new();
+        for plugin_dir in plugin_dirs(&dir)? {
+            let plugin = start(app, &plugin_dir).await;
+            if let Some(error) = &plugin.info.error {
+                tracing::warn!(path = ?plugin_dir, "Plugin not started: {}", error);
+            }
+            if plugins.contains_key(&plugin.info.name) {
+                tracing::warn!(path = ?plugin_dir, "Ignoring plugin with a duplicate name");
+                continue;
+            }
+            plugins.insert(plugin.info.name.clone(), plugin);
+        }
+
+        let mut tasks = BTreeMap::new();
+        let mut listeners = Vec::new();
+        for plugin in plugins.values() {
+            if let Some(connection) = &plugin.connection {
+                tasks.extend(connection.manifest.qualified_tasks());
+                for event in &plugin.info.events {
+                    let connection = connection.clone();
+                    let event_name = event.clone();
+                    listeners.push(app.listen_global(event.clone(), move |event| {
+                        let payload = event
+                            .payload()
+                            .and_then(|payload| serde_json::from_str(payload).ok())
+                            .unwrap_or(Value::Null);
+                        connection
+                            .notify("event", json!({ "event": event_name, "payload": payload }));
+                    }));
+                }
+            }
+        }
+        app.state::<TaskRunner>().set_contributed(tasks);
+
+        let infos = plugins.values().map(|plugin| plugin.info.clone()).collect();
+        let mut loaded = self.loaded.lock().unwrap();
+        loaded.plugins = plugins;
+        loaded.listeners = listeners;
+        tracing::info!(count = loaded.plugins.len(), "Loaded plugins");
+        Ok(infos)
+    }
+
+    pub fn list(&self) -> Vec<PluginInfo> {
+        self.loaded
+            .lock()
+            .unwrap()
+            .plugins
+            .values()
+            .map(|plugin| PluginInfo {
+                running: plugin
+                    .connection
+                    .as_ref()
+                    .is_some_and(|connection| connection.is_running()),
+                ..plugin.info.clone()
+            })
+            .collect()
+    }
+
+    /// Run `command` of `plugin` and return its result
+    pub async fn invoke(
+        &self,
+        plugin: &str,
+        command: &str,
+        args: Value,
+        project_root: Option<&Path>,
+    ) -> Result<Value> {
+        let connection = {
+            let loaded = self.loaded.lock().unwrap();
+            let found = loaded
+                .plugins
+                .get(plugin)
+                .with_context(|| format!("No plugin named {}", plugin))?;
+            if !found.info.commands.iter().any(|c| c.name == command) {
+                bail!("Plugin {} has no command {}", plugin, command);
+            }
+            found
+                .connection
+                .clone()
+                .filter(|connection| connection.is_running())
+                .with_context(|| format!("Plugin {} is not running", plugin))?
+        };
+
+        let params = json!({
+            "command": command,
+            "args": args,
+            "project_root": project_root,
+        });
+        connection.request("invoke", params, INVOKE_TIMEOUT).await
+    }
+
+    fn stop_all(&self, app: &AppHandle) {
+        let mut loaded = self.loaded.lock().unwrap();
+        for listener in loaded.listeners.drain(..) {
+            app.unlisten(listener);
+        }
+        for plugin in std::mem::take(&mut loaded.plugins).into_values() {
+            if let Some(connection) = plugin.connection {
+                connection.shutdown();
+            }
+        }
+    }
+}
+
+/// Subdirectories of `dir` holding a plugin manifest, in name order
+fn plugin_dirs(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", dir)),
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.join(manifest::MANIFEST_FILE).is_file())
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Start the plugin in `dir`; failures are recorded in the returned info
+async fn start(app: &AppHandle, dir: &Path) -> Plugin {
+    let fallback_name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let manifest = match PluginManifest::load(dir) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            return Plugin {
+                info: PluginInfo {
+                    name: fallback_name,
+                    description: None,
+                    dir: dir.to_path_buf(),
+                    running: false,
+                    commands: Vec::new(),
+                    events: Vec::new(),
+                    tasks: Vec::new(),
+                    error: Some(format!("{:#}", e)),
+                },
+                connection: None,
+            }
+        }
+    };
+
+    let mut info = PluginInfo {
+        name: manifest.name.clone(),
+        description: manifest.description.clone(),
+        dir: dir.to_path_buf(),
+        running: false,
+        commands: Vec::new(),
+        events: manifest.events.clone(),
+        tasks: manifest.qualified_tasks().into_keys().collect(),
+        error: None,
+    };
+    match Connection::spawn(app, dir, manifest).await {
+        Ok((connection, initialized)) => {
+            info.running = true;
+            info.commands = initialized.commands;
+            Plugin {
+                info,
+                connection: Some(connection),
+            }
+        }
+        Err(e) => {
+            info.error = Some(format!("{:#}", e));
+            Plugin {
+                info,
+                connection: None,
+            }
+        }
+    }
+}
+
+type Pending = Mutex<HashMap<u64, oneshot::Sender<Result<Value, RpcError>>>>;
+
+/// A running plugin process
+struct Connection {
+    manifest: PluginManifest,
+    outgoing: mpsc::UnboundedSender<String>,
+    pending: Arc<Pending>,
+    next_id: AtomicU64,
+    running: Arc<AtomicBool>,
+    stop: Arc<Notify>,
+}
+
+impl Connection {
+    async fn spawn(
+        app: &AppHandle,
+        dir: &Path,
+        manifest: PluginManifest,
+    ) -> Result<(Arc<Self>, InitializeResult)> {
+        let mut child = Command::new(manifest.program(dir))
+            .args(&manifest.args)
+            .envs(&manifest.env)
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start {}", manifest.command))?;
+
+        let name = manifest.name.clone();
+        let stdin = child.stdin.take().context("Plugin stdin is not piped")?;
+        let stdout = child.stdout.take().context("Plugin stdout is not piped")?;
+        let stderr = child.stderr.take().context("Plugin stderr is not piped")?;
+
+        let (outgoing, receiver) = mpsc::unbounded_channel();
+        let connection = Arc::new(Self {
+            manifest,
+            outgoing,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            next_id: AtomicU64::new(1),
+            running: Arc::new(AtomicBool::new(true)),
+            stop: Arc::new(Notify::new()),
+        });
+
+        tauri::async_runtime::spawn(write_lines(name.clone(), stdin, receiver));
+        tauri::async_runtime::spawn(read_messages(
+            name.clone(),
+            stdout,
+            connection.pending.clone(),
+        ));
+        tauri::async_runtime::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                tracing::debug!(plugin = %name, "{}", line);
+            }
+        });
+        tauri::async_runtime::spawn(supervise(
+            connection.manifest.name.clone(),
+            child,
+            connection.running.clone(),
+            connection.stop.clone(),
+        ));
+
+        let params = json!({
+            "zeami_version": app.package_info().version.to_string(),
+            "plugin_dir": dir,
+        });
+        let initialized = connection
+            .request("initialize", params, INITIALIZE_TIMEOUT)
+            .await
+            .and_then(|result| serde_json::from_value(result).context("Invalid initialize result"));
+        match initialized {
+            Ok(initialized) => Ok((connection, initialized)),
+            Err(e) => {
+                connection.shutdown();
+                Err(e)
+            }
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    async fn request(&self, method: &str, params: Value, timeout: Duration) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, sender);
+        self.send(Outgoing::request(id, method, params))?;
+
+        let result = tokio::time::timeout(timeout, receiver).await;
+        self.pending.lock().unwrap().remove(&id);
+        match result {
+            Ok(Ok(Ok(value))) => Ok(value),
+            Ok(Ok(Err(error))) => Err(anyhow!("{} (code {})", error.message, error.code)),
+            Ok(Err(_)) => bail!("Plugin {} exited", self.manifest.name),
+            Err(_) => bail!(
+                "Plugin {} did not answer {} within {}s",
+                self.manifest.name,
+                method,
+                timeout.as_secs()
+            ),
+        }
+    }
+
+    fn notify(&self, method: &str, params: Value) {
+        if let Err(e) = self.send(Outgoing::notification(method, params)) {
+            tracing::debug!(plugin = %self.manifest.name, "Dropped notification: {:#}", e);
+        }
+    }
+
+    fn send(&self, message: Outgoing) -> Result<()> {
+        self.outgoing
+            .send(message.to_line()?)
+            .map_err(|_| anyhow!("Plugin {} exited", self.manifest.name))
+    }
+
+    /// Ask the plugin to exit, killing it if it is still running after a grace period
+    fn shutdown(&self) {
+        self.notify("shutdown", Value::Null);
+        let stop = self.stop.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            stop.notify_one();
+        });
+    }
+}
+
+async fn write_lines(
+    plugin: String,
+    mut stdin: tokio::process::ChildStdin,
+    mut lines: mpsc::UnboundedReceiver<String>,
+) {
+    while let Some(line) = lines.recv().await {
+        if let Err(e) = stdin.write_all(line.as_bytes()).await {
+            tracing::debug!(%plugin, "Failed to write to plugin: {}", e);
+            break;
+        }
+    }
+}
+
+async fn read_messages(plugin: String, stdout: tokio::process::ChildStdout, pending: Arc<Pending>) {
+    let mut lines = BufReader::new(stdout).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match Incoming::parse(&line) {
+            Ok(Incoming::Response { id, result }) => {
+                if let Some(sender) = pending.lock().unwrap().remove(&id) {
+                    let _ = sender.send(result);
+                }
+            }
+            Ok(Incoming::Notification { method, params }) if method == "log" => {
+                log(&plugin, &params)
+            }
+            Ok(Incoming::Notification { method, .. }) => {
+                tracing::debug!(%plugin, "Ignoring plugin notification {}", method)
+            }
+            Err(e) => tracing::warn!(%plugin, "Invalid message from plugin: {:#}", e),
+        }
+    }
+    // Dropping the senders fails the requests still waiting
+    pending.lock().unwrap().clear();
+}
+
+/// Wait for the plugin to exit, or kill it once `stop` is notified
+async fn supervise(plugin: String, mut child: Child, running: Arc<AtomicBool>, stop: Arc<Notify>) {
+    let status = tokio::select! {
+        status = child.wait() => status,
+        _ = stop.notified() => {
+            if let Err(e) = child.kill().await {
+                tracing::debug!(%plugin, "Failed to kill plugin: {}", e);
+            }
+            child.wait().await
+        }
+    };
+    running.store(false, Ordering::SeqCst);
+    match status {
+        Ok(status) if status.success() => tracing::info!(%plugin, "Plugin exited"),
+        Ok(status) => tracing::warn!(%plugin, "Plugin exited with {}", status),
+        Err(e) => tracing::warn!(%plugin, "Failed to wait for plugin: {}", e),
+    }
+}
+
+/// Route a plugin's `log` notification into the app log
+fn log(plugin: &str, params: &Value) {
+    let message = params["message"].as_str().unwrap_or_default();
+    match params["level"].as_str().unwrap_or("info") {
+        "error" => tracing::error!(%plugin, "{}", message),
+        "warn" => tracing::warn!(%plugin, "{}", message),
+        "debug" | "trace" => tracing::debug!(%plugin, "{}", message),
+        _ => tracing::info!(%plugin, "{}", message),
+    }
+}
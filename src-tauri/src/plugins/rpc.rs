@@ -0,0 +1,161 @@
+//! JSON-RPC 2.0 messages exchanged with plugin processes, one per line
+//!
+//! Zeami sends `initialize` (answered with the plugin's commands) and
+//! `invoke` requests, plus `event` and `shutdown` notifications. A plugin
+//! answers requests and may send `log` notifications.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const VERSION: &str = "2.0";
+
+/// A request (with `id`) or notification (without) sent to a plugin
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Outgoing {
+    pub jsonrpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl Outgoing {
+    pub fn request(id: u64, method: &str, params: Value) -> Self {
+        Self {
+            jsonrpc: VERSION.to_string(),
+            id: Some(id),
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    pub fn notification(method: &str, params: Value) -> Self {
+        Self {
+            jsonrpc: VERSION.to_string(),
+            id: None,
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    /// The message as one line of JSON, newline included
+    pub fn to_line(&self) -> serde_json::Result<String> {
+        let mut line = serde_json::to_string(self)?;
+        line.push('\n');
+        Ok(line)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+/// A line received from a plugin
+#[derive(Debug, Clone, PartialEq)]
+pub enum Incoming {
+    Response {
+        id: u64,
+        result: Result<Value, RpcError>,
+    },
+    Notification {
+        method: String,
+        params: Value,
+    },
+}
+
+#[derive(Deserialize)]
+struct RawIncoming {
+    id: Option<u64>,
+    method: Option<String>,
+    #[serde(default)]
+    params: Value,
+    result: Option<Value>,
+    error: Option<RpcError>,
+}
+
+impl Incoming {
+    pub fn parse(line: &str) -> anyhow::Result<Self> {
+        let raw: RawIncoming = serde_json::from_str(line)?;
+        match (raw.method, raw.id) {
+            (Some(method), None) => Ok(Incoming::Notification {
+                method,
+                params: raw.params,
+            }),
+            (None, Some(id)) => Ok(Incoming::Response {
+                id,
+                result: match raw.error {
+                    Some(error) => Err(error),
+                    None => Ok(raw.result.unwrap_or(Value::Null)),
+                },
+            }),
+            (Some(method), Some(_)) => {
+                anyhow::bail!("Plugins cannot send requests ({})", method)
+            }
+            (None, None) => anyhow::bail!("Message is neither a response nor a notification"),
+        }
+    }
+}
+
+/// Command a plugin offers through `invoke_plugin_command`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginCommand {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Result of `initialize`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InitializeResult {
+    pub commands: Vec<PluginCommand>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_serializes_requests_and_notifications() {
+        let request = Outgoing::request(1, "invoke", json!({ "command": "sync" }));
+        assert_eq!(
+            request.to_line().unwrap(),
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"invoke\",\"params\":{\"command\":\"sync\"}}\n"
+        );
+        let notification = Outgoing::notification("shutdown", Value::Null);
+        assert!(!notification.to_line().unwrap().contains("\"id\""));
+    }
+
+    #[test]
+    fn test_parses_responses_and_notifications() {
+        assert_eq!(
+            Incoming::parse(r#"{"jsonrpc":"2.0","id":3,"result":{"ok":true}}"#).unwrap(),
+            Incoming::Response {
+                id: 3,
+                result: Ok(json!({ "ok": true }))
+            }
+        );
+        match Incoming::parse(r#"{"jsonrpc":"2.0","id":4,"error":{"code":-32601,"message":"no"}}"#)
+            .unwrap()
+        {
+            Incoming::Response { id: 4, result } => assert_eq!(result.unwrap_err().code, -32601),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            Incoming::parse(r#"{"jsonrpc":"2.0","method":"log","params":{"message":"hi"}}"#)
+                .unwrap(),
+            Incoming::Notification {
+                method: "log".to_string(),
+                params: json!({ "message": "hi" })
+            }
+        );
+        assert!(Incoming::parse(r#"{"jsonrpc":"2.0","id":1,"method":"x"}"#).is_err());
+        assert!(Incoming::parse("not json").is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use std::time::{Duration, Instant};
+
+/// Output gap after which a busy terminal counts as quiet
+pub const QUIET_AFTER: Duration = Duration::from_secs(10);
+
+/// Shortest burst of output worth a notification when it goes quiet
+const LONG_BURST: Duration = Duration::from_secs(60);
+
+/// Notices when a terminal that kept printing for a long time goes quiet
+///
+/// This catches long commands finishing in shells without integration marks:
+/// a burst is output with no gap of QUIET_AFTER or more.
+#[derive(Debug, Default)]
+pub struct SilenceDetector {
+    burst_start: Option<Instant>,
+    last_output: Option<Instant>,
+}
+
+impl SilenceDetector {
+    pub fn output(&mut self, now: Instant) {
+        let quiet = self
+            .last_output
+            .is_none_or(|last| now.duration_since(last) >= QUIET_AFTER);
+        if quiet || self.burst_start.is_none() {
+            self.burst_start = Some(now);
+        }
+        self.last_output = Some(now);
+    }
+
+    /// Forget the current burst, e.g. when shell integration already reported its end
+    pub fn reset(&mut self) {
+        self.burst_start = None;
+    }
+
+    /// Length of a long burst that has just gone quiet; each burst is reported once
+    pub fn check(&mut self, now: Instant) -> Option<Duration> {
+        let start = self.burst_start?;
+        let last = self.last_output?;
+        if now.duration_since(last) < QUIET_AFTER {
+            return None;
+        }
+        self.burst_start = None;
+        let burst = last.duration_since(start);
+        (burst >= LONG_BURST).then_some(burst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_burst_then_quiet() {
+        let start = Instant::now();
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+        let mut detector = SilenceDetector::default();
+
+        for second in (0..=90).step_by(5) {
+            detector.output(at(second));
+            assert_eq!(detector.check(at(second + 1)), None);
+        }
+        assert_eq!(detector.check(at(95)), None);
+        assert_eq!(detector.check(at(100)), Some(Duration::from_secs(90)));
+        assert_eq!(detector.check(at(120)), None);
+
+        // Short bursts, and output split by quiet gaps, are not reported
+        detector.output(at(200));
+        detector.output(at(230));
+        detector.output(at(250));
+        assert_eq!(detector.check(at(300)), None);
+
+        // A burst whose end shell integration reported is not reported again
+        for second in (400..=500).step_by(5) {
+            detector.output(at(second));
+        }
+        detector.reset();
+        assert_eq!(detector.check(at(600)), None);
+    }
+}
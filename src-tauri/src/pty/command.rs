@@ -0,0 +1,274 @@
+use super::shell_integration::clean_text;
+use anyhow::{Context, Result};
+use portable_pty::{CommandBuilder, NativePtySystem, PtySize, PtySystem};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Output kept from a command; longer output keeps its end
+const OUTPUT_MAX_BYTES: usize = 256 * 1024;
+
+/// How often the child is polled for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long output is still collected after the child exits
+const DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Result of a command run to completion in its own PTY
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandOutput {
+    /// None when the command was killed after timing out
+    pub exit_code: Option<u32>,
+    /// Combined stdout and stderr without escape sequences
+    pub output: String,
+    pub timed_out: bool,
+    /// Stopped through CommandOptions::cancel
+    #[serde(default)]
+    pub cancelled: bool,
+    pub duration_ms: u64,
+}
+
+/// The end of `output`, at most `max_bytes` long and cut at a character boundary
+pub fn output_tail(output: &str, max_bytes: usize) -> &str {
+    let mut start = output.len().saturating_sub(max_bytes);
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    &output[start..]
+}
+
+/// Settings for run_command_with beyond the command line
+#[derive(Debug, Clone, Default)]
+pub struct CommandOptions {
+    /// Variables added to the inherited environment
+    pub env: BTreeMap<String, String>,
+    /// Set to true to kill the command early
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Receives the child's process id once it starts
+    pub process_id: Option<Arc<AtomicU32>>,
+}
+
+/// Run `command` through the platform shell in a fresh PTY and wait for it
+///
+/// A PTY rather than pipes keeps tools that check for a terminal (colored test
+/// runners, progress bars) behaving as they do in the user's own sessions.
+/// Blocks the calling thread for up to `timeout`.
+pub fn run_command_with(
+    command: &str,
+    cwd: &Path,
+    timeout: Duration,
+    options: &CommandOptions,
+) -> Result<CommandOutput> {
+    #[cfg(target_os = "windows")]
+    let cmd = {
+        let mut cmd = CommandBuilder::new("cmd.exe");
+        cmd.args(["/C", command]);
+        cmd
+    };
+    #[cfg(not(target_os = "windows"))]
+    let cmd = {
+        let mut cmd = CommandBuilder::new("/bin/sh");
+        cmd.args(["-c", command]);
+        cmd
+    };
+    run(cmd, command, cwd, timeout, options)
+}
+
+/// Run `program` with `args` as run_command_with does, but without a shell
+pub fn run_program_with(
+    program: &str,
+    args: &[String],
+    cwd: &Path,
+    timeout: Duration,
+    options: &CommandOptions,
+) -> Result<CommandOutput> {
+    let mut cmd = CommandBuilder::new(program);
+    cmd.args(args);
+    run(cmd, program, cwd, timeout, options)
+}
+
+fn run(
+    mut cmd: CommandBuilder,
+    command: &str,
+    cwd: &Path,
+    timeout: Duration,
+    options: &CommandOptions,
+) -> Result<CommandOutput> {
+    let pair = NativePtySystem::default()
+        .openpty(PtySize {
+            rows: 40,
+            cols: 160,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .context("Failed to open PTY")?;
+
+    cmd.cwd(cwd);
+    for (key, value) in &options.env {
+        cmd.env(key, value);
+    }
+
+    let started = Instant::now();
+    let mut child = pair
+        .slave
+        .spawn_command(cmd)
+        .with_context(|| format!("Failed to run {}", command))?;
+    // Only the child holds the slave now, so the reader sees EOF when it exits
+    drop(pair.slave);
+    if let (Some(slot), Some(pid)) = (&options.process_id, child.process_id()) {
+        slot.store(pid, Ordering::Relaxed);
+    }
+
+    let mut reader = pair
+        .master
+        .try_clone_reader()
+        .context("Failed to get PTY reader")?;
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = [0u8; 8192];
+        while let Ok(n) = reader.read(&mut buffer) {
+            if n == 0 || sender.send(buffer[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut output = Vec::new();
+    let mut collect = |chunk: Vec<u8>| {
+        output.extend_from_slice(&chunk);
+        if output.len() > OUTPUT_MAX_BYTES {
+            output.drain(..output.len() - OUTPUT_MAX_BYTES);
+        }
+    };
+
+    let cancelled = || {
+        options
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    };
+    let (exit_code, timed_out, cancelled) = loop {
+        while let Ok(chunk) = receiver.try_recv() {
+            collect(chunk);
+        }
+        if let Some(status) = child.try_wait()? {
+            break (Some(status.exit_code()), false, false);
+        }
+        let timed_out = started.elapsed() >= timeout;
+        if timed_out || cancelled() {
+            if let Err(e) = child.kill() {
+                tracing::warn!("Failed to kill command {}: {}", command, e);
+            }
+            break (None, timed_out, !timed_out);
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    let drain_until = Instant::now() + DRAIN_TIMEOUT;
+    while let Some(remaining) = drain_until.checked_duration_since(Instant::now()) {
+        match receiver.recv_timeout(remaining) {
+            Ok(chunk) => collect(chunk),
+            Err(_) => break,
+        }
+    }
+
+    Ok(CommandOutput {
+        exit_code,
+        output: clean_text(&String::from_utf8_lossy(&output)),
+        timed_out,
+        cancelled,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_tail() {
+        assert_eq!(output_tail("short", 16), "short");
+        assert_eq!(output_tail("0123456789", 4), "6789");
+        assert_eq!(output_tail("aéé", 3), "é");
+    }
+
+    #[test]
+    fn test_run_command() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "hello").unwrap();
+
+        let result = run_command_with(
+            "cat a.txt; exit 3",
+            dir.path(),
+            Duration::from_secs(10),
+            &CommandOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(result.exit_code, Some(3));
+        assert_eq!(result.output, "hello");
+        assert!(!result.timed_out);
+
+        let result = run_command_with(
+            "sleep 5",
+            dir.path(),
+            Duration::from_millis(200),
+            &CommandOptions::default(),
+        )
+        .unwrap();
+        assert!(result.timed_out);
+        assert_eq!(result.exit_code, None);
+
+        let args = [
+            "-c".to_string(),
+            "echo $0".to_string(),
+            "direct".to_string(),
+        ];
+        let result = run_program_with(
+            "/bin/sh",
+            &args,
+            dir.path(),
+            Duration::from_secs(10),
+            &CommandOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(result.output, "direct");
+    }
+
+    #[test]
+    fn test_env_and_cancel() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = CommandOptions {
+            env: BTreeMap::from([("GREETING".to_string(), "hi".to_string())]),
+            cancel: Some(Arc::new(AtomicBool::new(false))),
+            process_id: Some(Arc::new(AtomicU32::new(0))),
+        };
+        let result = run_command_with(
+            "echo $GREETING",
+            dir.path(),
+            Duration::from_secs(10),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(result.output, "hi");
+        assert_ne!(
+            options.process_id.as_ref().unwrap().load(Ordering::Relaxed),
+            0
+        );
+
+        let cancel = options.cancel.clone().unwrap();
+        let stopper = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            cancel.store(true, Ordering::Relaxed);
+        });
+        let result =
+            run_command_with("sleep 5", dir.path(), Duration::from_secs(10), &options).unwrap();
+        stopper.join().unwrap();
+        assert!(result.cancelled);
+        assert!(!result.timed_out);
+    }
+}
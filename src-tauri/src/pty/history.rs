@@ -0,0 +1,318 @@
+//! Commands run in every terminal session, kept in ~/.zeami/history.db
+//!
+//! Each command reported by shell integration is stored with its working
+//! directory, exit code, duration and session, so a Ctrl-R search can reach
+//! commands typed in any session, past or present.
+
+use super::shell_integration::CommandRecord;
+use crate::config::storage;
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Commands kept; the oldest are removed beyond this
+const MAX_ENTRIES: i64 = 100_000;
+/// Results returned when the caller sets no limit
+const DEFAULT_LIMIT: usize = 100;
+
+/// A stored command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub command: String,
+    pub cwd: Option<String>,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    pub session_id: String,
+    pub started_at: DateTime<Utc>,
+    /// Times the command matched; above 1 only for deduplicated results
+    pub runs: u64,
+}
+
+/// Narrows search_command_history; every field is optional
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryFilter {
+    /// Only commands run in this directory or below it
+    pub cwd: Option<String>,
+    pub session_id: Option<String>,
+    /// Only commands that exited with 0
+    pub succeeded: Option<bool>,
+    pub since: Option<DateTime<Utc>>,
+    /// Most recent first; DEFAULT_LIMIT when unset
+    pub limit: Option<usize>,
+    /// Return each command line once, with its latest run; on unless false
+    pub dedup: Option<bool>,
+}
+
+/// SQLite store of terminal commands; managed by Tauri
+pub struct CommandHistory {
+    conn: Mutex<Connection>,
+}
+
+impl CommandHistory {
+    /// Open ~/.zeami/history.db, falling back to an in-memory store
+    pub fn open_default() -> Self {
+        storage::config_dir()
+            .and_then(|dir| Self::open(&dir.join("history.db")))
+            .or_else(|e| {
+                tracing::warn!("Using in-memory command history: {:#}", e);
+                Self::in_memory()
+            })
+            .expect("failed to create in-memory command history")
+    }
+
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open command history {:?}", path))?;
+        Self::init(conn)
+    }
+
+    /// A store that lives only as long as the process
+    pub fn in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS commands (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                command TEXT NOT NULL,
+                cwd TEXT,
+                exit_code INTEGER,
+                duration_ms INTEGER NOT NULL,
+                session_id TEXT NOT NULL,
+                started_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS commands_by_command ON commands (command);",
+        )
+        .context("Failed to initialize command history")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock command history: {}", e))
+    }
+
+    /// Store a command completed in `session_id`
+    pub fn record(&self, session_id: &str, record: &CommandRecord) -> Result<()> {
+        if record.command.is_empty() {
+            return Ok(());
+        }
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO commands (command, cwd, exit_code, duration_ms, session_id, started_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                record.command,
+                record.cwd,
+                record.exit_code,
+                record.duration_ms as i64,
+                session_id,
+                record.started_at.timestamp_millis(),
+            ],
+        )?;
+        let id = conn.last_insert_rowid();
+        if id % 1000 == 0 {
+            conn.execute(
+                "DELETE FROM commands WHERE id <= ?1",
+                params![id - MAX_ENTRIES],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Commands containing `query` (case-insensitive), most recent first
+    pub fn search(&self, query: &str, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>> {
+        let mut conditions = vec!["command LIKE ?1 ESCAPE '\\'".to_string()];
+        let mut values = vec![Value::Text(format!("%{}%", escape_like(query)))];
+        if let Some(cwd) = &filter.cwd {
+            let cwd = cwd.trim_end_matches('/');
+            values.push(Value::Text(cwd.to_string()));
+            values.push(Value::Text(format!("{}/%", escape_like(cwd))));
+            conditions.push(format!(
+                "(cwd = ?{} OR cwd LIKE ?{} ESCAPE '\\')",
+                values.len() - 1,
+                values.len()
+            ));
+        }
+        if let Some(session_id) = &filter.session_id {
+            values.push(Value::Text(session_id.clone()));
+            conditions.push(format!("session_id = ?{}", values.len()));
+        }
+        match filter.succeeded {
+            Some(true) => conditions.push("exit_code = 0".to_string()),
+            Some(false) => conditions.push("exit_code <> 0".to_string()),
+            None => {}
+        }
+        if let Some(since) = filter.since {
+            values.push(Value::Integer(since.timestamp_millis()));
+            conditions.push(format!("started_at >= ?{}", values.len()));
+        }
+        values.push(Value::Integer(
+            filter.limit.unwrap_or(DEFAULT_LIMIT).min(i64::MAX as usize) as i64,
+        ));
+        let limit = values.len();
+
+        let conditions = conditions.join(" AND ");
+        let sql = if filter.dedup.unwrap_or(true) {
+            format!(
+                "SELECT c.command, c.cwd, c.exit_code, c.duration_ms, c.session_id, c.started_at, g.runs
+                 FROM commands c
+                 JOIN (SELECT MAX(id) AS id, COUNT(*) AS runs FROM commands
+                       WHERE {} GROUP BY command) g ON c.id = g.id
+                 ORDER BY c.id DESC LIMIT ?{}",
+                conditions, limit
+            )
+        } else {
+            format!(
+                "SELECT command, cwd, exit_code, duration_ms, session_id, started_at, 1
+                 FROM commands WHERE {} ORDER BY id DESC LIMIT ?{}",
+                conditions, limit
+            )
+        };
+
+        let conn = self.conn()?;
+        let mut statement = conn.prepare(&sql)?;
+        let entries = statement
+            .query_map(params_from_iter(values), |row| {
+                Ok(HistoryEntry {
+                    command: row.get(0)?,
+                    cwd: row.get(1)?,
+                    exit_code: row.get(2)?,
+                    duration_ms: row.get::<_, i64>(3)? as u64,
+                    session_id: row.get(4)?,
+                    started_at: Utc
+                        .timestamp_millis_opt(row.get(5)?)
+                        .single()
+                        .unwrap_or_default(),
+                    runs: row.get::<_, i64>(6)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+}
+
+/// Escape LIKE wildcards so `text` matches literally
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn record(command: &str, cwd: &str, exit_code: i32, at: DateTime<Utc>) -> CommandRecord {
+        CommandRecord {
+            command: command.to_string(),
+            cwd: Some(cwd.to_string()),
+            exit_code: Some(exit_code),
+            output: String::new(),
+            output_truncated: false,
+            started_at: at,
+            duration_ms: 1200,
+        }
+    }
+
+    fn history() -> CommandHistory {
+        let history = CommandHistory::in_memory().unwrap();
+        let start = Utc::now() - Duration::hours(2);
+        let commands = [
+            ("a", "cargo test", "/src/app", 101),
+            ("a", "git status", "/src/app", 0),
+            ("b", "cargo test", "/src/app/crates/core", 0),
+            ("b", "npm run 100%_done", "/src/web", 0),
+            ("b", "", "/src/web", 0),
+        ];
+        for (i, (session, command, cwd, code)) in commands.into_iter().enumerate() {
+            let at = start + Duration::minutes(i as i64);
+            history
+                .record(session, &record(command, cwd, code, at))
+                .unwrap();
+        }
+        history
+    }
+
+    fn commands(entries: Vec<HistoryEntry>) -> Vec<String> {
+        entries.into_iter().map(|entry| entry.command).collect()
+    }
+
+    #[test]
+    fn test_searches_most_recent_first_with_dedup() {
+        let history = history();
+        let all = history.search("", &HistoryFilter::default()).unwrap();
+        assert_eq!(
+            commands(all.clone()),
+            ["npm run 100%_done", "cargo test", "git status"]
+        );
+        // The latest run represents a repeated command
+        assert_eq!(all[1].runs, 2);
+        assert_eq!(all[1].session_id, "b");
+        assert_eq!(all[1].exit_code, Some(0));
+
+        let every = HistoryFilter {
+            dedup: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(history.search("CARGO", &every).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_filters_by_directory_session_status_and_time() {
+        let history = history();
+        let search = |filter: HistoryFilter| commands(history.search("", &filter).unwrap());
+        assert_eq!(
+            search(HistoryFilter {
+                cwd: Some("/src/app/".to_string()),
+                dedup: Some(false),
+                ..Default::default()
+            }),
+            ["cargo test", "git status", "cargo test"]
+        );
+        assert_eq!(
+            search(HistoryFilter {
+                session_id: Some("a".to_string()),
+                succeeded: Some(false),
+                ..Default::default()
+            }),
+            ["cargo test"]
+        );
+        assert!(search(HistoryFilter {
+            since: Some(Utc::now()),
+            ..Default::default()
+        })
+        .is_empty());
+        assert_eq!(
+            search(HistoryFilter {
+                limit: Some(1),
+                ..Default::default()
+            }),
+            ["npm run 100%_done"]
+        );
+    }
+
+    #[test]
+    fn test_matches_wildcards_literally() {
+        let history = history();
+        let filter = HistoryFilter::default();
+        assert_eq!(
+            commands(history.search("100%_", &filter).unwrap()),
+            ["npm run 100%_done"]
+        );
+        assert!(history.search("0%x", &filter).unwrap().is_empty());
+    }
+}
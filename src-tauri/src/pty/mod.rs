@@ -1,3 +1,8 @@
+mod activity;
+pub mod command;
+pub mod history;
 mod session;
+pub mod shell_integration;
+pub mod ssh;
 
 pub use session::PtySession;
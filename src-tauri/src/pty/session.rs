@@ -1,9 +1,30 @@
+use super::activity::{SilenceDetector, QUIET_AFTER};
+use super::history::CommandHistory;
+use super::shell_integration::{CommandRecord, CommandTracker};
+use super::ssh;
+use crate::config::{SettingsState, SshHost};
+use crate::containers::{self, Container};
+use crate::events::schema::v1;
+use crate::events::PTY_OUTPUT_EVENT_NAME;
+use crate::frecency;
+use crate::git;
+use crate::i18n;
+use crate::journal::{self, JournalEntry, JournalKind};
+use crate::notifications::{self, NotificationCategory};
 use anyhow::{Context, Result};
 use portable_pty::{CommandBuilder, NativePtySystem, PtySize, PtySystem};
+use std::collections::BTreeMap;
 use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use tauri::Window;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Window};
+use tempfile::NamedTempFile;
+
+/// How often a session is checked for output going quiet
+const SILENCE_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 /// PTY session wrapper with shared writer and output reading
 /// Note: We don't store the PtyPair because it doesn't implement Sync
@@ -11,6 +32,13 @@ pub struct PtySession {
     writer: Arc<Mutex<Box<dyn Write + Send>>>,
     #[allow(dead_code)]
     size: Arc<Mutex<PtySize>>,
+    commands: Arc<Mutex<CommandTracker>>,
+    /// Whether the frontend shows this session; see set_focused
+    focused: Arc<AtomicBool>,
+    /// Private key of an SSH session, deleted with the session
+    _key_file: Option<NamedTempFile>,
+    /// Shell (or ssh) process, for finding what runs in the session
+    process_id: Option<u32>,
 }
 
 impl PtySession {
@@ -22,18 +50,6 @@ impl PtySession {
         window: Window,
         session_id: String,
     ) -> Result<Self> {
-        let pty_system = NativePtySystem::default();
-
-        // Create PTY with specified size
-        let pair = pty_system
-            .openpty(PtySize {
-                rows,
-                cols,
-                pixel_width: 0,
-                pixel_height: 0,
-            })
-            .context("Failed to open PTY")?;
-
         // Determine shell command
         let shell_cmd = shell.unwrap_or_else(|| {
             std::env::var("SHELL").unwrap_or_else(|_| {
@@ -48,14 +64,89 @@ impl PtySession {
             })
         });
 
-        // Spawn shell process
         let mut cmd = CommandBuilder::new(&shell_cmd);
         cmd.cwd(std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("/")));
+        Self::spawn(cmd, None, rows, cols, window, session_id)
+    }
+
+    /// Create a session running a shell on `host` over ssh
+    pub fn ssh(
+        host: &SshHost,
+        rows: u16,
+        cols: u16,
+        window: Window,
+        session_id: String,
+    ) -> Result<Self> {
+        let launch = ssh::launch(host)?;
+        let mut cmd = CommandBuilder::new(launch.program);
+        cmd.args(&launch.args);
+        cmd.cwd(std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("/")));
+        tracing::info!(%session_id, host = %host.name, "Opening SSH session");
+        Self::spawn(cmd, launch.key_file, rows, cols, window, session_id)
+    }
+
+    /// Create a session running `shell` (bash, else sh, when None) in a container
+    /// of the project at `project_root`
+    pub fn container(
+        project_root: &Path,
+        container: &Container,
+        shell: Option<String>,
+        rows: u16,
+        cols: u16,
+        window: Window,
+        session_id: String,
+    ) -> Result<Self> {
+        let workdir = containers::container_dir(&container.id, project_root)?;
+        let command = match shell {
+            Some(shell) => vec![shell],
+            None => [
+                "sh",
+                "-c",
+                "command -v bash >/dev/null && exec bash || exec sh",
+            ]
+            .map(String::from)
+            .to_vec(),
+        };
+        let args = containers::exec_args(
+            &container.id,
+            workdir.as_deref(),
+            &BTreeMap::new(),
+            true,
+            &command,
+        );
+        let mut cmd = CommandBuilder::new("docker");
+        cmd.args(&args);
+        cmd.cwd(project_root);
+        tracing::info!(%session_id, container = %container.name, "Opening container session");
+        Self::spawn(cmd, None, rows, cols, window, session_id)
+    }
+
+    fn spawn(
+        cmd: CommandBuilder,
+        key_file: Option<NamedTempFile>,
+        rows: u16,
+        cols: u16,
+        window: Window,
+        session_id: String,
+    ) -> Result<Self> {
+        let pty_system = NativePtySystem::default();
+
+        // Create PTY with specified size
+        let pair = pty_system
+            .openpty(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .context("Failed to open PTY")?;
 
-        let _child = pair
+        // Spawn shell process
+        let child = pair
             .slave
             .spawn_command(cmd)
             .context("Failed to spawn shell")?;
+        let process_id = child.process_id();
 
         // Get writer for sending data to PTY
         let writer = pair
@@ -78,6 +169,78 @@ impl PtySession {
             pixel_height: 0,
         }));
 
+        let app = window.app_handle();
+        let commands = Arc::new(Mutex::new(CommandTracker::default()));
+        let silence = Arc::new(Mutex::new(SilenceDetector::default()));
+        let focused = Arc::new(AtomicBool::new(true));
+        let track = {
+            let commands = commands.clone();
+            let silence = silence.clone();
+            let focused = focused.clone();
+            let app = app.clone();
+            let session_id = session_id.clone();
+            move |data: &str| {
+                let (finished, moved_to): (Vec<CommandRecord>, Option<String>) =
+                    match commands.lock() {
+                        Ok(mut commands) => {
+                            let cwd = commands.cwd().map(str::to_string);
+                            let completed = commands.feed(data, chrono::Utc::now());
+                            let moved_to = commands
+                                .cwd()
+                                .filter(|now| Some(*now) != cwd.as_deref())
+                                .map(str::to_string);
+                            (commands.recent(completed).cloned().collect(), moved_to)
+                        }
+                        Err(_) => (Vec::new(), None),
+                    };
+                if let Some(cwd) = moved_to {
+                    frecency::record_cwd(&app, &cwd);
+                }
+                if let Ok(mut silence) = silence.lock() {
+                    if finished.is_empty() {
+                        silence.output(Instant::now());
+                    } else {
+                        silence.reset();
+                    }
+                }
+                for record in &finished {
+                    notify_finished(&app, record, focused.load(Ordering::Relaxed));
+                    if let Err(e) = app.state::<CommandHistory>().record(&session_id, record) {
+                        tracing::warn!(session_id = %session_id, "Failed to record command: {:#}", e);
+                    }
+                }
+            }
+        };
+
+        // Notify when a long stretch of output goes quiet, until the PTY closes
+        let closed = Arc::new(AtomicBool::new(false));
+        {
+            let closed = closed.clone();
+            thread::spawn(move || {
+                while !closed.load(Ordering::Relaxed) {
+                    thread::sleep(SILENCE_POLL_INTERVAL);
+                    let burst = silence
+                        .lock()
+                        .ok()
+                        .and_then(|mut silence| silence.check(Instant::now()));
+                    if let Some(burst) = burst {
+                        notifications::notify(
+                            &app,
+                            NotificationCategory::CommandFinished,
+                            i18n::text("notification.terminal_quiet.title"),
+                            i18n::format(
+                                "notification.terminal_quiet.body",
+                                &[
+                                    ("quiet", &notifications::format_duration(QUIET_AFTER)),
+                                    ("active", &notifications::format_duration(burst)),
+                                ],
+                            ),
+                        );
+                    }
+                }
+            });
+        }
+
         // Spawn thread to read PTY output and send to frontend
         let session_id_clone = session_id.clone();
         thread::spawn(move || {
@@ -89,12 +252,8 @@ impl PtySession {
                     Ok(0) => {
                         // EOF - PTY closed
                         let _ = window.emit(
-                            "pty-output",
-                            serde_json::json!({
-                                "session_id": session_id_clone,
-                                "data": "",
-                                "closed": true,
-                            }),
+                            PTY_OUTPUT_EVENT_NAME,
+                            v1::PtyOutput::closed(&session_id_clone),
                         );
                         break;
                     }
@@ -106,15 +265,12 @@ impl PtySession {
                         match String::from_utf8(utf8_buffer.clone()) {
                             Ok(data) => {
                                 // Successfully decoded - send and clear buffer
+                                track(&data);
                                 if let Err(e) = window.emit(
-                                    "pty-output",
-                                    serde_json::json!({
-                                        "session_id": session_id_clone,
-                                        "data": data,
-                                        "closed": false,
-                                    }),
+                                    PTY_OUTPUT_EVENT_NAME,
+                                    v1::PtyOutput::data(&session_id_clone, data),
                                 ) {
-                                    eprintln!("Failed to emit PTY output: {}", e);
+                                    tracing::error!(session_id = %session_id_clone, "Failed to emit PTY output: {}", e);
                                     break;
                                 }
                                 utf8_buffer.clear();
@@ -125,17 +281,16 @@ impl PtySession {
 
                                 if valid_up_to > 0 {
                                     // Send valid portion
-                                    let valid_data = String::from_utf8_lossy(&utf8_buffer[..valid_up_to]).to_string();
+                                    let valid_data =
+                                        String::from_utf8_lossy(&utf8_buffer[..valid_up_to])
+                                            .to_string();
+                                    track(&valid_data);
 
                                     if let Err(e) = window.emit(
-                                        "pty-output",
-                                        serde_json::json!({
-                                            "session_id": session_id_clone,
-                                            "data": valid_data,
-                                            "closed": false,
-                                        }),
+                                        PTY_OUTPUT_EVENT_NAME,
+                                        v1::PtyOutput::data(&session_id_clone, valid_data),
                                     ) {
-                                        eprintln!("Failed to emit PTY output: {}", e);
+                                        tracing::error!(session_id = %session_id_clone, "Failed to emit PTY output: {}", e);
                                         break;
                                     }
 
@@ -151,14 +306,53 @@ impl PtySession {
                         }
                     }
                     Err(e) => {
-                        eprintln!("Error reading from PTY: {}", e);
+                        tracing::error!(session_id = %session_id_clone, "Error reading from PTY: {}", e);
                         break;
                     }
                 }
             }
+            closed.store(true, Ordering::Relaxed);
         });
 
-        Ok(Self { writer, size })
+        Ok(Self {
+            writer,
+            size,
+            commands,
+            focused,
+            _key_file: key_file,
+            process_id,
+        })
+    }
+
+    /// Mark the session as the one shown to the user, or as in the background
+    pub fn set_focused(&self, focused: bool) {
+        self.focused.store(focused, Ordering::Relaxed);
+    }
+
+    /// Process id of the session's shell, or of ssh for remote sessions
+    pub fn process_id(&self) -> Option<u32> {
+        self.process_id
+    }
+
+    /// Last command completed in the session, as reported by shell integration
+    pub fn last_command(&self) -> Option<CommandRecord> {
+        self.commands.lock().ok()?.last().cloned()
+    }
+
+    /// Working directory last reported by shell integration
+    pub fn cwd(&self) -> Option<String> {
+        self.commands.lock().ok()?.cwd().map(str::to_string)
+    }
+
+    /// Command lines of the last `count` completed commands, oldest first
+    pub fn recent_commands(&self, count: usize) -> Vec<String> {
+        let Ok(commands) = self.commands.lock() else {
+            return Vec::new();
+        };
+        commands
+            .recent(count)
+            .map(|record| record.command.clone())
+            .collect()
     }
 
     /// Write data to the PTY
@@ -199,10 +393,82 @@ impl PtySession {
     }
 }
 
+/// Notify about a long command that finished while its session was out of view
+fn notify_finished(app: &AppHandle, record: &CommandRecord, session_focused: bool) {
+    let threshold = app
+        .state::<SettingsState>()
+        .current()
+        .terminal
+        .long_command_seconds;
+    if threshold == 0 || record.duration_ms < threshold * 1000 {
+        return;
+    }
+    journal_finished(record);
+    if session_focused && notifications::app_focused(app) {
+        return;
+    }
+
+    let command = record.command.lines().next().unwrap_or_default();
+    let duration = notifications::format_duration(Duration::from_millis(record.duration_ms));
+    let (title, body) = match record.exit_code {
+        Some(code) => (
+            if code == 0 {
+                "notification.command_finished.title"
+            } else {
+                "notification.command_failed.title"
+            },
+            i18n::format(
+                "notification.command_exited.body",
+                &[
+                    ("command", command),
+                    ("duration", &duration),
+                    ("code", &code.to_string()),
+                ],
+            ),
+        ),
+        None => (
+            "notification.command_finished.title",
+            i18n::format(
+                "notification.command_finished.body",
+                &[("command", command), ("duration", &duration)],
+            ),
+        ),
+    };
+    notifications::notify(
+        app,
+        NotificationCategory::CommandFinished,
+        i18n::text(title),
+        body,
+    );
+}
+
+/// Add a long command to the journal of the repository it ran in, if any
+fn journal_finished(record: &CommandRecord) {
+    let Some(root) = record
+        .cwd
+        .as_deref()
+        .and_then(|cwd| git::open(Path::new(cwd)).ok())
+        .and_then(|repo| git::workdir(&repo).ok())
+    else {
+        return;
+    };
+    let mut summary = journal::one_line(&record.command);
+    if let Some(code) = record.exit_code.filter(|code| *code != 0) {
+        summary.push_str(&format!(" (exit {})", code));
+    }
+    journal::record(
+        &root,
+        JournalEntry::new(JournalKind::CommandFinished, summary).duration_ms(record.duration_ms),
+    );
+}
+
 // Manually implement Send for PtySession
 // This is safe because:
 // - writer is Arc<Mutex<...>> which is Send
 // - size is Arc<Mutex<...>> which is Send
+// - commands is Arc<Mutex<...>> which is Send
+// - focused is Arc<AtomicBool> which is Send
+// - _key_file is only held to be dropped with the session
 unsafe impl Send for PtySession {}
 
 // Manually implement Sync for PtySession
@@ -212,8 +478,6 @@ unsafe impl Sync for PtySession {}
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     #[test]
     fn test_pty_write() {
         // Note: This test would require a mock window, so it's simplified
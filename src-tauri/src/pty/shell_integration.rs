@@ -0,0 +1,413 @@
+//! Command tracking from shell integration marks in PTY output
+//!
+//! Shells with integration enabled (iTerm2, WezTerm, kitty and VS Code
+//! scripts, starship, ...) wrap every prompt and command in FinalTerm
+//! `OSC 133` marks: `A` prompt start, `B` command input start, `C` command
+//! output start and `D;<exit code>` command finished. VS Code's `OSC 633`
+//! variant is read the same way, including its `E;<command line>` mark, and
+//! `OSC 7` reports the working directory. Shells without integration emit no
+//! marks, so nothing is tracked for them.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Completed commands kept per session
+const HISTORY_LIMIT: usize = 100;
+
+/// Output kept per command; longer output keeps its end
+const OUTPUT_MAX_BYTES: usize = 64 * 1024;
+
+/// Longest escape sequence held back while waiting for its terminator
+const PENDING_MAX_BYTES: usize = 4096;
+
+/// A command run in a PTY session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandRecord {
+    pub command: String,
+    /// Working directory last reported by the shell
+    pub cwd: Option<String>,
+    /// None when the shell did not report it
+    pub exit_code: Option<i32>,
+    /// Combined stdout and stderr as shown in the terminal, without escape sequences
+    pub output: String,
+    /// Whether the start of the output was dropped to stay within OUTPUT_MAX_BYTES
+    pub output_truncated: bool,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Idle,
+    Prompt,
+    Input,
+    Output,
+}
+
+/// Follows the shell integration marks of one PTY session
+#[derive(Debug)]
+pub struct CommandTracker {
+    phase: Phase,
+    /// Incomplete escape sequence from the end of the previous chunk
+    pending: String,
+    input: String,
+    /// Command line reported by `633;E`, preferred over the echoed input
+    reported_command: Option<String>,
+    output: String,
+    output_truncated: bool,
+    cwd: Option<String>,
+    started_at: DateTime<Utc>,
+    history: VecDeque<CommandRecord>,
+    /// Commands completed since the session started
+    completed: usize,
+}
+
+impl Default for CommandTracker {
+    fn default() -> Self {
+        Self {
+            phase: Phase::Idle,
+            pending: String::new(),
+            input: String::new(),
+            reported_command: None,
+            output: String::new(),
+            output_truncated: false,
+            cwd: None,
+            started_at: Utc::now(),
+            history: VecDeque::new(),
+            completed: 0,
+        }
+    }
+}
+
+impl CommandTracker {
+    /// Process a chunk of PTY output received at `now`
+    /// Returns how many commands the chunk completed; see `recent`
+    pub fn feed(&mut self, data: &str, now: DateTime<Utc>) -> usize {
+        let completed_before = self.completed;
+        self.scan(data, now);
+        self.completed - completed_before
+    }
+
+    /// Up to `count` most recently completed commands, oldest first
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &CommandRecord> {
+        self.history
+            .iter()
+            .skip(self.history.len().saturating_sub(count))
+    }
+
+    fn scan(&mut self, data: &str, now: DateTime<Utc>) {
+        let data = std::mem::take(&mut self.pending) + data;
+        let mut rest = data.as_str();
+
+        while let Some(start) = rest.find('\x1b') {
+            self.capture(&rest[..start]);
+            let sequence = &rest[start..];
+            if sequence.len() < 2 {
+                self.pending = sequence.to_string();
+                return;
+            }
+            if !sequence.starts_with("\x1b]") {
+                // Other escape sequences are kept and stripped when the command completes
+                self.capture("\x1b");
+                rest = &sequence[1..];
+                continue;
+            }
+
+            let body = &sequence[2..];
+            match body.find(['\x07', '\x1b']) {
+                Some(end) if body[end..].starts_with('\x07') => {
+                    self.osc(&body[..end], now);
+                    rest = &body[end + 1..];
+                }
+                Some(end) if body[end..].starts_with("\x1b\\") => {
+                    self.osc(&body[..end], now);
+                    rest = &body[end + 2..];
+                }
+                // An ESC ending the chunk may be the start of the terminator
+                Some(end) if end + 1 == body.len() => {
+                    self.pending = sequence.to_string();
+                    return;
+                }
+                None if sequence.len() <= PENDING_MAX_BYTES => {
+                    self.pending = sequence.to_string();
+                    return;
+                }
+                _ => {
+                    self.capture("\x1b");
+                    rest = &sequence[1..];
+                }
+            }
+        }
+        self.capture(rest);
+    }
+
+    /// Most recently completed command
+    pub fn last(&self) -> Option<&CommandRecord> {
+        self.history.back()
+    }
+
+    /// Working directory last reported by the shell
+    pub fn cwd(&self) -> Option<&str> {
+        self.cwd.as_deref()
+    }
+
+    fn capture(&mut self, text: &str) {
+        match self.phase {
+            Phase::Input => self.input.push_str(text),
+            Phase::Output => {
+                self.output.push_str(text);
+                if self.output.len() > OUTPUT_MAX_BYTES {
+                    let mut cut = self.output.len() - OUTPUT_MAX_BYTES;
+                    while !self.output.is_char_boundary(cut) {
+                        cut += 1;
+                    }
+                    self.output.drain(..cut);
+                    self.output_truncated = true;
+                }
+            }
+            Phase::Idle | Phase::Prompt => {}
+        }
+    }
+
+    fn osc(&mut self, body: &str, now: DateTime<Utc>) {
+        let (code, payload) = body.split_once(';').unwrap_or((body, ""));
+        match code {
+            "7" => self.cwd = cwd_from_url(payload),
+            "133" | "633" => {
+                let (mark, args) = payload.split_once(';').unwrap_or((payload, ""));
+                self.mark(mark, args, now);
+            }
+            _ => {}
+        }
+    }
+
+    fn mark(&mut self, mark: &str, args: &str, now: DateTime<Utc>) {
+        match mark {
+            "A" => {
+                if self.phase == Phase::Output {
+                    self.finish(None, now);
+                }
+                self.phase = Phase::Prompt;
+            }
+            "B" => {
+                self.input.clear();
+                self.reported_command = None;
+                self.phase = Phase::Input;
+            }
+            "C" => {
+                self.output.clear();
+                self.output_truncated = false;
+                self.started_at = now;
+                self.phase = Phase::Output;
+            }
+            "D" => {
+                if self.phase == Phase::Output {
+                    let exit_code = args.split(';').next().and_then(|code| code.parse().ok());
+                    self.finish(exit_code, now);
+                }
+                self.phase = Phase::Idle;
+            }
+            "E" => {
+                let command = args.split(';').next().unwrap_or_default();
+                self.reported_command = Some(unescape_vscode(command));
+            }
+            "P" => {
+                if let Some(cwd) = args.strip_prefix("Cwd=") {
+                    self.cwd = Some(unescape_vscode(cwd));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(&mut self, exit_code: Option<i32>, now: DateTime<Utc>) {
+        let command = match self.reported_command.take() {
+            Some(command) => command,
+            None => clean_text(&self.input),
+        };
+        let output = clean_text(&std::mem::take(&mut self.output));
+        self.input.clear();
+        if command.trim().is_empty() && output.is_empty() {
+            return;
+        }
+
+        self.history.push_back(CommandRecord {
+            command: command.trim().to_string(),
+            cwd: self.cwd.clone(),
+            exit_code,
+            output,
+            output_truncated: self.output_truncated,
+            started_at: self.started_at,
+            duration_ms: (now - self.started_at).num_milliseconds().max(0) as u64,
+        });
+        if self.history.len() > HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+        self.completed += 1;
+    }
+}
+
+/// Terminal text as it would read on screen: escape sequences removed and
+/// lines overwritten with `\r` reduced to their final state
+pub fn clean_text(text: &str) -> String {
+    let stripped = strip_escapes(text);
+    let lines: Vec<&str> = stripped
+        .split('\n')
+        .map(|line| {
+            let line = line.trim_end_matches('\r');
+            line.rsplit('\r').next().unwrap_or(line)
+        })
+        .collect();
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+fn strip_escapes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // CSI: parameters up to a final byte in @..~
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC: up to BEL or ESC \
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\x08' => {
+                result.pop();
+            }
+            c if c.is_control() && c != '\n' && c != '\r' && c != '\t' => {}
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Path of a `file://host/path` URL, percent-decoded
+fn cwd_from_url(url: &str) -> Option<String> {
+    let rest = url.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex: Vec<u8> = iter.by_ref().take(2).collect();
+            match std::str::from_utf8(&hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(decoded) => bytes.push(decoded),
+                None => {
+                    bytes.push(b'%');
+                    bytes.extend(hex);
+                }
+            }
+        } else {
+            bytes.push(byte);
+        }
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// VS Code escapes `;`, `\` and control characters as `\xHH` in 633 marks
+fn unescape_vscode(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find("\\x") {
+        result.push_str(&rest[..index]);
+        let escaped = rest.get(index + 2..index + 4);
+        match escaped.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) => {
+                result.push(byte as char);
+                rest = &rest[index + 4..];
+            }
+            None => {
+                result.push_str("\\x");
+                rest = &rest[index + 2..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn test_tracks_commands() {
+        let mut tracker = CommandTracker::default();
+        tracker.feed(
+            "\x1b]7;file://host/home/me/my%20project\x07\x1b]133;A\x07$ \x1b]133;B\x07cargo tset\r\n",
+            at(0),
+        );
+        tracker.feed(
+            "\x1b]133;C\x07\x1b[31merror\x1b[0m: no such command\r\n",
+            at(1),
+        );
+        assert!(tracker.last().is_none());
+        assert_eq!(tracker.feed("\x1b]133;D;101\x07\x1b]133;A\x07$ ", at(3)), 1);
+
+        let last = tracker.last().unwrap();
+        assert_eq!(last.command, "cargo tset");
+        assert_eq!(last.cwd.as_deref(), Some("/home/me/my project"));
+        assert_eq!(last.exit_code, Some(101));
+        assert_eq!(last.output, "error: no such command");
+        assert_eq!(last.duration_ms, 2_000);
+        assert_eq!(tracker.recent(5).count(), 1);
+
+        // Empty command lines are not recorded
+        let completed = tracker.feed("\x1b]133;B\x07\r\n\x1b]133;C\x07\x1b]133;D;0\x07", at(4));
+        assert_eq!(completed, 0);
+        assert_eq!(tracker.last().unwrap().command, "cargo tset");
+    }
+
+    #[test]
+    fn test_marks_split_across_chunks_and_vscode_command() {
+        let mut tracker = CommandTracker::default();
+        tracker.feed("\x1b]633;B\x1b\\ls\x1b]633;E;ls a\\x3bb\x1b", at(0));
+        tracker.feed("\\\x1b]63", at(0));
+        tracker.feed("3;C\x0750%\rdone\n\x1b]633;D;0\x1b\\", at(0));
+
+        let last = tracker.last().unwrap();
+        assert_eq!(last.command, "ls a;b");
+        assert_eq!(last.exit_code, Some(0));
+        assert_eq!(last.output, "done");
+    }
+
+    #[test]
+    fn test_output_keeps_its_end() {
+        let mut tracker = CommandTracker::default();
+        tracker.feed("\x1b]133;B\x07yes\x1b]133;C\x07", at(0));
+        tracker.feed(&"y\n".repeat(OUTPUT_MAX_BYTES), at(0));
+        tracker.feed("last\n\x1b]133;A\x07", at(0));
+
+        let last = tracker.last().unwrap();
+        assert!(last.output_truncated);
+        assert!(last.output.ends_with("y\nlast"));
+        assert_eq!(last.exit_code, None);
+    }
+
+    #[test]
+    fn test_clean_text() {
+        assert_eq!(clean_text("ab\x08c\x1b]0;title\x07\x1b[1mok\r\n"), "acok");
+    }
+}
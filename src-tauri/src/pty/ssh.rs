@@ -0,0 +1,242 @@
+//! Terminals on remote hosts through the system's OpenSSH client
+//!
+//! A remote session is an ordinary PtySession running `ssh -tt`, so output
+//! streaming, resize, shell integration and session management work as for
+//! local shells, and host keys, passwords and ~/.ssh/config are handled by
+//! ssh itself in the terminal. Agent auth leaves key selection to ssh; key
+//! auth writes the keychain-stored key to a private temporary file that is
+//! removed when the session closes.
+
+use crate::config::keychain;
+use crate::config::{SshAuth, SshHost};
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+/// Seconds between keepalives, so idle sessions survive NAT timeouts
+const SERVER_ALIVE_INTERVAL: u32 = 30;
+
+/// Reject hosts ssh would misread, e.g. a host starting with `-` taken as an option
+pub fn validate(host: &SshHost) -> Result<()> {
+    if host.name.trim().is_empty() {
+        bail!("SSH host name must not be empty");
+    }
+    for (field, value) in [("host", Some(&host.host)), ("user", host.user.as_ref())] {
+        if let Some(value) = value {
+            if value.is_empty() || value.starts_with('-') || value.contains(char::is_whitespace) {
+                bail!("Invalid SSH {} {:?}", field, value);
+            }
+        }
+    }
+    if host.port == Some(0) {
+        bail!("SSH port must not be 0");
+    }
+    Ok(())
+}
+
+/// The ssh invocation for a session on `host`
+pub struct SshLaunch {
+    pub program: &'static str,
+    pub args: Vec<String>,
+    /// Keychain key written out for ssh; deleted when dropped
+    pub key_file: Option<NamedTempFile>,
+}
+
+/// Prepare to connect to `host`, reading its key from the keychain for key auth
+pub fn launch(host: &SshHost) -> Result<SshLaunch> {
+    validate(host)?;
+    let key_file = key_file(host)?;
+    Ok(SshLaunch {
+        program: "ssh",
+        args: args(host, key_file.as_ref().map(|file| file.path())),
+        key_file,
+    })
+}
+
+/// The host's keychain key written out for ssh; None for agent auth
+pub fn key_file(host: &SshHost) -> Result<Option<NamedTempFile>> {
+    match host.auth {
+        SshAuth::Agent => Ok(None),
+        SshAuth::Key => {
+            let key = keychain::retrieve_ssh_key(&host.name)?
+                .with_context(|| format!("No private key stored for SSH host {}", host.name))?;
+            Ok(Some(write_key(&key)?))
+        }
+    }
+}
+
+/// Arguments for `ssh`, given the key file for key auth
+pub fn args(host: &SshHost, key_file: Option<&Path>) -> Vec<String> {
+    let mut args = vec!["-tt".to_string()];
+    args.extend(connection_args(host, key_file));
+    if let Some(dir) = &host.remote_dir {
+        args.push(format!("cd {} && exec \"$SHELL\" -l", shell_quote(dir)));
+    }
+    args
+}
+
+/// Arguments for an `ssh` forwarding `local_port` to `remote_port` on the
+/// remote host's localhost, without a shell; it cannot prompt, so host keys
+/// must already be known
+pub fn forward_args(
+    host: &SshHost,
+    key_file: Option<&Path>,
+    local_port: u16,
+    remote_port: u16,
+) -> Vec<String> {
+    let mut args = vec![
+        "-N".to_string(),
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        "ExitOnForwardFailure=yes".to_string(),
+        "-L".to_string(),
+        format!("127.0.0.1:{}:localhost:{}", local_port, remote_port),
+    ];
+    args.extend(connection_args(host, key_file));
+    args
+}
+
+fn connection_args(host: &SshHost, key_file: Option<&Path>) -> Vec<String> {
+    let mut args = vec![
+        "-o".to_string(),
+        format!("ServerAliveInterval={}", SERVER_ALIVE_INTERVAL),
+    ];
+    if let Some(port) = host.port {
+        args.extend(["-p".to_string(), port.to_string()]);
+    }
+    if let Some(user) = &host.user {
+        args.extend(["-l".to_string(), user.clone()]);
+    }
+    if let Some(key_file) = key_file {
+        args.extend([
+            "-i".to_string(),
+            key_file.display().to_string(),
+            "-o".to_string(),
+            "IdentitiesOnly=yes".to_string(),
+        ]);
+    }
+    args.extend(["--".to_string(), host.host.clone()]);
+    args
+}
+
+/// Write `key` to a temporary file only the user can read, as ssh requires
+fn write_key(key: &str) -> Result<NamedTempFile> {
+    let mut file = tempfile::Builder::new()
+        .prefix("zeami-ssh-")
+        .tempfile()
+        .context("Failed to create SSH key file")?;
+    file.write_all(key.trim_end().as_bytes())
+        .and_then(|_| file.write_all(b"\n"))
+        .and_then(|_| file.flush())
+        .context("Failed to write SSH key file")?;
+    Ok(file)
+}
+
+/// Quote `value` for a POSIX shell on the remote host
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host() -> SshHost {
+        SshHost {
+            name: "dev".to_string(),
+            host: "dev.example.com".to_string(),
+            port: None,
+            user: None,
+            auth: SshAuth::Agent,
+            remote_dir: None,
+        }
+    }
+
+    #[test]
+    fn test_builds_agent_arguments() {
+        assert_eq!(
+            args(&host(), None),
+            vec![
+                "-tt",
+                "-o",
+                "ServerAliveInterval=30",
+                "--",
+                "dev.example.com"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builds_key_arguments_with_port_user_and_directory() {
+        let host = SshHost {
+            port: Some(2222),
+            user: Some("me".to_string()),
+            auth: SshAuth::Key,
+            remote_dir: Some("/srv/it's here".to_string()),
+            ..host()
+        };
+        let args = args(&host, Some(Path::new("/tmp/key")));
+        assert_eq!(
+            args[3..],
+            [
+                "-p",
+                "2222",
+                "-l",
+                "me",
+                "-i",
+                "/tmp/key",
+                "-o",
+                "IdentitiesOnly=yes",
+                "--",
+                "dev.example.com",
+                r#"cd '/srv/it'\''s here' && exec "$SHELL" -l"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builds_forward_arguments() {
+        let args = forward_args(&host(), None, 15173, 5173);
+        assert_eq!(
+            args[..7].join(" "),
+            "-N -o BatchMode=yes -o ExitOnForwardFailure=yes -L 127.0.0.1:15173:localhost:5173"
+        );
+        assert_eq!(args.last().unwrap(), "dev.example.com");
+        assert!(!args.contains(&"-tt".to_string()));
+    }
+
+    #[test]
+    fn test_rejects_option_like_hosts() {
+        assert!(validate(&host()).is_ok());
+        let option = SshHost {
+            host: "-oProxyCommand=evil".to_string(),
+            ..host()
+        };
+        assert!(validate(&option).is_err());
+        let spaced = SshHost {
+            user: Some("a b".to_string()),
+            ..host()
+        };
+        assert!(validate(&spaced).is_err());
+        assert!(validate(&SshHost {
+            name: " ".to_string(),
+            ..host()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_key_file_is_private() {
+        let file = write_key("-----BEGIN KEY-----\nabc\n-----END KEY-----\n\n").unwrap();
+        let written = std::fs::read_to_string(file.path()).unwrap();
+        assert!(written.ends_with("-----END KEY-----\n"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(file.path()).unwrap().permissions().mode();
+            assert_eq!(mode & 0o077, 0);
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! Periodic background jobs
+//!
+//! Features register a named Job whose interval comes from the settings
+//! (auto_fetch, auto_commit, auto_sync_progress, backups). Each job runs on its
+//! own task with up to a tenth of its interval of random jitter, can be paused
+//! and resumed, and keeps its last-run time across restarts (see store).
+//! Settings changes make every job re-read its interval.
+
+pub mod store;
+
+use crate::config::{storage, SettingsState};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use store::JobStore;
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Notify;
+
+/// How often a disabled or paused job re-reads its interval
+///
+/// Project settings are not watched, so this bounds how long turning a job on
+/// in .zeami/config.json takes to be noticed.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+pub type JobFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+/// A periodic background job
+pub trait Job: Send + Sync + 'static {
+    /// Time between runs under the current settings; None while disabled
+    fn interval(&self, app: &AppHandle) -> Option<Duration>;
+
+    /// Run the job once
+    fn run(&self, app: &AppHandle) -> JobFuture;
+}
+
+/// A registered job as reported by `list_scheduled_jobs`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    pub name: String,
+    /// Whether the settings currently turn the job on
+    pub enabled: bool,
+    pub paused: bool,
+    pub running: bool,
+    pub interval_secs: Option<u64>,
+    pub last_run: Option<DateTime<Utc>>,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+struct Slot {
+    job: Arc<dyn Job>,
+    status: Mutex<ScheduledJob>,
+    wake: Notify,
+    /// Skip the wait before the next run
+    run_now: AtomicBool,
+}
+
+/// Runs the registered jobs
+///
+/// Jobs are registered in setup before `spawn` starts them.
+pub struct Scheduler {
+    store: Arc<JobStore>,
+    slots: Mutex<BTreeMap<&'static str, Arc<Slot>>>,
+}
+
+impl Scheduler {
+    /// Scheduler with its state in ~/.zeami/scheduler.json
+    pub fn open_default() -> Result<Self> {
+        let store = JobStore::open(&storage::config_dir()?.join("scheduler.json"));
+        Ok(Self {
+            store: Arc::new(store),
+            slots: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    pub fn register(&self, name: &'static str, job: impl Job) {
+        let record = self.store.get(name);
+        let slot = Slot {
+            job: Arc::new(job),
+            status: Mutex::new(ScheduledJob {
+                name: name.to_string(),
+                enabled: false,
+                paused: record.paused,
+                running: false,
+                interval_secs: None,
+                last_run: record.last_run,
+                next_run: None,
+                last_error: None,
+            }),
+            wake: Notify::new(),
+            run_now: AtomicBool::new(false),
+        };
+        self.slots.lock().unwrap().insert(name, Arc::new(slot));
+    }
+
+    /// Start the registered jobs
+    pub fn spawn(&self, app: AppHandle) {
+        let slots: Vec<_> = self
+            .slots
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, slot)| (*name, slot.clone()))
+            .collect();
+
+        for (name, slot) in &slots {
+            tauri::async_runtime::spawn(run_job(
+                app.clone(),
+                self.store.clone(),
+                name,
+                slot.clone(),
+            ));
+        }
+
+        let mut changes = app.state::<SettingsState>().subscribe();
+        tauri::async_runtime::spawn(async move {
+            // Closed only when the settings state goes away
+            while let Ok(_) | Err(RecvError::Lagged(_)) = changes.recv().await {
+                for (_, slot) in &slots {
+                    slot.wake.notify_one();
+                }
+            }
+        });
+    }
+
+    /// All jobs in name order
+    pub fn list(&self) -> Vec<ScheduledJob> {
+        self.slots
+            .lock()
+            .unwrap()
+            .values()
+            .map(|slot| slot.status.lock().unwrap().clone())
+            .collect()
+    }
+
+    /// Pause or resume `name`; a pause lasts across restarts
+    pub fn set_paused(&self, name: &str, paused: bool) -> Result<ScheduledJob> {
+        let slot = self.slot(name)?;
+        self.store.update(name, |record| record.paused = paused)?;
+        let status = {
+            let mut status = slot.status.lock().unwrap();
+            status.paused = paused;
+            if paused {
+                status.next_run = None;
+            }
+            status.clone()
+        };
+        slot.wake.notify_one();
+        tracing::info!(job = name, paused, "Changed scheduled job");
+        Ok(status)
+    }
+
+    /// Run `name` right away if it is enabled and not paused, then resume its
+    /// interval; also makes the job re-read its interval
+    pub fn trigger(&self, name: &str) {
+        match self.slot(name) {
+            Ok(slot) => {
+                slot.run_now.store(true, Ordering::SeqCst);
+                slot.wake.notify_one();
+            }
+            Err(e) => tracing::error!("{:#}", e),
+        }
+    }
+
+    fn slot(&self, name: &str) -> Result<Arc<Slot>> {
+        self.slots
+            .lock()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("No scheduled job named {:?}", name))
+    }
+}
+
+async fn run_job(app: AppHandle, store: Arc<JobStore>, name: &'static str, slot: Arc<Slot>) {
+    loop {
+        let interval = slot.job.interval(&app);
+        let record = store.get(name);
+        let now = Utc::now();
+
+        let delay = match interval {
+            Some(interval) if !record.paused => {
+                if slot.run_now.swap(false, Ordering::SeqCst) {
+                    Some(Duration::ZERO)
+                } else {
+                    let jitter =
+                        rand::thread_rng().gen_range(Duration::ZERO..=store::max_jitter(interval));
+                    Some(store::delay_until_due(record.last_run, interval, now) + jitter)
+                }
+            }
+            _ => {
+                slot.run_now.store(false, Ordering::SeqCst);
+                None
+            }
+        };
+
+        {
+            let mut status = slot.status.lock().unwrap();
+            status.enabled = interval.is_some();
+            status.paused = record.paused;
+            status.interval_secs = interval.map(|interval| interval.as_secs());
+            status.next_run = delay
+                .and_then(|delay| chrono::Duration::from_std(delay).ok())
+                .map(|delay| now + delay);
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay.unwrap_or(RECHECK_INTERVAL)) => {}
+            _ = slot.wake.notified() => continue,
+        }
+        if delay.is_none() {
+            continue;
+        }
+
+        let started = Utc::now();
+        slot.status.lock().unwrap().running = true;
+        let result = slot.job.run(&app).await;
+        if let Err(e) = &result {
+            tracing::warn!(job = name, "Scheduled job failed: {:#}", e);
+        }
+        if let Err(e) = store.update(name, |record| record.last_run = Some(started)) {
+            tracing::warn!(job = name, "Failed to save scheduler state: {:#}", e);
+        }
+
+        let mut status = slot.status.lock().unwrap();
+        status.running = false;
+        status.last_run = Some(started);
+        status.last_error = result.err().map(|e| format!("{:#}", e));
+    }
+}
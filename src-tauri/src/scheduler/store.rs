@@ -0,0 +1,150 @@
+//! Persisted state of scheduled jobs
+//!
+//! Last-run times and pauses are kept in ~/.zeami/scheduler.json so that a
+//! restart neither reruns a job early nor forgets that it was paused.
+
+use crate::config::atomic::write_atomic;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bound of the jitter added to a job's interval
+const MAX_JITTER: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobRecord {
+    /// When the last run started
+    pub last_run: Option<DateTime<Utc>>,
+    pub paused: bool,
+}
+
+pub struct JobStore {
+    path: PathBuf,
+    records: Mutex<BTreeMap<String, JobRecord>>,
+}
+
+impl JobStore {
+    /// Load the records at `path`; a missing or unreadable file gives no records
+    pub fn open(path: &Path) -> Self {
+        let records = match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable scheduler state {:?}: {}", path, e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+
+        Self {
+            path: path.to_path_buf(),
+            records: Mutex::new(records),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> JobRecord {
+        self.records
+            .lock()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Change the record of `name` and write all records to disk
+    pub fn update(&self, name: &str, change: impl FnOnce(&mut JobRecord)) -> Result<JobRecord> {
+        let mut records = self.records.lock().unwrap();
+        let record = records.entry(name.to_string()).or_default();
+        change(record);
+        let record = record.clone();
+        write_atomic(&self.path, &serde_json::to_vec_pretty(&*records)?)?;
+        Ok(record)
+    }
+}
+
+/// Time left until a job that last ran at `last_run` is due again
+///
+/// A job that never ran or is overdue is due right away.
+pub fn delay_until_due(
+    last_run: Option<DateTime<Utc>>,
+    interval: Duration,
+    now: DateTime<Utc>,
+) -> Duration {
+    let Some(last_run) = last_run else {
+        return Duration::ZERO;
+    };
+    let elapsed = (now - last_run).to_std().unwrap_or_default();
+    interval.saturating_sub(elapsed)
+}
+
+/// Largest random delay added before a run of a job with `interval`, so that
+/// jobs started together drift apart
+pub fn max_jitter(interval: Duration) -> Duration {
+    (interval / 10).min(MAX_JITTER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_until_due() {
+        let now = Utc::now();
+        let interval = Duration::from_secs(300);
+        assert_eq!(delay_until_due(None, interval, now), Duration::ZERO);
+
+        let last_run = now - chrono::Duration::seconds(100);
+        assert_eq!(
+            delay_until_due(Some(last_run), interval, now),
+            Duration::from_secs(200)
+        );
+
+        let overdue = now - chrono::Duration::seconds(900);
+        assert_eq!(
+            delay_until_due(Some(overdue), interval, now),
+            Duration::ZERO
+        );
+
+        // A clock that went backwards makes the job wait one full interval
+        let future = now + chrono::Duration::seconds(30);
+        assert_eq!(delay_until_due(Some(future), interval, now), interval);
+    }
+
+    #[test]
+    fn test_max_jitter() {
+        assert_eq!(
+            max_jitter(Duration::from_secs(300)),
+            Duration::from_secs(30)
+        );
+        assert_eq!(max_jitter(Duration::from_secs(3600)), MAX_JITTER);
+    }
+
+    #[test]
+    fn test_records_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scheduler.json");
+        let store = JobStore::open(&path);
+        assert_eq!(store.get("auto_fetch"), JobRecord::default());
+
+        let now = Utc::now();
+        store
+            .update("auto_fetch", |record| record.last_run = Some(now))
+            .unwrap();
+        store
+            .update("auto_commit", |record| record.paused = true)
+            .unwrap();
+
+        let reopened = JobStore::open(&path);
+        assert_eq!(reopened.get("auto_fetch").last_run, Some(now));
+        assert!(reopened.get("auto_commit").paused);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert_eq!(
+            JobStore::open(&path).get("auto_fetch"),
+            JobRecord::default()
+        );
+    }
+}
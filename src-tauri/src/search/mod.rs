@@ -0,0 +1,349 @@
+//! Full-text search of a project, in the manner of ripgrep
+//!
+//! Files are walked in parallel honouring .gitignore, .ignore and hidden
+//! files like `rg` does; binary and oversized files are skipped. Matches are
+//! reported per file as they are found, so results appear while a large tree
+//! is still being searched. Starting a search stops the one before it.
+
+use anyhow::{bail, Context, Result};
+use ignore::overrides::OverrideBuilder;
+use ignore::{WalkBuilder, WalkState};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+/// Matching lines reported when the caller sets no limit
+pub const DEFAULT_MAX_RESULTS: usize = 1000;
+/// Files larger than this are not searched
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+/// Bytes inspected for a NUL to tell binary files apart
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+/// Longest line sent to the frontend, in characters
+const MAX_LINE_CHARS: usize = 500;
+
+/// What to search for
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    pub query: String,
+    /// Treat `query` as a regular expression instead of literal text
+    pub regex: bool,
+    /// ripgrep-style globs; `!` excludes, e.g. `["src/**", "!*.snap"]`
+    pub globs: Vec<String>,
+    pub max_results: Option<usize>,
+}
+
+/// Matches in one file, sent as a `search-result` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub search_id: u64,
+    /// Relative to the project root, with `/` separators
+    pub path: String,
+    pub lines: Vec<LineMatch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineMatch {
+    /// 1-based
+    pub line_number: usize,
+    /// The line, cut to MAX_LINE_CHARS characters
+    pub text: String,
+    /// Character ranges of the matches within `text`
+    pub ranges: Vec<(usize, usize)>,
+}
+
+/// Returned when a search ends
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchSummary {
+    pub search_id: u64,
+    pub files_searched: usize,
+    pub files_matched: usize,
+    pub lines_matched: usize,
+    /// Stopped at max_results
+    pub truncated: bool,
+    /// Stopped because another search started
+    pub cancelled: bool,
+}
+
+/// Hands out search ids; managed by Tauri
+#[derive(Default)]
+pub struct Searches {
+    latest: AtomicU64,
+}
+
+impl Searches {
+    /// Id for a new search, superseding any running one
+    pub fn start(&self) -> u64 {
+        self.latest.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    pub fn is_current(&self, search_id: u64) -> bool {
+        self.latest.load(Ordering::SeqCst) == search_id
+    }
+}
+
+/// Search `project_root`, calling `on_result` for each file with matches
+///
+/// `is_cancelled` is polled between files.
+pub fn search(
+    project_root: &Path,
+    search_id: u64,
+    query: &SearchQuery,
+    is_cancelled: &(dyn Fn() -> bool + Sync),
+    on_result: &(dyn Fn(SearchResult) + Sync),
+) -> Result<SearchSummary> {
+    let matcher = matcher(query)?;
+    let max_results = query.max_results.unwrap_or(DEFAULT_MAX_RESULTS).max(1);
+
+    let mut overrides = OverrideBuilder::new(project_root);
+    for glob in &query.globs {
+        overrides
+            .add(glob)
+            .with_context(|| format!("Invalid glob {:?}", glob))?;
+    }
+    let walker = WalkBuilder::new(project_root)
+        .overrides(overrides.build().context("Invalid globs")?)
+        .max_filesize(Some(MAX_FILE_BYTES))
+        // Honour .gitignore even outside a git repository, as the UI expects
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build_parallel();
+
+    let files_searched = AtomicUsize::new(0);
+    let files_matched = AtomicUsize::new(0);
+    let lines_matched = AtomicUsize::new(0);
+    let truncated = AtomicBool::new(false);
+    let cancelled = AtomicBool::new(false);
+
+    walker.run(|| {
+        Box::new(|entry| {
+            if is_cancelled() {
+                cancelled.store(true, Ordering::Relaxed);
+                return WalkState::Quit;
+            }
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
+            };
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                return WalkState::Continue;
+            }
+            let Ok(bytes) = std::fs::read(entry.path()) else {
+                return WalkState::Continue;
+            };
+            files_searched.fetch_add(1, Ordering::Relaxed);
+            let mut lines = search_text(&matcher, &bytes);
+            if lines.is_empty() {
+                return WalkState::Continue;
+            }
+
+            // Claim room for these lines under the limit
+            let before = lines_matched.fetch_add(lines.len(), Ordering::SeqCst);
+            if before >= max_results {
+                truncated.store(true, Ordering::Relaxed);
+                return WalkState::Quit;
+            }
+            let full = before + lines.len() >= max_results;
+            if before + lines.len() > max_results {
+                lines.truncate(max_results - before);
+                truncated.store(true, Ordering::Relaxed);
+            }
+            files_matched.fetch_add(1, Ordering::Relaxed);
+            on_result(SearchResult {
+                search_id,
+                path: relative_path(project_root, entry.path()),
+                lines,
+            });
+            if full {
+                WalkState::Quit
+            } else {
+                WalkState::Continue
+            }
+        })
+    });
+
+    Ok(SearchSummary {
+        search_id,
+        files_searched: files_searched.into_inner(),
+        files_matched: files_matched.into_inner(),
+        lines_matched: lines_matched.into_inner().min(max_results),
+        truncated: truncated.into_inner(),
+        cancelled: cancelled.into_inner(),
+    })
+}
+
+/// The regex for `query`, case-insensitive unless the query has capitals
+fn matcher(query: &SearchQuery) -> Result<Regex> {
+    if query.query.is_empty() {
+        bail!("Search query must not be empty");
+    }
+    let pattern = if query.regex {
+        query.query.clone()
+    } else {
+        regex::escape(&query.query)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!query.query.chars().any(char::is_uppercase))
+        .build()
+        .with_context(|| format!("Invalid regular expression {:?}", query.query))
+}
+
+/// Matching lines of a file; none for binary files
+fn search_text(matcher: &Regex, bytes: &[u8]) -> Vec<LineMatch> {
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return Vec::new();
+    }
+    let text = String::from_utf8_lossy(bytes);
+    text.lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let found: Vec<(usize, usize)> = matcher
+                .find_iter(line)
+                .filter(|m| !m.is_empty())
+                .map(|m| (char_offset(line, m.start()), char_offset(line, m.end())))
+                .collect();
+            if found.is_empty() {
+                return None;
+            }
+            // Matches past the cut still report the line, just unhighlighted
+            Some(LineMatch {
+                line_number: index + 1,
+                text: line.chars().take(MAX_LINE_CHARS).collect(),
+                ranges: found
+                    .into_iter()
+                    .filter(|(start, _)| *start < MAX_LINE_CHARS)
+                    .map(|(start, end)| (start, end.min(MAX_LINE_CHARS)))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+fn char_offset(line: &str, byte: usize) -> usize {
+    line[..byte].chars().count()
+}
+
+fn relative_path(project_root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(project_root).unwrap_or(path);
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::Mutex;
+
+    fn project() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::write(
+            root.join("src/main.rs"),
+            "fn main() {\n    Todo();\n}\n// TODO: tidy\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/lib.rs"), "// todo later\n").unwrap();
+        fs::write(root.join("target/out.rs"), "// todo generated\n").unwrap();
+        fs::write(root.join("image.bin"), b"todo\0binary").unwrap();
+        dir
+    }
+
+    fn run(root: &Path, query: SearchQuery) -> (SearchSummary, Vec<SearchResult>) {
+        let results = Mutex::new(Vec::new());
+        let summary = search(root, 1, &query, &|| false, &|result| {
+            results.lock().unwrap().push(result)
+        })
+        .unwrap();
+        let mut results = results.into_inner().unwrap();
+        results.sort_by(|a, b| a.path.cmp(&b.path));
+        (summary, results)
+    }
+
+    fn query(text: &str) -> SearchQuery {
+        SearchQuery {
+            query: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_finds_matches_respecting_gitignore_and_skipping_binaries() {
+        let dir = project();
+        let (summary, results) = run(dir.path(), query("todo"));
+        let paths: Vec<&str> = results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["src/lib.rs", "src/main.rs"]);
+        assert_eq!(summary.lines_matched, 3);
+        assert!(!summary.truncated);
+        assert_eq!(
+            results[1].lines[0],
+            LineMatch {
+                line_number: 2,
+                text: "    Todo();".to_string(),
+                ranges: vec![(4, 8)],
+            }
+        );
+    }
+
+    #[test]
+    fn test_capitals_make_the_search_case_sensitive() {
+        let dir = project();
+        let (summary, results) = run(dir.path(), query("TODO"));
+        assert_eq!(summary.lines_matched, 1);
+        assert_eq!(results[0].lines[0].line_number, 4);
+    }
+
+    #[test]
+    fn test_supports_regexes_globs_and_limits() {
+        let dir = project();
+        let regex = SearchQuery {
+            query: r"fn \w+\(".to_string(),
+            regex: true,
+            ..Default::default()
+        };
+        assert_eq!(run(dir.path(), regex).0.lines_matched, 1);
+
+        let globbed = SearchQuery {
+            globs: vec!["!src/main.rs".to_string()],
+            ..query("todo")
+        };
+        let (_, results) = run(dir.path(), globbed);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "src/lib.rs");
+
+        let limited = SearchQuery {
+            max_results: Some(1),
+            ..query("todo")
+        };
+        let (summary, results) = run(dir.path(), limited);
+        assert!(summary.truncated);
+        assert_eq!(results.iter().map(|r| r.lines.len()).sum::<usize>(), 1);
+    }
+
+    #[test]
+    fn test_rejects_bad_queries() {
+        assert!(matcher(&query("")).is_err());
+        let invalid = SearchQuery {
+            query: "(".to_string(),
+            regex: true,
+            ..Default::default()
+        };
+        assert!(matcher(&invalid).is_err());
+        // Literal searches escape regex syntax
+        assert!(matcher(&query("(")).unwrap().is_match("f("));
+    }
+
+    #[test]
+    fn test_newer_searches_supersede_older_ones() {
+        let searches = Searches::default();
+        let first = searches.start();
+        let second = searches.start();
+        assert!(!searches.is_current(first));
+        assert!(searches.is_current(second));
+    }
+}
@@ -0,0 +1,284 @@
+//! Named command snippets with placeholders, typed into terminals
+//!
+//! Profile snippets live in the settings (`terminal.snippets`) and follow the
+//! active profile; project snippets live in .zeami/snippets.toml and win over
+//! a profile snippet of the same name. A command's `{{name}}` placeholders are
+//! filled from the variables given when expanding; `{{name:default}}` falls
+//! back to the default. Values are inserted as given, without shell quoting.
+
+use crate::config::atomic::write_atomic;
+use crate::config::Snippet;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Project snippets, relative to the project root
+pub const SNIPPETS_FILE: &str = ".zeami/snippets.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnippetScope {
+    /// Settings of the active profile
+    Profile,
+    /// .zeami/snippets.toml of the project
+    Project,
+}
+
+/// A snippet with where it is defined and the placeholders it takes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScopedSnippet {
+    #[serde(flatten)]
+    pub snippet: Snippet,
+    pub scope: SnippetScope,
+    pub placeholders: Vec<Placeholder>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Placeholder {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+/// Contents of .zeami/snippets.toml
+///
+/// ```toml
+/// [[snippets]]
+/// name = "migrate"
+/// command = "npm run db:migrate -- --env {{env:development}}"
+/// description = "Run database migrations"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SnippetFile {
+    #[serde(default)]
+    pub snippets: Vec<Snippet>,
+}
+
+impl SnippetFile {
+    /// Snippets of the project at `project_root`; a missing file gives none
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let path = project_root.join(SNIPPETS_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text).with_context(|| format!("Invalid {:?}", path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        }
+    }
+
+    pub fn save(&self, project_root: &Path) -> Result<()> {
+        let text = toml::to_string_pretty(self)?;
+        write_atomic(&project_root.join(SNIPPETS_FILE), text.as_bytes())
+    }
+
+    /// Add a snippet, or replace the one with the same name
+    pub fn upsert(&mut self, snippet: Snippet) {
+        match self.snippets.iter_mut().find(|s| s.name == snippet.name) {
+            Some(existing) => *existing = snippet,
+            None => self.snippets.push(snippet),
+        }
+    }
+
+    /// Returns false when no such snippet exists
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.snippets.len();
+        self.snippets.retain(|snippet| snippet.name != name);
+        self.snippets.len() != before
+    }
+}
+
+/// Profile and project snippets by name, project ones replacing profile ones
+pub fn merge(profile: &[Snippet], project: &[Snippet]) -> Vec<ScopedSnippet> {
+    let mut merged = BTreeMap::new();
+    let scoped = profile
+        .iter()
+        .map(|snippet| (snippet, SnippetScope::Profile))
+        .chain(
+            project
+                .iter()
+                .map(|snippet| (snippet, SnippetScope::Project)),
+        );
+    for (snippet, scope) in scoped {
+        merged.insert(
+            snippet.name.clone(),
+            ScopedSnippet {
+                snippet: snippet.clone(),
+                scope,
+                placeholders: placeholders(&snippet.command),
+            },
+        );
+    }
+    merged.into_values().collect()
+}
+
+/// Reject snippets that could never expand
+pub fn validate(snippet: &Snippet) -> Result<()> {
+    if snippet.name.trim().is_empty() {
+        bail!("Snippet name must not be empty");
+    }
+    if snippet.command.trim().is_empty() {
+        bail!("Snippet {} has no command", snippet.name);
+    }
+    for (name, _) in slots(&snippet.command) {
+        if !valid_name(name) {
+            bail!("Invalid placeholder {{{{{}}}}} in {}", name, snippet.name);
+        }
+    }
+    Ok(())
+}
+
+/// Placeholders of `command` in order of first use
+pub fn placeholders(command: &str) -> Vec<Placeholder> {
+    let mut found: Vec<Placeholder> = Vec::new();
+    for (name, default) in slots(command) {
+        if !found.iter().any(|p| p.name == name) {
+            found.push(Placeholder {
+                name: name.to_string(),
+                default: default.map(str::to_string),
+            });
+        }
+    }
+    found
+}
+
+/// Fill the placeholders of `command` from `vars`
+pub fn expand(command: &str, vars: &BTreeMap<String, String>) -> Result<String> {
+    let mut expanded = String::with_capacity(command.len());
+    let mut missing = Vec::new();
+    let mut rest = command;
+    while let Some((before, name, default, after)) = next_slot(rest) {
+        expanded.push_str(before);
+        match vars.get(name).map(String::as_str).or(default) {
+            Some(value) => expanded.push_str(value),
+            None => {
+                if !missing.contains(&name) {
+                    missing.push(name);
+                }
+            }
+        }
+        rest = after;
+    }
+    expanded.push_str(rest);
+    if !missing.is_empty() {
+        bail!("Missing values for {}", missing.join(", "));
+    }
+    Ok(expanded)
+}
+
+/// Name and default of every `{{...}}` in `command`
+fn slots(command: &str) -> Vec<(&str, Option<&str>)> {
+    let mut slots = Vec::new();
+    let mut rest = command;
+    while let Some((_, name, default, after)) = next_slot(rest) {
+        slots.push((name, default));
+        rest = after;
+    }
+    slots
+}
+
+/// Text before the next placeholder, its name and default, and the text after it
+fn next_slot(text: &str) -> Option<(&str, &str, Option<&str>, &str)> {
+    let start = text.find("{{")?;
+    let end = start + text[start..].find("}}")?;
+    let inner = &text[start + 2..end];
+    let (name, default) = match inner.split_once(':') {
+        Some((name, default)) => (name.trim(), Some(default)),
+        None => (inner.trim(), None),
+    };
+    Some((&text[..start], name, default, &text[end + 2..]))
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(name: &str, command: &str) -> Snippet {
+        Snippet {
+            name: name.to_string(),
+            command: command.to_string(),
+            description: None,
+        }
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_expands_placeholders_with_defaults() {
+        let command = "kubectl -n {{ns:default}} logs {{pod}} --tail {{lines:100}} # {{pod}}";
+        assert_eq!(
+            expand(command, &vars(&[("pod", "web-1"), ("lines", "20")])).unwrap(),
+            "kubectl -n default logs web-1 --tail 20 # web-1"
+        );
+        let error = expand(command, &BTreeMap::new()).unwrap_err();
+        assert_eq!(error.to_string(), "Missing values for pod");
+        assert_eq!(
+            placeholders(command),
+            vec![
+                Placeholder {
+                    name: "ns".to_string(),
+                    default: Some("default".to_string())
+                },
+                Placeholder {
+                    name: "pod".to_string(),
+                    default: None
+                },
+                Placeholder {
+                    name: "lines".to_string(),
+                    default: Some("100".to_string())
+                },
+            ]
+        );
+        // An unclosed brace is plain text
+        assert_eq!(expand("echo {{x", &BTreeMap::new()).unwrap(), "echo {{x");
+    }
+
+    #[test]
+    fn test_project_snippets_replace_profile_ones() {
+        let merged = merge(
+            &[
+                snippet("test", "cargo test"),
+                snippet("up", "docker compose up"),
+            ],
+            &[snippet("test", "npm test -- {{filter:}}")],
+        );
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].snippet.command, "npm test -- {{filter:}}");
+        assert_eq!(merged[0].scope, SnippetScope::Project);
+        assert_eq!(merged[0].placeholders[0].default.as_deref(), Some(""));
+        assert_eq!(merged[1].scope, SnippetScope::Profile);
+    }
+
+    #[test]
+    fn test_validates_snippets() {
+        assert!(validate(&snippet("ok", "echo {{who:world}}")).is_ok());
+        assert!(validate(&snippet(" ", "echo")).is_err());
+        assert!(validate(&snippet("empty", "  ")).is_err());
+        assert!(validate(&snippet("bad", "echo {{two words}}")).is_err());
+    }
+
+    #[test]
+    fn test_saves_project_snippets() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = SnippetFile::load(dir.path()).unwrap();
+        file.upsert(snippet("build", "make"));
+        file.upsert(snippet("build", "make -j8"));
+        file.save(dir.path()).unwrap();
+
+        let mut loaded = SnippetFile::load(dir.path()).unwrap();
+        assert_eq!(loaded.snippets, vec![snippet("build", "make -j8")]);
+        assert!(loaded.remove("build"));
+        assert!(!loaded.remove("build"));
+    }
+}
@@ -0,0 +1,262 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Tabs and terminal panes of a project window
+///
+/// ```json
+/// {
+///   "tabs": [{
+///     "id": "t1",
+///     "root": {
+///       "type": "split", "id": "p1", "direction": "horizontal", "sizes": [0.6, 0.4],
+///       "children": [
+///         { "type": "terminal", "id": "p2", "session": { "session_id": "abc", "cwd": "/src/app" } },
+///         { "type": "terminal", "id": "p3" }
+///       ]
+///     }
+///   }],
+///   "active_tab": "t1"
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Layout {
+    /// In tab order
+    pub tabs: Vec<Tab>,
+    pub active_tab: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tab {
+    pub id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    pub root: Pane,
+    /// Terminal pane with the keyboard focus
+    #[serde(default)]
+    pub focused_pane: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Pane {
+    Terminal {
+        id: String,
+        /// None for a pane whose session has not been started
+        #[serde(default)]
+        session: Option<PaneSession>,
+    },
+    Split {
+        id: String,
+        direction: SplitDirection,
+        children: Vec<Pane>,
+        /// Share of the space of each child; even when empty
+        #[serde(default)]
+        sizes: Vec<f32>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitDirection {
+    /// Children side by side
+    Horizontal,
+    /// Children stacked
+    Vertical,
+}
+
+/// The PTY session shown in a pane, with what is needed to start a new one
+/// in its place after a restart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaneSession {
+    pub session_id: String,
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// Returned by get_layout and update_layout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectLayout {
+    pub layout: Layout,
+    /// Terminal panes whose session is no longer running; the frontend starts
+    /// a new session in each, in the pane's `cwd`
+    pub detached_panes: Vec<String>,
+}
+
+impl Pane {
+    pub fn id(&self) -> &str {
+        match self {
+            Pane::Terminal { id, .. } | Pane::Split { id, .. } => id,
+        }
+    }
+
+    /// Terminal panes in order, depth first
+    pub fn terminals(&self) -> Vec<(&str, Option<&PaneSession>)> {
+        match self {
+            Pane::Terminal { id, session } => vec![(id.as_str(), session.as_ref())],
+            Pane::Split { children, .. } => children.iter().flat_map(Pane::terminals).collect(),
+        }
+    }
+
+    fn validate(&self, pane_ids: &mut HashSet<String>) -> Result<()> {
+        if self.id().is_empty() || !pane_ids.insert(self.id().to_string()) {
+            bail!("Pane id {:?} is empty or used twice", self.id());
+        }
+        if let Pane::Split {
+            id,
+            children,
+            sizes,
+            ..
+        } = self
+        {
+            if children.len() < 2 {
+                bail!("Split {} needs at least two panes", id);
+            }
+            if !sizes.is_empty()
+                && (sizes.len() != children.len()
+                    || sizes.iter().any(|size| !size.is_finite() || *size <= 0.0))
+            {
+                bail!("Split {} needs one positive size per pane", id);
+            }
+            for child in children {
+                child.validate(pane_ids)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Layout {
+    /// Check ids are unique, splits are well formed and every session is
+    /// shown in one pane at most
+    pub fn validate(&self) -> Result<()> {
+        let mut tab_ids = HashSet::new();
+        let mut pane_ids = HashSet::new();
+        let mut sessions = HashSet::new();
+        for tab in &self.tabs {
+            if tab.id.is_empty() || !tab_ids.insert(tab.id.as_str()) {
+                bail!("Tab id {:?} is empty or used twice", tab.id);
+            }
+            tab.root.validate(&mut pane_ids)?;
+            let terminals = tab.root.terminals();
+            if let Some(focused) = &tab.focused_pane {
+                if !terminals.iter().any(|(id, _)| id == focused) {
+                    bail!(
+                        "Focused pane {} is not a terminal of tab {}",
+                        focused,
+                        tab.id
+                    );
+                }
+            }
+            for session in terminals.into_iter().filter_map(|(_, session)| session) {
+                if !sessions.insert(session.session_id.as_str()) {
+                    bail!("Session {} is in more than one pane", session.session_id);
+                }
+            }
+        }
+        if let Some(active) = &self.active_tab {
+            if !tab_ids.contains(active.as_str()) {
+                bail!("Active tab {} does not exist", active);
+            }
+        }
+        Ok(())
+    }
+
+    /// Terminal panes whose session is not in `live`, e.g. after a restart
+    pub fn detached_panes(&self, live: &HashSet<String>) -> Vec<String> {
+        self.tabs
+            .iter()
+            .flat_map(|tab| tab.root.terminals())
+            .filter(|(_, session)| {
+                session.is_some_and(|session| !live.contains(&session.session_id))
+            })
+            .map(|(id, _)| id.to_string())
+            .collect()
+    }
+}
+
+/// A layout saved before layouts had a schema is dropped rather than making
+/// the whole state file unreadable
+pub(super) fn lenient<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Layout>, D::Error> {
+    let value = Option::<Value>::deserialize(deserializer)?;
+    Ok(value.and_then(|value| match serde_json::from_value(value) {
+        Ok(layout) => Some(layout),
+        Err(e) => {
+            tracing::warn!("Dropped unreadable layout: {}", e);
+            None
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn layout() -> Layout {
+        serde_json::from_value(json!({
+            "tabs": [{
+                "id": "t1",
+                "root": {
+                    "type": "split", "id": "p1", "direction": "horizontal", "sizes": [0.6, 0.4],
+                    "children": [
+                        { "type": "terminal", "id": "p2", "session": { "session_id": "a", "cwd": "/src" } },
+                        { "type": "terminal", "id": "p3", "session": { "session_id": "b" } }
+                    ]
+                },
+                "focused_pane": "p3"
+            }, {
+                "id": "t2",
+                "root": { "type": "terminal", "id": "p4" }
+            }],
+            "active_tab": "t2"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validates_layouts() {
+        let valid = layout();
+        valid.validate().unwrap();
+
+        let mut twice = valid.clone();
+        twice.tabs[1].root = Pane::Terminal {
+            id: "p4".into(),
+            session: Some(PaneSession {
+                session_id: "a".into(),
+                cwd: None,
+                title: None,
+            }),
+        };
+        assert!(twice.validate().is_err());
+
+        let mut lopsided = valid.clone();
+        if let Pane::Split { sizes, .. } = &mut lopsided.tabs[0].root {
+            sizes.push(0.1);
+        }
+        assert!(lopsided.validate().is_err());
+
+        let mut focus = valid.clone();
+        focus.tabs[0].focused_pane = Some("p1".into());
+        assert!(focus.validate().is_err());
+
+        let mut active = valid;
+        active.active_tab = Some("t9".into());
+        assert!(active.validate().is_err());
+    }
+
+    #[test]
+    fn test_finds_panes_of_ended_sessions() {
+        let live = HashSet::from(["b".to_string()]);
+        assert_eq!(layout().detached_panes(&live), ["p2"]);
+    }
+}
@@ -0,0 +1,44 @@
+//! Per-project state kept in <project>/.zeami/state.json
+//!
+//! Holds what the app needs to resume work in a project: issue links, the
+//! profile last used, the tab and pane layout with the session of each pane
+//! (see layout) and the last sync times. Every change made through the app is
+//! announced as `state-changed`.
+
+mod layout;
+mod store;
+
+pub use layout::{Layout, Pane, PaneSession, ProjectLayout, SplitDirection, Tab};
+pub use store::*;
+
+use crate::config::GitSettings;
+use crate::events::schema::v1;
+use crate::events::STATE_CHANGED_EVENT_NAME;
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+/// Announce a changed project state as `state-changed`
+pub fn emit_changed(app: &AppHandle, snapshot: ProjectSnapshot) {
+    let payload: v1::StateChanged = snapshot;
+    if let Err(e) = app.emit_all(STATE_CHANGED_EVENT_NAME, payload) {
+        tracing::error!("Failed to emit state change: {}", e);
+    }
+}
+
+/// Record that the `kind` sync of the project at `project_root` ran at `at`
+pub fn record_sync(
+    app: &AppHandle,
+    project_root: &Path,
+    git: &GitSettings,
+    kind: &str,
+    at: DateTime<Utc>,
+) {
+    match update(project_root, |state| {
+        state.last_sync.insert(kind.to_string(), at);
+        Ok(())
+    }) {
+        Ok(state) => emit_changed(app, ProjectSnapshot::new(project_root, state, git)),
+        Err(e) => tracing::warn!(path = ?project_root, "Failed to record {} sync: {:#}", kind, e),
+    }
+}
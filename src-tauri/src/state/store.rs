@@ -0,0 +1,198 @@
+use super::layout::{self, Layout};
+use crate::config::atomic::write_atomic;
+use crate::config::GitSettings;
+use crate::git::links::{self, IssueLink};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Serializes read-modify-write cycles on state files
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Contents of <project>/.zeami/state.json
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectState {
+    /// Issues worked on in local branches, recorded by start_issue
+    pub links: Vec<IssueLink>,
+    /// Settings profile last used with the project
+    pub active_profile: Option<String>,
+    /// Tabs and panes as last arranged, with the session of each pane
+    #[serde(deserialize_with = "layout::lenient")]
+    pub layout: Option<Layout>,
+    /// When each kind of sync (e.g. "progress") last ran
+    pub last_sync: BTreeMap<String, DateTime<Utc>>,
+}
+
+/// Project state with the issue linked to the checked-out branch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSnapshot {
+    pub project_root: PathBuf,
+    pub current_issue: Option<IssueLink>,
+    #[serde(flatten)]
+    pub state: ProjectState,
+}
+
+impl ProjectSnapshot {
+    pub fn new(project_root: &Path, state: ProjectState, git: &GitSettings) -> Self {
+        let current_issue = links::linked_issue(project_root, git).unwrap_or_else(|e| {
+            tracing::debug!(path = ?project_root, "No linked issue: {:#}", e);
+            None
+        });
+        Self {
+            project_root: project_root.to_path_buf(),
+            current_issue,
+            state,
+        }
+    }
+}
+
+/// <project>/.zeami/state.json
+pub fn state_path(project_root: &Path) -> PathBuf {
+    project_root.join(".zeami").join("state.json")
+}
+
+/// State of the project at `project_root`; a missing file gives the default
+pub fn load(project_root: &Path) -> Result<ProjectState> {
+    let path = state_path(project_root);
+    match std::fs::read_to_string(&path) {
+        Ok(text) => {
+            serde_json::from_str(&text).with_context(|| format!("Failed to parse {:?}", path))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ProjectState::default()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", path)),
+    }
+}
+
+/// Apply `change` to the state of `project_root` and write it back atomically
+/// Returns the new state
+pub fn update(
+    project_root: &Path,
+    change: impl FnOnce(&mut ProjectState) -> Result<()>,
+) -> Result<ProjectState> {
+    let _guard = WRITE_LOCK.lock().unwrap();
+    let mut state = load(project_root)?;
+    change(&mut state)?;
+
+    let path = state_path(project_root);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    write_atomic(&path, serde_json::to_string_pretty(&state)?.as_bytes())?;
+    Ok(state)
+}
+
+/// Apply a JSON merge patch (RFC 7396) from the frontend to `state`
+///
+/// Links are only recorded by start_issue, so a patch may not touch them.
+pub fn apply_patch(state: &mut ProjectState, patch: Value) -> Result<()> {
+    let Value::Object(fields) = &patch else {
+        bail!("State update must be a JSON object");
+    };
+    if fields.contains_key("links") {
+        bail!("Issue links are recorded by start_issue and cannot be updated directly");
+    }
+
+    let sets_layout = fields.get("layout").is_some_and(|layout| !layout.is_null());
+
+    let mut value = serde_json::to_value(&*state)?;
+    merge(&mut value, patch);
+    let updated: ProjectState = serde_json::from_value(value).context("Invalid state update")?;
+    match &updated.layout {
+        Some(layout) => layout.validate().context("Invalid layout")?,
+        None if sets_layout => bail!("Invalid layout"),
+        None => {}
+    }
+    *state = updated;
+    Ok(())
+}
+
+fn merge(target: &mut Value, patch: Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch;
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let Value::Object(target) = target else {
+        unreachable!()
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(&key);
+        } else {
+            merge(target.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_update_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(load(dir.path()).unwrap(), ProjectState::default());
+
+        let now = Utc::now();
+        let state = update(dir.path(), |state| {
+            state.last_sync.insert("progress".into(), now);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(load(dir.path()).unwrap(), state);
+
+        // A failed change leaves the file alone
+        assert!(update(dir.path(), |_| bail!("no")).is_err());
+        assert_eq!(load(dir.path()).unwrap().last_sync["progress"], now);
+    }
+
+    #[test]
+    fn test_apply_patch() {
+        let mut state = ProjectState {
+            active_profile: Some("work".into()),
+            layout: Some(
+                serde_json::from_value(json!({
+                    "tabs": [{ "id": "t1", "root": { "type": "terminal", "id": "p1" } }],
+                    "active_tab": "t1"
+                }))
+                .unwrap(),
+            ),
+            ..ProjectState::default()
+        };
+
+        apply_patch(
+            &mut state,
+            json!({ "layout": { "active_tab": null }, "active_profile": null }),
+        )
+        .unwrap();
+        let layout = state.layout.as_ref().unwrap();
+        assert_eq!((layout.tabs.len(), layout.active_tab.as_deref()), (1, None));
+        assert_eq!(state.active_profile, None);
+
+        assert!(apply_patch(&mut state, json!({ "layout": { "active_tab": "t9" } })).is_err());
+        assert!(apply_patch(&mut state, json!({ "layout": { "panes": 3 } })).is_err());
+
+        assert!(apply_patch(&mut state, json!({ "links": [] })).is_err());
+        assert!(apply_patch(&mut state, json!({ "last_sync": { "github": "soon" } })).is_err());
+        assert!(apply_patch(&mut state, json!([])).is_err());
+    }
+
+    #[test]
+    fn test_drops_layouts_saved_without_a_schema() {
+        let state: ProjectState = serde_json::from_value(json!({
+            "active_profile": "work",
+            "layout": { "panes": 2, "split": "vertical" }
+        }))
+        .unwrap();
+        assert_eq!(state.active_profile.as_deref(), Some("work"));
+        assert_eq!(state.layout, None);
+    }
+}
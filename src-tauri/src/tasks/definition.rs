@@ -0,0 +1,248 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Task definitions, relative to the project root
+pub const TASKS_FILE: &str = ".zeami/tasks.toml";
+
+const DEFAULT_MAX_PARALLEL: usize = 4;
+
+/// Longest a task may run without its own `timeout`
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Contents of .zeami/tasks.toml
+///
+/// ```toml
+/// max_parallel = 2
+///
+/// [tasks.test]
+/// command = "npm test"
+/// depends_on = ["lint"]
+/// watch = ["src/**/*.ts"]
+/// env = { CI = "1" }
+///
+/// [tasks.integration]
+/// command = "pytest tests/integration"
+/// runs_in = "container"
+/// container = "app"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskFile {
+    /// Tasks of one run_task that may run at the same time
+    #[serde(default = "default_max_parallel")]
+    pub max_parallel: usize,
+    #[serde(default)]
+    pub tasks: BTreeMap<String, TaskDefinition>,
+}
+
+fn default_max_parallel() -> usize {
+    DEFAULT_MAX_PARALLEL
+}
+
+impl Default for TaskFile {
+    fn default() -> Self {
+        Self {
+            max_parallel: DEFAULT_MAX_PARALLEL,
+            tasks: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskDefinition {
+    pub command: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Working directory relative to the project root
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Tasks that must succeed before this one starts
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Globs relative to the project root; changes to matching files rerun the task
+    #[serde(default)]
+    pub watch: Vec<String>,
+    /// Seconds before the task is killed
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// Where the command runs
+    #[serde(default)]
+    pub runs_in: RunsIn,
+    /// Container name or Compose service for `runs_in = "container"`; the
+    /// project's first Compose container when unset
+    #[serde(default)]
+    pub container: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunsIn {
+    #[default]
+    Host,
+    /// A running Docker Compose container of the project, through `docker exec`
+    Container,
+}
+
+impl TaskDefinition {
+    pub fn cwd(&self, project_root: &Path) -> PathBuf {
+        match &self.cwd {
+            Some(cwd) => project_root.join(cwd),
+            None => project_root.to_path_buf(),
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout.map_or(DEFAULT_TIMEOUT, Duration::from_secs)
+    }
+}
+
+impl TaskFile {
+    /// Tasks of the project at `project_root`; empty when it has no tasks.toml
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let path = project_root.join(TASKS_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text).with_context(|| format!("Invalid {:?}", path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let file: TaskFile = toml::from_str(text)?;
+        if file.max_parallel == 0 {
+            bail!("max_parallel must be at least 1");
+        }
+        for name in file.tasks.keys() {
+            file.plan(name)?;
+        }
+        Ok(file)
+    }
+
+    /// Add tasks defined outside the project, such as by plugins; the
+    /// project's own task of the same name wins
+    pub fn with_tasks(mut self, tasks: &BTreeMap<String, TaskDefinition>) -> Self {
+        for (name, task) in tasks {
+            self.tasks
+                .entry(name.clone())
+                .or_insert_with(|| task.clone());
+        }
+        self
+    }
+
+    /// `name` and every task it depends on, each after its dependencies
+    pub fn plan(&self, name: &str) -> Result<Vec<String>> {
+        let mut order = Vec::new();
+        self.visit(name, &mut Vec::new(), &mut order)?;
+        Ok(order)
+    }
+
+    fn visit(&self, name: &str, path: &mut Vec<String>, order: &mut Vec<String>) -> Result<()> {
+        if order.iter().any(|done| done == name) {
+            return Ok(());
+        }
+        if path.iter().any(|visiting| visiting == name) {
+            path.push(name.to_string());
+            bail!("Task dependencies form a cycle: {}", path.join(" -> "));
+        }
+        let Some(task) = self.tasks.get(name) else {
+            match path.last() {
+                Some(parent) => bail!("Task {} depends on unknown task {}", parent, name),
+                None => bail!("Unknown task {}", name),
+            }
+        };
+
+        path.push(name.to_string());
+        for dependency in &task.depends_on {
+            self.visit(dependency, path, order)?;
+        }
+        path.pop();
+        order.push(name.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TASKS: &str = r#"
+        max_parallel = 2
+
+        [tasks.lint]
+        command = "npm run lint"
+
+        [tasks.test]
+        command = "npm test"
+        depends_on = ["lint", "build"]
+        watch = ["src/**/*.ts"]
+
+        [tasks.build]
+        command = "npm run build"
+        cwd = "web"
+        env = { NODE_ENV = "production" }
+        depends_on = ["lint"]
+        timeout = 120
+    "#;
+
+    #[test]
+    fn test_parse_and_plan() {
+        let file = TaskFile::parse(TASKS).unwrap();
+        assert_eq!(file.max_parallel, 2);
+        assert_eq!(file.plan("test").unwrap(), vec!["lint", "build", "test"]);
+        assert_eq!(file.plan("lint").unwrap(), vec!["lint"]);
+        assert!(file.plan("deploy").is_err());
+
+        let build = &file.tasks["build"];
+        assert_eq!(build.cwd(Path::new("/p")), Path::new("/p/web"));
+        assert_eq!(build.timeout(), Duration::from_secs(120));
+        assert_eq!(build.env["NODE_ENV"], "production");
+        assert_eq!(build.runs_in, RunsIn::Host);
+
+        let file =
+            TaskFile::parse("[tasks.it]\ncommand = \"pytest\"\nruns_in = \"container\"\n").unwrap();
+        assert_eq!(file.tasks["it"].runs_in, RunsIn::Container);
+        assert_eq!(file.tasks["it"].container, None);
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let cycle = r#"
+            [tasks.a]
+            command = "a"
+            depends_on = ["b"]
+            [tasks.b]
+            command = "b"
+            depends_on = ["a"]
+        "#;
+        let error = TaskFile::parse(cycle).unwrap_err().to_string();
+        assert!(error.contains("a -> b -> a"), "{}", error);
+
+        let unknown = "[tasks.a]\ncommand = \"a\"\ndepends_on = [\"missing\"]\n";
+        assert!(TaskFile::parse(unknown).is_err());
+        assert!(TaskFile::parse("[tasks.a]\ncomand = \"a\"\n").is_err());
+        assert!(TaskFile::parse("max_parallel = 0").is_err());
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(TaskFile::load(dir.path()).unwrap().tasks.is_empty());
+    }
+
+    #[test]
+    fn test_with_tasks_keeps_project_tasks() {
+        let extra = TaskFile::parse(
+            "[tasks.lint]\ncommand = \"other lint\"\n[tasks.\"jira:sync\"]\ncommand = \"jira sync\"\n",
+        )
+        .unwrap();
+        let file = TaskFile::parse(TASKS).unwrap().with_tasks(&extra.tasks);
+        assert_eq!(file.tasks["lint"].command, "npm run lint");
+        assert_eq!(file.tasks["jira:sync"].command, "jira sync");
+    }
+}
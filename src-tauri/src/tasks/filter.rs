@@ -0,0 +1,373 @@
+use crate::config::atomic::write_atomic;
+use anyhow::{bail, Context, Result};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Watcher ignore rules, relative to the project root
+pub const WATCHER_CONFIG_FILE: &str = ".zeami/watcher-config.toml";
+
+/// How a rule's pattern is compared with a path relative to the project root
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchType {
+    /// `*` also matches `/`, so `*.log` matches logs in any directory
+    #[default]
+    Glob,
+    /// The path is or is under this one, e.g. `target` or `web/dist`
+    Prefix,
+    /// The file extension, with or without the dot
+    Extension,
+    Regex,
+}
+
+/// Which entries a rule applies to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    #[default]
+    Any,
+    File,
+    Directory,
+}
+
+/// Paths the task watcher ignores
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilterRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub match_type: MatchType,
+    #[serde(default)]
+    pub kind: EntryKind,
+    /// Globs of paths the rule leaves alone; an ignored directory is still
+    /// visited when an exception names something in it
+    #[serde(default)]
+    pub exceptions: Vec<String>,
+}
+
+/// Contents of .zeami/watcher-config.toml
+///
+/// ```toml
+/// [[rules]]
+/// pattern = "dist"
+/// match_type = "prefix"
+/// kind = "directory"
+/// exceptions = ["dist/config/**"]
+///
+/// [[rules]]
+/// pattern = "log"
+/// match_type = "extension"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatcherConfig {
+    #[serde(default)]
+    pub rules: Vec<FilterRule>,
+}
+
+impl WatcherConfig {
+    /// Rules of the project at `project_root`; empty without a config file
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let path = project_root.join(WATCHER_CONFIG_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text).with_context(|| format!("Invalid {:?}", path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        }
+    }
+
+    pub fn save(&self, project_root: &Path) -> Result<()> {
+        write_atomic(
+            &project_root.join(WATCHER_CONFIG_FILE),
+            toml::to_string_pretty(self)?.as_bytes(),
+        )
+    }
+
+    /// Add `rule`; fails when one with the same pattern and match type exists
+    pub fn add(&mut self, rule: FilterRule) -> Result<()> {
+        if rule.pattern.trim().is_empty() {
+            bail!("The pattern is empty");
+        }
+        if self.rules.iter().any(|existing| {
+            existing.pattern == rule.pattern && existing.match_type == rule.match_type
+        }) {
+            bail!("A rule for {} already exists", rule.pattern);
+        }
+        // Reject patterns that would stop the watcher from scanning
+        CompiledRule::new(&rule)?;
+        self.rules.push(rule);
+        Ok(())
+    }
+
+    /// Remove the rules for `pattern`; false when there were none
+    pub fn remove(&mut self, pattern: &str) -> bool {
+        let before = self.rules.len();
+        self.rules.retain(|rule| rule.pattern != pattern);
+        self.rules.len() != before
+    }
+
+    pub fn compile(&self) -> Result<FilterSet> {
+        Ok(FilterSet {
+            rules: self
+                .rules
+                .iter()
+                .map(CompiledRule::new)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// Result of test_filter_rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterTest {
+    pub path: String,
+    pub ignored: bool,
+    /// The rule that ignores the path or one of its directories
+    pub rule: Option<FilterRule>,
+    /// The exception that keeps a matching rule from ignoring the path
+    pub exception: Option<String>,
+}
+
+/// Compiled watcher rules
+#[derive(Debug, Clone, Default)]
+pub struct FilterSet {
+    rules: Vec<CompiledRule>,
+}
+
+impl FilterSet {
+    /// Whether the watcher skips the entry at `relative`; for a directory,
+    /// everything in it is skipped too
+    pub fn ignores(&self, relative: &Path, is_dir: bool) -> bool {
+        self.check(relative, is_dir).0.is_some()
+    }
+
+    /// Why `relative` is or is not ignored, including through its directories
+    pub fn test(&self, relative: &Path, is_dir: bool) -> FilterTest {
+        let mut exception = None;
+        let ancestors: Vec<&Path> = relative
+            .ancestors()
+            .skip(1)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .collect();
+        for (path, is_dir) in ancestors
+            .into_iter()
+            .rev()
+            .map(|dir| (dir, true))
+            .chain([(relative, is_dir)])
+        {
+            let (rule, spared_by) = self.check(path, is_dir);
+            if let Some(rule) = rule {
+                return FilterTest {
+                    path: to_slash(relative),
+                    ignored: true,
+                    rule: Some(rule.rule.clone()),
+                    exception: None,
+                };
+            }
+            exception = exception.or(spared_by);
+        }
+        FilterTest {
+            path: to_slash(relative),
+            ignored: false,
+            rule: None,
+            exception,
+        }
+    }
+
+    /// The first rule ignoring the entry, or else the exception that spared it
+    fn check(&self, relative: &Path, is_dir: bool) -> (Option<&CompiledRule>, Option<String>) {
+        let path = to_slash(relative);
+        let mut spared_by = None;
+        for rule in &self.rules {
+            if !rule.matches(relative, &path, is_dir) {
+                continue;
+            }
+            match rule.exception(&path, is_dir) {
+                Some(exception) => spared_by = spared_by.or(Some(exception)),
+                None => return (Some(rule), None),
+            }
+        }
+        (None, spared_by)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    rule: FilterRule,
+    matcher: Matcher,
+    exceptions: Vec<GlobMatcher>,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Glob(GlobMatcher),
+    Prefix(String),
+    Extension(String),
+    Regex(Regex),
+}
+
+impl CompiledRule {
+    fn new(rule: &FilterRule) -> Result<Self> {
+        let pattern = rule.pattern.trim();
+        let matcher = match rule.match_type {
+            MatchType::Glob => Matcher::Glob(
+                Glob::new(pattern)
+                    .with_context(|| format!("Invalid glob {}", pattern))?
+                    .compile_matcher(),
+            ),
+            MatchType::Prefix => Matcher::Prefix(pattern.trim_matches('/').to_string()),
+            MatchType::Extension => Matcher::Extension(pattern.trim_start_matches('.').to_string()),
+            MatchType::Regex => Matcher::Regex(
+                Regex::new(pattern).with_context(|| format!("Invalid regex {}", pattern))?,
+            ),
+        };
+        let exceptions = rule
+            .exceptions
+            .iter()
+            .map(|exception| {
+                Glob::new(exception)
+                    .map(|glob| glob.compile_matcher())
+                    .with_context(|| format!("Invalid exception {}", exception))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            rule: rule.clone(),
+            matcher,
+            exceptions,
+        })
+    }
+
+    fn matches(&self, relative: &Path, path: &str, is_dir: bool) -> bool {
+        let kind_matches = match self.rule.kind {
+            EntryKind::Any => true,
+            EntryKind::File => !is_dir,
+            EntryKind::Directory => is_dir,
+        };
+        kind_matches
+            && match &self.matcher {
+                Matcher::Glob(glob) => glob.is_match(path),
+                Matcher::Prefix(prefix) => relative.starts_with(prefix),
+                Matcher::Extension(extension) => {
+                    !is_dir
+                        && relative
+                            .extension()
+                            .is_some_and(|ext| ext == extension.as_str())
+                }
+                Matcher::Regex(regex) => regex.is_match(path),
+            }
+    }
+
+    /// The exception matching `path`; for a directory, also one naming
+    /// something inside it
+    fn exception(&self, path: &str, is_dir: bool) -> Option<String> {
+        let inside = format!("{}/", path);
+        self.exceptions
+            .iter()
+            .find(|exception| {
+                exception.is_match(path) || (is_dir && exception.glob().glob().starts_with(&inside))
+            })
+            .map(|exception| exception.glob().glob().to_string())
+    }
+}
+
+fn to_slash(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, match_type: MatchType) -> FilterRule {
+        FilterRule {
+            pattern: pattern.to_string(),
+            match_type,
+            kind: EntryKind::Any,
+            exceptions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_matches_by_type_and_kind() {
+        let mut config = WatcherConfig::default();
+        config.add(rule("*.log", MatchType::Glob)).unwrap();
+        config.add(rule("web/dist", MatchType::Prefix)).unwrap();
+        config.add(rule(".tmp", MatchType::Extension)).unwrap();
+        config
+            .add(rule(r"^coverage-\d+", MatchType::Regex))
+            .unwrap();
+        config
+            .add(FilterRule {
+                kind: EntryKind::Directory,
+                ..rule("cache", MatchType::Prefix)
+            })
+            .unwrap();
+        let filter = config.compile().unwrap();
+
+        assert!(filter.ignores(Path::new("logs/app.log"), false));
+        assert!(filter.ignores(Path::new("web/dist"), true));
+        assert!(filter.ignores(Path::new("web/dist/main.js"), false));
+        assert!(!filter.ignores(Path::new("web/distribution.md"), false));
+        assert!(filter.ignores(Path::new("build/out.tmp"), false));
+        assert!(filter.ignores(Path::new("coverage-42"), true));
+        assert!(filter.ignores(Path::new("cache"), true));
+        assert!(!filter.ignores(Path::new("cache"), false));
+        assert!(!filter.ignores(Path::new("src/main.rs"), false));
+    }
+
+    #[test]
+    fn test_exceptions_keep_paths_inside_ignored_directories() {
+        let mut config = WatcherConfig::default();
+        config
+            .add(FilterRule {
+                exceptions: vec!["dist/config/**".into()],
+                ..rule("dist", MatchType::Prefix)
+            })
+            .unwrap();
+        let filter = config.compile().unwrap();
+
+        // dist itself is still visited so dist/config can be
+        assert!(!filter.ignores(Path::new("dist"), true));
+        assert!(filter.ignores(Path::new("dist/main.js"), false));
+        let test = filter.test(Path::new("dist/config/app.json"), false);
+        assert!(!test.ignored);
+        assert_eq!(test.exception.as_deref(), Some("dist/config/**"));
+
+        let test = filter.test(Path::new("dist/assets/logo.png"), false);
+        assert!(test.ignored);
+        assert_eq!(test.rule.unwrap().pattern, "dist");
+    }
+
+    #[test]
+    fn test_rejects_duplicate_and_invalid_rules() {
+        let mut config = WatcherConfig::default();
+        config.add(rule("target", MatchType::Prefix)).unwrap();
+        assert!(config.add(rule("target", MatchType::Prefix)).is_err());
+        assert!(config.add(rule("(", MatchType::Regex)).is_err());
+        assert!(config.add(rule(" ", MatchType::Glob)).is_err());
+        assert_eq!(config.rules.len(), 1);
+        assert!(config.remove("target"));
+        assert!(!config.remove("target"));
+    }
+
+    #[test]
+    fn test_round_trips_through_the_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            WatcherConfig::load(dir.path()).unwrap(),
+            WatcherConfig::default()
+        );
+        let mut config = WatcherConfig::default();
+        config
+            .add(FilterRule {
+                kind: EntryKind::File,
+                exceptions: vec!["keep.log".into()],
+                ..rule("*.log", MatchType::Glob)
+            })
+            .unwrap();
+        config.save(dir.path()).unwrap();
+        assert_eq!(WatcherConfig::load(dir.path()).unwrap(), config);
+    }
+}
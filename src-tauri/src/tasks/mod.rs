@@ -0,0 +1,490 @@
+//! Named project tasks from .zeami/tasks.toml
+//!
+//! run_task runs a task after everything it depends on, each in its own PTY
+//! (see pty::command), with at most `max_parallel` of them at once. Every
+//! state change is emitted as `task-status` for the task dashboard. Tasks with
+//! `watch` globs rerun when a matching file changes in the project selected
+//! with `watch_tasks`, skipping paths ignored by the project's
+//! .zeami/watcher-config.toml (see filter); files in git submodules are
+//! fingerprinted apart from the project's own. Tasks with
+//! `runs_in = "container"` run in a Docker Compose container of the project
+//! (see containers).
+
+mod definition;
+mod filter;
+mod status;
+mod triggers;
+
+pub use definition::{RunsIn, TaskDefinition, TaskFile};
+pub use filter::{EntryKind, FilterRule, FilterSet, FilterTest, MatchType, WatcherConfig};
+pub use status::{TaskInfo, TaskState, TaskStatus, TaskTrigger};
+
+use crate::capabilities::{Capability, CapabilityRegistry};
+use crate::containers;
+use crate::events::schema::v1;
+use crate::events::TASK_STATUS_EVENT_NAME;
+use crate::git::submodules;
+use crate::pty::command::{self, CommandOptions};
+use crate::telemetry::Telemetry;
+use anyhow::{bail, Result};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+use triggers::Fingerprint;
+
+/// How often watched tasks' files are checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+struct RunnerState {
+    /// Latest status of each task, by project root and task name
+    statuses: HashMap<PathBuf, BTreeMap<String, TaskStatus>>,
+    /// Queued and running tasks
+    active: HashMap<(PathBuf, String), ActiveTask>,
+}
+
+#[derive(Default)]
+struct ActiveTask {
+    stop: Arc<AtomicBool>,
+    /// Set once the task's command starts; 0 until then
+    process_id: Arc<AtomicU32>,
+}
+
+/// Runs tasks and remembers their latest status, managed as Tauri state
+#[derive(Default)]
+pub struct TaskRunner {
+    state: Arc<Mutex<RunnerState>>,
+    /// Tasks added to every project by plugins
+    contributed: Mutex<BTreeMap<String, TaskDefinition>>,
+}
+
+impl TaskRunner {
+    /// Replace the tasks plugins add to every project
+    pub fn set_contributed(&self, tasks: BTreeMap<String, TaskDefinition>) {
+        *self.contributed.lock().unwrap() = tasks;
+    }
+
+    /// The project's tasks.toml plus the tasks contributed by plugins
+    pub fn load(&self, project_root: &Path) -> Result<TaskFile> {
+        let contributed = self.contributed.lock().unwrap().clone();
+        Ok(TaskFile::load(project_root)?.with_tasks(&contributed))
+    }
+
+    /// Tasks of the project with their latest status
+    pub fn list(&self, project_root: &Path) -> Result<Vec<TaskInfo>> {
+        let file = self.load(project_root)?;
+        let state = self.lock()?;
+        let statuses = state.statuses.get(project_root);
+        Ok(file
+            .tasks
+            .into_iter()
+            .map(|(name, definition)| TaskInfo {
+                status: statuses.and_then(|statuses| statuses.get(&name)).cloned(),
+                name,
+                definition,
+            })
+            .collect())
+    }
+
+    /// Queue `name` and its dependencies and run them in the background
+    ///
+    /// Fails when the task or one of its dependencies is already queued or running.
+    pub fn start(
+        &self,
+        app: &AppHandle,
+        project_root: &Path,
+        name: &str,
+        trigger: TaskTrigger,
+    ) -> Result<()> {
+        let file = self.load(project_root)?;
+        let plan = file.plan(name)?;
+        {
+            let mut state = self.lock()?;
+            let key = |task: &String| (project_root.to_path_buf(), task.clone());
+            if let Some(busy) = plan
+                .iter()
+                .find(|task| state.active.contains_key(&key(task)))
+            {
+                bail!("Task {} is already running", busy);
+            }
+            for task in &plan {
+                state.active.insert(key(task), ActiveTask::default());
+            }
+        }
+        for task in &plan {
+            let status =
+                TaskStatus::new(project_root.to_path_buf(), task, TaskState::Queued, trigger);
+            publish(app, &self.state, status);
+        }
+
+        let run = Run {
+            app: app.clone(),
+            state: self.state.clone(),
+            project_root: project_root.to_path_buf(),
+            file,
+            trigger,
+        };
+        tauri::async_runtime::spawn(run.execute(plan));
+        Ok(())
+    }
+
+    /// Stop a queued or running task; tasks depending on it are skipped
+    /// Returns false when the task is not active
+    pub fn stop(&self, project_root: &Path, name: &str) -> Result<bool> {
+        let state = self.lock()?;
+        match state
+            .active
+            .get(&(project_root.to_path_buf(), name.to_string()))
+        {
+            Some(active) => {
+                active.stop.store(true, Ordering::Relaxed);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Project root, name and process id of every task whose command is running
+    pub fn running_processes(&self) -> Vec<(PathBuf, String, u32)> {
+        let Ok(state) = self.state.lock() else {
+            return Vec::new();
+        };
+        state
+            .active
+            .iter()
+            .filter_map(|((root, name), active)| {
+                let pid = active.process_id.load(Ordering::Relaxed);
+                (pid != 0).then(|| (root.clone(), name.clone(), pid))
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, RunnerState>> {
+        self.state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock task runner: {}", e))
+    }
+}
+
+/// One run_task: a plan of tasks in dependency order
+struct Run {
+    app: AppHandle,
+    state: Arc<Mutex<RunnerState>>,
+    project_root: PathBuf,
+    file: TaskFile,
+    trigger: TaskTrigger,
+}
+
+impl Run {
+    async fn execute(self, plan: Vec<String>) {
+        let mut pending: VecDeque<String> = plan.into();
+        let mut results: HashMap<String, TaskState> = HashMap::new();
+        let mut running = JoinSet::new();
+
+        loop {
+            // Plan order puts dependencies first, so one pass settles every task it can
+            let mut index = 0;
+            while index < pending.len() {
+                let name = &pending[index];
+                let dependencies: Vec<Option<TaskState>> = self.file.tasks[name]
+                    .depends_on
+                    .iter()
+                    .map(|dependency| results.get(dependency).copied())
+                    .collect();
+                let blocked = dependencies
+                    .iter()
+                    .any(|state| state.is_some_and(|state| state != TaskState::Succeeded));
+
+                let outcome = if self.stop_requested(name) {
+                    Some(TaskState::Cancelled)
+                } else if blocked {
+                    Some(TaskState::Skipped)
+                } else {
+                    None
+                };
+                if let Some(outcome) = outcome {
+                    let name = pending.remove(index).unwrap_or_default();
+                    self.finish(TaskStatus::new(
+                        self.project_root.clone(),
+                        &name,
+                        outcome,
+                        self.trigger,
+                    ));
+                    results.insert(name, outcome);
+                    continue;
+                }
+
+                let ready = dependencies.iter().all(Option::is_some);
+                if ready && running.len() < self.file.max_parallel {
+                    let name = pending.remove(index).unwrap_or_default();
+                    running.spawn(self.start_task(name));
+                    continue;
+                }
+                index += 1;
+            }
+
+            match running.join_next().await {
+                Some(Ok(status)) => {
+                    results.insert(status.name.clone(), status.state);
+                    self.finish(status);
+                }
+                Some(Err(e)) => tracing::error!("Task failed to complete: {}", e),
+                None => break,
+            }
+        }
+
+        // Only reachable with tasks left when a task panicked
+        for name in pending {
+            self.finish(TaskStatus::new(
+                self.project_root.clone(),
+                &name,
+                TaskState::Skipped,
+                self.trigger,
+            ));
+        }
+    }
+
+    fn start_task(&self, name: String) -> impl std::future::Future<Output = TaskStatus> {
+        let task = self.file.tasks[&name].clone();
+        let mut status = TaskStatus::new(
+            self.project_root.clone(),
+            &name,
+            TaskState::Running,
+            self.trigger,
+        );
+        status.started_at = Some(chrono::Utc::now());
+        publish(&self.app, &self.state, status.clone());
+        if let Some(telemetry) = self.app.try_state::<Telemetry>() {
+            telemetry.task_run();
+        }
+
+        let cwd = task.cwd(&self.project_root);
+        let options = CommandOptions {
+            env: task.env.clone(),
+            cancel: self.stop_flag(&name),
+            process_id: self.process_id_slot(&name),
+        };
+        let project_root = self.project_root.clone();
+        async move {
+            let result = tauri::async_runtime::spawn_blocking(move || match task.runs_in {
+                RunsIn::Host => {
+                    command::run_command_with(&task.command, &cwd, task.timeout(), &options)
+                }
+                RunsIn::Container => containers::run_command_with(
+                    &project_root,
+                    task.container.as_deref(),
+                    &task.command,
+                    &cwd,
+                    task.timeout(),
+                    &options,
+                ),
+            })
+            .await;
+            match result {
+                Ok(Ok(output)) => status.finished(&output),
+                Ok(Err(e)) => TaskStatus {
+                    state: TaskState::Failed,
+                    output: Some(format!("{:#}", e)),
+                    ..status
+                },
+                Err(e) => TaskStatus {
+                    state: TaskState::Failed,
+                    output: Some(e.to_string()),
+                    ..status
+                },
+            }
+        }
+    }
+
+    fn stop_flag(&self, name: &str) -> Option<Arc<AtomicBool>> {
+        let state = self.state.lock().ok()?;
+        state
+            .active
+            .get(&(self.project_root.clone(), name.to_string()))
+            .map(|active| active.stop.clone())
+    }
+
+    fn process_id_slot(&self, name: &str) -> Option<Arc<AtomicU32>> {
+        let state = self.state.lock().ok()?;
+        state
+            .active
+            .get(&(self.project_root.clone(), name.to_string()))
+            .map(|active| active.process_id.clone())
+    }
+
+    fn stop_requested(&self, name: &str) -> bool {
+        self.stop_flag(name)
+            .is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+
+    /// Publish a task's final status and release it for the next run
+    fn finish(&self, status: TaskStatus) {
+        if let Ok(mut state) = self.state.lock() {
+            state
+                .active
+                .remove(&(self.project_root.clone(), status.name.clone()));
+        }
+        tracing::info!(task = %status.name, state = ?status.state, "Task finished");
+        publish(&self.app, &self.state, status);
+    }
+}
+
+fn publish(app: &AppHandle, state: &Mutex<RunnerState>, status: TaskStatus) {
+    if let Ok(mut state) = state.lock() {
+        state
+            .statuses
+            .entry(status.project_root.clone())
+            .or_default()
+            .insert(status.name.clone(), status.clone());
+    }
+    let payload: v1::TaskStatus = status;
+    if let Err(e) = app.emit_all(TASK_STATUS_EVENT_NAME, payload) {
+        tracing::error!("Failed to emit task status: {}", e);
+    }
+}
+
+/// Background rerun of tasks with `watch` globs in the active project
+///
+/// The frontend selects the project with `watch_tasks`.
+#[derive(Default)]
+pub struct TaskWatcher {
+    project_root: Arc<Mutex<Option<PathBuf>>>,
+    wake: Arc<Notify>,
+    /// Why the last scan failed; cleared by the next successful scan
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl TaskWatcher {
+    /// Watch the project at `project_root`, or stop watching with None
+    pub fn watch(&self, project_root: Option<PathBuf>) {
+        *self.project_root.lock().unwrap() = project_root;
+        self.wake.notify_one();
+    }
+
+    /// The project selected with `watch_tasks`
+    pub fn project(&self) -> Option<PathBuf> {
+        self.project_root.lock().unwrap().clone()
+    }
+
+    /// Rescan now, e.g. after the ignore rules changed
+    pub fn reload(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Why the last scan of the watched project failed, if it did
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// Start the polling loop
+    pub fn spawn(&self, app: AppHandle) {
+        let project_root = self.project_root.clone();
+        let wake = self.wake.clone();
+        let last_error = self.last_error.clone();
+
+        tauri::async_runtime::spawn(async move {
+            let mut watched: Option<PathBuf> = None;
+            let mut fingerprints: HashMap<String, Fingerprint> = HashMap::new();
+            let mut rules: Option<WatcherConfig> = None;
+            loop {
+                let root = project_root.lock().unwrap().clone();
+                if root != watched {
+                    fingerprints.clear();
+                    rules = None;
+                    *last_error.lock().unwrap() = None;
+                    watched = root.clone();
+                }
+
+                if let Some(root) = root {
+                    let scan = {
+                        let root = root.clone();
+                        let contributed = app
+                            .state::<TaskRunner>()
+                            .contributed
+                            .lock()
+                            .unwrap()
+                            .clone();
+                        tauri::async_runtime::spawn_blocking(move || scan(&root, &contributed))
+                            .await
+                    };
+                    match scan {
+                        Ok(Ok((current_rules, current))) => {
+                            // Other ignore rules fingerprint other files, which
+                            // is no reason to rerun anything
+                            if rules.as_ref() != Some(&current_rules) {
+                                fingerprints.clear();
+                                rules = Some(current_rules);
+                            }
+                            for (name, fingerprint) in &current {
+                                let sources = fingerprints
+                                    .get(name)
+                                    .map(|previous| {
+                                        triggers::changed_sources(previous, fingerprint)
+                                    })
+                                    .unwrap_or_default();
+                                let changed = !sources.is_empty();
+                                let granted = app
+                                    .state::<CapabilityRegistry>()
+                                    .is_granted(&root, Capability::RunTasks);
+                                if changed && !granted {
+                                    tracing::debug!(task = %name, "Watch trigger ignored: run_tasks is not granted");
+                                } else if changed {
+                                    tracing::debug!(task = %name, sources = ?sources, "Watched files changed");
+                                    let runner = app.state::<TaskRunner>();
+                                    if let Err(e) =
+                                        runner.start(&app, &root, name, TaskTrigger::Watch)
+                                    {
+                                        tracing::debug!(task = %name, "Watch trigger ignored: {:#}", e);
+                                    }
+                                }
+                            }
+                            fingerprints = current;
+                            *last_error.lock().unwrap() = None;
+                        }
+                        Ok(Err(e)) => {
+                            tracing::debug!(path = ?root, "Task watch scan failed: {:#}", e);
+                            *last_error.lock().unwrap() = Some(format!("{:#}", e));
+                        }
+                        Err(e) => {
+                            tracing::error!("Task watch scan failed: {}", e);
+                            *last_error.lock().unwrap() = Some(e.to_string());
+                        }
+                    }
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(WATCH_INTERVAL) => {}
+                    _ = wake.notified() => {}
+                }
+            }
+        });
+    }
+}
+
+/// Fingerprint of the watched files of every task with `watch` globs, with
+/// the ignore rules they were taken under
+fn scan(
+    project_root: &Path,
+    contributed: &BTreeMap<String, TaskDefinition>,
+) -> Result<(WatcherConfig, HashMap<String, Fingerprint>)> {
+    let file = TaskFile::load(project_root)?.with_tasks(contributed);
+    let rules = WatcherConfig::load(project_root)?;
+    let filter = rules.compile()?;
+    let submodules = submodules::paths(project_root);
+    let fingerprints = file
+        .tasks
+        .into_iter()
+        .filter(|(_, task)| !task.watch.is_empty())
+        .map(|(name, task)| {
+            let fingerprint =
+                triggers::fingerprint(project_root, &task.watch, &filter, &submodules)?;
+            Ok((name, fingerprint))
+        })
+        .collect::<Result<_>>()?;
+    Ok((rules, fingerprints))
+}
@@ -0,0 +1,142 @@
+use super::definition::TaskDefinition;
+use crate::pty::command::{self, CommandOutput};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Output kept in a finished task's status, counted from the end
+const OUTPUT_TAIL_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    /// Waiting for dependencies or a free slot
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    /// Not run because a dependency failed or was cancelled
+    Skipped,
+}
+
+impl TaskState {
+    /// State of a task whose command completed with `output`
+    pub fn from_output(output: &CommandOutput) -> Self {
+        if output.cancelled {
+            TaskState::Cancelled
+        } else if output.exit_code == Some(0) {
+            TaskState::Succeeded
+        } else {
+            TaskState::Failed
+        }
+    }
+}
+
+/// What started a task run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskTrigger {
+    Manual,
+    /// A file matching the task's `watch` globs changed
+    Watch,
+    /// A zeami://run-task link was opened
+    DeepLink,
+}
+
+/// Latest state of a task; also the payload of `task-status`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskStatus {
+    pub project_root: PathBuf,
+    pub name: String,
+    pub state: TaskState,
+    pub trigger: TaskTrigger,
+    pub started_at: Option<DateTime<Utc>>,
+    pub duration_ms: Option<u64>,
+    pub exit_code: Option<u32>,
+    pub timed_out: bool,
+    /// End of the output once the task has finished
+    pub output: Option<String>,
+}
+
+impl TaskStatus {
+    pub fn new(project_root: PathBuf, name: &str, state: TaskState, trigger: TaskTrigger) -> Self {
+        Self {
+            project_root,
+            name: name.to_string(),
+            state,
+            trigger,
+            started_at: None,
+            duration_ms: None,
+            exit_code: None,
+            timed_out: false,
+            output: None,
+        }
+    }
+
+    /// This status after the task's command completed
+    pub fn finished(self, output: &CommandOutput) -> Self {
+        Self {
+            state: TaskState::from_output(output),
+            duration_ms: Some(output.duration_ms),
+            exit_code: output.exit_code,
+            timed_out: output.timed_out,
+            output: Some(command::output_tail(&output.output, OUTPUT_TAIL_BYTES).to_string()),
+            ..self
+        }
+    }
+}
+
+/// A task of tasks.toml with its latest status, for list_tasks
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskInfo {
+    pub name: String,
+    #[serde(flatten)]
+    pub definition: TaskDefinition,
+    /// None until the task has run since the app started
+    pub status: Option<TaskStatus>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(exit_code: Option<u32>, cancelled: bool) -> CommandOutput {
+        CommandOutput {
+            exit_code,
+            output: "é".repeat(OUTPUT_TAIL_BYTES),
+            timed_out: exit_code.is_none() && !cancelled,
+            cancelled,
+            duration_ms: 1_500,
+        }
+    }
+
+    #[test]
+    fn test_finished_status() {
+        let status = || {
+            TaskStatus::new(
+                PathBuf::from("/p"),
+                "test",
+                TaskState::Running,
+                TaskTrigger::Manual,
+            )
+        };
+
+        let done = status().finished(&output(Some(0), false));
+        assert_eq!(done.state, TaskState::Succeeded);
+        assert_eq!(done.duration_ms, Some(1_500));
+        assert!(done.output.unwrap().len() <= OUTPUT_TAIL_BYTES);
+
+        assert_eq!(
+            status().finished(&output(Some(1), false)).state,
+            TaskState::Failed
+        );
+        let timed_out = status().finished(&output(None, false));
+        assert_eq!(timed_out.state, TaskState::Failed);
+        assert!(timed_out.timed_out);
+        assert_eq!(
+            status().finished(&output(None, true)).state,
+            TaskState::Cancelled
+        );
+    }
+}
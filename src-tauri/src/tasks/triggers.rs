@@ -0,0 +1,187 @@
+use super::filter::FilterSet;
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSetBuilder};
+use ignore::WalkBuilder;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Source of the project's own files in a Fingerprint
+pub const PROJECT_SOURCE: &str = ".";
+
+/// Hash per source: the project itself (PROJECT_SOURCE) or a submodule
+/// (its relative path)
+pub type Fingerprint = BTreeMap<String, u64>;
+
+/// Hash of the size and modification time of every file under `project_root`
+/// matching `patterns`, per source; a source's hash changes whenever one of
+/// its matching files is added, removed or modified
+///
+/// Files under one of the `submodules` paths count towards that submodule, so
+/// a change there is told apart from a change to the project. Hidden and
+/// git-ignored files are not visited, which keeps dependency and build
+/// directories out of the scan, and neither is anything `filter` ignores.
+pub fn fingerprint(
+    project_root: &Path,
+    patterns: &[String],
+    filter: &FilterSet,
+    submodules: &[PathBuf],
+) -> Result<Fingerprint> {
+    let mut globs = GlobSetBuilder::new();
+    for pattern in patterns {
+        globs.add(Glob::new(pattern).with_context(|| format!("Invalid watch glob {}", pattern))?);
+    }
+    let globs = globs.build()?;
+
+    let root = project_root.to_path_buf();
+    let filter = filter.clone();
+    let walk = WalkBuilder::new(project_root)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+            entry.path().strip_prefix(&root).map_or(true, |relative| {
+                relative.as_os_str().is_empty() || !filter.ignores(relative, is_dir)
+            })
+        })
+        .build();
+
+    let mut files = Vec::new();
+    for entry in walk.flatten() {
+        let Ok(relative) = entry.path().strip_prefix(project_root) else {
+            continue;
+        };
+        if !entry.file_type().is_some_and(|kind| kind.is_file()) || !globs.is_match(relative) {
+            continue;
+        }
+        if let Ok(metadata) = entry.metadata() {
+            files.push((
+                relative.to_path_buf(),
+                metadata.len(),
+                metadata.modified().ok(),
+            ));
+        }
+    }
+    files.sort();
+
+    let mut sources: BTreeMap<String, Vec<_>> = BTreeMap::new();
+    for file in files {
+        let source = submodules
+            .iter()
+            .filter(|submodule| file.0.starts_with(submodule))
+            .max_by_key(|submodule| submodule.components().count())
+            .map_or_else(
+                || PROJECT_SOURCE.to_string(),
+                |submodule| submodule.to_string_lossy().replace('\\', "/"),
+            );
+        sources.entry(source).or_default().push(file);
+    }
+    Ok(sources
+        .into_iter()
+        .map(|(source, files)| {
+            let mut hasher = DefaultHasher::new();
+            files.hash(&mut hasher);
+            (source, hasher.finish())
+        })
+        .collect())
+}
+
+/// Sources whose files changed between two fingerprints
+pub fn changed_sources(before: &Fingerprint, after: &Fingerprint) -> Vec<String> {
+    before
+        .keys()
+        .chain(after.keys())
+        .filter(|source| before.get(*source) != after.get(*source))
+        .cloned()
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::filter::{EntryKind, FilterRule, MatchType, WatcherConfig};
+    use std::fs;
+
+    #[test]
+    fn test_fingerprint() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/lib")).unwrap();
+        fs::write(dir.path().join("src/lib/a.ts"), "a").unwrap();
+        fs::write(dir.path().join("README.md"), "docs").unwrap();
+        let patterns = vec!["src/**/*.ts".to_string()];
+        let filter = FilterSet::default();
+
+        let before = fingerprint(dir.path(), &patterns, &filter, &[]).unwrap();
+        fs::write(dir.path().join("README.md"), "more docs").unwrap();
+        assert_eq!(
+            fingerprint(dir.path(), &patterns, &filter, &[]).unwrap(),
+            before
+        );
+
+        fs::write(dir.path().join("src/lib/a.ts"), "changed").unwrap();
+        let changed = fingerprint(dir.path(), &patterns, &filter, &[]).unwrap();
+        assert_ne!(changed, before);
+        fs::write(dir.path().join("src/b.ts"), "b").unwrap();
+        assert_ne!(
+            fingerprint(dir.path(), &patterns, &filter, &[]).unwrap(),
+            changed
+        );
+
+        assert!(fingerprint(dir.path(), &["src/[".to_string()], &filter, &[]).is_err());
+    }
+
+    #[test]
+    fn test_ignored_paths_do_not_change_the_fingerprint() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/generated")).unwrap();
+        fs::write(dir.path().join("src/a.ts"), "a").unwrap();
+        let patterns = vec!["src/**/*.ts".to_string()];
+        let mut config = WatcherConfig::default();
+        config
+            .add(FilterRule {
+                pattern: "src/generated".into(),
+                match_type: MatchType::Prefix,
+                kind: EntryKind::Directory,
+                exceptions: Vec::new(),
+            })
+            .unwrap();
+        let filter = config.compile().unwrap();
+
+        let before = fingerprint(dir.path(), &patterns, &filter, &[]).unwrap();
+        fs::write(dir.path().join("src/generated/api.ts"), "api").unwrap();
+        assert_eq!(
+            fingerprint(dir.path(), &patterns, &filter, &[]).unwrap(),
+            before
+        );
+        fs::write(dir.path().join("src/a.ts"), "changed").unwrap();
+        assert_ne!(
+            fingerprint(dir.path(), &patterns, &filter, &[]).unwrap(),
+            before
+        );
+    }
+
+    #[test]
+    fn test_submodule_changes_count_towards_the_submodule() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("vendor/parser/src")).unwrap();
+        fs::write(dir.path().join("main.rs"), "main").unwrap();
+        fs::write(dir.path().join("vendor/parser/src/lib.rs"), "lib").unwrap();
+        let patterns = vec!["**/*.rs".to_string()];
+        let submodules = [PathBuf::from("vendor/parser")];
+        let filter = FilterSet::default();
+
+        let before = fingerprint(dir.path(), &patterns, &filter, &submodules).unwrap();
+        assert_eq!(
+            before.keys().collect::<Vec<_>>(),
+            [PROJECT_SOURCE, "vendor/parser"]
+        );
+        fs::write(dir.path().join("vendor/parser/src/lib.rs"), "changed").unwrap();
+        let after = fingerprint(dir.path(), &patterns, &filter, &submodules).unwrap();
+        assert_eq!(changed_sources(&before, &after), ["vendor/parser"]);
+
+        fs::remove_file(dir.path().join("main.rs")).unwrap();
+        let removed = fingerprint(dir.path(), &patterns, &filter, &submodules).unwrap();
+        assert_eq!(changed_sources(&after, &removed), [PROJECT_SOURCE]);
+    }
+}
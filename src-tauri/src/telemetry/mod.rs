@@ -0,0 +1,199 @@
+//! Opt-in usage statistics
+//!
+//! Command invocations, task runs and task watcher uptime are counted into
+//! daily rollups kept in ~/.zeami/telemetry.json. The rollups hold only
+//! counts: no paths, arguments, repository names or machine identifiers.
+//! Finished days are uploaded once a day, and only while TelemetrySettings
+//! turns uploads on; get_telemetry_report shows the exact payload beforehand.
+
+mod rollup;
+
+pub use rollup::DailyRollup;
+
+use crate::config::atomic::write_atomic;
+use crate::config::{storage, SettingsState, TelemetrySettings};
+use crate::scheduler::{Job, JobFuture};
+use crate::tasks::TaskWatcher;
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDate};
+use rollup::Rollups;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Scheduler name of the upload job
+pub const TELEMETRY_UPLOAD_JOB: &str = "telemetry_upload";
+
+const UPLOAD_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often counts are written to disk and watcher uptime is sampled
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Body POSTed to the telemetry endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryPayload {
+    pub app_version: String,
+    pub os: String,
+    pub days: Vec<DailyRollup>,
+}
+
+/// Returned by get_telemetry_report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryReport {
+    /// Whether uploads are on
+    pub enabled: bool,
+    pub endpoint: String,
+    /// Exactly what the next upload sends
+    pub payload: TelemetryPayload,
+    /// Counts so far today, sent once the day is over
+    pub today: DailyRollup,
+    pub uploaded_through: Option<NaiveDate>,
+}
+
+/// Daily usage counts; managed by Tauri and uploaded by the scheduler
+#[derive(Clone)]
+pub struct Telemetry {
+    path: PathBuf,
+    rollups: Arc<Mutex<Rollups>>,
+    dirty: Arc<Mutex<bool>>,
+    http: reqwest::Client,
+}
+
+impl Telemetry {
+    /// Rollups kept in ~/.zeami/telemetry.json
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::open(&storage::config_dir()?.join("telemetry.json")))
+    }
+
+    pub fn open(path: &Path) -> Self {
+        let rollups = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self {
+            path: path.to_path_buf(),
+            rollups: Arc::new(Mutex::new(rollups)),
+            dirty: Arc::new(Mutex::new(false)),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Count an invocation of the command `name`
+    pub fn command_invoked(&self, name: &str) {
+        self.record(|day| *day.commands.entry(name.to_string()).or_default() += 1);
+    }
+
+    pub fn task_run(&self) {
+        self.record(|day| day.tasks_run += 1);
+    }
+
+    fn record(&self, change: impl FnOnce(&mut DailyRollup)) {
+        if let Ok(mut rollups) = self.rollups.lock() {
+            change(rollups.day(today()));
+            *self.dirty.lock().unwrap() = true;
+        }
+    }
+
+    /// What an upload would send now, with today's counts so far
+    pub fn report(&self, settings: &TelemetrySettings, app_version: &str) -> TelemetryReport {
+        let rollups = self.rollups.lock().unwrap();
+        TelemetryReport {
+            enabled: settings.enabled,
+            endpoint: settings.endpoint.clone(),
+            payload: payload(&rollups, app_version),
+            today: rollups.days.get(&today()).cloned().unwrap_or(DailyRollup {
+                date: today(),
+                ..DailyRollup::default()
+            }),
+            uploaded_through: rollups.uploaded_through,
+        }
+    }
+
+    /// Write the pending payload to `path`
+    pub fn export(&self, path: &Path, app_version: &str) -> Result<()> {
+        let payload = payload(&self.rollups.lock().unwrap(), app_version);
+        write_atomic(path, serde_json::to_string_pretty(&payload)?.as_bytes())
+    }
+
+    /// Save counts changed since the last flush
+    pub fn flush(&self) -> Result<()> {
+        if !std::mem::take(&mut *self.dirty.lock().unwrap()) {
+            return Ok(());
+        }
+        let text = {
+            let mut rollups = self.rollups.lock().unwrap();
+            rollups.prune(today());
+            serde_json::to_string_pretty(&*rollups)?
+        };
+        write_atomic(&self.path, text.as_bytes())
+    }
+
+    /// Send finished days to the endpoint; does nothing while uploads are off
+    pub async fn upload(&self, settings: &TelemetrySettings, app_version: &str) -> Result<()> {
+        if !settings.enabled || settings.endpoint.trim().is_empty() {
+            return Ok(());
+        }
+        let payload = payload(&self.rollups.lock().unwrap(), app_version);
+        let Some(last) = payload.days.last().map(|day| day.date) else {
+            return Ok(());
+        };
+        self.http
+            .post(&settings.endpoint)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("Failed to upload telemetry")?;
+        self.rollups.lock().unwrap().mark_uploaded(last);
+        *self.dirty.lock().unwrap() = true;
+        tracing::info!(days = payload.days.len(), "Uploaded telemetry");
+        self.flush()
+    }
+
+    /// Start sampling watcher uptime and flushing counts
+    pub fn spawn(&self, app: AppHandle) {
+        let telemetry = self.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(FLUSH_INTERVAL).await;
+                if app.state::<TaskWatcher>().project().is_some() {
+                    telemetry.record(|day| day.watcher_uptime_secs += FLUSH_INTERVAL.as_secs());
+                }
+                if let Err(e) = telemetry.flush() {
+                    tracing::warn!("Failed to save telemetry: {:#}", e);
+                }
+            }
+        });
+    }
+}
+
+impl Job for Telemetry {
+    fn interval(&self, app: &AppHandle) -> Option<Duration> {
+        let settings = app.state::<SettingsState>().current().telemetry;
+        (settings.enabled && !settings.endpoint.trim().is_empty()).then_some(UPLOAD_INTERVAL)
+    }
+
+    fn run(&self, app: &AppHandle) -> JobFuture {
+        let (app, telemetry) = (app.clone(), self.clone());
+        Box::pin(async move {
+            let settings = app.state::<SettingsState>().current().telemetry;
+            let version = app.package_info().version.to_string();
+            telemetry.upload(&settings, &version).await
+        })
+    }
+}
+
+fn payload(rollups: &Rollups, app_version: &str) -> TelemetryPayload {
+    TelemetryPayload {
+        app_version: app_version.to_string(),
+        os: std::env::consts::OS.to_string(),
+        days: rollups.pending(today()),
+    }
+}
+
+/// Days follow the local calendar
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
@@ -0,0 +1,101 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Days of rollups kept locally, uploaded or not
+const RETAINED_DAYS: i64 = 90;
+
+/// Usage counted on one day; holds no paths, arguments or identifiers
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyRollup {
+    pub date: NaiveDate,
+    /// Invocations per command name
+    pub commands: BTreeMap<String, u64>,
+    pub tasks_run: u64,
+    /// Seconds a project was selected for task watching
+    pub watcher_uptime_secs: u64,
+}
+
+/// Contents of ~/.zeami/telemetry.json
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rollups {
+    pub days: BTreeMap<NaiveDate, DailyRollup>,
+    /// Last day already sent
+    pub uploaded_through: Option<NaiveDate>,
+}
+
+impl Rollups {
+    pub fn day(&mut self, date: NaiveDate) -> &mut DailyRollup {
+        self.days.entry(date).or_insert_with(|| DailyRollup {
+            date,
+            ..DailyRollup::default()
+        })
+    }
+
+    /// Finished days not yet uploaded, oldest first
+    pub fn pending(&self, today: NaiveDate) -> Vec<DailyRollup> {
+        self.days
+            .range(..today)
+            .filter(|(date, _)| self.uploaded_through.is_none_or(|last| **date > last))
+            .map(|(_, rollup)| rollup.clone())
+            .collect()
+    }
+
+    pub fn mark_uploaded(&mut self, through: NaiveDate) {
+        self.uploaded_through = Some(
+            self.uploaded_through
+                .map_or(through, |last| last.max(through)),
+        );
+    }
+
+    /// Forget days older than the retention period
+    pub fn prune(&mut self, today: NaiveDate) {
+        let oldest = today - chrono::Duration::days(RETAINED_DAYS);
+        self.days.retain(|date, _| *date >= oldest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    #[test]
+    fn test_pending_days_exclude_today_and_uploaded_days() {
+        let mut rollups = Rollups::default();
+        for day in [1, 2, 3] {
+            *rollups
+                .day(date(day))
+                .commands
+                .entry("git_status".into())
+                .or_default() += 1;
+        }
+        rollups.day(date(3)).tasks_run += 2;
+
+        let pending = rollups.pending(date(3));
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].date, date(1));
+        assert_eq!(pending[0].commands["git_status"], 1);
+
+        rollups.mark_uploaded(date(2));
+        // An older upload finishing late does not resend days
+        rollups.mark_uploaded(date(1));
+        let pending = rollups.pending(date(4));
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].tasks_run, 2);
+    }
+
+    #[test]
+    fn test_prunes_old_days() {
+        let mut rollups = Rollups::default();
+        rollups.day(date(1));
+        rollups.day(date(31));
+        rollups.prune(date(1) + chrono::Duration::days(RETAINED_DAYS + 1));
+        assert_eq!(rollups.days.keys().copied().collect::<Vec<_>>(), [date(31)]);
+    }
+}
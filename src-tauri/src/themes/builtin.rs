@@ -0,0 +1,92 @@
+use super::{Palette, Theme};
+
+/// Name, foreground, background, then black to bright white
+type Scheme = (&'static str, &'static str, &'static str, [&'static str; 16]);
+
+const SCHEMES: [Scheme; 5] = [
+    (
+        "default",
+        "#cccccc",
+        "#1e1e1e",
+        [
+            "#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd", "#e5e5e5",
+            "#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6", "#29b8db", "#ffffff",
+        ],
+    ),
+    (
+        "light",
+        "#333333",
+        "#ffffff",
+        [
+            "#000000", "#cd3131", "#107c10", "#795e26", "#0451a5", "#bc05bc", "#0598bc", "#e5e5e5",
+            "#666666", "#cd3131", "#14ce14", "#b5ba00", "#0451a5", "#bc05bc", "#0598bc", "#ffffff",
+        ],
+    ),
+    (
+        "solarized-dark",
+        "#93a1a1",
+        "#002b36",
+        [
+            "#073642", "#dc322f", "#859900", "#b58900", "#268bd2", "#d33682", "#2aa198", "#eee8d5",
+            "#002b36", "#cb4b16", "#586e75", "#657b83", "#839496", "#6c71c4", "#93a1a1", "#fdf6e3",
+        ],
+    ),
+    (
+        "dracula",
+        "#f8f8f2",
+        "#282a36",
+        [
+            "#21222c", "#ff5555", "#50fa7b", "#f1fa8c", "#bd93f9", "#ff79c6", "#8be9fd", "#f8f8f2",
+            "#6272a4", "#ff6e6e", "#69ff94", "#ffffa5", "#d6acff", "#ff92df", "#a4ffff", "#ffffff",
+        ],
+    ),
+    (
+        "nord",
+        "#d8dee9",
+        "#2e3440",
+        [
+            "#3b4252", "#bf616a", "#a3be8c", "#ebcb8b", "#81a1c1", "#b48ead", "#88c0d0", "#e5e9f0",
+            "#4c566a", "#bf616a", "#a3be8c", "#ebcb8b", "#81a1c1", "#b48ead", "#8fbcbb", "#eceff4",
+        ],
+    ),
+];
+
+/// Schemes shipped with the app
+pub fn themes() -> Vec<Theme> {
+    SCHEMES
+        .iter()
+        .map(|(name, foreground, background, ansi)| Theme {
+            name: name.to_string(),
+            palette: from_ansi(foreground, background, ansi),
+        })
+        .collect()
+}
+
+/// A palette from its foreground, background and 16 ANSI colors
+pub(super) fn from_ansi(foreground: &str, background: &str, ansi: &[&str; 16]) -> Palette {
+    let [black, red, green, yellow, blue, magenta, cyan, white, bright_black, bright_red, bright_green, bright_yellow, bright_blue, bright_magenta, bright_cyan, bright_white] =
+        ansi.map(str::to_string);
+    Palette {
+        foreground: foreground.to_string(),
+        background: background.to_string(),
+        cursor: None,
+        cursor_accent: None,
+        selection_background: None,
+        black,
+        red,
+        green,
+        yellow,
+        blue,
+        magenta,
+        cyan,
+        white,
+        bright_black,
+        bright_red,
+        bright_green,
+        bright_yellow,
+        bright_blue,
+        bright_magenta,
+        bright_cyan,
+        bright_white,
+    }
+}
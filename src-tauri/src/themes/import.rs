@@ -0,0 +1,249 @@
+//! Conversion of other terminals' color schemes into themes
+
+use super::{builtin, Palette, Theme};
+use anyhow::{bail, Context, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Themes in an iTerm2 `.itermcolors` file or Windows Terminal JSON
+///
+/// Windows Terminal files may hold one scheme or a settings.json with a
+/// `schemes` list. An iTerm2 file carries no name, so the file stem is used.
+pub fn from_file(path: &Path) -> Result<Vec<Theme>> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let themes = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("itermcolors") => {
+            let name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("Imported");
+            vec![iterm(name, &text)?]
+        }
+        Some("json") => windows_terminal(&text)?,
+        _ => bail!(
+            "Unsupported theme file {:?}; expected .itermcolors or .json",
+            path
+        ),
+    };
+    for theme in &themes {
+        theme
+            .palette
+            .resolved()
+            .with_context(|| format!("Invalid colors in scheme '{}'", theme.name))?;
+    }
+    Ok(themes)
+}
+
+/// An iTerm2 color preset: a plist dict of color dicts with 0-1 components
+pub fn iterm(name: &str, xml: &str) -> Result<Theme> {
+    let mut reader = Reader::from_str(xml);
+    let mut colors: HashMap<String, [f64; 3]> = HashMap::new();
+    let mut depth = 0;
+    let mut text = String::new();
+    let mut color = String::new();
+    let mut component = String::new();
+    loop {
+        match reader.read_event().context("Invalid iTerm2 color file")? {
+            Event::Start(element) => {
+                if element.name().as_ref() == b"dict" {
+                    depth += 1;
+                }
+                text.clear();
+            }
+            Event::Text(value) => text.push_str(&value.decode().unwrap_or_default()),
+            Event::End(element) => match element.name().as_ref() {
+                b"dict" => depth -= 1,
+                b"key" if depth == 1 => color = text.trim().to_string(),
+                b"key" if depth == 2 => component = text.trim().to_string(),
+                b"real" | b"integer" if depth == 2 => {
+                    let index = match component.as_str() {
+                        "Red Component" => 0,
+                        "Green Component" => 1,
+                        "Blue Component" => 2,
+                        _ => continue,
+                    };
+                    let value: f64 = text
+                        .trim()
+                        .parse()
+                        .with_context(|| format!("Invalid {} of {}", component, color))?;
+                    colors.entry(color.clone()).or_default()[index] = value;
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let hex = |key: &str| -> Result<String> {
+        let [r, g, b] = colors
+            .get(key)
+            .with_context(|| format!("iTerm2 color file has no {}", key))?;
+        let channel = |c: f64| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Ok(super::format_color((channel(*r), channel(*g), channel(*b))))
+    };
+    let ansi: Vec<String> = (0..16)
+        .map(|i| hex(&format!("Ansi {} Color", i)))
+        .collect::<Result<_>>()?;
+    let ansi: Vec<&str> = ansi.iter().map(String::as_str).collect();
+    let mut palette = builtin::from_ansi(
+        &hex("Foreground Color")?,
+        &hex("Background Color")?,
+        ansi.as_slice().try_into()?,
+    );
+    palette.cursor = hex("Cursor Color").ok();
+    palette.cursor_accent = hex("Cursor Text Color").ok();
+    palette.selection_background = hex("Selection Color").ok();
+    Ok(Theme {
+        name: name.to_string(),
+        palette,
+    })
+}
+
+/// A Windows Terminal scheme, which calls magenta purple
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Scheme {
+    name: String,
+    foreground: String,
+    background: String,
+    cursor_color: Option<String>,
+    selection_background: Option<String>,
+    black: String,
+    red: String,
+    green: String,
+    yellow: String,
+    blue: String,
+    purple: String,
+    cyan: String,
+    white: String,
+    bright_black: String,
+    bright_red: String,
+    bright_green: String,
+    bright_yellow: String,
+    bright_blue: String,
+    bright_purple: String,
+    bright_cyan: String,
+    bright_white: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SchemeFile {
+    Settings { schemes: Vec<Scheme> },
+    Scheme(Box<Scheme>),
+}
+
+/// Schemes of Windows Terminal JSON
+pub fn windows_terminal(json: &str) -> Result<Vec<Theme>> {
+    let schemes = match serde_json::from_str(json).context("Invalid Windows Terminal scheme")? {
+        SchemeFile::Settings { schemes } => schemes,
+        SchemeFile::Scheme(scheme) => vec![*scheme],
+    };
+    if schemes.is_empty() {
+        bail!("No color schemes found");
+    }
+    Ok(schemes
+        .into_iter()
+        .map(|scheme| Theme {
+            name: scheme.name,
+            palette: Palette {
+                foreground: scheme.foreground,
+                background: scheme.background,
+                cursor: scheme.cursor_color,
+                cursor_accent: None,
+                selection_background: scheme.selection_background,
+                black: scheme.black,
+                red: scheme.red,
+                green: scheme.green,
+                yellow: scheme.yellow,
+                blue: scheme.blue,
+                magenta: scheme.purple,
+                cyan: scheme.cyan,
+                white: scheme.white,
+                bright_black: scheme.bright_black,
+                bright_red: scheme.bright_red,
+                bright_green: scheme.bright_green,
+                bright_yellow: scheme.bright_yellow,
+                bright_blue: scheme.bright_blue,
+                bright_magenta: scheme.bright_purple,
+                bright_cyan: scheme.bright_cyan,
+                bright_white: scheme.bright_white,
+            },
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iterm_color(key: &str, (r, g, b): (f64, f64, f64)) -> String {
+        format!(
+            "<key>{}</key><dict><key>Alpha Component</key><real>1</real>\
+             <key>Blue Component</key><real>{}</real>\
+             <key>Color Space</key><string>sRGB</string>\
+             <key>Green Component</key><real>{}</real>\
+             <key>Red Component</key><real>{}</real></dict>",
+            key, b, g, r
+        )
+    }
+
+    #[test]
+    fn test_imports_iterm_colors() {
+        let mut body = String::new();
+        for i in 0..16 {
+            body.push_str(&iterm_color(
+                &format!("Ansi {} Color", i),
+                (0.0, 0.0, i as f64 / 15.0),
+            ));
+        }
+        body.push_str(&iterm_color("Background Color", (0.0, 0.0, 0.0)));
+        body.push_str(&iterm_color("Foreground Color", (1.0, 1.0, 1.0)));
+        body.push_str(&iterm_color("Selection Color", (0.2, 0.2, 0.2)));
+        let xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<plist version=\"1.0\"><dict>{}</dict></plist>",
+            body
+        );
+
+        let theme = iterm("Test", &xml).unwrap();
+        assert_eq!(theme.palette.black, "#000000");
+        assert_eq!(theme.palette.bright_white, "#0000ff");
+        assert_eq!(theme.palette.foreground, "#ffffff");
+        assert_eq!(
+            theme.palette.selection_background.as_deref(),
+            Some("#333333")
+        );
+        assert_eq!(theme.palette.cursor, None);
+
+        let missing = xml.replace("Ansi 3 Color", "Ansi 3 Colour");
+        assert!(iterm("Test", &missing).is_err());
+    }
+
+    #[test]
+    fn test_imports_windows_terminal_schemes() {
+        let scheme = r##"{
+            "name": "Campbell", "foreground": "#CCCCCC", "background": "#0C0C0C",
+            "cursorColor": "#FFFFFF", "selectionBackground": "#FFFFFF",
+            "black": "#0C0C0C", "red": "#C50F1F", "green": "#13A10E", "yellow": "#C19C00",
+            "blue": "#0037DA", "purple": "#881798", "cyan": "#3A96DD", "white": "#CCCCCC",
+            "brightBlack": "#767676", "brightRed": "#E74856", "brightGreen": "#16C60C",
+            "brightYellow": "#F9F1A5", "brightBlue": "#3B78FF", "brightPurple": "#B4009E",
+            "brightCyan": "#61D6D6", "brightWhite": "#F2F2F2"
+        }"##;
+        let themes = windows_terminal(scheme).unwrap();
+        assert_eq!(themes[0].name, "Campbell");
+        assert_eq!(themes[0].palette.magenta, "#881798");
+
+        let settings = format!(
+            r#"{{"profiles": {{}}, "schemes": [{}, {}]}}"#,
+            scheme, scheme
+        );
+        assert_eq!(windows_terminal(&settings).unwrap().len(), 2);
+        assert!(windows_terminal(r#"{"schemes": []}"#).is_err());
+    }
+}
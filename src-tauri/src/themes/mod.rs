@@ -0,0 +1,428 @@
+//! Terminal color schemes for xterm.js
+//!
+//! `terminal.theme` names either a built-in scheme or a user theme in
+//! ~/.zeami/themes/<file>.toml; user themes replace built-ins of the same
+//! name. iTerm2 `.itermcolors` and Windows Terminal scheme JSON files are
+//! converted into that format by import. Themes whose text is hard to read
+//! against their background still load, with a warning.
+
+mod builtin;
+pub mod import;
+
+use crate::config::atomic::write_atomic;
+use crate::config::storage;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Contrast below this between text and background gets a warning (WCAG AA)
+const MIN_TEXT_CONTRAST: f64 = 4.5;
+/// Contrast below this between an ANSI color and the background gets a warning
+const MIN_COLOR_CONTRAST: f64 = 1.5;
+
+/// A terminal color scheme, as stored in ~/.zeami/themes/*.toml
+///
+/// ```toml
+/// name = "Solarized Dark"
+/// foreground = "#839496"
+/// background = "#002b36"
+/// black = "#073642"
+/// red = "#dc322f"
+/// # ... the other 14 ANSI colors, brightBlack to brightWhite
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    #[serde(flatten)]
+    pub palette: Palette,
+}
+
+/// Colors as `#rrggbb`, named like xterm.js's ITheme
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Palette {
+    pub foreground: String,
+    pub background: String,
+    /// The foreground when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// The background when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor_accent: Option<String>,
+    /// The foreground blended into the background when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection_background: Option<String>,
+    pub black: String,
+    pub red: String,
+    pub green: String,
+    pub yellow: String,
+    pub blue: String,
+    pub magenta: String,
+    pub cyan: String,
+    pub white: String,
+    pub bright_black: String,
+    pub bright_red: String,
+    pub bright_green: String,
+    pub bright_yellow: String,
+    pub bright_blue: String,
+    pub bright_magenta: String,
+    pub bright_cyan: String,
+    pub bright_white: String,
+}
+
+impl Palette {
+    /// The 16 ANSI colors, black to bright white
+    pub fn ansi(&self) -> [&str; 16] {
+        [
+            &self.black,
+            &self.red,
+            &self.green,
+            &self.yellow,
+            &self.blue,
+            &self.magenta,
+            &self.cyan,
+            &self.white,
+            &self.bright_black,
+            &self.bright_red,
+            &self.bright_green,
+            &self.bright_yellow,
+            &self.bright_blue,
+            &self.bright_magenta,
+            &self.bright_cyan,
+            &self.bright_white,
+        ]
+    }
+
+    /// The palette with every color set and normalized to lowercase `#rrggbb`
+    pub fn resolved(&self) -> Result<Palette> {
+        let foreground = parse_color(&self.foreground)?;
+        let background = parse_color(&self.background)?;
+        let or = |value: &Option<String>, fallback: Rgb| -> Result<Option<String>> {
+            Ok(Some(match value {
+                Some(value) => format_color(parse_color(value)?),
+                None => format_color(fallback),
+            }))
+        };
+        let ansi = |value: &str| parse_color(value).map(format_color);
+        Ok(Palette {
+            cursor: or(&self.cursor, foreground)?,
+            cursor_accent: or(&self.cursor_accent, background)?,
+            selection_background: or(
+                &self.selection_background,
+                blend(foreground, background, 0.3),
+            )?,
+            foreground: format_color(foreground),
+            background: format_color(background),
+            black: ansi(&self.black)?,
+            red: ansi(&self.red)?,
+            green: ansi(&self.green)?,
+            yellow: ansi(&self.yellow)?,
+            blue: ansi(&self.blue)?,
+            magenta: ansi(&self.magenta)?,
+            cyan: ansi(&self.cyan)?,
+            white: ansi(&self.white)?,
+            bright_black: ansi(&self.bright_black)?,
+            bright_red: ansi(&self.bright_red)?,
+            bright_green: ansi(&self.bright_green)?,
+            bright_yellow: ansi(&self.bright_yellow)?,
+            bright_blue: ansi(&self.bright_blue)?,
+            bright_magenta: ansi(&self.bright_magenta)?,
+            bright_cyan: ansi(&self.bright_cyan)?,
+            bright_white: ansi(&self.bright_white)?,
+        })
+    }
+}
+
+/// A theme as listed for the settings screen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThemeInfo {
+    pub name: String,
+    pub builtin: bool,
+    /// User theme file; None for built-ins
+    pub path: Option<PathBuf>,
+    /// Readability problems found by contrast checks
+    pub warnings: Vec<String>,
+}
+
+/// ~/.zeami/themes
+pub fn themes_dir() -> Result<PathBuf> {
+    Ok(storage::config_dir()?.join("themes"))
+}
+
+/// Built-in and user themes by name; unreadable user theme files are skipped
+pub fn list(dir: &Path) -> Vec<ThemeInfo> {
+    let mut themes: BTreeMap<String, ThemeInfo> = builtin::themes()
+        .into_iter()
+        .map(|theme| {
+            let info = ThemeInfo {
+                warnings: warnings(&theme.palette),
+                name: theme.name.clone(),
+                builtin: true,
+                path: None,
+            };
+            (theme.name, info)
+        })
+        .collect();
+    for (path, theme) in user_themes(dir) {
+        themes.insert(
+            theme.name.clone(),
+            ThemeInfo {
+                warnings: warnings(&theme.palette),
+                name: theme.name,
+                builtin: false,
+                path: Some(path),
+            },
+        );
+    }
+    themes.into_values().collect()
+}
+
+/// The complete palette of theme `name`
+pub fn palette(dir: &Path, name: &str) -> Result<Palette> {
+    let theme = user_themes(dir)
+        .into_iter()
+        .map(|(_, theme)| theme)
+        .find(|theme| theme.name == name)
+        .or_else(|| builtin::themes().into_iter().find(|t| t.name == name))
+        .with_context(|| format!("Unknown theme '{}'", name))?;
+    theme
+        .palette
+        .resolved()
+        .with_context(|| format!("Invalid theme '{}'", name))
+}
+
+/// Parse and check a theme file's contents
+pub fn parse(text: &str) -> Result<Theme> {
+    let theme: Theme = toml::from_str(text)?;
+    if theme.name.trim().is_empty() {
+        bail!("Theme name must not be empty");
+    }
+    theme.palette.resolved()?;
+    Ok(theme)
+}
+
+/// Write `theme` to `dir` as <name>.toml, replacing a theme of the same name
+pub fn save(dir: &Path, theme: &Theme) -> Result<PathBuf> {
+    let resolved = theme.palette.resolved()?;
+    let file_name: String = theme
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let path = dir.join(format!("{}.toml", file_name.trim_matches('-')));
+    let theme = Theme {
+        name: theme.name.clone(),
+        palette: resolved,
+    };
+    write_atomic(&path, toml::to_string_pretty(&theme)?.as_bytes())?;
+    Ok(path)
+}
+
+/// Readability problems of `palette`; empty when it is fine or unparsable
+pub fn warnings(palette: &Palette) -> Vec<String> {
+    let Ok(resolved) = palette.resolved() else {
+        return vec!["Contains invalid colors".to_string()];
+    };
+    let Ok(background) = parse_color(&resolved.background) else {
+        return Vec::new();
+    };
+    let mut warnings = Vec::new();
+    if let Ok(foreground) = parse_color(&resolved.foreground) {
+        let ratio = contrast(foreground, background);
+        if ratio < MIN_TEXT_CONTRAST {
+            warnings.push(format!(
+                "Text contrast is {:.1}:1; at least {}:1 is recommended",
+                ratio, MIN_TEXT_CONTRAST
+            ));
+        }
+    }
+    const NAMES: [&str; 16] = [
+        "black",
+        "red",
+        "green",
+        "yellow",
+        "blue",
+        "magenta",
+        "cyan",
+        "white",
+        "brightBlack",
+        "brightRed",
+        "brightGreen",
+        "brightYellow",
+        "brightBlue",
+        "brightMagenta",
+        "brightCyan",
+        "brightWhite",
+    ];
+    // Black on dark and white on light backgrounds are expected to blend in
+    let dark = luminance(background) < 0.5;
+    let blends = if dark {
+        ["black", "brightBlack"]
+    } else {
+        ["white", "brightWhite"]
+    };
+    for (name, color) in NAMES.iter().zip(resolved.ansi()) {
+        if blends.contains(name) {
+            continue;
+        }
+        if let Ok(color) = parse_color(color) {
+            let ratio = contrast(color, background);
+            if ratio < MIN_COLOR_CONTRAST {
+                warnings.push(format!(
+                    "{} is nearly invisible on the background ({:.1}:1)",
+                    name, ratio
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+/// User theme files of `dir` that parse; the others are logged and skipped
+fn user_themes(dir: &Path) -> Vec<(PathBuf, Theme)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|text| parse(&text));
+            match parsed {
+                Ok(theme) => Some((path, theme)),
+                Err(e) => {
+                    tracing::warn!(?path, "Skipping invalid theme: {:#}", e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+type Rgb = (u8, u8, u8);
+
+/// `#rrggbb` or `#rgb`
+fn parse_color(value: &str) -> Result<Rgb> {
+    let hex = value
+        .strip_prefix('#')
+        .with_context(|| format!("Color {:?} must start with #", value))?;
+    let digits: Vec<u8> = hex
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<_>>()
+        .with_context(|| format!("Invalid color {:?}", value))?;
+    match digits[..] {
+        [r, g, b] => Ok((r * 17, g * 17, b * 17)),
+        [r1, r2, g1, g2, b1, b2] => Ok((r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2)),
+        _ => bail!("Invalid color {:?}; expected #rrggbb", value),
+    }
+}
+
+fn format_color((r, g, b): Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// `amount` of `top` over `bottom`
+fn blend(top: Rgb, bottom: Rgb, amount: f64) -> Rgb {
+    let mix = |a: u8, b: u8| (a as f64 * amount + b as f64 * (1.0 - amount)).round() as u8;
+    (
+        mix(top.0, bottom.0),
+        mix(top.1, bottom.1),
+        mix(top.2, bottom.2),
+    )
+}
+
+/// WCAG relative luminance
+fn luminance((r, g, b): Rgb) -> f64 {
+    let channel = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b)
+}
+
+/// WCAG contrast ratio, 1 to 21
+fn contrast(a: Rgb, b: Rgb) -> f64 {
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_themes_are_valid_and_readable() {
+        let dir = tempfile::tempdir().unwrap();
+        let themes = list(dir.path());
+        assert!(themes.iter().any(|theme| theme.name == "default"));
+        for theme in &themes {
+            assert!(
+                theme.warnings.is_empty(),
+                "{}: {:?}",
+                theme.name,
+                theme.warnings
+            );
+        }
+        let palette = palette(dir.path(), "default").unwrap();
+        assert_eq!(palette.cursor.as_ref(), Some(&palette.foreground));
+        assert!(palette.selection_background.is_some());
+    }
+
+    #[test]
+    fn test_user_themes_replace_builtins() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut theme = builtin::themes().remove(0);
+        theme.palette.foreground = "#FFF".to_string();
+        save(dir.path(), &theme).unwrap();
+        std::fs::write(dir.path().join("broken.toml"), "name = 1").unwrap();
+
+        let themes = list(dir.path());
+        let default = themes.iter().find(|t| t.name == theme.name).unwrap();
+        assert!(!default.builtin);
+        assert_eq!(
+            palette(dir.path(), &theme.name).unwrap().foreground,
+            "#ffffff"
+        );
+        assert!(palette(dir.path(), "missing").is_err());
+    }
+
+    #[test]
+    fn test_warns_about_low_contrast() {
+        let mut palette = builtin::themes().remove(0).palette;
+        palette.foreground = "#333333".to_string();
+        palette.blue = palette.background.clone();
+        let warnings = warnings(&palette);
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings[0].starts_with("Text contrast"));
+        assert!(warnings[1].starts_with("blue"));
+    }
+
+    #[test]
+    fn test_parses_colors() {
+        assert_eq!(parse_color("#0a0B0c").unwrap(), (10, 11, 12));
+        assert_eq!(parse_color("#fff").unwrap(), (255, 255, 255));
+        assert!(parse_color("0a0b0c").is_err());
+        assert!(parse_color("#0a0b0").is_err());
+        assert!(parse_color("#zzzzzz").is_err());
+        assert!((contrast((0, 0, 0), (255, 255, 255)) - 21.0).abs() < 0.01);
+    }
+}
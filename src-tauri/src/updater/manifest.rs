@@ -0,0 +1,210 @@
+//! Update manifests in the format of Tauri's updater
+//!
+//! Either a static file listing every platform:
+//!
+//! ```json
+//! {
+//!   "version": "1.2.0",
+//!   "notes": "Changelog",
+//!   "pub_date": "2024-05-01T12:00:00Z",
+//!   "platforms": {
+//!     "darwin-aarch64": { "url": "https://...", "signature": "..." }
+//!   }
+//! }
+//! ```
+//!
+//! or a dynamic response with `url` and `signature` at the top level. `size`
+//! and `sha256` may be added next to a url; when `sha256` is present the
+//! download is checked against it.
+
+use crate::config::UpdateChannel;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    pub version: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub pub_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub platforms: HashMap<String, Asset>,
+    #[serde(flatten)]
+    pub asset: Option<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Asset {
+    pub url: String,
+    #[serde(default)]
+    pub signature: Option<String>,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+/// An update newer than the running version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub channel: UpdateChannel,
+    /// Release notes
+    pub changelog: Option<String>,
+    pub pub_date: Option<DateTime<Utc>>,
+    pub url: String,
+    /// Download size in bytes, when the manifest or server reports it
+    pub size: Option<u64>,
+    pub signature: Option<String>,
+    pub sha256: Option<String>,
+    /// Pre-release, only offered on the beta channel
+    pub prerelease: bool,
+}
+
+/// `os-arch` key of the running platform in a manifest's `platforms`
+pub fn platform_key() -> String {
+    format!("{}-{}", target(), std::env::consts::ARCH)
+}
+
+/// Tauri's name of the running OS
+pub fn target() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    }
+}
+
+/// The manifest URL of `channel`
+pub fn endpoint(template: &str, channel: UpdateChannel, current_version: &str) -> String {
+    template
+        .replace("{{channel}}", channel.as_str())
+        .replace("{{target}}", target())
+        .replace("{{arch}}", std::env::consts::ARCH)
+        .replace("{{current_version}}", current_version)
+}
+
+/// The update `manifest` offers over `current_version` on `channel`, if any
+///
+/// The stable channel never offers pre-releases, so pointing it at a beta
+/// manifest by mistake does not move users onto betas.
+pub fn update(
+    manifest: Manifest,
+    platform: &str,
+    channel: UpdateChannel,
+    current_version: &str,
+) -> Result<Option<UpdateInfo>> {
+    let version = parse_version(&manifest.version)?;
+    let current = parse_version(current_version)?;
+    if version <= current {
+        return Ok(None);
+    }
+    let prerelease = !version.pre.is_empty();
+    if prerelease && channel == UpdateChannel::Stable {
+        return Ok(None);
+    }
+    let asset = match manifest.platforms.get(platform) {
+        Some(asset) => asset.clone(),
+        None => manifest
+            .asset
+            .with_context(|| format!("The update has no download for {}", platform))?,
+    };
+    Ok(Some(UpdateInfo {
+        version: version.to_string(),
+        current_version: current.to_string(),
+        channel,
+        changelog: manifest.notes.filter(|notes| !notes.trim().is_empty()),
+        pub_date: manifest.pub_date,
+        url: asset.url,
+        size: asset.size,
+        signature: asset.signature,
+        sha256: asset.sha256,
+        prerelease,
+    }))
+}
+
+/// Semver with an optional leading `v`
+fn parse_version(version: &str) -> Result<Version> {
+    let trimmed = version.trim();
+    Version::parse(trimmed.strip_prefix('v').unwrap_or(trimmed))
+        .with_context(|| format!("Invalid version {:?}", version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(version: &str) -> Manifest {
+        serde_json::from_value(serde_json::json!({
+            "version": version,
+            "notes": "Faster terminals",
+            "pub_date": "2024-05-01T12:00:00Z",
+            "platforms": {
+                "linux-x86_64": { "url": "https://example.com/zeami.AppImage.tar.gz", "size": 42 },
+                "darwin-aarch64": { "url": "https://example.com/zeami.app.tar.gz", "signature": "sig" }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_offers_newer_versions_for_the_platform() {
+        let found = update_for("v1.2.0", "1.1.9", UpdateChannel::Stable).unwrap();
+        assert_eq!(found.version, "1.2.0");
+        assert_eq!(found.size, Some(42));
+        assert_eq!(found.changelog.as_deref(), Some("Faster terminals"));
+        assert!(!found.prerelease);
+
+        let current = update_for("1.2.0", "1.2.0", UpdateChannel::Beta);
+        assert_eq!(current, None);
+        assert!(update(
+            manifest("1.3.0"),
+            "windows-x86_64",
+            UpdateChannel::Stable,
+            "1.0.0"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_stable_channel_skips_prereleases() {
+        assert_eq!(
+            update_for("1.3.0-beta.1", "1.2.0", UpdateChannel::Stable),
+            None
+        );
+        let beta = update_for("1.3.0-beta.1", "1.2.0", UpdateChannel::Beta).unwrap();
+        assert!(beta.prerelease);
+        // A beta user moves to the release of the same version
+        assert!(update_for("1.3.0", "1.3.0-beta.1", UpdateChannel::Beta).is_some());
+    }
+
+    #[test]
+    fn test_reads_dynamic_manifests() {
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "version": "2.0.0",
+            "url": "https://example.com/zeami.msi.zip",
+            "signature": "sig"
+        }))
+        .unwrap();
+        let found = update(manifest, "windows-x86_64", UpdateChannel::Stable, "1.0.0")
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.url, "https://example.com/zeami.msi.zip");
+        assert_eq!(
+            endpoint(
+                "https://u.example.com/{{channel}}/{{current_version}}",
+                UpdateChannel::Beta,
+                "1.0.0"
+            ),
+            "https://u.example.com/beta/1.0.0"
+        );
+    }
+
+    fn update_for(version: &str, current: &str, channel: UpdateChannel) -> Option<UpdateInfo> {
+        update(manifest(version), "linux-x86_64", channel, current).unwrap()
+    }
+}
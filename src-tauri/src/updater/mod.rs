@@ -0,0 +1,332 @@
+//! App updates from the stable or beta channel
+//!
+//! The manifest of the channel chosen in UpdateSettings is fetched in the
+//! background every check_interval_hours, or on demand by check_for_updates.
+//! A newer release is downloaded into ~/.zeami/updates (right away with
+//! auto_download, otherwise by download_update) and announced with an
+//! `update-downloaded` event so the frontend can offer to install it. Deferring
+//! an update pauses background checks until the deferral ends.
+
+pub mod manifest;
+
+use crate::config::atomic::write_atomic;
+use crate::config::{storage, SettingsState, UpdateChannel, UpdateSettings};
+use crate::events::schema::v1;
+use crate::events::UPDATE_DOWNLOADED_EVENT_NAME;
+use crate::scheduler::{Job, JobFuture};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use manifest::{Manifest, UpdateInfo};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Scheduler name of the background update check
+pub const UPDATE_CHECK_JOB: &str = "update_check";
+
+/// Shortest time between background checks, whatever check_interval_hours says
+const MIN_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// An update downloaded and ready to install
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownloadedUpdate {
+    pub version: String,
+    pub channel: UpdateChannel,
+    pub path: PathBuf,
+    pub size: u64,
+    pub changelog: Option<String>,
+    /// Minisign signature from the manifest, for the installer to verify
+    pub signature: Option<String>,
+}
+
+/// Result of check_for_updates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCheck {
+    pub current_version: String,
+    pub channel: UpdateChannel,
+    /// None when the running version is the latest of the channel
+    pub update: Option<UpdateInfo>,
+    /// Background checks are paused until then
+    pub deferred_until: Option<DateTime<Utc>>,
+    pub downloaded: Option<DownloadedUpdate>,
+}
+
+/// Kept in ~/.zeami/updates/updater.json
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct UpdaterRecord {
+    deferred_until: Option<DateTime<Utc>>,
+    downloaded: Option<DownloadedUpdate>,
+}
+
+/// Update checks and downloads; managed by Tauri and run by the scheduler
+#[derive(Clone)]
+pub struct Updater {
+    http: reqwest::Client,
+    dir: PathBuf,
+    record: Arc<Mutex<UpdaterRecord>>,
+    /// Held while downloading so concurrent requests do not fetch twice
+    download_lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl Updater {
+    /// Updater keeping its downloads and state in ~/.zeami/updates
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::open(&storage::config_dir()?.join("updates")))
+    }
+
+    pub fn open(dir: &Path) -> Self {
+        let record = std::fs::read_to_string(dir.join("updater.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self {
+            http: reqwest::Client::new(),
+            dir: dir.to_path_buf(),
+            record: Arc::new(Mutex::new(record)),
+            download_lock: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// End of the current deferral, if one is in effect
+    pub fn deferred_until(&self) -> Option<DateTime<Utc>> {
+        self.record
+            .lock()
+            .unwrap()
+            .deferred_until
+            .filter(|until| *until > Utc::now())
+    }
+
+    /// Pause background checks for `hours`; 0 lifts a deferral
+    pub fn defer(&self, hours: u64) -> Result<Option<DateTime<Utc>>> {
+        let until = (hours > 0).then(|| Utc::now() + chrono::Duration::hours(hours as i64));
+        self.update_record(|record| record.deferred_until = until)?;
+        tracing::info!(?until, "Deferred updates");
+        Ok(until)
+    }
+
+    /// Ask the `channel` manifest for a release newer than `current_version`
+    pub async fn check(
+        &self,
+        settings: &UpdateSettings,
+        channel: UpdateChannel,
+        current_version: &str,
+    ) -> Result<UpdateCheck> {
+        let url = manifest::endpoint(&settings.endpoint, channel, current_version);
+        let response = self
+            .http
+            .get(&url)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch the update manifest {}", url))?;
+        // Dynamic update servers answer 204 when there is nothing newer
+        let mut update = if response.status() == reqwest::StatusCode::NO_CONTENT {
+            None
+        } else {
+            let manifest: Manifest = response
+                .error_for_status()?
+                .json()
+                .await
+                .context("Invalid update manifest")?;
+            manifest::update(
+                manifest,
+                &manifest::platform_key(),
+                channel,
+                current_version,
+            )?
+        };
+        if let Some(update) = update.as_mut().filter(|update| update.size.is_none()) {
+            update.size = self.content_length(&update.url).await;
+        }
+
+        let offered = update.as_ref().map(|update| update.version.as_str());
+        let downloaded = self.record.lock().unwrap().downloaded.clone();
+        let downloaded = match downloaded {
+            Some(downloaded) if Some(downloaded.version.as_str()) == offered => Some(downloaded),
+            Some(stale) => {
+                self.discard(&stale)?;
+                None
+            }
+            None => None,
+        };
+        Ok(UpdateCheck {
+            current_version: current_version.to_string(),
+            channel,
+            update,
+            deferred_until: self.deferred_until(),
+            downloaded,
+        })
+    }
+
+    /// Download `update` unless it already is, then emit `update-downloaded`
+    pub async fn download(&self, app: &AppHandle, update: &UpdateInfo) -> Result<DownloadedUpdate> {
+        let _guard = self.download_lock.lock().await;
+        let existing = self.record.lock().unwrap().downloaded.clone();
+        if let Some(existing) = existing {
+            if existing.version == update.version && existing.path.is_file() {
+                return Ok(existing);
+            }
+            self.discard(&existing)?;
+        }
+
+        let file_name = update
+            .url
+            .rsplit('/')
+            .next()
+            .and_then(|name| name.split(['?', '#']).next())
+            .filter(|name| !name.is_empty() && !name.contains(".."))
+            .unwrap_or("update")
+            .to_string();
+        let dir = self.dir.join(&update.version);
+        std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        let path = dir.join(&file_name);
+        let partial = dir.join(format!("{}.part", file_name));
+
+        let response = self
+            .http
+            .get(&update.url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to download {}", update.url))?;
+        let mut file = std::fs::File::create(&partial)?;
+        let mut hasher = Sha256::new();
+        let mut size = 0u64;
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.context("Update download interrupted")?;
+            hasher.update(&chunk);
+            file.write_all(&chunk)?;
+            size += chunk.len() as u64;
+        }
+        file.sync_all()?;
+        drop(file);
+
+        if let Some(expected) = &update.sha256 {
+            let actual = format!("{:x}", hasher.finalize());
+            if !actual.eq_ignore_ascii_case(expected.trim()) {
+                let _ = std::fs::remove_file(&partial);
+                bail!(
+                    "Update download is corrupt: sha256 {} != {}",
+                    actual,
+                    expected
+                );
+            }
+        }
+        std::fs::rename(&partial, &path)?;
+
+        let downloaded = DownloadedUpdate {
+            version: update.version.clone(),
+            channel: update.channel,
+            path,
+            size,
+            changelog: update.changelog.clone(),
+            signature: update.signature.clone(),
+        };
+        self.update_record(|record| record.downloaded = Some(downloaded.clone()))?;
+        tracing::info!(version = %downloaded.version, size, "Downloaded update");
+
+        let payload: v1::UpdateDownloaded = downloaded.clone();
+        if let Err(e) = app.emit_all(UPDATE_DOWNLOADED_EVENT_NAME, payload) {
+            tracing::error!("Failed to emit update download: {}", e);
+        }
+        Ok(downloaded)
+    }
+
+    /// Size the server reports for `url`, when it reports one
+    async fn content_length(&self, url: &str) -> Option<u64> {
+        let response = self.http.head(url).send().await.ok()?;
+        response
+            .error_for_status()
+            .ok()?
+            .headers()
+            .get(reqwest::header::CONTENT_LENGTH)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    /// Remove a download that is no longer offered
+    fn discard(&self, downloaded: &DownloadedUpdate) -> Result<()> {
+        if let Some(dir) = downloaded
+            .path
+            .parent()
+            .filter(|dir| dir.starts_with(&self.dir))
+        {
+            if let Err(e) = std::fs::remove_dir_all(dir) {
+                tracing::warn!(?dir, "Failed to remove old update: {}", e);
+            }
+        }
+        self.update_record(|record| record.downloaded = None)
+    }
+
+    fn update_record(&self, change: impl FnOnce(&mut UpdaterRecord)) -> Result<()> {
+        let mut record = self.record.lock().unwrap();
+        change(&mut record);
+        write_atomic(
+            &self.dir.join("updater.json"),
+            serde_json::to_string_pretty(&*record)?.as_bytes(),
+        )
+    }
+}
+
+/// Version of the running app
+pub fn current_version(app: &AppHandle) -> String {
+    app.package_info().version.to_string()
+}
+
+impl Job for Updater {
+    fn interval(&self, app: &AppHandle) -> Option<Duration> {
+        let settings = app.state::<SettingsState>().current().updates;
+        settings
+            .auto_check
+            .then(|| Duration::from_secs(settings.check_interval_hours * 60 * 60).max(MIN_INTERVAL))
+    }
+
+    fn run(&self, app: &AppHandle) -> JobFuture {
+        let (app, updater) = (app.clone(), self.clone());
+        Box::pin(async move {
+            if let Some(until) = updater.deferred_until() {
+                tracing::debug!(%until, "Update check deferred");
+                return Ok(());
+            }
+            let settings = app.state::<SettingsState>().current().updates;
+            let check = updater
+                .check(&settings, settings.channel, &current_version(&app))
+                .await?;
+            if let Some(update) = check.update {
+                tracing::info!(version = %update.version, "Update available");
+                if settings.auto_download && check.downloaded.is_none() {
+                    updater.download(&app, &update).await?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deferral_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let updater = Updater::open(dir.path());
+        assert_eq!(updater.deferred_until(), None);
+        let until = updater.defer(24).unwrap().unwrap();
+        assert!(until > Utc::now() + chrono::Duration::hours(23));
+
+        let reopened = Updater::open(dir.path());
+        assert_eq!(reopened.deferred_until(), Some(until));
+        reopened.defer(0).unwrap();
+        assert_eq!(Updater::open(dir.path()).deferred_until(), None);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::capabilities::{Capability, CapabilityRegistry};
+use crate::config::{storage, SettingsState, WorkflowSettings};
+use crate::git::snapshot;
+use crate::scheduler::{Job, JobFuture};
+use anyhow::Context;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Scheduler name of the auto-commit job
+pub const AUTO_COMMIT_JOB: &str = "auto_commit";
+
+/// Shortest allowed interval between WIP commits
+const MIN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Periodic WIP commits of the active repository
+///
+/// The frontend selects the repository with `watch_auto_commit`; while
+/// WorkflowSettings::auto_commit is on, the scheduler snapshots the working
+/// tree every auto_commit_interval seconds (see git::snapshot).
+#[derive(Clone, Default)]
+pub struct AutoCommitter {
+    repo_path: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl AutoCommitter {
+    /// Snapshot the repository at `repo_path`, or stop with None
+    pub fn watch(&self, repo_path: Option<PathBuf>) {
+        *self.repo_path.lock().unwrap() = repo_path;
+    }
+
+    fn settings(&self, app: &AppHandle) -> Option<(PathBuf, WorkflowSettings)> {
+        let path = self.repo_path.lock().unwrap().clone()?;
+        let global = app.state::<SettingsState>().current();
+        match storage::settings_for_project(&global, &path) {
+            Ok(settings) if settings.workflow.auto_commit => Some((path, settings.workflow)),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!(path = ?path, "Failed to load project settings: {:#}", e);
+                None
+            }
+        }
+    }
+}
+
+impl Job for AutoCommitter {
+    fn interval(&self, app: &AppHandle) -> Option<Duration> {
+        let (_, workflow) = self.settings(app)?;
+        Some(Duration::from_secs(workflow.auto_commit_interval).max(MIN_INTERVAL))
+    }
+
+    fn run(&self, app: &AppHandle) -> JobFuture {
+        let settings = self.settings(app);
+        let granted = settings.as_ref().is_some_and(|(path, _)| {
+            app.state::<CapabilityRegistry>()
+                .is_granted(path, Capability::Commit)
+        });
+        Box::pin(async move {
+            let Some((path, workflow)) = settings else {
+                return Ok(());
+            };
+            if !granted {
+                tracing::debug!(path = ?path, "Auto-commit skipped: commit is not granted");
+                return Ok(());
+            }
+            let root = path.clone();
+            let commit = tauri::async_runtime::spawn_blocking(move || {
+                snapshot::auto_commit(&root, &workflow)
+            })
+            .await?
+            .with_context(|| format!("Auto-commit of {:?} failed", path))?;
+            if let Some(commit) = commit {
+                tracing::info!(path = ?path, id = %commit.id, "Created WIP commit");
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,100 @@
+//! Project builds with `workflow.build_command`
+//!
+//! A build runs on demand through run_build, or after a passing run_tests when
+//! `workflow.auto_build` is on. Its start and result are emitted as
+//! `build-status`, the result with the parsed diagnostics and artifact sizes.
+
+use super::build_report::{self, BuildStatus};
+use crate::config::WorkflowSettings;
+use crate::events::schema::v1;
+use crate::events::BUILD_STATUS_EVENT_NAME;
+use crate::pty::command::{self, CommandOptions};
+use anyhow::{bail, Result};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Longest a build may run before it is killed
+const BUILD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Runs builds, at most one per project at a time; managed as Tauri state
+#[derive(Default)]
+pub struct Builder {
+    running: Mutex<HashSet<PathBuf>>,
+}
+
+impl Builder {
+    /// Build the project and wait for the result
+    ///
+    /// Fails without building when a build of the project is already running.
+    pub async fn run(
+        &self,
+        app: &AppHandle,
+        project_root: PathBuf,
+        workflow: WorkflowSettings,
+    ) -> Result<BuildStatus> {
+        {
+            let mut running = self
+                .running
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock builder: {}", e))?;
+            if !running.insert(project_root.clone()) {
+                bail!("A build of {:?} is already running", project_root);
+            }
+        }
+
+        let status = BuildStatus::running(project_root.clone(), &workflow.build_command);
+        publish(app, status.clone());
+        let result = {
+            let status = status.clone();
+            tauri::async_runtime::spawn_blocking(move || build(status, &workflow)).await
+        };
+        let status = match result {
+            Ok(status) => status,
+            Err(e) => status.failed(e.to_string()),
+        };
+
+        if let Ok(mut running) = self.running.lock() {
+            running.remove(&project_root);
+        }
+        tracing::info!(
+            path = ?project_root,
+            state = ?status.state,
+            warnings = status.warnings,
+            errors = status.errors,
+            "Build finished"
+        );
+        publish(app, status.clone());
+        Ok(status)
+    }
+}
+
+fn build(status: BuildStatus, workflow: &WorkflowSettings) -> BuildStatus {
+    let root = status.project_root.clone();
+    let output = command::run_command_with(
+        &workflow.build_command,
+        &root,
+        BUILD_TIMEOUT,
+        &CommandOptions::default(),
+    );
+    match output {
+        Ok(output) => {
+            let artifacts = build_report::collect_artifacts(&root, &workflow.build_artifacts)
+                .unwrap_or_else(|e| {
+                    tracing::warn!(path = ?root, "Failed to collect build artifacts: {:#}", e);
+                    Vec::new()
+                });
+            status.finished(&output, artifacts)
+        }
+        Err(e) => status.failed(format!("{:#}", e)),
+    }
+}
+
+fn publish(app: &AppHandle, status: BuildStatus) {
+    let payload: v1::BuildStatus = status;
+    if let Err(e) = app.emit_all(BUILD_STATUS_EVENT_NAME, payload) {
+        tracing::error!("Failed to emit build status: {}", e);
+    }
+}
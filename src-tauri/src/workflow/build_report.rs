@@ -0,0 +1,423 @@
+//! Results of a build_command run: state, compiler diagnostics and artifacts
+//!
+//! Diagnostics are read from the console output of rustc/cargo, tsc and tools
+//! using the `file:line:col: error: message` convention (gcc, clang). Artifacts
+//! are the files matching `workflow.build_artifacts` once the build has finished.
+
+use crate::pty::command::{self, CommandOutput};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use globset::{Glob, GlobSetBuilder};
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+
+/// Most diagnostics kept per build
+const MAX_DIAGNOSTICS: usize = 200;
+
+/// Most artifacts listed per build
+const MAX_ARTIFACTS: usize = 500;
+
+/// Output kept in a finished build's status, counted from the end
+const OUTPUT_TAIL_BYTES: usize = 32 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildState {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildDiagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Source location when the compiler reported one
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildArtifact {
+    /// Relative to the project root
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified_at: Option<DateTime<Utc>>,
+}
+
+/// A build of a project; also the payload of `build-status`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildStatus {
+    pub project_root: PathBuf,
+    pub command: String,
+    pub state: BuildState,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: Option<u64>,
+    pub exit_code: Option<u32>,
+    pub timed_out: bool,
+    pub warnings: u32,
+    pub errors: u32,
+    pub diagnostics: Vec<BuildDiagnostic>,
+    pub artifacts: Vec<BuildArtifact>,
+    /// End of the output once the build has finished
+    pub output: Option<String>,
+}
+
+impl BuildStatus {
+    pub fn running(project_root: PathBuf, command: &str) -> Self {
+        Self {
+            project_root,
+            command: command.to_string(),
+            state: BuildState::Running,
+            started_at: Utc::now(),
+            duration_ms: None,
+            exit_code: None,
+            timed_out: false,
+            warnings: 0,
+            errors: 0,
+            diagnostics: Vec::new(),
+            artifacts: Vec::new(),
+            output: None,
+        }
+    }
+
+    /// This status after build_command completed with `output`
+    pub fn finished(self, output: &CommandOutput, artifacts: Vec<BuildArtifact>) -> Self {
+        let mut diagnostics = parse_diagnostics(&output.output);
+        let count = |severity| {
+            diagnostics
+                .iter()
+                .filter(|d: &&BuildDiagnostic| d.severity == severity)
+                .count() as u32
+        };
+        let (warnings, errors) = (count(Severity::Warning), count(Severity::Error));
+        diagnostics.truncate(MAX_DIAGNOSTICS);
+
+        Self {
+            state: if output.exit_code == Some(0) {
+                BuildState::Succeeded
+            } else {
+                BuildState::Failed
+            },
+            duration_ms: Some(output.duration_ms),
+            exit_code: output.exit_code,
+            timed_out: output.timed_out,
+            warnings,
+            errors,
+            diagnostics,
+            artifacts,
+            output: Some(command::output_tail(&output.output, OUTPUT_TAIL_BYTES).to_string()),
+            ..self
+        }
+    }
+
+    /// This status when build_command could not be started
+    pub fn failed(self, error: String) -> Self {
+        Self {
+            state: BuildState::Failed,
+            output: Some(error),
+            ..self
+        }
+    }
+}
+
+/// Warnings and errors in compiler output, in the order they appear
+pub fn parse_diagnostics(output: &str) -> Vec<BuildDiagnostic> {
+    let mut diagnostics: Vec<BuildDiagnostic> = Vec::new();
+    // rustc prints the location on a `-->` line after the message
+    let mut awaiting_location: Option<usize> = None;
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(location) = trimmed.strip_prefix("--> ") {
+            if let Some(index) = awaiting_location.take() {
+                let (file, line, column) = split_location(location);
+                let diagnostic = &mut diagnostics[index];
+                (diagnostic.file, diagnostic.line, diagnostic.column) =
+                    (Some(file.to_string()), line, column);
+            }
+            continue;
+        }
+
+        if let Some(diagnostic) = parse_rustc(line) {
+            awaiting_location = Some(diagnostics.len());
+            diagnostics.push(diagnostic);
+        } else if let Some(diagnostic) = parse_tsc(line).or_else(|| parse_gnu(line)) {
+            awaiting_location = None;
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics
+}
+
+/// `warning: unused variable: `x`` or `error[E0308]: mismatched types`
+fn parse_rustc(line: &str) -> Option<BuildDiagnostic> {
+    let (severity, rest) = if let Some(rest) = line.strip_prefix("warning") {
+        (Severity::Warning, rest)
+    } else {
+        (Severity::Error, line.strip_prefix("error")?)
+    };
+    let rest = match rest.strip_prefix('[') {
+        Some(code) => code.split_once(']')?.1,
+        None => rest,
+    };
+    let message = rest.strip_prefix(": ")?.trim();
+    // Summary lines such as `warning: `app` (bin "app") generated 2 warnings`
+    // and `error: could not compile` repeat what was already reported
+    if message.contains(" generated ")
+        || message.starts_with("could not compile")
+        || message.starts_with("aborting due to")
+    {
+        return None;
+    }
+    Some(BuildDiagnostic {
+        severity,
+        message: message.to_string(),
+        file: None,
+        line: None,
+        column: None,
+    })
+}
+
+/// `src/app.ts(10,5): error TS2322: Type 'string' is not assignable`
+fn parse_tsc(line: &str) -> Option<BuildDiagnostic> {
+    let (location, rest) = line.split_once("): ")?;
+    let (file, position) = location.rsplit_once('(')?;
+    let (line_number, column) = position.split_once(',')?;
+    let (severity, message) = severity_prefix(rest)?;
+    Some(BuildDiagnostic {
+        severity,
+        message: message.to_string(),
+        file: Some(file.trim().to_string()),
+        line: Some(line_number.parse().ok()?),
+        column: Some(column.parse().ok()?),
+    })
+}
+
+/// `src/main.c:3:10: warning: implicit declaration of function`
+fn parse_gnu(line: &str) -> Option<BuildDiagnostic> {
+    let (index, marker, severity) = [
+        (": error: ", Severity::Error),
+        (": fatal error: ", Severity::Error),
+        (": warning: ", Severity::Warning),
+    ]
+    .into_iter()
+    .filter_map(|(marker, severity)| Some((line.find(marker)?, marker, severity)))
+    .min_by_key(|(index, _, _)| *index)?;
+    let (file, line_number, column) = split_location(line[..index].trim());
+    Some(BuildDiagnostic {
+        severity,
+        message: line[index + marker.len()..].trim().to_string(),
+        file: Some(file.to_string()),
+        line: Some(line_number?),
+        column,
+    })
+}
+
+/// Message after an `error TS1234: ` or `warning: ` prefix
+fn severity_prefix(text: &str) -> Option<(Severity, &str)> {
+    let (severity, rest) = if let Some(rest) = text.strip_prefix("error") {
+        (Severity::Error, rest)
+    } else {
+        (Severity::Warning, text.strip_prefix("warning")?)
+    };
+    let (_, message) = rest.split_once(": ")?;
+    Some((severity, message.trim()))
+}
+
+/// `file:line:column`, with line and column optional
+fn split_location(location: &str) -> (&str, Option<u32>, Option<u32>) {
+    let mut parts = location.rsplitn(3, ':');
+    let last = parts.next().unwrap_or_default();
+    let middle = parts.next();
+    let first = parts.next();
+    match (first, middle.map(str::parse::<u32>), last.parse::<u32>()) {
+        (Some(file), Some(Ok(line)), Ok(column)) => (file, Some(line), Some(column)),
+        (_, Some(_), Ok(line)) => {
+            let file = location.rsplit_once(':').map_or(location, |(file, _)| file);
+            (file, Some(line), None)
+        }
+        _ => (location, None, None),
+    }
+}
+
+/// Files under `project_root` matching `patterns`, sorted by path
+///
+/// Build output is usually git-ignored, so ignore files are not honored; the
+/// walk starts at the literal directory prefix of each pattern instead of the
+/// project root to keep it off node_modules and friends.
+pub fn collect_artifacts(project_root: &Path, patterns: &[String]) -> Result<Vec<BuildArtifact>> {
+    let mut globs = GlobSetBuilder::new();
+    let mut bases = BTreeSet::new();
+    for pattern in patterns {
+        globs
+            .add(Glob::new(pattern).with_context(|| format!("Invalid artifact glob {}", pattern))?);
+        bases.insert(literal_prefix(pattern));
+    }
+    let globs = globs.build()?;
+
+    let mut seen = BTreeSet::new();
+    let mut artifacts = Vec::new();
+    for base in bases {
+        let base = project_root.join(base);
+        if !base.exists() {
+            continue;
+        }
+        for entry in WalkBuilder::new(&base)
+            .standard_filters(false)
+            .build()
+            .flatten()
+        {
+            let Ok(relative) = entry.path().strip_prefix(project_root) else {
+                continue;
+            };
+            if !entry.file_type().is_some_and(|kind| kind.is_file())
+                || !globs.is_match(relative)
+                || !seen.insert(relative.to_path_buf())
+            {
+                continue;
+            }
+            if let Ok(metadata) = entry.metadata() {
+                artifacts.push(BuildArtifact {
+                    path: relative.to_path_buf(),
+                    size: metadata.len(),
+                    modified_at: metadata.modified().ok().map(DateTime::<Utc>::from),
+                });
+            }
+        }
+    }
+    artifacts.sort_by(|a, b| a.path.cmp(&b.path));
+    artifacts.truncate(MAX_ARTIFACTS);
+    Ok(artifacts)
+}
+
+/// Leading directories of a glob that contain no wildcards
+fn literal_prefix(pattern: &str) -> PathBuf {
+    let mut prefix = PathBuf::new();
+    let components: Vec<Component> = Path::new(pattern).components().collect();
+    // The last component names files, not a directory to start from
+    for component in components.iter().take(components.len().saturating_sub(1)) {
+        let text = component.as_os_str().to_string_lossy();
+        if text.contains(['*', '?', '[', '{']) {
+            break;
+        }
+        prefix.push(component);
+    }
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_rustc_diagnostics() {
+        let output = "\
+   Compiling app v0.1.0 (/work/app)
+warning: unused variable: `x`
+ --> src/main.rs:2:9
+  |
+2 |     let x = 1;
+  |         ^ help: if this is intentional, prefix it with an underscore: `_x`
+
+error[E0308]: mismatched types
+  --> src/lib.rs:10:5
+   |
+warning: `app` (bin \"app\") generated 1 warning
+error: could not compile `app` (bin \"app\") due to 1 previous error";
+        let diagnostics = parse_diagnostics(output);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].message, "unused variable: `x`");
+        assert_eq!(diagnostics[0].file.as_deref(), Some("src/main.rs"));
+        assert_eq!(
+            (diagnostics[0].line, diagnostics[0].column),
+            (Some(2), Some(9))
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Error);
+        assert_eq!(diagnostics[1].message, "mismatched types");
+        assert_eq!(diagnostics[1].line, Some(10));
+    }
+
+    #[test]
+    fn test_tsc_and_gnu_diagnostics() {
+        let output = "\
+src/app.ts(10,5): error TS2322: Type 'string' is not assignable to type 'number'.
+main.c:3:10: warning: implicit declaration of function 'foo'
+src/lib.c:7:2: error: use of undeclared identifier 'bar'
+vite v5.0.0 building for production...";
+        let diagnostics = parse_diagnostics(output);
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0].file.as_deref(), Some("src/app.ts"));
+        assert_eq!(
+            (diagnostics[0].line, diagnostics[0].column),
+            (Some(10), Some(5))
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "Type 'string' is not assignable to type 'number'."
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert_eq!(diagnostics[1].file.as_deref(), Some("main.c"));
+        assert_eq!(diagnostics[2].message, "use of undeclared identifier 'bar'");
+    }
+
+    #[test]
+    fn test_finished_status() {
+        let output = CommandOutput {
+            exit_code: Some(0),
+            output: "warning: unused import\n --> src/a.rs:1:5".to_string(),
+            timed_out: false,
+            cancelled: false,
+            duration_ms: 900,
+        };
+        let status =
+            BuildStatus::running(PathBuf::from("/p"), "cargo build").finished(&output, Vec::new());
+        assert_eq!(status.state, BuildState::Succeeded);
+        assert_eq!((status.warnings, status.errors), (1, 0));
+        assert_eq!(status.duration_ms, Some(900));
+    }
+
+    #[test]
+    fn test_collect_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("dist/assets")).unwrap();
+        fs::write(dir.path().join("dist/index.html"), "<html>").unwrap();
+        fs::write(dir.path().join("dist/assets/app.js"), "console.log(1)").unwrap();
+        fs::write(dir.path().join("dist/assets/app.js.map"), "{}").unwrap();
+        // Git-ignored output is still collected
+        fs::write(dir.path().join(".gitignore"), "dist\n").unwrap();
+
+        let patterns = vec![
+            "dist/**/*.js".to_string(),
+            "dist/*.html".to_string(),
+            "build/**/*".to_string(),
+        ];
+        let artifacts = collect_artifacts(dir.path(), &patterns).unwrap();
+        let paths: Vec<_> = artifacts.iter().map(|a| a.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("dist/assets/app.js"),
+                PathBuf::from("dist/index.html")
+            ]
+        );
+        assert_eq!(artifacts[0].size, 14);
+        assert_eq!(
+            literal_prefix("target/release/app"),
+            PathBuf::from("target/release")
+        );
+        assert_eq!(literal_prefix("**/*.wasm"), PathBuf::new());
+    }
+}
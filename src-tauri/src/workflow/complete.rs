@@ -0,0 +1,169 @@
+//! complete_issue: hand in the work on the issue linked to the checked-out branch
+//!
+//! The steps run in order and stop at the first failure: the Definition of
+//! Done must be met (see dod), the tests must pass, the branch is pushed and a
+//! pull request linking the issue is opened. Each step is reported as
+//! `workflow-step`.
+
+use super::dod;
+use super::steps::{blocking, Run};
+use super::test_runs::{self, TestRun};
+use crate::config::{storage, SettingsState};
+use crate::events::schema::v1::WorkflowStepKind;
+use crate::git::credentials::Credentials;
+use crate::git::{links, remote};
+use crate::github::pulls::{self, NewPullRequest, PullRequest};
+use crate::github::{self, GitHubState};
+use crate::i18n;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+const STEPS: [WorkflowStepKind; 4] = [
+    WorkflowStepKind::VerifyDod,
+    WorkflowStepKind::RunTests,
+    WorkflowStepKind::Push,
+    WorkflowStepKind::OpenPullRequest,
+];
+
+/// Options for complete_issue
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompleteOptions {
+    /// Pull request title; defaults to the issue title
+    pub title: Option<String>,
+    pub draft: bool,
+    /// Close the issue when the pull request merges; defaults to
+    /// GitHubSettings::auto_close_issue_on_pr
+    pub close_issue: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueCompletion {
+    pub issue: u64,
+    pub test_run: TestRun,
+    pub pull_request: PullRequest,
+    /// An open pull request from the branch already existed
+    pub existing_pull_request: bool,
+}
+
+/// Run the completion steps for the project at `repo_path`
+pub async fn complete_issue(
+    app: &AppHandle,
+    repo_path: &Path,
+    options: CompleteOptions,
+) -> Result<IssueCompletion> {
+    let run = Run::start(app, &STEPS);
+
+    let (settings, link, repo, client) = run
+        .step(WorkflowStepKind::VerifyDod, async {
+            let global = app.state::<SettingsState>().current();
+            let mut settings = storage::settings_for_project(&global, repo_path)?;
+            if let Some(close_issue) = options.close_issue {
+                settings.github.auto_close_issue_on_pr = close_issue;
+            }
+            let link = links::linked_issue(repo_path, &settings.git)?
+                .context("The checked-out branch is not linked to an issue")?;
+            let repo = github::Repository::from_settings(&settings.github)?;
+            let client = app.state::<GitHubState>().client(&settings.github)?;
+
+            let status = dod::evaluate(&client, &repo, repo_path, &settings)
+                .await?
+                .context("The checked-out branch is not linked to an issue")?;
+            let remaining = status.remaining();
+            if !remaining.is_empty() {
+                bail!("Definition of Done not met: {}", remaining.join("; "));
+            }
+            let message = i18n::format(
+                "workflow.message.dod_done",
+                &[("count", &status.items.len().to_string())],
+            );
+            Ok((message, (settings, link, repo, client)))
+        })
+        .await?;
+
+    let test_run = run
+        .step(WorkflowStepKind::RunTests, async {
+            let test_run = blocking(repo_path, settings.clone(), |root, settings| {
+                test_runs::run_tests(&root, &settings.workflow)
+            })
+            .await?;
+            if !test_run.passed() {
+                bail!("Tests failed: {}", test_run.summary());
+            }
+            Ok((test_run.summary(), test_run))
+        })
+        .await?;
+
+    let branch = run
+        .step(WorkflowStepKind::Push, async {
+            let branch = blocking(repo_path, settings.clone(), |root, settings| {
+                remote::push(&root, &settings.git, &Credentials::load(&settings))
+            })
+            .await?;
+            let message = i18n::format(
+                "workflow.message.pushed",
+                &[("branch", &branch), ("remote", &settings.git.remote)],
+            );
+            Ok((message, branch))
+        })
+        .await?;
+
+    let (pull_request, existing) = run
+        .step(WorkflowStepKind::OpenPullRequest, async {
+            let existing = pulls::pull_request_for_branch(&client, &repo, &branch)
+                .await?
+                .filter(|pr| pr.state == "open");
+            if let Some(pr) = existing {
+                let message = i18n::format(
+                    "workflow.message.pull_request_exists",
+                    &[("number", &pr.number.to_string())],
+                );
+                return Ok((message, (pr, true)));
+            }
+
+            let title = match options.title.clone().filter(|t| !t.trim().is_empty()) {
+                Some(title) => title,
+                None if !link.title.is_empty() => link.title.clone(),
+                None => {
+                    github::issues::get_issue_summary(&client, &repo, link.issue)
+                        .await?
+                        .title
+                }
+            };
+            let new = NewPullRequest {
+                title,
+                draft: options.draft,
+                ..NewPullRequest::default()
+            };
+            let pr = pulls::create_pull_request(
+                &client,
+                &repo,
+                repo_path,
+                &settings.github,
+                &settings.git,
+                new,
+                |_| {},
+            )
+            .await?;
+            let message = i18n::format(
+                "workflow.message.pull_request_opened",
+                &[("number", &pr.number.to_string())],
+            );
+            Ok((message, (pr, false)))
+        })
+        .await?;
+
+    tracing::info!(
+        issue = link.issue,
+        pr = pull_request.number,
+        "Completed issue"
+    );
+    Ok(IssueCompletion {
+        issue: link.issue,
+        test_run,
+        pull_request,
+        existing_pull_request: existing,
+    })
+}
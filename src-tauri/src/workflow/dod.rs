@@ -0,0 +1,439 @@
+//! Definition of Done checklists on linked issues
+//!
+//! The checkbox items (`- [ ] ...`) in the body of the issue linked to the
+//! checked-out branch make up its DoD. Items naming something zeami can verify
+//! are checked automatically and ticked on GitHub once they pass:
+//!
+//! - "tests pass": the latest run_tests result passed
+//! - "lint": `workflow.lint_command` exits with 0
+//! - "coverage ≥ 90%": the latest test run reports at least that coverage
+//!   (`workflow.coverage_threshold` when the item names no number)
+//! - "PR opened" / "pull request": the branch has a pull request
+//! - "signed commits" / "signatures": every commit on the branch carries a
+//!   valid signature
+//!
+//! Other items are left for the user to tick. The issue is done once every
+//! item is ticked; with `git.sign_commits` the branch's commits must also be
+//! signed, whether or not the checklist says so.
+
+use super::test_runs;
+use crate::config::Settings;
+use crate::git::links;
+use crate::git::signatures;
+use crate::github::{issues, pulls, Repository};
+use crate::pty::command::{self, CommandOptions};
+use anyhow::Result;
+use octocrab::Octocrab;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// Longest lint_command may run
+const LINT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Automated check behind a checklist item
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DodCheck {
+    TestsPass,
+    LintClean,
+    Coverage { threshold: f64 },
+    PullRequest,
+    SignedCommits,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    /// Line of the item in the issue body, from 0
+    pub line: usize,
+    pub text: String,
+    pub checked: bool,
+    /// None for items only the user can tick
+    pub check: Option<DodCheck>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DodItem {
+    #[serde(flatten)]
+    pub item: ChecklistItem,
+    /// Outcome of the automated check; None for ticked and manual items
+    pub result: Option<CheckResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DodStatus {
+    pub issue: u64,
+    pub items: Vec<DodItem>,
+    /// Texts of the items ticked on GitHub by this evaluation
+    pub ticked: Vec<String>,
+    /// Signatures of the branch's commits, required by `git.sign_commits`;
+    /// None when signing is off
+    pub signed_commits: Option<CheckResult>,
+    /// Every item is ticked and the commits are signed when required; false
+    /// for an issue without a checklist
+    pub complete: bool,
+}
+
+impl DodStatus {
+    /// Items still unticked, then the signature requirement when it fails
+    pub fn remaining(&self) -> Vec<&str> {
+        self.items
+            .iter()
+            .filter(|item| !item.item.checked)
+            .map(|item| item.item.text.as_str())
+            .chain(
+                self.signed_commits
+                    .iter()
+                    .filter(|result| !result.passed)
+                    .map(|result| result.detail.as_str()),
+            )
+            .collect()
+    }
+}
+
+/// Checkbox items of a Markdown body
+pub fn parse_checklist(body: &str, default_threshold: f64) -> Vec<ChecklistItem> {
+    body.split('\n')
+        .enumerate()
+        .filter_map(|(line, text)| {
+            let (checked, text) = checkbox(text)?;
+            Some(ChecklistItem {
+                line,
+                text: text.to_string(),
+                checked,
+                check: check_for(text, default_threshold),
+            })
+        })
+        .collect()
+}
+
+/// `body` with the checkbox items on `lines` ticked
+pub fn tick(body: &str, lines: &[usize]) -> String {
+    body.split('\n')
+        .enumerate()
+        .map(|(line, text)| {
+            if lines.contains(&line) && checkbox(text).is_some_and(|(checked, _)| !checked) {
+                text.replacen("[ ]", "[x]", 1)
+            } else {
+                text.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Whether a line is a checkbox item, and its text
+fn checkbox(line: &str) -> Option<(bool, &str)> {
+    let rest = line.trim_start();
+    let rest = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| rest.strip_prefix(bullet))?;
+    let (checked, text) = if let Some(text) = rest.strip_prefix("[ ]") {
+        (false, text)
+    } else {
+        (
+            true,
+            rest.strip_prefix("[x]")
+                .or_else(|| rest.strip_prefix("[X]"))?,
+        )
+    };
+    Some((checked, text.trim()))
+}
+
+fn check_for(text: &str, default_threshold: f64) -> Option<DodCheck> {
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    if lower.contains("coverage") {
+        let threshold = words
+            .iter()
+            .find_map(|word| word.parse::<f64>().ok())
+            .unwrap_or(default_threshold);
+        Some(DodCheck::Coverage { threshold })
+    } else if lower.contains("lint") {
+        Some(DodCheck::LintClean)
+    } else if lower.contains("pull request") || words.contains(&"pr") {
+        Some(DodCheck::PullRequest)
+    } else if lower.contains("signed commit")
+        || lower.contains("commits signed")
+        || lower.contains("commits are signed")
+        || lower.contains("signature")
+    {
+        Some(DodCheck::SignedCommits)
+    } else if lower.contains("test") && (lower.contains("pass") || lower.contains("green")) {
+        Some(DodCheck::TestsPass)
+    } else {
+        None
+    }
+}
+
+/// Total coverage percentage in test runner output
+///
+/// Understands the istanbul text summary of Jest and Vitest (`All files |
+/// 85.3 | ...`), cargo-tarpaulin (`85.00% coverage`) and the `TOTAL` line of
+/// coverage.py and cargo-llvm-cov.
+pub fn coverage_percent(output: &str) -> Option<f64> {
+    output.lines().rev().find_map(|line| {
+        let line = line.trim();
+        if let Some(columns) = line.strip_prefix("All files") {
+            return columns.split('|').nth(1)?.trim().parse().ok();
+        }
+        if let Some((percent, _)) = line.split_once("% coverage") {
+            return percent.split_whitespace().last()?.parse().ok();
+        }
+        if line.starts_with("TOTAL") {
+            let percent = line.split_whitespace().rev().find(|t| t.ends_with('%'))?;
+            return percent.trim_end_matches('%').parse().ok();
+        }
+        None
+    })
+}
+
+/// DoD of the issue linked to the checked-out branch of `repo_path`, with
+/// passing items ticked on GitHub; None when the branch is not linked
+pub async fn evaluate(
+    client: &Octocrab,
+    repo: &Repository,
+    repo_path: &Path,
+    settings: &Settings,
+) -> Result<Option<DodStatus>> {
+    let Some(link) = links::linked_issue(repo_path, &settings.git)? else {
+        return Ok(None);
+    };
+    let issue = issues::get_issue_summary(client, repo, link.issue).await?;
+    let threshold = f64::from(settings.workflow.coverage_threshold);
+    let items = parse_checklist(&issue.body, threshold);
+
+    let test_run = test_runs::last_run(repo_path)?;
+    let mut lint: Option<CheckResult> = None;
+    let mut pull_request: Option<CheckResult> = None;
+    let mut signed_commits: Option<CheckResult> = None;
+
+    let mut evaluated = Vec::new();
+    for item in items {
+        let result = match item.check.filter(|_| !item.checked) {
+            None => None,
+            Some(DodCheck::TestsPass) => Some(match &test_run {
+                Some(run) => CheckResult {
+                    passed: run.passed(),
+                    detail: run.summary(),
+                },
+                None => failed("No test run yet"),
+            }),
+            Some(DodCheck::Coverage { threshold }) => {
+                let coverage = test_run
+                    .as_ref()
+                    .and_then(|run| coverage_percent(&run.output));
+                Some(match coverage {
+                    Some(coverage) => CheckResult {
+                        passed: coverage >= threshold,
+                        detail: format!("{:.1}% (needs {}%)", coverage, threshold),
+                    },
+                    None => failed("The latest test run reported no coverage"),
+                })
+            }
+            Some(DodCheck::LintClean) => {
+                if lint.is_none() {
+                    lint = Some(run_lint(repo_path, &settings.workflow.lint_command).await);
+                }
+                lint.clone()
+            }
+            Some(DodCheck::PullRequest) => {
+                if pull_request.is_none() {
+                    let found = pulls::pull_request_for_branch(client, repo, &link.branch).await?;
+                    pull_request = Some(match found {
+                        Some(pr) => CheckResult {
+                            passed: true,
+                            detail: format!("#{} {}", pr.number, pr.state),
+                        },
+                        None => failed(&format!("No pull request from {}", link.branch)),
+                    });
+                }
+                pull_request.clone()
+            }
+            Some(DodCheck::SignedCommits) => {
+                if signed_commits.is_none() {
+                    signed_commits = Some(check_signatures(repo_path, settings).await);
+                }
+                signed_commits.clone()
+            }
+        };
+        evaluated.push(DodItem { item, result });
+    }
+
+    let passed: Vec<usize> = evaluated
+        .iter()
+        .filter(|item| item.result.as_ref().is_some_and(|result| result.passed))
+        .map(|item| item.item.line)
+        .collect();
+    let mut ticked = Vec::new();
+    if !passed.is_empty() {
+        issues::update_issue_body(client, repo, link.issue, &tick(&issue.body, &passed)).await?;
+        for item in evaluated
+            .iter_mut()
+            .filter(|i| passed.contains(&i.item.line))
+        {
+            item.item.checked = true;
+            ticked.push(item.item.text.clone());
+        }
+        tracing::info!(issue = link.issue, count = ticked.len(), "Ticked DoD items");
+    }
+
+    if settings.git.sign_commits && signed_commits.is_none() {
+        signed_commits = Some(check_signatures(repo_path, settings).await);
+    }
+    let signed_commits = signed_commits.filter(|_| settings.git.sign_commits);
+
+    let complete = !evaluated.is_empty()
+        && evaluated.iter().all(|item| item.item.checked)
+        && signed_commits.as_ref().is_none_or(|result| result.passed);
+    Ok(Some(DodStatus {
+        issue: link.issue,
+        items: evaluated,
+        ticked,
+        signed_commits,
+        complete,
+    }))
+}
+
+fn failed(detail: &str) -> CheckResult {
+    CheckResult {
+        passed: false,
+        detail: detail.to_string(),
+    }
+}
+
+/// Whether every commit the branch adds to the base branch has a valid signature
+async fn check_signatures(repo_path: &Path, settings: &Settings) -> CheckResult {
+    let (root, git) = (repo_path.to_path_buf(), settings.git.clone());
+    let commits =
+        tokio::task::spawn_blocking(move || signatures::branch_signatures(&root, &git)).await;
+    match commits {
+        Ok(Ok(commits)) => {
+            let unsigned: Vec<String> = commits
+                .iter()
+                .filter(|commit| !commit.status.is_valid())
+                .map(|commit| format!("{} ({})", &commit.id[..7], commit.status.describe()))
+                .collect();
+            if unsigned.is_empty() {
+                CheckResult {
+                    passed: true,
+                    detail: format!("{} signed commits", commits.len()),
+                }
+            } else {
+                failed(&format!(
+                    "{} of {} commits lack a valid signature: {}",
+                    unsigned.len(),
+                    commits.len(),
+                    unsigned.join(", ")
+                ))
+            }
+        }
+        Ok(Err(e)) => failed(&format!("{:#}", e)),
+        Err(e) => failed(&e.to_string()),
+    }
+}
+
+async fn run_lint(repo_path: &Path, lint_command: &str) -> CheckResult {
+    let (root, lint_command) = (repo_path.to_path_buf(), lint_command.to_string());
+    let output = tokio::task::spawn_blocking(move || {
+        command::run_command_with(
+            &lint_command,
+            &root,
+            LINT_TIMEOUT,
+            &CommandOptions::default(),
+        )
+    })
+    .await;
+    match output {
+        Ok(Ok(output)) if output.exit_code == Some(0) => CheckResult {
+            passed: true,
+            detail: "Lint passed".to_string(),
+        },
+        Ok(Ok(output)) if output.timed_out => failed("Lint timed out"),
+        Ok(Ok(output)) => failed(&format!(
+            "Lint failed with exit code {}",
+            output.exit_code.unwrap_or_default()
+        )),
+        Ok(Err(e)) => failed(&format!("{:#}", e)),
+        Err(e) => failed(&e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &str = "Fix the login crash.\r\n\r\n## Definition of Done\r\n- [ ] All tests pass\r\n- [x] Reviewed by a teammate\r\n* [ ] Lint clean\r\n- [ ] Test coverage ≥ 90%\r\n- [ ] PR opened\r\n- [ ] Docs updated\r\n";
+
+    #[test]
+    fn test_parse_checklist() {
+        let items = parse_checklist(BODY, 80.0);
+        let checks: Vec<_> = items.iter().map(|item| item.check).collect();
+        assert_eq!(
+            checks,
+            vec![
+                Some(DodCheck::TestsPass),
+                None,
+                Some(DodCheck::LintClean),
+                Some(DodCheck::Coverage { threshold: 90.0 }),
+                Some(DodCheck::PullRequest),
+                None,
+            ]
+        );
+        assert_eq!(items[0].text, "All tests pass");
+        assert_eq!(items[0].line, 3);
+        assert!(items[1].checked);
+        assert_eq!(
+            parse_checklist("- [ ] Coverage does not drop", 75.0)[0].check,
+            Some(DodCheck::Coverage { threshold: 75.0 })
+        );
+        assert_eq!(
+            parse_checklist("- [ ] All commits signed", 80.0)[0].check,
+            Some(DodCheck::SignedCommits)
+        );
+        assert!(parse_checklist("- [ ] Improve prompt", 80.0)[0]
+            .check
+            .is_none());
+    }
+
+    #[test]
+    fn test_tick() {
+        let ticked = tick(BODY, &[3, 5, 4]);
+        assert!(ticked.contains("- [x] All tests pass\r\n"));
+        assert!(ticked.contains("* [x] Lint clean\r\n"));
+        assert!(ticked.contains("- [x] Reviewed by a teammate\r\n"));
+        assert!(ticked.contains("- [ ] PR opened\r\n"));
+        assert!(ticked.starts_with("Fix the login crash.\r\n"));
+        assert!(ticked.ends_with("Docs updated\r\n"));
+    }
+
+    #[test]
+    fn test_coverage_percent() {
+        let jest = "\
+----------|---------|----------|---------|---------|
+File      | % Stmts | % Branch | % Funcs | % Lines |
+----------|---------|----------|---------|---------|
+All files |   85.71 |       50 |     100 |   85.71 |
+ math.js  |   85.71 |       50 |     100 |   85.71 |";
+        assert_eq!(coverage_percent(jest), Some(85.71));
+        assert_eq!(
+            coverage_percent("92.50% coverage, 185/200 lines covered"),
+            Some(92.5)
+        );
+        assert_eq!(
+            coverage_percent("Name    Stmts   Miss  Cover\nTOTAL     200     30    85%"),
+            Some(85.0)
+        );
+        assert_eq!(coverage_percent("test result: ok"), None);
+    }
+}
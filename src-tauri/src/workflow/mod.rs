@@ -0,0 +1,15 @@
+pub mod auto_commit;
+pub mod build;
+pub mod build_report;
+pub mod complete;
+pub mod dod;
+pub mod progress;
+pub mod progress_sync;
+pub mod steps;
+pub mod sync_base;
+pub mod test_report;
+pub mod test_runs;
+
+pub use auto_commit::AutoCommitter;
+pub use build::Builder;
+pub use progress_sync::ProgressSyncer;
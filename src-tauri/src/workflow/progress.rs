@@ -0,0 +1,374 @@
+//! Progress reports for the issue linked to the checked-out branch
+//!
+//! A report lists the branch's commits (marking those since the previous
+//! sync), the files changed on the branch and in the working tree, and the
+//! latest test run, plus the journal activity since the previous sync (test
+//! runs, Claude sessions, long commands). It is rendered as one comment per
+//! issue that later syncs edit in place; what was synced is recorded in
+//! .zeami/progress.json.
+
+use super::test_runs::{self, TestRun};
+use crate::config::atomic::write_atomic;
+use crate::config::GitSettings;
+use crate::git::links::{self, IssueLink};
+use crate::git::{self, status};
+use crate::journal::{self, JournalEntry, JournalKind, JournalRange};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use git2::{Oid, Repository, Sort};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Sync records, relative to the repository root
+pub const PROGRESS_FILE: &str = ".zeami/progress.json";
+
+/// Commits listed in a report at most
+const MAX_COMMITS: usize = 30;
+
+/// Files listed in a report at most
+const MAX_FILES: usize = 50;
+
+/// Journal entries listed in a report at most, the newest ones
+const MAX_ACTIVITY: usize = 20;
+
+/// Journal entries listed under Activity; commits have their own section
+const ACTIVITY_KINDS: &[JournalKind] = &[
+    JournalKind::TestsPassed,
+    JournalKind::TestsFailed,
+    JournalKind::ClaudeSession,
+    JournalKind::CommandFinished,
+];
+
+/// First line of every progress comment
+const COMMENT_MARKER: &str = "<!-- zeami:progress -->";
+
+/// The last sync of an issue's progress comment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncRecord {
+    pub issue: u64,
+    pub comment_id: Option<u64>,
+    /// HEAD commit at the time of the sync
+    pub head: Option<String>,
+    pub test_run: Option<String>,
+    pub synced_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProgressCommit {
+    /// Short id
+    pub id: String,
+    pub summary: String,
+    /// Made since the previous sync
+    pub new: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressReport {
+    pub repo_root: PathBuf,
+    pub issue: IssueLink,
+    pub head: Option<String>,
+    /// Newest first
+    pub commits: Vec<ProgressCommit>,
+    /// Changed on the branch or in the working tree, sorted
+    pub files: Vec<String>,
+    pub files_total: usize,
+    pub test_run: Option<TestRun>,
+    /// Journal entries since the previous sync, oldest first; empty on the
+    /// first sync
+    pub activity: Vec<JournalEntry>,
+    pub previous: Option<SyncRecord>,
+}
+
+impl ProgressReport {
+    /// Whether the branch head or the latest test run changed since the last sync
+    pub fn changed(&self) -> bool {
+        match &self.previous {
+            Some(previous) => {
+                previous.head != self.head
+                    || previous.test_run != self.test_run.as_ref().map(|run| run.id.clone())
+            }
+            None => true,
+        }
+    }
+
+    pub fn new_commits(&self) -> usize {
+        self.commits.iter().filter(|commit| commit.new).count()
+    }
+
+    /// Record of this report having been synced to `comment_id`
+    pub fn record(&self, comment_id: u64, now: DateTime<Utc>) -> SyncRecord {
+        SyncRecord {
+            issue: self.issue.issue,
+            comment_id: Some(comment_id),
+            head: self.head.clone(),
+            test_run: self.test_run.as_ref().map(|run| run.id.clone()),
+            synced_at: now,
+        }
+    }
+
+    /// Markdown body of the progress comment
+    pub fn render(&self, now: DateTime<Utc>) -> String {
+        let mut body = String::new();
+        let _ = writeln!(body, "{}", COMMENT_MARKER);
+        let _ = writeln!(body, "### Progress on `{}`\n", self.issue.branch);
+        let _ = writeln!(body, "_Updated {}_\n", now.format("%Y-%m-%d %H:%M UTC"));
+
+        let new = self.new_commits();
+        if self.previous.is_some() && new > 0 {
+            let _ = writeln!(
+                body,
+                "**Commits** ({}, {} since the last update)",
+                self.commits.len(),
+                new
+            );
+        } else {
+            let _ = writeln!(body, "**Commits** ({})", self.commits.len());
+        }
+        if self.commits.is_empty() {
+            let _ = writeln!(body, "- None yet");
+        }
+        for commit in &self.commits {
+            let marker = if commit.new && self.previous.is_some() {
+                " 🆕"
+            } else {
+                ""
+            };
+            let _ = writeln!(body, "- `{}` {}{}", commit.id, commit.summary, marker);
+        }
+
+        let _ = write!(body, "\n**Tests**: ");
+        match &self.test_run {
+            Some(run) => {
+                let icon = if run.passed() { "✅" } else { "❌" };
+                let _ = writeln!(
+                    body,
+                    "{} {} (`{}`, {})",
+                    icon,
+                    run.summary(),
+                    run.command,
+                    run.started_at.format("%Y-%m-%d %H:%M UTC")
+                );
+            }
+            None => {
+                let _ = writeln!(body, "not run yet");
+            }
+        }
+
+        if !self.activity.is_empty() {
+            let _ = writeln!(body, "\n**Activity since the last update**");
+            for entry in &self.activity {
+                let _ = writeln!(
+                    body,
+                    "- {} {}",
+                    entry.at.format("%H:%M"),
+                    activity_label(entry)
+                );
+            }
+        }
+
+        let _ = writeln!(body, "\n**Files touched** ({})", self.files_total);
+        for file in &self.files {
+            let _ = writeln!(body, "- `{}`", file);
+        }
+        if self.files_total > self.files.len() {
+            let _ = writeln!(body, "- …and {} more", self.files_total - self.files.len());
+        }
+        body
+    }
+}
+
+/// Progress of the issue linked to the checked-out branch of the repository
+/// containing `repo_path`; None when the branch is not linked to an issue
+pub fn collect(repo_path: &Path, git: &GitSettings) -> Result<Option<ProgressReport>> {
+    let Some(issue) = links::linked_issue(repo_path, git)? else {
+        return Ok(None);
+    };
+    let repo = git::open(repo_path)?;
+    let repo_root = git::workdir(&repo)?;
+    let previous = load_record(&repo_root, issue.issue)?;
+
+    let head = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let base = head
+        .as_ref()
+        .and_then(|head| merge_base(&repo, &git.default_branch, head.id()));
+
+    let mut commits = Vec::new();
+    let mut files = BTreeSet::new();
+    if let Some(head) = &head {
+        let last_synced = previous
+            .as_ref()
+            .and_then(|previous| previous.head.as_deref())
+            .and_then(|id| Oid::from_str(id).ok());
+        let mut walk = repo.revwalk()?;
+        walk.set_sorting(Sort::TOPOLOGICAL)?;
+        walk.push(head.id())?;
+        if let Some(base) = base {
+            walk.hide(base)?;
+        }
+        let mut new = true;
+        for id in walk.take(MAX_COMMITS) {
+            let id = id?;
+            new &= Some(id) != last_synced;
+            let commit = repo.find_commit(id)?;
+            commits.push(ProgressCommit {
+                id: id.to_string()[..7].to_string(),
+                summary: commit.summary().unwrap_or_default().to_string(),
+                new,
+            });
+        }
+
+        if let Some(base) = base {
+            let base_tree = repo.find_commit(base)?.tree()?;
+            let diff = repo.diff_tree_to_tree(Some(&base_tree), Some(&head.tree()?), None)?;
+            files.extend(diff.deltas().filter_map(|delta| {
+                delta
+                    .new_file()
+                    .path()
+                    .map(|path| path.to_string_lossy().into_owned())
+            }));
+        }
+    }
+
+    let status = status::get_status(&repo_root)?;
+    files.extend(status.staged.into_iter().map(|change| change.path));
+    files.extend(status.unstaged.into_iter().map(|change| change.path));
+    files.extend(status.untracked);
+    files.extend(status.conflicted);
+    // zeami's own state is not part of the work
+    files.retain(|file| !file.starts_with(".zeami/"));
+    let files_total = files.len();
+
+    let test_run = test_runs::last_run(&repo_root).unwrap_or_else(|e| {
+        tracing::warn!(path = ?repo_root, "Ignoring test run history: {:#}", e);
+        None
+    });
+
+    let activity = match &previous {
+        Some(previous) => {
+            let range = JournalRange::since(previous.synced_at);
+            let mut entries =
+                journal::query(&repo_root, range, ACTIVITY_KINDS).unwrap_or_else(|e| {
+                    tracing::warn!(path = ?repo_root, "Ignoring journal: {:#}", e);
+                    Vec::new()
+                });
+            entries.drain(..entries.len().saturating_sub(MAX_ACTIVITY));
+            entries
+        }
+        None => Vec::new(),
+    };
+
+    Ok(Some(ProgressReport {
+        repo_root,
+        issue,
+        head: head.map(|head| head.id().to_string()),
+        commits,
+        files: files.into_iter().take(MAX_FILES).collect(),
+        files_total,
+        test_run,
+        activity,
+        previous,
+    }))
+}
+
+fn activity_label(entry: &JournalEntry) -> String {
+    match entry.kind {
+        JournalKind::TestsPassed => format!("✅ Tests: {}", entry.summary),
+        JournalKind::TestsFailed => format!("❌ Tests: {}", entry.summary),
+        JournalKind::ClaudeSession => format!("🤖 Claude: {}", entry.summary),
+        JournalKind::CommandFinished => match entry.duration_ms {
+            Some(ms) => format!("⏱ {} ({}s)", entry.summary, ms / 1000),
+            None => format!("⏱ {}", entry.summary),
+        },
+        JournalKind::Commit | JournalKind::IssueSynced => entry.summary.clone(),
+    }
+}
+
+/// Where the branch at `head` forked from the default branch, local or remote
+fn merge_base(repo: &Repository, default_branch: &str, head: Oid) -> Option<Oid> {
+    [
+        default_branch.to_string(),
+        format!("origin/{}", default_branch),
+    ]
+    .iter()
+    .filter_map(|name| repo.revparse_single(name).ok()?.peel_to_commit().ok())
+    .find_map(|base| repo.merge_base(base.id(), head).ok())
+    .filter(|base| *base != head)
+}
+
+fn read_records(repo_root: &Path) -> Result<BTreeMap<u64, SyncRecord>> {
+    let path = repo_root.join(PROGRESS_FILE);
+    match std::fs::read(&path) {
+        Ok(data) => serde_json::from_slice(&data).with_context(|| format!("Invalid {:?}", path)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", path)),
+    }
+}
+
+pub fn load_record(repo_root: &Path, issue: u64) -> Result<Option<SyncRecord>> {
+    Ok(read_records(repo_root)?.remove(&issue))
+}
+
+pub fn save_record(repo_root: &Path, record: SyncRecord) -> Result<()> {
+    let mut records = read_records(repo_root)?;
+    records.insert(record.issue, record);
+    write_atomic(
+        &repo_root.join(PROGRESS_FILE),
+        &serde_json::to_vec_pretty(&records)?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::{commit_file, init_repo};
+    use std::fs;
+
+    #[test]
+    fn test_collect_and_render() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "README.md", "initial", "initial");
+        let main = repo.head().unwrap().shorthand().unwrap().to_string();
+        let git = GitSettings {
+            default_branch: main,
+            ..GitSettings::default()
+        };
+        assert!(collect(dir.path(), &git).unwrap().is_none());
+
+        links::start_issue(dir.path(), 7, "Add login", &git).unwrap();
+        commit_file(&repo, "login.rs", "Add login form", "Add login form");
+        let report = collect(dir.path(), &git).unwrap().unwrap();
+        assert_eq!(report.issue.issue, 7);
+        assert_eq!(report.commits.len(), 1);
+        assert!(report.changed());
+        assert_eq!(report.files, vec!["login.rs"]);
+
+        let now = Utc::now();
+        save_record(dir.path(), report.record(1234, now)).unwrap();
+        commit_file(&repo, "session.rs", "Keep session", "Keep session");
+        fs::write(dir.path().join("notes.txt"), "wip").unwrap();
+        journal::append(
+            dir.path(),
+            &JournalEntry::new(JournalKind::ClaudeSession, "Refactor the session store"),
+        )
+        .unwrap();
+
+        let report = collect(dir.path(), &git).unwrap().unwrap();
+        assert_eq!(report.previous.as_ref().unwrap().comment_id, Some(1234));
+        assert_eq!(report.new_commits(), 1);
+        assert_eq!(report.files, vec!["login.rs", "notes.txt", "session.rs"]);
+
+        let body = report.render(now);
+        assert!(body.starts_with(COMMENT_MARKER));
+        assert!(body.contains("**Commits** (2, 1 since the last update)"));
+        assert!(body.contains("Keep session 🆕"));
+        assert!(body.contains("**Tests**: not run yet"));
+        assert_eq!(report.activity.len(), 1);
+        assert!(body.contains("🤖 Claude: Refactor the session store"));
+
+        save_record(dir.path(), report.record(1234, now)).unwrap();
+        assert!(!collect(dir.path(), &git).unwrap().unwrap().changed());
+    }
+}
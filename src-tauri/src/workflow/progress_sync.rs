@@ -0,0 +1,142 @@
+use super::progress;
+use crate::config::{storage, SettingsState};
+use crate::github::{self, issues, GitHubState};
+use crate::journal::{self, JournalEntry, JournalKind};
+use crate::scheduler::{Job, JobFuture};
+use crate::state;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Scheduler name of the progress sync job
+pub const PROGRESS_SYNC_JOB: &str = "auto_sync_progress";
+
+/// Shortest allowed interval between progress syncs
+const MIN_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Outcome of posting or updating a progress comment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressSync {
+    pub issue: u64,
+    pub comment_id: u64,
+    pub html_url: String,
+    /// A new comment was posted rather than the previous one edited
+    pub created: bool,
+    pub new_commits: usize,
+}
+
+/// Post or update the progress comment of the issue linked to the checked-out
+/// branch of `repo_path`
+///
+/// Unless `force` is set nothing is posted when neither the branch nor the test
+/// results changed since the last sync. None when nothing was posted or the
+/// branch is not linked to an issue.
+pub async fn sync_progress(
+    app: &AppHandle,
+    repo_path: &Path,
+    force: bool,
+) -> Result<Option<ProgressSync>> {
+    let global = app.state::<SettingsState>().current();
+    let settings = storage::settings_for_project(&global, repo_path)?;
+
+    let report = {
+        let (repo_path, git) = (repo_path.to_path_buf(), settings.git.clone());
+        tauri::async_runtime::spawn_blocking(move || progress::collect(&repo_path, &git)).await??
+    };
+    let Some(report) = report.filter(|report| force || report.changed()) else {
+        return Ok(None);
+    };
+
+    let repo = github::Repository::from_settings(&settings.github)?;
+    let client = app
+        .state::<GitHubState>()
+        .background_client(&settings.github)
+        .await?;
+    let now = Utc::now();
+    let body = report.render(now);
+    let number = report.issue.issue;
+
+    let previous = report
+        .previous
+        .as_ref()
+        .and_then(|record| record.comment_id);
+    let updated = match previous {
+        Some(comment_id) => issues::update_comment(&client, &repo, comment_id, &body).await?,
+        None => None,
+    };
+    let created = updated.is_none();
+    let comment = match updated {
+        Some(comment) => comment,
+        None => issues::comment_on_issue(&client, &repo, number, &body).await?,
+    };
+
+    progress::save_record(&report.repo_root, report.record(comment.id, now))?;
+    state::record_sync(app, &report.repo_root, &settings.git, "progress", now);
+    tracing::info!(issue = number, created, "Synced progress comment");
+    journal::record(
+        &report.repo_root,
+        JournalEntry::new(
+            JournalKind::IssueSynced,
+            format!("Progress synced to #{}", number),
+        )
+        .reference(number.to_string()),
+    );
+    Ok(Some(ProgressSync {
+        issue: number,
+        comment_id: comment.id,
+        html_url: comment.html_url,
+        created,
+        new_commits: report.new_commits(),
+    }))
+}
+
+/// Periodic progress comments for the active repository
+///
+/// The frontend selects the repository with `watch_progress_sync`; while
+/// WorkflowSettings::auto_sync_progress is on, the scheduler refreshes the
+/// linked issue's progress comment every sync_progress_interval seconds.
+#[derive(Clone, Default)]
+pub struct ProgressSyncer {
+    repo_path: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl ProgressSyncer {
+    /// Sync the repository at `repo_path`, or stop with None
+    pub fn watch(&self, repo_path: Option<PathBuf>) {
+        *self.repo_path.lock().unwrap() = repo_path;
+    }
+}
+
+impl Job for ProgressSyncer {
+    fn interval(&self, app: &AppHandle) -> Option<Duration> {
+        let path = self.repo_path.lock().unwrap().clone()?;
+        let global = app.state::<SettingsState>().current();
+        match storage::settings_for_project(&global, &path) {
+            Ok(settings) if settings.workflow.auto_sync_progress => Some(
+                Duration::from_secs(settings.workflow.sync_progress_interval).max(MIN_INTERVAL),
+            ),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!(path = ?path, "Failed to load project settings: {:#}", e);
+                None
+            }
+        }
+    }
+
+    fn run(&self, app: &AppHandle) -> JobFuture {
+        let app = app.clone();
+        let path = self.repo_path.lock().unwrap().clone();
+        Box::pin(async move {
+            if let Some(path) = path {
+                sync_progress(&app, &path, false)
+                    .await
+                    .with_context(|| format!("Progress sync of {:?} failed", path))?;
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,87 @@
+//! Step reporting shared by the multi-step workflows (complete_issue,
+//! sync_branch_with_base)
+
+use crate::config::Settings;
+use crate::events::schema::v1::{self, StepState, WorkflowStepKind};
+use crate::events::WORKFLOW_STEP_EVENT_NAME;
+use crate::i18n;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// Run `task` with an owned copy of the repository path and settings off the async runtime
+pub(super) async fn blocking<T: Send + 'static>(
+    repo_path: &Path,
+    settings: Settings,
+    task: impl FnOnce(PathBuf, Settings) -> Result<T> + Send + 'static,
+) -> Result<T> {
+    let root = repo_path.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || task(root, settings)).await?
+}
+
+/// One run of a workflow, reported as `workflow-step` events
+pub(super) struct Run {
+    app: AppHandle,
+    id: String,
+}
+
+impl Run {
+    /// A new run with every step of `steps` reported as pending, so the UI can
+    /// lay out the checklist
+    pub(super) fn start(app: &AppHandle, steps: &[WorkflowStepKind]) -> Self {
+        let run = Run {
+            app: app.clone(),
+            id: uuid::Uuid::new_v4().to_string(),
+        };
+        for step in steps {
+            run.emit(*step, StepState::Pending, String::new());
+        }
+        run
+    }
+
+    /// Report `step` as running, then as done with the message returned by
+    /// `work` or as failed with its error
+    pub(super) async fn step<T>(
+        &self,
+        step: WorkflowStepKind,
+        work: impl std::future::Future<Output = Result<(String, T)>>,
+    ) -> Result<T> {
+        self.emit(step, StepState::Running, String::new());
+        match work.await {
+            Ok((message, value)) => {
+                self.emit(step, StepState::Done, message);
+                Ok(value)
+            }
+            Err(e) => {
+                self.emit(step, StepState::Failed, format!("{:#}", e));
+                Err(e)
+            }
+        }
+    }
+
+    pub(super) fn emit(&self, step: WorkflowStepKind, state: StepState, message: String) {
+        let payload = v1::WorkflowStep {
+            run_id: self.id.clone(),
+            step,
+            label: i18n::text(label_key(step)),
+            state,
+            message,
+        };
+        if let Err(e) = self.app.emit_all(WORKFLOW_STEP_EVENT_NAME, payload) {
+            tracing::error!("Failed to emit workflow step: {}", e);
+        }
+    }
+}
+
+fn label_key(step: WorkflowStepKind) -> &'static str {
+    match step {
+        WorkflowStepKind::VerifyDod => "workflow.step.verify_dod",
+        WorkflowStepKind::RunTests => "workflow.step.run_tests",
+        WorkflowStepKind::Push => "workflow.step.push",
+        WorkflowStepKind::OpenPullRequest => "workflow.step.open_pull_request",
+        WorkflowStepKind::Fetch => "workflow.step.fetch",
+        WorkflowStepKind::CheckConflicts => "workflow.step.check_conflicts",
+        WorkflowStepKind::Merge => "workflow.step.merge",
+        WorkflowStepKind::Rebase => "workflow.step.rebase",
+    }
+}
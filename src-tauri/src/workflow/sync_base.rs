@@ -0,0 +1,117 @@
+//! sync_branch_with_base: bring the default branch into the issue branch
+//!
+//! The remote is fetched, the merge or rebase is first built in memory to
+//! find conflicts before anything is touched, and only a clean result moves
+//! the branch and working tree (see git::sync). Each step is reported as
+//! `workflow-step`.
+
+use super::steps::{blocking, Run};
+use crate::config::{storage, MergeStrategy, SettingsState};
+use crate::events::schema::v1::{StepState, WorkflowStepKind};
+use crate::git::fetcher;
+use crate::git::sync::{self, BaseSync, SyncMethod, SyncOutcome};
+use crate::i18n;
+use anyhow::Result;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+/// Sync the checked-out branch of `repo_path` with the default branch
+///
+/// `strategy` defaults to GitSettings::merge_strategy. Conflicts are returned
+/// as SyncOutcome::Conflicts with the branch left as it was.
+pub async fn sync_branch_with_base(
+    app: &AppHandle,
+    repo_path: &Path,
+    strategy: Option<MergeStrategy>,
+) -> Result<BaseSync> {
+    let global = app.state::<SettingsState>().current();
+    let settings = storage::settings_for_project(&global, repo_path)?;
+    let method = SyncMethod::from(strategy.unwrap_or(settings.git.merge_strategy));
+    let integrate = match method {
+        SyncMethod::Merge => WorkflowStepKind::Merge,
+        SyncMethod::Rebase => WorkflowStepKind::Rebase,
+    };
+    let run = Run::start(
+        app,
+        &[
+            WorkflowStepKind::Fetch,
+            WorkflowStepKind::CheckConflicts,
+            integrate,
+        ],
+    );
+
+    run.step(WorkflowStepKind::Fetch, async {
+        let update = fetcher::fetch_and_emit(app, repo_path).await?;
+        let message = i18n::format("workflow.message.fetched", &[("remote", &update.remote)]);
+        Ok((message, ()))
+    })
+    .await?;
+
+    // Reported by hand: conflicts fail the step without failing the sync
+    run.emit(
+        WorkflowStepKind::CheckConflicts,
+        StepState::Running,
+        String::new(),
+    );
+    let plan = blocking(repo_path, settings.clone(), move |root, settings| {
+        sync::sync_with_base(&root, &settings.git, method, true)
+    })
+    .await
+    .inspect_err(|e| {
+        run.emit(
+            WorkflowStepKind::CheckConflicts,
+            StepState::Failed,
+            format!("{:#}", e),
+        )
+    })?;
+    let conflicts = matches!(plan.outcome, SyncOutcome::Conflicts { .. });
+    let state = if conflicts {
+        StepState::Failed
+    } else {
+        StepState::Done
+    };
+    run.emit(
+        WorkflowStepKind::CheckConflicts,
+        state,
+        check_message(&plan),
+    );
+    if conflicts {
+        return Ok(plan);
+    }
+    if plan.outcome == SyncOutcome::UpToDate {
+        run.emit(integrate, StepState::Done, String::new());
+        return Ok(plan);
+    }
+
+    let sync = run
+        .step(integrate, async {
+            let sync = blocking(repo_path, settings.clone(), move |root, settings| {
+                sync::sync_with_base(&root, &settings.git, method, false)
+            })
+            .await?;
+            let message = i18n::format(
+                "workflow.message.synced",
+                &[("branch", &sync.branch), ("base", &sync.base)],
+            );
+            Ok((message, sync))
+        })
+        .await?;
+    tracing::info!(branch = %sync.branch, base = %sync.base, outcome = ?sync.outcome, "Synced branch");
+    Ok(sync)
+}
+
+fn check_message(plan: &BaseSync) -> String {
+    match &plan.outcome {
+        SyncOutcome::UpToDate => {
+            i18n::format("workflow.message.up_to_date", &[("base", &plan.base)])
+        }
+        SyncOutcome::Conflicts { files, .. } => i18n::format(
+            "workflow.message.sync_conflicts",
+            &[("files", &files.join(", "))],
+        ),
+        _ => i18n::format(
+            "workflow.message.behind",
+            &[("count", &plan.behind.to_string()), ("base", &plan.base)],
+        ),
+    }
+}
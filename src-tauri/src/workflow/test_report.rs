@@ -0,0 +1,593 @@
+//! Structured results from test runner output
+//!
+//! cargo test, Jest and Vitest are recognised from their console output (with
+//! escape sequences already removed); JUnit XML reports are read when the
+//! project writes one. Output that matches none of them yields no report.
+
+use anyhow::{Context, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+
+/// Longest failure message kept per test
+const MESSAGE_MAX_CHARS: usize = 2_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestFramework {
+    Cargo,
+    Jest,
+    Vitest,
+    Junit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestCase {
+    pub name: String,
+    /// Test file, module or suite
+    pub suite: Option<String>,
+    pub outcome: TestOutcome,
+    pub duration_ms: Option<u64>,
+    /// Assertion message or panic output of a failed test
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestReport {
+    pub framework: TestFramework,
+    pub passed: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    /// Individual tests as far as the output lists them; runners often only
+    /// name failures
+    pub tests: Vec<TestCase>,
+}
+
+impl TestReport {
+    fn new(framework: TestFramework) -> Self {
+        Self {
+            framework,
+            passed: 0,
+            failed: 0,
+            skipped: 0,
+            tests: Vec::new(),
+        }
+    }
+
+    /// Failed tests only
+    pub fn failures(&self) -> impl Iterator<Item = &TestCase> {
+        self.tests
+            .iter()
+            .filter(|test| test.outcome == TestOutcome::Failed)
+    }
+
+    /// Counts from the listed tests, for output without a summary line
+    fn count_tests(&mut self) {
+        let count = |outcome| self.tests.iter().filter(|t| t.outcome == outcome).count() as u32;
+        (self.passed, self.failed, self.skipped) = (
+            count(TestOutcome::Passed),
+            count(TestOutcome::Failed),
+            count(TestOutcome::Skipped),
+        );
+    }
+}
+
+/// Report parsed from console output, trying each known runner
+pub fn parse_output(output: &str) -> Option<TestReport> {
+    parse_cargo(output)
+        .or_else(|| parse_vitest(output))
+        .or_else(|| parse_jest(output))
+}
+
+fn case(name: &str, suite: Option<&str>, outcome: TestOutcome) -> TestCase {
+    TestCase {
+        name: name.trim().to_string(),
+        suite: suite.map(str::to_string),
+        outcome,
+        duration_ms: None,
+        message: None,
+    }
+}
+
+fn truncate_message(message: &str) -> Option<String> {
+    let message = message.trim();
+    if message.is_empty() {
+        return None;
+    }
+    Some(match message.char_indices().nth(MESSAGE_MAX_CHARS) {
+        Some((index, _)) => format!("{}…", &message[..index]),
+        None => message.to_string(),
+    })
+}
+
+/// Number before `label` in summary text such as `3 passed; 1 failed`
+fn count_before(text: &str, label: &str) -> Option<u32> {
+    let index = text.find(label)?;
+    text[..index].split_whitespace().last()?.parse().ok()
+}
+
+/// `test foo::bar ... ok` lines and `test result:` summaries of cargo test
+fn parse_cargo(output: &str) -> Option<TestReport> {
+    let mut report = TestReport::new(TestFramework::Cargo);
+    let mut summaries = 0;
+    let mut failure: Option<(String, String)> = None;
+    let mut messages = Vec::new();
+
+    for line in output.lines() {
+        if let Some(summary) = line.strip_prefix("test result: ") {
+            summaries += 1;
+            report.passed += count_before(summary, " passed").unwrap_or(0);
+            report.failed += count_before(summary, " failed").unwrap_or(0);
+            report.skipped += count_before(summary, " ignored").unwrap_or(0);
+        } else if let Some((name, result)) = line
+            .strip_prefix("test ")
+            .and_then(|rest| rest.split_once(" ... "))
+        {
+            let outcome = match result.split([' ', ',']).next() {
+                Some("ok") => TestOutcome::Passed,
+                Some("FAILED") => TestOutcome::Failed,
+                Some("ignored") => TestOutcome::Skipped,
+                _ => continue,
+            };
+            let (suite, name) = match name.rsplit_once("::") {
+                Some((suite, name)) => (Some(suite), name),
+                None => (None, name),
+            };
+            report.tests.push(case(name, suite, outcome));
+        } else if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" stdout ----"))
+        {
+            messages.extend(failure.take());
+            failure = Some((name.to_string(), String::new()));
+        } else if line == "failures:" || line.starts_with("test result") {
+            messages.extend(failure.take());
+        } else if let Some((_, message)) = failure.as_mut() {
+            message.push_str(line);
+            message.push('\n');
+        }
+    }
+    messages.extend(failure);
+    if summaries == 0 {
+        return None;
+    }
+
+    for (full_name, message) in messages {
+        let test = report.tests.iter_mut().find(|test| {
+            test.outcome == TestOutcome::Failed
+                && match &test.suite {
+                    Some(suite) => full_name == format!("{}::{}", suite, test.name),
+                    None => full_name == test.name,
+                }
+        });
+        if let Some(test) = test {
+            test.message = truncate_message(&message);
+        }
+    }
+    Some(report)
+}
+
+/// Duration in milliseconds of a `(5 ms)` or `5ms` suffix
+fn duration_suffix(text: &str) -> (&str, Option<u64>) {
+    let trimmed = text.trim_end();
+    let inner = trimmed
+        .strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once(" ("));
+    let (name, duration) = match inner {
+        Some(split) => split,
+        None => match trimmed.rsplit_once(' ') {
+            Some((name, duration)) if duration.ends_with("ms") => (name, duration),
+            _ => return (trimmed, None),
+        },
+    };
+    match duration.trim_end_matches("ms").trim().parse::<f64>().ok() {
+        Some(ms) => (name, Some(ms.round() as u64)),
+        None => (trimmed, None),
+    }
+}
+
+/// Jest console output: `PASS`/`FAIL` file headers, `✓`/`✕`/`○` test lines
+/// (with --verbose), `● Suite › test` failure blocks and the `Tests:` summary
+fn parse_jest(output: &str) -> Option<TestReport> {
+    let mut report = TestReport::new(TestFramework::Jest);
+    let mut summary = false;
+    let mut file: Option<String> = None;
+    let mut failure: Option<(String, String)> = None;
+    let mut messages = Vec::new();
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(counts) = trimmed.strip_prefix("Tests:") {
+            summary = true;
+            messages.extend(failure.take());
+            report.passed = count_before(counts, " passed").unwrap_or(0);
+            report.failed = count_before(counts, " failed").unwrap_or(0);
+            report.skipped = count_before(counts, " skipped").unwrap_or(0)
+                + count_before(counts, " todo").unwrap_or(0);
+        } else if let Some(path) = trimmed
+            .strip_prefix("PASS ")
+            .or_else(|| trimmed.strip_prefix("FAIL "))
+        {
+            messages.extend(failure.take());
+            file = Some(path.trim().to_string());
+        } else if let Some(name) = trimmed.strip_prefix("● ") {
+            messages.extend(failure.take());
+            failure = Some((name.to_string(), String::new()));
+        } else if let Some((outcome, rest)) = [
+            ("✓", TestOutcome::Passed),
+            ("√", TestOutcome::Passed),
+            ("✕", TestOutcome::Failed),
+            ("×", TestOutcome::Failed),
+            ("○", TestOutcome::Skipped),
+        ]
+        .into_iter()
+        .find_map(|(mark, outcome)| Some((outcome, trimmed.strip_prefix(mark)?)))
+        {
+            let rest = rest.trim_start().trim_start_matches("skipped ");
+            let (name, duration_ms) = duration_suffix(rest);
+            let mut test = case(name, file.as_deref(), outcome);
+            test.duration_ms = duration_ms;
+            report.tests.push(test);
+        } else if let Some((_, message)) = failure.as_mut() {
+            message.push_str(line);
+            message.push('\n');
+        }
+    }
+    messages.extend(failure);
+    if !summary {
+        return None;
+    }
+
+    for (title, message) in messages {
+        let name = title.rsplit(" › ").next().unwrap_or(&title).trim();
+        match report
+            .tests
+            .iter_mut()
+            .find(|test| test.outcome == TestOutcome::Failed && test.name == name)
+        {
+            Some(test) => test.message = truncate_message(&message),
+            None => {
+                let mut test = case(&title.replace(" › ", " > "), None, TestOutcome::Failed);
+                test.message = truncate_message(&message);
+                report.tests.push(test);
+            }
+        }
+    }
+    Some(report)
+}
+
+/// Vitest console output: `✓`/`×` test lines, ` FAIL  file > suite > test`
+/// failure headers and the ` Tests  1 failed | 9 passed (10)` summary
+fn parse_vitest(output: &str) -> Option<TestReport> {
+    let mut report = TestReport::new(TestFramework::Vitest);
+    let mut summary = false;
+    let mut failure: Option<(String, String)> = None;
+    let mut messages = Vec::new();
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(counts) = trimmed
+            .strip_prefix("Tests ")
+            .filter(|counts| !counts.starts_with(':'))
+        {
+            summary = true;
+            messages.extend(failure.take());
+            report.passed = count_before(counts, " passed").unwrap_or(0);
+            report.failed = count_before(counts, " failed").unwrap_or(0);
+            report.skipped = count_before(counts, " skipped").unwrap_or(0)
+                + count_before(counts, " todo").unwrap_or(0);
+        } else if let Some(title) = trimmed.strip_prefix("FAIL ") {
+            messages.extend(failure.take());
+            failure = Some((title.trim().to_string(), String::new()));
+        } else if trimmed.starts_with("Test Files ")
+            || trimmed.starts_with("Duration ")
+            || trimmed.starts_with("Start at ")
+            || trimmed.starts_with("⎯")
+        {
+            messages.extend(failure.take());
+        } else if let Some((outcome, rest)) = [
+            ("✓", TestOutcome::Passed),
+            ("×", TestOutcome::Failed),
+            ("↓", TestOutcome::Skipped),
+        ]
+        .into_iter()
+        .find_map(|(mark, outcome)| Some((outcome, trimmed.strip_prefix(mark)?)))
+        {
+            // File lines such as `✓ src/a.test.ts (3 tests) 5ms` only summarise
+            if rest.contains(" tests)") || rest.contains(" test)") {
+                continue;
+            }
+            let (name, duration_ms) = duration_suffix(rest.trim());
+            let (suite, name) = match name.rsplit_once(" > ") {
+                Some((suite, name)) => (Some(suite), name),
+                None => (None, name),
+            };
+            let mut test = case(name, suite, outcome);
+            test.duration_ms = duration_ms;
+            report.tests.push(test);
+        } else if let Some((_, message)) = failure.as_mut() {
+            message.push_str(line);
+            message.push('\n');
+        }
+    }
+    messages.extend(failure);
+    if !summary {
+        return None;
+    }
+
+    for (title, message) in messages {
+        let (suite, name) = match title.rsplit_once(" > ") {
+            Some((suite, name)) => (Some(suite.to_string()), name.to_string()),
+            None => (None, title.clone()),
+        };
+        let existing = report.tests.iter_mut().find(|test| {
+            test.outcome == TestOutcome::Failed
+                && test.name == name
+                && suite.as_deref().is_some_and(|suite| {
+                    test.suite
+                        .as_deref()
+                        .is_none_or(|test_suite| suite.ends_with(test_suite))
+                })
+        });
+        match existing {
+            Some(test) => test.message = truncate_message(&message),
+            None => {
+                let mut test = case(&name, suite.as_deref(), TestOutcome::Failed);
+                test.message = truncate_message(&message);
+                report.tests.push(test);
+            }
+        }
+    }
+    Some(report)
+}
+
+/// Report from a JUnit XML file, as written by Jest, Vitest, cargo-nextest,
+/// pytest, Maven and most CI tooling
+pub fn parse_junit(xml: &str) -> Result<TestReport> {
+    let mut report = TestReport::new(TestFramework::Junit);
+    let mut reader = Reader::from_str(xml);
+    let mut current: Option<TestCase> = None;
+    let mut in_failure = false;
+    let mut saw_testcase = false;
+
+    loop {
+        match reader.read_event().context("Invalid JUnit XML")? {
+            Event::Start(element) => match element.name().as_ref() {
+                b"testcase" => {
+                    saw_testcase = true;
+                    current = Some(junit_case(&element));
+                }
+                b"failure" | b"error" => {
+                    if let Some(test) = current.as_mut() {
+                        test.outcome = TestOutcome::Failed;
+                        test.message = attribute(&element, "message");
+                        in_failure = true;
+                    }
+                }
+                b"skipped" => {
+                    if let Some(test) = current.as_mut() {
+                        test.outcome = TestOutcome::Skipped;
+                    }
+                }
+                _ => {}
+            },
+            Event::Empty(element) => match element.name().as_ref() {
+                b"testcase" => {
+                    saw_testcase = true;
+                    report.tests.push(junit_case(&element));
+                }
+                b"failure" | b"error" => {
+                    if let Some(test) = current.as_mut() {
+                        test.outcome = TestOutcome::Failed;
+                        test.message = attribute(&element, "message");
+                    }
+                }
+                b"skipped" => {
+                    if let Some(test) = current.as_mut() {
+                        test.outcome = TestOutcome::Skipped;
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(text) if in_failure => {
+                if let Some(test) = current.as_mut() {
+                    let text = text.decode().unwrap_or_default();
+                    if !text.trim().is_empty() {
+                        test.message = truncate_message(&text);
+                    }
+                }
+            }
+            Event::CData(text) if in_failure => {
+                if let Some(test) = current.as_mut() {
+                    test.message = truncate_message(&String::from_utf8_lossy(&text));
+                }
+            }
+            Event::End(element) => match element.name().as_ref() {
+                b"testcase" => report.tests.extend(current.take()),
+                b"failure" | b"error" => in_failure = false,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if !saw_testcase {
+        anyhow::bail!("No test cases in JUnit XML");
+    }
+    report.count_tests();
+    Ok(report)
+}
+
+fn junit_case(element: &BytesStart) -> TestCase {
+    let mut test = case(
+        &attribute(element, "name").unwrap_or_default(),
+        None,
+        TestOutcome::Passed,
+    );
+    test.suite = attribute(element, "classname");
+    test.duration_ms = attribute(element, "time")
+        .and_then(|time| time.parse::<f64>().ok())
+        .map(|seconds| (seconds * 1000.0).round() as u64);
+    test
+}
+
+fn attribute(element: &BytesStart, name: &str) -> Option<String> {
+    element
+        .try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|value| value.unescape_value().ok())
+        .map(|value| value.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cargo() {
+        let output = "\
+running 3 tests
+test config::tests::test_load ... ok
+test git::tests::test_diff ... FAILED
+test slow ... ignored, needs network
+
+failures:
+
+---- git::tests::test_diff stdout ----
+thread 'git::tests::test_diff' panicked at src/git/diff.rs:10:5:
+assertion failed: patch.is_empty()
+
+failures:
+    git::tests::test_diff
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.02s
+
+running 1 test
+test src/lib.rs - add (line 3) ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.30s";
+        let report = parse_output(output).unwrap();
+        assert_eq!(report.framework, TestFramework::Cargo);
+        assert_eq!((report.passed, report.failed, report.skipped), (2, 1, 1));
+        assert_eq!(report.tests.len(), 4);
+
+        let failure = report.failures().next().unwrap();
+        assert_eq!(failure.name, "test_diff");
+        assert_eq!(failure.suite.as_deref(), Some("git::tests"));
+        assert!(failure
+            .message
+            .as_deref()
+            .unwrap()
+            .ends_with("assertion failed: patch.is_empty()"));
+    }
+
+    #[test]
+    fn test_jest() {
+        let output = "\
+PASS src/math.test.js
+  math
+    ✓ adds (3 ms)
+    ○ skipped divides
+FAIL src/api.test.js
+  api
+    ✕ fetches users (12 ms)
+
+  ● api › fetches users
+
+    expect(received).toBe(expected)
+
+    Expected: 200
+    Received: 500
+
+Test Suites: 1 failed, 1 passed, 2 total
+Tests:       1 failed, 1 skipped, 1 passed, 3 total
+Time:        1.234 s";
+        let report = parse_output(output).unwrap();
+        assert_eq!(report.framework, TestFramework::Jest);
+        assert_eq!((report.passed, report.failed, report.skipped), (1, 1, 1));
+        assert_eq!(report.tests[0].duration_ms, Some(3));
+        assert_eq!(report.tests[1].name, "divides");
+
+        let failure = report.failures().next().unwrap();
+        assert_eq!(failure.name, "fetches users");
+        assert_eq!(failure.suite.as_deref(), Some("src/api.test.js"));
+        assert!(failure
+            .message
+            .as_deref()
+            .unwrap()
+            .contains("Received: 500"));
+    }
+
+    #[test]
+    fn test_vitest() {
+        let output = "\
+ RUN  v1.6.0 /work
+
+ ✓ src/math.test.ts (2 tests) 3ms
+ ❯ src/api.test.ts (1 test | 1 failed) 9ms
+   × api > fetches users 8ms
+
+⎯⎯⎯⎯⎯⎯⎯ Failed Tests 1 ⎯⎯⎯⎯⎯⎯⎯
+
+ FAIL  src/api.test.ts > api > fetches users
+AssertionError: expected 500 to be 200
+ ❯ src/api.test.ts:5:20
+
+⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯[1/1]⎯
+
+ Test Files  1 failed | 1 passed (2)
+      Tests  1 failed | 2 passed (3)
+   Duration  1.02s";
+        let report = parse_output(output).unwrap();
+        assert_eq!(report.framework, TestFramework::Vitest);
+        assert_eq!((report.passed, report.failed, report.skipped), (2, 1, 0));
+        assert_eq!(report.tests.len(), 1);
+
+        let failure = &report.tests[0];
+        assert_eq!(failure.name, "fetches users");
+        assert_eq!(failure.suite.as_deref(), Some("api"));
+        assert_eq!(failure.duration_ms, Some(8));
+        assert!(failure
+            .message
+            .as_deref()
+            .unwrap()
+            .starts_with("AssertionError: expected 500 to be 200"));
+    }
+
+    #[test]
+    fn test_junit() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites>
+  <testsuite name="api" tests="3">
+    <testcase classname="api" name="lists users" time="0.012"/>
+    <testcase classname="api" name="fetches users" time="0.5">
+      <failure message="expected 200">AssertionError: expected 500 to be 200</failure>
+    </testcase>
+    <testcase classname="api" name="deletes users"><skipped/></testcase>
+  </testsuite>
+</testsuites>"#;
+        let report = parse_junit(xml).unwrap();
+        assert_eq!((report.passed, report.failed, report.skipped), (1, 1, 1));
+        assert_eq!(report.tests[0].duration_ms, Some(12));
+        let failure = report.failures().next().unwrap();
+        assert_eq!(
+            failure.message.as_deref(),
+            Some("AssertionError: expected 500 to be 200")
+        );
+        assert!(parse_junit("<testsuites/>").is_err());
+    }
+
+    #[test]
+    fn test_unrecognised_output() {
+        assert!(parse_output("make: *** [all] Error 1").is_none());
+    }
+}
@@ -0,0 +1,241 @@
+//! Test runs of the auto-test workflow
+//!
+//! run_tests runs `workflow.test_command` in a PTY, turns the result into a
+//! TestReport (from the JUnit file at `workflow.test_report_path` when the run
+//! wrote one, otherwise from the console output) and keeps the last runs in
+//! .zeami/test-runs.json so the test panel survives restarts. With
+//! `workflow.test_container` set the command runs in that dev container.
+
+use super::test_report::{self, TestReport};
+use crate::config::atomic::write_atomic;
+use crate::config::WorkflowSettings;
+use crate::containers;
+use crate::journal::{self, JournalEntry, JournalKind};
+use crate::pty::command::{self, CommandOptions};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// Test run history, relative to the project root
+pub const TEST_RUNS_FILE: &str = ".zeami/test-runs.json";
+
+/// Runs kept in the history
+const MAX_RUNS: usize = 20;
+
+/// Longest a test run may take before it is killed
+pub const TEST_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// File timestamps come from a coarser clock than SystemTime::now, so a report
+/// written right after the run started can look slightly older than the run
+const MTIME_SLACK: Duration = Duration::from_secs(1);
+
+/// Output kept per run, counted from the end
+const OUTPUT_TAIL_BYTES: usize = 32 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestRun {
+    pub id: String,
+    pub started_at: DateTime<Utc>,
+    pub command: String,
+    /// None when the command was killed after timing out
+    pub exit_code: Option<u32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    /// None when the output matched no known test runner
+    pub report: Option<TestReport>,
+    /// End of the console output
+    pub output: String,
+}
+
+impl TestRun {
+    pub fn passed(&self) -> bool {
+        self.exit_code == Some(0) && self.report.as_ref().is_none_or(|report| report.failed == 0)
+    }
+
+    /// One line for notifications, e.g. "2 failed, 40 passed in 12s, first: adds"
+    pub fn summary(&self) -> String {
+        let elapsed = Duration::from_millis(self.duration_ms).as_secs();
+        match &self.report {
+            _ if self.timed_out => format!("Timed out after {}s", elapsed),
+            Some(report) if report.failed > 0 => {
+                let mut summary = format!(
+                    "{} failed, {} passed in {}s",
+                    report.failed, report.passed, elapsed
+                );
+                if let Some(first) = report.failures().next() {
+                    summary.push_str(&format!(", first: {}", first.name));
+                }
+                summary
+            }
+            Some(report) => format!("{} passed in {}s", report.passed, elapsed),
+            None => match self.exit_code {
+                Some(0) => format!("Passed in {}s", elapsed),
+                Some(code) => format!("Failed with exit code {} in {}s", code, elapsed),
+                None => format!("Failed in {}s", elapsed),
+            },
+        }
+    }
+}
+
+/// Run the project's test command and record the result
+///
+/// `CI=1` is set so runners such as Vitest do not start in watch mode inside
+/// the PTY.
+pub fn run_tests(project_root: &Path, workflow: &WorkflowSettings) -> Result<TestRun> {
+    let started_at = Utc::now();
+    let started = SystemTime::now();
+    let options = CommandOptions {
+        env: [("CI".to_string(), "1".to_string())].into(),
+        ..CommandOptions::default()
+    };
+    let output = match &workflow.test_container {
+        Some(container) => containers::run_command_with(
+            project_root,
+            Some(container),
+            &workflow.test_command,
+            project_root,
+            TEST_TIMEOUT,
+            &options,
+        )?,
+        None => {
+            command::run_command_with(&workflow.test_command, project_root, TEST_TIMEOUT, &options)?
+        }
+    };
+
+    let junit = workflow
+        .test_report_path
+        .as_ref()
+        .and_then(|path| read_junit(&project_root.join(path), started));
+    let report = junit.or_else(|| test_report::parse_output(&output.output));
+
+    let run = TestRun {
+        id: uuid::Uuid::new_v4().to_string(),
+        started_at,
+        command: workflow.test_command.clone(),
+        exit_code: output.exit_code,
+        timed_out: output.timed_out,
+        duration_ms: output.duration_ms,
+        report,
+        output: command::output_tail(&output.output, OUTPUT_TAIL_BYTES).to_string(),
+    };
+    record(project_root, &run)?;
+    let kind = if run.passed() {
+        JournalKind::TestsPassed
+    } else {
+        JournalKind::TestsFailed
+    };
+    journal::record(
+        project_root,
+        JournalEntry::new(kind, run.summary())
+            .reference(&run.id)
+            .duration_ms(run.duration_ms),
+    );
+    Ok(run)
+}
+
+/// JUnit report at `path` if it was written during the current run
+fn read_junit(path: &Path, started: SystemTime) -> Option<TestReport> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    if modified + MTIME_SLACK < started {
+        return None;
+    }
+    let xml = std::fs::read_to_string(path).ok()?;
+    match test_report::parse_junit(&xml) {
+        Ok(report) => Some(report),
+        Err(e) => {
+            tracing::warn!(path = ?path, "Ignoring JUnit report: {:#}", e);
+            None
+        }
+    }
+}
+
+/// Recorded runs of the project, newest first
+pub fn history(project_root: &Path) -> Result<Vec<TestRun>> {
+    let path = project_root.join(TEST_RUNS_FILE);
+    match std::fs::read(&path) {
+        Ok(data) => serde_json::from_slice(&data).with_context(|| format!("Invalid {:?}", path)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", path)),
+    }
+}
+
+pub fn last_run(project_root: &Path) -> Result<Option<TestRun>> {
+    Ok(history(project_root)?.into_iter().next())
+}
+
+fn record(project_root: &Path, run: &TestRun) -> Result<()> {
+    // A corrupt history is replaced rather than failing the run
+    let mut runs = history(project_root).unwrap_or_else(|e| {
+        tracing::warn!("Discarding test run history: {:#}", e);
+        Vec::new()
+    });
+    runs.insert(0, run.clone());
+    runs.truncate(MAX_RUNS);
+    write_atomic(
+        &project_root.join(TEST_RUNS_FILE),
+        &serde_json::to_vec_pretty(&runs)?,
+    )
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::workflow::test_report::TestFramework;
+
+    #[test]
+    fn test_run_and_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let workflow = WorkflowSettings {
+            test_command: "echo 'test a ... ok'; echo 'test result: ok. 1 passed; 0 failed'"
+                .to_string(),
+            ..WorkflowSettings::default()
+        };
+
+        let run = run_tests(dir.path(), &workflow).unwrap();
+        assert!(run.passed());
+        let report = run.report.as_ref().unwrap();
+        assert_eq!(report.framework, TestFramework::Cargo);
+        assert_eq!(report.passed, 1);
+        assert_eq!(last_run(dir.path()).unwrap(), Some(run));
+
+        for _ in 0..MAX_RUNS {
+            run_tests(dir.path(), &workflow).unwrap();
+        }
+        assert_eq!(history(dir.path()).unwrap().len(), MAX_RUNS);
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let workflow = WorkflowSettings {
+            test_command: "printf '<testsuite><testcase name=\"a\"><failure/></testcase></testsuite>' > junit.xml; exit 1".to_string(),
+            test_report_path: Some("junit.xml".to_string()),
+            ..WorkflowSettings::default()
+        };
+
+        let run = run_tests(dir.path(), &workflow).unwrap();
+        assert!(!run.passed());
+        let report = run.report.unwrap();
+        assert_eq!(report.framework, TestFramework::Junit);
+        assert_eq!(report.failed, 1);
+        assert_eq!(run.exit_code, Some(1));
+    }
+
+    #[test]
+    fn test_summary() {
+        let run = TestRun {
+            id: "1".to_string(),
+            started_at: Utc::now(),
+            command: "make test".to_string(),
+            exit_code: Some(2),
+            timed_out: false,
+            duration_ms: 12_300,
+            report: None,
+            output: String::new(),
+        };
+        assert_eq!(run.summary(), "Failed with exit code 2 in 12s");
+        assert_eq!(last_run(Path::new("/nonexistent")).unwrap(), None);
+    }
+}